@@ -0,0 +1,53 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+// SS: programs that call C library functions other than the builtins of the interpreter
+const NOT_INTERPRETED: [&str; 2] = ["chars_and_strings", "structs"];
+
+// SS: generates a test function for every C file under tests/programs and tests/must_fail,
+// so that cargo test runs them in parallel and reports each one by name. The programs are
+// run a second time with all optimizations enabled, and once more in the interpreter.
+fn main() {
+    let mut tests = String::new();
+    for dir in ["programs", "must_fail"] {
+        let dir_path = Path::new("tests").join(dir);
+        println!("cargo:rerun-if-changed={}", dir_path.display());
+
+        let mut names = fs::read_dir(&dir_path)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "c"))
+            .map(|path| path.file_stem().unwrap().to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        names.sort();
+
+        for name in names {
+            let identifier = name
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                .collect::<String>();
+            if dir == "programs" {
+                tests.push_str(&format!(
+                    "#[test]\nfn program_{0}() {{\n    run_program({1:?}, &[]);\n}}\n\n\
+                     #[test]\nfn program_{0}_optimized() {{\n    run_program({1:?}, &[\"--optimize\"]);\n}}\n\n",
+                    identifier, name
+                ));
+                if !NOT_INTERPRETED.contains(&name.as_str()) {
+                    tests.push_str(&format!(
+                        "#[test]\nfn program_{}_interpreted() {{\n    interpret_program({:?});\n}}\n\n",
+                        identifier, name
+                    ));
+                }
+            } else {
+                tests.push_str(&format!(
+                    "#[test]\nfn must_fail_{}() {{\n    fail_to_compile({:?});\n}}\n\n",
+                    identifier, name
+                ));
+            }
+        }
+    }
+
+    let out_dir = env::var("OUT_DIR").unwrap();
+    fs::write(Path::new(&out_dir).join("e2e_tests.rs"), tests).unwrap();
+}
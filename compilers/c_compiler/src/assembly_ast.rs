@@ -1,27 +1,191 @@
 use crate::reg::Register;
+use crate::types::StaticInit;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct AssemblyProgramAST {
-    pub function_definition: AssemblyFunctionAST,
+    pub items: Vec<AssemblyTopLevelAST>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AssemblyTopLevelAST {
+    Function(AssemblyFunctionAST),
+    StaticVariable {
+        name: String,
+        global: bool,
+        alignment: i32,
+        init: Vec<StaticInit>,
+    },
+    // SS: a read-only constant, either a string literal or one the compiler needs, e.g. a
+    // double operand, since x64 has no floating-point immediates
+    StaticConstant {
+        name: String,
+        alignment: i32,
+        init: StaticInit,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct AssemblyFunctionAST {
     pub name: String,
+    pub global: bool,
     pub instructions: Vec<AssemblyInstructionAST>,
+    // SS: the callee-saved registers the function uses, saved in the prologue and restored
+    // before every return
+    pub callee_saved_registers: Vec<Register>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum AssemblyInstructionAST {
     Mov {
+        asm_type: AssemblyType,
+        src: AssemblyOperandAST,
+        dst: AssemblyOperandAST,
+    },
+    // SS: sign extends src into the larger dst
+    Movsx {
+        src_type: AssemblyType,
+        dst_type: AssemblyType,
+        src: AssemblyOperandAST,
+        dst: AssemblyOperandAST,
+    },
+    // SS: zero extends src into the larger dst
+    MovZeroExtend {
+        src_type: AssemblyType,
+        dst_type: AssemblyType,
         src: AssemblyOperandAST,
         dst: AssemblyOperandAST,
     },
+    // SS: loads the address of a memory operand
+    Lea {
+        src: AssemblyOperandAST,
+        dst: AssemblyOperandAST,
+    },
+    // SS: asm_type is the size of the integer, the conversion truncates toward zero
+    Cvttsd2si {
+        asm_type: AssemblyType,
+        src: AssemblyOperandAST,
+        dst: AssemblyOperandAST,
+    },
+    Cvtsi2sd {
+        asm_type: AssemblyType,
+        src: AssemblyOperandAST,
+        dst: AssemblyOperandAST,
+    },
+    Unary {
+        op: AssemblyUnaryOperatorAST,
+        asm_type: AssemblyType,
+        operand: AssemblyOperandAST,
+    },
+    Binary {
+        op: AssemblyBinaryOperatorAST,
+        asm_type: AssemblyType,
+        src: AssemblyOperandAST,
+        dst: AssemblyOperandAST,
+    },
+    Idiv {
+        asm_type: AssemblyType,
+        operand: AssemblyOperandAST,
+    },
+    Div {
+        asm_type: AssemblyType,
+        operand: AssemblyOperandAST,
+    },
+    // SS: sign extends ax into dx, cdq for longwords and cqo for quadwords
+    Cdq(AssemblyType),
+    Cmp {
+        asm_type: AssemblyType,
+        src: AssemblyOperandAST,
+        dst: AssemblyOperandAST,
+    },
+    Jmp(String),
+    JmpCC {
+        condition: ConditionCode,
+        target: String,
+    },
+    // SS: jumps to the target at position index, a quadword. The table holds the offsets of
+    // the targets from its own address, so it needs no relocations.
+    JmpTable {
+        index: AssemblyOperandAST,
+        table: String,
+        targets: Vec<String>,
+    },
+    SetCC {
+        condition: ConditionCode,
+        operand: AssemblyOperandAST,
+    },
+    Label(String),
+    AllocateStack(i32),
+    DeallocateStack(i32),
+    Push(AssemblyOperandAST),
+    Call(String),
     Ret,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AssemblyUnaryOperatorAST {
+    Neg,
+    Not,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AssemblyBinaryOperatorAST {
+    Add,
+    Sub,
+    Mult,
+    DivDouble,
+    And,
+    Or,
+    Xor,
+    // SS: logical shifts
+    Shl,
+    Shr,
+}
+
+// SS: the size and kind of the operands of an instruction
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AssemblyType {
+    Byte,
+    Longword,
+    Quadword,
+    Double,
+}
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConditionCode {
+    E,
+    NE,
+    G,
+    GE,
+    L,
+    LE,
+    // SS: the unsigned comparisons, also used for doubles since comisd sets CF and ZF
+    A,
+    AE,
+    B,
+    BE,
+    // SS: set by comisd when either operand is NaN
+    P,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum AssemblyOperandAST {
     Immediate(i64),
     Register(Register),
+    Pseudo(String),
+    Stack(i32),
+    // SS: the memory at the address in the register plus the offset
+    Memory(Register, i32),
+    // SS: the memory at base + index * scale
+    Indexed {
+        base: Register,
+        index: Register,
+        scale: i32,
+    },
+    // SS: a part of an aggregate object that is replaced by a stack or data operand like a
+    // pseudo register, offset in bytes from its start
+    PseudoMem(String, i32),
+    // SS: a variable with static storage duration, addressed relative to rip, plus the
+    // offset in bytes from its start
+    Data(String, i32),
 }
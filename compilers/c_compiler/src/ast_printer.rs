@@ -0,0 +1,361 @@
+use crate::parse_ast::{
+    BinaryOperatorAST, BlockAST, BlockItemAST, DeclarationAST, ExprAST, ExternalDeclarationAST,
+    ForInitAST, FunctionAST, IncrementOperatorAST, InitializerAST, ProgramAST, StmtAST,
+    StorageClassAST, StructDeclarationAST, UnaryOperatorAST,
+};
+use crate::tokens::Tokens;
+
+// SS: prints the AST as an indented tree, one node per line with its children below it
+pub fn print_program(ast: &ProgramAST) -> String {
+    let mut printer = AstPrinter {
+        lines: vec![],
+        depth: 0,
+    };
+    printer.line("Program".to_string());
+    printer.nested(|p| {
+        for declaration in &ast.declarations {
+            match declaration {
+                ExternalDeclarationAST::Function(function) => p.function(function),
+                ExternalDeclarationAST::Variable(declaration) => p.declaration(declaration),
+                ExternalDeclarationAST::Struct(declaration) => p.struct_declaration(declaration),
+            }
+        }
+    });
+    printer.lines.join("\n")
+}
+
+struct AstPrinter {
+    lines: Vec<String>,
+    depth: usize,
+}
+
+impl AstPrinter {
+    fn line(&mut self, text: String) {
+        self.lines
+            .push(format!("{}{}", "  ".repeat(self.depth), text));
+    }
+
+    fn nested(&mut self, print_children: impl FnOnce(&mut Self)) {
+        self.depth += 1;
+        print_children(self);
+        self.depth -= 1;
+    }
+
+    // SS: a line naming a child of the current node, i.e. the condition of an if, with the
+    // child below it
+    fn labeled(&mut self, label: &str, print_child: impl FnOnce(&mut Self)) {
+        self.line(label.to_string());
+        self.nested(print_child);
+    }
+
+    fn function(&mut self, function: &FunctionAST) {
+        self.line(format!(
+            "Function {}: {}{}",
+            function.name,
+            function.function_type,
+            storage_class(function.storage_class)
+        ));
+        self.nested(|p| {
+            if !function.params.is_empty() {
+                p.line(format!("Params {}", function.params.join(", ")));
+            }
+            if let Some(body) = &function.body {
+                p.labeled("Body", |p| p.block(body));
+            }
+        });
+    }
+
+    fn declaration(&mut self, declaration: &DeclarationAST) {
+        self.line(format!(
+            "Variable {}: {}{}",
+            declaration.name,
+            declaration.var_type,
+            storage_class(declaration.storage_class)
+        ));
+        if let Some(init) = &declaration.init {
+            self.nested(|p| p.initializer(init));
+        }
+    }
+
+    fn struct_declaration(&mut self, declaration: &StructDeclarationAST) {
+        let keyword = if declaration.is_union {
+            "Union"
+        } else {
+            "Struct"
+        };
+        self.line(format!("{} {}", keyword, declaration.tag));
+        if let Some(members) = &declaration.members {
+            self.nested(|p| {
+                for member in members {
+                    p.line(format!("Member {}: {}", member.name, member.member_type));
+                }
+            });
+        }
+    }
+
+    fn initializer(&mut self, init: &InitializerAST) {
+        match init {
+            InitializerAST::Single(expr) => self.expr(expr),
+            InitializerAST::Compound(inits) => self.labeled("Compound", |p| {
+                for init in inits {
+                    p.initializer(init);
+                }
+            }),
+        }
+    }
+
+    fn block(&mut self, block: &BlockAST) {
+        for item in &block.items {
+            match item {
+                BlockItemAST::Statement(stmt) => self.stmt(stmt),
+                BlockItemAST::Declaration(declaration) => self.declaration(declaration),
+                BlockItemAST::FunctionDeclaration(function) => self.function(function),
+                BlockItemAST::StructDeclaration(declaration) => {
+                    self.struct_declaration(declaration)
+                }
+            }
+        }
+    }
+
+    fn stmt(&mut self, stmt: &StmtAST) {
+        match stmt {
+            StmtAST::Return(expr) => self.labeled("Return", |p| p.expr(expr)),
+            StmtAST::Expression(expr) => self.labeled("Expression", |p| p.expr(expr)),
+            StmtAST::If {
+                condition,
+                then_stmt,
+                else_stmt,
+            } => self.labeled("If", |p| {
+                p.labeled("Condition", |p| p.expr(condition));
+                p.labeled("Then", |p| p.stmt(then_stmt));
+                if let Some(else_stmt) = else_stmt {
+                    p.labeled("Else", |p| p.stmt(else_stmt));
+                }
+            }),
+            StmtAST::Compound(block) => self.labeled("Block", |p| p.block(block)),
+            StmtAST::Break { label, .. } => self.line(format!("Break{}", loop_label(label))),
+            StmtAST::Continue { label, .. } => self.line(format!("Continue{}", loop_label(label))),
+            StmtAST::While {
+                condition,
+                body,
+                label,
+            } => self.labeled(&format!("While{}", loop_label(label)), |p| {
+                p.labeled("Condition", |p| p.expr(condition));
+                p.labeled("Body", |p| p.stmt(body));
+            }),
+            StmtAST::DoWhile {
+                body,
+                condition,
+                label,
+            } => self.labeled(&format!("DoWhile{}", loop_label(label)), |p| {
+                p.labeled("Body", |p| p.stmt(body));
+                p.labeled("Condition", |p| p.expr(condition));
+            }),
+            StmtAST::For {
+                init,
+                condition,
+                post,
+                body,
+                label,
+            } => self.labeled(&format!("For{}", loop_label(label)), |p| {
+                match init {
+                    ForInitAST::InitDecl(declaration) => {
+                        p.labeled("Init", |p| p.declaration(declaration))
+                    }
+                    ForInitAST::InitExpr(Some(expr)) => p.labeled("Init", |p| p.expr(expr)),
+                    ForInitAST::InitExpr(None) => {}
+                }
+                if let Some(condition) = condition {
+                    p.labeled("Condition", |p| p.expr(condition));
+                }
+                if let Some(post) = post {
+                    p.labeled("Post", |p| p.expr(post));
+                }
+                p.labeled("Body", |p| p.stmt(body));
+            }),
+            StmtAST::Switch {
+                condition,
+                body,
+                label,
+                ..
+            } => self.labeled(&format!("Switch{}", loop_label(label)), |p| {
+                p.labeled("Condition", |p| p.expr(condition));
+                p.labeled("Body", |p| p.stmt(body));
+            }),
+            StmtAST::Case {
+                value, body, label, ..
+            } => self.labeled(&format!("Case{}", loop_label(label)), |p| {
+                p.labeled("Value", |p| p.expr(value));
+                p.stmt(body);
+            }),
+            StmtAST::Default { body, label, .. } => {
+                self.labeled(&format!("Default{}", loop_label(label)), |p| p.stmt(body))
+            }
+            StmtAST::Goto { label, .. } => self.line(format!("Goto {}", label)),
+            StmtAST::Labeled { label, body, .. } => {
+                self.labeled(&format!("Label {}", label), |p| p.stmt(body))
+            }
+            StmtAST::Null => self.line("Null".to_string()),
+        }
+    }
+
+    fn expr(&mut self, expr: &ExprAST) {
+        match expr {
+            ExprAST::Constant(c) => self.line(format!("Constant {}", c)),
+            ExprAST::String(bytes) => self.line(format!(
+                "String {}",
+                Tokens::StringLiteral(bytes.clone()).spelling()
+            )),
+            ExprAST::Var { name, .. } => self.line(format!("Var {}", name)),
+            ExprAST::Unary(op, operand) => self
+                .labeled(&format!("Unary {}", unary_operator(*op)), |p| {
+                    p.expr(operand)
+                }),
+            ExprAST::Binary(op, lhs, rhs) => {
+                self.labeled(&format!("Binary {}", binary_operator(*op)), |p| {
+                    p.expr(lhs);
+                    p.expr(rhs);
+                })
+            }
+            ExprAST::Assignment { lhs, rhs, .. } => self.labeled("Assignment", |p| {
+                p.expr(lhs);
+                p.expr(rhs);
+            }),
+            ExprAST::CompoundAssignment { op, lhs, rhs, .. } => self.labeled(
+                &format!("CompoundAssignment {}=", binary_operator(*op)),
+                |p| {
+                    p.expr(lhs);
+                    p.expr(rhs);
+                },
+            ),
+            ExprAST::Increment { op, operand, .. } => {
+                let name = match op {
+                    IncrementOperatorAST::PreIncrement => "PreIncrement",
+                    IncrementOperatorAST::PreDecrement => "PreDecrement",
+                    IncrementOperatorAST::PostIncrement => "PostIncrement",
+                    IncrementOperatorAST::PostDecrement => "PostDecrement",
+                };
+                self.labeled(name, |p| p.expr(operand))
+            }
+            ExprAST::Conditional {
+                condition,
+                then_expr,
+                else_expr,
+            } => self.labeled("Conditional", |p| {
+                p.expr(condition);
+                p.expr(then_expr);
+                p.expr(else_expr);
+            }),
+            ExprAST::FunctionCall { name, args, .. } => {
+                self.labeled(&format!("FunctionCall {}", name), |p| {
+                    for arg in args {
+                        p.expr(arg);
+                    }
+                })
+            }
+            ExprAST::Cast {
+                target_type, expr, ..
+            } => self.labeled(&format!("Cast to {}", target_type), |p| p.expr(expr)),
+            ExprAST::Dereference { expr, .. } => self.labeled("Dereference", |p| p.expr(expr)),
+            ExprAST::AddressOf { expr, .. } => self.labeled("AddressOf", |p| p.expr(expr)),
+            ExprAST::Subscript { array, index, .. } => self.labeled("Subscript", |p| {
+                p.expr(array);
+                p.expr(index);
+            }),
+            ExprAST::SizeOf { expr, .. } => self.labeled("SizeOf", |p| p.expr(expr)),
+            ExprAST::SizeOfType { target_type, .. } => self.line(format!("SizeOf {}", target_type)),
+            ExprAST::Dot {
+                structure, member, ..
+            } => self.labeled(&format!("Dot .{}", member), |p| p.expr(structure)),
+            ExprAST::Arrow {
+                pointer, member, ..
+            } => self.labeled(&format!("Arrow ->{}", member), |p| p.expr(pointer)),
+        }
+    }
+}
+
+fn storage_class(storage_class: Option<StorageClassAST>) -> &'static str {
+    match storage_class {
+        Some(StorageClassAST::Static) => ", static",
+        Some(StorageClassAST::Extern) => ", extern",
+        None => "",
+    }
+}
+
+// SS: loop labeling attaches the label of the enclosing loop to loops, breaks and continues
+fn loop_label(label: &Option<String>) -> String {
+    match label {
+        Some(label) => format!(" ({})", label),
+        None => String::new(),
+    }
+}
+
+fn unary_operator(op: UnaryOperatorAST) -> &'static str {
+    match op {
+        UnaryOperatorAST::Negate => "-",
+        UnaryOperatorAST::Complement => "~",
+        UnaryOperatorAST::Not => "!",
+    }
+}
+
+fn binary_operator(op: BinaryOperatorAST) -> &'static str {
+    match op {
+        BinaryOperatorAST::Add => "+",
+        BinaryOperatorAST::Subtract => "-",
+        BinaryOperatorAST::Multiply => "*",
+        BinaryOperatorAST::Divide => "/",
+        BinaryOperatorAST::Remainder => "%",
+        BinaryOperatorAST::And => "&&",
+        BinaryOperatorAST::Or => "||",
+        BinaryOperatorAST::Equal => "==",
+        BinaryOperatorAST::NotEqual => "!=",
+        BinaryOperatorAST::LessThan => "<",
+        BinaryOperatorAST::LessOrEqual => "<=",
+        BinaryOperatorAST::GreaterThan => ">",
+        BinaryOperatorAST::GreaterOrEqual => ">=",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast_printer::print_program;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    #[test]
+    fn test_print_program() {
+        // SS: arrange
+        let source =
+            "int main(void) {\n    long x = 2;\n    while (x) x = x - 1;\n    return -x;\n}\n";
+        let ast = Parser::new(Lexer::new(source.to_string())).parse().unwrap();
+
+        // SS: act
+        let text = print_program(&ast);
+
+        // SS: assert
+        assert_eq!(
+            text,
+            [
+                "Program",
+                "  Function main: int (void)",
+                "    Body",
+                "      Variable x: long",
+                "        Constant 2",
+                "      While",
+                "        Condition",
+                "          Var x",
+                "        Body",
+                "          Expression",
+                "            Assignment",
+                "              Var x",
+                "              Binary -",
+                "                Var x",
+                "                Constant 1",
+                "      Return",
+                "        Unary -",
+                "          Var x",
+            ]
+            .join("\n")
+        );
+    }
+}
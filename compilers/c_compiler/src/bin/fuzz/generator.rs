@@ -0,0 +1,1284 @@
+use std::fmt::Display;
+
+// SS: helpers every generated program starts with. Signed arithmetic goes through the safe_
+// functions, which return their first operand instead of overflowing or dividing by zero,
+// and doubles are only converted to integers when the value fits. That keeps the programs
+// free of undefined behavior, so any difference between compilers is a bug.
+const PRELUDE: &str = "int putchar(int c);
+
+static int safe_add_int(int a, int b) { return (b > 0 && a > 2147483647 - b) || (b < 0 && a < -2147483647 - 1 - b) ? a : a + b; }
+static int safe_sub_int(int a, int b) { return (b < 0 && a > 2147483647 + b) || (b > 0 && a < -2147483647 - 1 + b) ? a : a - b; }
+static int safe_mul_int(int a, int b) { long r = (long)a * (long)b; return r > 2147483647L || r < -2147483648L ? a : (int)r; }
+static int safe_div_int(int a, int b) { return b == 0 || (a == -2147483647 - 1 && b == -1) ? a : a / b; }
+static int safe_mod_int(int a, int b) { return b == 0 || (a == -2147483647 - 1 && b == -1) ? a : a % b; }
+static int safe_neg_int(int a) { return a == -2147483647 - 1 ? a : -a; }
+static long safe_add_long(long a, long b) { return (b > 0L && a > 9223372036854775807L - b) || (b < 0L && a < -9223372036854775807L - 1L - b) ? a : a + b; }
+static long safe_sub_long(long a, long b) { return (b < 0L && a > 9223372036854775807L + b) || (b > 0L && a < -9223372036854775807L - 1L + b) ? a : a - b; }
+static long safe_mul_long(long a, long b) {
+    if (a == 0L || b == 0L) return 0L;
+    if (a > 0L ? (b > 0L ? a > 9223372036854775807L / b : b < (-9223372036854775807L - 1L) / a)
+               : (b > 0L ? a < (-9223372036854775807L - 1L) / b : b < 9223372036854775807L / a)) return a;
+    return a * b;
+}
+static long safe_div_long(long a, long b) { return b == 0L || (a == -9223372036854775807L - 1L && b == -1L) ? a : a / b; }
+static long safe_mod_long(long a, long b) { return b == 0L || (a == -9223372036854775807L - 1L && b == -1L) ? a : a % b; }
+static long safe_neg_long(long a) { return a == -9223372036854775807L - 1L ? a : -a; }
+static unsigned int safe_div_uint(unsigned int a, unsigned int b) { return b == 0u ? a : a / b; }
+static unsigned int safe_mod_uint(unsigned int a, unsigned int b) { return b == 0u ? a : a % b; }
+static unsigned long safe_div_ulong(unsigned long a, unsigned long b) { return b == 0ul ? a : a / b; }
+static unsigned long safe_mod_ulong(unsigned long a, unsigned long b) { return b == 0ul ? a : a % b; }
+static int safe_d2i(double d) { return d > -2147483649.0 && d < 2147483648.0 ? (int)d : 0; }
+static long safe_d2l(double d) { return d >= -9223372036854775808.0 && d < 9223372036854775808.0 ? (long)d : 0L; }
+static unsigned int safe_d2u(double d) { return d > -1.0 && d < 4294967296.0 ? (unsigned int)d : 0u; }
+static unsigned long safe_d2ul(double d) { return d > -1.0 && d < 18446744073709551616.0 ? (unsigned long)d : 0ul; }
+
+static int print_hex(unsigned long v, int digits) {
+    int digit = (int)(v % 16ul);
+    if (digits > 1) print_hex(v / 16ul, digits - 1);
+    putchar(digit < 10 ? '0' + digit : 'a' + digit - 10);
+    return 0;
+}
+";
+
+// SS: the only structure type, every generated program declares it
+const STRUCT_MEMBERS: [(&str, CType); 4] = [
+    ("a", CType::Int),
+    ("b", CType::Long),
+    ("c", CType::Double),
+    ("d", CType::UChar),
+];
+
+const SCALAR_TYPES: [CType; 7] = [
+    CType::Char,
+    CType::UChar,
+    CType::Int,
+    CType::Long,
+    CType::UInt,
+    CType::ULong,
+    CType::Double,
+];
+
+// SS: the types arithmetic is done in, narrower ones are promoted to int anyway
+const ARITHMETIC_TYPES: [CType; 5] = [
+    CType::Int,
+    CType::Long,
+    CType::UInt,
+    CType::ULong,
+    CType::Double,
+];
+
+// SS: limits that keep programs small and their run time bounded. Loops cannot contain
+// calls and functions only call the ones defined before them, so nothing runs for long.
+const MAX_FUNCTIONS: u64 = 6;
+const MAX_GLOBALS: u64 = 8;
+const MAX_STATEMENTS: u64 = 6;
+const MAX_BLOCK_DEPTH: usize = 3;
+const MAX_EXPR_DEPTH: usize = 4;
+const MAX_LOOP_BOUND: u64 = 6;
+const MAX_ARRAY_LENGTH: u64 = 5;
+const MAX_CALLS_PER_FUNCTION: usize = 2;
+const MAX_SWITCH_CASES: u64 = 5;
+
+// SS: splitmix64, small and good enough to pick program shapes. A seed always gives the
+// same program.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng(seed)
+    }
+
+    pub fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    pub fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    pub fn chance(&mut self, percent: u64) -> bool {
+        self.below(100) < percent
+    }
+
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len() as u64) as usize]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CType {
+    Char,
+    UChar,
+    Int,
+    Long,
+    UInt,
+    ULong,
+    Double,
+    Pointer(Box<CType>),
+    Array(Box<CType>, usize),
+    Struct,
+}
+
+impl CType {
+    fn is_signed(&self) -> bool {
+        matches!(self, CType::Char | CType::Int | CType::Long)
+    }
+
+    fn is_unsigned(&self) -> bool {
+        matches!(self, CType::UChar | CType::UInt | CType::ULong)
+    }
+
+    fn is_arithmetic(&self) -> bool {
+        self.is_signed() || self.is_unsigned() || *self == CType::Double
+    }
+
+    // SS: the suffix of the safe_ helpers for the type
+    fn helper_suffix(&self) -> &'static str {
+        match self {
+            CType::Int => "int",
+            CType::Long => "long",
+            CType::UInt => "uint",
+            CType::ULong => "ulong",
+            _ => unreachable!("no helpers for {}", self),
+        }
+    }
+
+    // SS: a declaration of name with this type, i.e. int *p or long a[3]
+    fn declare(&self, name: &str) -> String {
+        match self {
+            CType::Pointer(referenced) => format!("{} *{}", referenced, name),
+            CType::Array(element, length) => format!("{} {}[{}]", element, name, length),
+            _ => format!("{} {}", self, name),
+        }
+    }
+}
+
+impl Display for CType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CType::Char => write!(f, "char"),
+            CType::UChar => write!(f, "unsigned char"),
+            CType::Int => write!(f, "int"),
+            CType::Long => write!(f, "long"),
+            CType::UInt => write!(f, "unsigned int"),
+            CType::ULong => write!(f, "unsigned long"),
+            CType::Double => write!(f, "double"),
+            CType::Pointer(referenced) => write!(f, "{} *", referenced),
+            CType::Array(element, length) => write!(f, "{}[{}]", element, length),
+            CType::Struct => write!(f, "struct s"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Program {
+    pub globals: Vec<Global>,
+    pub functions: Vec<Function>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Global {
+    pub name: String,
+    pub ty: CType,
+    pub is_static: bool,
+    pub init: Initializer,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Initializer {
+    Single(Expr),
+    // SS: an array or structure, one expression per element or member
+    List(Vec<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    pub name: String,
+    pub ret: CType,
+    pub params: Vec<(String, CType)>,
+    pub body: Vec<Stmt>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Stmt {
+    Declare {
+        name: String,
+        ty: CType,
+        init: Initializer,
+    },
+    // SS: op is = or a compound assignment operator
+    Assign {
+        target: Expr,
+        op: &'static str,
+        value: Expr,
+    },
+    Increment {
+        target: Expr,
+        op: &'static str,
+    },
+    Call(Expr),
+    If {
+        condition: Expr,
+        then_body: Vec<Stmt>,
+        else_body: Vec<Stmt>,
+    },
+    // SS: for (int var = 0; var < bound; var = var + 1), the body never assigns var
+    For {
+        var: String,
+        bound: u64,
+        body: Vec<Stmt>,
+    },
+    // SS: the while, do-while and goto loops count var up from 0 first thing in the body, so
+    // a continue can not skip it. The goto loop jumps back to label while var < bound.
+    While {
+        var: String,
+        bound: u64,
+        body: Vec<Stmt>,
+    },
+    DoWhile {
+        var: String,
+        bound: u64,
+        body: Vec<Stmt>,
+    },
+    GotoLoop {
+        var: String,
+        label: String,
+        bound: u64,
+        body: Vec<Stmt>,
+    },
+    // SS: if (condition) goto label; past the body. The body is a block of its own, so the
+    // jump skips no declaration that is still in scope at the label.
+    GotoSkip {
+        condition: Expr,
+        label: String,
+        body: Vec<Stmt>,
+    },
+    // SS: switch on value % modulus, each case body is a block of its own for the same reason
+    Switch {
+        value: Expr,
+        modulus: u64,
+        cases: Vec<SwitchCase>,
+    },
+    Break,
+    Continue,
+    Return(Expr),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SwitchCase {
+    // SS: None is the default
+    pub value: Option<u64>,
+    pub body: Vec<Stmt>,
+    pub falls_through: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr {
+    pub ty: CType,
+    pub kind: ExprKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExprKind {
+    // SS: spelled the way C writes a constant of the type
+    Constant(String),
+    Var(String),
+    Deref(Box<Expr>),
+    Member(Box<Expr>, &'static str),
+    // SS: the index is an unsigned int, reduced modulo the length of the array
+    Index(Box<Expr>, Box<Expr>, usize),
+    AddressOf(Box<Expr>),
+    Unary(&'static str, Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+    Cast(Box<Expr>),
+    Conditional(Box<Expr>, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    // SS: a constant of an arithmetic type, which is what the shrinker replaces
+    // expressions with
+    pub fn constant(ty: &CType, value: i64) -> Expr {
+        let text = match ty {
+            CType::Char | CType::UChar => format!("({}){}", ty, value),
+            CType::Int if value < 0 => format!("({})", value),
+            CType::Int => value.to_string(),
+            CType::Long if value < 0 => format!("({}L)", value),
+            CType::Long => format!("{}L", value),
+            CType::UInt => format!("{}u", value as u32),
+            CType::ULong => format!("{}ul", value as u64),
+            CType::Double if value < 0 => format!("({}.0)", value),
+            CType::Double => format!("{}.0", value),
+            _ => unreachable!("no constants of type {}", ty),
+        };
+        Expr {
+            ty: ty.clone(),
+            kind: ExprKind::Constant(text),
+        }
+    }
+
+    // SS: the subexpressions, which the shrinker tries in place of the whole
+    pub fn children_mut(&mut self) -> Vec<&mut Expr> {
+        match &mut self.kind {
+            ExprKind::Constant(_) | ExprKind::Var(_) => vec![],
+            ExprKind::Deref(inner)
+            | ExprKind::Member(inner, _)
+            | ExprKind::AddressOf(inner)
+            | ExprKind::Unary(_, inner)
+            | ExprKind::Cast(inner) => vec![inner],
+            ExprKind::Index(array, index, _) => vec![array, index],
+            ExprKind::Binary(_, lhs, rhs) => vec![lhs, rhs],
+            ExprKind::Call(_, args) => args.iter_mut().collect(),
+            ExprKind::Conditional(condition, then_expr, else_expr) => {
+                vec![condition, then_expr, else_expr]
+            }
+        }
+    }
+}
+
+impl Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            ExprKind::Constant(text) | ExprKind::Var(text) => write!(f, "{}", text),
+            ExprKind::Deref(inner) => write!(f, "(*{})", inner),
+            ExprKind::Member(inner, member) => write!(f, "{}.{}", inner, member),
+            ExprKind::Index(array, index, length) => {
+                write!(f, "{}[({}) % {}u]", array, index, length)
+            }
+            ExprKind::AddressOf(inner) => write!(f, "&{}", inner),
+            ExprKind::Unary(op, inner) => write!(f, "({}{})", op, inner),
+            ExprKind::Binary(op, lhs, rhs) => write!(f, "({} {} {})", lhs, op, rhs),
+            ExprKind::Call(name, args) => {
+                let args = args.iter().map(Expr::to_string).collect::<Vec<_>>();
+                write!(f, "{}({})", name, args.join(", "))
+            }
+            ExprKind::Cast(inner) => write!(f, "(({}){})", self.ty, inner),
+            ExprKind::Conditional(condition, then_expr, else_expr) => {
+                write!(f, "({} ? {} : {})", condition, then_expr, else_expr)
+            }
+        }
+    }
+}
+
+impl Display for Initializer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Initializer::Single(expr) => write!(f, "{}", expr),
+            Initializer::List(exprs) => {
+                let exprs = exprs.iter().map(Expr::to_string).collect::<Vec<_>>();
+                write!(f, "{{{}}}", exprs.join(", "))
+            }
+        }
+    }
+}
+
+impl Stmt {
+    fn write(&self, f: &mut std::fmt::Formatter<'_>, depth: usize) -> std::fmt::Result {
+        let indent = "    ".repeat(depth);
+        match self {
+            Stmt::Declare { name, ty, init } => {
+                writeln!(f, "{}{} = {};", indent, ty.declare(name), init)
+            }
+            Stmt::Assign { target, op, value } => {
+                writeln!(f, "{}{} {} {};", indent, target, op, value)
+            }
+            Stmt::Increment { target, op } => writeln!(f, "{}{}{};", indent, target, op),
+            Stmt::Call(call) => writeln!(f, "{}{};", indent, call),
+            Stmt::If {
+                condition,
+                then_body,
+                else_body,
+            } => {
+                writeln!(f, "{}if ({}) {{", indent, condition)?;
+                write_block(f, then_body, depth + 1)?;
+                if else_body.is_empty() {
+                    writeln!(f, "{}}}", indent)
+                } else {
+                    writeln!(f, "{}}} else {{", indent)?;
+                    write_block(f, else_body, depth + 1)?;
+                    writeln!(f, "{}}}", indent)
+                }
+            }
+            Stmt::For { var, bound, body } => {
+                writeln!(
+                    f,
+                    "{}for (int {1} = 0; {1} < {2}; {1} = {1} + 1) {{",
+                    indent, var, bound
+                )?;
+                write_block(f, body, depth + 1)?;
+                writeln!(f, "{}}}", indent)
+            }
+            Stmt::While { var, bound, body } => {
+                writeln!(f, "{}int {} = 0;", indent, var)?;
+                writeln!(f, "{}while ({} < {}) {{", indent, var, bound)?;
+                write_counter(f, var, depth + 1)?;
+                write_block(f, body, depth + 1)?;
+                writeln!(f, "{}}}", indent)
+            }
+            Stmt::DoWhile { var, bound, body } => {
+                writeln!(f, "{}int {} = 0;", indent, var)?;
+                writeln!(f, "{}do {{", indent)?;
+                write_counter(f, var, depth + 1)?;
+                write_block(f, body, depth + 1)?;
+                writeln!(f, "{}}} while ({} < {});", indent, var, bound)
+            }
+            Stmt::GotoLoop {
+                var,
+                label,
+                bound,
+                body,
+            } => {
+                writeln!(f, "{}int {} = 0;", indent, var)?;
+                writeln!(f, "{}{}: {{", indent, label)?;
+                write_counter(f, var, depth + 1)?;
+                write_block(f, body, depth + 1)?;
+                writeln!(f, "{}}}", indent)?;
+                writeln!(f, "{}if ({} < {}) goto {};", indent, var, bound, label)
+            }
+            Stmt::GotoSkip {
+                condition,
+                label,
+                body,
+            } => {
+                writeln!(f, "{}if ({}) goto {};", indent, condition, label)?;
+                writeln!(f, "{}{{", indent)?;
+                write_block(f, body, depth + 1)?;
+                writeln!(f, "{}}}", indent)?;
+                writeln!(f, "{}{}:;", indent, label)
+            }
+            Stmt::Switch {
+                value,
+                modulus,
+                cases,
+            } => {
+                writeln!(f, "{}switch ({} % {}u) {{", indent, value, modulus)?;
+                for case in cases {
+                    match case.value {
+                        Some(value) => writeln!(f, "{}case {}: {{", indent, value)?,
+                        None => writeln!(f, "{}default: {{", indent)?,
+                    }
+                    write_block(f, &case.body, depth + 1)?;
+                    writeln!(f, "{}}}", indent)?;
+                    if !case.falls_through {
+                        writeln!(f, "{}    break;", indent)?;
+                    }
+                }
+                writeln!(f, "{}}}", indent)
+            }
+            Stmt::Break => writeln!(f, "{}break;", indent),
+            Stmt::Continue => writeln!(f, "{}continue;", indent),
+            Stmt::Return(expr) => writeln!(f, "{}return {};", indent, expr),
+        }
+    }
+}
+
+fn write_counter(f: &mut std::fmt::Formatter<'_>, var: &str, depth: usize) -> std::fmt::Result {
+    writeln!(f, "{}{1} = {1} + 1;", "    ".repeat(depth), var)
+}
+
+fn write_block(f: &mut std::fmt::Formatter<'_>, body: &[Stmt], depth: usize) -> std::fmt::Result {
+    for stmt in body {
+        stmt.write(f, depth)?;
+    }
+    Ok(())
+}
+
+impl Display for Program {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", PRELUDE)?;
+        let members = STRUCT_MEMBERS
+            .iter()
+            .map(|(name, ty)| format!("{};", ty.declare(name)))
+            .collect::<Vec<_>>();
+        writeln!(f, "struct s {{ {} }};\n", members.join(" "))?;
+        for global in &self.globals {
+            let storage_class = if global.is_static { "static " } else { "" };
+            writeln!(
+                f,
+                "{}{} = {};",
+                storage_class,
+                global.ty.declare(&global.name),
+                global.init
+            )?;
+        }
+        for function in &self.functions {
+            let params = function
+                .params
+                .iter()
+                .map(|(name, ty)| ty.declare(name))
+                .collect::<Vec<_>>();
+            let params = if params.is_empty() {
+                "void".to_string()
+            } else {
+                params.join(", ")
+            };
+            writeln!(f, "\n{} {}({}) {{", function.ret, function.name, params)?;
+            write_block(f, &function.body, 1)?;
+            writeln!(f, "}}")?;
+        }
+        Ok(())
+    }
+}
+
+struct Variable {
+    name: String,
+    ty: CType,
+    // SS: loop counters can be read but not assigned
+    assignable: bool,
+}
+
+struct Signature {
+    name: String,
+    ret: CType,
+    params: Vec<CType>,
+}
+
+pub struct Generator {
+    rng: Rng,
+    // SS: the innermost scope is last, the first one holds the globals
+    scopes: Vec<Vec<Variable>>,
+    functions: Vec<Signature>,
+    next_id: usize,
+    loop_depth: usize,
+    // SS: how many enclosing statements a continue or a break can leave
+    continue_depth: usize,
+    break_depth: usize,
+    calls_left: usize,
+}
+
+// SS: generates the program for a seed. Every function is called from main, which prints a
+// checksum of the values they return and of all globals, and exits with its low byte.
+pub fn generate(seed: u64) -> Program {
+    let mut generator = Generator {
+        rng: Rng::new(seed),
+        scopes: vec![vec![]],
+        functions: vec![],
+        next_id: 0,
+        loop_depth: 0,
+        continue_depth: 0,
+        break_depth: 0,
+        calls_left: 0,
+    };
+    let globals = (0..1 + generator.rng.below(MAX_GLOBALS))
+        .map(|_| generator.global())
+        .collect::<Vec<_>>();
+    let mut functions = (0..1 + generator.rng.below(MAX_FUNCTIONS))
+        .map(|_| generator.function())
+        .collect::<Vec<_>>();
+    functions.push(generator.main(&globals));
+    Program { globals, functions }
+}
+
+impl Generator {
+    fn fresh_name(&mut self, prefix: &str) -> String {
+        self.next_id += 1;
+        format!("{}_{}", prefix, self.next_id)
+    }
+
+    fn declare(&mut self, name: &str, ty: CType, assignable: bool) {
+        self.scopes.last_mut().unwrap().push(Variable {
+            name: name.to_string(),
+            ty,
+            assignable,
+        });
+    }
+
+    fn scalar_type(&mut self) -> CType {
+        self.rng.choose(&SCALAR_TYPES).clone()
+    }
+
+    fn integer_return_type(&mut self) -> CType {
+        self.rng
+            .choose(&[CType::Int, CType::Long, CType::UInt, CType::ULong])
+            .clone()
+    }
+
+    // SS: a constant of the type, biased towards the values at the edges of its range
+    fn constant(&mut self, ty: &CType) -> Expr {
+        let value = match self.rng.below(6) {
+            0 => 0,
+            1 => 1,
+            2 => -1,
+            3 => self.rng.below(200) as i64 - 100,
+            4 => self.rng.next() as i64,
+            _ => match ty {
+                CType::Char => *self.rng.choose(&[127, -128]),
+                CType::UChar => 255,
+                CType::Int => *self.rng.choose(&[i32::MAX as i64, i32::MIN as i64]),
+                CType::UInt => u32::MAX as i64,
+                _ => *self.rng.choose(&[i64::MAX, i64::MIN]),
+            },
+        };
+        // SS: C has no negative constants, the most negative values have to be written as
+        // an expression
+        match (ty, value) {
+            (CType::Int, _) if value as i32 == i32::MIN => Expr {
+                ty: CType::Int,
+                kind: ExprKind::Constant("(-2147483647 - 1)".to_string()),
+            },
+            (CType::Long, i64::MIN) => Expr {
+                ty: CType::Long,
+                kind: ExprKind::Constant("(-9223372036854775807L - 1L)".to_string()),
+            },
+            (CType::Char, _) => Expr::constant(ty, value as i8 as i64),
+            (CType::UChar, _) => Expr::constant(ty, value as u8 as i64),
+            (CType::Int, _) => Expr::constant(ty, value as i32 as i64),
+            // SS: doubles stay small enough to be exact, with a fraction now and then
+            (CType::Double, _) if self.rng.chance(50) => {
+                let value = (value % 4096) as f64 / 4.0;
+                Expr {
+                    ty: CType::Double,
+                    kind: ExprKind::Constant(format!("({:?})", value)),
+                }
+            }
+            (CType::Double, _) => Expr::constant(ty, value % 1_000_000),
+            _ => Expr::constant(ty, value),
+        }
+    }
+
+    // SS: static initializers have to be constants without casts or other operators, not
+    // even a unary minus
+    fn static_constant(&mut self, ty: &CType) -> Expr {
+        let value = self.rng.below(128);
+        let text = match ty {
+            CType::Char | CType::UChar | CType::Int => value.to_string(),
+            CType::Long => format!("{}L", value),
+            CType::UInt => format!("{}u", value),
+            CType::ULong => format!("{}ul", value),
+            CType::Double => format!("{:?}", value as f64 / 4.0),
+            _ => unreachable!("no constants of type {}", ty),
+        };
+        Expr {
+            ty: ty.clone(),
+            kind: ExprKind::Constant(text),
+        }
+    }
+
+    fn global(&mut self) -> Global {
+        let name = self.fresh_name("g");
+        let (ty, init) = match self.rng.below(6) {
+            0 => {
+                let element = self.scalar_type();
+                let length = 1 + self.rng.below(MAX_ARRAY_LENGTH) as usize;
+                let init = (0..length)
+                    .map(|_| self.static_constant(&element))
+                    .collect();
+                (
+                    CType::Array(Box::new(element), length),
+                    Initializer::List(init),
+                )
+            }
+            1 => {
+                let init = STRUCT_MEMBERS
+                    .iter()
+                    .map(|(_, ty)| self.static_constant(ty))
+                    .collect();
+                (CType::Struct, Initializer::List(init))
+            }
+            _ => {
+                let ty = self.scalar_type();
+                let init = Initializer::Single(self.static_constant(&ty));
+                (ty, init)
+            }
+        };
+        self.declare(&name, ty.clone(), true);
+        Global {
+            name,
+            ty,
+            is_static: self.rng.chance(30),
+            init,
+        }
+    }
+
+    fn function(&mut self) -> Function {
+        let name = self.fresh_name("f");
+        let ret = self.integer_return_type();
+        self.scopes.push(vec![]);
+        let params = (0..self.rng.below(4))
+            .map(|_| {
+                let ty = self.scalar_type();
+                let name = self.fresh_name("p");
+                self.declare(&name, ty.clone(), true);
+                (name, ty)
+            })
+            .collect::<Vec<_>>();
+        self.calls_left = MAX_CALLS_PER_FUNCTION;
+
+        let mut body = self.block(0);
+        body.push(Stmt::Return(self.value(&ret)));
+        self.scopes.pop();
+
+        self.functions.push(Signature {
+            name: name.clone(),
+            ret: ret.clone(),
+            params: params.iter().map(|(_, ty)| ty.clone()).collect(),
+        });
+        Function {
+            name,
+            ret,
+            params,
+            body,
+        }
+    }
+
+    // SS: calls every function once and folds the results and the final values of all
+    // globals into a checksum
+    fn main(&mut self, globals: &[Global]) -> Function {
+        let checksum = Expr {
+            ty: CType::ULong,
+            kind: ExprKind::Var("checksum".to_string()),
+        };
+        let mix = |value: Expr| Stmt::Assign {
+            target: checksum.clone(),
+            op: "=",
+            value: Expr {
+                ty: CType::ULong,
+                kind: ExprKind::Binary(
+                    "+",
+                    Box::new(Expr {
+                        ty: CType::ULong,
+                        kind: ExprKind::Binary(
+                            "*",
+                            Box::new(checksum.clone()),
+                            Box::new(Expr::constant(&CType::ULong, 31)),
+                        ),
+                    }),
+                    Box::new(convert(value, &CType::ULong)),
+                ),
+            },
+        };
+
+        let mut body = vec![Stmt::Declare {
+            name: "checksum".to_string(),
+            ty: CType::ULong,
+            init: Initializer::Single(Expr::constant(&CType::ULong, 0)),
+        }];
+        self.scopes.push(vec![]);
+        for index in 0..self.functions.len() {
+            self.calls_left = 0;
+            let args = self.functions[index]
+                .params
+                .clone()
+                .iter()
+                .map(|ty| self.expr(ty, 2))
+                .collect();
+            let signature = &self.functions[index];
+            body.push(mix(Expr {
+                ty: signature.ret.clone(),
+                kind: ExprKind::Call(signature.name.clone(), args),
+            }));
+        }
+        self.scopes.pop();
+
+        for global in globals {
+            let var = Expr {
+                ty: global.ty.clone(),
+                kind: ExprKind::Var(global.name.clone()),
+            };
+            match &global.ty {
+                CType::Array(element, length) => {
+                    for index in 0..*length {
+                        body.push(mix(Expr {
+                            ty: (**element).clone(),
+                            kind: ExprKind::Index(
+                                Box::new(var.clone()),
+                                Box::new(Expr::constant(&CType::UInt, index as i64)),
+                                *length,
+                            ),
+                        }));
+                    }
+                }
+                CType::Struct => {
+                    for (member, ty) in &STRUCT_MEMBERS {
+                        body.push(mix(Expr {
+                            ty: ty.clone(),
+                            kind: ExprKind::Member(Box::new(var.clone()), member),
+                        }));
+                    }
+                }
+                _ => body.push(mix(var)),
+            }
+        }
+
+        body.push(Stmt::Call(Expr {
+            ty: CType::Int,
+            kind: ExprKind::Call(
+                "print_hex".to_string(),
+                vec![checksum.clone(), Expr::constant(&CType::Int, 16)],
+            ),
+        }));
+        body.push(Stmt::Call(Expr {
+            ty: CType::Int,
+            kind: ExprKind::Call("putchar".to_string(), vec![Expr::constant(&CType::Int, 10)]),
+        }));
+        body.push(Stmt::Return(convert(
+            Expr {
+                ty: CType::ULong,
+                kind: ExprKind::Binary(
+                    "%",
+                    Box::new(checksum),
+                    Box::new(Expr::constant(&CType::ULong, 256)),
+                ),
+            },
+            &CType::Int,
+        )));
+        Function {
+            name: "main".to_string(),
+            ret: CType::Int,
+            params: vec![],
+            body,
+        }
+    }
+
+    fn block(&mut self, depth: usize) -> Vec<Stmt> {
+        self.scopes.push(vec![]);
+        let count = 1 + self.rng.below(MAX_STATEMENTS);
+        let body = (0..count).map(|_| self.stmt(depth)).collect();
+        self.scopes.pop();
+        body
+    }
+
+    fn stmt(&mut self, depth: usize) -> Stmt {
+        match self.rng.below(12) {
+            0 | 1 => self.declaration(),
+            2 if depth < MAX_BLOCK_DEPTH => Stmt::If {
+                condition: self.condition(),
+                then_body: self.block(depth + 1),
+                else_body: if self.rng.chance(50) {
+                    self.block(depth + 1)
+                } else {
+                    vec![]
+                },
+            },
+            3 if depth < MAX_BLOCK_DEPTH && self.loop_depth < 2 => self.loop_stmt(depth),
+            4 if self.can_call() => {
+                let ty = self.integer_return_type();
+                Stmt::Call(self.call(&ty))
+            }
+            5 => {
+                let ty = self
+                    .rng
+                    .choose(&[CType::UInt, CType::ULong, CType::Double])
+                    .clone();
+                match self.lvalue(&ty) {
+                    Some(target) => {
+                        let op = *self.rng.choose(&["++", "--"]);
+                        Stmt::Increment { target, op }
+                    }
+                    None => self.declaration(),
+                }
+            }
+            6 if depth < MAX_BLOCK_DEPTH => {
+                if self.rng.chance(50) {
+                    self.switch(depth)
+                } else {
+                    let condition = self.condition();
+                    Stmt::GotoSkip {
+                        condition,
+                        label: self.fresh_name("skip"),
+                        body: self.block(depth + 1),
+                    }
+                }
+            }
+            7 if self.break_depth > 0 => {
+                if self.continue_depth > 0 && self.rng.chance(50) {
+                    Stmt::Continue
+                } else {
+                    Stmt::Break
+                }
+            }
+            _ => {
+                let ty = self.scalar_type();
+                match self.lvalue(&ty) {
+                    Some(target) => {
+                        // SS: compound assignment is only safe where the arithmetic cannot
+                        // overflow
+                        let op = if ty.is_unsigned() || ty == CType::Double {
+                            *self.rng.choose(&["=", "=", "+=", "-=", "*="])
+                        } else {
+                            "="
+                        };
+                        // SS: neither the index of the target nor the value the compound
+                        // assignment reads may race with what a call changes
+                        let value = if op == "=" && !matches!(target.kind, ExprKind::Index(..)) {
+                            self.value(&ty)
+                        } else {
+                            self.expr(&ty, MAX_EXPR_DEPTH)
+                        };
+                        Stmt::Assign { target, op, value }
+                    }
+                    None => self.declaration(),
+                }
+            }
+        }
+    }
+
+    fn loop_stmt(&mut self, depth: usize) -> Stmt {
+        let kind = self.rng.below(4);
+        let var = self.fresh_name("i");
+        let bound = 1 + self.rng.below(MAX_LOOP_BOUND);
+        // SS: break and continue in the body of a goto loop leave the enclosing loop
+        let is_goto = kind == 3;
+        self.loop_depth += 1;
+        if !is_goto {
+            self.continue_depth += 1;
+            self.break_depth += 1;
+        }
+        self.scopes.push(vec![]);
+        self.declare(&var, CType::Int, false);
+        let body = self.block(depth + 1);
+        self.scopes.pop();
+        if !is_goto {
+            self.continue_depth -= 1;
+            self.break_depth -= 1;
+        }
+        self.loop_depth -= 1;
+        match kind {
+            0 => Stmt::For { var, bound, body },
+            1 => Stmt::While { var, bound, body },
+            2 => Stmt::DoWhile { var, bound, body },
+            _ => Stmt::GotoLoop {
+                var,
+                label: self.fresh_name("again"),
+                bound,
+                body,
+            },
+        }
+    }
+
+    // SS: the modulus keeps the value close to the case labels, which are dense enough for
+    // a jump table about half of the time
+    fn switch(&mut self, depth: usize) -> Stmt {
+        let value = self.expr(&CType::UInt, 2);
+        let modulus = 2 + self.rng.below(2 * MAX_SWITCH_CASES);
+        let mut values = (0..modulus).collect::<Vec<_>>();
+        let count = 1 + self.rng.below(MAX_SWITCH_CASES);
+        let mut labels = vec![];
+        while labels.len() < count as usize && !values.is_empty() {
+            let index = self.rng.below(values.len() as u64) as usize;
+            labels.push(Some(values.remove(index)));
+        }
+        if self.rng.chance(50) {
+            let index = self.rng.below(labels.len() as u64 + 1) as usize;
+            labels.insert(index, None);
+        }
+
+        self.break_depth += 1;
+        let cases = labels
+            .into_iter()
+            .map(|value| SwitchCase {
+                value,
+                body: self.block(depth + 1),
+                falls_through: self.rng.chance(25),
+            })
+            .collect();
+        self.break_depth -= 1;
+        Stmt::Switch {
+            value,
+            modulus,
+            cases,
+        }
+    }
+
+    fn declaration(&mut self) -> Stmt {
+        let name = self.fresh_name("l");
+        let (ty, init) = match self.rng.below(8) {
+            // SS: a pointer to a variable that is in scope, so it outlives the pointer
+            0 => {
+                let ty = self.scalar_type();
+                match self.variable(&ty, true) {
+                    Some(target) => (
+                        CType::Pointer(Box::new(ty.clone())),
+                        Initializer::Single(Expr {
+                            ty: CType::Pointer(Box::new(ty)),
+                            kind: ExprKind::AddressOf(Box::new(target)),
+                        }),
+                    ),
+                    None => (ty.clone(), Initializer::Single(self.constant(&ty))),
+                }
+            }
+            1 => {
+                let init = STRUCT_MEMBERS
+                    .iter()
+                    .map(|(_, ty)| self.expr(ty, 2))
+                    .collect();
+                (CType::Struct, Initializer::List(init))
+            }
+            _ => {
+                let ty = self.scalar_type();
+                let init = Initializer::Single(self.value(&ty));
+                (ty, init)
+            }
+        };
+        self.declare(&name, ty.clone(), true);
+        Stmt::Declare { name, ty, init }
+    }
+
+    fn variable(&mut self, ty: &CType, assignable_only: bool) -> Option<Expr> {
+        let candidates = self
+            .scopes
+            .iter()
+            .flatten()
+            .filter(|var| var.ty == *ty && (var.assignable || !assignable_only))
+            .map(|var| var.name.clone())
+            .collect::<Vec<_>>();
+        if candidates.is_empty() {
+            return None;
+        }
+        let name = self.rng.choose(&candidates).clone();
+        Some(Expr {
+            ty: ty.clone(),
+            kind: ExprKind::Var(name),
+        })
+    }
+
+    // SS: an object of the type, a variable or something reached through one, i.e. a
+    // member, an array element or the target of a pointer
+    fn lvalue(&mut self, ty: &CType) -> Option<Expr> {
+        let var_of = |var: &Variable| Expr {
+            ty: var.ty.clone(),
+            kind: ExprKind::Var(var.name.clone()),
+        };
+        let mut candidates = vec![];
+        for var in self.scopes.iter().flatten() {
+            match &var.ty {
+                t if t == ty && var.assignable => candidates.push(var_of(var)),
+                CType::Pointer(referenced) if **referenced == *ty => candidates.push(Expr {
+                    ty: ty.clone(),
+                    kind: ExprKind::Deref(Box::new(var_of(var))),
+                }),
+                CType::Struct => {
+                    for (member, member_type) in &STRUCT_MEMBERS {
+                        if member_type == ty {
+                            candidates.push(Expr {
+                                ty: ty.clone(),
+                                kind: ExprKind::Member(Box::new(var_of(var)), member),
+                            });
+                        }
+                    }
+                }
+                CType::Array(element, _) if **element == *ty => candidates.push(var_of(var)),
+                _ => {}
+            }
+        }
+        if candidates.is_empty() {
+            return None;
+        }
+        let candidate = self.rng.choose(&candidates).clone();
+        Some(match &candidate.ty {
+            CType::Array(_, length) => {
+                let length = *length;
+                let index = self.expr(&CType::UInt, 1);
+                Expr {
+                    ty: ty.clone(),
+                    kind: ExprKind::Index(Box::new(candidate), Box::new(index), length),
+                }
+            }
+            _ => candidate,
+        })
+    }
+
+    fn condition(&mut self) -> Expr {
+        let ty = self.scalar_type();
+        self.expr(&ty, 3)
+    }
+
+    fn leaf(&mut self, ty: &CType) -> Expr {
+        if self.rng.chance(60) {
+            let readable = if self.rng.chance(50) {
+                self.variable(ty, false)
+            } else {
+                self.lvalue(ty)
+            };
+            if let Some(expr) = readable {
+                return expr;
+            }
+        }
+        self.constant(ty)
+    }
+
+    fn can_call(&self) -> bool {
+        self.loop_depth == 0 && self.calls_left > 0 && !self.functions.is_empty()
+    }
+
+    // SS: the order in which C evaluates operands is unspecified, so a call that changes a
+    // global must not share an expression with a read of it. Calls are only ever the whole
+    // value of a statement.
+    fn value(&mut self, ty: &CType) -> Expr {
+        if self.can_call() && self.rng.chance(30) {
+            self.call(ty)
+        } else {
+            self.expr(ty, MAX_EXPR_DEPTH)
+        }
+    }
+
+    fn call(&mut self, ty: &CType) -> Expr {
+        self.calls_left -= 1;
+        let index = self.rng.below(self.functions.len() as u64) as usize;
+        let params = self.functions[index].params.clone();
+        let args = params.iter().map(|param| self.expr(param, 2)).collect();
+        let signature = &self.functions[index];
+        convert(
+            Expr {
+                ty: signature.ret.clone(),
+                kind: ExprKind::Call(signature.name.clone(), args),
+            },
+            ty,
+        )
+    }
+
+    fn expr(&mut self, ty: &CType, depth: usize) -> Expr {
+        if depth == 0 || self.rng.chance(25) {
+            return self.leaf(ty);
+        }
+        let depth = depth - 1;
+        // SS: arithmetic on char types happens in int
+        if !ARITHMETIC_TYPES.contains(ty) {
+            let operand = self.expr(&CType::Int, depth);
+            return convert(operand, ty);
+        }
+
+        match self.rng.below(8) {
+            0..=2 => self.arithmetic(ty, depth),
+            3 if *ty == CType::Int => {
+                let operand_type = self.scalar_type();
+                let op = *self.rng.choose(&["<", "<=", ">", ">=", "==", "!="]);
+                let lhs = self.expr(&operand_type, depth);
+                let rhs = self.expr(&operand_type, depth);
+                binary(ty, op, lhs, rhs)
+            }
+            4 if *ty == CType::Int => {
+                let op = *self.rng.choose(&["&&", "||"]);
+                let lhs = self.condition_of_depth(depth);
+                let rhs = self.condition_of_depth(depth);
+                binary(ty, op, lhs, rhs)
+            }
+            5 => {
+                let operand = self.expr(ty, depth);
+                self.unary(ty, operand)
+            }
+            6 => {
+                let from = self.scalar_type();
+                let operand = self.expr(&from, depth);
+                convert(operand, ty)
+            }
+            7 => {
+                let condition = self.condition_of_depth(depth);
+                let then_expr = self.expr(ty, depth);
+                let else_expr = self.expr(ty, depth);
+                Expr {
+                    ty: ty.clone(),
+                    kind: ExprKind::Conditional(
+                        Box::new(condition),
+                        Box::new(then_expr),
+                        Box::new(else_expr),
+                    ),
+                }
+            }
+            _ => self.leaf(ty),
+        }
+    }
+
+    fn condition_of_depth(&mut self, depth: usize) -> Expr {
+        let ty = self.scalar_type();
+        self.expr(&ty, depth)
+    }
+
+    fn arithmetic(&mut self, ty: &CType, depth: usize) -> Expr {
+        let lhs = self.expr(ty, depth);
+        let rhs = self.expr(ty, depth);
+        match ty {
+            CType::Double => {
+                let op = *self.rng.choose(&["+", "-", "*", "/"]);
+                binary(ty, op, lhs, rhs)
+            }
+            CType::UInt | CType::ULong => {
+                let op = *self.rng.choose(&["+", "-", "*", "/", "%"]);
+                match op {
+                    "/" => helper(ty, "div", vec![lhs, rhs]),
+                    "%" => helper(ty, "mod", vec![lhs, rhs]),
+                    _ => binary(ty, op, lhs, rhs),
+                }
+            }
+            _ => {
+                let op = *self.rng.choose(&["add", "sub", "mul", "div", "mod"]);
+                helper(ty, op, vec![lhs, rhs])
+            }
+        }
+    }
+
+    fn unary(&mut self, ty: &CType, operand: Expr) -> Expr {
+        let op = match ty {
+            CType::Double => "-",
+            _ => *self.rng.choose(&["-", "~"]),
+        };
+        if op == "-" && ty.is_signed() {
+            return helper(ty, "neg", vec![operand]);
+        }
+        Expr {
+            ty: ty.clone(),
+            kind: ExprKind::Unary(op, Box::new(operand)),
+        }
+    }
+}
+
+fn binary(ty: &CType, op: &'static str, lhs: Expr, rhs: Expr) -> Expr {
+    Expr {
+        ty: ty.clone(),
+        kind: ExprKind::Binary(op, Box::new(lhs), Box::new(rhs)),
+    }
+}
+
+fn helper(ty: &CType, op: &str, args: Vec<Expr>) -> Expr {
+    Expr {
+        ty: ty.clone(),
+        kind: ExprKind::Call(format!("safe_{}_{}", op, ty.helper_suffix()), args),
+    }
+}
+
+// SS: converts expr to the type, going through the safe_d2 helpers where a double might not
+// fit
+fn convert(expr: Expr, ty: &CType) -> Expr {
+    if expr.ty == *ty {
+        return expr;
+    }
+    if expr.ty == CType::Double && *ty != CType::Double {
+        let (via, name) = match ty {
+            CType::Long => (CType::Long, "safe_d2l"),
+            CType::UInt => (CType::UInt, "safe_d2u"),
+            CType::ULong => (CType::ULong, "safe_d2ul"),
+            _ => (CType::Int, "safe_d2i"),
+        };
+        let converted = Expr {
+            ty: via,
+            kind: ExprKind::Call(name.to_string(), vec![expr]),
+        };
+        return convert(converted, ty);
+    }
+    debug_assert!(expr.ty.is_arithmetic() && ty.is_arithmetic());
+    Expr {
+        ty: ty.clone(),
+        kind: ExprKind::Cast(Box::new(expr)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::generator::generate;
+
+    #[test]
+    fn test_generate_is_deterministic() {
+        // SS: act
+        let programs = [generate(7), generate(7), generate(8)].map(|program| program.to_string());
+
+        // SS: assert
+        assert_eq!(programs[0], programs[1]);
+        assert_ne!(programs[0], programs[2]);
+        assert!(programs[0].contains("\nint main(void) {\n"));
+        assert!(programs[0].contains("print_hex(checksum, 16);"));
+    }
+}
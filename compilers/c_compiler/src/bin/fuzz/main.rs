@@ -0,0 +1,162 @@
+// SS: differential testing. Generates random C programs without undefined behavior, runs
+// them through the system compiler and through this one and reports every program on
+// which they disagree, shrunk to a small reproducer.
+use clap::Parser;
+mod generator;
+mod runner;
+mod shrinker;
+
+use crate::generator::generate;
+use crate::runner::{MODES, Outcome, Runner};
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Parser)]
+#[command(name = "fuzz", version = "1.0")]
+struct Args {
+    /// Number of programs to generate
+    #[arg(long, default_value_t = 100)]
+    count: u64,
+
+    /// Seed of the first program, the ones after it use the seeds that follow. Defaults to
+    /// the current time
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// The compiler to test, defaults to c_compiler next to this executable
+    #[arg(long, value_name = "PATH")]
+    compiler: Option<PathBuf>,
+
+    /// Directory for the reproducers of failing programs and for temporary files
+    #[arg(long, value_name = "DIR", default_value = "fuzz_output")]
+    output_dir: PathBuf,
+
+    /// Print the program generated for the seed and exit
+    #[arg(long)]
+    print: bool,
+
+    /// Keep failing programs as they were generated
+    #[arg(long)]
+    no_shrink: bool,
+}
+
+fn main() {
+    let args = Args::parse();
+    let first_seed = args.seed.unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    });
+    if args.print {
+        print!("{}", generate(first_seed));
+        return;
+    }
+
+    let compiler = args.compiler.clone().unwrap_or_else(|| {
+        std::env::current_exe()
+            .unwrap()
+            .with_file_name("c_compiler")
+    });
+    let compiler = fs::canonicalize(&compiler).unwrap_or_else(|err| {
+        eprintln!("Compiler {:?} not found: {}", compiler, err);
+        process::exit(1);
+    });
+    let work_dir = args.output_dir.join("work");
+    fs::create_dir_all(&work_dir).unwrap_or_else(|err| {
+        eprintln!("Could not create {:?}: {}", work_dir, err);
+        process::exit(1);
+    });
+    let runner = Runner::new(compiler, work_dir);
+
+    let mut failures = 0;
+    for seed in first_seed..first_seed + args.count {
+        failures += fuzz(&args, &runner, seed);
+    }
+    println!(
+        "{} programs from seed {}, {} failures",
+        args.count, first_seed, failures
+    );
+    if failures > 0 {
+        process::exit(1);
+    }
+}
+
+// SS: runs the program for the seed in every mode, returns the number of modes that failed
+fn fuzz(args: &Args, runner: &Runner, seed: u64) -> u64 {
+    let program = generate(seed);
+    let reference = runner.run_reference(&program.to_string());
+    if !matches!(reference, Outcome::Exited { .. }) {
+        // SS: a bug in the generator rather than in the compiler
+        report(
+            args,
+            seed,
+            "reference",
+            &program.to_string(),
+            &reference,
+            &reference,
+        );
+        return 1;
+    }
+
+    let mut failures = 0;
+    for (mode, flags) in MODES {
+        let outcome = runner.run_compiler(&program.to_string(), flags);
+        if outcome == reference {
+            continue;
+        }
+        failures += 1;
+        println!(
+            "Seed {}: {} build disagrees with {:?}",
+            seed, mode, reference
+        );
+        let source = if args.no_shrink {
+            program.to_string()
+        } else {
+            shrinker::shrink(&program, |candidate| {
+                let source = candidate.to_string();
+                let expected = runner.run_reference(&source);
+                matches!(expected, Outcome::Exited { .. }) && {
+                    let actual = runner.run_compiler(&source, flags);
+                    actual != expected && actual.same_kind(&outcome)
+                }
+            })
+            .to_string()
+        };
+        // SS: the outcomes of the reproducer, which differ from those of the full program
+        let expected = runner.run_reference(&source);
+        let actual = runner.run_compiler(&source, flags);
+        report(args, seed, mode, &source, &expected, &actual);
+    }
+    failures
+}
+
+// SS: writes the program to the output directory with what was expected and what happened
+// in a comment at the top
+fn report(args: &Args, seed: u64, mode: &str, source: &str, expected: &Outcome, actual: &Outcome) {
+    let path = args.output_dir.join(format!("seed_{}_{}.c", seed, mode));
+    let header = format!(
+        "/* Seed {}, {} build\n * expected: {}\n * actual: {}\n */\n",
+        seed,
+        mode,
+        describe(expected),
+        describe(actual)
+    );
+    match fs::write(&path, header + source) {
+        Ok(()) => println!("Wrote {:?}", path),
+        Err(err) => eprintln!("Could not write {:?}: {}", path, err),
+    }
+}
+
+// SS: one line per outcome, a comment cannot contain the compiler's diagnostics verbatim
+fn describe(outcome: &Outcome) -> String {
+    let text = match outcome {
+        Outcome::Exited { code, stdout } => format!("exit code {}, output {:?}", code, stdout),
+        Outcome::CompileError(stderr) => format!("compile error {:?}", stderr),
+        Outcome::Crashed(message) => format!("crash {:?}", message),
+        Outcome::TimedOut => "timeout".to_string(),
+    };
+    text.replace("*/", "* /")
+}
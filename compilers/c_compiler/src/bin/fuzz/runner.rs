@@ -0,0 +1,150 @@
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const SYSTEM_COMPILER: &str = "cc";
+const COMPILE_TIMEOUT: Duration = Duration::from_secs(30);
+const RUN_TIMEOUT: Duration = Duration::from_secs(5);
+
+// SS: the ways this compiler is run on every program, each has to agree with the system
+// compiler
+pub const MODES: [(&str, &[&str]); 3] = [
+    ("plain", &[]),
+    ("optimized", &["--optimize"]),
+    ("interpreted", &["--interpret"]),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    Exited { code: i32, stdout: String },
+    CompileError(String),
+    Crashed(String),
+    TimedOut,
+}
+
+impl Outcome {
+    // SS: a failure stays the same failure while shrinking if the kind of outcome does not
+    // change, a miscompile must not turn into a compile error
+    pub fn same_kind(&self, other: &Outcome) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+}
+
+// SS: compiles and runs programs in a work directory of its own, the compiler writes its
+// output next to the input
+pub struct Runner {
+    compiler: PathBuf,
+    work_dir: PathBuf,
+}
+
+impl Runner {
+    pub fn new(compiler: PathBuf, work_dir: PathBuf) -> Self {
+        Runner { compiler, work_dir }
+    }
+
+    pub fn run_reference(&self, source: &str) -> Outcome {
+        let input = self.write_source(source);
+        let executable = self.work_dir.join("reference");
+        let mut command = Command::new(SYSTEM_COMPILER);
+        command.arg("-w").arg("-o").arg(&executable).arg(&input);
+        match self.execute(&mut command, COMPILE_TIMEOUT) {
+            Some((status, _, _)) if status.success() => self.run_executable(&executable),
+            Some((_, _, stderr)) => Outcome::CompileError(stderr),
+            None => Outcome::TimedOut,
+        }
+    }
+
+    pub fn run_compiler(&self, source: &str, flags: &[&str]) -> Outcome {
+        let input = self.write_source(source);
+        let executable = input.with_extension("");
+        let _ = fs::remove_file(&executable);
+        let mut command = Command::new(&self.compiler);
+        command.arg(&input).args(flags);
+        let Some((status, stdout, stderr)) = self.execute(&mut command, COMPILE_TIMEOUT) else {
+            return Outcome::TimedOut;
+        };
+        if flags.contains(&"--interpret") {
+            // SS: the compiler reports its progress on stdout as well, the program output
+            // follows
+            return match stdout.split_once("Running interpreter...\n") {
+                Some((_, program_stdout)) if status.code().is_some() => Outcome::Exited {
+                    code: status.code().unwrap(),
+                    stdout: program_stdout.to_string(),
+                },
+                _ => failure(status, stderr),
+            };
+        }
+        if !status.success() {
+            return failure(status, stderr);
+        }
+        self.run_executable(&executable)
+    }
+
+    fn write_source(&self, source: &str) -> PathBuf {
+        let path = self.work_dir.join("program.c");
+        fs::write(&path, source)
+            .unwrap_or_else(|err| panic!("Could not write {:?}: {}", path, err));
+        path
+    }
+
+    fn run_executable(&self, executable: &Path) -> Outcome {
+        match self.execute(&mut Command::new(executable), RUN_TIMEOUT) {
+            Some((status, stdout, _)) => match status.code() {
+                Some(code) => Outcome::Exited { code, stdout },
+                None => Outcome::Crashed(format!("{}", status)),
+            },
+            None => Outcome::TimedOut,
+        }
+    }
+
+    // SS: runs the command with its output going to files, so a chatty child can never
+    // block on a full pipe. None when it had to be killed.
+    fn execute(
+        &self,
+        command: &mut Command,
+        timeout: Duration,
+    ) -> Option<(ExitStatus, String, String)> {
+        let stdout_path = self.work_dir.join("stdout");
+        let stderr_path = self.work_dir.join("stderr");
+        let mut child = command
+            .current_dir(&self.work_dir)
+            .stdin(Stdio::null())
+            .stdout(File::create(&stdout_path).unwrap())
+            .stderr(File::create(&stderr_path).unwrap())
+            .spawn()
+            .unwrap_or_else(|err| {
+                panic!(
+                    "Could not run {:?}: {}",
+                    command.get_program().to_string_lossy(),
+                    err
+                )
+            });
+
+        let start = Instant::now();
+        let status = loop {
+            if let Some(status) = child.try_wait().unwrap() {
+                break status;
+            }
+            if start.elapsed() > timeout {
+                let _ = child.kill();
+                let _ = child.wait();
+                return None;
+            }
+            thread::sleep(Duration::from_millis(2));
+        };
+        let read = |path: &Path| String::from_utf8_lossy(&fs::read(path).unwrap()).into_owned();
+        Some((status, read(&stdout_path), read(&stderr_path)))
+    }
+}
+
+// SS: a compiler that panics or is killed crashed, one that exits with an error rejected
+// the program
+fn failure(status: ExitStatus, stderr: String) -> Outcome {
+    if status.code() == Some(1) && !stderr.contains("panicked") {
+        Outcome::CompileError(stderr)
+    } else {
+        Outcome::Crashed(format!("{}\n{}", status, stderr))
+    }
+}
@@ -0,0 +1,258 @@
+use crate::generator::{CType, Expr, ExprKind, Initializer, Program, Stmt};
+
+// SS: greedily makes the program smaller while is_interesting still holds for it. Edits are
+// numbered in a fixed order, every pass tries all of them and the loop stops after a pass
+// in which none was kept.
+pub fn shrink(program: &Program, mut is_interesting: impl FnMut(&Program) -> bool) -> Program {
+    let mut best = program.clone();
+    loop {
+        let mut progress = false;
+        let mut index = 0;
+        while let Some(candidate) = edit(&best, index) {
+            if is_interesting(&candidate) {
+                best = candidate;
+                progress = true;
+            } else {
+                index += 1;
+            }
+        }
+        if !progress {
+            return best;
+        }
+    }
+}
+
+// SS: the program with edit number index applied, None when there are fewer edits
+pub fn edit(program: &Program, index: usize) -> Option<Program> {
+    let mut candidate = program.clone();
+    let mut editor = Editor {
+        remaining: index,
+        done: false,
+    };
+    editor.program(&mut candidate);
+    editor.done.then_some(candidate)
+}
+
+struct Editor {
+    remaining: usize,
+    done: bool,
+}
+
+impl Editor {
+    // SS: counts an edit opportunity, true when it is the one to apply
+    fn take(&mut self) -> bool {
+        if self.done {
+            return false;
+        }
+        if self.remaining == 0 {
+            self.done = true;
+            return true;
+        }
+        self.remaining -= 1;
+        false
+    }
+
+    fn program(&mut self, program: &mut Program) {
+        for index in 0..program.globals.len() {
+            if self.take() {
+                program.globals.remove(index);
+                return;
+            }
+        }
+        for index in 0..program.functions.len() {
+            if program.functions[index].name != "main" && self.take() {
+                program.functions.remove(index);
+                return;
+            }
+        }
+        for function in &mut program.functions {
+            self.block(&mut function.body);
+            if self.done {
+                return;
+            }
+        }
+    }
+
+    fn block(&mut self, body: &mut Vec<Stmt>) {
+        // SS: a function that falls off its end returns garbage, so returns stay
+        for index in 0..body.len() {
+            if !matches!(body[index], Stmt::Return(_)) && self.take() {
+                body.remove(index);
+                return;
+            }
+        }
+        // SS: replaces compound statements by their contents
+        for index in 0..body.len() {
+            let replacement = match &mut body[index] {
+                Stmt::If {
+                    then_body,
+                    else_body,
+                    ..
+                } => {
+                    if self.take() {
+                        Some(then_body.clone())
+                    } else if !else_body.is_empty() && self.take() {
+                        Some(else_body.clone())
+                    } else {
+                        None
+                    }
+                }
+                Stmt::For { bound, .. }
+                | Stmt::While { bound, .. }
+                | Stmt::DoWhile { bound, .. }
+                | Stmt::GotoLoop { bound, .. }
+                    if *bound > 1 =>
+                {
+                    if self.take() {
+                        *bound = 1;
+                        return;
+                    }
+                    None
+                }
+                Stmt::GotoSkip { body, .. } if self.take() => Some(body.clone()),
+                Stmt::Switch { cases, .. } => {
+                    for index in 0..cases.len() {
+                        if self.take() {
+                            cases.remove(index);
+                            return;
+                        }
+                    }
+                    None
+                }
+                _ => None,
+            };
+            if let Some(replacement) = replacement {
+                body.splice(index..=index, replacement);
+                return;
+            }
+        }
+        for stmt in body.iter_mut() {
+            self.stmt(stmt);
+            if self.done {
+                return;
+            }
+        }
+    }
+
+    fn stmt(&mut self, stmt: &mut Stmt) {
+        match stmt {
+            Stmt::Declare { init, .. } => match init {
+                Initializer::Single(expr) => self.expr(expr),
+                Initializer::List(exprs) => {
+                    for expr in exprs {
+                        self.expr(expr);
+                    }
+                }
+            },
+            Stmt::Assign { target, value, .. } => {
+                self.lvalue(target);
+                self.expr(value);
+            }
+            Stmt::Increment { target, .. } => self.lvalue(target),
+            Stmt::Call(expr) | Stmt::Return(expr) => self.expr(expr),
+            Stmt::If {
+                condition,
+                then_body,
+                else_body,
+            } => {
+                self.expr(condition);
+                self.block(then_body);
+                self.block(else_body);
+            }
+            Stmt::For { body, .. }
+            | Stmt::While { body, .. }
+            | Stmt::DoWhile { body, .. }
+            | Stmt::GotoLoop { body, .. } => self.block(body),
+            Stmt::GotoSkip {
+                condition, body, ..
+            } => {
+                self.expr(condition);
+                self.block(body);
+            }
+            Stmt::Switch { value, cases, .. } => {
+                self.expr(value);
+                for case in cases {
+                    self.block(&mut case.body);
+                    if self.done {
+                        return;
+                    }
+                }
+            }
+            Stmt::Break | Stmt::Continue => {}
+        }
+    }
+
+    // SS: only the index of an assignment target can change, the rest has to stay an object
+    fn lvalue(&mut self, target: &mut Expr) {
+        if let ExprKind::Index(_, index, _) = &mut target.kind {
+            self.expr(index);
+        }
+    }
+
+    fn expr(&mut self, expr: &mut Expr) {
+        if self.done {
+            return;
+        }
+        let arithmetic = !matches!(
+            expr.ty,
+            CType::Pointer(_) | CType::Array(..) | CType::Struct
+        );
+        if arithmetic && !matches!(expr.kind, ExprKind::Constant(_)) {
+            for value in [0, 1] {
+                if self.take() {
+                    *expr = Expr::constant(&expr.ty, value);
+                    return;
+                }
+            }
+        }
+        if arithmetic && !matches!(expr.kind, ExprKind::Index(..) | ExprKind::Member(..)) {
+            let ty = expr.ty.clone();
+            let replacement = expr
+                .children_mut()
+                .into_iter()
+                .filter(|child| child.ty == ty)
+                .find_map(|child| self.take().then(|| child.clone()));
+            if let Some(replacement) = replacement {
+                *expr = replacement;
+                return;
+            }
+        }
+        match &mut expr.kind {
+            // SS: the operand of & has to stay an object as well
+            ExprKind::AddressOf(_) => {}
+            ExprKind::Index(_, index, _) => self.expr(index),
+            _ => {
+                for child in expr.children_mut() {
+                    self.expr(child);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::generator::{Program, Stmt, generate};
+    use crate::shrinker::shrink;
+
+    #[test]
+    fn test_shrink() {
+        // SS: arrange
+        let program = generate(3);
+        let is_interesting = |program: &Program| program.to_string().contains("checksum = (");
+
+        // SS: act
+        let shrunk = shrink(&program, is_interesting);
+
+        // SS: assert
+        let text = shrunk.to_string();
+        assert!(text.contains("checksum = ("));
+        assert!(text.len() < program.to_string().len());
+        assert!(shrunk.globals.is_empty());
+        assert_eq!(shrunk.functions.len(), 1);
+        assert!(matches!(
+            shrunk.functions[0].body.as_slice(),
+            [Stmt::Assign { .. }, Stmt::Return(_)]
+        ));
+    }
+}
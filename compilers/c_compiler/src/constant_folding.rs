@@ -0,0 +1,390 @@
+use crate::control_flow_graph::{ControlFlow, ControlFlowGraph, Instruction, NodeId};
+use crate::optimization::value_type;
+use crate::symbol_table::SymbolTable;
+use crate::tacky_ast::{
+    TackyBinaryOperatorAST, TackyInstructionAST, TackyUnaryOperatorAST, TackyValueAST,
+};
+use crate::types::Constant;
+
+// SS: evaluates every instruction whose operands are all constants at compile time,
+// replacing it with a copy of the result. Conditional jumps on a constant become an
+// unconditional jump or disappear, along with the edges they can no longer take. Integer
+// division by zero is left alone so that it still traps at run time. Returns whether
+// anything was folded.
+pub fn fold_constants(
+    cfg: &mut ControlFlowGraph<TackyInstructionAST>,
+    symbols: &SymbolTable,
+) -> bool {
+    let mut changed = false;
+    let mut folded_jumps = vec![];
+    for (id, block) in cfg.blocks.iter_mut() {
+        let mut instructions = vec![];
+        for instruction in block.instructions.drain(..) {
+            let Some(folded) = fold_instruction(&instruction, symbols) else {
+                instructions.push(instruction);
+                continue;
+            };
+            changed = true;
+            if !matches!(instruction.control_flow(), ControlFlow::Next) {
+                folded_jumps.push(*id);
+            }
+            instructions.extend(folded);
+        }
+        block.instructions = instructions;
+    }
+
+    // SS: a folded jump only ever goes to one of the places it could go before
+    if !folded_jumps.is_empty() {
+        let labels = cfg.labels();
+        for id in folded_jumps {
+            let block = &cfg.blocks[&id];
+            let successor = match block.instructions.last().map(|i| i.control_flow()) {
+                Some(ControlFlow::Jump(target)) => NodeId::Block(labels[target]),
+                _ => cfg.next_block(id),
+            };
+            for other in block.successors.clone() {
+                if other != successor {
+                    cfg.remove_edge(NodeId::Block(id), other);
+                }
+            }
+        }
+    }
+    changed
+}
+
+// SS: what the instruction folds to, None if it does not fold. A conditional jump that is
+// never taken folds to nothing.
+fn fold_instruction(
+    instruction: &TackyInstructionAST,
+    symbols: &SymbolTable,
+) -> Option<Option<TackyInstructionAST>> {
+    let folded = match instruction {
+        TackyInstructionAST::Unary {
+            op,
+            src: TackyValueAST::Constant(c),
+            dst,
+        } => fold_unary(*op, *c).map(|result| copy_constant(result, dst)),
+        TackyInstructionAST::Binary {
+            op,
+            src1: TackyValueAST::Constant(c1),
+            src2: TackyValueAST::Constant(c2),
+            dst,
+        } => fold_binary(*op, *c1, *c2).map(|result| copy_constant(result, dst)),
+        // SS: a copy can change the signedness, i.e. an int constant assigned to an unsigned
+        // variable, so convert it to the exact type of the destination. One that already
+        // has it is left as it is.
+        TackyInstructionAST::Copy {
+            src: TackyValueAST::Constant(c),
+            dst,
+        } => {
+            let converted = c.convert_to(&value_type(dst, symbols));
+            (converted != *c).then(|| copy_constant(converted, dst))
+        }
+        TackyInstructionAST::SignExtend {
+            src: TackyValueAST::Constant(c),
+            dst,
+        }
+        | TackyInstructionAST::Truncate {
+            src: TackyValueAST::Constant(c),
+            dst,
+        }
+        | TackyInstructionAST::ZeroExtend {
+            src: TackyValueAST::Constant(c),
+            dst,
+        }
+        | TackyInstructionAST::DoubleToInt {
+            src: TackyValueAST::Constant(c),
+            dst,
+        }
+        | TackyInstructionAST::DoubleToUInt {
+            src: TackyValueAST::Constant(c),
+            dst,
+        }
+        | TackyInstructionAST::IntToDouble {
+            src: TackyValueAST::Constant(c),
+            dst,
+        }
+        | TackyInstructionAST::UIntToDouble {
+            src: TackyValueAST::Constant(c),
+            dst,
+        } => Some(copy_constant(c.convert_to(&value_type(dst, symbols)), dst)),
+        TackyInstructionAST::JumpIfZero(TackyValueAST::Constant(c), target) => {
+            return Some(is_zero(c).then(|| TackyInstructionAST::Jump(target.clone())));
+        }
+        TackyInstructionAST::JumpIfNotZero(TackyValueAST::Constant(c), target) => {
+            return Some((!is_zero(c)).then(|| TackyInstructionAST::Jump(target.clone())));
+        }
+        TackyInstructionAST::JumpTable {
+            index: TackyValueAST::Constant(c),
+            targets,
+        } => targets
+            .get(c.bits() as usize)
+            .map(|target| TackyInstructionAST::Jump(target.clone())),
+        _ => None,
+    };
+    folded.map(Some)
+}
+
+fn copy_constant(c: Constant, dst: &TackyValueAST) -> TackyInstructionAST {
+    TackyInstructionAST::Copy {
+        src: TackyValueAST::Constant(c),
+        dst: dst.clone(),
+    }
+}
+
+// SS: -0.0 compares equal to zero, a NaN does not
+pub fn is_zero(c: &Constant) -> bool {
+    match c {
+        Constant::Double(val) => *val == 0.0,
+        _ => c.bits() == 0,
+    }
+}
+
+pub fn fold_unary(op: TackyUnaryOperatorAST, c: Constant) -> Option<Constant> {
+    match (op, c) {
+        (TackyUnaryOperatorAST::Not, _) => Some(Constant::Int(is_zero(&c) as i32)),
+        (TackyUnaryOperatorAST::Negate, Constant::Double(val)) => Some(Constant::Double(-val)),
+        (TackyUnaryOperatorAST::Complement, Constant::Double(_)) => None,
+        // SS: integer operands have been promoted to at least int, so the operation can be
+        // done on the 64 bit pattern and truncated back to the type of the operand
+        (TackyUnaryOperatorAST::Negate, _) => {
+            Some(Constant::Long(c.bits().wrapping_neg()).convert_to(&c.constant_type()))
+        }
+        (TackyUnaryOperatorAST::Complement, _) => {
+            Some(Constant::Long(!c.bits()).convert_to(&c.constant_type()))
+        }
+    }
+}
+
+pub fn fold_binary(op: TackyBinaryOperatorAST, c1: Constant, c2: Constant) -> Option<Constant> {
+    if let (Constant::Double(v1), Constant::Double(v2)) = (c1, c2) {
+        return fold_double(op, v1, v2);
+    }
+
+    // SS: both operands have already been converted to their common type
+    let operand_type = c1.constant_type();
+    if operand_type != c2.constant_type() {
+        return None;
+    }
+    let (v1, v2) = (c1.bits(), c2.bits());
+    let signed = operand_type.is_signed();
+    let compare = |ordering: fn(std::cmp::Ordering) -> bool| {
+        let result = if signed {
+            v1.cmp(&v2)
+        } else {
+            (v1 as u64).cmp(&(v2 as u64))
+        };
+        Some(Constant::Int(ordering(result) as i32))
+    };
+    let result = match op {
+        TackyBinaryOperatorAST::Add => v1.wrapping_add(v2),
+        TackyBinaryOperatorAST::Subtract => v1.wrapping_sub(v2),
+        TackyBinaryOperatorAST::Multiply => v1.wrapping_mul(v2),
+        TackyBinaryOperatorAST::Divide if signed => v1.checked_div(v2)?,
+        TackyBinaryOperatorAST::Divide => (v1 as u64).checked_div(v2 as u64)? as i64,
+        TackyBinaryOperatorAST::Remainder if signed => v1.checked_rem(v2)?,
+        TackyBinaryOperatorAST::Remainder => (v1 as u64).checked_rem(v2 as u64)? as i64,
+        TackyBinaryOperatorAST::Equal => return compare(|o| o.is_eq()),
+        TackyBinaryOperatorAST::NotEqual => return compare(|o| o.is_ne()),
+        TackyBinaryOperatorAST::LessThan => return compare(|o| o.is_lt()),
+        TackyBinaryOperatorAST::LessOrEqual => return compare(|o| o.is_le()),
+        TackyBinaryOperatorAST::GreaterThan => return compare(|o| o.is_gt()),
+        TackyBinaryOperatorAST::GreaterOrEqual => return compare(|o| o.is_ge()),
+    };
+    Some(Constant::Long(result).convert_to(&operand_type))
+}
+
+fn fold_double(op: TackyBinaryOperatorAST, v1: f64, v2: f64) -> Option<Constant> {
+    let result = match op {
+        TackyBinaryOperatorAST::Add => v1 + v2,
+        TackyBinaryOperatorAST::Subtract => v1 - v2,
+        TackyBinaryOperatorAST::Multiply => v1 * v2,
+        TackyBinaryOperatorAST::Divide => v1 / v2,
+        TackyBinaryOperatorAST::Remainder => return None,
+        TackyBinaryOperatorAST::Equal => return Some(Constant::Int((v1 == v2) as i32)),
+        TackyBinaryOperatorAST::NotEqual => return Some(Constant::Int((v1 != v2) as i32)),
+        TackyBinaryOperatorAST::LessThan => return Some(Constant::Int((v1 < v2) as i32)),
+        TackyBinaryOperatorAST::LessOrEqual => return Some(Constant::Int((v1 <= v2) as i32)),
+        TackyBinaryOperatorAST::GreaterThan => return Some(Constant::Int((v1 > v2) as i32)),
+        TackyBinaryOperatorAST::GreaterOrEqual => {
+            return Some(Constant::Int((v1 >= v2) as i32));
+        }
+    };
+    Some(Constant::Double(result))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constant_folding::fold_constants;
+    use crate::control_flow_graph::{ControlFlowGraph, NodeId};
+    use crate::symbol_table::{IdentifierAttrs, Symbol, SymbolTable};
+    use crate::tacky_ast::{
+        TackyBinaryOperatorAST, TackyInstructionAST, TackyUnaryOperatorAST, TackyValueAST,
+    };
+    use crate::types::{Constant, Type};
+
+    fn locals(vars: &[(&str, Type)]) -> SymbolTable {
+        vars.iter()
+            .map(|(name, symbol_type)| {
+                (
+                    name.to_string(),
+                    Symbol {
+                        symbol_type: symbol_type.clone(),
+                        attrs: IdentifierAttrs::Local,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    fn var(name: &str) -> TackyValueAST {
+        TackyValueAST::Var(name.to_string())
+    }
+
+    fn constant(c: Constant) -> TackyValueAST {
+        TackyValueAST::Constant(c)
+    }
+
+    #[test]
+    fn test_fold_constants() {
+        // SS: arrange
+        let symbols = locals(&[
+            ("a", Type::Int),
+            ("b", Type::UInt),
+            ("c", Type::Int),
+            ("d", Type::Double),
+            ("e", Type::Long),
+            ("f", Type::Int),
+        ]);
+        let mut cfg = ControlFlowGraph::new(vec![
+            TackyInstructionAST::Binary {
+                op: TackyBinaryOperatorAST::Add,
+                src1: constant(Constant::Int(i32::MAX)),
+                src2: constant(Constant::Int(1)),
+                dst: var("a"),
+            },
+            TackyInstructionAST::Binary {
+                op: TackyBinaryOperatorAST::Divide,
+                src1: constant(Constant::UInt(u32::MAX)),
+                src2: constant(Constant::UInt(2)),
+                dst: var("b"),
+            },
+            TackyInstructionAST::Binary {
+                op: TackyBinaryOperatorAST::LessThan,
+                src1: constant(Constant::UInt(1)),
+                src2: constant(Constant::UInt(u32::MAX)),
+                dst: var("c"),
+            },
+            TackyInstructionAST::Unary {
+                op: TackyUnaryOperatorAST::Negate,
+                src: constant(Constant::Double(0.0)),
+                dst: var("d"),
+            },
+            TackyInstructionAST::SignExtend {
+                src: constant(Constant::Int(-1)),
+                dst: var("e"),
+            },
+            TackyInstructionAST::Copy {
+                src: constant(Constant::Int(-1)),
+                dst: var("b"),
+            },
+            TackyInstructionAST::Binary {
+                op: TackyBinaryOperatorAST::Remainder,
+                src1: constant(Constant::Int(1)),
+                src2: constant(Constant::Int(0)),
+                dst: var("f"),
+            },
+            TackyInstructionAST::Return(var("a")),
+        ]);
+
+        // SS: act
+        let changed = fold_constants(&mut cfg, &symbols);
+
+        // SS: assert
+        assert!(changed);
+        assert_eq!(
+            cfg.into_instructions(),
+            vec![
+                TackyInstructionAST::Copy {
+                    src: constant(Constant::Int(i32::MIN)),
+                    dst: var("a"),
+                },
+                TackyInstructionAST::Copy {
+                    src: constant(Constant::UInt(u32::MAX / 2)),
+                    dst: var("b"),
+                },
+                TackyInstructionAST::Copy {
+                    src: constant(Constant::Int(1)),
+                    dst: var("c"),
+                },
+                TackyInstructionAST::Copy {
+                    src: constant(Constant::Double(-0.0)),
+                    dst: var("d"),
+                },
+                TackyInstructionAST::Copy {
+                    src: constant(Constant::Long(-1)),
+                    dst: var("e"),
+                },
+                TackyInstructionAST::Copy {
+                    src: constant(Constant::UInt(u32::MAX)),
+                    dst: var("b"),
+                },
+                TackyInstructionAST::Binary {
+                    op: TackyBinaryOperatorAST::Remainder,
+                    src1: constant(Constant::Int(1)),
+                    src2: constant(Constant::Int(0)),
+                    dst: var("f"),
+                },
+                TackyInstructionAST::Return(var("a")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fold_constants_conditional_jumps() {
+        // SS: arrange
+        let label = |name: &str| TackyInstructionAST::Label(name.to_string());
+        let mut cfg = ControlFlowGraph::new(vec![
+            TackyInstructionAST::JumpIfZero(constant(Constant::Int(0)), "b".to_string()),
+            label("a"),
+            TackyInstructionAST::JumpIfNotZero(constant(Constant::Long(0)), "c".to_string()),
+            label("b"),
+            TackyInstructionAST::JumpIfZero(constant(Constant::Double(-0.0)), "a".to_string()),
+            label("c"),
+            TackyInstructionAST::JumpIfZero(var("x"), "d".to_string()),
+            TackyInstructionAST::JumpIfNotZero(constant(Constant::UChar(1)), "c".to_string()),
+            label("d"),
+            TackyInstructionAST::Return(var("x")),
+        ]);
+        let symbols = locals(&[("x", Type::Int)]);
+
+        // SS: act
+        let changed = fold_constants(&mut cfg, &symbols);
+
+        // SS: assert
+        assert!(changed);
+        assert!(!fold_constants(&mut cfg, &symbols));
+        assert_eq!(cfg.successors(NodeId::Block(0)), &[NodeId::Block(2)]);
+        assert_eq!(cfg.successors(NodeId::Block(1)), &[NodeId::Block(2)]);
+        assert_eq!(cfg.successors(NodeId::Block(2)), &[NodeId::Block(1)]);
+        assert_eq!(
+            cfg.successors(NodeId::Block(3)),
+            &[NodeId::Block(5), NodeId::Block(4)]
+        );
+        assert_eq!(cfg.successors(NodeId::Block(4)), &[NodeId::Block(3)]);
+        assert_eq!(
+            cfg.into_instructions(),
+            vec![
+                TackyInstructionAST::Jump("b".to_string()),
+                label("a"),
+                label("b"),
+                TackyInstructionAST::Jump("a".to_string()),
+                label("c"),
+                TackyInstructionAST::JumpIfZero(var("x"), "d".to_string()),
+                TackyInstructionAST::Jump("c".to_string()),
+                label("d"),
+                TackyInstructionAST::Return(var("x")),
+            ]
+        );
+    }
+}
@@ -0,0 +1,441 @@
+use crate::assembly_ast::AssemblyInstructionAST;
+use crate::tacky_ast::TackyInstructionAST;
+use clap::ValueEnum;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Display;
+
+// SS: a node of the graph. Blocks are numbered in the order they appear in the function,
+// entry and exit stand for where execution starts and where it returns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum NodeId {
+    Entry,
+    Block(usize),
+    Exit,
+}
+
+// SS: how an instruction affects the flow of control, all the graph needs to know about it
+pub enum ControlFlow<'a> {
+    Label(&'a str),
+    Jump(&'a str),
+    ConditionalJump(&'a str),
+    // SS: an indirect jump to one of the targets, it never falls through
+    JumpTable(&'a [String]),
+    Return,
+    Next,
+}
+
+pub trait Instruction {
+    fn control_flow(&self) -> ControlFlow<'_>;
+}
+
+impl Instruction for TackyInstructionAST {
+    fn control_flow(&self) -> ControlFlow<'_> {
+        match self {
+            TackyInstructionAST::Label(label) => ControlFlow::Label(label),
+            TackyInstructionAST::Jump(target) => ControlFlow::Jump(target),
+            TackyInstructionAST::JumpIfZero(_, target)
+            | TackyInstructionAST::JumpIfNotZero(_, target) => ControlFlow::ConditionalJump(target),
+            TackyInstructionAST::JumpTable { targets, .. } => ControlFlow::JumpTable(targets),
+            TackyInstructionAST::Return(_) => ControlFlow::Return,
+            _ => ControlFlow::Next,
+        }
+    }
+}
+
+impl Instruction for AssemblyInstructionAST {
+    fn control_flow(&self) -> ControlFlow<'_> {
+        match self {
+            AssemblyInstructionAST::Label(label) => ControlFlow::Label(label),
+            AssemblyInstructionAST::Jmp(target) => ControlFlow::Jump(target),
+            AssemblyInstructionAST::JmpCC { target, .. } => ControlFlow::ConditionalJump(target),
+            AssemblyInstructionAST::JmpTable { targets, .. } => ControlFlow::JumpTable(targets),
+            AssemblyInstructionAST::Ret => ControlFlow::Return,
+            _ => ControlFlow::Next,
+        }
+    }
+}
+
+// SS: the formats the graph can be dumped in
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum CfgFormat {
+    Dot,
+}
+
+// SS: a straight-line sequence of instructions, only the first one can be jumped to and
+// only the last one can jump
+#[derive(Debug, Clone, PartialEq)]
+pub struct BasicBlock<I> {
+    pub instructions: Vec<I>,
+    pub predecessors: Vec<NodeId>,
+    pub successors: Vec<NodeId>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ControlFlowGraph<I> {
+    // SS: keyed by block number, so iterating visits the blocks in program order even after
+    // some have been removed
+    pub blocks: BTreeMap<usize, BasicBlock<I>>,
+    pub entry_successors: Vec<NodeId>,
+    pub exit_predecessors: Vec<NodeId>,
+}
+
+impl<I: Instruction> ControlFlowGraph<I> {
+    // SS: a block starts at a label and ends after a jump or return. Every block falls
+    // through to the next one unless it ends in an unconditional jump or a return, the last
+    // one falls through to the exit.
+    pub fn new(instructions: Vec<I>) -> Self {
+        let mut partitions = vec![];
+        let mut current = vec![];
+        for instruction in instructions {
+            match instruction.control_flow() {
+                ControlFlow::Label(_) => {
+                    if !current.is_empty() {
+                        partitions.push(std::mem::take(&mut current));
+                    }
+                    current.push(instruction);
+                }
+                ControlFlow::Jump(_)
+                | ControlFlow::ConditionalJump(_)
+                | ControlFlow::JumpTable(_)
+                | ControlFlow::Return => {
+                    current.push(instruction);
+                    partitions.push(std::mem::take(&mut current));
+                }
+                ControlFlow::Next => current.push(instruction),
+            }
+        }
+        if !current.is_empty() {
+            partitions.push(current);
+        }
+
+        let mut cfg = ControlFlowGraph {
+            blocks: partitions
+                .into_iter()
+                .enumerate()
+                .map(|(id, instructions)| {
+                    (
+                        id,
+                        BasicBlock {
+                            instructions,
+                            predecessors: vec![],
+                            successors: vec![],
+                        },
+                    )
+                })
+                .collect(),
+            entry_successors: vec![],
+            exit_predecessors: vec![],
+        };
+
+        let labels = cfg.labels();
+        let count = cfg.blocks.len();
+        let next = |id: usize| {
+            if id + 1 < count {
+                NodeId::Block(id + 1)
+            } else {
+                NodeId::Exit
+            }
+        };
+
+        cfg.add_edge(
+            NodeId::Entry,
+            if count == 0 {
+                NodeId::Exit
+            } else {
+                NodeId::Block(0)
+            },
+        );
+        for id in 0..count {
+            let successors = match cfg.blocks[&id].instructions.last().unwrap().control_flow() {
+                ControlFlow::Jump(target) => vec![NodeId::Block(labels[target])],
+                ControlFlow::ConditionalJump(target) => {
+                    vec![NodeId::Block(labels[target]), next(id)]
+                }
+                ControlFlow::JumpTable(targets) => targets
+                    .iter()
+                    .map(|target| NodeId::Block(labels[target]))
+                    .collect(),
+                ControlFlow::Return => vec![NodeId::Exit],
+                ControlFlow::Label(_) | ControlFlow::Next => vec![next(id)],
+            };
+            for successor in successors {
+                cfg.add_edge(NodeId::Block(id), successor);
+            }
+        }
+        cfg
+    }
+
+    // SS: the block each label starts
+    pub fn labels(&self) -> HashMap<String, usize> {
+        self.blocks
+            .iter()
+            .filter_map(
+                |(id, block)| match block.instructions.first()?.control_flow() {
+                    ControlFlow::Label(label) => Some((label.to_string(), *id)),
+                    _ => None,
+                },
+            )
+            .collect()
+    }
+}
+
+impl<I> ControlFlowGraph<I> {
+    pub fn successors(&self, node: NodeId) -> &[NodeId] {
+        match node {
+            NodeId::Entry => &self.entry_successors,
+            NodeId::Block(id) => &self.blocks[&id].successors,
+            NodeId::Exit => &[],
+        }
+    }
+
+    pub fn predecessors(&self, node: NodeId) -> &[NodeId] {
+        match node {
+            NodeId::Entry => &[],
+            NodeId::Block(id) => &self.blocks[&id].predecessors,
+            NodeId::Exit => &self.exit_predecessors,
+        }
+    }
+
+    pub fn add_edge(&mut self, from: NodeId, to: NodeId) {
+        let successors = match from {
+            NodeId::Entry => &mut self.entry_successors,
+            NodeId::Block(id) => &mut self.blocks.get_mut(&id).unwrap().successors,
+            NodeId::Exit => unreachable!("the exit has no successors"),
+        };
+        if successors.contains(&to) {
+            return;
+        }
+        successors.push(to);
+        match to {
+            NodeId::Block(id) => self.blocks.get_mut(&id).unwrap().predecessors.push(from),
+            NodeId::Exit => self.exit_predecessors.push(from),
+            NodeId::Entry => unreachable!("the entry has no predecessors"),
+        }
+    }
+
+    pub fn remove_edge(&mut self, from: NodeId, to: NodeId) {
+        match from {
+            NodeId::Entry => self.entry_successors.retain(|node| *node != to),
+            NodeId::Block(id) => {
+                if let Some(block) = self.blocks.get_mut(&id) {
+                    block.successors.retain(|node| *node != to);
+                }
+            }
+            NodeId::Exit => {}
+        }
+        match to {
+            NodeId::Block(id) => {
+                if let Some(block) = self.blocks.get_mut(&id) {
+                    block.predecessors.retain(|node| *node != from);
+                }
+            }
+            NodeId::Exit => self.exit_predecessors.retain(|node| *node != from),
+            NodeId::Entry => {}
+        }
+    }
+
+    // SS: removes a block along with all edges to and from it
+    pub fn remove_block(&mut self, id: usize) {
+        let block = &self.blocks[&id];
+        let predecessors = block.predecessors.clone();
+        let successors = block.successors.clone();
+        for predecessor in predecessors {
+            self.remove_edge(predecessor, NodeId::Block(id));
+        }
+        for successor in successors {
+            self.remove_edge(NodeId::Block(id), successor);
+        }
+        self.blocks.remove(&id);
+    }
+
+    // SS: the block that follows in program order, where this one falls through to
+    pub fn next_block(&self, id: usize) -> NodeId {
+        self.blocks
+            .range(id + 1..)
+            .next()
+            .map_or(NodeId::Exit, |(next, _)| NodeId::Block(*next))
+    }
+
+    // SS: the block that precedes in program order, the entry for the first one
+    pub fn previous_block(&self, id: usize) -> NodeId {
+        self.blocks
+            .range(..id)
+            .next_back()
+            .map_or(NodeId::Entry, |(previous, _)| NodeId::Block(*previous))
+    }
+
+    pub fn instructions(&self) -> impl Iterator<Item = &I> {
+        self.blocks
+            .values()
+            .flat_map(|block| block.instructions.iter())
+    }
+
+    pub fn into_instructions(self) -> Vec<I> {
+        self.blocks
+            .into_values()
+            .flat_map(|block| block.instructions)
+            .collect()
+    }
+}
+
+// SS: renders the graphs of a program's functions in Graphviz's dot language, each one in a
+// cluster of its own. Every block is a box listing its instructions.
+pub fn to_dot<I: Display>(graphs: &[(&str, ControlFlowGraph<I>)]) -> String {
+    let mut lines = vec![
+        "digraph cfg {".to_string(),
+        "    node [shape=box, fontname=monospace];".to_string(),
+    ];
+    for (name, cfg) in graphs {
+        let node = |id: &NodeId| match id {
+            NodeId::Entry => format!("\"{}.entry\"", name),
+            NodeId::Block(id) => format!("\"{}.{}\"", name, id),
+            NodeId::Exit => format!("\"{}.exit\"", name),
+        };
+
+        lines.push(format!("    subgraph \"cluster_{}\" {{", name));
+        lines.push(format!("        label=\"{}\";", name));
+        lines.push(format!(
+            "        {} [label=\"entry\", shape=oval];",
+            node(&NodeId::Entry)
+        ));
+        for (id, block) in &cfg.blocks {
+            // SS: \l ends a left-justified line of the label
+            let label = block
+                .instructions
+                .iter()
+                .map(|instruction| format!("{}\\l", escape(&instruction.to_string())))
+                .collect::<String>();
+            lines.push(format!(
+                "        {} [label=\"{}\"];",
+                node(&NodeId::Block(*id)),
+                label
+            ));
+        }
+        lines.push(format!(
+            "        {} [label=\"exit\", shape=oval];",
+            node(&NodeId::Exit)
+        ));
+
+        for successor in &cfg.entry_successors {
+            lines.push(format!(
+                "        {} -> {};",
+                node(&NodeId::Entry),
+                node(successor)
+            ));
+        }
+        for (id, block) in &cfg.blocks {
+            for successor in &block.successors {
+                lines.push(format!(
+                    "        {} -> {};",
+                    node(&NodeId::Block(*id)),
+                    node(successor)
+                ));
+            }
+        }
+        lines.push("    }".to_string());
+    }
+    lines.push("}".to_string());
+    lines.join("\n")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::control_flow_graph::{ControlFlowGraph, NodeId, to_dot};
+    use crate::tacky_ast::{TackyInstructionAST, TackyValueAST};
+    use crate::types::Constant;
+
+    #[test]
+    fn test_control_flow_graph() {
+        // SS: arrange
+        let x = TackyValueAST::Var("x".to_string());
+        let instructions = vec![
+            TackyInstructionAST::Copy {
+                src: TackyValueAST::Constant(Constant::Int(1)),
+                dst: x.clone(),
+            },
+            TackyInstructionAST::Label("loop".to_string()),
+            TackyInstructionAST::JumpIfZero(x.clone(), "end".to_string()),
+            TackyInstructionAST::Jump("loop".to_string()),
+            TackyInstructionAST::Label("end".to_string()),
+            TackyInstructionAST::Return(x),
+        ];
+
+        // SS: act
+        let cfg = ControlFlowGraph::new(instructions.clone());
+
+        // SS: assert
+        assert_eq!(cfg.blocks.len(), 4);
+        assert_eq!(cfg.successors(NodeId::Entry), &[NodeId::Block(0)]);
+        assert_eq!(cfg.successors(NodeId::Block(0)), &[NodeId::Block(1)]);
+        assert_eq!(
+            cfg.successors(NodeId::Block(1)),
+            &[NodeId::Block(3), NodeId::Block(2)]
+        );
+        assert_eq!(cfg.successors(NodeId::Block(2)), &[NodeId::Block(1)]);
+        assert_eq!(
+            cfg.predecessors(NodeId::Block(1)),
+            &[NodeId::Block(0), NodeId::Block(2)]
+        );
+        assert_eq!(cfg.predecessors(NodeId::Exit), &[NodeId::Block(3)]);
+        assert_eq!(cfg.into_instructions(), instructions);
+    }
+
+    #[test]
+    fn test_control_flow_graph_remove_block() {
+        // SS: arrange
+        let instructions = vec![
+            TackyInstructionAST::Jump("end".to_string()),
+            TackyInstructionAST::Return(TackyValueAST::Constant(Constant::Int(1))),
+            TackyInstructionAST::Label("end".to_string()),
+            TackyInstructionAST::Return(TackyValueAST::Constant(Constant::Int(2))),
+        ];
+        let mut cfg = ControlFlowGraph::new(instructions);
+
+        // SS: act
+        cfg.remove_block(1);
+
+        // SS: assert
+        assert_eq!(cfg.next_block(0), NodeId::Block(2));
+        assert_eq!(cfg.previous_block(2), NodeId::Block(0));
+        assert_eq!(cfg.predecessors(NodeId::Exit), &[NodeId::Block(2)]);
+        assert_eq!(cfg.predecessors(NodeId::Block(2)), &[NodeId::Block(0)]);
+    }
+
+    #[test]
+    fn test_to_dot() {
+        // SS: arrange
+        let x = TackyValueAST::Var("x".to_string());
+        let cfg = ControlFlowGraph::new(vec![
+            TackyInstructionAST::JumpIfZero(x.clone(), "end".to_string()),
+            TackyInstructionAST::Label("end".to_string()),
+            TackyInstructionAST::Return(x),
+        ]);
+
+        // SS: act
+        let dot = to_dot(&[("main", cfg)]);
+
+        // SS: assert
+        assert_eq!(
+            dot.lines().collect::<Vec<_>>(),
+            vec![
+                "digraph cfg {",
+                "    node [shape=box, fontname=monospace];",
+                "    subgraph \"cluster_main\" {",
+                "        label=\"main\";",
+                "        \"main.entry\" [label=\"entry\", shape=oval];",
+                "        \"main.0\" [label=\"if !x goto end\\l\"];",
+                "        \"main.1\" [label=\"end:\\lreturn x\\l\"];",
+                "        \"main.exit\" [label=\"exit\", shape=oval];",
+                "        \"main.entry\" -> \"main.0\";",
+                "        \"main.0\" -> \"main.1\";",
+                "        \"main.1\" -> \"main.exit\";",
+                "    }",
+                "}",
+            ]
+        );
+    }
+}
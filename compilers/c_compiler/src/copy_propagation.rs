@@ -0,0 +1,565 @@
+use crate::control_flow_graph::{ControlFlowGraph, NodeId};
+use crate::optimization::{destination, value_type};
+use crate::symbol_table::SymbolTable;
+use crate::tacky_ast::{TackyInstructionAST, TackyValueAST};
+use crate::types::Type;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ops::Range;
+
+// SS: a copy dst = src, both sides are values so that constants propagate as well
+type Copy = (TackyValueAST, TackyValueAST);
+
+// SS: replaces uses of a variable with the value most recently copied into it, provided
+// that copy reaches the use along every path. Copies that are known to be redundant are
+// removed. Aliased variables can change behind our back on any store or function call,
+// so copies involving them do not survive those. Returns whether anything was replaced
+// or removed.
+pub fn propagate_copies(
+    cfg: &mut ControlFlowGraph<TackyInstructionAST>,
+    symbols: &SymbolTable,
+    aliased: &HashSet<String>,
+) -> bool {
+    let mut propagation = CopyPropagation {
+        symbols,
+        numbering: CopyNumbering::new(cfg, aliased),
+    };
+    let reaching = propagation.find_reaching_copies(cfg);
+
+    let mut changed = false;
+    for (id, block) in cfg.blocks.iter_mut() {
+        let mut copies = reaching[id].clone();
+        let mut instructions = vec![];
+        for instruction in block.instructions.drain(..) {
+            if propagation.is_redundant(&instruction, &copies) {
+                changed = true;
+                continue;
+            }
+            let mut instruction = instruction;
+            for value in sources_mut(&mut instruction) {
+                if let Some(src) = propagation.numbering.copied_into(value, &copies) {
+                    *value = src.clone();
+                    changed = true;
+                }
+            }
+            // SS: the rewritten instruction computes the same values, so it can be used to
+            // track the copies within the block
+            if propagation.is_redundant(&instruction, &copies)
+                || matches!(&instruction, TackyInstructionAST::Copy { src, dst } if src == dst)
+            {
+                changed = true;
+                continue;
+            }
+            propagation.transfer(&instruction, &mut copies);
+            instructions.push(instruction);
+        }
+        block.instructions = instructions;
+    }
+    changed
+}
+
+// SS: a set of copies, bit i stands for the copy numbered i. Bits past the end are clear.
+#[derive(Debug, Clone, Default)]
+struct CopySet {
+    bits: Vec<u64>,
+}
+
+impl CopySet {
+    // SS: every copy numbered below count
+    fn all(count: usize) -> Self {
+        let mut bits = vec![u64::MAX; count / 64];
+        if !count.is_multiple_of(64) {
+            bits.push((1 << (count % 64)) - 1);
+        }
+        CopySet { bits }
+    }
+
+    fn contains(&self, number: usize) -> bool {
+        self.bits
+            .get(number / 64)
+            .is_some_and(|word| word & (1 << (number % 64)) != 0)
+    }
+
+    fn insert(&mut self, number: usize) {
+        if number / 64 >= self.bits.len() {
+            self.bits.resize(number / 64 + 1, 0);
+        }
+        self.bits[number / 64] |= 1 << (number % 64);
+    }
+
+    fn remove(&mut self, number: usize) {
+        if let Some(word) = self.bits.get_mut(number / 64) {
+            *word &= !(1 << (number % 64));
+        }
+    }
+
+    // SS: the lowest number in range that is in the set, a word at a time
+    fn first_in(&self, range: Range<usize>) -> Option<usize> {
+        let mut number = range.start;
+        while number < range.end {
+            let word = self.bits.get(number / 64)? >> (number % 64);
+            if word != 0 {
+                let found = number + word.trailing_zeros() as usize;
+                return (found < range.end).then_some(found);
+            }
+            number = (number / 64 + 1) * 64;
+        }
+        None
+    }
+
+    fn remove_range(&mut self, range: Range<usize>) {
+        let mut number = range.start;
+        while number < range.end.min(self.bits.len() * 64) {
+            let offset = number % 64;
+            let count = (64 - offset).min(range.end - number);
+            let mask = if count == 64 {
+                u64::MAX
+            } else {
+                ((1 << count) - 1) << offset
+            };
+            self.bits[number / 64] &= !mask;
+            number += count;
+        }
+    }
+
+    fn intersect(&mut self, other: &CopySet) {
+        self.bits.truncate(other.bits.len());
+        for (word, other) in self.bits.iter_mut().zip(&other.bits) {
+            *word &= other;
+        }
+    }
+
+    fn subtract(&mut self, other: &CopySet) {
+        for (word, other) in self.bits.iter_mut().zip(&other.bits) {
+            *word &= !other;
+        }
+    }
+}
+
+impl PartialEq for CopySet {
+    fn eq(&self, other: &Self) -> bool {
+        let word = |set: &CopySet, index: usize| set.bits.get(index).copied().unwrap_or(0);
+        (0..self.bits.len().max(other.bits.len())).all(|i| word(self, i) == word(other, i))
+    }
+}
+
+// SS: the copy instructions of a function, numbered so that the copies into the same
+// variable are next to each other. Finding the one that holds, or killing them all, then
+// looks at a range of bits rather than at every copy.
+struct CopyNumbering<'a> {
+    aliased: &'a HashSet<String>,
+    copies: Vec<Copy>,
+    numbers: HashMap<Copy, usize>,
+    into: HashMap<TackyValueAST, Range<usize>>,
+    from: HashMap<String, Vec<usize>>,
+    involving_aliased: CopySet,
+    // SS: rewriting a copy whose source was propagated makes a new one, numbered past the
+    // others. It can only be in the set of the block being rewritten, and only if it is the
+    // last copy into its destination added to that set.
+    latest_into: HashMap<TackyValueAST, usize>,
+}
+
+impl<'a> CopyNumbering<'a> {
+    fn new(cfg: &ControlFlowGraph<TackyInstructionAST>, aliased: &'a HashSet<String>) -> Self {
+        let mut seen = HashSet::new();
+        let mut sources = HashMap::<&TackyValueAST, Vec<&TackyValueAST>>::new();
+        let mut destinations = vec![];
+        for instruction in cfg.instructions() {
+            if let TackyInstructionAST::Copy { src, dst } = instruction
+                && seen.insert((src, dst))
+            {
+                sources
+                    .entry(dst)
+                    .or_insert_with(|| {
+                        destinations.push(dst);
+                        vec![]
+                    })
+                    .push(src);
+            }
+        }
+
+        let mut numbering = CopyNumbering {
+            aliased,
+            copies: vec![],
+            numbers: HashMap::new(),
+            into: HashMap::new(),
+            from: HashMap::new(),
+            involving_aliased: CopySet::default(),
+            latest_into: HashMap::new(),
+        };
+        for dst in destinations {
+            let start = numbering.copies.len();
+            for src in &sources[dst] {
+                numbering.add(src, dst);
+            }
+            numbering
+                .into
+                .insert(dst.clone(), start..numbering.copies.len());
+        }
+        numbering
+    }
+
+    fn add(&mut self, src: &TackyValueAST, dst: &TackyValueAST) -> usize {
+        let number = self.copies.len();
+        if let TackyValueAST::Var(name) = src {
+            self.from.entry(name.clone()).or_default().push(number);
+        }
+        let is_aliased = |value: &TackyValueAST| match value {
+            TackyValueAST::Var(name) => self.aliased.contains(name),
+            TackyValueAST::Constant(_) => false,
+        };
+        if is_aliased(src) || is_aliased(dst) {
+            self.involving_aliased.insert(number);
+        }
+        let copy = (src.clone(), dst.clone());
+        self.numbers.insert(copy.clone(), number);
+        self.copies.push(copy);
+        number
+    }
+
+    fn contains(&self, copies: &CopySet, src: &TackyValueAST, dst: &TackyValueAST) -> bool {
+        self.numbers
+            .get(&(src.clone(), dst.clone()))
+            .is_some_and(|number| copies.contains(*number))
+    }
+
+    fn insert(&mut self, copies: &mut CopySet, src: &TackyValueAST, dst: &TackyValueAST) {
+        let number = match self.numbers.get(&(src.clone(), dst.clone())) {
+            Some(number) => *number,
+            None => self.add(src, dst),
+        };
+        copies.insert(number);
+        self.latest_into.insert(dst.clone(), number);
+    }
+
+    // SS: removes the copies a write to value makes stale, those into it and those from it
+    fn kill(&self, copies: &mut CopySet, value: &TackyValueAST) {
+        if let Some(range) = self.into.get(value) {
+            copies.remove_range(range.clone());
+        }
+        if let Some(number) = self.latest_into.get(value) {
+            copies.remove(*number);
+        }
+        if let TackyValueAST::Var(name) = value
+            && let Some(numbers) = self.from.get(name)
+        {
+            for number in numbers {
+                copies.remove(*number);
+            }
+        }
+    }
+
+    // SS: the value copied into value, if that copy is in copies. Each copy into it kills
+    // the others, so there is at most one.
+    fn copied_into(&self, value: &TackyValueAST, copies: &CopySet) -> Option<&TackyValueAST> {
+        let number = self
+            .latest_into
+            .get(value)
+            .copied()
+            .filter(|number| copies.contains(*number))
+            .or_else(|| copies.first_in(self.into.get(value)?.clone()))?;
+        Some(&self.copies[number].0)
+    }
+}
+
+struct CopyPropagation<'a> {
+    symbols: &'a SymbolTable,
+    numbering: CopyNumbering<'a>,
+}
+
+impl CopyPropagation<'_> {
+    // SS: a forward analysis, a copy reaches the start of a block if it reaches the end of
+    // all its predecessors. Blocks start out with every copy in the function reaching them
+    // and the sets shrink until nothing changes.
+    fn find_reaching_copies(
+        &mut self,
+        cfg: &ControlFlowGraph<TackyInstructionAST>,
+    ) -> HashMap<usize, CopySet> {
+        let all_copies = CopySet::all(self.numbering.copies.len());
+        let mut outgoing = cfg
+            .blocks
+            .keys()
+            .map(|id| (*id, all_copies.clone()))
+            .collect::<HashMap<_, _>>();
+        let mut incoming = HashMap::new();
+
+        let mut worklist = cfg.blocks.keys().copied().collect::<VecDeque<_>>();
+        let mut in_worklist = cfg
+            .blocks
+            .keys()
+            .map(|id| (*id, true))
+            .collect::<HashMap<_, _>>();
+        while let Some(id) = worklist.pop_front() {
+            in_worklist.insert(id, false);
+            let mut copies: Option<CopySet> = None;
+            for predecessor in cfg.predecessors(NodeId::Block(id)) {
+                let reaching = match predecessor {
+                    NodeId::Block(predecessor) => &outgoing[predecessor],
+                    _ => &CopySet::default(),
+                };
+                match &mut copies {
+                    Some(copies) => copies.intersect(reaching),
+                    None => copies = Some(reaching.clone()),
+                }
+            }
+            let copies = copies.unwrap_or_default();
+            incoming.insert(id, copies.clone());
+
+            let mut copies = copies;
+            for instruction in &cfg.blocks[&id].instructions {
+                self.transfer(instruction, &mut copies);
+            }
+            if copies != outgoing[&id] {
+                outgoing.insert(id, copies);
+                for successor in cfg.successors(NodeId::Block(id)) {
+                    if let NodeId::Block(successor) = successor
+                        && !in_worklist[successor]
+                    {
+                        in_worklist.insert(*successor, true);
+                        worklist.push_back(*successor);
+                    }
+                }
+            }
+        }
+        incoming
+    }
+
+    fn transfer(&mut self, instruction: &TackyInstructionAST, copies: &mut CopySet) {
+        match instruction {
+            TackyInstructionAST::Copy { src, dst } => {
+                if self.is_redundant(instruction, copies) {
+                    return;
+                }
+                self.numbering.kill(copies, dst);
+                if src != dst && self.same_representation(src, dst) {
+                    self.numbering.insert(copies, src, dst);
+                }
+            }
+            TackyInstructionAST::FunctionCall { dst, .. } => {
+                self.numbering.kill(copies, dst);
+                copies.subtract(&self.numbering.involving_aliased);
+            }
+            TackyInstructionAST::Store { .. } => copies.subtract(&self.numbering.involving_aliased),
+            TackyInstructionAST::CopyToOffset { dst, .. } => {
+                self.numbering
+                    .kill(copies, &TackyValueAST::Var(dst.clone()));
+            }
+            _ => {
+                if let Some(dst) = destination(instruction) {
+                    self.numbering.kill(copies, dst);
+                }
+            }
+        }
+    }
+
+    // SS: copying y into x is redundant if x already holds y, whether from the same copy
+    // or from copying x into y
+    fn is_redundant(&self, instruction: &TackyInstructionAST, copies: &CopySet) -> bool {
+        match instruction {
+            TackyInstructionAST::Copy { src, dst } => {
+                self.numbering.contains(copies, src, dst)
+                    || self.numbering.contains(copies, dst, src)
+            }
+            _ => false,
+        }
+    }
+
+    // SS: a copy between an int and an unsigned int changes the type of the value, so it
+    // can only be propagated if both sides are stored the same way
+    fn same_representation(&self, src: &TackyValueAST, dst: &TackyValueAST) -> bool {
+        let src_type = value_type(src, self.symbols);
+        let dst_type = value_type(dst, self.symbols);
+        src_type == dst_type
+            || matches!(
+                (&src_type, &dst_type),
+                (Type::ULong, Type::Pointer(_))
+                    | (Type::Pointer(_), Type::ULong)
+                    | (Type::Pointer(_), Type::Pointer(_))
+            )
+    }
+}
+
+// SS: the operands an instruction reads. The object whose address is taken and the
+// aggregates of the offset copies are not values and are never replaced.
+fn sources_mut(instruction: &mut TackyInstructionAST) -> Vec<&mut TackyValueAST> {
+    match instruction {
+        TackyInstructionAST::Return(value)
+        | TackyInstructionAST::JumpIfZero(value, _)
+        | TackyInstructionAST::JumpIfNotZero(value, _)
+        | TackyInstructionAST::JumpTable { index: value, .. } => vec![value],
+        TackyInstructionAST::Unary { src, .. }
+        | TackyInstructionAST::Copy { src, .. }
+        | TackyInstructionAST::SignExtend { src, .. }
+        | TackyInstructionAST::Truncate { src, .. }
+        | TackyInstructionAST::ZeroExtend { src, .. }
+        | TackyInstructionAST::DoubleToInt { src, .. }
+        | TackyInstructionAST::DoubleToUInt { src, .. }
+        | TackyInstructionAST::IntToDouble { src, .. }
+        | TackyInstructionAST::UIntToDouble { src, .. }
+        | TackyInstructionAST::CopyToOffset { src, .. } => vec![src],
+        TackyInstructionAST::Binary { src1, src2, .. } => vec![src1, src2],
+        TackyInstructionAST::Load { src_ptr, .. } => vec![src_ptr],
+        TackyInstructionAST::Store { src, dst_ptr } => vec![src, dst_ptr],
+        TackyInstructionAST::AddPtr { ptr, index, .. } => vec![ptr, index],
+        TackyInstructionAST::FunctionCall { args, .. } => args.iter_mut().collect(),
+        TackyInstructionAST::GetAddress { .. }
+        | TackyInstructionAST::CopyFromOffset { .. }
+        | TackyInstructionAST::Jump(_)
+        | TackyInstructionAST::Label(_) => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::control_flow_graph::ControlFlowGraph;
+    use crate::copy_propagation::propagate_copies;
+    use crate::symbol_table::{IdentifierAttrs, InitialValue, Symbol, SymbolTable};
+    use crate::tacky_ast::{TackyBinaryOperatorAST, TackyInstructionAST, TackyValueAST};
+    use crate::types::{Constant, Type};
+    use std::collections::HashSet;
+
+    fn symbol(symbol_type: Type) -> Symbol {
+        Symbol {
+            symbol_type,
+            attrs: IdentifierAttrs::Local,
+        }
+    }
+
+    fn var(name: &str) -> TackyValueAST {
+        TackyValueAST::Var(name.to_string())
+    }
+
+    fn int(val: i32) -> TackyValueAST {
+        TackyValueAST::Constant(Constant::Int(val))
+    }
+
+    #[test]
+    fn test_propagate_copies() {
+        // SS: arrange
+        let symbols = ["x", "y", "z"]
+            .into_iter()
+            .map(|name| (name.to_string(), symbol(Type::Int)))
+            .collect::<SymbolTable>();
+        let mut cfg = ControlFlowGraph::new(vec![
+            TackyInstructionAST::Copy {
+                src: int(3),
+                dst: var("x"),
+            },
+            TackyInstructionAST::Copy {
+                src: var("x"),
+                dst: var("y"),
+            },
+            TackyInstructionAST::Copy {
+                src: var("y"),
+                dst: var("x"),
+            },
+            TackyInstructionAST::Binary {
+                op: TackyBinaryOperatorAST::Add,
+                src1: var("x"),
+                src2: var("y"),
+                dst: var("z"),
+            },
+            TackyInstructionAST::Return(var("z")),
+        ]);
+
+        // SS: act
+        propagate_copies(&mut cfg, &symbols, &HashSet::new());
+
+        // SS: assert
+        assert_eq!(
+            cfg.into_instructions(),
+            vec![
+                TackyInstructionAST::Copy {
+                    src: int(3),
+                    dst: var("x"),
+                },
+                TackyInstructionAST::Copy {
+                    src: int(3),
+                    dst: var("y"),
+                },
+                TackyInstructionAST::Binary {
+                    op: TackyBinaryOperatorAST::Add,
+                    src1: int(3),
+                    src2: int(3),
+                    dst: var("z"),
+                },
+                TackyInstructionAST::Return(var("z")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_propagate_copies_meets_paths() {
+        // SS: arrange
+        let symbols = ["x", "c"]
+            .into_iter()
+            .map(|name| (name.to_string(), symbol(Type::Int)))
+            .collect::<SymbolTable>();
+        let instructions = vec![
+            TackyInstructionAST::Copy {
+                src: int(1),
+                dst: var("x"),
+            },
+            TackyInstructionAST::JumpIfZero(var("c"), "end".to_string()),
+            TackyInstructionAST::Copy {
+                src: int(2),
+                dst: var("x"),
+            },
+            TackyInstructionAST::Label("end".to_string()),
+            TackyInstructionAST::Return(var("x")),
+        ];
+        let mut cfg = ControlFlowGraph::new(instructions.clone());
+
+        // SS: act
+        propagate_copies(&mut cfg, &symbols, &HashSet::new());
+
+        // SS: assert
+        assert_eq!(cfg.into_instructions(), instructions);
+    }
+
+    #[test]
+    fn test_propagate_copies_aliased_and_converted() {
+        // SS: arrange
+        let mut symbols = SymbolTable::new();
+        symbols.insert("g".to_string(), {
+            Symbol {
+                symbol_type: Type::Int,
+                attrs: IdentifierAttrs::Static {
+                    init: InitialValue::Tentative,
+                    global: true,
+                },
+            }
+        });
+        symbols.insert("x".to_string(), symbol(Type::Int));
+        symbols.insert("u".to_string(), symbol(Type::UInt));
+        let aliased = HashSet::from(["g".to_string()]);
+        let instructions = vec![
+            TackyInstructionAST::Copy {
+                src: int(1),
+                dst: var("g"),
+            },
+            TackyInstructionAST::Copy {
+                src: var("u"),
+                dst: var("x"),
+            },
+            TackyInstructionAST::FunctionCall {
+                name: "f".to_string(),
+                args: vec![],
+                dst: var("u"),
+            },
+            TackyInstructionAST::Binary {
+                op: TackyBinaryOperatorAST::Add,
+                src1: var("g"),
+                src2: var("x"),
+                dst: var("x"),
+            },
+            TackyInstructionAST::Return(var("x")),
+        ];
+        let mut cfg = ControlFlowGraph::new(instructions.clone());
+
+        // SS: act
+        propagate_copies(&mut cfg, &symbols, &aliased);
+
+        // SS: assert
+        assert_eq!(cfg.into_instructions(), instructions);
+    }
+}
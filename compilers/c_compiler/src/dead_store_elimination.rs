@@ -0,0 +1,261 @@
+use crate::control_flow_graph::{ControlFlowGraph, NodeId};
+use crate::optimization::destination;
+use crate::tacky_ast::{TackyInstructionAST, TackyValueAST};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+// SS: removes instructions that write a variable nobody reads afterwards. Function calls
+// and stores through pointers are kept regardless since they have other effects. Static
+// variables are live when the function returns, and the callee of a call or the target of
+// a load may read any aliased variable. Returns whether anything was removed.
+pub fn eliminate_dead_stores(
+    cfg: &mut ControlFlowGraph<TackyInstructionAST>,
+    aliased: &HashSet<String>,
+    statics: &HashSet<String>,
+) -> bool {
+    let liveness = Liveness { aliased, statics };
+    let live_out = liveness.find_live_variables(cfg);
+
+    let mut changed = false;
+    for (id, block) in cfg.blocks.iter_mut() {
+        let mut live = live_out[id].clone();
+        let mut instructions = vec![];
+        for instruction in block.instructions.drain(..).rev() {
+            if is_dead_store(&instruction, &live) {
+                changed = true;
+                continue;
+            }
+            liveness.transfer(&instruction, &mut live);
+            instructions.push(instruction);
+        }
+        instructions.reverse();
+        block.instructions = instructions;
+    }
+    changed
+}
+
+fn is_dead_store(instruction: &TackyInstructionAST, live: &HashSet<String>) -> bool {
+    let dst = match instruction {
+        TackyInstructionAST::FunctionCall { .. } | TackyInstructionAST::Store { .. } => {
+            return false;
+        }
+        TackyInstructionAST::CopyToOffset { dst, .. } => dst,
+        _ => match destination(instruction) {
+            Some(TackyValueAST::Var(dst)) => dst,
+            _ => return false,
+        },
+    };
+    !live.contains(dst)
+}
+
+struct Liveness<'a> {
+    aliased: &'a HashSet<String>,
+    statics: &'a HashSet<String>,
+}
+
+impl Liveness<'_> {
+    // SS: a backward analysis, a variable is live at the end of a block if it is live at
+    // the start of any of its successors
+    fn find_live_variables(
+        &self,
+        cfg: &ControlFlowGraph<TackyInstructionAST>,
+    ) -> HashMap<usize, HashSet<String>> {
+        let mut incoming = cfg
+            .blocks
+            .keys()
+            .map(|id| (*id, HashSet::new()))
+            .collect::<HashMap<_, _>>();
+        let mut outgoing = HashMap::new();
+
+        // SS: visiting the blocks from the last one needs the fewest rounds
+        let mut worklist = cfg.blocks.keys().rev().copied().collect::<VecDeque<_>>();
+        let mut in_worklist = cfg
+            .blocks
+            .keys()
+            .map(|id| (*id, true))
+            .collect::<HashMap<_, _>>();
+        while let Some(id) = worklist.pop_front() {
+            in_worklist.insert(id, false);
+            let mut live = HashSet::new();
+            for successor in cfg.successors(NodeId::Block(id)) {
+                match successor {
+                    NodeId::Block(successor) => live.extend(incoming[successor].iter().cloned()),
+                    _ => live.extend(self.statics.iter().cloned()),
+                }
+            }
+            outgoing.insert(id, live.clone());
+
+            for instruction in cfg.blocks[&id].instructions.iter().rev() {
+                self.transfer(instruction, &mut live);
+            }
+            if live != incoming[&id] {
+                incoming.insert(id, live);
+                for predecessor in cfg.predecessors(NodeId::Block(id)) {
+                    if let NodeId::Block(predecessor) = predecessor
+                        && !in_worklist[predecessor]
+                    {
+                        in_worklist.insert(*predecessor, true);
+                        worklist.push_back(*predecessor);
+                    }
+                }
+            }
+        }
+        outgoing
+    }
+
+    // SS: moves from the variables live after the instruction to those live before it
+    fn transfer(&self, instruction: &TackyInstructionAST, live: &mut HashSet<String>) {
+        if let Some(TackyValueAST::Var(dst)) = destination(instruction) {
+            live.remove(dst);
+        }
+
+        let mut uses = |value: &TackyValueAST| {
+            if let TackyValueAST::Var(name) = value {
+                live.insert(name.clone());
+            }
+        };
+        match instruction {
+            TackyInstructionAST::Return(value)
+            | TackyInstructionAST::JumpIfZero(value, _)
+            | TackyInstructionAST::JumpIfNotZero(value, _)
+            | TackyInstructionAST::JumpTable { index: value, .. } => uses(value),
+            TackyInstructionAST::Unary { src, .. }
+            | TackyInstructionAST::Copy { src, .. }
+            | TackyInstructionAST::SignExtend { src, .. }
+            | TackyInstructionAST::Truncate { src, .. }
+            | TackyInstructionAST::ZeroExtend { src, .. }
+            | TackyInstructionAST::DoubleToInt { src, .. }
+            | TackyInstructionAST::DoubleToUInt { src, .. }
+            | TackyInstructionAST::IntToDouble { src, .. }
+            | TackyInstructionAST::UIntToDouble { src, .. }
+            | TackyInstructionAST::CopyToOffset { src, .. } => uses(src),
+            TackyInstructionAST::Binary { src1, src2, .. } => {
+                uses(src1);
+                uses(src2);
+            }
+            TackyInstructionAST::Store { src, dst_ptr } => {
+                uses(src);
+                uses(dst_ptr);
+            }
+            TackyInstructionAST::AddPtr { ptr, index, .. } => {
+                uses(ptr);
+                uses(index);
+            }
+            TackyInstructionAST::Load { src_ptr, .. } => {
+                uses(src_ptr);
+                live.extend(self.aliased.iter().cloned());
+            }
+            TackyInstructionAST::FunctionCall { args, .. } => {
+                args.iter().for_each(uses);
+                live.extend(self.aliased.iter().cloned());
+            }
+            TackyInstructionAST::CopyFromOffset { src, .. } => {
+                live.insert(src.clone());
+            }
+            TackyInstructionAST::GetAddress { .. }
+            | TackyInstructionAST::Jump(_)
+            | TackyInstructionAST::Label(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::control_flow_graph::ControlFlowGraph;
+    use crate::dead_store_elimination::eliminate_dead_stores;
+    use crate::tacky_ast::{TackyBinaryOperatorAST, TackyInstructionAST, TackyValueAST};
+    use crate::types::Constant;
+    use std::collections::HashSet;
+
+    fn var(name: &str) -> TackyValueAST {
+        TackyValueAST::Var(name.to_string())
+    }
+
+    fn int(val: i32) -> TackyValueAST {
+        TackyValueAST::Constant(Constant::Int(val))
+    }
+
+    #[test]
+    fn test_eliminate_dead_stores() {
+        // SS: arrange
+        let mut cfg = ControlFlowGraph::new(vec![
+            TackyInstructionAST::Copy {
+                src: int(1),
+                dst: var("x"),
+            },
+            TackyInstructionAST::Copy {
+                src: int(2),
+                dst: var("x"),
+            },
+            TackyInstructionAST::Binary {
+                op: TackyBinaryOperatorAST::Add,
+                src1: var("x"),
+                src2: int(3),
+                dst: var("y"),
+            },
+            TackyInstructionAST::FunctionCall {
+                name: "f".to_string(),
+                args: vec![var("x")],
+                dst: var("z"),
+            },
+            TackyInstructionAST::Copy {
+                src: int(4),
+                dst: var("g"),
+            },
+            TackyInstructionAST::Return(int(0)),
+        ]);
+        let statics = HashSet::from(["g".to_string()]);
+
+        // SS: act
+        eliminate_dead_stores(&mut cfg, &statics, &statics);
+
+        // SS: assert
+        assert_eq!(
+            cfg.into_instructions(),
+            vec![
+                TackyInstructionAST::Copy {
+                    src: int(2),
+                    dst: var("x"),
+                },
+                TackyInstructionAST::FunctionCall {
+                    name: "f".to_string(),
+                    args: vec![var("x")],
+                    dst: var("z"),
+                },
+                TackyInstructionAST::Copy {
+                    src: int(4),
+                    dst: var("g"),
+                },
+                TackyInstructionAST::Return(int(0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_eliminate_dead_stores_in_loop() {
+        // SS: arrange
+        let instructions = vec![
+            TackyInstructionAST::Copy {
+                src: int(10),
+                dst: var("i"),
+            },
+            TackyInstructionAST::Label("loop".to_string()),
+            TackyInstructionAST::JumpIfZero(var("i"), "end".to_string()),
+            TackyInstructionAST::Binary {
+                op: TackyBinaryOperatorAST::Subtract,
+                src1: var("i"),
+                src2: int(1),
+                dst: var("i"),
+            },
+            TackyInstructionAST::Jump("loop".to_string()),
+            TackyInstructionAST::Label("end".to_string()),
+            TackyInstructionAST::Return(int(0)),
+        ];
+        let mut cfg = ControlFlowGraph::new(instructions.clone());
+
+        // SS: act
+        eliminate_dead_stores(&mut cfg, &HashSet::new(), &HashSet::new());
+
+        // SS: assert
+        assert_eq!(cfg.into_instructions(), instructions);
+    }
+}
@@ -0,0 +1,178 @@
+use std::fmt::Display;
+use std::rc::Rc;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    pub file: Rc<str>,
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub span: Span,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn error(span: Span, message: String) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            span,
+            message,
+        }
+    }
+
+    pub fn warning(span: Span, message: String) -> Self {
+        Diagnostic {
+            severity: Severity::Warning,
+            span,
+            message,
+        }
+    }
+
+    // SS: renders the diagnostic the way gcc and clang do, followed by the offending source
+    // line and a caret under the column
+    pub fn render(&self, source: &str) -> String {
+        let mut rendered = format!(
+            "{}:{}:{}: {}: {}",
+            self.span.file, self.span.line, self.span.column, self.severity, self.message
+        );
+
+        if let Some(source_line) = source.lines().nth(self.span.line - 1) {
+            // SS: keep tabs so the caret lines up however the terminal expands them
+            let indent = source_line
+                .chars()
+                .take(self.span.column - 1)
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect::<String>();
+            rendered.push_str(&format!("\n{}\n{}^", source_line, indent));
+        }
+
+        rendered
+    }
+}
+
+// SS: the short form used in tests and by callers that have no source text at hand
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Line {}: {}", self.span.line, self.message)
+    }
+}
+
+// SS: takes a line of the lexer's input back to the file and line it came from, following
+// the line markers the preprocessor left. The AST records lines of the lexer's input, so
+// errors found after parsing are located through it.
+#[derive(Debug, Clone)]
+pub struct LineMap {
+    // SS: the input line at which each marker takes effect, with the file and line it names,
+    // in input order
+    markers: Vec<(usize, Rc<str>, usize)>,
+}
+
+impl LineMap {
+    pub fn new(file: Rc<str>) -> Self {
+        LineMap {
+            markers: vec![(1, file, 1)],
+        }
+    }
+
+    pub fn add_marker(&mut self, input_line: usize, file: Rc<str>, line: usize) {
+        self.markers.push((input_line, file, line));
+    }
+
+    pub fn locate(&self, input_line: usize) -> (Rc<str>, usize) {
+        let (start, file, line) = self
+            .markers
+            .iter()
+            .rev()
+            .find(|(start, _, _)| *start <= input_line)
+            .unwrap_or(&self.markers[0]);
+        (file.clone(), line + input_line.saturating_sub(*start))
+    }
+
+    // SS: errors found after parsing read "Line N: message", these are reported the way gcc
+    // reports an error without a column. Anything else is returned unchanged.
+    pub fn locate_error(&self, error: &str) -> String {
+        let located = error
+            .strip_prefix("Line ")
+            .and_then(|rest| rest.split_once(": "))
+            .and_then(|(line, message)| Some((line.parse::<usize>().ok()?, message)));
+        match located {
+            Some((input_line, message)) => {
+                let (file, line) = self.locate(input_line);
+                format!("{}:{}: {}: {}", file, line, Severity::Error, message)
+            }
+            None => error.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostic::{Diagnostic, LineMap, Span};
+
+    #[test]
+    fn test_render_diagnostic() {
+        // SS: arrange
+        let source = "int main(void) {\n\treturn 1 +;\n}\n";
+        let diagnostic = Diagnostic::error(
+            Span {
+                file: "test.c".into(),
+                line: 2,
+                column: 12,
+            },
+            r#"Syntax error: Expected expression, but found ";""#.to_string(),
+        );
+
+        // SS: act
+        let rendered = diagnostic.render(source);
+
+        // SS: assert
+        assert_eq!(
+            rendered,
+            "test.c:2:12: error: Syntax error: Expected expression, but found \";\"\n\treturn 1 +;\n\t          ^"
+        );
+        assert_eq!(
+            diagnostic.to_string(),
+            r#"Line 2: Syntax error: Expected expression, but found ";""#
+        );
+    }
+
+    #[test]
+    fn test_line_map_locate_error() {
+        // SS: arrange, a header included on the first line, then the rest of the file
+        let mut line_map = LineMap::new("main.c".into());
+        line_map.add_marker(2, "header.h".into(), 1);
+        line_map.add_marker(6, "main.c".into(), 2);
+
+        // SS: act
+        let in_header = line_map.locate_error("Line 3: Semantic error: Invalid lvalue");
+        let in_main = line_map.locate_error("Line 8: Semantic error: Invalid lvalue");
+        let other = line_map.locate_error("Runtime error");
+
+        // SS: assert
+        assert_eq!(
+            in_header,
+            "header.h:2: error: Semantic error: Invalid lvalue"
+        );
+        assert_eq!(in_main, "main.c:4: error: Semantic error: Invalid lvalue");
+        assert_eq!(other, "Runtime error");
+    }
+}
@@ -0,0 +1,50 @@
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
+
+// SS: the system compiler, used for everything we do not implement ourselves, i.e.
+// assembling and linking
+const SYSTEM_COMPILER: &str = "cc";
+
+// SS: where to write an intermediate file for input, in the temporary directory so that it
+// can not overwrite the input or anything else next to it
+pub fn intermediate_path(input: &Path, extension: &str) -> PathBuf {
+    let stem = input.file_stem().unwrap_or_default().to_string_lossy();
+    env::temp_dir().join(format!("{}.{}.{}", stem, process::id(), extension))
+}
+
+// SS: errors carry the exit code the driver should terminate with, a failing child
+// process determines it
+pub fn assemble(assembly: &Path, output: &Path) -> Result<(), i32> {
+    run(Command::new(SYSTEM_COMPILER)
+        .arg("-c")
+        .arg(assembly)
+        .arg("-o")
+        .arg(output))
+}
+
+pub fn assemble_and_link(assembly: &Path, output: &Path, libraries: &[String]) -> Result<(), i32> {
+    run(Command::new(SYSTEM_COMPILER)
+        .arg(assembly)
+        .arg("-o")
+        .arg(output)
+        .args(libraries.iter().map(|library| format!("-l{}", library))))
+}
+
+fn run(command: &mut Command) -> Result<(), i32> {
+    let status = command.status().map_err(|err| {
+        eprintln!(
+            "Could not run {:?}: {}",
+            command.get_program().to_string_lossy(),
+            err
+        );
+        1
+    })?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        // SS: a child killed by a signal has no exit code
+        Err(status.code().unwrap_or(1))
+    }
+}
@@ -0,0 +1,834 @@
+use crate::assembly_ast::{
+    AssemblyBinaryOperatorAST, AssemblyFunctionAST, AssemblyInstructionAST, AssemblyOperandAST,
+    AssemblyProgramAST, AssemblyTopLevelAST, AssemblyType,
+};
+use crate::reg::Register;
+
+// SS: rewrites instructions that are not encodable in x64, i.e. those with two memory
+// operands, an imul with a memory destination, a cmp with an immediate destination, a
+// quadword immediate that does not fit in 32 bits or an SSE instruction with a memory
+// destination, or a jump table indexed by memory. R10 and XMM14 are the scratch registers
+// for source operands, R11 and XMM15 for destination operands.
+pub fn fixup_instructions(assembly_ast: AssemblyProgramAST) -> AssemblyProgramAST {
+    AssemblyProgramAST {
+        items: assembly_ast
+            .items
+            .into_iter()
+            .map(|item| match item {
+                AssemblyTopLevelAST::Function(function) => {
+                    AssemblyTopLevelAST::Function(fixup_function(function))
+                }
+                item @ (AssemblyTopLevelAST::StaticVariable { .. }
+                | AssemblyTopLevelAST::StaticConstant { .. }) => item,
+            })
+            .collect(),
+    }
+}
+
+fn fixup_function(function: AssemblyFunctionAST) -> AssemblyFunctionAST {
+    AssemblyFunctionAST {
+        instructions: function
+            .instructions
+            .into_iter()
+            .flat_map(fixup_instruction)
+            .collect(),
+        ..function
+    }
+}
+
+fn fixup_instruction(instruction: AssemblyInstructionAST) -> Vec<AssemblyInstructionAST> {
+    match instruction {
+        // SS: movl only keeps the lower 32 bits of an immediate, make that explicit so the
+        // assembler does not complain about unsigned or truncated values
+        AssemblyInstructionAST::Mov {
+            asm_type: AssemblyType::Longword,
+            src: AssemblyOperandAST::Immediate(val),
+            dst,
+        } if !fits_in_i32(val) => vec![AssemblyInstructionAST::Mov {
+            asm_type: AssemblyType::Longword,
+            src: AssemblyOperandAST::Immediate(val as i32 as i64),
+            dst,
+        }],
+        // SS: and movb the lower 8
+        AssemblyInstructionAST::Mov {
+            asm_type: AssemblyType::Byte,
+            src: AssemblyOperandAST::Immediate(val),
+            dst,
+        } if i8::try_from(val).is_err() => vec![AssemblyInstructionAST::Mov {
+            asm_type: AssemblyType::Byte,
+            src: AssemblyOperandAST::Immediate(val as i8 as i64),
+            dst,
+        }],
+        // SS: only movabsq to a register takes a 64-bit immediate
+        AssemblyInstructionAST::Mov {
+            asm_type: AssemblyType::Quadword,
+            src: src @ AssemblyOperandAST::Immediate(_),
+            dst,
+        } if is_large_immediate(&src) && is_memory(&dst) => vec![
+            AssemblyInstructionAST::Mov {
+                asm_type: AssemblyType::Quadword,
+                src,
+                dst: AssemblyOperandAST::Register(Register::R10D),
+            },
+            AssemblyInstructionAST::Mov {
+                asm_type: AssemblyType::Quadword,
+                src: AssemblyOperandAST::Register(Register::R10D),
+                dst,
+            },
+        ],
+        AssemblyInstructionAST::Mov { asm_type, src, dst }
+            if is_memory(&src) && is_memory(&dst) =>
+        {
+            let scratch = AssemblyOperandAST::Register(if asm_type == AssemblyType::Double {
+                Register::XMM14
+            } else {
+                Register::R10D
+            });
+            vec![
+                AssemblyInstructionAST::Mov {
+                    asm_type,
+                    src,
+                    dst: scratch.clone(),
+                },
+                AssemblyInstructionAST::Mov {
+                    asm_type,
+                    src: scratch,
+                    dst,
+                },
+            ]
+        }
+        // SS: a movl to a register clears the upper 32 bits, a memory destination needs the
+        // value built in a register first
+        AssemblyInstructionAST::MovZeroExtend {
+            src_type: AssemblyType::Longword,
+            dst_type,
+            src,
+            dst,
+        } if is_memory(&dst) => vec![
+            AssemblyInstructionAST::Mov {
+                asm_type: AssemblyType::Longword,
+                src,
+                dst: AssemblyOperandAST::Register(Register::R11D),
+            },
+            AssemblyInstructionAST::Mov {
+                asm_type: dst_type,
+                src: AssemblyOperandAST::Register(Register::R11D),
+                dst,
+            },
+        ],
+        AssemblyInstructionAST::MovZeroExtend {
+            src_type: AssemblyType::Longword,
+            src,
+            dst,
+            ..
+        } => fixup_instruction(AssemblyInstructionAST::Mov {
+            asm_type: AssemblyType::Longword,
+            src,
+            dst,
+        }),
+        // SS: movsx and movzb take neither an immediate source nor a memory destination
+        AssemblyInstructionAST::Movsx {
+            src_type,
+            dst_type,
+            src,
+            dst,
+        } => fixup_extension(src_type, dst_type, src, dst, |src, dst| {
+            AssemblyInstructionAST::Movsx {
+                src_type,
+                dst_type,
+                src,
+                dst,
+            }
+        }),
+        AssemblyInstructionAST::MovZeroExtend {
+            src_type,
+            dst_type,
+            src,
+            dst,
+        } => fixup_extension(src_type, dst_type, src, dst, |src, dst| {
+            AssemblyInstructionAST::MovZeroExtend {
+                src_type,
+                dst_type,
+                src,
+                dst,
+            }
+        }),
+        // SS: lea needs a register destination
+        AssemblyInstructionAST::Lea { src, dst } if is_memory(&dst) => vec![
+            AssemblyInstructionAST::Lea {
+                src,
+                dst: AssemblyOperandAST::Register(Register::R11D),
+            },
+            AssemblyInstructionAST::Mov {
+                asm_type: AssemblyType::Quadword,
+                src: AssemblyOperandAST::Register(Register::R11D),
+                dst,
+            },
+        ],
+        AssemblyInstructionAST::Cvttsd2si { asm_type, src, dst } if is_memory(&dst) => vec![
+            AssemblyInstructionAST::Cvttsd2si {
+                asm_type,
+                src,
+                dst: AssemblyOperandAST::Register(Register::R11D),
+            },
+            AssemblyInstructionAST::Mov {
+                asm_type,
+                src: AssemblyOperandAST::Register(Register::R11D),
+                dst,
+            },
+        ],
+        AssemblyInstructionAST::Cvtsi2sd { asm_type, src, dst } => {
+            let mut instructions = vec![];
+            let src = if matches!(src, AssemblyOperandAST::Immediate(_)) {
+                instructions.push(AssemblyInstructionAST::Mov {
+                    asm_type,
+                    src,
+                    dst: AssemblyOperandAST::Register(Register::R10D),
+                });
+                AssemblyOperandAST::Register(Register::R10D)
+            } else {
+                src
+            };
+            if is_register(&dst) {
+                instructions.push(AssemblyInstructionAST::Cvtsi2sd { asm_type, src, dst });
+            } else {
+                instructions.push(AssemblyInstructionAST::Cvtsi2sd {
+                    asm_type,
+                    src,
+                    dst: AssemblyOperandAST::Register(Register::XMM15),
+                });
+                instructions.push(AssemblyInstructionAST::Mov {
+                    asm_type: AssemblyType::Double,
+                    src: AssemblyOperandAST::Register(Register::XMM15),
+                    dst,
+                });
+            }
+            instructions
+        }
+        // SS: SSE arithmetic needs a register destination
+        AssemblyInstructionAST::Binary {
+            op,
+            asm_type: AssemblyType::Double,
+            src,
+            dst,
+        } if !is_register(&dst) => vec![
+            AssemblyInstructionAST::Mov {
+                asm_type: AssemblyType::Double,
+                src: dst.clone(),
+                dst: AssemblyOperandAST::Register(Register::XMM15),
+            },
+            AssemblyInstructionAST::Binary {
+                op,
+                asm_type: AssemblyType::Double,
+                src,
+                dst: AssemblyOperandAST::Register(Register::XMM15),
+            },
+            AssemblyInstructionAST::Mov {
+                asm_type: AssemblyType::Double,
+                src: AssemblyOperandAST::Register(Register::XMM15),
+                dst,
+            },
+        ],
+        AssemblyInstructionAST::Binary {
+            op,
+            asm_type,
+            src,
+            dst,
+        } if is_large_immediate(&src) => {
+            let mut instructions = vec![AssemblyInstructionAST::Mov {
+                asm_type,
+                src,
+                dst: AssemblyOperandAST::Register(Register::R10D),
+            }];
+            instructions.extend(fixup_instruction(AssemblyInstructionAST::Binary {
+                op,
+                asm_type,
+                src: AssemblyOperandAST::Register(Register::R10D),
+                dst,
+            }));
+            instructions
+        }
+        AssemblyInstructionAST::Binary {
+            op: AssemblyBinaryOperatorAST::Mult,
+            asm_type,
+            src,
+            dst,
+        } if is_memory(&dst) => vec![
+            AssemblyInstructionAST::Mov {
+                asm_type,
+                src: dst.clone(),
+                dst: AssemblyOperandAST::Register(Register::R11D),
+            },
+            AssemblyInstructionAST::Binary {
+                op: AssemblyBinaryOperatorAST::Mult,
+                asm_type,
+                src,
+                dst: AssemblyOperandAST::Register(Register::R11D),
+            },
+            AssemblyInstructionAST::Mov {
+                asm_type,
+                src: AssemblyOperandAST::Register(Register::R11D),
+                dst,
+            },
+        ],
+        AssemblyInstructionAST::Binary {
+            op,
+            asm_type,
+            src,
+            dst,
+        } if is_memory(&src) && is_memory(&dst) => {
+            vec![
+                AssemblyInstructionAST::Mov {
+                    asm_type,
+                    src,
+                    dst: AssemblyOperandAST::Register(Register::R10D),
+                },
+                AssemblyInstructionAST::Binary {
+                    op,
+                    asm_type,
+                    src: AssemblyOperandAST::Register(Register::R10D),
+                    dst,
+                },
+            ]
+        }
+        // SS: comisd needs a register destination
+        AssemblyInstructionAST::Cmp {
+            asm_type: AssemblyType::Double,
+            src,
+            dst,
+        } if !is_register(&dst) => vec![
+            AssemblyInstructionAST::Mov {
+                asm_type: AssemblyType::Double,
+                src: dst,
+                dst: AssemblyOperandAST::Register(Register::XMM15),
+            },
+            AssemblyInstructionAST::Cmp {
+                asm_type: AssemblyType::Double,
+                src,
+                dst: AssemblyOperandAST::Register(Register::XMM15),
+            },
+        ],
+        AssemblyInstructionAST::Cmp { asm_type, src, dst }
+            if is_large_immediate(&src) || (is_memory(&src) && is_memory(&dst)) =>
+        {
+            let mut instructions = vec![AssemblyInstructionAST::Mov {
+                asm_type,
+                src,
+                dst: AssemblyOperandAST::Register(Register::R10D),
+            }];
+            instructions.extend(fixup_instruction(AssemblyInstructionAST::Cmp {
+                asm_type,
+                src: AssemblyOperandAST::Register(Register::R10D),
+                dst,
+            }));
+            instructions
+        }
+        AssemblyInstructionAST::Cmp {
+            asm_type,
+            src,
+            dst: dst @ AssemblyOperandAST::Immediate(_),
+        } => vec![
+            AssemblyInstructionAST::Mov {
+                asm_type,
+                src: dst,
+                dst: AssemblyOperandAST::Register(Register::R11D),
+            },
+            AssemblyInstructionAST::Cmp {
+                asm_type,
+                src,
+                dst: AssemblyOperandAST::Register(Register::R11D),
+            },
+        ],
+        // SS: there is no push for SSE registers
+        AssemblyInstructionAST::Push(AssemblyOperandAST::Register(register))
+            if register.is_xmm() =>
+        {
+            vec![
+                AssemblyInstructionAST::AllocateStack(8),
+                AssemblyInstructionAST::Mov {
+                    asm_type: AssemblyType::Double,
+                    src: AssemblyOperandAST::Register(register),
+                    dst: AssemblyOperandAST::Memory(Register::RSP, 0),
+                },
+            ]
+        }
+        AssemblyInstructionAST::Push(operand) if is_large_immediate(&operand) => vec![
+            AssemblyInstructionAST::Mov {
+                asm_type: AssemblyType::Quadword,
+                src: operand,
+                dst: AssemblyOperandAST::Register(Register::R10D),
+            },
+            AssemblyInstructionAST::Push(AssemblyOperandAST::Register(Register::R10D)),
+        ],
+        // SS: the index of a jump table is part of an address, so it has to be a register
+        AssemblyInstructionAST::JmpTable {
+            index,
+            table,
+            targets,
+        } if !is_register(&index) => vec![
+            AssemblyInstructionAST::Mov {
+                asm_type: AssemblyType::Quadword,
+                src: index,
+                dst: AssemblyOperandAST::Register(Register::R10D),
+            },
+            AssemblyInstructionAST::JmpTable {
+                index: AssemblyOperandAST::Register(Register::R10D),
+                table,
+                targets,
+            },
+        ],
+        instruction => vec![instruction],
+    }
+}
+
+fn fixup_extension(
+    src_type: AssemblyType,
+    dst_type: AssemblyType,
+    src: AssemblyOperandAST,
+    dst: AssemblyOperandAST,
+    extend: impl Fn(AssemblyOperandAST, AssemblyOperandAST) -> AssemblyInstructionAST,
+) -> Vec<AssemblyInstructionAST> {
+    let mut instructions = vec![];
+    let src = if matches!(src, AssemblyOperandAST::Immediate(_)) {
+        instructions.extend(fixup_instruction(AssemblyInstructionAST::Mov {
+            asm_type: src_type,
+            src,
+            dst: AssemblyOperandAST::Register(Register::R10D),
+        }));
+        AssemblyOperandAST::Register(Register::R10D)
+    } else {
+        src
+    };
+    if is_memory(&dst) {
+        instructions.push(extend(src, AssemblyOperandAST::Register(Register::R11D)));
+        instructions.push(AssemblyInstructionAST::Mov {
+            asm_type: dst_type,
+            src: AssemblyOperandAST::Register(Register::R11D),
+            dst,
+        });
+    } else {
+        instructions.push(extend(src, dst));
+    }
+    instructions
+}
+
+fn fits_in_i32(val: i64) -> bool {
+    i32::try_from(val).is_ok()
+}
+
+// SS: instructions other than mov only take immediates that sign extend from 32 bits
+fn is_large_immediate(operand: &AssemblyOperandAST) -> bool {
+    matches!(operand, AssemblyOperandAST::Immediate(val) if !fits_in_i32(*val))
+}
+
+fn is_memory(operand: &AssemblyOperandAST) -> bool {
+    matches!(
+        operand,
+        AssemblyOperandAST::Stack(_)
+            | AssemblyOperandAST::Memory(..)
+            | AssemblyOperandAST::Indexed { .. }
+            | AssemblyOperandAST::Data(..)
+    )
+}
+
+fn is_register(operand: &AssemblyOperandAST) -> bool {
+    matches!(operand, AssemblyOperandAST::Register(_))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assembly_ast::{
+        AssemblyBinaryOperatorAST, AssemblyFunctionAST, AssemblyInstructionAST, AssemblyOperandAST,
+        AssemblyProgramAST, AssemblyTopLevelAST, AssemblyType,
+    };
+    use crate::instruction_fixup::fixup_instructions;
+    use crate::reg::Register;
+
+    fn program(instructions: Vec<AssemblyInstructionAST>) -> AssemblyProgramAST {
+        AssemblyProgramAST {
+            items: vec![AssemblyTopLevelAST::Function(AssemblyFunctionAST {
+                name: "main".to_string(),
+                global: true,
+                instructions,
+                callee_saved_registers: vec![],
+            })],
+        }
+    }
+
+    fn instructions(ast: &AssemblyProgramAST) -> Vec<AssemblyInstructionAST> {
+        match &ast.items[0] {
+            AssemblyTopLevelAST::Function(function) => function.instructions.clone(),
+            item => panic!("expected a function, found {:?}", item),
+        }
+    }
+
+    #[test]
+    fn test_fixup_jump_table_index_in_memory() {
+        // SS: arrange
+        let targets = vec!["case.0".to_string(), "case.1".to_string()];
+        let assembly_ast = program(vec![AssemblyInstructionAST::JmpTable {
+            index: AssemblyOperandAST::Stack(-8),
+            table: "jump_table.0".to_string(),
+            targets: targets.clone(),
+        }]);
+
+        // SS: act
+        let assembly_ast = fixup_instructions(assembly_ast);
+
+        // SS: assert
+        assert_eq!(
+            instructions(&assembly_ast),
+            vec![
+                AssemblyInstructionAST::Mov {
+                    asm_type: AssemblyType::Quadword,
+                    src: AssemblyOperandAST::Stack(-8),
+                    dst: AssemblyOperandAST::Register(Register::R10D),
+                },
+                AssemblyInstructionAST::JmpTable {
+                    index: AssemblyOperandAST::Register(Register::R10D),
+                    table: "jump_table.0".to_string(),
+                    targets,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fixup_mov_memory_to_memory() {
+        // SS: arrange
+        let assembly_ast = program(vec![AssemblyInstructionAST::Mov {
+            asm_type: AssemblyType::Longword,
+            src: AssemblyOperandAST::Stack(-4),
+            dst: AssemblyOperandAST::Stack(-8),
+        }]);
+
+        // SS: act
+        let assembly_ast = fixup_instructions(assembly_ast);
+
+        // SS: assert
+        assert_eq!(
+            instructions(&assembly_ast),
+            vec![
+                AssemblyInstructionAST::Mov {
+                    asm_type: AssemblyType::Longword,
+                    src: AssemblyOperandAST::Stack(-4),
+                    dst: AssemblyOperandAST::Register(Register::R10D),
+                },
+                AssemblyInstructionAST::Mov {
+                    asm_type: AssemblyType::Longword,
+                    src: AssemblyOperandAST::Register(Register::R10D),
+                    dst: AssemblyOperandAST::Stack(-8),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fixup_imul_memory_destination() {
+        // SS: arrange
+        let assembly_ast = program(vec![AssemblyInstructionAST::Binary {
+            asm_type: AssemblyType::Longword,
+            op: AssemblyBinaryOperatorAST::Mult,
+            src: AssemblyOperandAST::Immediate(3),
+            dst: AssemblyOperandAST::Stack(-4),
+        }]);
+
+        // SS: act
+        let assembly_ast = fixup_instructions(assembly_ast);
+
+        // SS: assert
+        assert_eq!(
+            instructions(&assembly_ast),
+            vec![
+                AssemblyInstructionAST::Mov {
+                    asm_type: AssemblyType::Longword,
+                    src: AssemblyOperandAST::Stack(-4),
+                    dst: AssemblyOperandAST::Register(Register::R11D),
+                },
+                AssemblyInstructionAST::Binary {
+                    asm_type: AssemblyType::Longword,
+                    op: AssemblyBinaryOperatorAST::Mult,
+                    src: AssemblyOperandAST::Immediate(3),
+                    dst: AssemblyOperandAST::Register(Register::R11D),
+                },
+                AssemblyInstructionAST::Mov {
+                    asm_type: AssemblyType::Longword,
+                    src: AssemblyOperandAST::Register(Register::R11D),
+                    dst: AssemblyOperandAST::Stack(-4),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fixup_add_memory_to_memory() {
+        // SS: arrange
+        let assembly_ast = program(vec![AssemblyInstructionAST::Binary {
+            asm_type: AssemblyType::Longword,
+            op: AssemblyBinaryOperatorAST::Add,
+            src: AssemblyOperandAST::Stack(-4),
+            dst: AssemblyOperandAST::Stack(-8),
+        }]);
+
+        // SS: act
+        let assembly_ast = fixup_instructions(assembly_ast);
+
+        // SS: assert
+        assert_eq!(
+            instructions(&assembly_ast),
+            vec![
+                AssemblyInstructionAST::Mov {
+                    asm_type: AssemblyType::Longword,
+                    src: AssemblyOperandAST::Stack(-4),
+                    dst: AssemblyOperandAST::Register(Register::R10D),
+                },
+                AssemblyInstructionAST::Binary {
+                    asm_type: AssemblyType::Longword,
+                    op: AssemblyBinaryOperatorAST::Add,
+                    src: AssemblyOperandAST::Register(Register::R10D),
+                    dst: AssemblyOperandAST::Stack(-8),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fixup_large_quadword_immediates() {
+        // SS: arrange
+        let assembly_ast = program(vec![
+            AssemblyInstructionAST::Mov {
+                asm_type: AssemblyType::Quadword,
+                src: AssemblyOperandAST::Immediate(4294967296),
+                dst: AssemblyOperandAST::Stack(-8),
+            },
+            AssemblyInstructionAST::Binary {
+                asm_type: AssemblyType::Quadword,
+                op: AssemblyBinaryOperatorAST::Add,
+                src: AssemblyOperandAST::Immediate(4294967296),
+                dst: AssemblyOperandAST::Register(Register::EAX),
+            },
+            AssemblyInstructionAST::Mov {
+                asm_type: AssemblyType::Longword,
+                src: AssemblyOperandAST::Immediate(4294967297),
+                dst: AssemblyOperandAST::Stack(-12),
+            },
+        ]);
+
+        // SS: act
+        let assembly_ast = fixup_instructions(assembly_ast);
+
+        // SS: assert
+        assert_eq!(
+            instructions(&assembly_ast),
+            vec![
+                AssemblyInstructionAST::Mov {
+                    asm_type: AssemblyType::Quadword,
+                    src: AssemblyOperandAST::Immediate(4294967296),
+                    dst: AssemblyOperandAST::Register(Register::R10D),
+                },
+                AssemblyInstructionAST::Mov {
+                    asm_type: AssemblyType::Quadword,
+                    src: AssemblyOperandAST::Register(Register::R10D),
+                    dst: AssemblyOperandAST::Stack(-8),
+                },
+                AssemblyInstructionAST::Mov {
+                    asm_type: AssemblyType::Quadword,
+                    src: AssemblyOperandAST::Immediate(4294967296),
+                    dst: AssemblyOperandAST::Register(Register::R10D),
+                },
+                AssemblyInstructionAST::Binary {
+                    asm_type: AssemblyType::Quadword,
+                    op: AssemblyBinaryOperatorAST::Add,
+                    src: AssemblyOperandAST::Register(Register::R10D),
+                    dst: AssemblyOperandAST::Register(Register::EAX),
+                },
+                AssemblyInstructionAST::Mov {
+                    asm_type: AssemblyType::Longword,
+                    src: AssemblyOperandAST::Immediate(1),
+                    dst: AssemblyOperandAST::Stack(-12),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fixup_conversions_to_memory() {
+        // SS: arrange
+        let assembly_ast = program(vec![
+            AssemblyInstructionAST::Movsx {
+                src_type: AssemblyType::Longword,
+                dst_type: AssemblyType::Quadword,
+                src: AssemblyOperandAST::Immediate(-1),
+                dst: AssemblyOperandAST::Stack(-8),
+            },
+            AssemblyInstructionAST::MovZeroExtend {
+                src_type: AssemblyType::Longword,
+                dst_type: AssemblyType::Quadword,
+                src: AssemblyOperandAST::Stack(-12),
+                dst: AssemblyOperandAST::Stack(-24),
+            },
+            AssemblyInstructionAST::Cvtsi2sd {
+                asm_type: AssemblyType::Longword,
+                src: AssemblyOperandAST::Immediate(3),
+                dst: AssemblyOperandAST::Stack(-32),
+            },
+        ]);
+
+        // SS: act
+        let assembly_ast = fixup_instructions(assembly_ast);
+
+        // SS: assert
+        assert_eq!(
+            instructions(&assembly_ast),
+            vec![
+                AssemblyInstructionAST::Mov {
+                    asm_type: AssemblyType::Longword,
+                    src: AssemblyOperandAST::Immediate(-1),
+                    dst: AssemblyOperandAST::Register(Register::R10D),
+                },
+                AssemblyInstructionAST::Movsx {
+                    src_type: AssemblyType::Longword,
+                    dst_type: AssemblyType::Quadword,
+                    src: AssemblyOperandAST::Register(Register::R10D),
+                    dst: AssemblyOperandAST::Register(Register::R11D),
+                },
+                AssemblyInstructionAST::Mov {
+                    asm_type: AssemblyType::Quadword,
+                    src: AssemblyOperandAST::Register(Register::R11D),
+                    dst: AssemblyOperandAST::Stack(-8),
+                },
+                AssemblyInstructionAST::Mov {
+                    asm_type: AssemblyType::Longword,
+                    src: AssemblyOperandAST::Stack(-12),
+                    dst: AssemblyOperandAST::Register(Register::R11D),
+                },
+                AssemblyInstructionAST::Mov {
+                    asm_type: AssemblyType::Quadword,
+                    src: AssemblyOperandAST::Register(Register::R11D),
+                    dst: AssemblyOperandAST::Stack(-24),
+                },
+                AssemblyInstructionAST::Mov {
+                    asm_type: AssemblyType::Longword,
+                    src: AssemblyOperandAST::Immediate(3),
+                    dst: AssemblyOperandAST::Register(Register::R10D),
+                },
+                AssemblyInstructionAST::Cvtsi2sd {
+                    asm_type: AssemblyType::Longword,
+                    src: AssemblyOperandAST::Register(Register::R10D),
+                    dst: AssemblyOperandAST::Register(Register::XMM15),
+                },
+                AssemblyInstructionAST::Mov {
+                    asm_type: AssemblyType::Double,
+                    src: AssemblyOperandAST::Register(Register::XMM15),
+                    dst: AssemblyOperandAST::Stack(-32),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fixup_double_arithmetic_and_comparison() {
+        // SS: arrange
+        let assembly_ast = program(vec![
+            AssemblyInstructionAST::Binary {
+                asm_type: AssemblyType::Double,
+                op: AssemblyBinaryOperatorAST::Mult,
+                src: AssemblyOperandAST::Data("const_double.0".to_string(), 0),
+                dst: AssemblyOperandAST::Stack(-8),
+            },
+            AssemblyInstructionAST::Cmp {
+                asm_type: AssemblyType::Double,
+                src: AssemblyOperandAST::Stack(-8),
+                dst: AssemblyOperandAST::Stack(-16),
+            },
+        ]);
+
+        // SS: act
+        let assembly_ast = fixup_instructions(assembly_ast);
+
+        // SS: assert
+        assert_eq!(
+            instructions(&assembly_ast),
+            vec![
+                AssemblyInstructionAST::Mov {
+                    asm_type: AssemblyType::Double,
+                    src: AssemblyOperandAST::Stack(-8),
+                    dst: AssemblyOperandAST::Register(Register::XMM15),
+                },
+                AssemblyInstructionAST::Binary {
+                    asm_type: AssemblyType::Double,
+                    op: AssemblyBinaryOperatorAST::Mult,
+                    src: AssemblyOperandAST::Data("const_double.0".to_string(), 0),
+                    dst: AssemblyOperandAST::Register(Register::XMM15),
+                },
+                AssemblyInstructionAST::Mov {
+                    asm_type: AssemblyType::Double,
+                    src: AssemblyOperandAST::Register(Register::XMM15),
+                    dst: AssemblyOperandAST::Stack(-8),
+                },
+                AssemblyInstructionAST::Mov {
+                    asm_type: AssemblyType::Double,
+                    src: AssemblyOperandAST::Stack(-16),
+                    dst: AssemblyOperandAST::Register(Register::XMM15),
+                },
+                AssemblyInstructionAST::Cmp {
+                    asm_type: AssemblyType::Double,
+                    src: AssemblyOperandAST::Stack(-8),
+                    dst: AssemblyOperandAST::Register(Register::XMM15),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fixup_lea_memory_destination() {
+        // SS: arrange
+        let assembly_ast = program(vec![AssemblyInstructionAST::Lea {
+            src: AssemblyOperandAST::Stack(-16),
+            dst: AssemblyOperandAST::Stack(-24),
+        }]);
+
+        // SS: act
+        let assembly_ast = fixup_instructions(assembly_ast);
+
+        // SS: assert
+        assert_eq!(
+            instructions(&assembly_ast),
+            vec![
+                AssemblyInstructionAST::Lea {
+                    src: AssemblyOperandAST::Stack(-16),
+                    dst: AssemblyOperandAST::Register(Register::R11D),
+                },
+                AssemblyInstructionAST::Mov {
+                    asm_type: AssemblyType::Quadword,
+                    src: AssemblyOperandAST::Register(Register::R11D),
+                    dst: AssemblyOperandAST::Stack(-24),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fixup_push_sse_register() {
+        // SS: arrange
+        let assembly_ast = program(vec![AssemblyInstructionAST::Push(
+            AssemblyOperandAST::Register(Register::XMM3),
+        )]);
+
+        // SS: act
+        let assembly_ast = fixup_instructions(assembly_ast);
+
+        // SS: assert
+        assert_eq!(
+            instructions(&assembly_ast),
+            vec![
+                AssemblyInstructionAST::AllocateStack(8),
+                AssemblyInstructionAST::Mov {
+                    asm_type: AssemblyType::Double,
+                    src: AssemblyOperandAST::Register(Register::XMM3),
+                    dst: AssemblyOperandAST::Memory(Register::RSP, 0),
+                },
+            ]
+        );
+    }
+}
@@ -1,76 +1,2251 @@
 use crate::assembly_ast::{
-    AssemblyFunctionAST, AssemblyInstructionAST, AssemblyOperandAST, AssemblyProgramAST,
+    AssemblyBinaryOperatorAST, AssemblyFunctionAST, AssemblyInstructionAST, AssemblyOperandAST,
+    AssemblyProgramAST, AssemblyTopLevelAST, AssemblyType, AssemblyUnaryOperatorAST, ConditionCode,
 };
-use crate::parse_ast::{ExprAST, FunctionAST, ProgramAST, StmtAST};
+use crate::name_generator::NameGenerator;
+use crate::reg::Register;
+use crate::symbol_table::{IdentifierAttrs, Symbol, SymbolTable};
+use crate::tacky_ast::{
+    TackyBinaryOperatorAST, TackyFunctionAST, TackyInstructionAST, TackyProgramAST,
+    TackyTopLevelAST, TackyUnaryOperatorAST, TackyValueAST,
+};
+use crate::type_table::TypeTable;
+use crate::types::{Constant, StaticInit, Type};
 
-pub fn generate_assembly_program_ast(parse_ast: ProgramAST) -> AssemblyProgramAST {
-    let ProgramAST {
-        function_definition,
-    } = parse_ast;
+// SS: System V passes the first six integer arguments in these registers, the rest on the stack
+pub(crate) const ARG_REGISTERS: [Register; 6] = [
+    Register::EDI,
+    Register::ESI,
+    Register::EDX,
+    Register::ECX,
+    Register::R8D,
+    Register::R9D,
+];
 
-    AssemblyProgramAST {
-        function_definition: generate_assembly_function_ast(function_definition),
-    }
+// SS: and the first eight floating-point arguments in these
+pub(crate) const DOUBLE_ARG_REGISTERS: [Register; 8] = [
+    Register::XMM0,
+    Register::XMM1,
+    Register::XMM2,
+    Register::XMM3,
+    Register::XMM4,
+    Register::XMM5,
+    Register::XMM6,
+    Register::XMM7,
+];
+
+// SS: and return values in these
+pub(crate) const INT_RETURN_REGISTERS: [Register; 2] = [Register::EAX, Register::EDX];
+pub(crate) const DOUBLE_RETURN_REGISTERS: [Register; 2] = [Register::XMM0, Register::XMM1];
+
+// SS: 2^63, the smallest double that does not fit in a signed quadword
+const TWO_TO_THE_63: f64 = 9223372036854775808.0;
+
+pub fn generate_assembly_program_ast(
+    tacky_ast: TackyProgramAST,
+    symbols: &mut SymbolTable,
+    types: &TypeTable,
+    name_generator: &mut NameGenerator,
+) -> AssemblyProgramAST {
+    let mut generator = AssemblyGenerator {
+        symbols,
+        types,
+        name_generator,
+        constants: vec![],
+        return_pointer: None,
+    };
+    let mut items = tacky_ast
+        .items
+        .into_iter()
+        .map(|item| match item {
+            TackyTopLevelAST::Function(function) => {
+                AssemblyTopLevelAST::Function(generator.generate_function(function))
+            }
+            TackyTopLevelAST::StaticVariable { name, global, init } => {
+                AssemblyTopLevelAST::StaticVariable {
+                    alignment: generator.symbols[&name]
+                        .symbol_type
+                        .variable_alignment(types),
+                    name,
+                    global,
+                    init,
+                }
+            }
+            TackyTopLevelAST::StaticConstant { name, init } => {
+                AssemblyTopLevelAST::StaticConstant {
+                    name,
+                    alignment: 1,
+                    init,
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+    items.extend(
+        generator
+            .constants
+            .into_iter()
+            .map(
+                |(name, alignment, val)| AssemblyTopLevelAST::StaticConstant {
+                    name,
+                    alignment,
+                    init: StaticInit::Value(Constant::Double(val)),
+                },
+            ),
+    );
+    AssemblyProgramAST { items }
 }
 
-fn generate_assembly_function_ast(function_definition: FunctionAST) -> AssemblyFunctionAST {
-    let FunctionAST { name, body } = function_definition;
-    AssemblyFunctionAST {
-        name,
-        instructions: generate_assembly_instructions_ast(body),
-    }
+// SS: the System V classes of an eightbyte of a structure. A structure larger than 16
+// bytes is passed in memory, otherwise an eightbyte holding only doubles goes in an SSE
+// register and any other in a general-purpose one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Class {
+    Memory,
+    Sse,
+    Integer,
 }
 
-fn generate_assembly_instructions_ast(stmt: StmtAST) -> Vec<AssemblyInstructionAST> {
-    match stmt {
-        StmtAST::Return(expr) => vec![
+// SS: what fits in one register or stack slot, a scalar or an eightbyte of a structure.
+// The last eightbyte of a structure may be shorter than eight bytes, in which case its
+// size is not one an instruction can move at once.
+struct ValuePart {
+    operand: AssemblyOperandAST,
+    asm_type: AssemblyType,
+    size: i32,
+}
+
+struct AssemblyGenerator<'a> {
+    symbols: &'a mut SymbolTable,
+    types: &'a TypeTable,
+    name_generator: &'a mut NameGenerator,
+
+    // SS: the double constants used so far with their label and alignment, each distinct
+    // value is only emitted once
+    constants: Vec<(String, i32, f64)>,
+
+    // SS: where the caller wants the return value of the current function, if it returns
+    // a structure in memory
+    return_pointer: Option<AssemblyOperandAST>,
+}
+
+impl AssemblyGenerator<'_> {
+    fn generate_function(&mut self, function: TackyFunctionAST) -> AssemblyFunctionAST {
+        let TackyFunctionAST {
+            name,
+            global,
+            params,
+            instructions,
+        } = function;
+
+        // SS: a structure returned in memory is written where the caller's hidden first
+        // argument points, which has to survive until the return
+        let mut result = vec![];
+        let return_in_memory = self.returns_in_memory(&name);
+        self.return_pointer = None;
+        if return_in_memory {
+            let return_pointer = self.name_generator.make_unique("return_ptr");
+            self.symbols.insert(
+                return_pointer.clone(),
+                Symbol {
+                    symbol_type: Type::Pointer(Box::new(Type::Long)),
+                    attrs: IdentifierAttrs::Local,
+                },
+            );
+            let return_pointer = AssemblyOperandAST::Pseudo(return_pointer);
+            result.push(AssemblyInstructionAST::Mov {
+                asm_type: AssemblyType::Quadword,
+                src: AssemblyOperandAST::Register(Register::EDI),
+                dst: return_pointer.clone(),
+            });
+            self.return_pointer = Some(return_pointer);
+        }
+
+        // SS: copy the parameters into pseudo-registers, stack arguments start above the saved
+        // rbp and the return address
+        let (register_params, stack_params) = self.classify_arguments(
+            params
+                .into_iter()
+                .map(TackyValueAST::Var)
+                .collect::<Vec<_>>(),
+            return_in_memory,
+        );
+        for (register, param) in register_params {
+            result.extend(store_part(register, param));
+        }
+        for (i, param) in stack_params.into_iter().enumerate() {
+            let src = AssemblyOperandAST::Stack(16 + 8 * i as i32);
+            if matches!(param.operand, AssemblyOperandAST::PseudoMem(..)) {
+                result.extend(copy_bytes(&src, &param.operand, param.size));
+            } else {
+                result.push(AssemblyInstructionAST::Mov {
+                    asm_type: param.asm_type,
+                    src,
+                    dst: param.operand,
+                });
+            }
+        }
+        for instruction in instructions {
+            result.extend(self.generate_instructions(instruction));
+        }
+
+        AssemblyFunctionAST {
+            name,
+            global,
+            instructions: result,
+            callee_saved_registers: vec![],
+        }
+    }
+
+    fn generate_return(&mut self, value: TackyValueAST) -> Vec<AssemblyInstructionAST> {
+        let mut instructions = vec![];
+        if let Some(return_pointer) = self.return_pointer.clone() {
+            // SS: the caller expects the address back in rax
+            let size = self.value_type(&value).size(self.types);
+            instructions.push(AssemblyInstructionAST::Mov {
+                asm_type: AssemblyType::Quadword,
+                src: return_pointer,
+                dst: AssemblyOperandAST::Register(Register::EAX),
+            });
+            instructions.extend(copy_bytes(
+                &self.operand(value),
+                &AssemblyOperandAST::Memory(Register::EAX, 0),
+                size,
+            ));
+        } else {
+            for (register, part) in self.return_registers(value) {
+                instructions.extend(load_part(part, register));
+            }
+        }
+        instructions.push(AssemblyInstructionAST::Ret);
+        instructions
+    }
+
+    // SS: the registers a value that is not returned in memory is returned in
+    fn return_registers(&mut self, value: TackyValueAST) -> Vec<(Register, ValuePart)> {
+        let mut int_registers = INT_RETURN_REGISTERS.iter();
+        let mut double_registers = DOUBLE_RETURN_REGISTERS.iter();
+        self.value_parts(value)
+            .1
+            .into_iter()
+            .map(|part| {
+                let register = if part.asm_type == AssemblyType::Double {
+                    double_registers.next()
+                } else {
+                    int_registers.next()
+                };
+                (*register.unwrap(), part)
+            })
+            .collect()
+    }
+
+    fn returns_in_memory(&self, function: &str) -> bool {
+        let Type::Function { ret, .. } = &self.symbols[function].symbol_type else {
+            unreachable!("{} is not a function", function);
+        };
+        match &**ret {
+            Type::Structure(tag) | Type::Union(tag) => {
+                self.classify_structure(tag)[0] == Class::Memory
+            }
+            _ => false,
+        }
+    }
+
+    fn generate_instructions(
+        &mut self,
+        instruction: TackyInstructionAST,
+    ) -> Vec<AssemblyInstructionAST> {
+        match instruction {
+            TackyInstructionAST::Return(value) => self.generate_return(value),
+            TackyInstructionAST::Unary {
+                op: TackyUnaryOperatorAST::Not,
+                src,
+                dst,
+            } if self.asm_type(&src) == AssemblyType::Double => {
+                // SS: a NaN operand compares unordered, which also sets ZF, but is not zero
+                let end_label = self.name_generator.make_unique("not_nan");
+                let dst = self.operand(dst);
+                vec![
+                    zero_xmm(Register::XMM0),
+                    AssemblyInstructionAST::Cmp {
+                        asm_type: AssemblyType::Double,
+                        src: self.operand(src),
+                        dst: AssemblyOperandAST::Register(Register::XMM0),
+                    },
+                    AssemblyInstructionAST::Mov {
+                        asm_type: AssemblyType::Longword,
+                        src: AssemblyOperandAST::Immediate(0),
+                        dst: dst.clone(),
+                    },
+                    AssemblyInstructionAST::JmpCC {
+                        condition: ConditionCode::P,
+                        target: end_label.clone(),
+                    },
+                    AssemblyInstructionAST::SetCC {
+                        condition: ConditionCode::E,
+                        operand: dst,
+                    },
+                    AssemblyInstructionAST::Label(end_label),
+                ]
+            }
+            TackyInstructionAST::Unary {
+                op: TackyUnaryOperatorAST::Not,
+                src,
+                dst,
+            } => {
+                // SS: !x is x == 0, so compare against zero and materialize the flag
+                let dst = self.operand(dst);
+                vec![
+                    AssemblyInstructionAST::Cmp {
+                        asm_type: self.asm_type(&src),
+                        src: AssemblyOperandAST::Immediate(0),
+                        dst: self.operand(src),
+                    },
+                    AssemblyInstructionAST::Mov {
+                        asm_type: AssemblyType::Longword,
+                        src: AssemblyOperandAST::Immediate(0),
+                        dst: dst.clone(),
+                    },
+                    AssemblyInstructionAST::SetCC {
+                        condition: ConditionCode::E,
+                        operand: dst,
+                    },
+                ]
+            }
+            TackyInstructionAST::Unary {
+                op: TackyUnaryOperatorAST::Negate,
+                src,
+                dst,
+            } if self.asm_type(&src) == AssemblyType::Double => {
+                // SS: flip the sign bit, xorpd needs its memory operand 16-byte aligned
+                let dst = self.operand(dst);
+                vec![
+                    AssemblyInstructionAST::Mov {
+                        asm_type: AssemblyType::Double,
+                        src: self.operand(src),
+                        dst: dst.clone(),
+                    },
+                    AssemblyInstructionAST::Binary {
+                        op: AssemblyBinaryOperatorAST::Xor,
+                        asm_type: AssemblyType::Double,
+                        src: self.double_constant(-0.0, 16),
+                        dst,
+                    },
+                ]
+            }
+            TackyInstructionAST::Unary { op, src, dst } => {
+                let asm_type = self.asm_type(&src);
+                let dst = self.operand(dst);
+                vec![
+                    AssemblyInstructionAST::Mov {
+                        asm_type,
+                        src: self.operand(src),
+                        dst: dst.clone(),
+                    },
+                    AssemblyInstructionAST::Unary {
+                        op: convert_unary_operator(op),
+                        asm_type,
+                        operand: dst,
+                    },
+                ]
+            }
+            TackyInstructionAST::Binary {
+                op:
+                    op @ (TackyBinaryOperatorAST::Equal
+                    | TackyBinaryOperatorAST::NotEqual
+                    | TackyBinaryOperatorAST::LessThan
+                    | TackyBinaryOperatorAST::LessOrEqual
+                    | TackyBinaryOperatorAST::GreaterThan
+                    | TackyBinaryOperatorAST::GreaterOrEqual),
+                src1,
+                src2,
+                dst,
+            } if self.asm_type(&src1) == AssemblyType::Double => {
+                self.generate_double_comparison(op, src1, src2, dst)
+            }
+            TackyInstructionAST::Binary {
+                op:
+                    op @ (TackyBinaryOperatorAST::Equal
+                    | TackyBinaryOperatorAST::NotEqual
+                    | TackyBinaryOperatorAST::LessThan
+                    | TackyBinaryOperatorAST::LessOrEqual
+                    | TackyBinaryOperatorAST::GreaterThan
+                    | TackyBinaryOperatorAST::GreaterOrEqual),
+                src1,
+                src2,
+                dst,
+            } => {
+                // SS: cmp computes dst - src, so src1 goes into the destination slot
+                let asm_type = self.asm_type(&src1);
+                let signed = self.value_type(&src1).is_signed();
+                let dst = self.operand(dst);
+                vec![
+                    AssemblyInstructionAST::Cmp {
+                        asm_type,
+                        src: self.operand(src2),
+                        dst: self.operand(src1),
+                    },
+                    AssemblyInstructionAST::Mov {
+                        asm_type: AssemblyType::Longword,
+                        src: AssemblyOperandAST::Immediate(0),
+                        dst: dst.clone(),
+                    },
+                    AssemblyInstructionAST::SetCC {
+                        condition: convert_condition_code(op, signed),
+                        operand: dst,
+                    },
+                ]
+            }
+            TackyInstructionAST::Binary {
+                op: TackyBinaryOperatorAST::Divide,
+                src1,
+                src2,
+                dst,
+            } if self.asm_type(&src1) == AssemblyType::Double => {
+                let dst = self.operand(dst);
+                vec![
+                    AssemblyInstructionAST::Mov {
+                        asm_type: AssemblyType::Double,
+                        src: self.operand(src1),
+                        dst: dst.clone(),
+                    },
+                    AssemblyInstructionAST::Binary {
+                        op: AssemblyBinaryOperatorAST::DivDouble,
+                        asm_type: AssemblyType::Double,
+                        src: self.operand(src2),
+                        dst,
+                    },
+                ]
+            }
+            TackyInstructionAST::Binary {
+                op: op @ (TackyBinaryOperatorAST::Divide | TackyBinaryOperatorAST::Remainder),
+                src1,
+                src2,
+                dst,
+            } => {
+                // SS: idiv and div divide dx:ax by their operand, leaving the quotient in ax
+                // and the remainder in dx. The dividend is sign extended into dx for idiv and
+                // zero extended for div.
+                let result_register = if op == TackyBinaryOperatorAST::Divide {
+                    Register::EAX
+                } else {
+                    Register::EDX
+                };
+                let asm_type = self.asm_type(&src1);
+                let signed = self.value_type(&src1).is_signed();
+
+                let mut instructions = vec![AssemblyInstructionAST::Mov {
+                    asm_type,
+                    src: self.operand(src1),
+                    dst: AssemblyOperandAST::Register(Register::EAX),
+                }];
+                if signed {
+                    instructions.push(AssemblyInstructionAST::Cdq(asm_type));
+                } else {
+                    instructions.push(AssemblyInstructionAST::Mov {
+                        asm_type,
+                        src: AssemblyOperandAST::Immediate(0),
+                        dst: AssemblyOperandAST::Register(Register::EDX),
+                    });
+                }
+
+                // SS: idiv and div cannot take an immediate operand
+                let divisor = match self.operand(src2) {
+                    immediate @ AssemblyOperandAST::Immediate(_) => {
+                        instructions.push(AssemblyInstructionAST::Mov {
+                            asm_type,
+                            src: immediate,
+                            dst: AssemblyOperandAST::Register(Register::R10D),
+                        });
+                        AssemblyOperandAST::Register(Register::R10D)
+                    }
+                    divisor => divisor,
+                };
+
+                if signed {
+                    instructions.push(AssemblyInstructionAST::Idiv {
+                        asm_type,
+                        operand: divisor,
+                    });
+                } else {
+                    instructions.push(AssemblyInstructionAST::Div {
+                        asm_type,
+                        operand: divisor,
+                    });
+                }
+                instructions.push(AssemblyInstructionAST::Mov {
+                    asm_type,
+                    src: AssemblyOperandAST::Register(result_register),
+                    dst: self.operand(dst),
+                });
+                instructions
+            }
+            TackyInstructionAST::Binary {
+                op,
+                src1,
+                src2,
+                dst,
+            } => {
+                let asm_type = self.asm_type(&src1);
+                let dst = self.operand(dst);
+                vec![
+                    AssemblyInstructionAST::Mov {
+                        asm_type,
+                        src: self.operand(src1),
+                        dst: dst.clone(),
+                    },
+                    AssemblyInstructionAST::Binary {
+                        op: convert_binary_operator(op),
+                        asm_type,
+                        src: self.operand(src2),
+                        dst,
+                    },
+                ]
+            }
+            TackyInstructionAST::Copy { src, dst } if self.is_structure(&src) => {
+                let size = self.value_type(&src).size(self.types);
+                copy_bytes(&self.operand(src), &self.operand(dst), size)
+            }
+            TackyInstructionAST::Copy { src, dst } => vec![AssemblyInstructionAST::Mov {
+                asm_type: self.asm_type(&src),
+                src: self.operand(src),
+                dst: self.operand(dst),
+            }],
+            // SS: the lower bytes of an integer are the truncated value
+            TackyInstructionAST::Truncate { src, dst } => vec![AssemblyInstructionAST::Mov {
+                asm_type: self.asm_type(&dst),
+                src: self.operand(src),
+                dst: self.operand(dst),
+            }],
+            TackyInstructionAST::SignExtend { src, dst } => vec![AssemblyInstructionAST::Movsx {
+                src_type: self.asm_type(&src),
+                dst_type: self.asm_type(&dst),
+                src: self.operand(src),
+                dst: self.operand(dst),
+            }],
+            TackyInstructionAST::ZeroExtend { src, dst } => {
+                vec![AssemblyInstructionAST::MovZeroExtend {
+                    src_type: self.asm_type(&src),
+                    dst_type: self.asm_type(&dst),
+                    src: self.operand(src),
+                    dst: self.operand(dst),
+                }]
+            }
+            // SS: cvtsi2sd has no byte form, a character is extended to a longword first
+            TackyInstructionAST::IntToDouble { src, dst }
+                if self.asm_type(&src) == AssemblyType::Byte =>
+            {
+                vec![
+                    AssemblyInstructionAST::Movsx {
+                        src_type: AssemblyType::Byte,
+                        dst_type: AssemblyType::Longword,
+                        src: self.operand(src),
+                        dst: AssemblyOperandAST::Register(Register::EAX),
+                    },
+                    AssemblyInstructionAST::Cvtsi2sd {
+                        asm_type: AssemblyType::Longword,
+                        src: AssemblyOperandAST::Register(Register::EAX),
+                        dst: self.operand(dst),
+                    },
+                ]
+            }
+            TackyInstructionAST::IntToDouble { src, dst } => {
+                vec![AssemblyInstructionAST::Cvtsi2sd {
+                    asm_type: self.asm_type(&src),
+                    src: self.operand(src),
+                    dst: self.operand(dst),
+                }]
+            }
+            // SS: and cvttsd2si has no byte form either, every character fits in a longword
+            TackyInstructionAST::DoubleToInt { src, dst }
+            | TackyInstructionAST::DoubleToUInt { src, dst }
+                if self.asm_type(&dst) == AssemblyType::Byte =>
+            {
+                vec![
+                    AssemblyInstructionAST::Cvttsd2si {
+                        asm_type: AssemblyType::Longword,
+                        src: self.operand(src),
+                        dst: AssemblyOperandAST::Register(Register::EAX),
+                    },
+                    AssemblyInstructionAST::Mov {
+                        asm_type: AssemblyType::Byte,
+                        src: AssemblyOperandAST::Register(Register::EAX),
+                        dst: self.operand(dst),
+                    },
+                ]
+            }
+            TackyInstructionAST::DoubleToInt { src, dst } => {
+                vec![AssemblyInstructionAST::Cvttsd2si {
+                    asm_type: self.asm_type(&dst),
+                    src: self.operand(src),
+                    dst: self.operand(dst),
+                }]
+            }
+            TackyInstructionAST::UIntToDouble { src, dst } => {
+                let src_type = self.asm_type(&src);
+                if src_type == AssemblyType::Quadword {
+                    self.generate_ulong_to_double(src, dst)
+                } else {
+                    // SS: every unsigned int fits in a signed quadword
+                    let dst_type = if src_type == AssemblyType::Byte {
+                        AssemblyType::Longword
+                    } else {
+                        AssemblyType::Quadword
+                    };
+                    vec![
+                        AssemblyInstructionAST::MovZeroExtend {
+                            src_type,
+                            dst_type,
+                            src: self.operand(src),
+                            dst: AssemblyOperandAST::Register(Register::EAX),
+                        },
+                        AssemblyInstructionAST::Cvtsi2sd {
+                            asm_type: dst_type,
+                            src: AssemblyOperandAST::Register(Register::EAX),
+                            dst: self.operand(dst),
+                        },
+                    ]
+                }
+            }
+            TackyInstructionAST::DoubleToUInt { src, dst } => {
+                if self.asm_type(&dst) == AssemblyType::Longword {
+                    // SS: every unsigned int fits in a signed quadword, keep its lower half
+                    vec![
+                        AssemblyInstructionAST::Cvttsd2si {
+                            asm_type: AssemblyType::Quadword,
+                            src: self.operand(src),
+                            dst: AssemblyOperandAST::Register(Register::EAX),
+                        },
+                        AssemblyInstructionAST::Mov {
+                            asm_type: AssemblyType::Longword,
+                            src: AssemblyOperandAST::Register(Register::EAX),
+                            dst: self.operand(dst),
+                        },
+                    ]
+                } else {
+                    self.generate_double_to_ulong(src, dst)
+                }
+            }
+            TackyInstructionAST::GetAddress { src, dst } => vec![AssemblyInstructionAST::Lea {
+                src: self.operand(src),
+                dst: self.operand(dst),
+            }],
+            // SS: the pointer is loaded into rax so the memory it points to can be addressed
+            TackyInstructionAST::Load { src_ptr, dst } => {
+                let mut instructions = vec![AssemblyInstructionAST::Mov {
+                    asm_type: AssemblyType::Quadword,
+                    src: self.operand(src_ptr),
+                    dst: AssemblyOperandAST::Register(Register::EAX),
+                }];
+                instructions
+                    .extend(self.generate_move(AssemblyOperandAST::Memory(Register::EAX, 0), dst));
+                instructions
+            }
+            TackyInstructionAST::Store { src, dst_ptr } => {
+                let mut instructions = vec![AssemblyInstructionAST::Mov {
+                    asm_type: AssemblyType::Quadword,
+                    src: self.operand(dst_ptr),
+                    dst: AssemblyOperandAST::Register(Register::EAX),
+                }];
+                instructions.extend(
+                    self.generate_move_to(src, AssemblyOperandAST::Memory(Register::EAX, 0)),
+                );
+                instructions
+            }
+            TackyInstructionAST::AddPtr {
+                ptr,
+                index,
+                scale,
+                dst,
+            } => self.generate_add_ptr(ptr, index, scale, dst),
+            TackyInstructionAST::CopyToOffset { src, dst, offset } => {
+                self.generate_move_to(src, AssemblyOperandAST::PseudoMem(dst, offset))
+            }
+            TackyInstructionAST::CopyFromOffset { src, offset, dst } => {
+                self.generate_move(AssemblyOperandAST::PseudoMem(src, offset), dst)
+            }
+            TackyInstructionAST::Jump(target) => vec![AssemblyInstructionAST::Jmp(target)],
+            TackyInstructionAST::JumpTable { index, targets } => {
+                vec![AssemblyInstructionAST::JmpTable {
+                    index: self.operand(index),
+                    table: self.name_generator.make_unique("jump_table"),
+                    targets,
+                }]
+            }
+            TackyInstructionAST::JumpIfZero(value, target) => {
+                self.generate_conditional_jump(false, value, target)
+            }
+            TackyInstructionAST::JumpIfNotZero(value, target) => {
+                self.generate_conditional_jump(true, value, target)
+            }
+            TackyInstructionAST::Label(name) => vec![AssemblyInstructionAST::Label(name)],
+            TackyInstructionAST::FunctionCall { name, args, dst } => {
+                self.generate_function_call(name, args, dst)
+            }
+        }
+    }
+
+    // SS: comisd sets the flags like an unsigned comparison, and sets ZF, PF and CF when
+    // either operand is NaN. Every comparison with NaN is false except !=, so < and <= are
+    // turned around to use A and AE, which are false when CF is set, and == and != skip the
+    // setcc when PF is set.
+    fn generate_double_comparison(
+        &mut self,
+        op: TackyBinaryOperatorAST,
+        src1: TackyValueAST,
+        src2: TackyValueAST,
+        dst: TackyValueAST,
+    ) -> Vec<AssemblyInstructionAST> {
+        let dst = self.operand(dst);
+        let src1 = self.operand(src1);
+        let src2 = self.operand(src2);
+        let (condition, src, cmp_dst) = match op {
+            TackyBinaryOperatorAST::GreaterThan => (ConditionCode::A, src2, src1),
+            TackyBinaryOperatorAST::GreaterOrEqual => (ConditionCode::AE, src2, src1),
+            TackyBinaryOperatorAST::LessThan => (ConditionCode::A, src1, src2),
+            TackyBinaryOperatorAST::LessOrEqual => (ConditionCode::AE, src1, src2),
+            TackyBinaryOperatorAST::Equal => (ConditionCode::E, src2, src1),
+            TackyBinaryOperatorAST::NotEqual => (ConditionCode::NE, src2, src1),
+            _ => unreachable!("{:?} is not a relational operator", op),
+        };
+        let cmp = AssemblyInstructionAST::Cmp {
+            asm_type: AssemblyType::Double,
+            src,
+            dst: cmp_dst,
+        };
+        let set = AssemblyInstructionAST::SetCC {
+            condition,
+            operand: dst.clone(),
+        };
+        match op {
+            TackyBinaryOperatorAST::Equal | TackyBinaryOperatorAST::NotEqual => {
+                let unordered_result = i64::from(op == TackyBinaryOperatorAST::NotEqual);
+                let end_label = self.name_generator.make_unique("cmp_nan");
+                vec![
+                    AssemblyInstructionAST::Mov {
+                        asm_type: AssemblyType::Longword,
+                        src: AssemblyOperandAST::Immediate(unordered_result),
+                        dst,
+                    },
+                    cmp,
+                    AssemblyInstructionAST::JmpCC {
+                        condition: ConditionCode::P,
+                        target: end_label.clone(),
+                    },
+                    set,
+                    AssemblyInstructionAST::Label(end_label),
+                ]
+            }
+            _ => vec![
+                cmp,
+                AssemblyInstructionAST::Mov {
+                    asm_type: AssemblyType::Longword,
+                    src: AssemblyOperandAST::Immediate(0),
+                    dst,
+                },
+                set,
+            ],
+        }
+    }
+
+    // SS: cvttsd2si only converts to signed integers. Values of 2^63 and above have 2^63
+    // subtracted before the conversion and added back afterwards.
+    fn generate_double_to_ulong(
+        &mut self,
+        src: TackyValueAST,
+        dst: TackyValueAST,
+    ) -> Vec<AssemblyInstructionAST> {
+        let out_of_range_label = self.name_generator.make_unique("out_of_range");
+        let end_label = self.name_generator.make_unique("end_conversion");
+        let upper_bound = self.double_constant(TWO_TO_THE_63, 8);
+        let src = self.operand(src);
+        let dst = self.operand(dst);
+        vec![
+            AssemblyInstructionAST::Cmp {
+                asm_type: AssemblyType::Double,
+                src: upper_bound.clone(),
+                dst: src.clone(),
+            },
+            AssemblyInstructionAST::JmpCC {
+                condition: ConditionCode::AE,
+                target: out_of_range_label.clone(),
+            },
+            AssemblyInstructionAST::Cvttsd2si {
+                asm_type: AssemblyType::Quadword,
+                src: src.clone(),
+                dst: dst.clone(),
+            },
+            AssemblyInstructionAST::Jmp(end_label.clone()),
+            AssemblyInstructionAST::Label(out_of_range_label),
+            AssemblyInstructionAST::Mov {
+                asm_type: AssemblyType::Double,
+                src,
+                dst: AssemblyOperandAST::Register(Register::XMM1),
+            },
+            AssemblyInstructionAST::Binary {
+                op: AssemblyBinaryOperatorAST::Sub,
+                asm_type: AssemblyType::Double,
+                src: upper_bound,
+                dst: AssemblyOperandAST::Register(Register::XMM1),
+            },
+            AssemblyInstructionAST::Cvttsd2si {
+                asm_type: AssemblyType::Quadword,
+                src: AssemblyOperandAST::Register(Register::XMM1),
+                dst: dst.clone(),
+            },
             AssemblyInstructionAST::Mov {
-                src: generate_assembly_expr_ast(expr),
-                dst: AssemblyOperandAST::Register(crate::reg::Register::EAX),
+                asm_type: AssemblyType::Quadword,
+                src: AssemblyOperandAST::Immediate(i64::MIN),
+                dst: AssemblyOperandAST::Register(Register::EDX),
             },
-            AssemblyInstructionAST::Ret,
-        ],
+            AssemblyInstructionAST::Binary {
+                op: AssemblyBinaryOperatorAST::Add,
+                asm_type: AssemblyType::Quadword,
+                src: AssemblyOperandAST::Register(Register::EDX),
+                dst,
+            },
+            AssemblyInstructionAST::Label(end_label),
+        ]
+    }
+
+    // SS: cvtsi2sd only converts signed integers. Values with the top bit set are halved
+    // first, keeping the lowest bit so the result rounds correctly, and doubled afterwards.
+    fn generate_ulong_to_double(
+        &mut self,
+        src: TackyValueAST,
+        dst: TackyValueAST,
+    ) -> Vec<AssemblyInstructionAST> {
+        let negative_label = self.name_generator.make_unique("top_bit_set");
+        let end_label = self.name_generator.make_unique("end_conversion");
+        let src = self.operand(src);
+        let dst = self.operand(dst);
+        let ax = AssemblyOperandAST::Register(Register::EAX);
+        let dx = AssemblyOperandAST::Register(Register::EDX);
+        vec![
+            AssemblyInstructionAST::Cmp {
+                asm_type: AssemblyType::Quadword,
+                src: AssemblyOperandAST::Immediate(0),
+                dst: src.clone(),
+            },
+            AssemblyInstructionAST::JmpCC {
+                condition: ConditionCode::L,
+                target: negative_label.clone(),
+            },
+            AssemblyInstructionAST::Cvtsi2sd {
+                asm_type: AssemblyType::Quadword,
+                src: src.clone(),
+                dst: dst.clone(),
+            },
+            AssemblyInstructionAST::Jmp(end_label.clone()),
+            AssemblyInstructionAST::Label(negative_label),
+            AssemblyInstructionAST::Mov {
+                asm_type: AssemblyType::Quadword,
+                src,
+                dst: ax.clone(),
+            },
+            AssemblyInstructionAST::Mov {
+                asm_type: AssemblyType::Quadword,
+                src: ax.clone(),
+                dst: dx.clone(),
+            },
+            AssemblyInstructionAST::Binary {
+                op: AssemblyBinaryOperatorAST::Shr,
+                asm_type: AssemblyType::Quadword,
+                src: AssemblyOperandAST::Immediate(1),
+                dst: dx.clone(),
+            },
+            AssemblyInstructionAST::Binary {
+                op: AssemblyBinaryOperatorAST::And,
+                asm_type: AssemblyType::Quadword,
+                src: AssemblyOperandAST::Immediate(1),
+                dst: ax.clone(),
+            },
+            AssemblyInstructionAST::Binary {
+                op: AssemblyBinaryOperatorAST::Or,
+                asm_type: AssemblyType::Quadword,
+                src: ax,
+                dst: dx.clone(),
+            },
+            AssemblyInstructionAST::Cvtsi2sd {
+                asm_type: AssemblyType::Quadword,
+                src: dx,
+                dst: dst.clone(),
+            },
+            AssemblyInstructionAST::Binary {
+                op: AssemblyBinaryOperatorAST::Add,
+                asm_type: AssemblyType::Double,
+                src: dst.clone(),
+                dst,
+            },
+            AssemblyInstructionAST::Label(end_label),
+        ]
+    }
+
+    fn generate_conditional_jump(
+        &mut self,
+        jump_if_not_zero: bool,
+        value: TackyValueAST,
+        target: String,
+    ) -> Vec<AssemblyInstructionAST> {
+        if self.asm_type(&value) != AssemblyType::Double {
+            let condition = if jump_if_not_zero {
+                ConditionCode::NE
+            } else {
+                ConditionCode::E
+            };
+            return vec![
+                AssemblyInstructionAST::Cmp {
+                    asm_type: self.asm_type(&value),
+                    src: AssemblyOperandAST::Immediate(0),
+                    dst: self.operand(value),
+                },
+                AssemblyInstructionAST::JmpCC { condition, target },
+            ];
+        }
+
+        // SS: NaN is not zero, but compares unordered with zero which sets ZF along with PF
+        let mut instructions = vec![
+            zero_xmm(Register::XMM0),
+            AssemblyInstructionAST::Cmp {
+                asm_type: AssemblyType::Double,
+                src: self.operand(value),
+                dst: AssemblyOperandAST::Register(Register::XMM0),
+            },
+        ];
+        if jump_if_not_zero {
+            instructions.push(AssemblyInstructionAST::JmpCC {
+                condition: ConditionCode::P,
+                target: target.clone(),
+            });
+            instructions.push(AssemblyInstructionAST::JmpCC {
+                condition: ConditionCode::NE,
+                target,
+            });
+        } else {
+            let skip_label = self.name_generator.make_unique("skip_nan");
+            instructions.push(AssemblyInstructionAST::JmpCC {
+                condition: ConditionCode::P,
+                target: skip_label.clone(),
+            });
+            instructions.push(AssemblyInstructionAST::JmpCC {
+                condition: ConditionCode::E,
+                target,
+            });
+            instructions.push(AssemblyInstructionAST::Label(skip_label));
+        }
+        instructions
+    }
+
+    fn generate_function_call(
+        &mut self,
+        name: String,
+        args: Vec<TackyValueAST>,
+        dst: TackyValueAST,
+    ) -> Vec<AssemblyInstructionAST> {
+        let mut instructions = vec![];
+        let return_in_memory = self.returns_in_memory(&name);
+        let (register_args, stack_args) = self.classify_arguments(args, return_in_memory);
+
+        // SS: rsp has to be 16-byte aligned at the call, every stack argument takes 8 bytes
+        let padding = if stack_args.len() % 2 == 1 { 8 } else { 0 };
+        if padding != 0 {
+            instructions.push(AssemblyInstructionAST::AllocateStack(padding));
+        }
+
+        // SS: the callee writes a structure returned in memory straight into dst
+        if return_in_memory {
+            instructions.push(AssemblyInstructionAST::Lea {
+                src: self.operand(dst.clone()),
+                dst: AssemblyOperandAST::Register(Register::EDI),
+            });
+        }
+        for (register, arg) in register_args {
+            instructions.extend(load_part(arg, register));
+        }
+
+        // SS: stack arguments are pushed in reverse order. pushq reads 8 bytes, so anything
+        // smaller in memory goes through eax rather than being pushed directly.
+        let stack_bytes = 8 * stack_args.len() as i32;
+        for arg in stack_args.into_iter().rev() {
+            match arg.operand {
+                immediate @ AssemblyOperandAST::Immediate(_) => {
+                    instructions.push(AssemblyInstructionAST::Push(immediate))
+                }
+                operand if arg.size == 8 => {
+                    instructions.push(AssemblyInstructionAST::Push(operand))
+                }
+                _ => {
+                    instructions.extend(load_part(arg, Register::EAX));
+                    instructions.push(AssemblyInstructionAST::Push(AssemblyOperandAST::Register(
+                        Register::EAX,
+                    )));
+                }
+            }
+        }
+
+        instructions.push(AssemblyInstructionAST::Call(name));
+
+        if stack_bytes + padding != 0 {
+            instructions.push(AssemblyInstructionAST::DeallocateStack(
+                stack_bytes + padding,
+            ));
+        }
+
+        if !return_in_memory {
+            for (register, part) in self.return_registers(dst) {
+                instructions.extend(store_part(register, part));
+            }
+        }
+        instructions
+    }
+
+    // SS: assigns arguments to registers following System V. Integers take the next free
+    // general-purpose register and doubles the next free SSE register, whatever does not
+    // fit goes on the stack in its original order. A structure takes one register per
+    // eightbyte, but only if there are enough left for all of them, otherwise it goes on
+    // the stack entirely. If the return value is in memory rdi holds its address.
+    #[allow(clippy::type_complexity)]
+    fn classify_arguments(
+        &mut self,
+        args: Vec<TackyValueAST>,
+        return_in_memory: bool,
+    ) -> (Vec<(Register, ValuePart)>, Vec<ValuePart>) {
+        let mut register_args = vec![];
+        let mut stack_args = vec![];
+        let mut int_registers = ARG_REGISTERS[usize::from(return_in_memory)..].iter();
+        let mut double_registers = DOUBLE_ARG_REGISTERS.iter();
+        for arg in args {
+            let (class, parts) = self.value_parts(arg);
+            let int_count = parts
+                .iter()
+                .filter(|part| part.asm_type != AssemblyType::Double)
+                .count();
+            let double_count = parts.len() - int_count;
+            let fits = class != Class::Memory
+                && int_count <= int_registers.len()
+                && double_count <= double_registers.len();
+            if !fits {
+                stack_args.extend(parts);
+                continue;
+            }
+            for part in parts {
+                let register = if part.asm_type == AssemblyType::Double {
+                    double_registers.next()
+                } else {
+                    int_registers.next()
+                };
+                register_args.push((*register.unwrap(), part));
+            }
+        }
+        (register_args, stack_args)
+    }
+
+    // SS: splits a value into the parts that are passed in separate registers, along with
+    // the class of a structure
+    fn value_parts(&mut self, value: TackyValueAST) -> (Class, Vec<ValuePart>) {
+        let value_type = self.value_type(&value);
+        let (Type::Structure(tag) | Type::Union(tag)) = &value_type else {
+            let class = if value_type == Type::Double {
+                Class::Sse
+            } else {
+                Class::Integer
+            };
+            let part = ValuePart {
+                asm_type: self.asm_type(&value),
+                size: value_type.scalar_size(),
+                operand: self.operand(value),
+            };
+            return (class, vec![part]);
+        };
+
+        let classes = self.classify_structure(tag);
+        let size = self.types[tag].size;
+        let operand = self.operand(value);
+        let parts = classes
+            .iter()
+            .enumerate()
+            .map(|(i, class)| {
+                let part_size = (size - 8 * i as i32).min(8);
+                let asm_type = match (class, part_size) {
+                    (Class::Sse, _) => AssemblyType::Double,
+                    (_, 1) => AssemblyType::Byte,
+                    (_, 4) => AssemblyType::Longword,
+                    _ => AssemblyType::Quadword,
+                };
+                ValuePart {
+                    operand: offset_operand(&operand, 8 * i as i32),
+                    asm_type,
+                    size: part_size,
+                }
+            })
+            .collect();
+        (classes[0], parts)
+    }
+
+    // SS: one class per eightbyte of the structure
+    fn classify_structure(&self, tag: &str) -> Vec<Class> {
+        let size = self.types[tag].size;
+        let eightbytes = (size as usize).div_ceil(8);
+        if size > 16 {
+            return vec![Class::Memory; eightbytes];
+        }
+        let mut scalars = vec![];
+        self.scalar_members(&Type::Structure(tag.to_string()), 0, &mut scalars);
+        (0..eightbytes as i32)
+            .map(|i| {
+                let all_doubles = scalars
+                    .iter()
+                    .filter(|(offset, _)| *offset / 8 == i)
+                    .all(|(_, scalar_type)| *scalar_type == Type::Double);
+                if all_doubles {
+                    Class::Sse
+                } else {
+                    Class::Integer
+                }
+            })
+            .collect()
+    }
+
+    // SS: the offset and type of every scalar within an object of the given type, looking
+    // through nested structures and arrays
+    fn scalar_members(&self, member_type: &Type, offset: i32, scalars: &mut Vec<(i32, Type)>) {
+        match member_type {
+            Type::Structure(tag) | Type::Union(tag) => {
+                for member in &self.types[tag].members {
+                    self.scalar_members(&member.member_type, offset + member.offset, scalars);
+                }
+            }
+            Type::Array { element, size } => {
+                let element_size = element.size(self.types);
+                for i in 0..*size as i32 {
+                    self.scalar_members(element, offset + i * element_size, scalars);
+                }
+            }
+            scalar => scalars.push((offset, scalar.clone())),
+        }
+    }
+
+    // SS: moves memory into a value, a structure is copied byte-wise
+    fn generate_move(
+        &mut self,
+        src: AssemblyOperandAST,
+        dst: TackyValueAST,
+    ) -> Vec<AssemblyInstructionAST> {
+        if self.is_structure(&dst) {
+            let size = self.value_type(&dst).size(self.types);
+            return copy_bytes(&src, &self.operand(dst), size);
+        }
+        vec![AssemblyInstructionAST::Mov {
+            asm_type: self.asm_type(&dst),
+            src,
+            dst: self.operand(dst),
+        }]
+    }
+
+    // SS: moves a value into memory
+    fn generate_move_to(
+        &mut self,
+        src: TackyValueAST,
+        dst: AssemblyOperandAST,
+    ) -> Vec<AssemblyInstructionAST> {
+        if self.is_structure(&src) {
+            let size = self.value_type(&src).size(self.types);
+            return copy_bytes(&self.operand(src), &dst, size);
+        }
+        vec![AssemblyInstructionAST::Mov {
+            asm_type: self.asm_type(&src),
+            src: self.operand(src),
+            dst,
+        }]
+    }
+
+    // SS: a constant index is folded into the displacement, otherwise lea scales the index
+    // in rdx if the element size is one the addressing mode supports
+    fn generate_add_ptr(
+        &mut self,
+        ptr: TackyValueAST,
+        index: TackyValueAST,
+        scale: i64,
+        dst: TackyValueAST,
+    ) -> Vec<AssemblyInstructionAST> {
+        let mut instructions = vec![AssemblyInstructionAST::Mov {
+            asm_type: AssemblyType::Quadword,
+            src: self.operand(ptr),
+            dst: AssemblyOperandAST::Register(Register::EAX),
+        }];
+        let src = match index {
+            TackyValueAST::Constant(val) => {
+                AssemblyOperandAST::Memory(Register::EAX, (val.bits() * scale) as i32)
+            }
+            index => {
+                instructions.push(AssemblyInstructionAST::Mov {
+                    asm_type: AssemblyType::Quadword,
+                    src: self.operand(index),
+                    dst: AssemblyOperandAST::Register(Register::EDX),
+                });
+                let scale = if matches!(scale, 1 | 2 | 4 | 8) {
+                    scale as i32
+                } else {
+                    instructions.push(AssemblyInstructionAST::Binary {
+                        op: AssemblyBinaryOperatorAST::Mult,
+                        asm_type: AssemblyType::Quadword,
+                        src: AssemblyOperandAST::Immediate(scale),
+                        dst: AssemblyOperandAST::Register(Register::EDX),
+                    });
+                    1
+                };
+                AssemblyOperandAST::Indexed {
+                    base: Register::EAX,
+                    index: Register::EDX,
+                    scale,
+                }
+            }
+        };
+        instructions.push(AssemblyInstructionAST::Lea {
+            src,
+            dst: self.operand(dst),
+        });
+        instructions
+    }
+
+    fn value_type(&self, value: &TackyValueAST) -> Type {
+        match value {
+            TackyValueAST::Constant(val) => val.constant_type(),
+            TackyValueAST::Var(name) => self.symbols[name].symbol_type.clone(),
+        }
+    }
+
+    fn is_structure(&self, value: &TackyValueAST) -> bool {
+        self.value_type(value).is_structure()
+    }
+
+    fn asm_type(&self, value: &TackyValueAST) -> AssemblyType {
+        match self.value_type(value) {
+            Type::Char | Type::SChar | Type::UChar => AssemblyType::Byte,
+            Type::Int | Type::UInt => AssemblyType::Longword,
+            Type::Long | Type::ULong | Type::Pointer(_) => AssemblyType::Quadword,
+            Type::Double => AssemblyType::Double,
+            Type::Array { .. } => unreachable!("arrays are only accessed through pointers"),
+            Type::Function { .. } => unreachable!("functions are not values"),
+            Type::Structure(_) | Type::Union(_) => {
+                unreachable!("structures are copied in parts")
+            }
+        }
+    }
+
+    fn operand(&mut self, value: TackyValueAST) -> AssemblyOperandAST {
+        match value {
+            // SS: there are no floating-point immediates, doubles are loaded from memory
+            TackyValueAST::Constant(Constant::Double(val)) => self.double_constant(val, 8),
+            // SS: the assembler wants longword immediates in signed range
+            TackyValueAST::Constant(Constant::UInt(val)) => {
+                AssemblyOperandAST::Immediate(val as i32 as i64)
+            }
+            TackyValueAST::Constant(val) => AssemblyOperandAST::Immediate(val.bits()),
+            // SS: an array is only ever the operand of lea, which needs a memory operand, and
+            // a structure is accessed in parts at offsets from its start
+            TackyValueAST::Var(name)
+                if matches!(
+                    self.symbols[&name].symbol_type,
+                    Type::Array { .. } | Type::Structure(_) | Type::Union(_)
+                ) =>
+            {
+                AssemblyOperandAST::PseudoMem(name, 0)
+            }
+            TackyValueAST::Var(name) => AssemblyOperandAST::Pseudo(name),
+        }
+    }
+
+    // SS: returns the read-only constant holding the value, creating it on first use
+    fn double_constant(&mut self, val: f64, alignment: i32) -> AssemblyOperandAST {
+        let existing = self
+            .constants
+            .iter()
+            .find(|(_, constant_alignment, constant)| {
+                constant.to_bits() == val.to_bits() && *constant_alignment == alignment
+            });
+        let name = match existing {
+            Some((name, ..)) => name.clone(),
+            None => {
+                let name = self.name_generator.make_unique("const_double");
+                self.constants.push((name.clone(), alignment, val));
+                name
+            }
+        };
+        AssemblyOperandAST::Data(name, 0)
+    }
+}
+
+// SS: the memory operand delta bytes further on
+fn offset_operand(operand: &AssemblyOperandAST, delta: i32) -> AssemblyOperandAST {
+    match operand {
+        AssemblyOperandAST::PseudoMem(name, offset) => {
+            AssemblyOperandAST::PseudoMem(name.clone(), offset + delta)
+        }
+        AssemblyOperandAST::Memory(register, offset) => {
+            AssemblyOperandAST::Memory(*register, offset + delta)
+        }
+        AssemblyOperandAST::Stack(offset) => AssemblyOperandAST::Stack(offset + delta),
+        AssemblyOperandAST::Data(name, offset) => {
+            AssemblyOperandAST::Data(name.clone(), offset + delta)
+        }
+        _ => unreachable!("{:?} is not a memory operand", operand),
+    }
+}
+
+// SS: copies size bytes between two memory operands, eight or four bytes at a time where
+// possible
+fn copy_bytes(
+    src: &AssemblyOperandAST,
+    dst: &AssemblyOperandAST,
+    size: i32,
+) -> Vec<AssemblyInstructionAST> {
+    let mut instructions = vec![];
+    let mut offset = 0;
+    while offset < size {
+        let (asm_type, length) = match size - offset {
+            8.. => (AssemblyType::Quadword, 8),
+            4..=7 => (AssemblyType::Longword, 4),
+            _ => (AssemblyType::Byte, 1),
+        };
+        instructions.push(AssemblyInstructionAST::Mov {
+            asm_type,
+            src: offset_operand(src, offset),
+            dst: offset_operand(dst, offset),
+        });
+        offset += length;
+    }
+    instructions
+}
+
+// SS: moves a part of a value into a register. The last eightbyte of a structure may
+// have a size no instruction moves at once, it is put together byte by byte from its
+// highest byte down, shifting the register left to make room for the next one.
+fn load_part(part: ValuePart, register: Register) -> Vec<AssemblyInstructionAST> {
+    let dst = AssemblyOperandAST::Register(register);
+    if part.asm_type == AssemblyType::Double || matches!(part.size, 1 | 4 | 8) {
+        return vec![AssemblyInstructionAST::Mov {
+            asm_type: part.asm_type,
+            src: part.operand,
+            dst,
+        }];
+    }
+    let mut instructions = vec![];
+    for i in (0..part.size).rev() {
+        if i != part.size - 1 {
+            instructions.push(AssemblyInstructionAST::Binary {
+                op: AssemblyBinaryOperatorAST::Shl,
+                asm_type: AssemblyType::Quadword,
+                src: AssemblyOperandAST::Immediate(8),
+                dst: dst.clone(),
+            });
+        }
+        instructions.push(AssemblyInstructionAST::Mov {
+            asm_type: AssemblyType::Byte,
+            src: offset_operand(&part.operand, i),
+            dst: dst.clone(),
+        });
     }
+    instructions
 }
 
-fn generate_assembly_expr_ast(expr: ExprAST) -> AssemblyOperandAST {
-    match expr {
-        ExprAST::Constant(val) => AssemblyOperandAST::Immediate(val),
+// SS: the reverse of load_part, writing the lowest byte and shifting the register right
+// for the next one, which clobbers the register
+fn store_part(register: Register, part: ValuePart) -> Vec<AssemblyInstructionAST> {
+    let src = AssemblyOperandAST::Register(register);
+    if part.asm_type == AssemblyType::Double || matches!(part.size, 1 | 4 | 8) {
+        return vec![AssemblyInstructionAST::Mov {
+            asm_type: part.asm_type,
+            src,
+            dst: part.operand,
+        }];
+    }
+    let mut instructions = vec![];
+    for i in 0..part.size {
+        if i != 0 {
+            instructions.push(AssemblyInstructionAST::Binary {
+                op: AssemblyBinaryOperatorAST::Shr,
+                asm_type: AssemblyType::Quadword,
+                src: AssemblyOperandAST::Immediate(8),
+                dst: src.clone(),
+            });
+        }
+        instructions.push(AssemblyInstructionAST::Mov {
+            asm_type: AssemblyType::Byte,
+            src: src.clone(),
+            dst: offset_operand(&part.operand, i),
+        });
+    }
+    instructions
+}
+
+fn zero_xmm(register: Register) -> AssemblyInstructionAST {
+    AssemblyInstructionAST::Binary {
+        op: AssemblyBinaryOperatorAST::Xor,
+        asm_type: AssemblyType::Double,
+        src: AssemblyOperandAST::Register(register),
+        dst: AssemblyOperandAST::Register(register),
+    }
+}
+
+fn convert_condition_code(op: TackyBinaryOperatorAST, signed: bool) -> ConditionCode {
+    match (op, signed) {
+        (TackyBinaryOperatorAST::Equal, _) => ConditionCode::E,
+        (TackyBinaryOperatorAST::NotEqual, _) => ConditionCode::NE,
+        (TackyBinaryOperatorAST::LessThan, true) => ConditionCode::L,
+        (TackyBinaryOperatorAST::LessOrEqual, true) => ConditionCode::LE,
+        (TackyBinaryOperatorAST::GreaterThan, true) => ConditionCode::G,
+        (TackyBinaryOperatorAST::GreaterOrEqual, true) => ConditionCode::GE,
+        (TackyBinaryOperatorAST::LessThan, false) => ConditionCode::B,
+        (TackyBinaryOperatorAST::LessOrEqual, false) => ConditionCode::BE,
+        (TackyBinaryOperatorAST::GreaterThan, false) => ConditionCode::A,
+        (TackyBinaryOperatorAST::GreaterOrEqual, false) => ConditionCode::AE,
+        _ => unreachable!("{:?} is not a relational operator", op),
+    }
+}
+
+fn convert_binary_operator(op: TackyBinaryOperatorAST) -> AssemblyBinaryOperatorAST {
+    match op {
+        TackyBinaryOperatorAST::Add => AssemblyBinaryOperatorAST::Add,
+        TackyBinaryOperatorAST::Subtract => AssemblyBinaryOperatorAST::Sub,
+        TackyBinaryOperatorAST::Multiply => AssemblyBinaryOperatorAST::Mult,
+        TackyBinaryOperatorAST::Divide | TackyBinaryOperatorAST::Remainder => {
+            unreachable!("integer division is lowered to idiv or div")
+        }
+        _ => unreachable!("relational operators are lowered to cmp/setcc"),
+    }
+}
+
+fn convert_unary_operator(op: TackyUnaryOperatorAST) -> AssemblyUnaryOperatorAST {
+    match op {
+        TackyUnaryOperatorAST::Negate => AssemblyUnaryOperatorAST::Neg,
+        TackyUnaryOperatorAST::Complement => AssemblyUnaryOperatorAST::Not,
+        TackyUnaryOperatorAST::Not => unreachable!("logical not is lowered to cmp/setcc"),
     }
 }
 
 #[cfg(test)]
 mod tests {
+    fn generate(
+        tacky_ast: crate::tacky_ast::TackyProgramAST,
+        variables: &[(&str, crate::types::Type)],
+    ) -> crate::assembly_ast::AssemblyProgramAST {
+        generate_with_types(tacky_ast, variables, &crate::type_table::TypeTable::new())
+    }
+
+    // SS: generates assembly with the given variables, every function defined or called
+    // returns int
+    fn generate_with_types(
+        tacky_ast: crate::tacky_ast::TackyProgramAST,
+        variables: &[(&str, crate::types::Type)],
+        types: &crate::type_table::TypeTable,
+    ) -> crate::assembly_ast::AssemblyProgramAST {
+        let mut symbols: crate::symbol_table::SymbolTable = variables
+            .iter()
+            .map(|(name, symbol_type)| {
+                (
+                    name.to_string(),
+                    crate::symbol_table::Symbol {
+                        symbol_type: symbol_type.clone(),
+                        attrs: crate::symbol_table::IdentifierAttrs::Local,
+                    },
+                )
+            })
+            .collect();
+        for item in &tacky_ast.items {
+            let crate::tacky_ast::TackyTopLevelAST::Function(function) = item else {
+                continue;
+            };
+            let called = function
+                .instructions
+                .iter()
+                .filter_map(|instruction| match instruction {
+                    crate::tacky_ast::TackyInstructionAST::FunctionCall { name, .. } => {
+                        Some(name.clone())
+                    }
+                    _ => None,
+                });
+            for name in std::iter::once(function.name.clone()).chain(called) {
+                symbols.entry(name).or_insert(crate::symbol_table::Symbol {
+                    symbol_type: crate::types::Type::Function {
+                        params: vec![],
+                        ret: Box::new(crate::types::Type::Int),
+                    },
+                    attrs: crate::symbol_table::IdentifierAttrs::Function {
+                        defined: true,
+                        global: true,
+                    },
+                });
+            }
+        }
+        crate::ir_generation::generate_assembly_program_ast(
+            tacky_ast,
+            &mut symbols,
+            types,
+            &mut crate::name_generator::NameGenerator::new(),
+        )
+    }
+
+    fn instructions(
+        ast: &crate::assembly_ast::AssemblyProgramAST,
+    ) -> Vec<crate::assembly_ast::AssemblyInstructionAST> {
+        match &ast.items[0] {
+            crate::assembly_ast::AssemblyTopLevelAST::Function(function) => {
+                function.instructions.clone()
+            }
+            item => panic!("expected a function, found {:?}", item),
+        }
+    }
+
     #[test]
     fn test_generate_assembly_program_ast() {
         // SS: arrange
-        let parse_ast = crate::parse_ast::ProgramAST {
-            function_definition: crate::parse_ast::FunctionAST {
-                name: "main".to_string(),
-                body: crate::parse_ast::StmtAST::Return(crate::parse_ast::ExprAST::Constant(2)),
-            },
+        let tacky_ast = crate::tacky_ast::TackyProgramAST {
+            items: vec![crate::tacky_ast::TackyTopLevelAST::Function(
+                crate::tacky_ast::TackyFunctionAST {
+                    name: "main".to_string(),
+                    global: true,
+                    params: vec![],
+                    instructions: vec![crate::tacky_ast::TackyInstructionAST::Return(
+                        crate::tacky_ast::TackyValueAST::Constant(crate::types::Constant::Int(2)),
+                    )],
+                },
+            )],
         };
 
         // SS: act
-        let assembly_ast = crate::ir_generation::generate_assembly_program_ast(parse_ast);
+        let assembly_ast = generate(tacky_ast, &[]);
 
         // SS: assert
         assert_eq!(
             assembly_ast,
             crate::assembly_ast::AssemblyProgramAST {
-                function_definition: crate::assembly_ast::AssemblyFunctionAST {
+                items: vec![crate::assembly_ast::AssemblyTopLevelAST::Function(
+                    crate::assembly_ast::AssemblyFunctionAST {
+                        name: "main".to_string(),
+                        global: true,
+                        instructions: vec![
+                            crate::assembly_ast::AssemblyInstructionAST::Mov {
+                                asm_type: crate::assembly_ast::AssemblyType::Longword,
+                                src: crate::assembly_ast::AssemblyOperandAST::Immediate(2),
+                                dst: crate::assembly_ast::AssemblyOperandAST::Register(
+                                    crate::reg::Register::EAX
+                                ),
+                            },
+                            crate::assembly_ast::AssemblyInstructionAST::Ret,
+                        ],
+                        callee_saved_registers: vec![],
+                    }
+                )]
+            }
+        );
+    }
+
+    #[test]
+    fn test_generate_assembly_program_ast_unary() {
+        // SS: arrange
+        let tacky_ast = crate::tacky_ast::TackyProgramAST {
+            items: vec![crate::tacky_ast::TackyTopLevelAST::Function(
+                crate::tacky_ast::TackyFunctionAST {
                     name: "main".to_string(),
+                    global: true,
+                    params: vec![],
                     instructions: vec![
-                        crate::assembly_ast::AssemblyInstructionAST::Mov {
-                            src: crate::assembly_ast::AssemblyOperandAST::Immediate(2),
-                            dst: crate::assembly_ast::AssemblyOperandAST::Register(
-                                crate::reg::Register::EAX
+                        crate::tacky_ast::TackyInstructionAST::Unary {
+                            op: crate::tacky_ast::TackyUnaryOperatorAST::Negate,
+                            src: crate::tacky_ast::TackyValueAST::Constant(
+                                crate::types::Constant::Int(2),
                             ),
+                            dst: crate::tacky_ast::TackyValueAST::Var("tmp.0".to_string()),
                         },
-                        crate::assembly_ast::AssemblyInstructionAST::Ret,
+                        crate::tacky_ast::TackyInstructionAST::Return(
+                            crate::tacky_ast::TackyValueAST::Var("tmp.0".to_string()),
+                        ),
                     ],
-                }
+                },
+            )],
+        };
+
+        // SS: act
+        let assembly_ast = generate(tacky_ast, &[("tmp.0", crate::types::Type::Int)]);
+
+        // SS: assert
+        assert_eq!(
+            instructions(&assembly_ast),
+            vec![
+                crate::assembly_ast::AssemblyInstructionAST::Mov {
+                    asm_type: crate::assembly_ast::AssemblyType::Longword,
+                    src: crate::assembly_ast::AssemblyOperandAST::Immediate(2),
+                    dst: crate::assembly_ast::AssemblyOperandAST::Pseudo("tmp.0".to_string()),
+                },
+                crate::assembly_ast::AssemblyInstructionAST::Unary {
+                    asm_type: crate::assembly_ast::AssemblyType::Longword,
+                    op: crate::assembly_ast::AssemblyUnaryOperatorAST::Neg,
+                    operand: crate::assembly_ast::AssemblyOperandAST::Pseudo("tmp.0".to_string()),
+                },
+                crate::assembly_ast::AssemblyInstructionAST::Mov {
+                    asm_type: crate::assembly_ast::AssemblyType::Longword,
+                    src: crate::assembly_ast::AssemblyOperandAST::Pseudo("tmp.0".to_string()),
+                    dst: crate::assembly_ast::AssemblyOperandAST::Register(
+                        crate::reg::Register::EAX
+                    ),
+                },
+                crate::assembly_ast::AssemblyInstructionAST::Ret,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_generate_assembly_program_ast_remainder() {
+        // SS: arrange
+        let tacky_ast = crate::tacky_ast::TackyProgramAST {
+            items: vec![crate::tacky_ast::TackyTopLevelAST::Function(
+                crate::tacky_ast::TackyFunctionAST {
+                    name: "main".to_string(),
+                    global: true,
+                    params: vec![],
+                    instructions: vec![crate::tacky_ast::TackyInstructionAST::Binary {
+                        op: crate::tacky_ast::TackyBinaryOperatorAST::Remainder,
+                        src1: crate::tacky_ast::TackyValueAST::Constant(
+                            crate::types::Constant::Int(7),
+                        ),
+                        src2: crate::tacky_ast::TackyValueAST::Constant(
+                            crate::types::Constant::Int(3),
+                        ),
+                        dst: crate::tacky_ast::TackyValueAST::Var("tmp.0".to_string()),
+                    }],
+                },
+            )],
+        };
+
+        // SS: act
+        let assembly_ast = generate(tacky_ast, &[("tmp.0", crate::types::Type::Int)]);
+
+        // SS: assert
+        assert_eq!(
+            instructions(&assembly_ast),
+            vec![
+                crate::assembly_ast::AssemblyInstructionAST::Mov {
+                    asm_type: crate::assembly_ast::AssemblyType::Longword,
+                    src: crate::assembly_ast::AssemblyOperandAST::Immediate(7),
+                    dst: crate::assembly_ast::AssemblyOperandAST::Register(
+                        crate::reg::Register::EAX
+                    ),
+                },
+                crate::assembly_ast::AssemblyInstructionAST::Cdq(
+                    crate::assembly_ast::AssemblyType::Longword
+                ),
+                crate::assembly_ast::AssemblyInstructionAST::Mov {
+                    asm_type: crate::assembly_ast::AssemblyType::Longword,
+                    src: crate::assembly_ast::AssemblyOperandAST::Immediate(3),
+                    dst: crate::assembly_ast::AssemblyOperandAST::Register(
+                        crate::reg::Register::R10D
+                    ),
+                },
+                crate::assembly_ast::AssemblyInstructionAST::Idiv {
+                    asm_type: crate::assembly_ast::AssemblyType::Longword,
+                    operand: crate::assembly_ast::AssemblyOperandAST::Register(
+                        crate::reg::Register::R10D
+                    )
+                },
+                crate::assembly_ast::AssemblyInstructionAST::Mov {
+                    asm_type: crate::assembly_ast::AssemblyType::Longword,
+                    src: crate::assembly_ast::AssemblyOperandAST::Register(
+                        crate::reg::Register::EDX
+                    ),
+                    dst: crate::assembly_ast::AssemblyOperandAST::Pseudo("tmp.0".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_generate_assembly_program_ast_relational() {
+        // SS: arrange
+        let tacky_ast = crate::tacky_ast::TackyProgramAST {
+            items: vec![crate::tacky_ast::TackyTopLevelAST::Function(
+                crate::tacky_ast::TackyFunctionAST {
+                    name: "main".to_string(),
+                    global: true,
+                    params: vec![],
+                    instructions: vec![
+                        crate::tacky_ast::TackyInstructionAST::Binary {
+                            op: crate::tacky_ast::TackyBinaryOperatorAST::LessThan,
+                            src1: crate::tacky_ast::TackyValueAST::Constant(
+                                crate::types::Constant::Int(1),
+                            ),
+                            src2: crate::tacky_ast::TackyValueAST::Constant(
+                                crate::types::Constant::Int(2),
+                            ),
+                            dst: crate::tacky_ast::TackyValueAST::Var("tmp.0".to_string()),
+                        },
+                        crate::tacky_ast::TackyInstructionAST::JumpIfNotZero(
+                            crate::tacky_ast::TackyValueAST::Var("tmp.0".to_string()),
+                            "or_true.0".to_string(),
+                        ),
+                    ],
+                },
+            )],
+        };
+
+        // SS: act
+        let assembly_ast = generate(tacky_ast, &[("tmp.0", crate::types::Type::Int)]);
+
+        // SS: assert
+        assert_eq!(
+            instructions(&assembly_ast),
+            vec![
+                crate::assembly_ast::AssemblyInstructionAST::Cmp {
+                    asm_type: crate::assembly_ast::AssemblyType::Longword,
+                    src: crate::assembly_ast::AssemblyOperandAST::Immediate(2),
+                    dst: crate::assembly_ast::AssemblyOperandAST::Immediate(1),
+                },
+                crate::assembly_ast::AssemblyInstructionAST::Mov {
+                    asm_type: crate::assembly_ast::AssemblyType::Longword,
+                    src: crate::assembly_ast::AssemblyOperandAST::Immediate(0),
+                    dst: crate::assembly_ast::AssemblyOperandAST::Pseudo("tmp.0".to_string()),
+                },
+                crate::assembly_ast::AssemblyInstructionAST::SetCC {
+                    condition: crate::assembly_ast::ConditionCode::L,
+                    operand: crate::assembly_ast::AssemblyOperandAST::Pseudo("tmp.0".to_string()),
+                },
+                crate::assembly_ast::AssemblyInstructionAST::Cmp {
+                    asm_type: crate::assembly_ast::AssemblyType::Longword,
+                    src: crate::assembly_ast::AssemblyOperandAST::Immediate(0),
+                    dst: crate::assembly_ast::AssemblyOperandAST::Pseudo("tmp.0".to_string()),
+                },
+                crate::assembly_ast::AssemblyInstructionAST::JmpCC {
+                    condition: crate::assembly_ast::ConditionCode::NE,
+                    target: "or_true.0".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_generate_assembly_program_ast_function_call() {
+        // SS: arrange
+        let args = (1..=6)
+            .map(|i| crate::tacky_ast::TackyValueAST::Constant(crate::types::Constant::Int(i)))
+            .chain(std::iter::once(crate::tacky_ast::TackyValueAST::Var(
+                "a.0".to_string(),
+            )))
+            .collect();
+        let tacky_ast = crate::tacky_ast::TackyProgramAST {
+            items: vec![crate::tacky_ast::TackyTopLevelAST::Function(
+                crate::tacky_ast::TackyFunctionAST {
+                    name: "main".to_string(),
+                    global: true,
+                    params: vec!["a.0".to_string()],
+                    instructions: vec![crate::tacky_ast::TackyInstructionAST::FunctionCall {
+                        name: "f".to_string(),
+                        args,
+                        dst: crate::tacky_ast::TackyValueAST::Var("tmp.1".to_string()),
+                    }],
+                },
+            )],
+        };
+
+        // SS: act
+        let assembly_ast = generate(
+            tacky_ast,
+            &[
+                ("a.0", crate::types::Type::Int),
+                ("tmp.1", crate::types::Type::Int),
+            ],
+        );
+
+        // SS: assert
+        let register_args = [
+            crate::reg::Register::EDI,
+            crate::reg::Register::ESI,
+            crate::reg::Register::EDX,
+            crate::reg::Register::ECX,
+            crate::reg::Register::R8D,
+            crate::reg::Register::R9D,
+        ]
+        .into_iter()
+        .enumerate()
+        .map(
+            |(i, register)| crate::assembly_ast::AssemblyInstructionAST::Mov {
+                asm_type: crate::assembly_ast::AssemblyType::Longword,
+                src: crate::assembly_ast::AssemblyOperandAST::Immediate(i as i64 + 1),
+                dst: crate::assembly_ast::AssemblyOperandAST::Register(register),
+            },
+        );
+        let expected = vec![
+            crate::assembly_ast::AssemblyInstructionAST::Mov {
+                asm_type: crate::assembly_ast::AssemblyType::Longword,
+                src: crate::assembly_ast::AssemblyOperandAST::Register(crate::reg::Register::EDI),
+                dst: crate::assembly_ast::AssemblyOperandAST::Pseudo("a.0".to_string()),
+            },
+            crate::assembly_ast::AssemblyInstructionAST::AllocateStack(8),
+        ]
+        .into_iter()
+        .chain(register_args)
+        .chain(vec![
+            crate::assembly_ast::AssemblyInstructionAST::Mov {
+                asm_type: crate::assembly_ast::AssemblyType::Longword,
+                src: crate::assembly_ast::AssemblyOperandAST::Pseudo("a.0".to_string()),
+                dst: crate::assembly_ast::AssemblyOperandAST::Register(crate::reg::Register::EAX),
+            },
+            crate::assembly_ast::AssemblyInstructionAST::Push(
+                crate::assembly_ast::AssemblyOperandAST::Register(crate::reg::Register::EAX),
+            ),
+            crate::assembly_ast::AssemblyInstructionAST::Call("f".to_string()),
+            crate::assembly_ast::AssemblyInstructionAST::DeallocateStack(16),
+            crate::assembly_ast::AssemblyInstructionAST::Mov {
+                asm_type: crate::assembly_ast::AssemblyType::Longword,
+                src: crate::assembly_ast::AssemblyOperandAST::Register(crate::reg::Register::EAX),
+                dst: crate::assembly_ast::AssemblyOperandAST::Pseudo("tmp.1".to_string()),
+            },
+        ])
+        .collect::<Vec<_>>();
+        assert_eq!(instructions(&assembly_ast), expected);
+    }
+
+    #[test]
+    fn test_generate_assembly_program_ast_unsigned_division() {
+        // SS: arrange
+        let tacky_ast = crate::tacky_ast::TackyProgramAST {
+            items: vec![crate::tacky_ast::TackyTopLevelAST::Function(
+                crate::tacky_ast::TackyFunctionAST {
+                    name: "main".to_string(),
+                    global: true,
+                    params: vec![],
+                    instructions: vec![crate::tacky_ast::TackyInstructionAST::Binary {
+                        op: crate::tacky_ast::TackyBinaryOperatorAST::Divide,
+                        src1: crate::tacky_ast::TackyValueAST::Var("a.0".to_string()),
+                        src2: crate::tacky_ast::TackyValueAST::Constant(
+                            crate::types::Constant::ULong(3),
+                        ),
+                        dst: crate::tacky_ast::TackyValueAST::Var("tmp.0".to_string()),
+                    }],
+                },
+            )],
+        };
+
+        // SS: act
+        let assembly_ast = generate(
+            tacky_ast,
+            &[
+                ("a.0", crate::types::Type::ULong),
+                ("tmp.0", crate::types::Type::ULong),
+            ],
+        );
+
+        // SS: assert
+        assert_eq!(
+            instructions(&assembly_ast),
+            vec![
+                crate::assembly_ast::AssemblyInstructionAST::Mov {
+                    asm_type: crate::assembly_ast::AssemblyType::Quadword,
+                    src: crate::assembly_ast::AssemblyOperandAST::Pseudo("a.0".to_string()),
+                    dst: crate::assembly_ast::AssemblyOperandAST::Register(
+                        crate::reg::Register::EAX
+                    ),
+                },
+                crate::assembly_ast::AssemblyInstructionAST::Mov {
+                    asm_type: crate::assembly_ast::AssemblyType::Quadword,
+                    src: crate::assembly_ast::AssemblyOperandAST::Immediate(0),
+                    dst: crate::assembly_ast::AssemblyOperandAST::Register(
+                        crate::reg::Register::EDX
+                    ),
+                },
+                crate::assembly_ast::AssemblyInstructionAST::Mov {
+                    asm_type: crate::assembly_ast::AssemblyType::Quadword,
+                    src: crate::assembly_ast::AssemblyOperandAST::Immediate(3),
+                    dst: crate::assembly_ast::AssemblyOperandAST::Register(
+                        crate::reg::Register::R10D
+                    ),
+                },
+                crate::assembly_ast::AssemblyInstructionAST::Div {
+                    asm_type: crate::assembly_ast::AssemblyType::Quadword,
+                    operand: crate::assembly_ast::AssemblyOperandAST::Register(
+                        crate::reg::Register::R10D
+                    ),
+                },
+                crate::assembly_ast::AssemblyInstructionAST::Mov {
+                    asm_type: crate::assembly_ast::AssemblyType::Quadword,
+                    src: crate::assembly_ast::AssemblyOperandAST::Register(
+                        crate::reg::Register::EAX
+                    ),
+                    dst: crate::assembly_ast::AssemblyOperandAST::Pseudo("tmp.0".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_generate_assembly_program_ast_double_constants() {
+        // SS: arrange
+        let tacky_ast = crate::tacky_ast::TackyProgramAST {
+            items: vec![crate::tacky_ast::TackyTopLevelAST::Function(
+                crate::tacky_ast::TackyFunctionAST {
+                    name: "main".to_string(),
+                    global: true,
+                    params: vec![],
+                    instructions: vec![
+                        crate::tacky_ast::TackyInstructionAST::Binary {
+                            op: crate::tacky_ast::TackyBinaryOperatorAST::Add,
+                            src1: crate::tacky_ast::TackyValueAST::Var("d.0".to_string()),
+                            src2: crate::tacky_ast::TackyValueAST::Constant(
+                                crate::types::Constant::Double(1.5),
+                            ),
+                            dst: crate::tacky_ast::TackyValueAST::Var("tmp.1".to_string()),
+                        },
+                        crate::tacky_ast::TackyInstructionAST::Binary {
+                            op: crate::tacky_ast::TackyBinaryOperatorAST::Subtract,
+                            src1: crate::tacky_ast::TackyValueAST::Var("tmp.1".to_string()),
+                            src2: crate::tacky_ast::TackyValueAST::Constant(
+                                crate::types::Constant::Double(1.5),
+                            ),
+                            dst: crate::tacky_ast::TackyValueAST::Var("tmp.1".to_string()),
+                        },
+                        crate::tacky_ast::TackyInstructionAST::Return(
+                            crate::tacky_ast::TackyValueAST::Var("tmp.1".to_string()),
+                        ),
+                    ],
+                },
+            )],
+        };
+
+        // SS: act
+        let assembly_ast = generate(
+            tacky_ast,
+            &[
+                ("d.0", crate::types::Type::Double),
+                ("tmp.1", crate::types::Type::Double),
+            ],
+        );
+
+        // SS: assert
+        let constant =
+            crate::assembly_ast::AssemblyOperandAST::Data("const_double.0".to_string(), 0);
+        let tmp = crate::assembly_ast::AssemblyOperandAST::Pseudo("tmp.1".to_string());
+        assert_eq!(
+            instructions(&assembly_ast),
+            vec![
+                crate::assembly_ast::AssemblyInstructionAST::Mov {
+                    asm_type: crate::assembly_ast::AssemblyType::Double,
+                    src: crate::assembly_ast::AssemblyOperandAST::Pseudo("d.0".to_string()),
+                    dst: tmp.clone(),
+                },
+                crate::assembly_ast::AssemblyInstructionAST::Binary {
+                    op: crate::assembly_ast::AssemblyBinaryOperatorAST::Add,
+                    asm_type: crate::assembly_ast::AssemblyType::Double,
+                    src: constant.clone(),
+                    dst: tmp.clone(),
+                },
+                crate::assembly_ast::AssemblyInstructionAST::Mov {
+                    asm_type: crate::assembly_ast::AssemblyType::Double,
+                    src: tmp.clone(),
+                    dst: tmp.clone(),
+                },
+                crate::assembly_ast::AssemblyInstructionAST::Binary {
+                    op: crate::assembly_ast::AssemblyBinaryOperatorAST::Sub,
+                    asm_type: crate::assembly_ast::AssemblyType::Double,
+                    src: constant,
+                    dst: tmp.clone(),
+                },
+                crate::assembly_ast::AssemblyInstructionAST::Mov {
+                    asm_type: crate::assembly_ast::AssemblyType::Double,
+                    src: tmp,
+                    dst: crate::assembly_ast::AssemblyOperandAST::Register(
+                        crate::reg::Register::XMM0
+                    ),
+                },
+                crate::assembly_ast::AssemblyInstructionAST::Ret,
+            ]
+        );
+        assert_eq!(
+            assembly_ast.items[1],
+            crate::assembly_ast::AssemblyTopLevelAST::StaticConstant {
+                name: "const_double.0".to_string(),
+                alignment: 8,
+                init: crate::types::StaticInit::Value(crate::types::Constant::Double(1.5)),
             }
         );
+        assert_eq!(assembly_ast.items.len(), 2);
+    }
+
+    #[test]
+    fn test_generate_assembly_program_ast_double_arguments() {
+        // SS: arrange
+        let var = |name: &str| crate::tacky_ast::TackyValueAST::Var(name.to_string());
+        let tacky_ast = crate::tacky_ast::TackyProgramAST {
+            items: vec![crate::tacky_ast::TackyTopLevelAST::Function(
+                crate::tacky_ast::TackyFunctionAST {
+                    name: "f".to_string(),
+                    global: true,
+                    params: vec!["x.0".to_string(), "i.1".to_string(), "y.2".to_string()],
+                    instructions: vec![crate::tacky_ast::TackyInstructionAST::FunctionCall {
+                        name: "f".to_string(),
+                        args: vec![var("y.2"), var("i.1"), var("x.0")],
+                        dst: var("tmp.3"),
+                    }],
+                },
+            )],
+        };
+
+        // SS: act
+        let assembly_ast = generate(
+            tacky_ast,
+            &[
+                ("x.0", crate::types::Type::Double),
+                ("i.1", crate::types::Type::Int),
+                ("y.2", crate::types::Type::Double),
+                ("tmp.3", crate::types::Type::Double),
+            ],
+        );
+
+        // SS: assert
+        let mov = |asm_type, src, dst| crate::assembly_ast::AssemblyInstructionAST::Mov {
+            asm_type,
+            src,
+            dst,
+        };
+        let pseudo = |name: &str| crate::assembly_ast::AssemblyOperandAST::Pseudo(name.to_string());
+        let register = crate::assembly_ast::AssemblyOperandAST::Register;
+        let double = crate::assembly_ast::AssemblyType::Double;
+        let longword = crate::assembly_ast::AssemblyType::Longword;
+        assert_eq!(
+            instructions(&assembly_ast),
+            vec![
+                mov(double, register(crate::reg::Register::XMM0), pseudo("x.0")),
+                mov(longword, register(crate::reg::Register::EDI), pseudo("i.1")),
+                mov(double, register(crate::reg::Register::XMM1), pseudo("y.2")),
+                mov(double, pseudo("y.2"), register(crate::reg::Register::XMM0)),
+                mov(longword, pseudo("i.1"), register(crate::reg::Register::EDI)),
+                mov(double, pseudo("x.0"), register(crate::reg::Register::XMM1)),
+                crate::assembly_ast::AssemblyInstructionAST::Call("f".to_string()),
+                mov(
+                    double,
+                    register(crate::reg::Register::XMM0),
+                    pseudo("tmp.3")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_generate_assembly_program_ast_pointer_arithmetic() {
+        // SS: arrange
+        let var = |name: &str| crate::tacky_ast::TackyValueAST::Var(name.to_string());
+        let tacky_ast = crate::tacky_ast::TackyProgramAST {
+            items: vec![crate::tacky_ast::TackyTopLevelAST::Function(
+                crate::tacky_ast::TackyFunctionAST {
+                    name: "main".to_string(),
+                    global: true,
+                    params: vec![],
+                    instructions: vec![
+                        crate::tacky_ast::TackyInstructionAST::AddPtr {
+                            ptr: var("p.0"),
+                            index: crate::tacky_ast::TackyValueAST::Constant(
+                                crate::types::Constant::Long(3),
+                            ),
+                            scale: 4,
+                            dst: var("tmp.0"),
+                        },
+                        crate::tacky_ast::TackyInstructionAST::AddPtr {
+                            ptr: var("p.0"),
+                            index: var("i.1"),
+                            scale: 12,
+                            dst: var("tmp.0"),
+                        },
+                        crate::tacky_ast::TackyInstructionAST::Load {
+                            src_ptr: var("tmp.0"),
+                            dst: var("x.2"),
+                        },
+                    ],
+                },
+            )],
+        };
+
+        // SS: act
+        let pointer = crate::types::Type::Pointer(Box::new(crate::types::Type::Int));
+        let assembly_ast = generate(
+            tacky_ast,
+            &[
+                ("p.0", pointer.clone()),
+                ("i.1", crate::types::Type::Long),
+                ("tmp.0", pointer),
+                ("x.2", crate::types::Type::Int),
+            ],
+        );
+
+        // SS: assert
+        let pseudo = |name: &str| crate::assembly_ast::AssemblyOperandAST::Pseudo(name.to_string());
+        let register = crate::assembly_ast::AssemblyOperandAST::Register;
+        assert_eq!(
+            instructions(&assembly_ast),
+            vec![
+                crate::assembly_ast::AssemblyInstructionAST::Mov {
+                    asm_type: crate::assembly_ast::AssemblyType::Quadword,
+                    src: pseudo("p.0"),
+                    dst: register(crate::reg::Register::EAX),
+                },
+                crate::assembly_ast::AssemblyInstructionAST::Lea {
+                    src: crate::assembly_ast::AssemblyOperandAST::Memory(
+                        crate::reg::Register::EAX,
+                        12
+                    ),
+                    dst: pseudo("tmp.0"),
+                },
+                crate::assembly_ast::AssemblyInstructionAST::Mov {
+                    asm_type: crate::assembly_ast::AssemblyType::Quadword,
+                    src: pseudo("p.0"),
+                    dst: register(crate::reg::Register::EAX),
+                },
+                crate::assembly_ast::AssemblyInstructionAST::Mov {
+                    asm_type: crate::assembly_ast::AssemblyType::Quadword,
+                    src: pseudo("i.1"),
+                    dst: register(crate::reg::Register::EDX),
+                },
+                crate::assembly_ast::AssemblyInstructionAST::Binary {
+                    op: crate::assembly_ast::AssemblyBinaryOperatorAST::Mult,
+                    asm_type: crate::assembly_ast::AssemblyType::Quadword,
+                    src: crate::assembly_ast::AssemblyOperandAST::Immediate(12),
+                    dst: register(crate::reg::Register::EDX),
+                },
+                crate::assembly_ast::AssemblyInstructionAST::Lea {
+                    src: crate::assembly_ast::AssemblyOperandAST::Indexed {
+                        base: crate::reg::Register::EAX,
+                        index: crate::reg::Register::EDX,
+                        scale: 1,
+                    },
+                    dst: pseudo("tmp.0"),
+                },
+                crate::assembly_ast::AssemblyInstructionAST::Mov {
+                    asm_type: crate::assembly_ast::AssemblyType::Quadword,
+                    src: pseudo("tmp.0"),
+                    dst: register(crate::reg::Register::EAX),
+                },
+                crate::assembly_ast::AssemblyInstructionAST::Mov {
+                    asm_type: crate::assembly_ast::AssemblyType::Longword,
+                    src: crate::assembly_ast::AssemblyOperandAST::Memory(
+                        crate::reg::Register::EAX,
+                        0
+                    ),
+                    dst: pseudo("x.2"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_generate_assembly_program_ast_structure_arguments() {
+        // SS: arrange
+        let mut types = crate::type_table::TypeTable::new();
+        types.insert(
+            "s.0".to_string(),
+            crate::type_table::StructEntry::new(
+                vec![
+                    ("d".to_string(), crate::types::Type::Double),
+                    ("i".to_string(), crate::types::Type::Int),
+                ],
+                false,
+                &types,
+            ),
+        );
+        types.insert(
+            "t.1".to_string(),
+            crate::type_table::StructEntry::new(
+                vec![(
+                    "c".to_string(),
+                    crate::types::Type::Array {
+                        element: Box::new(crate::types::Type::Char),
+                        size: 3,
+                    },
+                )],
+                false,
+                &types,
+            ),
+        );
+        let tacky_ast = crate::tacky_ast::TackyProgramAST {
+            items: vec![crate::tacky_ast::TackyTopLevelAST::Function(
+                crate::tacky_ast::TackyFunctionAST {
+                    name: "main".to_string(),
+                    global: true,
+                    params: vec![],
+                    instructions: vec![crate::tacky_ast::TackyInstructionAST::FunctionCall {
+                        name: "f".to_string(),
+                        args: vec![
+                            crate::tacky_ast::TackyValueAST::Var("x.2".to_string()),
+                            crate::tacky_ast::TackyValueAST::Var("y.3".to_string()),
+                        ],
+                        dst: crate::tacky_ast::TackyValueAST::Var("tmp.4".to_string()),
+                    }],
+                },
+            )],
+        };
+
+        // SS: act
+        let assembly_ast = generate_with_types(
+            tacky_ast,
+            &[
+                ("x.2", crate::types::Type::Structure("s.0".to_string())),
+                ("y.3", crate::types::Type::Structure("t.1".to_string())),
+                ("tmp.4", crate::types::Type::Int),
+            ],
+            &types,
+        );
+
+        // SS: assert
+        // the first eightbyte of x only holds a double and goes in xmm0, the second one in
+        // rdi. y is three bytes, which are put together in rsi one at a time.
+        let x =
+            |offset| crate::assembly_ast::AssemblyOperandAST::PseudoMem("x.2".to_string(), offset);
+        let y =
+            |offset| crate::assembly_ast::AssemblyOperandAST::PseudoMem("y.3".to_string(), offset);
+        let register = crate::assembly_ast::AssemblyOperandAST::Register;
+        let mov = |asm_type, src, dst| crate::assembly_ast::AssemblyInstructionAST::Mov {
+            asm_type,
+            src,
+            dst,
+        };
+        let shl = crate::assembly_ast::AssemblyInstructionAST::Binary {
+            op: crate::assembly_ast::AssemblyBinaryOperatorAST::Shl,
+            asm_type: crate::assembly_ast::AssemblyType::Quadword,
+            src: crate::assembly_ast::AssemblyOperandAST::Immediate(8),
+            dst: register(crate::reg::Register::ESI),
+        };
+        assert_eq!(
+            instructions(&assembly_ast),
+            vec![
+                mov(
+                    crate::assembly_ast::AssemblyType::Double,
+                    x(0),
+                    register(crate::reg::Register::XMM0)
+                ),
+                mov(
+                    crate::assembly_ast::AssemblyType::Quadword,
+                    x(8),
+                    register(crate::reg::Register::EDI)
+                ),
+                mov(
+                    crate::assembly_ast::AssemblyType::Byte,
+                    y(2),
+                    register(crate::reg::Register::ESI)
+                ),
+                shl.clone(),
+                mov(
+                    crate::assembly_ast::AssemblyType::Byte,
+                    y(1),
+                    register(crate::reg::Register::ESI)
+                ),
+                shl,
+                mov(
+                    crate::assembly_ast::AssemblyType::Byte,
+                    y(0),
+                    register(crate::reg::Register::ESI)
+                ),
+                crate::assembly_ast::AssemblyInstructionAST::Call("f".to_string()),
+                mov(
+                    crate::assembly_ast::AssemblyType::Longword,
+                    register(crate::reg::Register::EAX),
+                    crate::assembly_ast::AssemblyOperandAST::Pseudo("tmp.4".to_string())
+                ),
+            ]
+        );
     }
 }
@@ -0,0 +1,311 @@
+use crate::name_generator::NameGenerator;
+use crate::parse_ast::{
+    BlockAST, BlockItemAST, ExternalDeclarationAST, FunctionAST, ProgramAST, StmtAST,
+};
+use std::collections::{HashMap, HashSet};
+
+// SS: checks the labels of every function, each one has to be defined exactly once in the
+// function and every goto has to name one of them. Labels are then renamed to
+// function.label.N, the compiler's own labels never contain more than one dot so the two
+// can not collide in the IR or the assembly.
+pub fn validate_labels(
+    ast: ProgramAST,
+    name_generator: &mut NameGenerator,
+) -> Result<ProgramAST, String> {
+    let declarations = ast
+        .declarations
+        .into_iter()
+        .map(|declaration| match declaration {
+            ExternalDeclarationAST::Function(function) => {
+                validate_function(function, name_generator).map(ExternalDeclarationAST::Function)
+            }
+            declaration @ (ExternalDeclarationAST::Variable(_)
+            | ExternalDeclarationAST::Struct(_)) => Ok(declaration),
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    Ok(ProgramAST { declarations })
+}
+
+fn validate_function(
+    function: FunctionAST,
+    name_generator: &mut NameGenerator,
+) -> Result<FunctionAST, String> {
+    let mut validator = LabelValidator {
+        name_generator,
+        function: function.name.clone(),
+        unique_names: HashMap::new(),
+        defined: HashSet::new(),
+        gotos: vec![],
+    };
+    let body = function
+        .body
+        .map(|body| validator.validate_block(body))
+        .transpose()?;
+
+    // SS: a goto may jump forward, so undefined labels are only known at the end
+    if let Some((label, line)) = validator
+        .gotos
+        .iter()
+        .find(|(label, _)| !validator.defined.contains(label))
+    {
+        return Err(format!(
+            "Line {}: Semantic error: Use of undefined label \"{}\"",
+            line, label
+        ));
+    }
+    Ok(FunctionAST { body, ..function })
+}
+
+struct LabelValidator<'a> {
+    name_generator: &'a mut NameGenerator,
+    function: String,
+
+    // SS: the unique name of every label seen so far, defined or only jumped to
+    unique_names: HashMap<String, String>,
+    defined: HashSet<String>,
+
+    // SS: the label and line of every goto in source order
+    gotos: Vec<(String, usize)>,
+}
+
+impl LabelValidator<'_> {
+    fn unique_name(&mut self, label: &str) -> String {
+        if let Some(unique_name) = self.unique_names.get(label) {
+            return unique_name.clone();
+        }
+        let unique_name = self
+            .name_generator
+            .make_unique(&format!("{}.{}", self.function, label));
+        self.unique_names
+            .insert(label.to_string(), unique_name.clone());
+        unique_name
+    }
+
+    fn validate_block(&mut self, block: BlockAST) -> Result<BlockAST, String> {
+        let items = block
+            .items
+            .into_iter()
+            .map(|block_item| match block_item {
+                BlockItemAST::Statement(stmt) => {
+                    Ok(BlockItemAST::Statement(self.validate_stmt(stmt)?))
+                }
+                declaration @ (BlockItemAST::Declaration(_)
+                | BlockItemAST::FunctionDeclaration(_)
+                | BlockItemAST::StructDeclaration(_)) => Ok(declaration),
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(BlockAST { items })
+    }
+
+    fn validate_stmt(&mut self, stmt: StmtAST) -> Result<StmtAST, String> {
+        match stmt {
+            StmtAST::Goto { label, line } => {
+                let unique_name = self.unique_name(&label);
+                self.gotos.push((label, line));
+                Ok(StmtAST::Goto {
+                    label: unique_name,
+                    line,
+                })
+            }
+            StmtAST::Labeled { label, body, line } => {
+                if !self.defined.insert(label.clone()) {
+                    return Err(format!(
+                        "Line {}: Semantic error: Duplicate label \"{}\"",
+                        line, label
+                    ));
+                }
+                Ok(StmtAST::Labeled {
+                    label: self.unique_name(&label),
+                    body: Box::new(self.validate_stmt(*body)?),
+                    line,
+                })
+            }
+            StmtAST::If {
+                condition,
+                then_stmt,
+                else_stmt,
+            } => Ok(StmtAST::If {
+                condition,
+                then_stmt: Box::new(self.validate_stmt(*then_stmt)?),
+                else_stmt: else_stmt
+                    .map(|stmt| self.validate_stmt(*stmt).map(Box::new))
+                    .transpose()?,
+            }),
+            StmtAST::Compound(block) => Ok(StmtAST::Compound(self.validate_block(block)?)),
+            StmtAST::While {
+                condition,
+                body,
+                label,
+            } => Ok(StmtAST::While {
+                condition,
+                body: Box::new(self.validate_stmt(*body)?),
+                label,
+            }),
+            StmtAST::DoWhile {
+                body,
+                condition,
+                label,
+            } => Ok(StmtAST::DoWhile {
+                body: Box::new(self.validate_stmt(*body)?),
+                condition,
+                label,
+            }),
+            StmtAST::For {
+                init,
+                condition,
+                post,
+                body,
+                label,
+            } => Ok(StmtAST::For {
+                init,
+                condition,
+                post,
+                body: Box::new(self.validate_stmt(*body)?),
+                label,
+            }),
+            StmtAST::Switch {
+                condition,
+                body,
+                label,
+                cases,
+            } => Ok(StmtAST::Switch {
+                condition,
+                body: Box::new(self.validate_stmt(*body)?),
+                label,
+                cases,
+            }),
+            StmtAST::Case {
+                value,
+                body,
+                label,
+                line,
+            } => Ok(StmtAST::Case {
+                value,
+                body: Box::new(self.validate_stmt(*body)?),
+                label,
+                line,
+            }),
+            StmtAST::Default { body, label, line } => Ok(StmtAST::Default {
+                body: Box::new(self.validate_stmt(*body)?),
+                label,
+                line,
+            }),
+            stmt @ (StmtAST::Return(_)
+            | StmtAST::Expression(_)
+            | StmtAST::Break { .. }
+            | StmtAST::Continue { .. }
+            | StmtAST::Null) => Ok(stmt),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::label_validation::validate_labels;
+    use crate::lexer::Lexer;
+    use crate::name_generator::NameGenerator;
+    use crate::parse_ast::{BlockItemAST, ExprAST, ExternalDeclarationAST, ProgramAST, StmtAST};
+    use crate::parser::Parser;
+    use crate::types::Constant;
+
+    fn validate(input: &str) -> Result<ProgramAST, String> {
+        let lexer = Lexer::new(input.to_string());
+        let mut parser = Parser::new(lexer);
+        let ast = parser.parse().unwrap();
+        validate_labels(ast, &mut NameGenerator::new())
+    }
+
+    fn body_items(ast: &ProgramAST, index: usize) -> Vec<BlockItemAST> {
+        match &ast.declarations[index] {
+            ExternalDeclarationAST::Function(function) => {
+                function.body.as_ref().unwrap().items.clone()
+            }
+            declaration => panic!("expected a function, found {:?}", declaration),
+        }
+    }
+
+    #[test]
+    fn test_validate_labels() {
+        // SS: arrange
+        let input = r"int foo(void) {
+                            goto end;
+                        end:
+                            return 1;
+                    }
+                    int main(void) {
+                        end:
+                            goto end;
+                    }";
+
+        // SS: act
+        let ast = validate(input).unwrap();
+
+        // SS: assert
+        assert_eq!(
+            body_items(&ast, 0),
+            vec![
+                BlockItemAST::Statement(StmtAST::Goto {
+                    label: "foo.end.0".to_string(),
+                    line: 2
+                }),
+                BlockItemAST::Statement(StmtAST::Labeled {
+                    label: "foo.end.0".to_string(),
+                    body: Box::new(StmtAST::Return(ExprAST::Constant(Constant::Int(1)))),
+                    line: 3
+                }),
+            ]
+        );
+        assert_eq!(
+            body_items(&ast, 1),
+            vec![BlockItemAST::Statement(StmtAST::Labeled {
+                label: "main.end.1".to_string(),
+                body: Box::new(StmtAST::Goto {
+                    label: "main.end.1".to_string(),
+                    line: 8
+                }),
+                line: 7
+            })]
+        );
+    }
+
+    #[test]
+    fn test_validate_labels_undefined() {
+        // SS: arrange
+        let input = r"int main(void) {
+                            while (1) {
+                                goto done;
+                            }
+                    }
+                    int done(void) { return 0; }";
+
+        // SS: act
+        let ast = validate(input);
+
+        // SS: assert
+        assert_eq!(
+            ast,
+            Err(r#"Line 3: Semantic error: Use of undefined label "done""#.to_string())
+        );
+    }
+
+    #[test]
+    fn test_validate_labels_duplicate() {
+        // SS: arrange
+        let input = r"int main(void) {
+                        a:
+                            if (1) {
+                        a:
+                                ;
+                            }
+                    }";
+
+        // SS: act
+        let ast = validate(input);
+
+        // SS: assert
+        assert_eq!(
+            ast,
+            Err(r#"Line 4: Semantic error: Duplicate label "a""#.to_string())
+        );
+    }
+}
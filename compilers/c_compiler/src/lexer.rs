@@ -1,41 +1,111 @@
-use crate::tokens::Tokens;
+use crate::diagnostic::{Diagnostic, LineMap, Span};
+use crate::tokens::{KEYWORDS, PUNCTUATORS, Tokens};
 use regex::Regex;
+use std::rc::Rc;
 use std::sync::LazyLock;
 
-// SS: master regex
+// SS: master regex, keywords are matched as identifiers and looked up afterwards
 static MASTER_RE: LazyLock<Regex> = LazyLock::new(|| {
-    Regex::new(concat!(
-        r"(?P<int>^int\b)|",
-        r"(?P<void>^void\b)|",
-        r"(?P<return>^return\b)|",
-        r"(?P<open_paren>^\()|",
-        r"(?P<close_paren>^\))|",
-        r"(?P<open_brace>^\{)|",
-        r"(?P<close_brace>^\})|",
-        r"(?P<semicolon>^;)|",
-        r"(?P<identifier>^[a-zA-Z_]\w*\b)|",
-        r"(?P<constant>^[0-9]+\b)"
+    // SS: try the longest punctuators first so that e.g. <<= is not lexed as < <=
+    let mut punctuators = PUNCTUATORS
+        .iter()
+        .map(|(spelling, _)| *spelling)
+        .collect::<Vec<_>>();
+    punctuators.sort_by_key(|spelling| std::cmp::Reverse(spelling.len()));
+    let punctuators = punctuators
+        .into_iter()
+        .map(regex::escape)
+        .collect::<Vec<_>>()
+        .join("|");
+
+    Regex::new(&format!(
+        concat!(
+            r"(?P<identifier>^[a-zA-Z_][a-zA-Z0-9_]*)|",
+            r"(?P<double>^(?:(?:[0-9]*\.[0-9]+|[0-9]+\.)(?:[eE][+-]?[0-9]+)?|[0-9]+[eE][+-]?[0-9]+))|",
+            r"(?P<constant>^(?:0[xX][0-9a-fA-F]+|0[0-7]*|[1-9][0-9]*)",
+            r"(?P<suffix>[uU](?:ll|LL|[lL])?|(?:ll|LL|[lL])[uU]?)?\b)|",
+            r"(?P<invalid_constant>^[0-9][0-9a-zA-Z_]*)|",
+            r"(?P<char>^'(?:[^'\\\n]|\\.)*')|",
+            r#"(?P<string>^"(?:[^"\\\n]|\\.)*")|"#,
+            r#"(?P<unterminated>^['"])|"#,
+            r"(?P<punctuator>^(?:{}))"
+        ),
+        punctuators
     ))
     .unwrap()
 });
 
+// SS: a line marker the preprocessor leaves where its output continues at another line or in
+// another file, i.e. # 12 "header.h"
+static LINE_MARKER_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"^#[ \t]*([0-9]+)[ \t]+"((?:[^"\\\n]|\\.)*)"[^\n]*"#).unwrap());
+
 pub(crate) struct Lexer {
     input: String,
     position: usize,
-    pub current_line: usize,
+    current_line: usize,
+    file_name: Rc<str>,
+
+    // SS: the line in the input itself, which line markers do not change, and the files and
+    // lines the markers seen so far map it to
+    current_input_line: usize,
+    line_map: LineMap,
+
+    // SS: byte offset at which the current line starts, used to compute columns
+    line_start: usize,
+
+    // SS: where the most recently returned token starts
+    token_span: Span,
+    token_input_line: usize,
 }
 
 impl Lexer {
+    #[cfg(test)]
     pub fn new(input: String) -> Self {
+        Self::with_file_name(input, "<input>")
+    }
+
+    pub fn with_file_name(input: String, file_name: &str) -> Self {
+        let file_name: Rc<str> = file_name.into();
         Self {
             input,
             position: 0,
             current_line: 1,
+            line_start: 0,
+            token_span: Span {
+                file: file_name.clone(),
+                line: 1,
+                column: 1,
+            },
+            token_input_line: 1,
+            current_input_line: 1,
+            line_map: LineMap::new(file_name.clone()),
+            file_name,
         }
     }
 
-    pub fn next_token(&mut self) -> Result<Tokens, String> {
+    // SS: the location of the token returned by the last call to next_token
+    pub fn span(&self) -> Span {
+        self.token_span.clone()
+    }
+
+    // SS: the line of the input the most recently returned token is on
+    pub fn input_line(&self) -> usize {
+        self.token_input_line
+    }
+
+    pub fn line_map(&self) -> &LineMap {
+        &self.line_map
+    }
+
+    pub fn next_token(&mut self) -> Result<Tokens, Diagnostic> {
         self.skip_whitespace();
+        self.token_span = Span {
+            file: self.file_name.clone(),
+            line: self.current_line,
+            column: self.position - self.line_start + 1,
+        };
+        self.token_input_line = self.current_input_line;
 
         if self.position >= self.input.len() {
             return Ok(Tokens::EOF);
@@ -43,50 +113,220 @@ impl Lexer {
 
         let remaining = &self.input[self.position..];
 
+        if remaining.starts_with("/*") {
+            // SS: only an unterminated comment is left here, it runs to the end of the input
+            self.position = self.input.len();
+            return Err(self.error("Unterminated comment".to_string()));
+        }
+
         if let Some(caps) = MASTER_RE.captures(remaining) {
+            let mat = caps.get(0).unwrap();
+            self.position += mat.end();
+            let text = mat.as_str();
+
             // Find which named group matched
-            if caps.name("int").is_some() {
-                self.position += 3;
-                return Ok(Tokens::Int);
-            } else if caps.name("void").is_some() {
-                self.position += 4;
-                return Ok(Tokens::Void);
-            } else if caps.name("return").is_some() {
-                self.position += 6;
-                return Ok(Tokens::Return);
-            } else if let Some(mat) = caps.name("identifier") {
-                self.position += mat.end();
-                return Ok(Tokens::Identifier(mat.as_str().to_string()));
-            } else if let Some(mat) = caps.name("constant") {
-                let val = mat.as_str().parse::<i64>().unwrap();
-                self.position += mat.end();
-                return Ok(Tokens::Constant(val));
+            if caps.name("identifier").is_some() {
+                return Ok(KEYWORDS
+                    .iter()
+                    .find(|(spelling, _)| *spelling == text)
+                    .map_or_else(
+                        || Tokens::Identifier(text.to_string()),
+                        |(_, token)| token.clone(),
+                    ));
+            } else if caps.name("double").is_some() {
+                return self.double_constant(text.to_string());
+            } else if caps.name("constant").is_some() {
+                let suffix = caps.name("suffix").map_or("", |suffix| suffix.as_str());
+                return self.integer_constant(&text[..text.len() - suffix.len()], suffix);
+            } else if caps.name("invalid_constant").is_some() {
+                return Err(self.error(format!("Invalid integer constant {:?}", text)));
+            } else if caps.name("char").is_some() {
+                let bytes = self.unescape(&text[1..text.len() - 1])?;
+                return match bytes[..] {
+                    // SS: char is signed, so the value is sign extended to int
+                    [byte] => Ok(Tokens::CharConstant(byte as i8 as i64)),
+                    [] => Err(self.error("Empty character constant".to_string())),
+                    _ => Err(self.error("Multi-character character constant".to_string())),
+                };
+            } else if caps.name("string").is_some() {
+                return Ok(Tokens::StringLiteral(
+                    self.unescape(&text[1..text.len() - 1])?,
+                ));
+            } else if caps.name("unterminated").is_some() {
+                // SS: skip the rest of the line rather than lexing the contents as tokens
+                self.position += remaining.find('\n').unwrap_or(remaining.len()) - 1;
+                return Err(self.error(format!("Missing terminating {} character", text)));
             }
 
             // SS: final catch-all for punctuation
-            let mat = caps.get(0).unwrap();
-            self.position += mat.end();
+            return Ok(PUNCTUATORS
+                .iter()
+                .find(|(spelling, _)| *spelling == text)
+                .map(|(_, token)| token.clone())
+                .unwrap());
+        }
 
-            return match mat.as_str() {
-                "(" => Ok(Tokens::OpenParen),
-                ")" => Ok(Tokens::CloseParen),
-                "{" => Ok(Tokens::OpenBrace),
-                "}" => Ok(Tokens::CloseBrace),
-                ";" => Ok(Tokens::Semicolon),
-                _ => unreachable!(),
-            };
+        // SS: skip the offending character so that the parser can recover and continue
+        self.position += remaining.chars().next().map_or(1, char::len_utf8);
+        Err(self.error("Unexpected character".to_string()))
+    }
+
+    fn error(&self, message: String) -> Diagnostic {
+        Diagnostic::error(self.span(), message)
+    }
+
+    // SS: the regex cannot check what follows a floating constant, a letter, digit or dot
+    // right after it means the constant is malformed, e.g. it has a suffix like 1.0f. The
+    // whole malformed constant is skipped.
+    fn double_constant(&mut self, text: String) -> Result<Tokens, Diagnostic> {
+        let rest = &self.input[self.position..];
+        let invalid_len = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '.'))
+            .unwrap_or(rest.len());
+        if invalid_len > 0 {
+            let text = format!("{}{}", text, &rest[..invalid_len]);
+            self.position += invalid_len;
+            return Err(self.error(format!("Invalid floating constant {:?}", text)));
         }
 
-        Err(format!("Line {}: Unexpected character", self.current_line))
+        // SS: Rust parses the same decimal syntax and rounds to nearest like C does
+        Ok(Tokens::DoubleConstant(text.parse().unwrap()))
+    }
+
+    // SS: decimal constants that do not fit in a signed long are an error. Octal and hex
+    // ones become unsigned when they only fit in the unsigned type of the same size, which
+    // the parser needs to know to give them the right type.
+    fn integer_constant(&self, digits: &str, suffix: &str) -> Result<Tokens, Diagnostic> {
+        let (digits, radix) = if let Some(hex) = digits
+            .strip_prefix("0x")
+            .or_else(|| digits.strip_prefix("0X"))
+        {
+            (hex, 16)
+        } else if digits.len() > 1 && digits.starts_with('0') {
+            (&digits[1..], 8)
+        } else {
+            (digits, 10)
+        };
+
+        let too_large = || self.error("Integer constant is too large".to_string());
+        let val = u64::from_str_radix(digits, radix).map_err(|_| too_large())?;
+        let suffix = suffix.to_ascii_lowercase();
+        match (suffix.contains('u'), suffix.contains('l')) {
+            (true, false) => Ok(Tokens::UnsignedConstant(val)),
+            (true, true) => Ok(Tokens::UnsignedLongConstant(val)),
+            (false, long) => match i64::try_from(val) {
+                Ok(val) if long => Ok(Tokens::LongConstant(val)),
+                Ok(val) if radix != 10 && i32::try_from(val).is_err() && val <= u32::MAX as i64 => {
+                    Ok(Tokens::UnsignedConstant(val as u64))
+                }
+                Ok(val) => Ok(Tokens::Constant(val)),
+                Err(_) if radix != 10 => Ok(Tokens::UnsignedLongConstant(val)),
+                Err(_) => Err(too_large()),
+            },
+        }
+    }
+
+    // SS: replaces the escape sequences in the body of a character constant or string literal
+    fn unescape(&self, body: &str) -> Result<Vec<u8>, Diagnostic> {
+        let mut result = vec![];
+        let mut bytes = body.bytes().peekable();
+        while let Some(byte) = bytes.next() {
+            if byte != b'\\' {
+                result.push(byte);
+                continue;
+            }
+
+            let escaped = bytes.next().unwrap();
+            let byte = match escaped {
+                b'\'' | b'"' | b'?' | b'\\' => escaped,
+                b'a' => 0x07,
+                b'b' => 0x08,
+                b'f' => 0x0c,
+                b'n' => b'\n',
+                b'r' => b'\r',
+                b't' => b'\t',
+                b'v' => 0x0b,
+                b'0'..=b'7' => {
+                    // SS: up to three octal digits
+                    let mut val = (escaped - b'0') as u32;
+                    for _ in 0..2 {
+                        match bytes.peek() {
+                            Some(digit @ b'0'..=b'7') => {
+                                val = val * 8 + (digit - b'0') as u32;
+                                bytes.next();
+                            }
+                            _ => break,
+                        }
+                    }
+                    u8::try_from(val)
+                        .map_err(|_| self.error("Octal escape sequence out of range".to_string()))?
+                }
+                b'x' => {
+                    // SS: as many hex digits as follow
+                    let mut val: u32 = 0;
+                    let mut digits = 0;
+                    while let Some(digit) = bytes.peek().and_then(|c| (*c as char).to_digit(16)) {
+                        val = val.saturating_mul(16).saturating_add(digit);
+                        digits += 1;
+                        bytes.next();
+                    }
+                    if digits == 0 {
+                        return Err(self.error("\\x used with no following hex digits".to_string()));
+                    }
+                    u8::try_from(val)
+                        .map_err(|_| self.error("Hex escape sequence out of range".to_string()))?
+                }
+                _ => {
+                    return Err(
+                        self.error(format!("Unknown escape sequence \\{}", escaped as char))
+                    );
+                }
+            };
+            result.push(byte);
+        }
+        Ok(result)
     }
 
     fn skip_whitespace(&mut self) {
-        while let Some(c) = self.input[self.position..].chars().next() {
-            if c.is_whitespace() {
+        loop {
+            let remaining = &self.input[self.position..];
+            if let Some(c) = remaining.chars().next()
+                && c.is_whitespace()
+            {
+                self.position += c.len_utf8();
                 if c == '\n' {
                     self.current_line += 1;
+                    self.current_input_line += 1;
+                    self.line_start = self.position;
                 }
-                self.position += c.len_utf8();
+            } else if remaining.starts_with("//") {
+                // SS: line comment, the newline itself is consumed as whitespace
+                let end = remaining.find('\n').unwrap_or(remaining.len());
+                self.position += end;
+            } else if self.position == self.line_start
+                && let Some(caps) = LINE_MARKER_RE.captures(remaining)
+            {
+                // SS: the line after the marker is the one it names, the newline ending the
+                // marker counts towards it
+                let line = caps[1].parse::<usize>().unwrap_or(1);
+                self.current_line = line.saturating_sub(1);
+                self.file_name = caps[2].replace("\\\"", "\"").replace("\\\\", "\\").into();
+                self.line_map
+                    .add_marker(self.current_input_line + 1, self.file_name.clone(), line);
+                self.position += caps[0].len();
+            } else if let Some(idx) = remaining
+                .strip_prefix("/*")
+                .and_then(|body| body.find("*/"))
+            {
+                // SS: block comment, an unterminated one is left for next_token to report
+                let end = idx + 4;
+                let comment = &remaining[..end];
+                self.current_line += comment.matches('\n').count();
+                self.current_input_line += comment.matches('\n').count();
+                if let Some(idx) = comment.rfind('\n') {
+                    self.line_start = self.position + idx + 1;
+                }
+                self.position += end;
             } else {
                 break;
             }
@@ -97,7 +337,31 @@ impl Lexer {
 #[cfg(test)]
 mod tests {
     use crate::lexer::Lexer;
-    use crate::tokens::Tokens;
+    use crate::tokens::{KEYWORDS, PUNCTUATORS, Tokens};
+    use std::fs;
+    use std::path::Path;
+
+    // SS: lexes the whole input, along with the line and column of each token
+    fn lex_all(input: &str) -> Vec<(Tokens, usize, usize)> {
+        let mut lexer = Lexer::new(input.to_string());
+        let mut tokens = vec![];
+        loop {
+            let token = lexer.next_token().unwrap();
+            if token == Tokens::EOF {
+                return tokens;
+            }
+            let span = lexer.span();
+            tokens.push((token, span.line, span.column));
+        }
+    }
+
+    // SS: lexes the input, expecting exactly one token
+    fn lex_one(input: &str) -> Result<Tokens, String> {
+        let mut lexer = Lexer::new(input.to_string());
+        let token = lexer.next_token().map_err(|err| err.message)?;
+        assert_eq!(lexer.next_token(), Ok(Tokens::EOF), "{}", input);
+        Ok(token)
+    }
 
     #[test]
     fn test_lexer() {
@@ -126,4 +390,577 @@ mod tests {
         assert_eq!(lexer.next_token().unwrap(), Tokens::CloseBrace);
         assert_eq!(lexer.next_token().unwrap(), Tokens::EOF);
     }
+
+    #[test]
+    fn test_lexer_unary_operators() {
+        // SS: arrange
+        let input = r"return ~-!--2;".to_string();
+
+        // SS: act
+        let mut lexer = Lexer::new(input);
+
+        // SS: assert
+        assert_eq!(lexer.next_token().unwrap(), Tokens::Return);
+        assert_eq!(lexer.next_token().unwrap(), Tokens::Tilde);
+        assert_eq!(lexer.next_token().unwrap(), Tokens::Minus);
+        assert_eq!(lexer.next_token().unwrap(), Tokens::Bang);
+        assert_eq!(lexer.next_token().unwrap(), Tokens::Decrement);
+        assert_eq!(lexer.next_token().unwrap(), Tokens::Constant(2));
+        assert_eq!(lexer.next_token().unwrap(), Tokens::Semicolon);
+        assert_eq!(lexer.next_token().unwrap(), Tokens::EOF);
+    }
+
+    #[test]
+    fn test_lexer_binary_operators() {
+        // SS: arrange
+        let input = r"1+2*3/4%5-6".to_string();
+
+        // SS: act
+        let mut lexer = Lexer::new(input);
+
+        // SS: assert
+        assert_eq!(lexer.next_token().unwrap(), Tokens::Constant(1));
+        assert_eq!(lexer.next_token().unwrap(), Tokens::Plus);
+        assert_eq!(lexer.next_token().unwrap(), Tokens::Constant(2));
+        assert_eq!(lexer.next_token().unwrap(), Tokens::Star);
+        assert_eq!(lexer.next_token().unwrap(), Tokens::Constant(3));
+        assert_eq!(lexer.next_token().unwrap(), Tokens::Slash);
+        assert_eq!(lexer.next_token().unwrap(), Tokens::Constant(4));
+        assert_eq!(lexer.next_token().unwrap(), Tokens::Percent);
+        assert_eq!(lexer.next_token().unwrap(), Tokens::Constant(5));
+        assert_eq!(lexer.next_token().unwrap(), Tokens::Minus);
+        assert_eq!(lexer.next_token().unwrap(), Tokens::Constant(6));
+        assert_eq!(lexer.next_token().unwrap(), Tokens::EOF);
+    }
+
+    #[test]
+    fn test_lexer_relational_operators() {
+        // SS: arrange
+        let input = r"!a != b && c<=d || e>=f == g<h>i".to_string();
+
+        // SS: act
+        let mut lexer = Lexer::new(input);
+
+        // SS: assert
+        let identifier = |name: &str| Tokens::Identifier(name.to_string());
+        assert_eq!(lexer.next_token().unwrap(), Tokens::Bang);
+        assert_eq!(lexer.next_token().unwrap(), identifier("a"));
+        assert_eq!(lexer.next_token().unwrap(), Tokens::BangEqual);
+        assert_eq!(lexer.next_token().unwrap(), identifier("b"));
+        assert_eq!(lexer.next_token().unwrap(), Tokens::AmpersandAmpersand);
+        assert_eq!(lexer.next_token().unwrap(), identifier("c"));
+        assert_eq!(lexer.next_token().unwrap(), Tokens::LessEqual);
+        assert_eq!(lexer.next_token().unwrap(), identifier("d"));
+        assert_eq!(lexer.next_token().unwrap(), Tokens::PipePipe);
+        assert_eq!(lexer.next_token().unwrap(), identifier("e"));
+        assert_eq!(lexer.next_token().unwrap(), Tokens::GreaterEqual);
+        assert_eq!(lexer.next_token().unwrap(), identifier("f"));
+        assert_eq!(lexer.next_token().unwrap(), Tokens::EqualEqual);
+        assert_eq!(lexer.next_token().unwrap(), identifier("g"));
+        assert_eq!(lexer.next_token().unwrap(), Tokens::Less);
+        assert_eq!(lexer.next_token().unwrap(), identifier("h"));
+        assert_eq!(lexer.next_token().unwrap(), Tokens::Greater);
+        assert_eq!(lexer.next_token().unwrap(), identifier("i"));
+        assert_eq!(lexer.next_token().unwrap(), Tokens::EOF);
+    }
+
+    #[test]
+    fn test_lexer_assignment_operators() {
+        // SS: arrange
+        let input = r"a = b += c -= d *= e /= f %= g++ + --h == i".to_string();
+
+        // SS: act
+        let mut lexer = Lexer::new(input);
+
+        // SS: assert
+        let identifier = |name: &str| Tokens::Identifier(name.to_string());
+        assert_eq!(lexer.next_token().unwrap(), identifier("a"));
+        assert_eq!(lexer.next_token().unwrap(), Tokens::Equal);
+        assert_eq!(lexer.next_token().unwrap(), identifier("b"));
+        assert_eq!(lexer.next_token().unwrap(), Tokens::PlusEqual);
+        assert_eq!(lexer.next_token().unwrap(), identifier("c"));
+        assert_eq!(lexer.next_token().unwrap(), Tokens::MinusEqual);
+        assert_eq!(lexer.next_token().unwrap(), identifier("d"));
+        assert_eq!(lexer.next_token().unwrap(), Tokens::StarEqual);
+        assert_eq!(lexer.next_token().unwrap(), identifier("e"));
+        assert_eq!(lexer.next_token().unwrap(), Tokens::SlashEqual);
+        assert_eq!(lexer.next_token().unwrap(), identifier("f"));
+        assert_eq!(lexer.next_token().unwrap(), Tokens::PercentEqual);
+        assert_eq!(lexer.next_token().unwrap(), identifier("g"));
+        assert_eq!(lexer.next_token().unwrap(), Tokens::Increment);
+        assert_eq!(lexer.next_token().unwrap(), Tokens::Plus);
+        assert_eq!(lexer.next_token().unwrap(), Tokens::Decrement);
+        assert_eq!(lexer.next_token().unwrap(), identifier("h"));
+        assert_eq!(lexer.next_token().unwrap(), Tokens::EqualEqual);
+        assert_eq!(lexer.next_token().unwrap(), identifier("i"));
+        assert_eq!(lexer.next_token().unwrap(), Tokens::EOF);
+    }
+
+    #[test]
+    fn test_lexer_if_else() {
+        // SS: arrange
+        let input = r"if (a) b; else c ? d : elsewhere;".to_string();
+
+        // SS: act
+        let mut lexer = Lexer::new(input);
+
+        // SS: assert
+        let identifier = |name: &str| Tokens::Identifier(name.to_string());
+        assert_eq!(lexer.next_token().unwrap(), Tokens::If);
+        assert_eq!(lexer.next_token().unwrap(), Tokens::OpenParen);
+        assert_eq!(lexer.next_token().unwrap(), identifier("a"));
+        assert_eq!(lexer.next_token().unwrap(), Tokens::CloseParen);
+        assert_eq!(lexer.next_token().unwrap(), identifier("b"));
+        assert_eq!(lexer.next_token().unwrap(), Tokens::Semicolon);
+        assert_eq!(lexer.next_token().unwrap(), Tokens::Else);
+        assert_eq!(lexer.next_token().unwrap(), identifier("c"));
+        assert_eq!(lexer.next_token().unwrap(), Tokens::Question);
+        assert_eq!(lexer.next_token().unwrap(), identifier("d"));
+        assert_eq!(lexer.next_token().unwrap(), Tokens::Colon);
+        assert_eq!(lexer.next_token().unwrap(), identifier("elsewhere"));
+        assert_eq!(lexer.next_token().unwrap(), Tokens::Semicolon);
+        assert_eq!(lexer.next_token().unwrap(), Tokens::EOF);
+    }
+
+    #[test]
+    fn test_lexer_loop_keywords() {
+        // SS: arrange
+        let input = r"do while for break continue format doing".to_string();
+
+        // SS: act
+        let mut lexer = Lexer::new(input);
+
+        // SS: assert
+        assert_eq!(lexer.next_token().unwrap(), Tokens::Do);
+        assert_eq!(lexer.next_token().unwrap(), Tokens::While);
+        assert_eq!(lexer.next_token().unwrap(), Tokens::For);
+        assert_eq!(lexer.next_token().unwrap(), Tokens::Break);
+        assert_eq!(lexer.next_token().unwrap(), Tokens::Continue);
+        assert_eq!(
+            lexer.next_token().unwrap(),
+            Tokens::Identifier("format".to_string())
+        );
+        assert_eq!(
+            lexer.next_token().unwrap(),
+            Tokens::Identifier("doing".to_string())
+        );
+        assert_eq!(lexer.next_token().unwrap(), Tokens::EOF);
+    }
+
+    #[test]
+    fn test_lexer_function_call() {
+        // SS: arrange
+        let input = r"add(a, 2)".to_string();
+
+        // SS: act
+        let mut lexer = Lexer::new(input);
+
+        // SS: assert
+        assert_eq!(
+            lexer.next_token().unwrap(),
+            Tokens::Identifier("add".to_string())
+        );
+        assert_eq!(lexer.next_token().unwrap(), Tokens::OpenParen);
+        assert_eq!(
+            lexer.next_token().unwrap(),
+            Tokens::Identifier("a".to_string())
+        );
+        assert_eq!(lexer.next_token().unwrap(), Tokens::Comma);
+        assert_eq!(lexer.next_token().unwrap(), Tokens::Constant(2));
+        assert_eq!(lexer.next_token().unwrap(), Tokens::CloseParen);
+        assert_eq!(lexer.next_token().unwrap(), Tokens::EOF);
+    }
+
+    #[test]
+    fn test_lexer_spans() {
+        // SS: arrange
+        let input = "int x;\n  /* a\n comment */ x = @1;".to_string();
+
+        // SS: act
+        let mut lexer = Lexer::new(input);
+        let mut spans = vec![];
+        while let Ok(token) = lexer.next_token() {
+            let span = lexer.span();
+            spans.push((token, span.line, span.column));
+        }
+        let error = lexer.span();
+        let rest = lexer.next_token();
+
+        // SS: assert
+        assert_eq!(
+            spans,
+            vec![
+                (Tokens::Int, 1, 1),
+                (Tokens::Identifier("x".to_string()), 1, 5),
+                (Tokens::Semicolon, 1, 6),
+                (Tokens::Identifier("x".to_string()), 3, 13),
+                (Tokens::Equal, 3, 15),
+            ]
+        );
+        assert_eq!((error.line, error.column), (3, 17));
+        assert_eq!(rest, Ok(Tokens::Constant(1)));
+    }
+
+    #[test]
+    fn test_lexer_line_markers() {
+        // SS: arrange
+        let input = "# 1 \"a.h\"\nint x;\n# 20 \"dir\\\\b.c\" 2\n  x = #1;".to_string();
+
+        // SS: act
+        let mut lexer = Lexer::new(input);
+        let mut spans = vec![];
+        for _ in 0..5 {
+            let token = lexer.next_token().unwrap();
+            let span = lexer.span();
+            spans.push((token, span.file.to_string(), span.line, span.column));
+        }
+
+        // SS: assert
+        assert_eq!(
+            spans,
+            vec![
+                (Tokens::Int, "a.h".to_string(), 1, 1),
+                (Tokens::Identifier("x".to_string()), "a.h".to_string(), 1, 5),
+                (Tokens::Semicolon, "a.h".to_string(), 1, 6),
+                (
+                    Tokens::Identifier("x".to_string()),
+                    "dir\\b.c".to_string(),
+                    20,
+                    3
+                ),
+                (Tokens::Equal, "dir\\b.c".to_string(), 20, 5),
+            ]
+        );
+        assert_eq!(lexer.input_line(), 4);
+        assert_eq!(lexer.line_map().locate(2), ("a.h".into(), 1));
+        assert_eq!(lexer.line_map().locate(4), ("dir\\b.c".into(), 20));
+        assert_eq!(lexer.next_token(), Ok(Tokens::Hash));
+    }
+
+    #[test]
+    fn test_lexer_keywords() {
+        // SS: arrange
+        let input = KEYWORDS
+            .iter()
+            .map(|(spelling, _)| *spelling)
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        // SS: act
+        let tokens = lex_all(&input);
+
+        // SS: assert
+        let tokens = tokens.into_iter().map(|(token, _, _)| token);
+        assert!(tokens.eq(KEYWORDS.iter().map(|(_, token)| token.clone())));
+        assert_eq!(
+            lex_one("_Boolean"),
+            Ok(Tokens::Identifier("_Boolean".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_lexer_punctuators() {
+        // SS: arrange
+        let input = r"a->b[c]...d<<=e>>=f&=g|=h^=i<<j>>k&l|m^n#o##p<:q:><%r%>%:s%:%:t";
+
+        // SS: act
+        let tokens = lex_all(input)
+            .into_iter()
+            .map(|(token, _, _)| token)
+            .filter(|token| !matches!(token, Tokens::Identifier(_)))
+            .collect::<Vec<_>>();
+
+        // SS: assert
+        assert_eq!(
+            tokens,
+            vec![
+                Tokens::Arrow,
+                Tokens::OpenBracket,
+                Tokens::CloseBracket,
+                Tokens::Ellipsis,
+                Tokens::LessLessEqual,
+                Tokens::GreaterGreaterEqual,
+                Tokens::AmpersandEqual,
+                Tokens::PipeEqual,
+                Tokens::CaretEqual,
+                Tokens::LessLess,
+                Tokens::GreaterGreater,
+                Tokens::Ampersand,
+                Tokens::Pipe,
+                Tokens::Caret,
+                Tokens::Hash,
+                Tokens::HashHash,
+                Tokens::OpenBracket,
+                Tokens::CloseBracket,
+                Tokens::OpenBrace,
+                Tokens::CloseBrace,
+                Tokens::Hash,
+                Tokens::HashHash,
+            ]
+        );
+        for (spelling, token) in PUNCTUATORS {
+            assert_eq!(lex_one(spelling).as_ref(), Ok(token));
+        }
+    }
+
+    #[test]
+    fn test_lexer_integer_constants() {
+        // SS: act & assert
+        assert_eq!(lex_one("0"), Ok(Tokens::Constant(0)));
+        assert_eq!(lex_one("0x1F"), Ok(Tokens::Constant(31)));
+        assert_eq!(lex_one("0XaBc"), Ok(Tokens::Constant(2748)));
+        assert_eq!(lex_one("017"), Ok(Tokens::Constant(15)));
+        assert_eq!(lex_one("10l"), Ok(Tokens::LongConstant(10)));
+        assert_eq!(lex_one("10LL"), Ok(Tokens::LongConstant(10)));
+        assert_eq!(lex_one("10u"), Ok(Tokens::UnsignedConstant(10)));
+        assert_eq!(lex_one("10Ul"), Ok(Tokens::UnsignedLongConstant(10)));
+        assert_eq!(lex_one("10lU"), Ok(Tokens::UnsignedLongConstant(10)));
+        assert_eq!(lex_one("0x10ull"), Ok(Tokens::UnsignedLongConstant(16)));
+        assert_eq!(
+            lex_one("9223372036854775807"),
+            Ok(Tokens::Constant(i64::MAX))
+        );
+        assert_eq!(
+            lex_one("0xffffffffffffffff"),
+            Ok(Tokens::UnsignedLongConstant(u64::MAX))
+        );
+        assert_eq!(
+            lex_one("9223372036854775808"),
+            Err("Integer constant is too large".to_string())
+        );
+        assert_eq!(
+            lex_one("18446744073709551616u"),
+            Err("Integer constant is too large".to_string())
+        );
+        assert_eq!(
+            lex_one("09"),
+            Err(r#"Invalid integer constant "09""#.to_string())
+        );
+        assert_eq!(
+            lex_one("12lL"),
+            Err(r#"Invalid integer constant "12lL""#.to_string())
+        );
+        assert_eq!(
+            lex_one("0x"),
+            Err(r#"Invalid integer constant "0x""#.to_string())
+        );
+    }
+
+    #[test]
+    fn test_lexer_unsigned_hex_constants() {
+        // SS: act & assert
+        assert_eq!(lex_one("0x7fffffff"), Ok(Tokens::Constant(i32::MAX as i64)));
+        assert_eq!(
+            lex_one("0xffffffff"),
+            Ok(Tokens::UnsignedConstant(u32::MAX as u64))
+        );
+        assert_eq!(
+            lex_one("037777777777"),
+            Ok(Tokens::UnsignedConstant(u32::MAX as u64))
+        );
+        assert_eq!(lex_one("0x100000000"), Ok(Tokens::Constant(1 << 32)));
+        assert_eq!(lex_one("4294967295"), Ok(Tokens::Constant(4294967295)));
+    }
+
+    #[test]
+    fn test_lexer_double_constants() {
+        // SS: act & assert
+        assert_eq!(lex_one("1.5"), Ok(Tokens::DoubleConstant(1.5)));
+        assert_eq!(lex_one("1."), Ok(Tokens::DoubleConstant(1.0)));
+        assert_eq!(lex_one(".25"), Ok(Tokens::DoubleConstant(0.25)));
+        assert_eq!(lex_one("1e3"), Ok(Tokens::DoubleConstant(1000.0)));
+        assert_eq!(lex_one("2.5E-1"), Ok(Tokens::DoubleConstant(0.25)));
+        assert_eq!(lex_one("1.e+2"), Ok(Tokens::DoubleConstant(100.0)));
+        assert_eq!(lex_one("0.1"), Ok(Tokens::DoubleConstant(0.1)));
+        assert_eq!(lex_one("1e999"), Ok(Tokens::DoubleConstant(f64::INFINITY)));
+        assert_eq!(
+            lex_one("1.0f"),
+            Err(r#"Invalid floating constant "1.0f""#.to_string())
+        );
+        assert_eq!(
+            lex_one("1.2.3"),
+            Err(r#"Invalid floating constant "1.2.3""#.to_string())
+        );
+        assert_eq!(
+            lex_one("1e"),
+            Err(r#"Invalid integer constant "1e""#.to_string())
+        );
+        assert_eq!(
+            lex_all("a.b ...")
+                .into_iter()
+                .map(|(token, _, _)| token)
+                .collect::<Vec<_>>(),
+            vec![
+                Tokens::Identifier("a".to_string()),
+                Tokens::Dot,
+                Tokens::Identifier("b".to_string()),
+                Tokens::Ellipsis,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lexer_character_constants() {
+        // SS: act & assert
+        assert_eq!(lex_one("'a'"), Ok(Tokens::CharConstant(97)));
+        assert_eq!(lex_one(r"'\n'"), Ok(Tokens::CharConstant(10)));
+        assert_eq!(lex_one(r"'\''"), Ok(Tokens::CharConstant(39)));
+        assert_eq!(lex_one(r#"'"'"#), Ok(Tokens::CharConstant(34)));
+        assert_eq!(lex_one(r"'\\'"), Ok(Tokens::CharConstant(92)));
+        assert_eq!(lex_one(r"'\0'"), Ok(Tokens::CharConstant(0)));
+        assert_eq!(lex_one(r"'\101'"), Ok(Tokens::CharConstant(65)));
+        assert_eq!(lex_one(r"'\x7f'"), Ok(Tokens::CharConstant(127)));
+        assert_eq!(lex_one(r"'\xff'"), Ok(Tokens::CharConstant(-1)));
+        assert_eq!(lex_one("''"), Err("Empty character constant".to_string()));
+        assert_eq!(
+            lex_one("'ab'"),
+            Err("Multi-character character constant".to_string())
+        );
+        assert_eq!(
+            lex_one(r"'\400'"),
+            Err("Octal escape sequence out of range".to_string())
+        );
+        assert_eq!(
+            lex_one(r"'\x100'"),
+            Err("Hex escape sequence out of range".to_string())
+        );
+        assert_eq!(
+            lex_one(r"'\q'"),
+            Err(r"Unknown escape sequence \q".to_string())
+        );
+    }
+
+    #[test]
+    fn test_lexer_string_literals() {
+        // SS: act & assert
+        assert_eq!(lex_one(r#""""#), Ok(Tokens::StringLiteral(vec![])));
+        assert_eq!(
+            lex_one(r#""hi \"there\"\n""#),
+            Ok(Tokens::StringLiteral(b"hi \"there\"\n".to_vec()))
+        );
+        assert_eq!(
+            lex_one(r#""\a\b\f\r\t\v\?\\""#),
+            Ok(Tokens::StringLiteral(b"\x07\x08\x0c\r\t\x0b?\\".to_vec()))
+        );
+        assert_eq!(
+            lex_one(r#""\1234\x41g""#),
+            Ok(Tokens::StringLiteral(b"S4Ag".to_vec()))
+        );
+        assert_eq!(
+            lex_one(r#""/* not a comment */""#),
+            Ok(Tokens::StringLiteral(b"/* not a comment */".to_vec()))
+        );
+        assert_eq!(
+            lex_one(r#""\x""#),
+            Err(r"\x used with no following hex digits".to_string())
+        );
+    }
+
+    #[test]
+    fn test_lexer_unterminated_literal() {
+        // SS: arrange
+        let input = "x = \"abc;\ny = 'c;\nz".to_string();
+
+        // SS: act
+        let mut lexer = Lexer::new(input);
+        let mut results = vec![];
+        loop {
+            match lexer.next_token() {
+                Ok(Tokens::EOF) => break,
+                result => results.push(result.map_err(|err| err.message)),
+            }
+        }
+
+        // SS: assert
+        let identifier = |name: &str| Ok(Tokens::Identifier(name.to_string()));
+        assert_eq!(
+            results,
+            vec![
+                identifier("x"),
+                Ok(Tokens::Equal),
+                Err(r#"Missing terminating " character"#.to_string()),
+                identifier("y"),
+                Ok(Tokens::Equal),
+                Err("Missing terminating ' character".to_string()),
+                identifier("z"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lexer_unterminated_comment() {
+        // SS: arrange
+        let input = "x;\n  /* a\n comment;".to_string();
+
+        // SS: act
+        let mut lexer = Lexer::new(input);
+        let mut results = vec![];
+        loop {
+            match lexer.next_token() {
+                Ok(Tokens::EOF) => break,
+                result => results
+                    .push(result.map_err(|err| (err.span.line, err.span.column, err.message))),
+            }
+        }
+
+        // SS: assert
+        assert_eq!(
+            results,
+            vec![
+                Ok(Tokens::Identifier("x".to_string())),
+                Ok(Tokens::Semicolon),
+                Err((2, 3, "Unterminated comment".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lexer_round_trip() {
+        // SS: arrange, real C files with comments and preprocessing directives, which are
+        // lexed like any other tokens here
+        let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let mut paths = fs::read_dir(manifest_dir.join("tests/lexer"))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "c"))
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        for path in paths {
+            let source = fs::read_to_string(&path).unwrap();
+
+            // SS: act, print the tokens back out and lex them again
+            let tokens = lex_all(&source);
+            let printed = tokens
+                .iter()
+                .map(|(token, _, _)| token.spelling())
+                .collect::<Vec<_>>()
+                .join(" ");
+            let relexed = lex_all(&printed);
+
+            // SS: assert, the tokens survive the round trip and each one is found again in
+            // the original source at its line and column
+            assert!(!tokens.is_empty(), "{:?}", path);
+            assert!(
+                relexed
+                    .iter()
+                    .map(|(token, _, _)| token)
+                    .eq(tokens.iter().map(|(token, _, _)| token)),
+                "{:?}",
+                path
+            );
+            let lines = source.split('\n').collect::<Vec<_>>();
+            for (token, line, column) in tokens {
+                let mut lexer = Lexer::new(lines[line - 1][column - 1..].to_string());
+                assert_eq!(
+                    lexer.next_token(),
+                    Ok(token),
+                    "{:?}:{}:{}",
+                    path,
+                    line,
+                    column
+                );
+            }
+        }
+    }
 }
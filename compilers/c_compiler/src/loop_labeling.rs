@@ -0,0 +1,415 @@
+use crate::name_generator::NameGenerator;
+use crate::parse_ast::{
+    BlockAST, BlockItemAST, ExternalDeclarationAST, FunctionAST, ProgramAST, StmtAST,
+};
+
+// SS: gives every loop and switch a unique label and annotates each break and continue with
+// the label of the statement it leaves, a break leaves the innermost loop or switch and a
+// continue the innermost loop. Cases and defaults get labels of their own.
+pub fn label_loops(
+    ast: ProgramAST,
+    name_generator: &mut NameGenerator,
+) -> Result<ProgramAST, String> {
+    let mut labeler = LoopLabeler {
+        name_generator,
+        switches: vec![],
+    };
+    let declarations = ast
+        .declarations
+        .into_iter()
+        .map(|declaration| match declaration {
+            ExternalDeclarationAST::Function(function) => labeler
+                .label_function(function)
+                .map(ExternalDeclarationAST::Function),
+            declaration @ (ExternalDeclarationAST::Variable(_)
+            | ExternalDeclarationAST::Struct(_)) => Ok(declaration),
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    Ok(ProgramAST { declarations })
+}
+
+struct LoopLabeler<'a> {
+    name_generator: &'a mut NameGenerator,
+    // SS: one entry per enclosing switch, true once it has a default
+    switches: Vec<bool>,
+}
+
+// SS: the labels a break and a continue at the current position refer to
+#[derive(Clone, Copy, Default)]
+struct Targets<'a> {
+    break_label: Option<&'a String>,
+    continue_label: Option<&'a String>,
+}
+
+impl LoopLabeler<'_> {
+    fn label_function(&mut self, function: FunctionAST) -> Result<FunctionAST, String> {
+        let body = function
+            .body
+            .map(|body| self.label_block(body, Targets::default()))
+            .transpose()?;
+        Ok(FunctionAST { body, ..function })
+    }
+
+    fn label_block(&mut self, block: BlockAST, targets: Targets) -> Result<BlockAST, String> {
+        let items = block
+            .items
+            .into_iter()
+            .map(|block_item| match block_item {
+                BlockItemAST::Statement(stmt) => {
+                    Ok(BlockItemAST::Statement(self.label_stmt(stmt, targets)?))
+                }
+                declaration @ (BlockItemAST::Declaration(_)
+                | BlockItemAST::FunctionDeclaration(_)
+                | BlockItemAST::StructDeclaration(_)) => Ok(declaration),
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(BlockAST { items })
+    }
+
+    fn label_stmt(&mut self, stmt: StmtAST, targets: Targets) -> Result<StmtAST, String> {
+        match stmt {
+            StmtAST::Break { line, .. } => match targets.break_label {
+                Some(label) => Ok(StmtAST::Break {
+                    label: Some(label.clone()),
+                    line,
+                }),
+                None => Err(format!(
+                    "Line {}: Semantic error: break statement not within a loop or switch",
+                    line
+                )),
+            },
+            StmtAST::Continue { line, .. } => match targets.continue_label {
+                Some(label) => Ok(StmtAST::Continue {
+                    label: Some(label.clone()),
+                    line,
+                }),
+                None => Err(format!(
+                    "Line {}: Semantic error: continue statement not within a loop",
+                    line
+                )),
+            },
+            StmtAST::While {
+                condition, body, ..
+            } => {
+                let label = self.name_generator.make_unique("loop");
+                let body = self.label_stmt(*body, loop_targets(&label))?;
+                Ok(StmtAST::While {
+                    condition,
+                    body: Box::new(body),
+                    label: Some(label),
+                })
+            }
+            StmtAST::DoWhile {
+                body, condition, ..
+            } => {
+                let label = self.name_generator.make_unique("loop");
+                let body = self.label_stmt(*body, loop_targets(&label))?;
+                Ok(StmtAST::DoWhile {
+                    body: Box::new(body),
+                    condition,
+                    label: Some(label),
+                })
+            }
+            StmtAST::For {
+                init,
+                condition,
+                post,
+                body,
+                ..
+            } => {
+                let label = self.name_generator.make_unique("loop");
+                let body = self.label_stmt(*body, loop_targets(&label))?;
+                Ok(StmtAST::For {
+                    init,
+                    condition,
+                    post,
+                    body: Box::new(body),
+                    label: Some(label),
+                })
+            }
+            StmtAST::If {
+                condition,
+                then_stmt,
+                else_stmt,
+            } => Ok(StmtAST::If {
+                condition,
+                then_stmt: Box::new(self.label_stmt(*then_stmt, targets)?),
+                else_stmt: else_stmt
+                    .map(|stmt| self.label_stmt(*stmt, targets).map(Box::new))
+                    .transpose()?,
+            }),
+            StmtAST::Compound(block) => Ok(StmtAST::Compound(self.label_block(block, targets)?)),
+            StmtAST::Switch {
+                condition, body, ..
+            } => {
+                let label = self.name_generator.make_unique("switch");
+                let switch_targets = Targets {
+                    break_label: Some(&label),
+                    ..targets
+                };
+                self.switches.push(false);
+                let body = self.label_stmt(*body, switch_targets);
+                self.switches.pop();
+                Ok(StmtAST::Switch {
+                    condition,
+                    body: Box::new(body?),
+                    label: Some(label),
+                    cases: vec![],
+                })
+            }
+            StmtAST::Case {
+                value, body, line, ..
+            } => {
+                if self.switches.is_empty() {
+                    return Err(format!(
+                        "Line {}: Semantic error: case label not within a switch statement",
+                        line
+                    ));
+                }
+                let label = self.name_generator.make_unique("case");
+                Ok(StmtAST::Case {
+                    value,
+                    body: Box::new(self.label_stmt(*body, targets)?),
+                    label: Some(label),
+                    line,
+                })
+            }
+            StmtAST::Default { body, line, .. } => {
+                match self.switches.last_mut() {
+                    None => {
+                        return Err(format!(
+                            "Line {}: Semantic error: default label not within a switch statement",
+                            line
+                        ));
+                    }
+                    Some(true) => {
+                        return Err(format!(
+                            "Line {}: Semantic error: multiple default labels in one switch",
+                            line
+                        ));
+                    }
+                    Some(has_default) => *has_default = true,
+                }
+                let label = self.name_generator.make_unique("default");
+                Ok(StmtAST::Default {
+                    body: Box::new(self.label_stmt(*body, targets)?),
+                    label: Some(label),
+                    line,
+                })
+            }
+            StmtAST::Labeled { label, body, line } => Ok(StmtAST::Labeled {
+                label,
+                body: Box::new(self.label_stmt(*body, targets)?),
+                line,
+            }),
+            stmt @ (StmtAST::Return(_)
+            | StmtAST::Expression(_)
+            | StmtAST::Goto { .. }
+            | StmtAST::Null) => Ok(stmt),
+        }
+    }
+}
+
+fn loop_targets(label: &String) -> Targets<'_> {
+    Targets {
+        break_label: Some(label),
+        continue_label: Some(label),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lexer::Lexer;
+    use crate::loop_labeling::label_loops;
+    use crate::name_generator::NameGenerator;
+    use crate::parse_ast::{
+        BlockAST, BlockItemAST, ExprAST, ExternalDeclarationAST, ProgramAST, StmtAST,
+    };
+    use crate::parser::Parser;
+    use crate::types::Constant;
+
+    fn label(input: &str) -> Result<ProgramAST, String> {
+        let lexer = Lexer::new(input.to_string());
+        let mut parser = Parser::new(lexer);
+        let ast = parser.parse().unwrap();
+        label_loops(ast, &mut NameGenerator::new())
+    }
+
+    fn body_items(ast: &ProgramAST) -> Vec<BlockItemAST> {
+        match &ast.declarations[0] {
+            ExternalDeclarationAST::Function(function) => {
+                function.body.as_ref().unwrap().items.clone()
+            }
+            declaration => panic!("expected a function, found {:?}", declaration),
+        }
+    }
+
+    #[test]
+    fn test_label_nested_loops() {
+        // SS: arrange
+        let input = r"int main(void) {
+                            while (1) {
+                                do continue; while (0);
+                                break;
+                            }
+                    }";
+
+        // SS: act
+        let ast = label(input).unwrap();
+
+        // SS: assert
+        assert_eq!(
+            body_items(&ast),
+            vec![BlockItemAST::Statement(StmtAST::While {
+                condition: ExprAST::Constant(Constant::Int(1)),
+                body: Box::new(StmtAST::Compound(BlockAST {
+                    items: vec![
+                        BlockItemAST::Statement(StmtAST::DoWhile {
+                            body: Box::new(StmtAST::Continue {
+                                label: Some("loop.1".to_string()),
+                                line: 3
+                            }),
+                            condition: ExprAST::Constant(Constant::Int(0)),
+                            label: Some("loop.1".to_string()),
+                        }),
+                        BlockItemAST::Statement(StmtAST::Break {
+                            label: Some("loop.0".to_string()),
+                            line: 4
+                        }),
+                    ]
+                })),
+                label: Some("loop.0".to_string()),
+            })]
+        );
+    }
+
+    #[test]
+    fn test_label_switch() {
+        // SS: arrange
+        let input = r"int main(void) {
+                            while (1)
+                                switch (2) {
+                                    case 2: break;
+                                    default: continue;
+                                }
+                    }";
+
+        // SS: act
+        let ast = label(input).unwrap();
+
+        // SS: assert
+        assert_eq!(
+            body_items(&ast),
+            vec![BlockItemAST::Statement(StmtAST::While {
+                condition: ExprAST::Constant(Constant::Int(1)),
+                body: Box::new(StmtAST::Switch {
+                    condition: ExprAST::Constant(Constant::Int(2)),
+                    body: Box::new(StmtAST::Compound(BlockAST {
+                        items: vec![
+                            BlockItemAST::Statement(StmtAST::Case {
+                                value: ExprAST::Constant(Constant::Int(2)),
+                                body: Box::new(StmtAST::Break {
+                                    label: Some("switch.1".to_string()),
+                                    line: 4
+                                }),
+                                label: Some("case.2".to_string()),
+                                line: 4,
+                            }),
+                            BlockItemAST::Statement(StmtAST::Default {
+                                body: Box::new(StmtAST::Continue {
+                                    label: Some("loop.0".to_string()),
+                                    line: 5
+                                }),
+                                label: Some("default.3".to_string()),
+                                line: 5,
+                            }),
+                        ]
+                    })),
+                    label: Some("switch.1".to_string()),
+                    cases: vec![],
+                }),
+                label: Some("loop.0".to_string()),
+            })]
+        );
+    }
+
+    #[test]
+    fn test_label_case_outside_switch() {
+        // SS: arrange
+        let input = r"int main(void) {
+                            while (1) {
+                                case 1: break;
+                            }
+                    }";
+
+        // SS: act
+        let ast = label(input);
+
+        // SS: assert
+        assert_eq!(
+            ast,
+            Err(r#"Line 3: Semantic error: case label not within a switch statement"#.to_string())
+        );
+    }
+
+    #[test]
+    fn test_label_multiple_defaults() {
+        // SS: arrange
+        let input = r"int main(void) {
+                            switch (1) {
+                                default:
+                                    switch (2) { default: ; }
+                                default: ;
+                            }
+                    }";
+
+        // SS: act
+        let ast = label(input);
+
+        // SS: assert
+        assert_eq!(
+            ast,
+            Err(r#"Line 5: Semantic error: multiple default labels in one switch"#.to_string())
+        );
+    }
+
+    #[test]
+    fn test_label_break_outside_loop() {
+        // SS: arrange
+        let input = r"int main(void) {
+                            if (1)
+                                break;
+                    }";
+
+        // SS: act
+        let ast = label(input);
+
+        // SS: assert
+        assert_eq!(
+            ast,
+            Err(
+                r#"Line 3: Semantic error: break statement not within a loop or switch"#
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_label_continue_outside_loop() {
+        // SS: arrange
+        let input = r"int main(void) {
+                            {
+                                continue;
+                            }
+                    }";
+
+        // SS: act
+        let ast = label(input);
+
+        // SS: assert
+        assert_eq!(
+            ast,
+            Err(r#"Line 3: Semantic error: continue statement not within a loop"#.to_string())
+        );
+    }
+}
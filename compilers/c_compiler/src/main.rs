@@ -1,34 +1,79 @@
 use clap::{ArgGroup, Parser};
 mod assembly_ast;
+mod ast_printer;
+mod constant_folding;
+mod control_flow_graph;
+mod copy_propagation;
+mod dead_store_elimination;
+mod diagnostic;
+mod driver;
 mod emitter;
 mod file_emitter;
+mod instruction_fixup;
 mod ir_generation;
+mod label_validation;
 mod lexer;
+mod loop_labeling;
+mod name_generator;
+mod optimization;
 mod parse_ast;
 mod parser;
+mod preprocessor;
+mod pseudo_replacement;
 mod reg;
+mod register_allocation;
+mod semantic_analysis;
 mod string_emitter;
+mod symbol_table;
+mod tacky_ast;
+mod tacky_generation;
+mod tacky_interpreter;
+mod target;
 mod tokens;
+mod type_checking;
+mod type_table;
+mod types;
+mod unreachable_code_elimination;
 mod x64_code_gen;
 
+use crate::control_flow_graph::{CfgFormat, ControlFlowGraph};
+use crate::diagnostic::Diagnostic;
 use crate::emitter::Emitter;
 use crate::file_emitter::FileEmitter;
+use crate::name_generator::NameGenerator;
+use crate::preprocessor::Preprocessor;
+use crate::string_emitter::StringEmitter;
+use crate::tacky_ast::{TackyProgramAST, TackyTopLevelAST};
+use crate::target::Target;
 use crate::x64_code_gen::X64CodeGen;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process;
+use std::thread;
+
+// SS: every pass recurses over the AST, so the compiler runs on a thread with a stack deep
+// enough for the most deeply nested program the parser accepts, even in a debug build
+const STACK_SIZE: usize = 256 * 1024 * 1024;
 
 #[derive(Parser)]
 #[command(name = "compiler-driver", version = "1.0")]
 #[command(group(
     ArgGroup::new("stage")
-        .args(["lex", "parse", "codegen", "s"])
+        .args(["e", "lex", "parse", "validate", "tacky", "interpret", "codegen", "s", "c"])
         .multiple(false) // Ensures only one can be picked
 ))]
 struct Args {
     /// The C source file to compile
     input: PathBuf,
 
+    /// Run the preprocessor and print its output
+    #[arg(short = 'E')]
+    e: bool,
+
+    /// Add a directory to search for included files
+    #[arg(short = 'I', value_name = "DIR")]
+    include_dirs: Vec<PathBuf>,
+
     /// Run the lexer and stop
     #[arg(long, conflicts_with = "parse")]
     lex: bool,
@@ -37,6 +82,18 @@ struct Args {
     #[arg(long)]
     parse: bool,
 
+    /// Stop after semantic analysis
+    #[arg(long)]
+    validate: bool,
+
+    /// Stop after TACKY generation
+    #[arg(long)]
+    tacky: bool,
+
+    /// Run the program in an interpreter for TACKY and exit with its exit code
+    #[arg(long)]
+    interpret: bool,
+
     /// Stop after assembly generation
     #[arg(long)]
     codegen: bool,
@@ -44,9 +101,56 @@ struct Args {
     /// Emit assembly file but do not link
     #[arg(short = 'S')]
     s: bool,
+
+    /// Emit an object file but do not link
+    #[arg(short = 'c')]
+    c: bool,
+
+    /// Link against the given library
+    #[arg(short = 'l', value_name = "LIBRARY")]
+    libraries: Vec<String>,
+
+    /// Evaluate constant expressions at compile time
+    #[arg(long)]
+    fold_constants: bool,
+
+    /// Replace variables with the values copied into them
+    #[arg(long)]
+    propagate_copies: bool,
+
+    /// Remove code that can never execute
+    #[arg(long)]
+    eliminate_unreachable_code: bool,
+
+    /// Remove assignments to variables that are never read
+    #[arg(long)]
+    eliminate_dead_stores: bool,
+
+    /// Enable all optimizations
+    #[arg(long)]
+    optimize: bool,
+
+    /// Write the control-flow graph of every function after optimization to a file
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    dump_cfg: Option<CfgFormat>,
+
+    /// The platform to generate assembly for, defaults to the host
+    #[arg(long, value_enum, default_value_t = Target::host())]
+    target: Target,
 }
 
 fn main() {
+    let compiler = thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(run)
+        .unwrap();
+    // SS: a panic has been reported by the thread already, exit like it would have
+    if compiler.join().is_err() {
+        process::exit(101);
+    }
+}
+
+fn run() {
     // Only pause if we explicitly ask for it
     if std::env::var("WAIT_FOR_DEBUGGER").is_ok() {
         println!(
@@ -60,61 +164,221 @@ fn main() {
     let args = Args::parse();
     println!("Processing: {:?}", args.input);
 
-    println!("Running lexer...");
-
     // SS: read the file into a String
     let source_code = fs::read_to_string(&args.input).unwrap_or_else(|err| {
         eprintln!("Error reading file {:?}: {}", args.input, err);
         process::exit(1);
     });
 
-    // SS: pass the string to the lexer
-    let mut lexer = lexer::Lexer::new(source_code);
+    println!("Running preprocessor...");
+    let mut preprocessor = Preprocessor::new(args.include_dirs.clone(), args.target);
+    let source_code = preprocessor
+        .preprocess(source_code, &args.input.to_string_lossy())
+        .unwrap_or_else(|diagnostics| {
+            for diagnostic in diagnostics {
+                eprintln!(
+                    "{}",
+                    diagnostic.render(preprocessor.source(&diagnostic.span.file))
+                );
+            }
+            process::exit(1);
+        });
+    if args.e {
+        println!("Preprocessed source:\n{}", source_code);
+        return;
+    }
+
+    // SS: with -S the assembly is the output, otherwise only an intermediate
+    let assembly_path = if args.s {
+        args.input.with_extension("s")
+    } else {
+        driver::intermediate_path(&args.input, "s")
+    };
+    compile(&args, source_code, &preprocessor, &assembly_path);
+    if args.lex || args.parse || args.validate || args.tacky || args.codegen || args.s {
+        return;
+    }
+
+    // SS: the assembly file is an intermediate, remove it whether or not assembling succeeds
+    let result = if args.c {
+        println!("Assembling...");
+        driver::assemble(&assembly_path, &args.input.with_extension("o"))
+    } else {
+        println!("Assembling and linking...");
+        driver::assemble_and_link(
+            &assembly_path,
+            &args.input.with_extension(""),
+            &args.libraries,
+        )
+    };
+    let _ = fs::remove_file(&assembly_path);
+    result.unwrap_or_else(|code| process::exit(code));
+}
+
+// SS: runs the compiler proper on the preprocessed source, stopping after the stage requested
+// on the command line. Writes the assembly to assembly_path unless it stops earlier.
+fn compile(args: &Args, source_code: String, preprocessor: &Preprocessor, assembly_path: &Path) {
+    println!("Running lexer...");
+
+    // SS: the line markers in the source take diagnostics back to the file they came from,
+    // whose text the preprocessor keeps
+    let file_name = args.input.to_string_lossy();
+    let mut lexer = lexer::Lexer::with_file_name(source_code, &file_name);
+    let render =
+        |diagnostic: &Diagnostic| diagnostic.render(preprocessor.source(&diagnostic.span.file));
 
     if args.lex {
-        // SS: iterate through tokens until EOF
+        // SS: iterate through tokens until EOF, the lexer skips past anything it rejects
+        let mut failed = false;
         loop {
             match lexer.next_token() {
                 Ok(tokens::Tokens::EOF) => {
                     println!("Reached end of file.");
                     break;
                 }
-                Ok(token) => println!("Token: {:?}", token),
-                Err(err) => {
-                    eprintln!("Lexer error: {}", err);
-                    process::exit(1);
+                Ok(token) => {
+                    let span = lexer.span();
+                    println!("{}:{}:{} {}", span.file, span.line, span.column, token);
+                }
+                Err(diagnostic) => {
+                    eprintln!("{}", render(&diagnostic));
+                    failed = true;
                 }
             }
         }
+        if failed {
+            process::exit(1);
+        }
     } else {
         println!("Running parser...");
         let mut parser = parser::Parser::new(lexer);
         match parser.parse() {
             Ok(ast) => {
-                println!("Parsed AST: {:?}", ast);
+                for warning in parser.warnings() {
+                    eprintln!("{}", render(warning));
+                }
+                println!("Parsed AST:\n{}", ast_printer::print_program(&ast));
+                if args.parse {
+                    return;
+                }
+
+                println!("Running semantic analysis...");
+                let mut name_generator = NameGenerator::new();
+                let (ast, mut symbols, types) =
+                    semantic_analysis::resolve_program(ast, &mut name_generator)
+                        .and_then(|ast| loop_labeling::label_loops(ast, &mut name_generator))
+                        .and_then(|ast| label_validation::validate_labels(ast, &mut name_generator))
+                        .and_then(|ast| type_checking::typecheck_program(ast, &mut name_generator))
+                        .unwrap_or_else(|err| {
+                            eprintln!("{}", parser.line_map().locate_error(&err));
+                            process::exit(1);
+                        });
+                if args.validate {
+                    println!("Validated AST:\n{}", ast_printer::print_program(&ast));
+                    return;
+                }
 
-                let assembly_ast = ir_generation::generate_assembly_program_ast(ast);
+                println!("Running TACKY generation...");
+                let tacky_ast = tacky_generation::generate_tacky_program_ast(
+                    ast,
+                    &mut symbols,
+                    &types,
+                    &mut name_generator,
+                );
+
+                let options = optimization::OptimizationOptions {
+                    fold_constants: args.fold_constants || args.optimize,
+                    propagate_copies: args.propagate_copies || args.optimize,
+                    eliminate_unreachable_code: args.eliminate_unreachable_code || args.optimize,
+                    eliminate_dead_stores: args.eliminate_dead_stores || args.optimize,
+                };
+                let tacky_ast = if options.is_enabled() {
+                    println!("Running optimizations...");
+                    optimization::optimize_program(tacky_ast, options, &symbols)
+                } else {
+                    tacky_ast
+                };
+                if let Some(format) = args.dump_cfg {
+                    dump_cfg(&tacky_ast, format, &args.input.with_extension("dot"));
+                }
+                if args.tacky {
+                    println!("Generated TACKY:\n{}", tacky_ast);
+                    return;
+                }
+                if args.interpret {
+                    println!("Running interpreter...");
+                    let mut stdout = std::io::stdout();
+                    match tacky_interpreter::interpret_program(
+                        &tacky_ast,
+                        &symbols,
+                        &types,
+                        &mut stdout,
+                    ) {
+                        Ok(exit_code) => process::exit(exit_code),
+                        Err(err) => {
+                            eprintln!("Runtime error: {}", err);
+                            process::exit(1);
+                        }
+                    }
+                }
+
+                let assembly_ast = ir_generation::generate_assembly_program_ast(
+                    tacky_ast,
+                    &mut symbols,
+                    &types,
+                    &mut name_generator,
+                );
+                let assembly_ast = register_allocation::allocate_registers(assembly_ast, &symbols);
+                let assembly_ast =
+                    pseudo_replacement::replace_pseudo_registers(assembly_ast, &symbols, &types);
+                let assembly_ast = instruction_fixup::fixup_instructions(assembly_ast);
 
                 if args.codegen {
                     println!("Running codegen...");
-                    println!("Generated Assembly AST: {:?}", assembly_ast);
-                } else if !args.parse {
+                    let mut string_emitter = StringEmitter::new();
+                    X64CodeGen::new(&mut string_emitter, args.target).emit(&assembly_ast);
+                    println!("Generated assembly:\n{}", string_emitter.buffer.join("\n"));
+                } else {
                     println!("Emitting assembly...");
-                    let output_path = args.input.with_extension("s");
-                    let mut file_emitter = FileEmitter::new(Path::new(&output_path))
-                        .unwrap_or_else(|err| {
-                            eprintln!("Could not create output file {:?}: {}", output_path, err);
-                            process::exit(1);
-                        });
-                    let mut x86_code_gen = X64CodeGen::new(&mut file_emitter);
+                    let mut file_emitter = FileEmitter::new(assembly_path).unwrap_or_else(|err| {
+                        eprintln!("Could not create output file {:?}: {}", assembly_path, err);
+                        process::exit(1);
+                    });
+                    let mut x86_code_gen = X64CodeGen::new(&mut file_emitter, args.target);
                     x86_code_gen.emit(&assembly_ast);
                     file_emitter.finish().unwrap();
                 }
             }
-            Err(err) => {
-                eprintln!("Parser error: {}", err);
+            Err(diagnostics) => {
+                for diagnostic in diagnostics {
+                    eprintln!("{}", render(&diagnostic));
+                }
                 process::exit(1);
             }
         }
     }
 }
+
+// SS: writes the control-flow graphs of the functions in the program to dot_path
+fn dump_cfg(tacky_ast: &TackyProgramAST, format: CfgFormat, dot_path: &Path) {
+    let graphs = tacky_ast
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            TackyTopLevelAST::Function(function) => Some((
+                function.name.as_str(),
+                ControlFlowGraph::new(function.instructions.clone()),
+            )),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let text = match format {
+        CfgFormat::Dot => control_flow_graph::to_dot(&graphs),
+    };
+
+    println!("Writing control-flow graph to {:?}...", dot_path);
+    fs::write(dot_path, text + "\n").unwrap_or_else(|err| {
+        eprintln!("Could not write {:?}: {}", dot_path, err);
+        process::exit(1);
+    });
+}
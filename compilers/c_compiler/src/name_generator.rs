@@ -0,0 +1,18 @@
+// SS: hands out program-wide unique names. All passes that invent names (identifier
+// resolution, TACKY temporaries and labels) share one counter, so a user variable called
+// e.g. "tmp" can never clash with a compiler-generated temporary.
+pub(crate) struct NameGenerator {
+    counter: usize,
+}
+
+impl NameGenerator {
+    pub fn new() -> Self {
+        NameGenerator { counter: 0 }
+    }
+
+    pub fn make_unique(&mut self, prefix: &str) -> String {
+        let name = format!("{}.{}", prefix, self.counter);
+        self.counter += 1;
+        name
+    }
+}
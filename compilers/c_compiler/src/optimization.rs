@@ -0,0 +1,340 @@
+use crate::constant_folding::fold_constants;
+use crate::control_flow_graph::ControlFlowGraph;
+use crate::copy_propagation::propagate_copies;
+use crate::dead_store_elimination::eliminate_dead_stores;
+use crate::symbol_table::SymbolTable;
+use crate::tacky_ast::{
+    TackyFunctionAST, TackyInstructionAST, TackyProgramAST, TackyTopLevelAST, TackyValueAST,
+};
+use crate::types::Type;
+use crate::unreachable_code_elimination::eliminate_unreachable_code;
+use std::collections::HashSet;
+
+// SS: the TACKY optimizations selected on the command line
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OptimizationOptions {
+    pub fold_constants: bool,
+    pub propagate_copies: bool,
+    pub eliminate_unreachable_code: bool,
+    pub eliminate_dead_stores: bool,
+}
+
+impl OptimizationOptions {
+    pub fn is_enabled(&self) -> bool {
+        self.fold_constants
+            || self.propagate_copies
+            || self.eliminate_unreachable_code
+            || self.eliminate_dead_stores
+    }
+}
+
+pub fn optimize_program(
+    ast: TackyProgramAST,
+    options: OptimizationOptions,
+    symbols: &SymbolTable,
+) -> TackyProgramAST {
+    TackyProgramAST {
+        items: ast
+            .items
+            .into_iter()
+            .map(|item| match item {
+                TackyTopLevelAST::Function(function) => {
+                    TackyTopLevelAST::Function(optimize_function(function, options, symbols))
+                }
+                item => item,
+            })
+            .collect(),
+    }
+}
+
+// SS: each pass can expose more work for the others, i.e. folding a condition makes a
+// branch unreachable, which removes a copy, which lets another constant propagate. Run
+// them all on the same graph until none of them changes it.
+fn optimize_function(
+    function: TackyFunctionAST,
+    options: OptimizationOptions,
+    symbols: &SymbolTable,
+) -> TackyFunctionAST {
+    let mut cfg = ControlFlowGraph::new(function.instructions);
+    loop {
+        // SS: a dead store can take the last address of a variable with it
+        let statics = static_variables(cfg.instructions(), symbols);
+        let aliased = aliased_variables(cfg.instructions(), &statics);
+
+        let mut changed = false;
+        if options.fold_constants {
+            changed |= fold_constants(&mut cfg, symbols);
+        }
+        if options.eliminate_unreachable_code {
+            changed |= eliminate_unreachable_code(&mut cfg);
+        }
+        if options.propagate_copies {
+            changed |= propagate_copies(&mut cfg, symbols, &aliased);
+        }
+        if options.eliminate_dead_stores {
+            changed |= eliminate_dead_stores(&mut cfg, &aliased, &statics);
+        }
+        if !changed {
+            return TackyFunctionAST {
+                instructions: cfg.into_instructions(),
+                ..function
+            };
+        }
+    }
+}
+
+// SS: the type of a value, constants carry their own and temporaries have been added to
+// the symbol table during TACKY generation
+pub fn value_type(value: &TackyValueAST, symbols: &SymbolTable) -> Type {
+    match value {
+        TackyValueAST::Constant(c) => c.constant_type(),
+        TackyValueAST::Var(name) => symbols[name].symbol_type.clone(),
+    }
+}
+
+// SS: the value an instruction overwrites as a whole. Copying into part of an aggregate
+// and storing through a pointer do not count.
+pub fn destination(instruction: &TackyInstructionAST) -> Option<&TackyValueAST> {
+    match instruction {
+        TackyInstructionAST::Unary { dst, .. }
+        | TackyInstructionAST::Binary { dst, .. }
+        | TackyInstructionAST::Copy { dst, .. }
+        | TackyInstructionAST::SignExtend { dst, .. }
+        | TackyInstructionAST::Truncate { dst, .. }
+        | TackyInstructionAST::ZeroExtend { dst, .. }
+        | TackyInstructionAST::DoubleToInt { dst, .. }
+        | TackyInstructionAST::DoubleToUInt { dst, .. }
+        | TackyInstructionAST::IntToDouble { dst, .. }
+        | TackyInstructionAST::UIntToDouble { dst, .. }
+        | TackyInstructionAST::GetAddress { dst, .. }
+        | TackyInstructionAST::Load { dst, .. }
+        | TackyInstructionAST::AddPtr { dst, .. }
+        | TackyInstructionAST::CopyFromOffset { dst, .. }
+        | TackyInstructionAST::FunctionCall { dst, .. } => Some(dst),
+        TackyInstructionAST::Return(_)
+        | TackyInstructionAST::Store { .. }
+        | TackyInstructionAST::CopyToOffset { .. }
+        | TackyInstructionAST::Jump(_)
+        | TackyInstructionAST::JumpIfZero(..)
+        | TackyInstructionAST::JumpIfNotZero(..)
+        | TackyInstructionAST::JumpTable { .. }
+        | TackyInstructionAST::Label(_) => None,
+    }
+}
+
+// SS: the variables with static storage duration the function refers to, other functions
+// can see them so they are live on return
+fn static_variables<'a>(
+    instructions: impl Iterator<Item = &'a TackyInstructionAST>,
+    symbols: &SymbolTable,
+) -> HashSet<String> {
+    let mut statics = HashSet::new();
+    let mut add = |name: &String| {
+        if symbols.get(name).is_some_and(|symbol| symbol.is_static()) {
+            statics.insert(name.clone());
+        }
+    };
+    for instruction in instructions {
+        match instruction {
+            TackyInstructionAST::CopyToOffset { dst: name, .. }
+            | TackyInstructionAST::CopyFromOffset { src: name, .. } => add(name),
+            _ => {}
+        }
+        let mut values = vec![];
+        match instruction {
+            TackyInstructionAST::Return(value)
+            | TackyInstructionAST::JumpIfZero(value, _)
+            | TackyInstructionAST::JumpIfNotZero(value, _)
+            | TackyInstructionAST::JumpTable { index: value, .. } => values.push(value),
+            TackyInstructionAST::Unary { src, dst, .. }
+            | TackyInstructionAST::Copy { src, dst }
+            | TackyInstructionAST::SignExtend { src, dst }
+            | TackyInstructionAST::Truncate { src, dst }
+            | TackyInstructionAST::ZeroExtend { src, dst }
+            | TackyInstructionAST::DoubleToInt { src, dst }
+            | TackyInstructionAST::DoubleToUInt { src, dst }
+            | TackyInstructionAST::IntToDouble { src, dst }
+            | TackyInstructionAST::UIntToDouble { src, dst }
+            | TackyInstructionAST::GetAddress { src, dst }
+            | TackyInstructionAST::Load { src_ptr: src, dst }
+            | TackyInstructionAST::Store { src, dst_ptr: dst } => values.extend([src, dst]),
+            TackyInstructionAST::Binary {
+                src1, src2, dst, ..
+            } => values.extend([src1, src2, dst]),
+            TackyInstructionAST::AddPtr {
+                ptr, index, dst, ..
+            } => values.extend([ptr, index, dst]),
+            TackyInstructionAST::CopyToOffset { src, .. } => values.push(src),
+            TackyInstructionAST::CopyFromOffset { dst, .. } => values.push(dst),
+            TackyInstructionAST::FunctionCall { args, dst, .. } => {
+                values.extend(args);
+                values.push(dst);
+            }
+            TackyInstructionAST::Jump(_) | TackyInstructionAST::Label(_) => {}
+        }
+        for value in values {
+            if let TackyValueAST::Var(name) = value {
+                add(name);
+            }
+        }
+    }
+    statics
+}
+
+// SS: variables that can be read or written without being named, through a pointer or by
+// another function. That is every static variable and every variable whose address is
+// taken.
+fn aliased_variables<'a>(
+    instructions: impl Iterator<Item = &'a TackyInstructionAST>,
+    statics: &HashSet<String>,
+) -> HashSet<String> {
+    let mut aliased = statics.clone();
+    for instruction in instructions {
+        if let TackyInstructionAST::GetAddress {
+            src: TackyValueAST::Var(name),
+            ..
+        } = instruction
+        {
+            aliased.insert(name.clone());
+        }
+    }
+    aliased
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::optimization::{OptimizationOptions, optimize_program};
+    use crate::symbol_table::{IdentifierAttrs, Symbol, SymbolTable};
+    use crate::tacky_ast::{
+        TackyBinaryOperatorAST, TackyFunctionAST, TackyInstructionAST, TackyProgramAST,
+        TackyTopLevelAST, TackyValueAST,
+    };
+    use crate::types::{Constant, Type};
+
+    fn var(name: &str) -> TackyValueAST {
+        TackyValueAST::Var(name.to_string())
+    }
+
+    fn int(val: i32) -> TackyValueAST {
+        TackyValueAST::Constant(Constant::Int(val))
+    }
+
+    fn program(instructions: Vec<TackyInstructionAST>) -> TackyProgramAST {
+        TackyProgramAST {
+            items: vec![TackyTopLevelAST::Function(TackyFunctionAST {
+                name: "main".to_string(),
+                global: true,
+                params: vec![],
+                instructions,
+            })],
+        }
+    }
+
+    #[test]
+    fn test_optimize_program() {
+        // SS: arrange
+        let symbols = ["x", "c", "tmp.0", "tmp.1"]
+            .into_iter()
+            .map(|name| {
+                (
+                    name.to_string(),
+                    Symbol {
+                        symbol_type: Type::Int,
+                        attrs: IdentifierAttrs::Local,
+                    },
+                )
+            })
+            .collect::<SymbolTable>();
+        // SS: int x = 2; int c = x > 1; if (c) x = x * 3; else x = 0; return x;
+        let tacky_ast = program(vec![
+            TackyInstructionAST::Copy {
+                src: int(2),
+                dst: var("x"),
+            },
+            TackyInstructionAST::Binary {
+                op: TackyBinaryOperatorAST::GreaterThan,
+                src1: var("x"),
+                src2: int(1),
+                dst: var("tmp.0"),
+            },
+            TackyInstructionAST::Copy {
+                src: var("tmp.0"),
+                dst: var("c"),
+            },
+            TackyInstructionAST::JumpIfZero(var("c"), "else".to_string()),
+            TackyInstructionAST::Binary {
+                op: TackyBinaryOperatorAST::Multiply,
+                src1: var("x"),
+                src2: int(3),
+                dst: var("tmp.1"),
+            },
+            TackyInstructionAST::Copy {
+                src: var("tmp.1"),
+                dst: var("x"),
+            },
+            TackyInstructionAST::Jump("end".to_string()),
+            TackyInstructionAST::Label("else".to_string()),
+            TackyInstructionAST::Copy {
+                src: int(0),
+                dst: var("x"),
+            },
+            TackyInstructionAST::Label("end".to_string()),
+            TackyInstructionAST::Return(var("x")),
+        ]);
+        let options = OptimizationOptions {
+            fold_constants: true,
+            propagate_copies: true,
+            eliminate_unreachable_code: true,
+            eliminate_dead_stores: true,
+        };
+
+        // SS: act
+        let tacky_ast = optimize_program(tacky_ast, options, &symbols);
+
+        // SS: assert
+        assert_eq!(
+            tacky_ast,
+            program(vec![TackyInstructionAST::Return(int(6))])
+        );
+    }
+
+    #[test]
+    fn test_optimize_program_only_selected_passes() {
+        // SS: arrange
+        let symbols = SymbolTable::from([(
+            "x".to_string(),
+            Symbol {
+                symbol_type: Type::Int,
+                attrs: IdentifierAttrs::Local,
+            },
+        )]);
+        let tacky_ast = program(vec![
+            TackyInstructionAST::Binary {
+                op: TackyBinaryOperatorAST::Add,
+                src1: int(1),
+                src2: int(2),
+                dst: var("x"),
+            },
+            TackyInstructionAST::Return(int(0)),
+        ]);
+        let options = OptimizationOptions {
+            fold_constants: true,
+            ..Default::default()
+        };
+
+        // SS: act
+        let tacky_ast = optimize_program(tacky_ast, options, &symbols);
+
+        // SS: assert
+        assert_eq!(
+            tacky_ast,
+            program(vec![
+                TackyInstructionAST::Copy {
+                    src: int(3),
+                    dst: var("x"),
+                },
+                TackyInstructionAST::Return(int(0)),
+            ])
+        );
+    }
+}
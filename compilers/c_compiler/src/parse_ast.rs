@@ -1,20 +1,270 @@
+use crate::types::{Constant, Type};
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct ProgramAST {
-    pub function_definition: FunctionAST,
+    pub declarations: Vec<ExternalDeclarationAST>,
 }
 
+// SS: a declaration at file scope
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExternalDeclarationAST {
+    Function(FunctionAST),
+    Variable(DeclarationAST),
+    Struct(StructDeclarationAST),
+}
+
+// SS: a function declaration, it is a definition if it has a body
 #[derive(Debug, Clone, PartialEq)]
 pub struct FunctionAST {
     pub name: String,
-    pub body: StmtAST,
+    pub params: Vec<String>,
+    pub body: Option<BlockAST>,
+    // SS: a Type::Function holding the parameter and return types
+    pub function_type: Type,
+    pub storage_class: Option<StorageClassAST>,
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StorageClassAST {
+    Static,
+    Extern,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockAST {
+    pub items: Vec<BlockItemAST>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlockItemAST {
+    Statement(StmtAST),
+    Declaration(DeclarationAST),
+    FunctionDeclaration(FunctionAST),
+    StructDeclaration(StructDeclarationAST),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeclarationAST {
+    pub name: String,
+    pub init: Option<InitializerAST>,
+    pub var_type: Type,
+    pub storage_class: Option<StorageClassAST>,
+    pub line: usize,
+}
+
+// SS: declares a structure or union tag, it is a definition if it has a member list
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructDeclarationAST {
+    pub tag: String,
+    pub is_union: bool,
+    pub members: Option<Vec<MemberDeclarationAST>>,
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemberDeclarationAST {
+    pub name: String,
+    pub member_type: Type,
+    pub line: usize,
+}
+
+// SS: an initializer list initializes an array or structure, its elements are the
+// initializers of the array elements or members in order
+#[derive(Debug, Clone, PartialEq)]
+pub enum InitializerAST {
+    Single(ExprAST),
+    Compound(Vec<InitializerAST>),
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum StmtAST {
     Return(ExprAST),
+    Expression(ExprAST),
+    If {
+        condition: ExprAST,
+        then_stmt: Box<StmtAST>,
+        else_stmt: Option<Box<StmtAST>>,
+    },
+    Compound(BlockAST),
+    Break {
+        label: Option<String>,
+        line: usize,
+    },
+    Continue {
+        label: Option<String>,
+        line: usize,
+    },
+    While {
+        condition: ExprAST,
+        body: Box<StmtAST>,
+        label: Option<String>,
+    },
+    DoWhile {
+        body: Box<StmtAST>,
+        condition: ExprAST,
+        label: Option<String>,
+    },
+    For {
+        init: ForInitAST,
+        condition: Option<ExprAST>,
+        post: Option<ExprAST>,
+        body: Box<StmtAST>,
+        label: Option<String>,
+    },
+    Switch {
+        condition: ExprAST,
+        body: Box<StmtAST>,
+        label: Option<String>,
+        // SS: the cases in the body in the order they appear, collected by type checking
+        cases: Vec<SwitchCaseAST>,
+    },
+    Case {
+        value: ExprAST,
+        body: Box<StmtAST>,
+        label: Option<String>,
+        line: usize,
+    },
+    Default {
+        body: Box<StmtAST>,
+        label: Option<String>,
+        line: usize,
+    },
+    // SS: the label is the name in the source until label validation makes it unique
+    Goto {
+        label: String,
+        line: usize,
+    },
+    Labeled {
+        label: String,
+        body: Box<StmtAST>,
+        line: usize,
+    },
+    Null,
+}
+
+// SS: a case of a switch and the label of its statement, the value has been converted to
+// the promoted type of the controlling expression. Default has no value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SwitchCaseAST {
+    pub value: Option<Constant>,
+    pub label: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ForInitAST {
+    InitDecl(DeclarationAST),
+    InitExpr(Option<ExprAST>),
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum ExprAST {
-    Constant(i64),
+    Constant(Constant),
+    // SS: the bytes of a string literal without the terminating null, adjacent literals
+    // have been concatenated
+    String(Vec<u8>),
+    Var {
+        name: String,
+        line: usize,
+    },
+    Unary(UnaryOperatorAST, Box<ExprAST>),
+    Binary(BinaryOperatorAST, Box<ExprAST>, Box<ExprAST>),
+    Assignment {
+        lhs: Box<ExprAST>,
+        rhs: Box<ExprAST>,
+        line: usize,
+    },
+    CompoundAssignment {
+        op: BinaryOperatorAST,
+        lhs: Box<ExprAST>,
+        rhs: Box<ExprAST>,
+        line: usize,
+    },
+    Increment {
+        op: IncrementOperatorAST,
+        operand: Box<ExprAST>,
+        line: usize,
+    },
+    Conditional {
+        condition: Box<ExprAST>,
+        then_expr: Box<ExprAST>,
+        else_expr: Box<ExprAST>,
+    },
+    FunctionCall {
+        name: String,
+        args: Vec<ExprAST>,
+        line: usize,
+    },
+    // SS: an explicit cast in the source, type checking adds one for every implicit
+    // conversion as well
+    Cast {
+        target_type: Type,
+        expr: Box<ExprAST>,
+        line: usize,
+    },
+    Dereference {
+        expr: Box<ExprAST>,
+        line: usize,
+    },
+    AddressOf {
+        expr: Box<ExprAST>,
+        line: usize,
+    },
+    // SS: type checking rewrites a[i] as *(a + i)
+    Subscript {
+        array: Box<ExprAST>,
+        index: Box<ExprAST>,
+        line: usize,
+    },
+    // SS: type checking replaces sizeof with the size as an unsigned long constant
+    SizeOf {
+        expr: Box<ExprAST>,
+        line: usize,
+    },
+    SizeOfType {
+        target_type: Type,
+        line: usize,
+    },
+    Dot {
+        structure: Box<ExprAST>,
+        member: String,
+        line: usize,
+    },
+    Arrow {
+        pointer: Box<ExprAST>,
+        member: String,
+        line: usize,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnaryOperatorAST {
+    Negate,
+    Complement,
+    Not,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOperatorAST {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+    And,
+    Or,
+    Equal,
+    NotEqual,
+    LessThan,
+    LessOrEqual,
+    GreaterThan,
+    GreaterOrEqual,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IncrementOperatorAST {
+    PreIncrement,
+    PreDecrement,
+    PostIncrement,
+    PostDecrement,
 }
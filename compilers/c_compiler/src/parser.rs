@@ -1,124 +1,2204 @@
+use crate::diagnostic::{Diagnostic, LineMap, Severity, Span};
 use crate::lexer::Lexer;
-use crate::parse_ast::{ExprAST, FunctionAST, ProgramAST, StmtAST};
+use crate::parse_ast::{
+    BinaryOperatorAST, BlockAST, BlockItemAST, DeclarationAST, ExprAST, ExternalDeclarationAST,
+    ForInitAST, FunctionAST, IncrementOperatorAST, InitializerAST, MemberDeclarationAST,
+    ProgramAST, StmtAST, StorageClassAST, StructDeclarationAST, UnaryOperatorAST,
+};
 use crate::tokens::Tokens;
+use crate::types::{Constant, Type};
+
+// SS: a token with its location for diagnostics and the line of the lexer's input it is on,
+// which is what the AST records
+type Symbol = (Tokens, Span, usize);
+
+// SS: statements, expressions, declarators and initializers nested deeper than this are
+// rejected, parsing them would overflow the stack
+const MAX_NESTING_DEPTH: usize = 1000;
 
 pub(crate) struct Parser {
     lexer: Lexer,
-    current_symbol: Option<Tokens>,
+    current_symbol: Option<Symbol>,
+    // SS: the token after it, only read to tell a label from an expression
+    next_symbol: Option<Symbol>,
+
+    // SS: location and input line of the most recently consumed token
+    previous_span: Span,
+    previous_line: usize,
+
+    // SS: errors and warnings reported so far, parsing continues after an error so that
+    // one run can report several of them
+    diagnostics: Vec<Diagnostic>,
+
+    // SS: how many of the recursive parse functions are active
+    depth: usize,
 }
 
 impl Parser {
     pub fn new(lexer: Lexer) -> Self {
+        let previous_span = lexer.span();
+        let previous_line = lexer.input_line();
         Parser {
             lexer,
             current_symbol: None,
+            next_symbol: None,
+            previous_span,
+            previous_line,
+            diagnostics: vec![],
+            depth: 0,
+        }
+    }
+
+    // SS: on success any warnings remain available through warnings(), on failure all
+    // diagnostics are returned
+    pub fn parse(&mut self) -> Result<ProgramAST, Vec<Diagnostic>> {
+        let mut declarations = vec![];
+        loop {
+            match self.peek() {
+                Ok(Tokens::EOF) => break,
+                Ok(_) => match self.parse_declaration() {
+                    Ok(declaration) => declarations.extend(declaration),
+                    Err(diagnostic) => {
+                        self.diagnostics.push(diagnostic);
+                        self.synchronize(true);
+                    }
+                },
+                Err(diagnostic) => self.diagnostics.push(diagnostic),
+            }
+        }
+
+        if self
+            .diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity == Severity::Error)
+        {
+            return Err(std::mem::take(&mut self.diagnostics));
+        }
+        Ok(ProgramAST { declarations })
+    }
+
+    pub fn warnings(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    pub fn line_map(&self) -> &LineMap {
+        self.lexer.line_map()
+    }
+
+    // SS: panic-mode recovery, skips tokens up to and including the next ; or up to the }
+    // closing a block that was opened while skipping. A } that closes an enclosing block is
+    // left for the block to consume, except at file scope where there is no such block.
+    fn synchronize(&mut self, at_file_scope: bool) {
+        let mut depth = 0;
+        loop {
+            match self.peek() {
+                Ok(Tokens::EOF) => return,
+                Ok(Tokens::Semicolon) if depth == 0 => {
+                    let _ = self.advance();
+                    return;
+                }
+                Ok(Tokens::OpenBrace) => {
+                    depth += 1;
+                    let _ = self.advance();
+                }
+                Ok(Tokens::CloseBrace) => {
+                    if depth == 0 && !at_file_scope {
+                        return;
+                    }
+                    let _ = self.advance();
+                    if depth <= 1 {
+                        return;
+                    }
+                    depth -= 1;
+                }
+                Ok(_) => {
+                    let _ = self.advance();
+                }
+                Err(diagnostic) => self.diagnostics.push(diagnostic),
+            }
+        }
+    }
+
+    // SS: a function, variable or structure declaration, the same grammar is used at file
+    // and block scope. Structures defined in the specifiers are declared first, followed by
+    // the function or variable if there is a declarator.
+    fn parse_declaration(&mut self) -> Result<Vec<ExternalDeclarationAST>, Diagnostic> {
+        let specifiers_line = self.next_line()?;
+        let (specifier_type, storage_class, structures) = self.parse_specifiers()?;
+        let mut declarations = structures
+            .into_iter()
+            .map(ExternalDeclarationAST::Struct)
+            .collect::<Vec<_>>();
+
+        // SS: struct s; on its own declares the tag, even if it is already visible
+        if specifier_type.is_structure() && self.peek()? == Tokens::Semicolon {
+            self.advance()?;
+            if storage_class.is_some() {
+                return Err(
+                    self.syntax_error("Storage class specifier without a declarator".to_string())
+                );
+            }
+            if declarations.is_empty() {
+                let (Type::Structure(tag) | Type::Union(tag)) = &specifier_type else {
+                    unreachable!("checked above");
+                };
+                declarations.push(ExternalDeclarationAST::Struct(StructDeclarationAST {
+                    tag: tag.clone(),
+                    is_union: matches!(specifier_type, Type::Union(_)),
+                    members: None,
+                    line: specifiers_line,
+                }));
+            }
+            return Ok(declarations);
+        }
+
+        let line = self.next_line()?;
+        let declarator = self.parse_declarator("declaration name")?;
+        let (name, declared_type, params) = self.process_declarator(declarator, specifier_type)?;
+
+        // SS: a declaration declares a function if the declarator ends in a parameter list
+        declarations.push(match params {
+            Some(params) => {
+                ExternalDeclarationAST::Function(self.parse_function_declaration_rest(
+                    name,
+                    params,
+                    declared_type,
+                    storage_class,
+                    line,
+                )?)
+            }
+            None => ExternalDeclarationAST::Variable(self.parse_variable_declaration_rest(
+                name,
+                declared_type,
+                storage_class,
+                line,
+            )?),
+        });
+        Ok(declarations)
+    }
+
+    // SS: parses the type and storage class specifiers of a declaration, which may appear in
+    // any order, along with the structures they define
+    #[allow(clippy::type_complexity)]
+    fn parse_specifiers(
+        &mut self,
+    ) -> Result<(Type, Option<StorageClassAST>, Vec<StructDeclarationAST>), Diagnostic> {
+        if !is_specifier(&self.peek()?) {
+            self.expect("int", Tokens::Int)?;
+        }
+
+        let (specifier_type, mut storage_classes, structures) = self.parse_specifier_list()?;
+        if storage_classes.len() > 1 {
+            return Err(self.syntax_error("Invalid storage class".to_string()));
+        }
+        Ok((specifier_type, storage_classes.pop(), structures))
+    }
+
+    // SS: the type specifiers of a parameter or a type name, which can refer to a structure
+    // but not define one
+    fn parse_type_specifiers(&mut self) -> Result<Type, Diagnostic> {
+        let (specifier_type, storage_classes, structures) = self.parse_specifier_list()?;
+        if !storage_classes.is_empty() {
+            return Err(self.syntax_error("Invalid storage class".to_string()));
+        }
+        if let Some(structure) = structures.first() {
+            return Err(self.syntax_error(format!(
+                "Structure {:?} cannot be defined here",
+                structure.tag
+            )));
+        }
+        Ok(specifier_type)
+    }
+
+    // SS: collects specifiers up to the first token that is not one
+    #[allow(clippy::type_complexity)]
+    fn parse_specifier_list(
+        &mut self,
+    ) -> Result<(Type, Vec<StorageClassAST>, Vec<StructDeclarationAST>), Diagnostic> {
+        let mut type_specifiers = vec![];
+        let mut structure_types = vec![];
+        let mut structures = vec![];
+        let mut storage_classes = vec![];
+        loop {
+            match self.peek()? {
+                Tokens::Static => storage_classes.push(StorageClassAST::Static),
+                Tokens::Extern => storage_classes.push(StorageClassAST::Extern),
+                Tokens::Struct | Tokens::Union => {
+                    let (structure_type, definitions) = self.parse_structure_specifier()?;
+                    structure_types.push(structure_type);
+                    structures.extend(definitions);
+                    continue;
+                }
+                token if is_type_specifier(&token) => type_specifiers.push(token),
+                _ => break,
+            }
+            self.advance()?;
+        }
+
+        let specifier_type = self.parse_type(&type_specifiers, structure_types)?;
+        Ok((specifier_type, storage_classes, structures))
+    }
+
+    // SS: struct-or-union-specifier := ("struct" | "union") identifier [ "{" member { member } "}" ]
+    // member := specifiers declarator ";"
+    // Returns the structure type along with the definitions of it and of any structure
+    // defined inside its member list, innermost first since C gives them all the same scope.
+    fn parse_structure_specifier(
+        &mut self,
+    ) -> Result<(Type, Vec<StructDeclarationAST>), Diagnostic> {
+        self.nested(|parser| {
+            let line = parser.next_line()?;
+            let is_union = parser.advance()? == Tokens::Union;
+            let tag = parser.expect_identifier("structure tag")?;
+            let structure_type = if is_union {
+                Type::Union(tag.clone())
+            } else {
+                Type::Structure(tag.clone())
+            };
+            if parser.peek()? != Tokens::OpenBrace {
+                return Ok((structure_type, vec![]));
+            }
+
+            parser.advance()?;
+            let mut definitions = vec![];
+            let mut members = vec![];
+            while parser.peek()? != Tokens::CloseBrace {
+                let (base_type, storage_classes, structures) = parser.parse_specifier_list()?;
+                if !storage_classes.is_empty() {
+                    return Err(parser.syntax_error("Invalid storage class".to_string()));
+                }
+                definitions.extend(structures);
+                let member_line = parser.next_line()?;
+                let declarator = parser.parse_declarator("member name")?;
+                let (name, member_type, params) =
+                    parser.process_declarator(declarator, base_type)?;
+                if params.is_some() {
+                    return Err(
+                        parser.syntax_error(format!("Member {:?} declared as a function", name))
+                    );
+                }
+                parser.expect(";", Tokens::Semicolon)?;
+                members.push(MemberDeclarationAST {
+                    name,
+                    member_type,
+                    line: member_line,
+                });
+            }
+            if members.is_empty() {
+                return Err(parser.syntax_error(format!("Structure {:?} has no members", tag)));
+            }
+            parser.advance()?;
+
+            definitions.push(StructDeclarationAST {
+                tag,
+                is_union,
+                members: Some(members),
+                line,
+            });
+            Ok((structure_type, definitions))
+        })
+    }
+
+    // SS: a type name as it appears in a cast or sizeof, i.e. type specifiers followed by a
+    // declarator without a name
+    fn parse_type_name(&mut self) -> Result<Type, Diagnostic> {
+        let base_type = self.parse_type_specifiers()?;
+        let declarator = self.parse_abstract_declarator()?;
+        self.process_abstract_declarator(declarator, base_type)
+    }
+
+    // SS: works out the type named by a list of type specifiers, which may appear in any
+    // order. long long is the same as long on x64. A structure type cannot be combined with
+    // any other type specifier.
+    fn parse_type(
+        &self,
+        type_specifiers: &[Tokens],
+        mut structure_types: Vec<Type>,
+    ) -> Result<Type, Diagnostic> {
+        if !structure_types.is_empty() {
+            if structure_types.len() > 1 || !type_specifiers.is_empty() {
+                return Err(self.syntax_error("Invalid type specifier".to_string()));
+            }
+            return Ok(structure_types.pop().unwrap());
+        }
+
+        let count = |token: Tokens| {
+            type_specifiers
+                .iter()
+                .filter(|specifier| **specifier == token)
+                .count()
+        };
+        let (ints, longs, signeds, unsigneds, doubles, chars) = (
+            count(Tokens::Int),
+            count(Tokens::Long),
+            count(Tokens::Signed),
+            count(Tokens::Unsigned),
+            count(Tokens::Double),
+            count(Tokens::Char),
+        );
+
+        if type_specifiers.is_empty()
+            || ints > 1
+            || longs > 2
+            || signeds + unsigneds > 1
+            || (doubles > 0 && type_specifiers.len() > 1)
+            || (chars > 0 && type_specifiers.len() > chars + signeds + unsigneds)
+            || chars > 1
+        {
+            return Err(self.syntax_error("Invalid type specifier".to_string()));
+        }
+
+        Ok(if doubles > 0 {
+            Type::Double
+        } else if chars > 0 && signeds > 0 {
+            Type::SChar
+        } else if chars > 0 && unsigneds > 0 {
+            Type::UChar
+        } else if chars > 0 {
+            Type::Char
+        } else if unsigneds > 0 && longs > 0 {
+            Type::ULong
+        } else if unsigneds > 0 {
+            Type::UInt
+        } else if longs > 0 {
+            Type::Long
+        } else {
+            Type::Int
+        })
+    }
+
+    // SS: declarator := "*" declarator | direct-declarator
+    // direct-declarator := ( identifier | "(" declarator ")" ) [ param-list ] { "[" const "]" }
+    fn parse_declarator(&mut self, expected_name: &str) -> Result<Declarator, Diagnostic> {
+        self.nested(|parser| match parser.peek()? {
+            Tokens::Star => {
+                parser.advance()?;
+                Ok(Declarator::Pointer(Box::new(
+                    parser.parse_declarator(expected_name)?,
+                )))
+            }
+            Tokens::OpenParen => {
+                parser.advance()?;
+                let declarator = parser.parse_declarator(expected_name)?;
+                parser.expect(")", Tokens::CloseParen)?;
+                if parser.peek()? == Tokens::OpenParen {
+                    return Err(
+                        parser.syntax_error("Function pointers are not supported".to_string())
+                    );
+                }
+                parser.parse_declarator_suffixes(declarator)
+            }
+            _ => {
+                let declarator = Declarator::Identifier(parser.expect_identifier(expected_name)?);
+                if parser.peek()? == Tokens::OpenParen {
+                    parser.advance()?;
+                    let params = parser.parse_params()?;
+                    parser.expect(")", Tokens::CloseParen)?;
+                    parser.parse_declarator_suffixes(Declarator::Function(
+                        params,
+                        Box::new(declarator),
+                    ))
+                } else {
+                    parser.parse_declarator_suffixes(declarator)
+                }
+            }
+        })
+    }
+
+    // SS: the array dimensions following a direct declarator, an empty dimension is only
+    // allowed for a parameter and marked with a size of zero
+    fn parse_declarator_suffixes(
+        &mut self,
+        mut declarator: Declarator,
+    ) -> Result<Declarator, Diagnostic> {
+        while self.peek()? == Tokens::OpenBracket {
+            self.advance()?;
+            let size = if self.peek()? == Tokens::CloseBracket {
+                0
+            } else {
+                self.parse_array_size()?
+            };
+            self.expect("]", Tokens::CloseBracket)?;
+            declarator = Declarator::Array(Box::new(declarator), size);
+        }
+        Ok(declarator)
+    }
+
+    fn parse_array_size(&mut self) -> Result<usize, Diagnostic> {
+        let size = match self.peek()? {
+            Tokens::Constant(val) | Tokens::LongConstant(val) => u64::try_from(val).ok(),
+            Tokens::UnsignedConstant(val) | Tokens::UnsignedLongConstant(val) => Some(val),
+            token => {
+                return Err(self.syntax_error(format!(
+                    "Expected array size, but found {:?}",
+                    token.to_string()
+                )));
+            }
+        };
+        match size.and_then(|size| usize::try_from(size).ok()) {
+            Some(size) if size > 0 => {
+                self.advance()?;
+                Ok(size)
+            }
+            _ => Err(self.syntax_error("Array size must be positive".to_string())),
+        }
+    }
+
+    // SS: applies the declarator to the base type, working from the outside in. Returns the
+    // declared name and type, along with the parameter names if it declares a function.
+    fn process_declarator(
+        &self,
+        declarator: Declarator,
+        base_type: Type,
+    ) -> Result<(String, Type, Option<Vec<String>>), Diagnostic> {
+        match declarator {
+            Declarator::Identifier(name) => {
+                if has_incomplete_array(&base_type) {
+                    return Err(self.syntax_error(format!("Array size missing for {:?}", name)));
+                }
+                Ok((name, base_type, None))
+            }
+            Declarator::Pointer(inner) => {
+                self.process_declarator(*inner, Type::Pointer(Box::new(base_type)))
+            }
+            Declarator::Array(inner, size) => self.process_declarator(
+                *inner,
+                Type::Array {
+                    element: Box::new(base_type),
+                    size,
+                },
+            ),
+            Declarator::Function(params, inner) => {
+                let Declarator::Identifier(name) = *inner else {
+                    return Err(
+                        self.syntax_error("Function pointers are not supported".to_string())
+                    );
+                };
+                if matches!(base_type, Type::Array { .. }) {
+                    return Err(self.syntax_error(format!("Function {:?} returns an array", name)));
+                }
+                let mut param_names = vec![];
+                let mut param_types = vec![];
+                for (param_type, param_declarator) in params {
+                    // SS: a parameter declared as an array is a pointer to its first element
+                    let param_declarator = match param_declarator {
+                        Declarator::Array(inner, _)
+                            if matches!(*inner, Declarator::Identifier(_)) =>
+                        {
+                            Declarator::Pointer(inner)
+                        }
+                        param_declarator => param_declarator,
+                    };
+                    let (param_name, param_type, param_params) =
+                        self.process_declarator(param_declarator, param_type)?;
+                    if param_params.is_some() {
+                        return Err(
+                            self.syntax_error("Function pointers are not supported".to_string())
+                        );
+                    }
+                    param_names.push(param_name);
+                    param_types.push(param_type);
+                }
+                Ok((
+                    name,
+                    Type::Function {
+                        params: param_types,
+                        ret: Box::new(base_type),
+                    },
+                    Some(param_names),
+                ))
+            }
+            Declarator::Abstract => unreachable!("only abstract declarators omit the name"),
+        }
+    }
+
+    // SS: abstract-declarator := "*" [ abstract-declarator ] | direct-abstract-declarator
+    // direct-abstract-declarator := "(" abstract-declarator ")" { "[" const "]" }
+    //                             | { "[" const "]" }
+    fn parse_abstract_declarator(&mut self) -> Result<Declarator, Diagnostic> {
+        self.nested(|parser| match parser.peek()? {
+            Tokens::Star => {
+                parser.advance()?;
+                Ok(Declarator::Pointer(Box::new(
+                    parser.parse_abstract_declarator()?,
+                )))
+            }
+            Tokens::OpenParen => {
+                parser.advance()?;
+                let declarator = parser.parse_abstract_declarator()?;
+                parser.expect(")", Tokens::CloseParen)?;
+                parser.parse_declarator_suffixes(declarator)
+            }
+            _ => parser.parse_declarator_suffixes(Declarator::Abstract),
+        })
+    }
+
+    fn process_abstract_declarator(
+        &self,
+        declarator: Declarator,
+        base_type: Type,
+    ) -> Result<Type, Diagnostic> {
+        match declarator {
+            Declarator::Abstract => {
+                if has_incomplete_array(&base_type) {
+                    return Err(self.syntax_error("Array size missing".to_string()));
+                }
+                Ok(base_type)
+            }
+            Declarator::Pointer(inner) => {
+                self.process_abstract_declarator(*inner, Type::Pointer(Box::new(base_type)))
+            }
+            Declarator::Array(inner, size) => self.process_abstract_declarator(
+                *inner,
+                Type::Array {
+                    element: Box::new(base_type),
+                    size,
+                },
+            ),
+            Declarator::Identifier(_) | Declarator::Function(..) => {
+                unreachable!("abstract declarators have no name or parameter list")
+            }
+        }
+    }
+
+    // SS: parses everything after the parameter list, i.e. either a body or a terminating
+    // semicolon
+    fn parse_function_declaration_rest(
+        &mut self,
+        name: String,
+        params: Vec<String>,
+        function_type: Type,
+        storage_class: Option<StorageClassAST>,
+        line: usize,
+    ) -> Result<FunctionAST, Diagnostic> {
+        let body = if self.peek()? == Tokens::Semicolon {
+            self.advance()?;
+            None
+        } else {
+            Some(self.parse_block()?)
+        };
+
+        Ok(FunctionAST {
+            name,
+            params,
+            body,
+            function_type,
+            storage_class,
+            line,
+        })
+    }
+
+    fn parse_params(&mut self) -> Result<Vec<(Type, Declarator)>, Diagnostic> {
+        if !is_type_specifier(&self.peek()?) {
+            self.expect("void", Tokens::Void)?;
+            return Ok(vec![]);
+        }
+
+        let mut params = vec![];
+        loop {
+            let param_type = self.parse_type_specifiers()?;
+            params.push((param_type, self.parse_declarator("parameter name")?));
+
+            if self.peek()? != Tokens::Comma {
+                break;
+            }
+            self.advance()?;
+        }
+
+        Ok(params)
+    }
+
+    fn parse_block(&mut self) -> Result<BlockAST, Diagnostic> {
+        self.expect("{", Tokens::OpenBrace)?;
+
+        let mut items = vec![];
+        loop {
+            match self.peek() {
+                Ok(Tokens::CloseBrace | Tokens::EOF) => break,
+                Ok(_) => match self.parse_block_item() {
+                    Ok(item) => items.extend(item),
+                    Err(diagnostic) => {
+                        self.diagnostics.push(diagnostic);
+                        self.synchronize(false);
+                    }
+                },
+                Err(diagnostic) => self.diagnostics.push(diagnostic),
+            }
+        }
+
+        self.expect("}", Tokens::CloseBrace)?;
+
+        Ok(BlockAST { items })
+    }
+
+    // SS: a declaration that defines a structure yields more than one block item
+    fn parse_block_item(&mut self) -> Result<Vec<BlockItemAST>, Diagnostic> {
+        if !is_specifier(&self.peek()?) {
+            return Ok(vec![BlockItemAST::Statement(self.parse_stmt()?)]);
+        }
+
+        Ok(self
+            .parse_declaration()?
+            .into_iter()
+            .map(|declaration| match declaration {
+                ExternalDeclarationAST::Function(function) => {
+                    BlockItemAST::FunctionDeclaration(function)
+                }
+                ExternalDeclarationAST::Variable(declaration) => {
+                    BlockItemAST::Declaration(declaration)
+                }
+                ExternalDeclarationAST::Struct(declaration) => {
+                    BlockItemAST::StructDeclaration(declaration)
+                }
+            })
+            .collect())
+    }
+
+    // SS: a declaration in the header of a for loop, which cannot declare a function
+    fn parse_variable_declaration(&mut self) -> Result<DeclarationAST, Diagnostic> {
+        let (specifier_type, storage_class, structures) = self.parse_specifiers()?;
+        if let Some(structure) = structures.first() {
+            return Err(self.syntax_error(format!(
+                "Structure {:?} declared in for loop header",
+                structure.tag
+            )));
+        }
+        let line = self.next_line()?;
+        let declarator = self.parse_declarator("variable name")?;
+        let (name, var_type, params) = self.process_declarator(declarator, specifier_type)?;
+        if params.is_some() {
+            return Err(
+                self.syntax_error(format!("Function {:?} declared in for loop header", name))
+            );
+        }
+        self.parse_variable_declaration_rest(name, var_type, storage_class, line)
+    }
+
+    fn parse_variable_declaration_rest(
+        &mut self,
+        name: String,
+        var_type: Type,
+        storage_class: Option<StorageClassAST>,
+        line: usize,
+    ) -> Result<DeclarationAST, Diagnostic> {
+        let init = if self.peek()? == Tokens::Equal {
+            self.advance()?;
+            Some(self.parse_initializer()?)
+        } else {
+            None
+        };
+
+        self.expect(";", Tokens::Semicolon)?;
+
+        Ok(DeclarationAST {
+            name,
+            init,
+            var_type,
+            storage_class,
+            line,
+        })
+    }
+
+    // SS: initializer := expr | "{" initializer { "," initializer } [ "," ] "}"
+    fn parse_initializer(&mut self) -> Result<InitializerAST, Diagnostic> {
+        self.nested(|parser| {
+            if parser.peek()? != Tokens::OpenBrace {
+                return Ok(InitializerAST::Single(parser.parse_expr()?));
+            }
+
+            parser.advance()?;
+            let mut initializers = vec![];
+            loop {
+                initializers.push(parser.parse_initializer()?);
+                if parser.peek()? != Tokens::Comma {
+                    break;
+                }
+                parser.advance()?;
+                if parser.peek()? == Tokens::CloseBrace {
+                    break;
+                }
+            }
+            parser.expect("}", Tokens::CloseBrace)?;
+            Ok(InitializerAST::Compound(initializers))
+        })
+    }
+
+    fn parse_stmt(&mut self) -> Result<StmtAST, Diagnostic> {
+        self.nested(|parser| {
+            match parser.peek()? {
+                Tokens::Return => {
+                    parser.advance()?;
+                    let expr = parser.parse_expr()?;
+                    parser.expect(";", Tokens::Semicolon)?;
+                    Ok(StmtAST::Return(expr))
+                }
+                Tokens::If => {
+                    parser.advance()?;
+                    parser.expect("(", Tokens::OpenParen)?;
+                    let condition = parser.parse_expr()?;
+                    parser.expect(")", Tokens::CloseParen)?;
+                    let then_stmt = parser.parse_stmt()?;
+
+                    // SS: a dangling else binds to the innermost if
+                    let else_stmt = if parser.peek()? == Tokens::Else {
+                        parser.advance()?;
+                        Some(Box::new(parser.parse_stmt()?))
+                    } else {
+                        None
+                    };
+
+                    Ok(StmtAST::If {
+                        condition,
+                        then_stmt: Box::new(then_stmt),
+                        else_stmt,
+                    })
+                }
+                Tokens::OpenBrace => Ok(StmtAST::Compound(parser.parse_block()?)),
+                Tokens::Break => {
+                    parser.advance()?;
+                    let line = parser.previous_line;
+                    parser.expect(";", Tokens::Semicolon)?;
+                    Ok(StmtAST::Break { label: None, line })
+                }
+                Tokens::Continue => {
+                    parser.advance()?;
+                    let line = parser.previous_line;
+                    parser.expect(";", Tokens::Semicolon)?;
+                    Ok(StmtAST::Continue { label: None, line })
+                }
+                Tokens::While => {
+                    parser.advance()?;
+                    parser.expect("(", Tokens::OpenParen)?;
+                    let condition = parser.parse_expr()?;
+                    parser.expect(")", Tokens::CloseParen)?;
+                    let body = parser.parse_stmt()?;
+                    Ok(StmtAST::While {
+                        condition,
+                        body: Box::new(body),
+                        label: None,
+                    })
+                }
+                Tokens::Do => {
+                    parser.advance()?;
+                    let body = parser.parse_stmt()?;
+                    parser.expect("while", Tokens::While)?;
+                    parser.expect("(", Tokens::OpenParen)?;
+                    let condition = parser.parse_expr()?;
+                    parser.expect(")", Tokens::CloseParen)?;
+                    parser.expect(";", Tokens::Semicolon)?;
+                    Ok(StmtAST::DoWhile {
+                        body: Box::new(body),
+                        condition,
+                        label: None,
+                    })
+                }
+                Tokens::For => {
+                    parser.advance()?;
+                    parser.expect("(", Tokens::OpenParen)?;
+                    let init = if is_specifier(&parser.peek()?) {
+                        ForInitAST::InitDecl(parser.parse_variable_declaration()?)
+                    } else {
+                        let expr = parser.parse_optional_expr(Tokens::Semicolon)?;
+                        parser.expect(";", Tokens::Semicolon)?;
+                        ForInitAST::InitExpr(expr)
+                    };
+                    let condition = parser.parse_optional_expr(Tokens::Semicolon)?;
+                    parser.expect(";", Tokens::Semicolon)?;
+                    let post = parser.parse_optional_expr(Tokens::CloseParen)?;
+                    parser.expect(")", Tokens::CloseParen)?;
+                    let body = parser.parse_stmt()?;
+                    Ok(StmtAST::For {
+                        init,
+                        condition,
+                        post,
+                        body: Box::new(body),
+                        label: None,
+                    })
+                }
+                Tokens::Switch => {
+                    parser.advance()?;
+                    parser.expect("(", Tokens::OpenParen)?;
+                    let condition = parser.parse_expr()?;
+                    parser.expect(")", Tokens::CloseParen)?;
+                    let body = parser.parse_stmt()?;
+                    Ok(StmtAST::Switch {
+                        condition,
+                        body: Box::new(body),
+                        label: None,
+                        cases: vec![],
+                    })
+                }
+                Tokens::Case => {
+                    parser.advance()?;
+                    let line = parser.previous_line;
+                    let value = parser.parse_expr()?;
+                    parser.expect(":", Tokens::Colon)?;
+                    let body = parser.parse_stmt()?;
+                    Ok(StmtAST::Case {
+                        value,
+                        body: Box::new(body),
+                        label: None,
+                        line,
+                    })
+                }
+                Tokens::Default => {
+                    parser.advance()?;
+                    let line = parser.previous_line;
+                    parser.expect(":", Tokens::Colon)?;
+                    let body = parser.parse_stmt()?;
+                    Ok(StmtAST::Default {
+                        body: Box::new(body),
+                        label: None,
+                        line,
+                    })
+                }
+                Tokens::Goto => {
+                    parser.advance()?;
+                    let line = parser.previous_line;
+                    let label = parser.expect_identifier("label")?;
+                    parser.expect(";", Tokens::Semicolon)?;
+                    Ok(StmtAST::Goto { label, line })
+                }
+                Tokens::Identifier(label) if parser.peek_second()? == Tokens::Colon => {
+                    parser.advance()?;
+                    let line = parser.previous_line;
+                    parser.advance()?;
+                    let body = parser.parse_stmt()?;
+                    Ok(StmtAST::Labeled {
+                        label,
+                        body: Box::new(body),
+                        line,
+                    })
+                }
+                Tokens::Semicolon => {
+                    parser.advance()?;
+                    Ok(StmtAST::Null)
+                }
+                _ => {
+                    let expr = parser.parse_expr()?;
+                    parser.expect(";", Tokens::Semicolon)?;
+                    Ok(StmtAST::Expression(expr))
+                }
+            }
+        })
+    }
+
+    fn parse_expr(&mut self) -> Result<ExprAST, Diagnostic> {
+        self.parse_binary_expr(0)
+    }
+
+    fn parse_optional_expr(&mut self, terminator: Tokens) -> Result<Option<ExprAST>, Diagnostic> {
+        if self.peek()? == terminator {
+            Ok(None)
+        } else {
+            Ok(Some(self.parse_expr()?))
+        }
+    }
+
+    // SS: precedence climbing, assignment operators and the conditional operator are
+    // right-associative, all other binary operators are left-associative
+    fn parse_binary_expr(&mut self, min_precedence: u8) -> Result<ExprAST, Diagnostic> {
+        self.nested(|parser| {
+            let mut left = parser.parse_factor()?;
+
+            loop {
+                let token = parser.peek()?;
+                let Some(precedence) = precedence(&token) else {
+                    break;
+                };
+                if precedence < min_precedence {
+                    break;
+                }
+                parser.advance()?;
+                let line = parser.previous_line;
+
+                left = if token == Tokens::Question {
+                    // SS: the middle operand is parsed as if it were parenthesized
+                    let then_expr = parser.parse_expr()?;
+                    parser.expect(":", Tokens::Colon)?;
+                    let else_expr = parser.parse_binary_expr(precedence)?;
+                    ExprAST::Conditional {
+                        condition: Box::new(left),
+                        then_expr: Box::new(then_expr),
+                        else_expr: Box::new(else_expr),
+                    }
+                } else if token == Tokens::Equal {
+                    let right = parser.parse_binary_expr(precedence)?;
+                    ExprAST::Assignment {
+                        lhs: Box::new(left),
+                        rhs: Box::new(right),
+                        line,
+                    }
+                } else if let Some(op) = compound_assignment_operator(&token) {
+                    let right = parser.parse_binary_expr(precedence)?;
+                    ExprAST::CompoundAssignment {
+                        op,
+                        lhs: Box::new(left),
+                        rhs: Box::new(right),
+                        line,
+                    }
+                } else {
+                    let op = binary_operator(&token).unwrap();
+                    let right = parser.parse_binary_expr(precedence + 1)?;
+                    ExprAST::Binary(op, Box::new(left), Box::new(right))
+                };
+            }
+
+            Ok(left)
+        })
+    }
+
+    fn parse_factor(&mut self) -> Result<ExprAST, Diagnostic> {
+        self.nested(|parser| {
+            let expr = parser.parse_prefix_factor()?;
+            parser.parse_postfix_operators(expr)
+        })
+    }
+
+    // SS: postfix operators bind tighter than any prefix operator
+    fn parse_postfix_operators(&mut self, mut expr: ExprAST) -> Result<ExprAST, Diagnostic> {
+        loop {
+            let op = match self.peek()? {
+                Tokens::Increment => IncrementOperatorAST::PostIncrement,
+                Tokens::Decrement => IncrementOperatorAST::PostDecrement,
+                Tokens::OpenBracket => {
+                    self.advance()?;
+                    let line = self.previous_line;
+                    let index = self.parse_expr()?;
+                    self.expect("]", Tokens::CloseBracket)?;
+                    expr = ExprAST::Subscript {
+                        array: Box::new(expr),
+                        index: Box::new(index),
+                        line,
+                    };
+                    continue;
+                }
+                Tokens::Dot => {
+                    self.advance()?;
+                    let line = self.previous_line;
+                    let member = self.expect_identifier("member name")?;
+                    expr = ExprAST::Dot {
+                        structure: Box::new(expr),
+                        member,
+                        line,
+                    };
+                    continue;
+                }
+                Tokens::Arrow => {
+                    self.advance()?;
+                    let line = self.previous_line;
+                    let member = self.expect_identifier("member name")?;
+                    expr = ExprAST::Arrow {
+                        pointer: Box::new(expr),
+                        member,
+                        line,
+                    };
+                    continue;
+                }
+                _ => break,
+            };
+            self.advance()?;
+            expr = ExprAST::Increment {
+                op,
+                operand: Box::new(expr),
+                line: self.previous_line,
+            };
+        }
+
+        Ok(expr)
+    }
+
+    fn parse_prefix_factor(&mut self) -> Result<ExprAST, Diagnostic> {
+        let token = self.peek()?;
+        if !matches!(
+            token,
+            Tokens::Constant(_)
+                | Tokens::LongConstant(_)
+                | Tokens::UnsignedConstant(_)
+                | Tokens::UnsignedLongConstant(_)
+                | Tokens::DoubleConstant(_)
+                | Tokens::CharConstant(_)
+                | Tokens::StringLiteral(_)
+                | Tokens::Identifier(_)
+                | Tokens::Minus
+                | Tokens::Tilde
+                | Tokens::Bang
+                | Tokens::Increment
+                | Tokens::Decrement
+                | Tokens::Star
+                | Tokens::Ampersand
+                | Tokens::Sizeof
+                | Tokens::OpenParen
+        ) {
+            return Err(self.syntax_error(format!(
+                "Expected expression, but found {:?}",
+                token.to_string()
+            )));
+        }
+
+        match self.advance()? {
+            // SS: a constant has the first of its possible types that can represent it, the
+            // lexer has already made octal and hex constants unsigned where necessary
+            Tokens::Constant(val) => Ok(ExprAST::Constant(match i32::try_from(val) {
+                Ok(val) => Constant::Int(val),
+                Err(_) => Constant::Long(val),
+            })),
+            Tokens::LongConstant(val) => Ok(ExprAST::Constant(Constant::Long(val))),
+            Tokens::UnsignedConstant(val) => Ok(ExprAST::Constant(match u32::try_from(val) {
+                Ok(val) => Constant::UInt(val),
+                Err(_) => Constant::ULong(val),
+            })),
+            Tokens::UnsignedLongConstant(val) => Ok(ExprAST::Constant(Constant::ULong(val))),
+            Tokens::DoubleConstant(val) => {
+                if val.is_infinite() {
+                    self.diagnostics.push(Diagnostic::warning(
+                        self.previous_span.clone(),
+                        "Floating constant exceeds range of double".to_string(),
+                    ));
+                }
+                Ok(ExprAST::Constant(Constant::Double(val)))
+            }
+            // SS: a character constant is just another way to write an int
+            Tokens::CharConstant(val) => Ok(ExprAST::Constant(Constant::Int(val as i32))),
+            // SS: adjacent string literals are concatenated
+            Tokens::StringLiteral(mut bytes) => {
+                while let Tokens::StringLiteral(next) = self.peek()? {
+                    self.advance()?;
+                    bytes.extend(next);
+                }
+                Ok(ExprAST::String(bytes))
+            }
+            Tokens::Identifier(name) => {
+                let line = self.previous_line;
+                if self.peek()? == Tokens::OpenParen {
+                    self.advance()?;
+                    let args = self.parse_args()?;
+                    Ok(ExprAST::FunctionCall { name, args, line })
+                } else {
+                    Ok(ExprAST::Var { name, line })
+                }
+            }
+            Tokens::Minus => {
+                let expr = self.parse_factor()?;
+                Ok(ExprAST::Unary(UnaryOperatorAST::Negate, Box::new(expr)))
+            }
+            Tokens::Tilde => {
+                let expr = self.parse_factor()?;
+                Ok(ExprAST::Unary(UnaryOperatorAST::Complement, Box::new(expr)))
+            }
+            Tokens::Bang => {
+                let expr = self.parse_factor()?;
+                Ok(ExprAST::Unary(UnaryOperatorAST::Not, Box::new(expr)))
+            }
+            Tokens::Increment => {
+                let line = self.previous_line;
+                let expr = self.parse_factor()?;
+                Ok(ExprAST::Increment {
+                    op: IncrementOperatorAST::PreIncrement,
+                    operand: Box::new(expr),
+                    line,
+                })
+            }
+            Tokens::Decrement => {
+                let line = self.previous_line;
+                let expr = self.parse_factor()?;
+                Ok(ExprAST::Increment {
+                    op: IncrementOperatorAST::PreDecrement,
+                    operand: Box::new(expr),
+                    line,
+                })
+            }
+            Tokens::Star => {
+                let line = self.previous_line;
+                let expr = self.parse_factor()?;
+                Ok(ExprAST::Dereference {
+                    expr: Box::new(expr),
+                    line,
+                })
+            }
+            Tokens::Ampersand => {
+                let line = self.previous_line;
+                let expr = self.parse_factor()?;
+                Ok(ExprAST::AddressOf {
+                    expr: Box::new(expr),
+                    line,
+                })
+            }
+            // SS: the operand of sizeof is either a parenthesized type name or an expression,
+            // which may itself start with a parenthesis
+            Tokens::Sizeof => {
+                let line = self.previous_line;
+                if self.peek()? != Tokens::OpenParen {
+                    let expr = self.parse_factor()?;
+                    return Ok(ExprAST::SizeOf {
+                        expr: Box::new(expr),
+                        line,
+                    });
+                }
+                self.advance()?;
+                if is_type_specifier(&self.peek()?) {
+                    let target_type = self.parse_type_name()?;
+                    self.expect(")", Tokens::CloseParen)?;
+                    return Ok(ExprAST::SizeOfType { target_type, line });
+                }
+                let expr = self.parse_expr()?;
+                self.expect(")", Tokens::CloseParen)?;
+                let expr = self.parse_postfix_operators(expr)?;
+                Ok(ExprAST::SizeOf {
+                    expr: Box::new(expr),
+                    line,
+                })
+            }
+            // SS: a parenthesized type name is a cast, which binds like a prefix operator
+            Tokens::OpenParen if is_type_specifier(&self.peek()?) => {
+                let line = self.previous_line;
+                let target_type = self.parse_type_name()?;
+                self.expect(")", Tokens::CloseParen)?;
+                let expr = self.parse_factor()?;
+                Ok(ExprAST::Cast {
+                    target_type,
+                    expr: Box::new(expr),
+                    line,
+                })
+            }
+            Tokens::OpenParen => {
+                let expr = self.parse_expr()?;
+                self.expect(")", Tokens::CloseParen)?;
+                Ok(expr)
+            }
+            _ => unreachable!("checked above"),
+        }
+    }
+
+    // SS: parses the argument list of a call, the opening parenthesis has already been consumed
+    fn parse_args(&mut self) -> Result<Vec<ExprAST>, Diagnostic> {
+        let mut args = vec![];
+        if self.peek()? != Tokens::CloseParen {
+            loop {
+                args.push(self.parse_expr()?);
+                if self.peek()? != Tokens::Comma {
+                    break;
+                }
+                self.advance()?;
+            }
+        }
+        self.expect(")", Tokens::CloseParen)?;
+        Ok(args)
+    }
+
+    fn expect(&mut self, expected_string: &str, token: Tokens) -> Result<Tokens, Diagnostic> {
+        let actual = self.peek()?;
+        if actual == token {
+            self.advance()
+        } else {
+            Err(self.syntax_error(format!(
+                "Expected token {:?}, but found {:?}",
+                expected_string,
+                actual.to_string()
+            )))
         }
     }
 
-    pub fn parse(&mut self) -> Result<ProgramAST, String> {
-        let ast = self.parse_function_definition()?;
+    fn expect_identifier(&mut self, expected_string: &str) -> Result<String, Diagnostic> {
+        match self.peek()? {
+            Tokens::Identifier(name) => {
+                self.advance()?;
+                Ok(name)
+            }
+            token => Err(self.syntax_error(format!(
+                "Expected {}, but found {:?}",
+                expected_string,
+                token.to_string()
+            ))),
+        }
+    }
+
+    // SS: runs a recursive parse function one level deeper
+    fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, Diagnostic>,
+    ) -> Result<T, Diagnostic> {
+        if self.depth >= MAX_NESTING_DEPTH {
+            return Err(Diagnostic::error(
+                self.lookahead_span(),
+                "Nested too deeply".to_string(),
+            ));
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    // SS: reports an error at the lookahead token, which is left unconsumed so that recovery
+    // can start from it
+    fn syntax_error(&self, message: String) -> Diagnostic {
+        Diagnostic::error(self.lookahead_span(), format!("Syntax error: {}", message))
+    }
+
+    fn lookahead_span(&self) -> Span {
+        match &self.current_symbol {
+            Some((_, span, _)) => span.clone(),
+            None => self.previous_span.clone(),
+        }
+    }
+
+    fn next_line(&mut self) -> Result<usize, Diagnostic> {
+        self.peek()?;
+        Ok(self.current_symbol.as_ref().unwrap().2)
+    }
+
+    fn peek(&mut self) -> Result<Tokens, Diagnostic> {
+        if self.current_symbol.is_none() {
+            self.current_symbol = Some(self.next_symbol()?);
+        }
+        Ok(self.current_symbol.as_ref().unwrap().0.clone())
+    }
+
+    // SS: the token after the lookahead token
+    fn peek_second(&mut self) -> Result<Tokens, Diagnostic> {
+        self.peek()?;
+        if self.next_symbol.is_none() {
+            let token = self.lexer.next_token()?;
+            self.next_symbol = Some((token, self.lexer.span(), self.lexer.input_line()));
+        }
+        Ok(self.next_symbol.as_ref().unwrap().0.clone())
+    }
+
+    fn next_symbol(&mut self) -> Result<Symbol, Diagnostic> {
+        match self.next_symbol.take() {
+            Some(symbol) => Ok(symbol),
+            None => {
+                let token = self.lexer.next_token()?;
+                Ok((token, self.lexer.span(), self.lexer.input_line()))
+            }
+        }
+    }
+
+    fn advance(&mut self) -> Result<Tokens, Diagnostic> {
+        let (token, span, line) = match self.current_symbol.take() {
+            Some(symbol) => symbol,
+            None => self.next_symbol()?,
+        };
+        self.previous_span = span;
+        self.previous_line = line;
+        Ok(token)
+    }
+}
+
+// SS: a declarator before the base type is applied to it. Declarators read inside out, so
+// the type derivation nearest the name is the last one applied.
+enum Declarator {
+    Identifier(String),
+    // SS: the missing name of an abstract declarator
+    Abstract,
+    Pointer(Box<Declarator>),
+    Array(Box<Declarator>, usize),
+    Function(Vec<(Type, Declarator)>, Box<Declarator>),
+}
+
+// SS: only a parameter may omit the size of an array, which is recorded as zero
+fn has_incomplete_array(declared_type: &Type) -> bool {
+    match declared_type {
+        Type::Array { size: 0, .. } => true,
+        Type::Array { element, .. } | Type::Pointer(element) => has_incomplete_array(element),
+        _ => false,
+    }
+}
+
+fn is_specifier(token: &Tokens) -> bool {
+    is_type_specifier(token) || matches!(token, Tokens::Static | Tokens::Extern)
+}
+
+fn is_type_specifier(token: &Tokens) -> bool {
+    matches!(
+        token,
+        Tokens::Int
+            | Tokens::Long
+            | Tokens::Signed
+            | Tokens::Unsigned
+            | Tokens::Double
+            | Tokens::Char
+            | Tokens::Struct
+            | Tokens::Union
+    )
+}
+
+fn precedence(token: &Tokens) -> Option<u8> {
+    match token {
+        Tokens::Star | Tokens::Slash | Tokens::Percent => Some(50),
+        Tokens::Plus | Tokens::Minus => Some(45),
+        Tokens::Less | Tokens::LessEqual | Tokens::Greater | Tokens::GreaterEqual => Some(35),
+        Tokens::EqualEqual | Tokens::BangEqual => Some(30),
+        Tokens::AmpersandAmpersand => Some(10),
+        Tokens::PipePipe => Some(5),
+        Tokens::Question => Some(3),
+        Tokens::Equal
+        | Tokens::PlusEqual
+        | Tokens::MinusEqual
+        | Tokens::StarEqual
+        | Tokens::SlashEqual
+        | Tokens::PercentEqual => Some(1),
+        _ => None,
+    }
+}
+
+fn binary_operator(token: &Tokens) -> Option<BinaryOperatorAST> {
+    match token {
+        Tokens::Star => Some(BinaryOperatorAST::Multiply),
+        Tokens::Slash => Some(BinaryOperatorAST::Divide),
+        Tokens::Percent => Some(BinaryOperatorAST::Remainder),
+        Tokens::Plus => Some(BinaryOperatorAST::Add),
+        Tokens::Minus => Some(BinaryOperatorAST::Subtract),
+        Tokens::Less => Some(BinaryOperatorAST::LessThan),
+        Tokens::LessEqual => Some(BinaryOperatorAST::LessOrEqual),
+        Tokens::Greater => Some(BinaryOperatorAST::GreaterThan),
+        Tokens::GreaterEqual => Some(BinaryOperatorAST::GreaterOrEqual),
+        Tokens::EqualEqual => Some(BinaryOperatorAST::Equal),
+        Tokens::BangEqual => Some(BinaryOperatorAST::NotEqual),
+        Tokens::AmpersandAmpersand => Some(BinaryOperatorAST::And),
+        Tokens::PipePipe => Some(BinaryOperatorAST::Or),
+        _ => None,
+    }
+}
+
+fn compound_assignment_operator(token: &Tokens) -> Option<BinaryOperatorAST> {
+    match token {
+        Tokens::PlusEqual => Some(BinaryOperatorAST::Add),
+        Tokens::MinusEqual => Some(BinaryOperatorAST::Subtract),
+        Tokens::StarEqual => Some(BinaryOperatorAST::Multiply),
+        Tokens::SlashEqual => Some(BinaryOperatorAST::Divide),
+        Tokens::PercentEqual => Some(BinaryOperatorAST::Remainder),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostic::Diagnostic;
+    use crate::lexer::Lexer;
+    use crate::parse_ast::{
+        BinaryOperatorAST, BlockAST, BlockItemAST, DeclarationAST, ExprAST, ExternalDeclarationAST,
+        ForInitAST, FunctionAST, IncrementOperatorAST, InitializerAST, MemberDeclarationAST,
+        ProgramAST, StmtAST, StorageClassAST, StructDeclarationAST, UnaryOperatorAST,
+    };
+    use crate::parser::Parser;
+    use crate::types::{Constant, Type};
+    use std::thread;
+
+    fn messages(ast: Result<ProgramAST, Vec<Diagnostic>>) -> Vec<String> {
+        ast.unwrap_err()
+            .iter()
+            .map(|diagnostic| diagnostic.to_string())
+            .collect()
+    }
+
+    fn body_items(ast: &ProgramAST) -> Vec<BlockItemAST> {
+        match &ast.declarations[0] {
+            ExternalDeclarationAST::Function(function) => {
+                function.body.as_ref().unwrap().items.clone()
+            }
+            declaration => panic!("expected a function, found {:?}", declaration),
+        }
+    }
+
+    #[test]
+    fn test_parser() {
+        // SS: arrange
+        let input = r"int main(void) {
+                            return 2;
+                    }"
+        .to_string();
+
+        // SS: act
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let ast = parser.parse().unwrap();
+
+        // SS: assert
+        assert_eq!(
+            ast,
+            ProgramAST {
+                declarations: vec![ExternalDeclarationAST::Function(FunctionAST {
+                    name: "main".to_string(),
+                    params: vec![],
+                    body: Some(BlockAST {
+                        items: vec![BlockItemAST::Statement(StmtAST::Return(ExprAST::Constant(
+                            Constant::Int(2)
+                        )))],
+                    }),
+                    function_type: Type::Function {
+                        params: vec![],
+                        ret: Box::new(Type::Int),
+                    },
+                    storage_class: None,
+                    line: 1,
+                })]
+            }
+        );
+    }
+
+    #[test]
+    fn test_parser_unary() {
+        // SS: arrange
+        let input = r"int main(void) {
+                            return -(~(!2));
+                    }"
+        .to_string();
+
+        // SS: act
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let ast = parser.parse().unwrap();
+
+        // SS: assert
+        assert_eq!(
+            ast,
+            ProgramAST {
+                declarations: vec![ExternalDeclarationAST::Function(FunctionAST {
+                    name: "main".to_string(),
+                    params: vec![],
+                    body: Some(BlockAST {
+                        items: vec![BlockItemAST::Statement(StmtAST::Return(ExprAST::Unary(
+                            UnaryOperatorAST::Negate,
+                            Box::new(ExprAST::Unary(
+                                UnaryOperatorAST::Complement,
+                                Box::new(ExprAST::Unary(
+                                    UnaryOperatorAST::Not,
+                                    Box::new(ExprAST::Constant(Constant::Int(2)))
+                                ))
+                            ))
+                        )))],
+                    }),
+                    function_type: Type::Function {
+                        params: vec![],
+                        ret: Box::new(Type::Int),
+                    },
+                    storage_class: None,
+                    line: 1,
+                })]
+            }
+        );
+    }
+
+    #[test]
+    fn test_parser_binary_precedence() {
+        // SS: arrange
+        let input = r"int main(void) {
+                            return 1 - 2 - 3 * -4 % (5 + 6);
+                    }"
+        .to_string();
+
+        // SS: act
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let ast = parser.parse().unwrap();
+
+        // SS: assert
+        // (1 - 2) - ((3 * -4) % (5 + 6))
+        assert_eq!(
+            body_items(&ast),
+            vec![BlockItemAST::Statement(StmtAST::Return(ExprAST::Binary(
+                BinaryOperatorAST::Subtract,
+                Box::new(ExprAST::Binary(
+                    BinaryOperatorAST::Subtract,
+                    Box::new(ExprAST::Constant(Constant::Int(1))),
+                    Box::new(ExprAST::Constant(Constant::Int(2)))
+                )),
+                Box::new(ExprAST::Binary(
+                    BinaryOperatorAST::Remainder,
+                    Box::new(ExprAST::Binary(
+                        BinaryOperatorAST::Multiply,
+                        Box::new(ExprAST::Constant(Constant::Int(3))),
+                        Box::new(ExprAST::Unary(
+                            UnaryOperatorAST::Negate,
+                            Box::new(ExprAST::Constant(Constant::Int(4)))
+                        ))
+                    )),
+                    Box::new(ExprAST::Binary(
+                        BinaryOperatorAST::Add,
+                        Box::new(ExprAST::Constant(Constant::Int(5))),
+                        Box::new(ExprAST::Constant(Constant::Int(6)))
+                    ))
+                ))
+            )))]
+        );
+    }
+
+    #[test]
+    fn test_parser_logical_precedence() {
+        // SS: arrange
+        let input = r"int main(void) {
+                            return 1 || 2 && 3 == 4 < 5;
+                    }"
+        .to_string();
+
+        // SS: act
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let ast = parser.parse().unwrap();
+
+        // SS: assert
+        // 1 || (2 && (3 == (4 < 5)))
+        assert_eq!(
+            body_items(&ast),
+            vec![BlockItemAST::Statement(StmtAST::Return(ExprAST::Binary(
+                BinaryOperatorAST::Or,
+                Box::new(ExprAST::Constant(Constant::Int(1))),
+                Box::new(ExprAST::Binary(
+                    BinaryOperatorAST::And,
+                    Box::new(ExprAST::Constant(Constant::Int(2))),
+                    Box::new(ExprAST::Binary(
+                        BinaryOperatorAST::Equal,
+                        Box::new(ExprAST::Constant(Constant::Int(3))),
+                        Box::new(ExprAST::Binary(
+                            BinaryOperatorAST::LessThan,
+                            Box::new(ExprAST::Constant(Constant::Int(4))),
+                            Box::new(ExprAST::Constant(Constant::Int(5)))
+                        ))
+                    ))
+                ))
+            )))]
+        );
+    }
+
+    #[test]
+    fn test_parser_fail_missing_operand() {
+        // SS: arrange
+        let input = r"int main(void) {
+                            return 1 + ;
+                    }"
+        .to_string();
+
+        // SS: act
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let ast = parser.parse();
+
+        // SS: assert
+        assert_eq!(
+            messages(ast),
+            vec![r#"Line 2: Syntax error: Expected expression, but found ";""#]
+        );
+    }
+
+    #[test]
+    fn test_parser_declarations_and_assignment() {
+        // SS: arrange
+        let input = r"int main(void) {
+                            int a;
+                            int b = 2;
+                            a = b += 3;
+                            ;
+                            return a++ + --b;
+                    }"
+        .to_string();
+
+        // SS: act
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let ast = parser.parse().unwrap();
+
+        // SS: assert
+        let var = |name: &str, line: usize| ExprAST::Var {
+            name: name.to_string(),
+            line,
+        };
+        assert_eq!(
+            body_items(&ast),
+            vec![
+                BlockItemAST::Declaration(DeclarationAST {
+                    name: "a".to_string(),
+                    init: None,
+                    var_type: Type::Int,
+                    storage_class: None,
+                    line: 2,
+                }),
+                BlockItemAST::Declaration(DeclarationAST {
+                    name: "b".to_string(),
+                    init: Some(InitializerAST::Single(ExprAST::Constant(Constant::Int(2)))),
+                    var_type: Type::Int,
+                    storage_class: None,
+                    line: 3,
+                }),
+                BlockItemAST::Statement(StmtAST::Expression(ExprAST::Assignment {
+                    lhs: Box::new(var("a", 4)),
+                    rhs: Box::new(ExprAST::CompoundAssignment {
+                        op: BinaryOperatorAST::Add,
+                        lhs: Box::new(var("b", 4)),
+                        rhs: Box::new(ExprAST::Constant(Constant::Int(3))),
+                        line: 4,
+                    }),
+                    line: 4,
+                })),
+                BlockItemAST::Statement(StmtAST::Null),
+                BlockItemAST::Statement(StmtAST::Return(ExprAST::Binary(
+                    BinaryOperatorAST::Add,
+                    Box::new(ExprAST::Increment {
+                        op: IncrementOperatorAST::PostIncrement,
+                        operand: Box::new(var("a", 6)),
+                        line: 6,
+                    }),
+                    Box::new(ExprAST::Increment {
+                        op: IncrementOperatorAST::PreDecrement,
+                        operand: Box::new(var("b", 6)),
+                        line: 6,
+                    })
+                ))),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parser_dangling_else() {
+        // SS: arrange
+        let input = r"int main(void) {
+                            if (1)
+                                if (2)
+                                    return 3;
+                                else
+                                    return 4;
+                    }"
+        .to_string();
+
+        // SS: act
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let ast = parser.parse().unwrap();
+
+        // SS: assert
+        // the else belongs to the inner if
+        assert_eq!(
+            body_items(&ast),
+            vec![BlockItemAST::Statement(StmtAST::If {
+                condition: ExprAST::Constant(Constant::Int(1)),
+                then_stmt: Box::new(StmtAST::If {
+                    condition: ExprAST::Constant(Constant::Int(2)),
+                    then_stmt: Box::new(StmtAST::Return(ExprAST::Constant(Constant::Int(3)))),
+                    else_stmt: Some(Box::new(StmtAST::Return(ExprAST::Constant(Constant::Int(
+                        4
+                    ))))),
+                }),
+                else_stmt: None,
+            })]
+        );
+    }
+
+    #[test]
+    fn test_parser_conditional() {
+        // SS: arrange
+        let input = r"int main(void) {
+                            return 1 ? 2 : 3 ? 4 : 5;
+                    }"
+        .to_string();
+
+        // SS: act
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let ast = parser.parse().unwrap();
+
+        // SS: assert
+        // the conditional operator is right-associative: 1 ? 2 : (3 ? 4 : 5)
+        assert_eq!(
+            body_items(&ast),
+            vec![BlockItemAST::Statement(StmtAST::Return(
+                ExprAST::Conditional {
+                    condition: Box::new(ExprAST::Constant(Constant::Int(1))),
+                    then_expr: Box::new(ExprAST::Constant(Constant::Int(2))),
+                    else_expr: Box::new(ExprAST::Conditional {
+                        condition: Box::new(ExprAST::Constant(Constant::Int(3))),
+                        then_expr: Box::new(ExprAST::Constant(Constant::Int(4))),
+                        else_expr: Box::new(ExprAST::Constant(Constant::Int(5))),
+                    }),
+                }
+            ))]
+        );
+    }
+
+    #[test]
+    fn test_parser_shadowing_in_nested_blocks() {
+        // SS: arrange
+        let input = r"int main(void) {
+                            int a = 1;
+                            {
+                                int a = 2;
+                            }
+                            return a;
+                    }"
+        .to_string();
+
+        // SS: act
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let ast = parser.parse().unwrap();
+
+        // SS: assert
+        // the parser keeps the source names, identifier resolution renames them later
+        assert_eq!(
+            body_items(&ast),
+            vec![
+                BlockItemAST::Declaration(DeclarationAST {
+                    name: "a".to_string(),
+                    init: Some(InitializerAST::Single(ExprAST::Constant(Constant::Int(1)))),
+                    var_type: Type::Int,
+                    storage_class: None,
+                    line: 2,
+                }),
+                BlockItemAST::Statement(StmtAST::Compound(BlockAST {
+                    items: vec![BlockItemAST::Declaration(DeclarationAST {
+                        name: "a".to_string(),
+                        init: Some(InitializerAST::Single(ExprAST::Constant(Constant::Int(2)))),
+                        var_type: Type::Int,
+                        storage_class: None,
+                        line: 4,
+                    })],
+                })),
+                BlockItemAST::Statement(StmtAST::Return(ExprAST::Var {
+                    name: "a".to_string(),
+                    line: 6,
+                })),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parser_loops() {
+        // SS: arrange
+        let input = r"int main(void) {
+                            for (int i = 0; ; )
+                                while (1)
+                                    do break; while (0);
+                            for (;;) continue;
+                    }"
+        .to_string();
+
+        // SS: act
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let ast = parser.parse().unwrap();
+
+        // SS: assert
+        assert_eq!(
+            body_items(&ast),
+            vec![
+                BlockItemAST::Statement(StmtAST::For {
+                    init: ForInitAST::InitDecl(DeclarationAST {
+                        name: "i".to_string(),
+                        init: Some(InitializerAST::Single(ExprAST::Constant(Constant::Int(0)))),
+                        var_type: Type::Int,
+                        storage_class: None,
+                        line: 2,
+                    }),
+                    condition: None,
+                    post: None,
+                    body: Box::new(StmtAST::While {
+                        condition: ExprAST::Constant(Constant::Int(1)),
+                        body: Box::new(StmtAST::DoWhile {
+                            body: Box::new(StmtAST::Break {
+                                label: None,
+                                line: 4
+                            }),
+                            condition: ExprAST::Constant(Constant::Int(0)),
+                            label: None,
+                        }),
+                        label: None,
+                    }),
+                    label: None,
+                }),
+                BlockItemAST::Statement(StmtAST::For {
+                    init: ForInitAST::InitExpr(None),
+                    condition: None,
+                    post: None,
+                    body: Box::new(StmtAST::Continue {
+                        label: None,
+                        line: 5
+                    }),
+                    label: None,
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parser_switch() {
+        // SS: arrange
+        let input = r"int main(void) {
+                            switch (3) {
+                                case 1 + 1:
+                                default: ;
+                            }
+                    }"
+        .to_string();
+
+        // SS: act
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let ast = parser.parse().unwrap();
+
+        // SS: assert
+        assert_eq!(
+            body_items(&ast),
+            vec![BlockItemAST::Statement(StmtAST::Switch {
+                condition: ExprAST::Constant(Constant::Int(3)),
+                body: Box::new(StmtAST::Compound(BlockAST {
+                    items: vec![BlockItemAST::Statement(StmtAST::Case {
+                        value: ExprAST::Binary(
+                            BinaryOperatorAST::Add,
+                            Box::new(ExprAST::Constant(Constant::Int(1))),
+                            Box::new(ExprAST::Constant(Constant::Int(1))),
+                        ),
+                        body: Box::new(StmtAST::Default {
+                            body: Box::new(StmtAST::Null),
+                            label: None,
+                            line: 4,
+                        }),
+                        label: None,
+                        line: 3,
+                    })]
+                })),
+                label: None,
+                cases: vec![],
+            })]
+        );
+    }
+
+    #[test]
+    fn test_parser_goto_and_labels() {
+        // SS: arrange
+        let input = r"int main(void) {
+                        end:
+                            x = 1;
+                            goto end;
+                    }"
+        .to_string();
+
+        // SS: act
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let ast = parser.parse().unwrap();
+
+        // SS: assert
+        assert_eq!(
+            body_items(&ast),
+            vec![
+                BlockItemAST::Statement(StmtAST::Labeled {
+                    label: "end".to_string(),
+                    body: Box::new(StmtAST::Expression(ExprAST::Assignment {
+                        lhs: Box::new(ExprAST::Var {
+                            name: "x".to_string(),
+                            line: 3,
+                        }),
+                        rhs: Box::new(ExprAST::Constant(Constant::Int(1))),
+                        line: 3,
+                    })),
+                    line: 2,
+                }),
+                BlockItemAST::Statement(StmtAST::Goto {
+                    label: "end".to_string(),
+                    line: 4,
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parser_fail_do_without_while() {
+        // SS: arrange
+        let input = r"int main(void) {
+                            do return 1;
+                            return 2;
+                    }"
+        .to_string();
+
+        // SS: act
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let ast = parser.parse();
+
+        // SS: assert
+        assert_eq!(
+            messages(ast),
+            vec![r#"Line 3: Syntax error: Expected token "while", but found "Return""#]
+        );
+    }
+
+    #[test]
+    fn test_parser_fail_else_without_if() {
+        // SS: arrange
+        let input = r"int main(void) {
+                            else return 1;
+                    }"
+        .to_string();
+
+        // SS: act
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let ast = parser.parse();
+
+        // SS: assert
+        assert_eq!(
+            messages(ast),
+            vec![r#"Line 2: Syntax error: Expected expression, but found "Else""#]
+        );
+    }
+
+    #[test]
+    fn test_parser_fail_declaration_without_name() {
+        // SS: arrange
+        let input = r"int main(void) {
+                            int = 2;
+                            return 0;
+                    }"
+        .to_string();
 
-        // SS: ensure we have consumed all tokens
-        if self.advance()? != Tokens::EOF {
-            return Err(format!(
-                "Line {}: Syntax error: Expected end of file, but found {:?}",
-                self.lexer.current_line,
-                self.current_symbol.as_ref().unwrap().to_string()
-            ));
-        }
+        // SS: act
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let ast = parser.parse();
 
-        Ok(ProgramAST {
-            function_definition: ast,
-        })
+        // SS: assert
+        assert_eq!(
+            messages(ast),
+            vec![r#"Line 2: Syntax error: Expected declaration name, but found "=""#]
+        );
     }
 
-    pub(crate) fn parse_function_definition(&mut self) -> Result<FunctionAST, String> {
-        self.expect("int", Tokens::Int)?;
+    #[test]
+    fn test_parser_fail() {
+        // SS: arrange
+        let input = r"inta main(void) {
+                            return 2;
+                    }"
+        .to_string();
 
-        // SS: parse the function name
-        let Tokens::Identifier(name) = self.advance()? else {
-            return Err(format!(
-                "Line {}: Syntax error: Expected function name, but found {:?}",
-                self.lexer.current_line,
-                self.current_symbol.as_ref().unwrap().to_string()
-            ));
-        };
+        // SS: act
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let ast = parser.parse();
 
-        self.expect("(", Tokens::OpenParen)?;
-        self.expect("void", Tokens::Void)?;
-        self.expect(")", Tokens::CloseParen)?;
-        self.expect("{", Tokens::OpenBrace)?;
+        // SS: assert
+        assert_eq!(
+            messages(ast),
+            vec![r#"Line 1: Syntax error: Expected token "int", but found "Identifier(inta)""#]
+        );
+    }
 
-        let stmt = self.parse_stmt()?;
+    #[test]
+    fn test_parser_fail_missing_semicolon() {
+        // SS: arrange
+        let input = r"/* A single backslash is not a valid token. */
+\"
+        .to_string();
 
-        self.expect("}", Tokens::CloseBrace)?;
+        // SS: act
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let ast = parser.parse();
 
-        Ok(FunctionAST {
-            name: name.to_string(),
-            body: stmt,
-        })
+        // SS: assert
+        assert_eq!(messages(ast), vec![r#"Line 2: Unexpected character"#]);
     }
 
-    fn parse_stmt(&mut self) -> Result<StmtAST, String> {
-        self.expect("return", Tokens::Return)?;
-        let expr = self.parse_expr()?;
-        self.expect(";", Tokens::Semicolon)?;
-        Ok(StmtAST::Return(expr))
+    #[test]
+    fn test_parser_fail_unclosed_paren() {
+        // SS: arrange
+        let input = r"int main( {
+    return 0;
+}"
+        .to_string();
+
+        // SS: act
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let ast = parser.parse();
+
+        // SS: assert
+        assert_eq!(
+            messages(ast),
+            vec![r##"Line 1: Syntax error: Expected token "void", but found "{""##]
+        );
     }
 
-    fn parse_expr(&mut self) -> Result<ExprAST, String> {
-        let Tokens::Constant(val) = self.advance()? else {
-            return Err(format!(
-                "Line {}: Syntax error: Expected constant, but found {:?}",
-                self.lexer.current_line,
-                self.current_symbol.as_ref().unwrap().to_string()
-            ));
-        };
-        Ok(ExprAST::Constant(val))
+    #[test]
+    fn test_parser_fail_nested_too_deeply() {
+        // SS: arrange
+        let input = format!(
+            "int {}p;\nint main(void) {{\n    return 0;\n}}",
+            "*".repeat(1001)
+        );
+
+        // SS: act
+        // SS: a test thread's stack is too small to reach the limit in a debug build
+        let messages = thread::Builder::new()
+            .stack_size(256 * 1024 * 1024)
+            .spawn(move || messages(Parser::new(Lexer::new(input)).parse()))
+            .unwrap()
+            .join()
+            .unwrap();
+
+        // SS: assert
+        assert_eq!(messages, vec!["Line 1: Nested too deeply"]);
     }
 
-    fn expect(&mut self, expected_string: &str, token: Tokens) -> Result<Tokens, String> {
-        let actual = self.advance()?;
-        if actual == token {
-            Ok(actual)
-        } else {
-            Err(format!(
-                "Line {}: Syntax error: Expected token {:?}, but found {:?}",
-                self.lexer.current_line,
-                expected_string,
-                actual.to_string()
-            ))
-        }
+    #[test]
+    fn test_parser_functions() {
+        // SS: arrange
+        let input = r"int add(int a, int b);
+                    int main(void) {
+                            int putchar(int c);
+                            return add(1, putchar(65));
+                    }"
+        .to_string();
+
+        // SS: act
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let ast = parser.parse().unwrap();
+
+        // SS: assert
+        assert_eq!(
+            ast,
+            ProgramAST {
+                declarations: vec![
+                    ExternalDeclarationAST::Function(FunctionAST {
+                        name: "add".to_string(),
+                        params: vec!["a".to_string(), "b".to_string()],
+                        body: None,
+                        function_type: Type::Function {
+                            params: vec![Type::Int, Type::Int],
+                            ret: Box::new(Type::Int),
+                        },
+                        storage_class: None,
+                        line: 1,
+                    }),
+                    ExternalDeclarationAST::Function(FunctionAST {
+                        name: "main".to_string(),
+                        params: vec![],
+                        body: Some(BlockAST {
+                            items: vec![
+                                BlockItemAST::FunctionDeclaration(FunctionAST {
+                                    name: "putchar".to_string(),
+                                    params: vec!["c".to_string()],
+                                    body: None,
+                                    function_type: Type::Function {
+                                        params: vec![Type::Int],
+                                        ret: Box::new(Type::Int),
+                                    },
+                                    storage_class: None,
+                                    line: 3,
+                                }),
+                                BlockItemAST::Statement(StmtAST::Return(ExprAST::FunctionCall {
+                                    name: "add".to_string(),
+                                    args: vec![
+                                        ExprAST::Constant(Constant::Int(1)),
+                                        ExprAST::FunctionCall {
+                                            name: "putchar".to_string(),
+                                            args: vec![ExprAST::Constant(Constant::Int(65))],
+                                            line: 4,
+                                        },
+                                    ],
+                                    line: 4,
+                                })),
+                            ],
+                        }),
+                        function_type: Type::Function {
+                            params: vec![],
+                            ret: Box::new(Type::Int),
+                        },
+                        storage_class: None,
+                        line: 2,
+                    }),
+                ]
+            }
+        );
     }
 
-    fn peek(&mut self) -> Result<Tokens, String> {
-        if self.current_symbol.is_none() {
-            self.current_symbol = Some(self.lexer.next_token()?);
-        }
-        Ok(self.current_symbol.clone().unwrap())
+    #[test]
+    fn test_parser_fail_missing_parameter_name() {
+        // SS: arrange
+        let input = r"int add(int a, int) {
+                            return a;
+                    }"
+        .to_string();
+
+        // SS: act
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let ast = parser.parse();
+
+        // SS: assert
+        assert_eq!(
+            messages(ast),
+            vec![r##"Line 1: Syntax error: Expected parameter name, but found ")""##]
+        );
     }
 
-    fn advance(&mut self) -> Result<Tokens, String> {
-        let token = if let Some(t) = self.current_symbol.take() {
-            t
-        } else {
-            self.lexer.next_token()?
-        };
-        Ok(token)
+    #[test]
+    fn test_parser_recovers_after_errors() {
+        // SS: arrange
+        let input = r"int main(void) {
+                            int a = ;
+                            if (a +) {
+                                a = 1;
+                            }
+                            return a
+                    }
+                    int foo( {
+                            return 1;
+                    }
+                    int bar(void) {
+                            return 1 @ 2;
+                    }"
+        .to_string();
+
+        // SS: act
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let ast = parser.parse();
+
+        // SS: assert
+        assert_eq!(
+            messages(ast),
+            vec![
+                r#"Line 2: Syntax error: Expected expression, but found ";""#,
+                r#"Line 3: Syntax error: Expected expression, but found ")""#,
+                r#"Line 7: Syntax error: Expected token ";", but found "}""#,
+                r#"Line 8: Syntax error: Expected token "void", but found "{""#,
+                r#"Line 12: Unexpected character"#,
+            ]
+        );
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use crate::lexer::Lexer;
-    use crate::parse_ast::{ExprAST, FunctionAST, ProgramAST, StmtAST};
-    use crate::parser::Parser;
+    #[test]
+    fn test_parser_diagnostic_span() {
+        // SS: arrange
+        let input = r"int main(void) {
+    return 1 +;
+}"
+        .to_string();
+
+        // SS: act
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let diagnostics = parser.parse().unwrap_err();
+
+        // SS: assert
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].span.line, 2);
+        assert_eq!(diagnostics[0].span.column, 15);
+    }
 
     #[test]
-    fn test_parser() {
+    fn test_parser_constant_types() {
         // SS: arrange
         let input = r"int main(void) {
-                            return 2;
+                            4294967297;
+                            2147483647;
+                            2147483648;
+                            4294967295u;
+                            10l;
+                            5ul;
+                            1.5;
                     }"
         .to_string();
 
@@ -128,25 +2208,158 @@ mod tests {
         let ast = parser.parse().unwrap();
 
         // SS: assert
+        let constants = [
+            Constant::Long(4294967297),
+            Constant::Int(2147483647),
+            Constant::Long(2147483648),
+            Constant::UInt(4294967295),
+            Constant::Long(10),
+            Constant::ULong(5),
+            Constant::Double(1.5),
+        ];
         assert_eq!(
-            ast,
-            ProgramAST {
-                function_definition: FunctionAST {
-                    name: "main".to_string(),
-                    body: StmtAST::Return(ExprAST::Constant(2)),
-                }
-            }
+            body_items(&ast),
+            constants
+                .into_iter()
+                .map(
+                    |constant| BlockItemAST::Statement(StmtAST::Expression(ExprAST::Constant(
+                        constant
+                    )))
+                )
+                .collect::<Vec<_>>()
         );
+        assert!(parser.warnings().is_empty());
     }
 
     #[test]
-    fn test_parser_fail() {
+    fn test_parser_types_and_casts() {
         // SS: arrange
-        let input = r"inta main(void) {
-                            return 2;
+        let input = r"unsigned long f(long int a, double b);
+                    int main(void) {
+                            signed long long x = (unsigned) 3;
+                            return (double) x;
                     }"
         .to_string();
 
+        // SS: act
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let ast = parser.parse().unwrap();
+
+        // SS: assert
+        let ExternalDeclarationAST::Function(f) = &ast.declarations[0] else {
+            panic!("expected a function declaration");
+        };
+        assert_eq!(
+            f.function_type,
+            Type::Function {
+                params: vec![Type::Long, Type::Double],
+                ret: Box::new(Type::ULong),
+            }
+        );
+        let ExternalDeclarationAST::Function(main) = &ast.declarations[1] else {
+            panic!("expected a function definition");
+        };
+        assert_eq!(
+            main.body.as_ref().unwrap().items,
+            vec![
+                BlockItemAST::Declaration(DeclarationAST {
+                    name: "x".to_string(),
+                    init: Some(InitializerAST::Single(ExprAST::Cast {
+                        target_type: Type::UInt,
+                        expr: Box::new(ExprAST::Constant(Constant::Int(3))),
+                        line: 3,
+                    })),
+                    var_type: Type::Long,
+                    storage_class: None,
+                    line: 3,
+                }),
+                BlockItemAST::Statement(StmtAST::Return(ExprAST::Cast {
+                    target_type: Type::Double,
+                    expr: Box::new(ExprAST::Var {
+                        name: "x".to_string(),
+                        line: 4,
+                    }),
+                    line: 4,
+                })),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parser_invalid_type_specifiers() {
+        // SS: arrange
+        let inputs = ["double long x;", "unsigned signed int y;", "int int z;"];
+
+        for input in inputs {
+            // SS: act
+            let lexer = Lexer::new(input.to_string());
+            let mut parser = Parser::new(lexer);
+            let result = parser.parse();
+
+            // SS: assert
+            assert_eq!(
+                messages(result),
+                vec!["Line 1: Syntax error: Invalid type specifier".to_string()],
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_parser_storage_classes() {
+        // SS: arrange
+        let input = r"static int a = 1;
+                    extern int b;
+                    int static f(void);"
+            .to_string();
+
+        // SS: act
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let ast = parser.parse().unwrap();
+
+        // SS: assert
+        assert_eq!(
+            ast.declarations,
+            vec![
+                ExternalDeclarationAST::Variable(DeclarationAST {
+                    name: "a".to_string(),
+                    init: Some(InitializerAST::Single(ExprAST::Constant(Constant::Int(1)))),
+                    var_type: Type::Int,
+                    storage_class: Some(StorageClassAST::Static),
+                    line: 1,
+                }),
+                ExternalDeclarationAST::Variable(DeclarationAST {
+                    name: "b".to_string(),
+                    init: None,
+                    var_type: Type::Int,
+                    storage_class: Some(StorageClassAST::Extern),
+                    line: 2,
+                }),
+                ExternalDeclarationAST::Function(FunctionAST {
+                    name: "f".to_string(),
+                    params: vec![],
+                    body: None,
+                    function_type: Type::Function {
+                        params: vec![],
+                        ret: Box::new(Type::Int),
+                    },
+                    storage_class: Some(StorageClassAST::Static),
+                    line: 3,
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parser_fail_invalid_specifiers() {
+        // SS: arrange
+        let input = r"static extern int a;
+                    int int b;"
+            .to_string();
+
         // SS: act
         let lexer = Lexer::new(input);
         let mut parser = Parser::new(lexer);
@@ -154,38 +2367,222 @@ mod tests {
 
         // SS: assert
         assert_eq!(
-            ast,
-            Err(
-                r#"Line 1: Syntax error: Expected token "int", but found "Identifier(inta)""#
-                    .to_string()
-            )
+            messages(ast),
+            vec![
+                "Line 1: Syntax error: Invalid storage class",
+                "Line 2: Syntax error: Invalid type specifier",
+            ]
         );
     }
 
     #[test]
-    fn test_parser_fail_missing_semicolon() {
+    fn test_parser_pointer_and_array_declarators() {
         // SS: arrange
-        let input = r"/* A single backslash is not a valid token. */
-\"
+        let input = r"int *p;
+                    long a[2][3];
+                    int *(b[4]);
+                    double (*q)[5];
+                    int f(int a[], unsigned **u);"
+            .to_string();
+
+        // SS: act
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let ast = parser.parse().unwrap();
+
+        // SS: assert
+        let types = ast
+            .declarations
+            .iter()
+            .map(|declaration| match declaration {
+                ExternalDeclarationAST::Variable(variable) => variable.var_type.clone(),
+                ExternalDeclarationAST::Function(function) => function.function_type.clone(),
+                ExternalDeclarationAST::Struct(_) => unreachable!("no structures declared"),
+            })
+            .collect::<Vec<_>>();
+        let pointer = |t: Type| Type::Pointer(Box::new(t));
+        let array = |t: Type, size: usize| Type::Array {
+            element: Box::new(t),
+            size,
+        };
+        assert_eq!(
+            types,
+            vec![
+                pointer(Type::Int),
+                array(array(Type::Long, 3), 2),
+                array(pointer(Type::Int), 4),
+                pointer(array(Type::Double, 5)),
+                Type::Function {
+                    params: vec![pointer(Type::Int), pointer(pointer(Type::UInt))],
+                    ret: Box::new(Type::Int),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parser_initializers_and_subscripts() {
+        // SS: arrange
+        let input = r"int main(void) {
+                            int a[2] = {1, 2,};
+                            return *&a[1] + (long *) 0;
+                    }"
         .to_string();
 
+        // SS: act
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let ast = parser.parse().unwrap();
+
+        // SS: assert
+        assert_eq!(
+            body_items(&ast),
+            vec![
+                BlockItemAST::Declaration(DeclarationAST {
+                    name: "a".to_string(),
+                    init: Some(InitializerAST::Compound(vec![
+                        InitializerAST::Single(ExprAST::Constant(Constant::Int(1))),
+                        InitializerAST::Single(ExprAST::Constant(Constant::Int(2))),
+                    ])),
+                    var_type: Type::Array {
+                        element: Box::new(Type::Int),
+                        size: 2,
+                    },
+                    storage_class: None,
+                    line: 2,
+                }),
+                BlockItemAST::Statement(StmtAST::Return(ExprAST::Binary(
+                    BinaryOperatorAST::Add,
+                    Box::new(ExprAST::Dereference {
+                        expr: Box::new(ExprAST::AddressOf {
+                            expr: Box::new(ExprAST::Subscript {
+                                array: Box::new(ExprAST::Var {
+                                    name: "a".to_string(),
+                                    line: 3,
+                                }),
+                                index: Box::new(ExprAST::Constant(Constant::Int(1))),
+                                line: 3,
+                            }),
+                            line: 3,
+                        }),
+                        line: 3,
+                    }),
+                    Box::new(ExprAST::Cast {
+                        target_type: Type::Pointer(Box::new(Type::Long)),
+                        expr: Box::new(ExprAST::Constant(Constant::Int(0))),
+                        line: 3,
+                    }),
+                ))),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parser_fail_invalid_declarators() {
+        // SS: arrange
+        let input = r"int a[0];
+                    int b[];
+                    int (*f)(void);
+                    int g(void)[3];"
+            .to_string();
+
         // SS: act
         let lexer = Lexer::new(input);
         let mut parser = Parser::new(lexer);
         let ast = parser.parse();
 
         // SS: assert
-        assert_eq!(ast, Err(r#"Line 1: Unexpected character"#.to_string()));
+        assert_eq!(
+            messages(ast),
+            vec![
+                "Line 1: Syntax error: Array size must be positive",
+                "Line 2: Syntax error: Array size missing for \"b\"",
+                "Line 3: Syntax error: Function pointers are not supported",
+                "Line 4: Syntax error: Function \"g\" returns an array",
+            ]
+        );
     }
 
     #[test]
-    fn test_parser_fail_unclosed_paren() {
+    fn test_parser_structures_and_strings() {
         // SS: arrange
-        let input = r"int main( {
-    return 0;
-}"
+        let input = r#"struct s { char c; union u *next; };
+                    int main(void) {
+                            struct s x;
+                            return sizeof(struct s) + sizeof x.next->c + "a\n"[0];
+                    }"#
         .to_string();
 
+        // SS: act
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let ast = parser.parse().unwrap();
+
+        // SS: assert
+        assert_eq!(
+            ast.declarations[0],
+            ExternalDeclarationAST::Struct(StructDeclarationAST {
+                tag: "s".to_string(),
+                is_union: false,
+                members: Some(vec![
+                    MemberDeclarationAST {
+                        name: "c".to_string(),
+                        member_type: Type::Char,
+                        line: 1,
+                    },
+                    MemberDeclarationAST {
+                        name: "next".to_string(),
+                        member_type: Type::Pointer(Box::new(Type::Union("u".to_string()))),
+                        line: 1,
+                    },
+                ]),
+                line: 1,
+            })
+        );
+        let ExternalDeclarationAST::Function(function) = &ast.declarations[1] else {
+            panic!("expected a function");
+        };
+        let items = &function.body.as_ref().unwrap().items;
+        assert_eq!(
+            items[1],
+            BlockItemAST::Statement(StmtAST::Return(ExprAST::Binary(
+                BinaryOperatorAST::Add,
+                Box::new(ExprAST::Binary(
+                    BinaryOperatorAST::Add,
+                    Box::new(ExprAST::SizeOfType {
+                        target_type: Type::Structure("s".to_string()),
+                        line: 4,
+                    }),
+                    Box::new(ExprAST::SizeOf {
+                        expr: Box::new(ExprAST::Arrow {
+                            pointer: Box::new(ExprAST::Dot {
+                                structure: Box::new(ExprAST::Var {
+                                    name: "x".to_string(),
+                                    line: 4,
+                                }),
+                                member: "next".to_string(),
+                                line: 4,
+                            }),
+                            member: "c".to_string(),
+                            line: 4,
+                        }),
+                        line: 4,
+                    }),
+                )),
+                Box::new(ExprAST::Subscript {
+                    array: Box::new(ExprAST::String(b"a\n".to_vec())),
+                    index: Box::new(ExprAST::Constant(Constant::Int(0))),
+                    line: 4,
+                }),
+            )))
+        );
+    }
+
+    #[test]
+    fn test_parser_fail_anonymous_structure() {
+        // SS: arrange
+        let input = r"struct { int a; };".to_string();
+
         // SS: act
         let lexer = Lexer::new(input);
         let mut parser = Parser::new(lexer);
@@ -193,8 +2590,8 @@ mod tests {
 
         // SS: assert
         assert_eq!(
-            ast,
-            Err(r##"Line 1: Syntax error: Expected token "void", but found "{""##.to_string())
+            messages(ast)[0],
+            "Line 1: Syntax error: Expected structure tag, but found \"{\""
         );
     }
 }
@@ -0,0 +1,1427 @@
+use crate::diagnostic::{Diagnostic, Span};
+use crate::lexer::Lexer;
+use crate::target::Target;
+use crate::tokens::Tokens;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+// SS: includes nested deeper than this are taken to be recursive
+const MAX_INCLUDE_DEPTH: usize = 200;
+
+// SS: the output jumps to a line this far ahead with a line marker rather than newlines
+const MAX_BLANK_LINES: usize = 8;
+
+// SS: longest first, so that the first match is the longest one
+const PUNCTUATORS: [&str; 54] = [
+    "%:%:", "...", "<<=", ">>=", "->", "++", "--", "<<", ">>", "<=", ">=", "==", "!=", "&&", "||",
+    "*=", "/=", "%=", "+=", "-=", "&=", "^=", "|=", "##", "<:", ":>", "<%", "%>", "%:", "[", "]",
+    "(", ")", "{", "}", ".", "&", "*", "+", "-", "~", "!", "/", "%", "<", ">", "^", "|", "?", ":",
+    ";", "=", ",", "#",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PpTokenKind {
+    Identifier,
+    Number,
+    CharConstant,
+    StringLiteral,
+    Punctuator,
+    // SS: a character no token starts with, an unterminated quote or the opening of an
+    // unterminated comment, passed through for the lexer to report
+    Other,
+}
+
+// SS: a preprocessing token, which is coarser than a C token, e.g. 1.2.3 is a single number
+#[derive(Debug, Clone)]
+struct PpToken {
+    kind: PpTokenKind,
+    text: String,
+    line: usize,
+    // SS: 0 for a token produced by a macro expansion, it has no place in the source line
+    column: usize,
+    // SS: whether whitespace comes before the token, which matters when stringizing
+    space_before: bool,
+    // SS: only a # that starts a line starts a directive
+    line_start: bool,
+    // SS: the macros this token came out of. Expanding one of them again would recurse.
+    hide_set: HashSet<String>,
+}
+
+impl PpToken {
+    fn is(&self, text: &str) -> bool {
+        self.kind == PpTokenKind::Punctuator && self.text == text
+    }
+
+    // SS: a token made up by the preprocessor, placed where the token it replaces was
+    fn synthesized(kind: PpTokenKind, text: String, at: &PpToken) -> PpToken {
+        PpToken {
+            kind,
+            text,
+            hide_set: HashSet::new(),
+            ..at.clone()
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Macro {
+    Object(Vec<PpToken>),
+    // SS: a variadic macro has __VA_ARGS__ as its last parameter
+    Function {
+        params: Vec<String>,
+        variadic: bool,
+        body: Vec<PpToken>,
+    },
+}
+
+// SS: the state of an #if, #ifdef or #ifndef and the #elif and #else that belong to it
+struct Conditional {
+    // SS: whether the lines of the current branch are kept
+    active: bool,
+    // SS: whether a branch has been taken, all later ones are skipped then
+    taken: bool,
+    // SS: whether the region around the whole conditional is kept
+    parent_active: bool,
+    seen_else: bool,
+    directive: PpToken,
+}
+
+// SS: replaces directives and expands macros, producing C source for the lexer. Tokens keep
+// their line and column, and the output has a line marker wherever it continues in a
+// different file or skips ahead, so that diagnostics refer to the original source.
+pub(crate) struct Preprocessor {
+    include_dirs: Vec<PathBuf>,
+    target: Target,
+    macros: HashMap<String, Macro>,
+    sources: HashMap<String, String>,
+    diagnostics: Vec<Diagnostic>,
+    include_depth: usize,
+    output: Output,
+}
+
+impl Preprocessor {
+    pub fn new(include_dirs: Vec<PathBuf>, target: Target) -> Self {
+        let mut preprocessor = Preprocessor {
+            include_dirs,
+            target,
+            macros: HashMap::new(),
+            sources: HashMap::new(),
+            diagnostics: vec![],
+            include_depth: 0,
+            output: Output::default(),
+        };
+        // SS: C17 is the standard we follow
+        let predefined = [("__STDC__", "1"), ("__STDC_VERSION__", "201710L")];
+        for (name, value) in predefined.iter().chain(target.predefined_macros()) {
+            preprocessor
+                .macros
+                .insert(name.to_string(), Macro::Object(tokenize(value)));
+        }
+        preprocessor
+    }
+
+    pub fn preprocess(
+        &mut self,
+        source: String,
+        file_name: &str,
+    ) -> Result<String, Vec<Diagnostic>> {
+        self.process_file(source, file_name.into());
+        if self.diagnostics.is_empty() {
+            Ok(std::mem::take(&mut self.output).text)
+        } else {
+            Err(std::mem::take(&mut self.diagnostics))
+        }
+    }
+
+    // SS: the text of a file read while preprocessing, diagnostics show the offending line
+    pub fn source(&self, file_name: &str) -> &str {
+        self.sources.get(file_name).map_or("", String::as_str)
+    }
+
+    fn error(&mut self, file: &Rc<str>, token: &PpToken, message: String) {
+        let span = Span {
+            file: file.clone(),
+            line: token.line,
+            column: token.column.max(1),
+        };
+        self.diagnostics.push(Diagnostic::error(span, message));
+    }
+
+    fn process_file(&mut self, source: String, file: Rc<str>) {
+        let tokens = tokenize(&source);
+        self.sources.insert(file.to_string(), source);
+
+        let mut conditionals: Vec<Conditional> = vec![];
+        let mut text = vec![];
+        let mut start = 0;
+        while start < tokens.len() {
+            let end = (start + 1..tokens.len())
+                .find(|index| tokens[*index].line_start)
+                .unwrap_or(tokens.len());
+            let line = &tokens[start..end];
+            start = end;
+
+            if line[0].is("#") && line[0].line_start {
+                // SS: a directive ends the text before it, a macro invocation cannot span it
+                self.flush(&mut text, &file);
+                self.directive(&line[0], &line[1..], &file, &mut conditionals);
+            } else if conditionals
+                .last()
+                .is_none_or(|conditional| conditional.active)
+            {
+                text.extend(line.iter().cloned());
+            }
+        }
+        self.flush(&mut text, &file);
+
+        for conditional in conditionals {
+            let message = format!("Unterminated #{}", conditional.directive.text);
+            self.error(&file, &conditional.directive, message);
+        }
+    }
+
+    fn flush(&mut self, text: &mut Vec<PpToken>, file: &Rc<str>) {
+        let tokens = self.expand(std::mem::take(text), file);
+        for token in &tokens {
+            self.output.emit(token, file);
+        }
+    }
+
+    fn directive(
+        &mut self,
+        hash: &PpToken,
+        tokens: &[PpToken],
+        file: &Rc<str>,
+        conditionals: &mut Vec<Conditional>,
+    ) {
+        // SS: the null directive, a # on its own
+        let Some(name) = tokens.first() else {
+            return;
+        };
+        let rest = &tokens[1..];
+        let active = conditionals
+            .last()
+            .is_none_or(|conditional| conditional.active);
+
+        match name.text.as_str() {
+            "if" | "ifdef" | "ifndef" => {
+                let taken = active && self.condition(name, rest, file);
+                conditionals.push(Conditional {
+                    active: taken,
+                    taken,
+                    parent_active: active,
+                    seen_else: false,
+                    directive: name.clone(),
+                });
+            }
+            "elif" => {
+                let Some(conditional) = conditionals.last() else {
+                    return self.error(file, name, "#elif without #if".to_string());
+                };
+                if conditional.seen_else {
+                    return self.error(file, name, "#elif after #else".to_string());
+                }
+                let taken = conditional.parent_active
+                    && !conditional.taken
+                    && self.condition(name, rest, file);
+                let conditional = conditionals.last_mut().unwrap();
+                conditional.active = taken;
+                conditional.taken |= taken;
+            }
+            "else" => {
+                let Some(conditional) = conditionals.last_mut() else {
+                    return self.error(file, name, "#else without #if".to_string());
+                };
+                if conditional.seen_else {
+                    return self.error(file, name, "#else after #else".to_string());
+                }
+                conditional.active = conditional.parent_active && !conditional.taken;
+                conditional.taken = true;
+                conditional.seen_else = true;
+            }
+            "endif" => {
+                if conditionals.pop().is_none() {
+                    self.error(file, name, "#endif without #if".to_string());
+                }
+            }
+            // SS: nothing else matters in a skipped region, not even invalid directives
+            _ if !active => {}
+            "define" => self.define(name, rest, file),
+            "undef" => match rest.first() {
+                Some(macro_name) if macro_name.kind == PpTokenKind::Identifier => {
+                    self.macros.remove(&macro_name.text);
+                }
+                _ => self.error(file, name, "Macro names must be identifiers".to_string()),
+            },
+            "include" => self.include(name, rest, file),
+            "error" => {
+                let message = format!("#error {}", spell(rest));
+                self.error(file, hash, message.trim_end().to_string());
+            }
+            // SS: no pragmas are supported, and the standard says to ignore unknown ones
+            "pragma" => {}
+            _ => {
+                let message = format!("Invalid preprocessing directive #{}", name.text);
+                self.error(file, name, message);
+            }
+        }
+    }
+
+    fn define(&mut self, directive: &PpToken, tokens: &[PpToken], file: &Rc<str>) {
+        let Some(name) = tokens
+            .first()
+            .filter(|name| name.kind == PpTokenKind::Identifier)
+        else {
+            return self.error(
+                file,
+                directive,
+                "Macro names must be identifiers".to_string(),
+            );
+        };
+        if name.text == "defined" {
+            let message = "\"defined\" cannot be used as a macro name".to_string();
+            return self.error(file, name, message);
+        }
+
+        // SS: the parameter list has to follow the name without whitespace in between,
+        // otherwise the parenthesis starts the replacement list of an object-like macro
+        let (params, variadic, body) = match tokens.get(1) {
+            Some(paren) if paren.is("(") && !paren.space_before => {
+                let mut params: Vec<String> = vec![];
+                let mut variadic = false;
+                let mut index = 2;
+                loop {
+                    let Some(token) = tokens.get(index) else {
+                        let message = "Missing ')' in macro parameter list".to_string();
+                        return self.error(file, name, message);
+                    };
+                    index += 1;
+                    if token.is(")") && params.is_empty() && !variadic {
+                        break;
+                    }
+                    if token.is("...") {
+                        variadic = true;
+                        params.push("__VA_ARGS__".to_string());
+                    } else if token.kind == PpTokenKind::Identifier && !variadic {
+                        if params.contains(&token.text) {
+                            let message = format!("Duplicate macro parameter \"{}\"", token.text);
+                            return self.error(file, token, message);
+                        }
+                        params.push(token.text.clone());
+                    } else {
+                        let message = format!("Expected parameter name, found \"{}\"", token.text);
+                        return self.error(file, token, message);
+                    }
+                    match tokens.get(index) {
+                        Some(token) if token.is(")") => {
+                            index += 1;
+                            break;
+                        }
+                        Some(token) if token.is(",") && !variadic => index += 1,
+                        _ => {
+                            let message = "Expected ',' or ')' in macro parameter list".to_string();
+                            return self.error(file, name, message);
+                        }
+                    }
+                }
+                (Some(params), variadic, &tokens[index..])
+            }
+            _ => (None, false, &tokens[1..]),
+        };
+
+        let mut body = body.to_vec();
+        if let Some(first) = body.first_mut() {
+            first.space_before = false;
+        }
+        if body.first().is_some_and(|token| token.is("##"))
+            || body.last().is_some_and(|token| token.is("##"))
+        {
+            let message = "'##' cannot appear at either end of a macro expansion".to_string();
+            return self.error(file, name, message);
+        }
+
+        let definition = match params {
+            Some(params) => {
+                let stringizes_non_param = body.iter().enumerate().any(|(index, token)| {
+                    token.is("#")
+                        && body
+                            .get(index + 1)
+                            .is_none_or(|next| !params.contains(&next.text))
+                });
+                if stringizes_non_param {
+                    let message = "'#' is not followed by a macro parameter".to_string();
+                    return self.error(file, name, message);
+                }
+                Macro::Function {
+                    params,
+                    variadic,
+                    body,
+                }
+            }
+            None => Macro::Object(body),
+        };
+        self.macros.insert(name.text.clone(), definition);
+    }
+
+    fn include(&mut self, directive: &PpToken, tokens: &[PpToken], file: &Rc<str>) {
+        // SS: a header name can also come out of a macro expansion
+        let tokens = match tokens.first() {
+            Some(token) if token.kind == PpTokenKind::StringLiteral || token.is("<") => {
+                tokens.to_vec()
+            }
+            _ => self.expand(tokens.to_vec(), file),
+        };
+
+        let (header, is_quoted) = match tokens.first() {
+            Some(token) if token.kind == PpTokenKind::StringLiteral => {
+                (token.text[1..token.text.len() - 1].to_string(), true)
+            }
+            Some(token) if token.is("<") => match tokens.iter().position(|token| token.is(">")) {
+                Some(end) => (spell(&tokens[1..end]), false),
+                None => {
+                    let message = "Missing terminating > character".to_string();
+                    return self.error(file, directive, message);
+                }
+            },
+            _ => {
+                let message = "#include expects \"FILENAME\" or <FILENAME>".to_string();
+                return self.error(file, directive, message);
+            }
+        };
+
+        // SS: a quoted header is looked for next to the file including it first
+        let current_dir = Path::new(&**file).parent().map(Path::to_path_buf);
+        let path = current_dir
+            .filter(|_| is_quoted)
+            .into_iter()
+            .chain(self.include_dirs.iter().cloned())
+            .chain(self.target.system_include_dirs().iter().map(PathBuf::from))
+            .map(|dir| dir.join(&header))
+            .find(|path| path.is_file());
+        let Some(path) = path else {
+            let message = format!("{}: No such file or directory", header);
+            return self.error(file, directive, message);
+        };
+
+        if self.include_depth >= MAX_INCLUDE_DEPTH {
+            return self.error(file, directive, "#include nested too deeply".to_string());
+        }
+        match fs::read_to_string(&path) {
+            Ok(source) => {
+                self.include_depth += 1;
+                self.process_file(source, path.to_string_lossy().into());
+                self.include_depth -= 1;
+            }
+            Err(err) => self.error(file, directive, format!("{}: {}", header, err)),
+        }
+    }
+
+    // SS: evaluates the controlling expression of an #if or #elif, or checks the macro of an
+    // #ifdef or #ifndef
+    fn condition(&mut self, directive: &PpToken, tokens: &[PpToken], file: &Rc<str>) -> bool {
+        if directive.text != "if" && directive.text != "elif" {
+            let Some(name) = tokens
+                .first()
+                .filter(|name| name.kind == PpTokenKind::Identifier)
+            else {
+                let message = format!("#{} expects a macro name", directive.text);
+                self.error(file, directive, message);
+                return false;
+            };
+            return self.macros.contains_key(&name.text) == (directive.text == "ifdef");
+        }
+
+        // SS: defined has to be replaced before expanding, it takes the macro name as is
+        let mut replaced = vec![];
+        let mut index = 0;
+        while index < tokens.len() {
+            let token = &tokens[index];
+            index += 1;
+            if token.kind != PpTokenKind::Identifier || token.text != "defined" {
+                replaced.push(token.clone());
+                continue;
+            }
+            let parenthesized = tokens.get(index).is_some_and(|token| token.is("("));
+            let name = tokens.get(index + usize::from(parenthesized));
+            let closed = !parenthesized || tokens.get(index + 2).is_some_and(|t| t.is(")"));
+            match name {
+                Some(name) if name.kind == PpTokenKind::Identifier && closed => {
+                    let defined = self.macros.contains_key(&name.text);
+                    let value = if defined { "1" } else { "0" };
+                    replaced.push(PpToken::synthesized(
+                        PpTokenKind::Number,
+                        value.to_string(),
+                        token,
+                    ));
+                    index += 1 + 2 * usize::from(parenthesized);
+                }
+                _ => {
+                    let message = "Operator \"defined\" requires an identifier".to_string();
+                    self.error(file, token, message);
+                    return false;
+                }
+            }
+        }
+
+        // SS: identifiers left after expansion are not macros and count as 0
+        let tokens = self
+            .expand(replaced, file)
+            .into_iter()
+            .map(|token| match token.kind {
+                PpTokenKind::Identifier => {
+                    PpToken::synthesized(PpTokenKind::Number, "0".to_string(), &token)
+                }
+                _ => token,
+            })
+            .collect::<Vec<_>>();
+        if tokens.is_empty() {
+            let message = format!("#{} with no expression", directive.text);
+            self.error(file, directive, message);
+            return false;
+        }
+
+        let mut parser = ConditionParser {
+            tokens: &tokens,
+            position: 0,
+        };
+        let result = parser
+            .conditional(true)
+            .and_then(|value| match parser.peek() {
+                Some(token) => Err(format!(
+                    "Missing binary operator before token \"{}\"",
+                    token
+                )),
+                None => Ok(value),
+            });
+        match result {
+            Ok(value) => value.bits != 0,
+            Err(message) => {
+                self.error(file, directive, message);
+                false
+            }
+        }
+    }
+
+    // SS: expands the macros in tokens, rescanning each expansion together with the tokens
+    // that follow it. A token is not expanded again by a macro in its hide set.
+    fn expand(&mut self, tokens: Vec<PpToken>, file: &Rc<str>) -> Vec<PpToken> {
+        let mut input = VecDeque::from(tokens);
+        let mut output = vec![];
+        while let Some(token) = input.pop_front() {
+            if token.kind != PpTokenKind::Identifier || token.hide_set.contains(&token.text) {
+                output.push(token);
+                continue;
+            }
+            match token.text.as_str() {
+                "__LINE__" => {
+                    let line = token.line.to_string();
+                    output.push(PpToken::synthesized(PpTokenKind::Number, line, &token));
+                    continue;
+                }
+                "__FILE__" => {
+                    let name = format!("\"{}\"", escape(file));
+                    output.push(PpToken::synthesized(
+                        PpTokenKind::StringLiteral,
+                        name,
+                        &token,
+                    ));
+                    continue;
+                }
+                _ => {}
+            }
+
+            let expansion = match self.macros.get(&token.text).cloned() {
+                Some(Macro::Object(body)) => {
+                    let mut hide_set = token.hide_set.clone();
+                    hide_set.insert(token.text.clone());
+                    self.substitute(&body, &[], &[], &token, hide_set, file)
+                }
+                // SS: the name of a function-like macro is just an identifier unless it is
+                // followed by an argument list
+                Some(Macro::Function {
+                    params,
+                    variadic,
+                    body,
+                }) if input.front().is_some_and(|next| next.is("(")) => {
+                    let Some((args, close)) = self.arguments(&token, &mut input, file) else {
+                        continue;
+                    };
+                    let Some(args) = self.match_arguments(&token, args, &params, variadic, file)
+                    else {
+                        continue;
+                    };
+                    let mut hide_set = token
+                        .hide_set
+                        .intersection(&close.hide_set)
+                        .cloned()
+                        .collect::<HashSet<_>>();
+                    hide_set.insert(token.text.clone());
+                    self.substitute(&body, &params, &args, &token, hide_set, file)
+                }
+                _ => {
+                    output.push(token);
+                    continue;
+                }
+            };
+            for token in expansion.into_iter().rev() {
+                input.push_front(token);
+            }
+        }
+        output
+    }
+
+    // SS: splits the argument list of a macro invocation at the commas outside parentheses,
+    // returning the arguments and the closing parenthesis
+    fn arguments(
+        &mut self,
+        name: &PpToken,
+        input: &mut VecDeque<PpToken>,
+        file: &Rc<str>,
+    ) -> Option<(Vec<Vec<PpToken>>, PpToken)> {
+        input.pop_front();
+        let mut args = vec![];
+        let mut current = vec![];
+        let mut depth = 0;
+        while let Some(token) = input.pop_front() {
+            if token.is(")") && depth == 0 {
+                args.push(current);
+                return Some((args, token));
+            } else if token.is(",") && depth == 0 {
+                args.push(std::mem::take(&mut current));
+                continue;
+            } else if token.is("(") {
+                depth += 1;
+            } else if token.is(")") {
+                depth -= 1;
+            }
+            current.push(token);
+        }
+        let message = format!(
+            "Unterminated argument list invoking macro \"{}\"",
+            name.text
+        );
+        self.error(file, name, message);
+        None
+    }
+
+    fn match_arguments(
+        &mut self,
+        name: &PpToken,
+        mut args: Vec<Vec<PpToken>>,
+        params: &[String],
+        variadic: bool,
+        file: &Rc<str>,
+    ) -> Option<Vec<Vec<PpToken>>> {
+        // SS: f() passes a single empty argument, which is no argument at all for a macro
+        // without parameters
+        if params.is_empty() && args.len() == 1 && args[0].is_empty() {
+            return Some(vec![]);
+        }
+
+        let named = params.len() - usize::from(variadic);
+        if variadic && args.len() > params.len() {
+            // SS: the variable arguments are passed on together with the commas between them
+            let rest = args.split_off(named);
+            let mut joined = vec![];
+            for (index, arg) in rest.into_iter().enumerate() {
+                if index > 0 {
+                    joined.push(PpToken::synthesized(
+                        PpTokenKind::Punctuator,
+                        ",".to_string(),
+                        name,
+                    ));
+                }
+                joined.extend(arg);
+            }
+            args.push(joined);
+        } else if variadic && args.len() == named {
+            args.push(vec![]);
+        }
+
+        if args.len() < params.len() {
+            let message = format!(
+                "Macro \"{}\" requires {} arguments, but only {} given",
+                name.text,
+                params.len(),
+                args.len()
+            );
+            self.error(file, name, message);
+            return None;
+        }
+        if args.len() > params.len() {
+            let message = format!(
+                "Macro \"{}\" passed {} arguments, but takes just {}",
+                name.text,
+                args.len(),
+                params.len()
+            );
+            self.error(file, name, message);
+            return None;
+        }
+        Some(args)
+    }
+
+    // SS: the replacement list of a macro with the arguments substituted for the
+    // parameters. An argument is macro expanded first unless it is stringized by # or
+    // pasted by ##. The result takes the place of the invocation.
+    fn substitute(
+        &mut self,
+        body: &[PpToken],
+        params: &[String],
+        args: &[Vec<PpToken>],
+        invocation: &PpToken,
+        hide_set: HashSet<String>,
+        file: &Rc<str>,
+    ) -> Vec<PpToken> {
+        let param = |token: &PpToken| {
+            (token.kind == PpTokenKind::Identifier)
+                .then(|| params.iter().position(|param| *param == token.text))
+                .flatten()
+        };
+
+        let mut result: Vec<PpToken> = vec![];
+        // SS: whether the last thing substituted was an empty argument, the placemarker of
+        // the standard, which pastes with the other operand of ## to give that operand
+        let mut placemarker = false;
+        let mut index = 0;
+        while index < body.len() {
+            let token = &body[index];
+            index += 1;
+
+            if token.is("#")
+                && let Some(arg) = body.get(index).and_then(param)
+            {
+                index += 1;
+                let text = format!("\"{}\"", stringize(&args[arg]));
+                let mut string = PpToken::synthesized(PpTokenKind::StringLiteral, text, token);
+                string.space_before = token.space_before;
+                result.push(string);
+                placemarker = false;
+            } else if token.is("##") {
+                let rhs = &body[index];
+                index += 1;
+                let rhs = match param(rhs) {
+                    Some(arg) => args[arg].clone(),
+                    None => vec![rhs.clone()],
+                };
+                let lhs = if placemarker { None } else { result.pop() };
+                match (lhs, rhs.split_first()) {
+                    (lhs, None) => {
+                        placemarker = lhs.is_none();
+                        result.extend(lhs);
+                    }
+                    (None, Some(_)) => {
+                        result.extend(rhs);
+                        placemarker = false;
+                    }
+                    (Some(lhs), Some((first, rest))) => {
+                        result.extend(self.paste(&lhs, first, file));
+                        result.extend(rest.iter().cloned());
+                        placemarker = false;
+                    }
+                }
+            } else if let Some(arg) = param(token) {
+                let pasted = body.get(index).is_some_and(|next| next.is("##"));
+                let mut tokens = if pasted {
+                    args[arg].clone()
+                } else {
+                    self.expand(args[arg].clone(), file)
+                };
+                if let Some(first) = tokens.first_mut() {
+                    first.space_before = token.space_before;
+                }
+                placemarker = tokens.is_empty();
+                result.extend(tokens);
+            } else {
+                result.push(token.clone());
+                placemarker = false;
+            }
+        }
+
+        for token in &mut result {
+            token.hide_set.extend(hide_set.iter().cloned());
+            token.line = invocation.line;
+            token.column = 0;
+            token.line_start = false;
+        }
+        if let Some(first) = result.first_mut() {
+            first.space_before = invocation.space_before;
+        }
+        result
+    }
+
+    // SS: ## glues two tokens into one, which has to be a valid token again
+    fn paste(&mut self, lhs: &PpToken, rhs: &PpToken, file: &Rc<str>) -> Vec<PpToken> {
+        let text = format!("{}{}", lhs.text, rhs.text);
+        let mut tokens = tokenize(&text);
+        if tokens.len() == 1 {
+            let pasted = tokens.remove(0);
+            return vec![PpToken {
+                kind: pasted.kind,
+                text: pasted.text,
+                ..lhs.clone()
+            }];
+        }
+        let message = format!(
+            "Pasting \"{}\" and \"{}\" does not give a valid preprocessing token",
+            lhs.text, rhs.text
+        );
+        self.error(file, lhs, message);
+        vec![lhs.clone(), rhs.clone()]
+    }
+}
+
+// SS: writes tokens to the output text, each one on its line and at its column where that
+// is known. Tokens from macro expansions are separated by a space so that they cannot run
+// together into a different token.
+#[derive(Default)]
+struct Output {
+    text: String,
+    file: Option<Rc<str>>,
+    line: usize,
+    column: usize,
+}
+
+impl Output {
+    fn emit(&mut self, token: &PpToken, file: &Rc<str>) {
+        if self.file.as_ref() != Some(file)
+            || token.line < self.line
+            || token.line > self.line + MAX_BLANK_LINES
+        {
+            if !self.text.is_empty() {
+                self.text.push('\n');
+            }
+            self.text
+                .push_str(&format!("# {} \"{}\"\n", token.line, escape(file)));
+            self.file = Some(file.clone());
+            self.line = token.line;
+            self.column = 1;
+        }
+        while self.line < token.line {
+            self.text.push('\n');
+            self.line += 1;
+            self.column = 1;
+        }
+
+        if token.column > self.column {
+            self.text.push_str(&" ".repeat(token.column - self.column));
+            self.column = token.column;
+        } else if self.column > 1 && token.column != self.column {
+            self.text.push(' ');
+            self.column += 1;
+        }
+        self.text.push_str(&token.text);
+        self.column += token.text.len();
+    }
+}
+
+fn tokenize(text: &str) -> Vec<PpToken> {
+    let bytes = text.as_bytes();
+    let mut tokens = vec![];
+    let mut position = 0;
+    let mut line = 1;
+    let mut line_begin = 0;
+    let mut space_before = false;
+    let mut line_start = true;
+
+    while position < bytes.len() {
+        let rest = &text[position..];
+        let c = bytes[position];
+        if rest.starts_with("\\\n") {
+            // SS: a backslash at the end of a line joins it with the next one
+            position += 2;
+            line += 1;
+            line_begin = position;
+            continue;
+        } else if c == b'\n' {
+            position += 1;
+            line += 1;
+            line_begin = position;
+            line_start = true;
+            space_before = true;
+            continue;
+        } else if matches!(c, b' ' | b'\t' | b'\r' | 0x0b | 0x0c) {
+            position += 1;
+            space_before = true;
+            continue;
+        } else if rest.starts_with("//") {
+            position += rest.find('\n').unwrap_or(rest.len());
+            space_before = true;
+            continue;
+        } else if let Some(body) = rest.strip_prefix("/*") {
+            // SS: a comment is a single space, even one spanning lines does not end a
+            // directive. An unterminated one takes the rest of the file, its opening is
+            // passed through for the lexer to report.
+            let Some(end) = body.find("*/").map(|end| end + 4) else {
+                tokens.push(PpToken {
+                    kind: PpTokenKind::Other,
+                    text: "/*".to_string(),
+                    line,
+                    column: position - line_begin + 1,
+                    space_before,
+                    line_start,
+                    hide_set: HashSet::new(),
+                });
+                break;
+            };
+            let comment = &rest[..end];
+            if let Some(last_newline) = comment.rfind('\n') {
+                line += comment.matches('\n').count();
+                line_begin = position + last_newline + 1;
+            }
+            position += end;
+            space_before = true;
+            continue;
+        }
+
+        let start = position;
+        let kind = if c.is_ascii_alphabetic() || c == b'_' {
+            position += rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            PpTokenKind::Identifier
+        } else if c.is_ascii_digit()
+            || (c == b'.' && bytes.get(position + 1).is_some_and(u8::is_ascii_digit))
+        {
+            // SS: a preprocessing number also takes in suffixes and signed exponents
+            position += 1;
+            while let Some(&next) = bytes.get(position) {
+                if matches!(next, b'e' | b'E' | b'p' | b'P')
+                    && matches!(bytes.get(position + 1), Some(b'+' | b'-'))
+                {
+                    position += 2;
+                } else if next.is_ascii_alphanumeric() || next == b'_' || next == b'.' {
+                    position += 1;
+                } else {
+                    break;
+                }
+            }
+            PpTokenKind::Number
+        } else if c == b'"' || c == b'\'' {
+            let mut end = position + 1;
+            let terminated = loop {
+                match bytes.get(end) {
+                    None | Some(b'\n') => break false,
+                    Some(b'\\') if bytes.get(end + 1).is_some_and(|next| *next != b'\n') => {
+                        end += 2
+                    }
+                    Some(&next) if next == c => break true,
+                    _ => end += 1,
+                }
+            };
+            if terminated {
+                position = end + 1;
+                if c == b'"' {
+                    PpTokenKind::StringLiteral
+                } else {
+                    PpTokenKind::CharConstant
+                }
+            } else {
+                position = end;
+                PpTokenKind::Other
+            }
+        } else if let Some(punctuator) = PUNCTUATORS.iter().find(|p| rest.starts_with(**p)) {
+            position += punctuator.len();
+            PpTokenKind::Punctuator
+        } else {
+            position += rest.chars().next().map_or(1, char::len_utf8);
+            PpTokenKind::Other
+        };
+
+        // SS: the digraphs of # and ## act like them in directives and macros
+        let text = match &text[start..position] {
+            "%:" => "#",
+            "%:%:" => "##",
+            text => text,
+        };
+        tokens.push(PpToken {
+            kind,
+            text: text.to_string(),
+            line,
+            column: start - line_begin + 1,
+            space_before,
+            line_start,
+            hide_set: HashSet::new(),
+        });
+        space_before = false;
+        line_start = false;
+    }
+    tokens
+}
+
+// SS: the tokens as text, with a single space wherever there is whitespace between them
+fn spell(tokens: &[PpToken]) -> String {
+    let mut text = String::new();
+    for (index, token) in tokens.iter().enumerate() {
+        if index > 0 && token.space_before {
+            text.push(' ');
+        }
+        text.push_str(&token.text);
+    }
+    text
+}
+
+// SS: the body of the string literal # makes of an argument. Quotes and backslashes in
+// string literals and character constants are escaped so that they come out as written.
+fn stringize(tokens: &[PpToken]) -> String {
+    let mut text = String::new();
+    for (index, token) in tokens.iter().enumerate() {
+        if index > 0 && token.space_before {
+            text.push(' ');
+        }
+        match token.kind {
+            PpTokenKind::StringLiteral | PpTokenKind::CharConstant => {
+                text.push_str(&escape(&token.text))
+            }
+            _ => text.push_str(&token.text),
+        }
+    }
+    text
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+// SS: a value in a preprocessor expression, which is computed in the widest integer types
+#[derive(Debug, Clone, Copy)]
+struct Value {
+    bits: i64,
+    unsigned: bool,
+}
+
+impl Value {
+    fn int(bits: bool) -> Value {
+        Value {
+            bits: i64::from(bits),
+            unsigned: false,
+        }
+    }
+}
+
+// SS: evaluates the controlling expression of an #if by recursive descent. Operands that
+// are not evaluated because of &&, || or ?: cannot fail, i.e. divide by zero.
+struct ConditionParser<'a> {
+    tokens: &'a [PpToken],
+    position: usize,
+}
+
+impl<'a> ConditionParser<'a> {
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens
+            .get(self.position)
+            .map(|token| token.text.as_str())
+    }
+
+    fn conditional(&mut self, evaluate: bool) -> Result<Value, String> {
+        let condition = self.binary(1, evaluate)?;
+        if self.peek() != Some("?") {
+            return Ok(condition);
+        }
+        self.position += 1;
+        let taken = condition.bits != 0;
+        let then_value = self.conditional(evaluate && taken)?;
+        if self.peek() != Some(":") {
+            return Err("Expected ':' in preprocessor expression".to_string());
+        }
+        self.position += 1;
+        let else_value = self.conditional(evaluate && !taken)?;
+        Ok(Value {
+            bits: if taken {
+                then_value.bits
+            } else {
+                else_value.bits
+            },
+            unsigned: then_value.unsigned || else_value.unsigned,
+        })
+    }
+
+    // SS: precedence climbing over the binary operators, all of which are left associative
+    fn binary(&mut self, min_precedence: u8, evaluate: bool) -> Result<Value, String> {
+        let mut lhs = self.unary(evaluate)?;
+        while let Some(op) = self.peek()
+            && let Some(precedence) = precedence(op)
+            && precedence >= min_precedence
+        {
+            self.position += 1;
+            let evaluate_rhs = match op {
+                "&&" => evaluate && lhs.bits != 0,
+                "||" => evaluate && lhs.bits == 0,
+                _ => evaluate,
+            };
+            let rhs = self.binary(precedence + 1, evaluate_rhs)?;
+            lhs = apply(op, lhs, rhs, evaluate)?;
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self, evaluate: bool) -> Result<Value, String> {
+        let Some(token) = self.tokens.get(self.position) else {
+            return Err("Expected value in preprocessor expression".to_string());
+        };
+        self.position += 1;
+        match token.text.as_str() {
+            "+" => self.unary(evaluate),
+            "-" => self.unary(evaluate).map(|value| Value {
+                bits: value.bits.wrapping_neg(),
+                ..value
+            }),
+            "~" => self.unary(evaluate).map(|value| Value {
+                bits: !value.bits,
+                ..value
+            }),
+            "!" => self
+                .unary(evaluate)
+                .map(|value| Value::int(value.bits == 0)),
+            "(" => {
+                let value = self.conditional(evaluate)?;
+                if self.peek() != Some(")") {
+                    return Err("Expected ')' in preprocessor expression".to_string());
+                }
+                self.position += 1;
+                Ok(value)
+            }
+            _ if matches!(token.kind, PpTokenKind::Number | PpTokenKind::CharConstant) => {
+                constant(&token.text)
+            }
+            text => Err(format!(
+                "Token \"{}\" is not valid in preprocessor expressions",
+                text
+            )),
+        }
+    }
+}
+
+fn precedence(op: &str) -> Option<u8> {
+    match op {
+        "||" => Some(1),
+        "&&" => Some(2),
+        "|" => Some(3),
+        "^" => Some(4),
+        "&" => Some(5),
+        "==" | "!=" => Some(6),
+        "<" | ">" | "<=" | ">=" => Some(7),
+        "<<" | ">>" => Some(8),
+        "+" | "-" => Some(9),
+        "*" | "/" | "%" => Some(10),
+        _ => None,
+    }
+}
+
+// SS: the usual arithmetic conversions make the operation unsigned if either operand is,
+// shifts take the type of their left operand
+fn apply(op: &str, lhs: Value, rhs: Value, evaluate: bool) -> Result<Value, String> {
+    let unsigned = lhs.unsigned || rhs.unsigned;
+    let (a, b) = (lhs.bits, rhs.bits);
+    let arithmetic = |bits: i64| Value { bits, unsigned };
+    Ok(match op {
+        "||" => Value::int(a != 0 || b != 0),
+        "&&" => Value::int(a != 0 && b != 0),
+        "|" => arithmetic(a | b),
+        "^" => arithmetic(a ^ b),
+        "&" => arithmetic(a & b),
+        "==" => Value::int(a == b),
+        "!=" => Value::int(a != b),
+        "<" | ">" | "<=" | ">=" => {
+            let ordering = if unsigned {
+                (a as u64).cmp(&(b as u64))
+            } else {
+                a.cmp(&b)
+            };
+            Value::int(match op {
+                "<" => ordering.is_lt(),
+                ">" => ordering.is_gt(),
+                "<=" => ordering.is_le(),
+                _ => ordering.is_ge(),
+            })
+        }
+        "<<" => Value {
+            bits: a.wrapping_shl(b as u32),
+            ..lhs
+        },
+        ">>" if lhs.unsigned => Value {
+            bits: (a as u64).wrapping_shr(b as u32) as i64,
+            ..lhs
+        },
+        ">>" => Value {
+            bits: a.wrapping_shr(b as u32),
+            ..lhs
+        },
+        "+" => arithmetic(a.wrapping_add(b)),
+        "-" => arithmetic(a.wrapping_sub(b)),
+        "*" => arithmetic(a.wrapping_mul(b)),
+        "/" | "%" if b == 0 => {
+            if evaluate {
+                return Err("Division by zero in preprocessor expression".to_string());
+            }
+            arithmetic(0)
+        }
+        "/" if unsigned => arithmetic(((a as u64) / (b as u64)) as i64),
+        "/" => arithmetic(a.wrapping_div(b)),
+        "%" if unsigned => arithmetic(((a as u64) % (b as u64)) as i64),
+        _ => arithmetic(a.wrapping_rem(b)),
+    })
+}
+
+// SS: integer and character constants are read the way the lexer reads them
+fn constant(text: &str) -> Result<Value, String> {
+    let mut lexer = Lexer::with_file_name(text.to_string(), "");
+    let value = match lexer.next_token() {
+        Ok(Tokens::Constant(val) | Tokens::LongConstant(val) | Tokens::CharConstant(val)) => {
+            Value {
+                bits: val,
+                unsigned: false,
+            }
+        }
+        Ok(Tokens::UnsignedConstant(val) | Tokens::UnsignedLongConstant(val)) => Value {
+            bits: val as i64,
+            unsigned: true,
+        },
+        Ok(Tokens::DoubleConstant(_)) => {
+            return Err("Floating constant in preprocessor expression".to_string());
+        }
+        Ok(_) => {
+            return Err(format!(
+                "Invalid constant \"{}\" in preprocessor expression",
+                text
+            ));
+        }
+        Err(diagnostic) => return Err(diagnostic.message),
+    };
+    match lexer.next_token() {
+        Ok(Tokens::EOF) => Ok(value),
+        _ => Err(format!(
+            "Invalid constant \"{}\" in preprocessor expression",
+            text
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::preprocessor::Preprocessor;
+    use crate::target::Target;
+    use std::fs;
+
+    // SS: the non-empty lines of the output without line markers, with whitespace collapsed
+    fn preprocess(source: &str) -> Result<Vec<String>, Vec<String>> {
+        preprocess_for(source, Target::Linux)
+    }
+
+    fn preprocess_for(source: &str, target: Target) -> Result<Vec<String>, Vec<String>> {
+        let mut preprocessor = Preprocessor::new(vec![], target);
+        let text = preprocessor
+            .preprocess(source.to_string(), "test.c")
+            .map_err(|diagnostics| {
+                diagnostics
+                    .into_iter()
+                    .map(|diagnostic| {
+                        format!(
+                            "{}:{}: {}",
+                            diagnostic.span.line, diagnostic.span.column, diagnostic.message
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            })?;
+        Ok(text
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|line| !line.is_empty())
+            .collect())
+    }
+
+    #[test]
+    fn test_preprocess_macros() {
+        // SS: arrange
+        let source = r#"#define N 10
+#define SQUARE(x) ((x) * (x))
+#define CAT(a, b) a ## b
+#define STR(x) #x
+#define XSTR(x) STR(x)
+#define CALL(f, ...) f(__VA_ARGS__)
+#define EMPTY()
+#define SELF SELF + 1
+#define F(x) x + F(x)
+int CAT(var, N) = SQUARE(N + 1);
+char *s = XSTR(N) STR( "a\n"  'b'  c );
+CALL(g, 1, (2, 3)) CALL(h) EMPTY() CAT(, N) CAT(x,);
+SELF; F(F(1)); SQUARE
+(
+2) __LINE__ __FILE__;
+"#;
+
+        // SS: act
+        let lines = preprocess(source);
+
+        // SS: assert
+        assert_eq!(
+            lines,
+            Ok(vec![
+                "int varN = ( ( 10 + 1 ) * ( 10 + 1 ) ) ;".to_string(),
+                r#"char *s = "10" "\"a\\n\" 'b' c" ;"#.to_string(),
+                "g ( 1 , ( 2 , 3 ) ) h ( ) 10 x ;".to_string(),
+                "SELF + 1 ; 1 + F ( 1 ) + F ( 1 + F ( 1 ) ) ; ( ( 2 ) * ( 2 ) )".to_string(),
+                "15 \"test.c\";".to_string(),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_preprocess_predefined_macros() {
+        // SS: arrange
+        let source = "__STDC__ __STDC_VERSION__ __x86_64__ __LP64__;
+#if defined __linux__ && !defined __APPLE__
+linux;
+#endif
+";
+
+        // SS: act
+        let linux = preprocess(source);
+        let macos = preprocess_for("__APPLE__ __linux__;", Target::MacOs);
+
+        // SS: assert
+        assert_eq!(
+            linux,
+            Ok(vec!["1 201710L 1 1 ;".to_string(), "linux;".to_string()])
+        );
+        assert_eq!(macos, Ok(vec!["1 __linux__;".to_string()]));
+    }
+
+    #[test]
+    fn test_preprocess_conditionals() {
+        // SS: arrange
+        let source = "#define A 2
+#if A * 3 == 6 && defined(A) && !defined B
+one
+#elif 1 / 0
+two
+#else
+three
+#endif
+#ifdef B
+#if 1 / 0
+#error not evaluated
+#endif
+#elif -1 < 0u
+four
+#elif 1
+five
+#endif
+#ifndef A
+six
+#else
+seven
+#endif
+#if 0
+#bogus directive
+#elif (A > 1 ? 1 || 1 / 0 : 0) && 'a' == 97 && 0x10 >> 1 == 8 && UNDEFINED == 0
+eight
+#endif
+";
+
+        // SS: act
+        let lines = preprocess(source);
+
+        // SS: assert
+        assert_eq!(
+            lines,
+            Ok(vec![
+                "one".to_string(),
+                "five".to_string(),
+                "seven".to_string(),
+                "eight".to_string(),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_preprocess_include() {
+        // SS: arrange
+        let dir = std::env::temp_dir().join(format!("preprocessor_test_{}", std::process::id()));
+        fs::create_dir_all(dir.join("include")).unwrap();
+        fs::write(
+            dir.join("include").join("header.h"),
+            "#pragma once\nint from_header;\n#include \"local.h\"\n",
+        )
+        .unwrap();
+        fs::write(dir.join("include").join("local.h"), "#define LOCAL 1\n").unwrap();
+        let main = dir.join("main.c");
+        let source =
+            "#define HEADER <header.h>\n#include HEADER\n\n\n\n\n\n\n\n\n\n\nint x = LOCAL;\n";
+
+        // SS: act
+        let mut preprocessor = Preprocessor::new(vec![dir.join("include")], Target::Linux);
+        let text = preprocessor.preprocess(source.to_string(), &main.to_string_lossy());
+        let header_source = preprocessor
+            .source(&dir.join("include").join("header.h").to_string_lossy())
+            .to_string();
+        fs::remove_dir_all(&dir).unwrap();
+
+        // SS: assert
+        assert_eq!(
+            text.unwrap(),
+            format!(
+                "# 2 \"{}\"\nint from_header;\n# 13 \"{}\"\nint x = 1    ;",
+                dir.join("include").join("header.h").display(),
+                main.display()
+            )
+        );
+        assert!(header_source.starts_with("#pragma once"));
+    }
+
+    #[test]
+    fn test_preprocess_errors() {
+        // SS: arrange
+        let source = "#if 1 / 0
+#endif
+#define CAT(a, b) a ## b
+CAT(+, /)
+#define ONE(x) x
+ONE(1, 2) ONE(
+#include <does_not_exist.h>
+#error Stop  here
+#endif
+#define 1
+#if 1
+";
+
+        // SS: act
+        let errors = preprocess(source);
+
+        // SS: assert
+        assert_eq!(
+            errors,
+            Err(vec![
+                "1:2: Division by zero in preprocessor expression".to_string(),
+                "4:5: Pasting \"+\" and \"/\" does not give a valid preprocessing token"
+                    .to_string(),
+                "6:1: Macro \"ONE\" passed 2 arguments, but takes just 1".to_string(),
+                "6:11: Unterminated argument list invoking macro \"ONE\"".to_string(),
+                "7:2: does_not_exist.h: No such file or directory".to_string(),
+                "8:1: #error Stop here".to_string(),
+                "9:2: #endif without #if".to_string(),
+                "10:2: Macro names must be identifiers".to_string(),
+                "11:2: Unterminated #if".to_string(),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_preprocess_keeps_positions() {
+        // SS: arrange
+        let source = "int a; /* a\ncomment */ int b;\n#define X \\\n  (1 + 2)\n\nint c = X;\n\n\n\n\n\n\n\n\n\nint d;\n";
+
+        // SS: act
+        let text =
+            Preprocessor::new(vec![], Target::Linux).preprocess(source.to_string(), "dir/test.c");
+
+        // SS: assert
+        assert_eq!(
+            text,
+            Ok("# 1 \"dir/test.c\"\nint a;\n           int b;\n\n\n\nint c = ( 1 + 2 ) ;\n# 16 \"dir/test.c\"\nint d;".to_string())
+        );
+    }
+}
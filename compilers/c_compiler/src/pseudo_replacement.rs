@@ -0,0 +1,489 @@
+use crate::assembly_ast::{
+    AssemblyFunctionAST, AssemblyInstructionAST, AssemblyOperandAST, AssemblyProgramAST,
+    AssemblyTopLevelAST,
+};
+use crate::symbol_table::SymbolTable;
+use crate::type_table::TypeTable;
+use std::collections::HashMap;
+
+// SS: System V requires rsp to be 16-byte aligned at every call
+const STACK_ALIGNMENT: i32 = 16;
+
+pub fn replace_pseudo_registers(
+    assembly_ast: AssemblyProgramAST,
+    symbols: &SymbolTable,
+    types: &TypeTable,
+) -> AssemblyProgramAST {
+    AssemblyProgramAST {
+        items: assembly_ast
+            .items
+            .into_iter()
+            .map(|item| match item {
+                AssemblyTopLevelAST::Function(function) => {
+                    AssemblyTopLevelAST::Function(replace_in_function(function, symbols, types))
+                }
+                item @ (AssemblyTopLevelAST::StaticVariable { .. }
+                | AssemblyTopLevelAST::StaticConstant { .. }) => item,
+            })
+            .collect(),
+    }
+}
+
+fn replace_in_function(
+    function: AssemblyFunctionAST,
+    symbols: &SymbolTable,
+    types: &TypeTable,
+) -> AssemblyFunctionAST {
+    let AssemblyFunctionAST {
+        name,
+        global,
+        instructions,
+        callee_saved_registers,
+    } = function;
+
+    // SS: the callee-saved registers are pushed right below the saved rbp, the stack slots
+    // go below them
+    let saved_size = 8 * callee_saved_registers.len() as i32;
+    let mut replacer = PseudoReplacer::new(symbols, types, saved_size);
+    let instructions = instructions
+        .into_iter()
+        .map(|instruction| replacer.replace_instruction(instruction))
+        .collect::<Vec<_>>();
+
+    // SS: reserve the stack slots in the function prologue, rounded up so that rsp stays
+    // aligned after the pushq %rbp and the pushes of the callee-saved registers
+    let mut result = vec![];
+    let stack_size = (replacer.stack_size + STACK_ALIGNMENT - 1) / STACK_ALIGNMENT
+        * STACK_ALIGNMENT
+        - saved_size;
+    if stack_size > 0 {
+        result.push(AssemblyInstructionAST::AllocateStack(stack_size));
+    }
+    result.extend(instructions);
+
+    AssemblyFunctionAST {
+        name,
+        global,
+        instructions: result,
+        callee_saved_registers,
+    }
+}
+
+struct PseudoReplacer<'a> {
+    symbols: &'a SymbolTable,
+    types: &'a TypeTable,
+    offsets: HashMap<String, i32>,
+    stack_size: i32,
+}
+
+impl<'a> PseudoReplacer<'a> {
+    fn new(symbols: &'a SymbolTable, types: &'a TypeTable, stack_size: i32) -> Self {
+        PseudoReplacer {
+            symbols,
+            types,
+            offsets: HashMap::new(),
+            stack_size,
+        }
+    }
+
+    fn replace_instruction(
+        &mut self,
+        instruction: AssemblyInstructionAST,
+    ) -> AssemblyInstructionAST {
+        match instruction {
+            AssemblyInstructionAST::Mov { asm_type, src, dst } => AssemblyInstructionAST::Mov {
+                asm_type,
+                src: self.replace_operand(src),
+                dst: self.replace_operand(dst),
+            },
+            AssemblyInstructionAST::Movsx {
+                src_type,
+                dst_type,
+                src,
+                dst,
+            } => AssemblyInstructionAST::Movsx {
+                src_type,
+                dst_type,
+                src: self.replace_operand(src),
+                dst: self.replace_operand(dst),
+            },
+            AssemblyInstructionAST::MovZeroExtend {
+                src_type,
+                dst_type,
+                src,
+                dst,
+            } => AssemblyInstructionAST::MovZeroExtend {
+                src_type,
+                dst_type,
+                src: self.replace_operand(src),
+                dst: self.replace_operand(dst),
+            },
+            AssemblyInstructionAST::Lea { src, dst } => AssemblyInstructionAST::Lea {
+                src: self.replace_operand(src),
+                dst: self.replace_operand(dst),
+            },
+            AssemblyInstructionAST::Cvttsd2si { asm_type, src, dst } => {
+                AssemblyInstructionAST::Cvttsd2si {
+                    asm_type,
+                    src: self.replace_operand(src),
+                    dst: self.replace_operand(dst),
+                }
+            }
+            AssemblyInstructionAST::Cvtsi2sd { asm_type, src, dst } => {
+                AssemblyInstructionAST::Cvtsi2sd {
+                    asm_type,
+                    src: self.replace_operand(src),
+                    dst: self.replace_operand(dst),
+                }
+            }
+            AssemblyInstructionAST::Unary {
+                op,
+                asm_type,
+                operand,
+            } => AssemblyInstructionAST::Unary {
+                op,
+                asm_type,
+                operand: self.replace_operand(operand),
+            },
+            AssemblyInstructionAST::Binary {
+                op,
+                asm_type,
+                src,
+                dst,
+            } => AssemblyInstructionAST::Binary {
+                op,
+                asm_type,
+                src: self.replace_operand(src),
+                dst: self.replace_operand(dst),
+            },
+            AssemblyInstructionAST::Idiv { asm_type, operand } => AssemblyInstructionAST::Idiv {
+                asm_type,
+                operand: self.replace_operand(operand),
+            },
+            AssemblyInstructionAST::Div { asm_type, operand } => AssemblyInstructionAST::Div {
+                asm_type,
+                operand: self.replace_operand(operand),
+            },
+            AssemblyInstructionAST::Cmp { asm_type, src, dst } => AssemblyInstructionAST::Cmp {
+                asm_type,
+                src: self.replace_operand(src),
+                dst: self.replace_operand(dst),
+            },
+            AssemblyInstructionAST::SetCC { condition, operand } => AssemblyInstructionAST::SetCC {
+                condition,
+                operand: self.replace_operand(operand),
+            },
+            AssemblyInstructionAST::Push(operand) => {
+                AssemblyInstructionAST::Push(self.replace_operand(operand))
+            }
+            AssemblyInstructionAST::JmpTable {
+                index,
+                table,
+                targets,
+            } => AssemblyInstructionAST::JmpTable {
+                index: self.replace_operand(index),
+                table,
+                targets,
+            },
+            instruction @ (AssemblyInstructionAST::Cdq(_)
+            | AssemblyInstructionAST::Jmp(_)
+            | AssemblyInstructionAST::JmpCC { .. }
+            | AssemblyInstructionAST::Label(_)
+            | AssemblyInstructionAST::AllocateStack(_)
+            | AssemblyInstructionAST::DeallocateStack(_)
+            | AssemblyInstructionAST::Call(_)
+            | AssemblyInstructionAST::Ret) => instruction,
+        }
+    }
+
+    fn replace_operand(&mut self, operand: AssemblyOperandAST) -> AssemblyOperandAST {
+        match operand {
+            // SS: static variables live in the data section
+            AssemblyOperandAST::Pseudo(name) if self.symbols[&name].is_static() => {
+                AssemblyOperandAST::Data(name, 0)
+            }
+            AssemblyOperandAST::Pseudo(name) => AssemblyOperandAST::Stack(self.stack_offset(name)),
+            AssemblyOperandAST::PseudoMem(name, offset) if self.symbols[&name].is_static() => {
+                AssemblyOperandAST::Data(name, offset)
+            }
+            AssemblyOperandAST::PseudoMem(name, offset) => {
+                AssemblyOperandAST::Stack(self.stack_offset(name) + offset)
+            }
+            operand => operand,
+        }
+    }
+
+    // SS: every slot is as large as its type and aligned to it, the offset is that of the
+    // lowest address in the slot
+    fn stack_offset(&mut self, name: String) -> i32 {
+        if let Some(offset) = self.offsets.get(&name) {
+            return *offset;
+        }
+        let var_type = &self.symbols[&name].symbol_type;
+        let alignment = var_type.variable_alignment(self.types);
+        self.stack_size += var_type.size(self.types);
+        self.stack_size = (self.stack_size + alignment - 1) / alignment * alignment;
+        let offset = -self.stack_size;
+        self.offsets.insert(name, offset);
+        offset
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assembly_ast::{
+        AssemblyFunctionAST, AssemblyInstructionAST, AssemblyOperandAST, AssemblyProgramAST,
+        AssemblyTopLevelAST, AssemblyType, AssemblyUnaryOperatorAST,
+    };
+    use crate::pseudo_replacement::replace_pseudo_registers;
+    use crate::reg::Register;
+    use crate::symbol_table::{IdentifierAttrs, InitialValue, Symbol, SymbolTable};
+    use crate::type_table::TypeTable;
+    use crate::types::Type;
+
+    fn locals(variables: &[(&str, Type)]) -> SymbolTable {
+        variables
+            .iter()
+            .map(|(name, symbol_type)| {
+                (
+                    name.to_string(),
+                    Symbol {
+                        symbol_type: symbol_type.clone(),
+                        attrs: IdentifierAttrs::Local,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    fn instructions(ast: &AssemblyProgramAST) -> Vec<AssemblyInstructionAST> {
+        match &ast.items[0] {
+            AssemblyTopLevelAST::Function(function) => function.instructions.clone(),
+            item => panic!("expected a function, found {:?}", item),
+        }
+    }
+
+    #[test]
+    fn test_replace_pseudo_registers() {
+        // SS: arrange
+        let assembly_ast = AssemblyProgramAST {
+            items: vec![AssemblyTopLevelAST::Function(AssemblyFunctionAST {
+                name: "main".to_string(),
+                global: true,
+                instructions: vec![
+                    AssemblyInstructionAST::Mov {
+                        asm_type: AssemblyType::Longword,
+                        src: AssemblyOperandAST::Immediate(2),
+                        dst: AssemblyOperandAST::Pseudo("tmp.0".to_string()),
+                    },
+                    AssemblyInstructionAST::Mov {
+                        asm_type: AssemblyType::Longword,
+                        src: AssemblyOperandAST::Pseudo("tmp.0".to_string()),
+                        dst: AssemblyOperandAST::Pseudo("tmp.1".to_string()),
+                    },
+                    AssemblyInstructionAST::Unary {
+                        asm_type: AssemblyType::Longword,
+                        op: AssemblyUnaryOperatorAST::Neg,
+                        operand: AssemblyOperandAST::Pseudo("tmp.1".to_string()),
+                    },
+                    AssemblyInstructionAST::Mov {
+                        asm_type: AssemblyType::Longword,
+                        src: AssemblyOperandAST::Pseudo("tmp.1".to_string()),
+                        dst: AssemblyOperandAST::Register(Register::EAX),
+                    },
+                    AssemblyInstructionAST::Ret,
+                ],
+                callee_saved_registers: vec![],
+            })],
+        };
+
+        // SS: act
+        let symbols = locals(&[("tmp.0", Type::Int), ("tmp.1", Type::Int)]);
+        let assembly_ast = replace_pseudo_registers(assembly_ast, &symbols, &TypeTable::new());
+
+        // SS: assert
+        assert_eq!(
+            instructions(&assembly_ast),
+            vec![
+                AssemblyInstructionAST::AllocateStack(16),
+                AssemblyInstructionAST::Mov {
+                    asm_type: AssemblyType::Longword,
+                    src: AssemblyOperandAST::Immediate(2),
+                    dst: AssemblyOperandAST::Stack(-4),
+                },
+                AssemblyInstructionAST::Mov {
+                    asm_type: AssemblyType::Longword,
+                    src: AssemblyOperandAST::Stack(-4),
+                    dst: AssemblyOperandAST::Stack(-8),
+                },
+                AssemblyInstructionAST::Unary {
+                    asm_type: AssemblyType::Longword,
+                    op: AssemblyUnaryOperatorAST::Neg,
+                    operand: AssemblyOperandAST::Stack(-8),
+                },
+                AssemblyInstructionAST::Mov {
+                    asm_type: AssemblyType::Longword,
+                    src: AssemblyOperandAST::Stack(-8),
+                    dst: AssemblyOperandAST::Register(Register::EAX),
+                },
+                AssemblyInstructionAST::Ret,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_replace_static_variables() {
+        // SS: arrange
+        let assembly_ast = AssemblyProgramAST {
+            items: vec![AssemblyTopLevelAST::Function(AssemblyFunctionAST {
+                name: "main".to_string(),
+                global: true,
+                instructions: vec![
+                    AssemblyInstructionAST::Mov {
+                        asm_type: AssemblyType::Longword,
+                        src: AssemblyOperandAST::Pseudo("a".to_string()),
+                        dst: AssemblyOperandAST::Pseudo("tmp.0".to_string()),
+                    },
+                    AssemblyInstructionAST::Mov {
+                        asm_type: AssemblyType::Longword,
+                        src: AssemblyOperandAST::Pseudo("tmp.0".to_string()),
+                        dst: AssemblyOperandAST::Register(Register::EAX),
+                    },
+                    AssemblyInstructionAST::Ret,
+                ],
+                callee_saved_registers: vec![],
+            })],
+        };
+        let mut symbols = locals(&[("tmp.0", Type::Int)]);
+        symbols.insert(
+            "a".to_string(),
+            Symbol {
+                symbol_type: Type::Int,
+                attrs: IdentifierAttrs::Static {
+                    init: InitialValue::Tentative,
+                    global: true,
+                },
+            },
+        );
+
+        // SS: act
+        let assembly_ast = replace_pseudo_registers(assembly_ast, &symbols, &TypeTable::new());
+
+        // SS: assert
+        assert_eq!(
+            instructions(&assembly_ast),
+            vec![
+                AssemblyInstructionAST::AllocateStack(16),
+                AssemblyInstructionAST::Mov {
+                    asm_type: AssemblyType::Longword,
+                    src: AssemblyOperandAST::Data("a".to_string(), 0),
+                    dst: AssemblyOperandAST::Stack(-4),
+                },
+                AssemblyInstructionAST::Mov {
+                    asm_type: AssemblyType::Longword,
+                    src: AssemblyOperandAST::Stack(-4),
+                    dst: AssemblyOperandAST::Register(Register::EAX),
+                },
+                AssemblyInstructionAST::Ret,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_replace_pseudo_registers_sized_slots() {
+        // SS: arrange
+        let pseudo = |name: &str| AssemblyOperandAST::Pseudo(name.to_string());
+        let assembly_ast = AssemblyProgramAST {
+            items: vec![AssemblyTopLevelAST::Function(AssemblyFunctionAST {
+                name: "main".to_string(),
+                global: true,
+                instructions: vec![
+                    AssemblyInstructionAST::Movsx {
+                        src_type: AssemblyType::Longword,
+                        dst_type: AssemblyType::Quadword,
+                        src: pseudo("i.0"),
+                        dst: pseudo("l.1"),
+                    },
+                    AssemblyInstructionAST::Cvtsi2sd {
+                        asm_type: AssemblyType::Quadword,
+                        src: pseudo("l.1"),
+                        dst: pseudo("d.2"),
+                    },
+                    AssemblyInstructionAST::Cvttsd2si {
+                        asm_type: AssemblyType::Longword,
+                        src: pseudo("d.2"),
+                        dst: pseudo("u.3"),
+                    },
+                ],
+                callee_saved_registers: vec![],
+            })],
+        };
+        let symbols = locals(&[
+            ("i.0", Type::Int),
+            ("l.1", Type::Long),
+            ("d.2", Type::Double),
+            ("u.3", Type::UInt),
+        ]);
+
+        // SS: act
+        let assembly_ast = replace_pseudo_registers(assembly_ast, &symbols, &TypeTable::new());
+
+        // SS: assert
+        assert_eq!(
+            instructions(&assembly_ast),
+            vec![
+                AssemblyInstructionAST::AllocateStack(32),
+                AssemblyInstructionAST::Movsx {
+                    src_type: AssemblyType::Longword,
+                    dst_type: AssemblyType::Quadword,
+                    src: AssemblyOperandAST::Stack(-4),
+                    dst: AssemblyOperandAST::Stack(-16),
+                },
+                AssemblyInstructionAST::Cvtsi2sd {
+                    asm_type: AssemblyType::Quadword,
+                    src: AssemblyOperandAST::Stack(-16),
+                    dst: AssemblyOperandAST::Stack(-24),
+                },
+                AssemblyInstructionAST::Cvttsd2si {
+                    asm_type: AssemblyType::Longword,
+                    src: AssemblyOperandAST::Stack(-24),
+                    dst: AssemblyOperandAST::Stack(-28),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_replace_pseudo_registers_below_callee_saved() {
+        // SS: arrange
+        let assembly_ast = AssemblyProgramAST {
+            items: vec![AssemblyTopLevelAST::Function(AssemblyFunctionAST {
+                name: "main".to_string(),
+                global: true,
+                instructions: vec![AssemblyInstructionAST::Mov {
+                    asm_type: AssemblyType::Longword,
+                    src: AssemblyOperandAST::Register(Register::EBX),
+                    dst: AssemblyOperandAST::Pseudo("tmp.0".to_string()),
+                }],
+                callee_saved_registers: vec![Register::EBX],
+            })],
+        };
+        let symbols = locals(&[("tmp.0", Type::Int)]);
+
+        // SS: act
+        let assembly_ast = replace_pseudo_registers(assembly_ast, &symbols, &TypeTable::new());
+
+        // SS: assert
+        assert_eq!(
+            instructions(&assembly_ast),
+            vec![
+                AssemblyInstructionAST::AllocateStack(8),
+                AssemblyInstructionAST::Mov {
+                    asm_type: AssemblyType::Longword,
+                    src: AssemblyOperandAST::Register(Register::EBX),
+                    dst: AssemblyOperandAST::Stack(-12),
+                },
+            ]
+        );
+    }
+}
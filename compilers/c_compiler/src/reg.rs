@@ -1,18 +1,149 @@
+use crate::assembly_ast::AssemblyType;
 use std::fmt::Display;
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum Register {
     EAX,
+    EBX,
+    ECX,
+    EDX,
+    EDI,
+    ESI,
+    R8D,
+    R9D,
+    R10D,
+    R11D,
+    R12D,
+    R13D,
+    R14D,
+    R15D,
+    // SS: the stack pointer, only ever used as the base of a memory operand
+    RSP,
+    XMM0,
+    XMM1,
+    XMM2,
+    XMM3,
+    XMM4,
+    XMM5,
+    XMM6,
+    XMM7,
+    XMM8,
+    XMM9,
+    XMM10,
+    XMM11,
+    XMM12,
+    XMM13,
+    XMM14,
+    XMM15,
+}
+
+impl Register {
+    // SS: the name of the full 64-bit register, needed for push
+    pub fn quadword_name(&self) -> &'static str {
+        match self {
+            Register::EAX => "rax",
+            Register::EBX => "rbx",
+            Register::ECX => "rcx",
+            Register::EDX => "rdx",
+            Register::EDI => "rdi",
+            Register::ESI => "rsi",
+            Register::R8D => "r8",
+            Register::R9D => "r9",
+            Register::R10D => "r10",
+            Register::R11D => "r11",
+            Register::R12D => "r12",
+            Register::R13D => "r13",
+            Register::R14D => "r14",
+            Register::R15D => "r15",
+            Register::RSP => "rsp",
+            xmm => xmm.xmm_name(),
+        }
+    }
+
+    // SS: the name of the lower 32 bits of the register
+    pub fn longword_name(&self) -> &'static str {
+        match self {
+            Register::EAX => "eax",
+            Register::EBX => "ebx",
+            Register::ECX => "ecx",
+            Register::EDX => "edx",
+            Register::EDI => "edi",
+            Register::ESI => "esi",
+            Register::R8D => "r8d",
+            Register::R9D => "r9d",
+            Register::R10D => "r10d",
+            Register::R11D => "r11d",
+            Register::R12D => "r12d",
+            Register::R13D => "r13d",
+            Register::R14D => "r14d",
+            Register::R15D => "r15d",
+            Register::RSP => "esp",
+            xmm => xmm.xmm_name(),
+        }
+    }
+
+    // SS: the name of the lowest byte of the register
+    pub fn byte_name(&self) -> &'static str {
+        match self {
+            Register::EAX => "al",
+            Register::EBX => "bl",
+            Register::ECX => "cl",
+            Register::EDX => "dl",
+            Register::EDI => "dil",
+            Register::ESI => "sil",
+            Register::R8D => "r8b",
+            Register::R9D => "r9b",
+            Register::R10D => "r10b",
+            Register::R11D => "r11b",
+            Register::R12D => "r12b",
+            Register::R13D => "r13b",
+            Register::R14D => "r14b",
+            Register::R15D => "r15b",
+            Register::RSP => "spl",
+            xmm => xmm.xmm_name(),
+        }
+    }
+
+    // SS: the name of the register when it holds an operand of the given type
+    pub fn name(&self, asm_type: AssemblyType) -> &'static str {
+        match asm_type {
+            AssemblyType::Byte => self.byte_name(),
+            AssemblyType::Longword => self.longword_name(),
+            AssemblyType::Quadword | AssemblyType::Double => self.quadword_name(),
+        }
+    }
+
+    // SS: the SSE registers come last in the enum
+    pub fn is_xmm(&self) -> bool {
+        *self >= Register::XMM0
+    }
+
+    fn xmm_name(&self) -> &'static str {
+        match self {
+            Register::XMM0 => "xmm0",
+            Register::XMM1 => "xmm1",
+            Register::XMM2 => "xmm2",
+            Register::XMM3 => "xmm3",
+            Register::XMM4 => "xmm4",
+            Register::XMM5 => "xmm5",
+            Register::XMM6 => "xmm6",
+            Register::XMM7 => "xmm7",
+            Register::XMM8 => "xmm8",
+            Register::XMM9 => "xmm9",
+            Register::XMM10 => "xmm10",
+            Register::XMM11 => "xmm11",
+            Register::XMM12 => "xmm12",
+            Register::XMM13 => "xmm13",
+            Register::XMM14 => "xmm14",
+            Register::XMM15 => "xmm15",
+            _ => unreachable!("{:?} is not an SSE register", self),
+        }
+    }
 }
 
 impl Display for Register {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                Register::EAX => "eax".to_string(),
-            }
-        )
+        write!(f, "{}", self.longword_name())
     }
 }
@@ -0,0 +1,1095 @@
+use crate::assembly_ast::{
+    AssemblyFunctionAST, AssemblyInstructionAST, AssemblyOperandAST, AssemblyProgramAST,
+    AssemblyTopLevelAST, AssemblyType,
+};
+use crate::control_flow_graph::{ControlFlowGraph, NodeId};
+use crate::ir_generation::{
+    ARG_REGISTERS, DOUBLE_ARG_REGISTERS, DOUBLE_RETURN_REGISTERS, INT_RETURN_REGISTERS,
+};
+use crate::reg::Register;
+use crate::symbol_table::SymbolTable;
+use crate::types::Type;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+// SS: the general-purpose registers handed out, in order of preference. The caller-saved
+// ones come first since using them costs nothing in the prologue. R10 and R11 are left to
+// instruction fixup as scratch registers, rsp and rbp hold the stack frame.
+const GENERAL_REGISTERS: [Register; 12] = [
+    Register::EAX,
+    Register::ECX,
+    Register::EDX,
+    Register::ESI,
+    Register::EDI,
+    Register::R8D,
+    Register::R9D,
+    Register::EBX,
+    Register::R12D,
+    Register::R13D,
+    Register::R14D,
+    Register::R15D,
+];
+
+// SS: XMM14 and XMM15 are the scratch registers of instruction fixup
+const SSE_REGISTERS: [Register; 14] = [
+    Register::XMM0,
+    Register::XMM1,
+    Register::XMM2,
+    Register::XMM3,
+    Register::XMM4,
+    Register::XMM5,
+    Register::XMM6,
+    Register::XMM7,
+    Register::XMM8,
+    Register::XMM9,
+    Register::XMM10,
+    Register::XMM11,
+    Register::XMM12,
+    Register::XMM13,
+];
+
+// SS: the registers System V requires a function to preserve for its caller, a call may
+// overwrite any other
+const CALLEE_SAVED_REGISTERS: [Register; 5] = [
+    Register::EBX,
+    Register::R12D,
+    Register::R13D,
+    Register::R14D,
+    Register::R15D,
+];
+
+// SS: assigns hard registers to pseudo registers by colouring the interference graph of
+// each function, Chaitin-Briggs style. Moves between pseudos that do not interfere are
+// coalesced first, as long as that keeps the graph colourable. Pseudos that do not get a
+// register are left for pseudo replacement to put on the stack.
+pub fn allocate_registers(
+    assembly_ast: AssemblyProgramAST,
+    symbols: &SymbolTable,
+) -> AssemblyProgramAST {
+    AssemblyProgramAST {
+        items: assembly_ast
+            .items
+            .into_iter()
+            .map(|item| match item {
+                AssemblyTopLevelAST::Function(function) => {
+                    AssemblyTopLevelAST::Function(allocate_in_function(function, symbols))
+                }
+                item => item,
+            })
+            .collect(),
+    }
+}
+
+fn allocate_in_function(
+    function: AssemblyFunctionAST,
+    symbols: &SymbolTable,
+) -> AssemblyFunctionAST {
+    // SS: a pseudo whose address is taken has to stay in memory
+    let aliased = function
+        .instructions
+        .iter()
+        .filter_map(|instruction| match instruction {
+            AssemblyInstructionAST::Lea {
+                src: AssemblyOperandAST::Pseudo(name),
+                ..
+            } => Some(name.clone()),
+            _ => None,
+        })
+        .collect::<BTreeSet<_>>();
+
+    let mut instructions = function.instructions;
+    for class in [RegisterClass::General, RegisterClass::Sse] {
+        let allocator = Allocator {
+            symbols,
+            class,
+            aliased: &aliased,
+        };
+        instructions = allocator.allocate(instructions);
+    }
+    // SS: the moves coalescing turned into a register moved to itself are only dropped now,
+    // a call or ret reads the registers set right before it and the move is what sets them
+    instructions.retain(|instruction| {
+        !matches!(instruction, AssemblyInstructionAST::Mov { src, dst, .. } if src == dst)
+    });
+
+    let callee_saved_registers = CALLEE_SAVED_REGISTERS
+        .into_iter()
+        .filter(|register| {
+            instructions.iter().any(|instruction| {
+                operands(instruction)
+                    .into_iter()
+                    .any(|operand| *operand == AssemblyOperandAST::Register(*register))
+            })
+        })
+        .collect();
+
+    AssemblyFunctionAST {
+        instructions,
+        callee_saved_registers,
+        ..function
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RegisterClass {
+    General,
+    Sse,
+}
+
+impl RegisterClass {
+    fn registers(&self) -> &'static [Register] {
+        match self {
+            RegisterClass::General => &GENERAL_REGISTERS,
+            RegisterClass::Sse => &SSE_REGISTERS,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Node {
+    Register(Register),
+    Pseudo(String),
+}
+
+impl Node {
+    fn from_operand(operand: &AssemblyOperandAST) -> Option<Node> {
+        match operand {
+            AssemblyOperandAST::Register(register) => Some(Node::Register(*register)),
+            AssemblyOperandAST::Pseudo(name) => Some(Node::Pseudo(name.clone())),
+            _ => None,
+        }
+    }
+
+    fn into_operand(self) -> AssemblyOperandAST {
+        match self {
+            Node::Register(register) => AssemblyOperandAST::Register(register),
+            Node::Pseudo(name) => AssemblyOperandAST::Pseudo(name),
+        }
+    }
+}
+
+// SS: the hard registers of a class and the pseudos competing for them. Two hard registers
+// always interfere, so the edges between them are not stored. The hard registers and the
+// pseudos with at least k neighbors are kept aside as significant, they are all Briggs' test
+// has to look at.
+#[derive(Debug, Default)]
+struct InterferenceGraph {
+    neighbors: BTreeMap<Node, BTreeSet<Node>>,
+    spill_costs: BTreeMap<String, usize>,
+    significant: BTreeSet<Node>,
+    k: usize,
+}
+
+impl InterferenceGraph {
+    fn contains(&self, node: &Node) -> bool {
+        self.neighbors.contains_key(node)
+    }
+
+    fn add_edge(&mut self, a: &Node, b: &Node) {
+        if a == b || matches!((a, b), (Node::Register(_), Node::Register(_))) {
+            return;
+        }
+        self.neighbors.get_mut(a).unwrap().insert(b.clone());
+        self.neighbors.get_mut(b).unwrap().insert(a.clone());
+        for node in [a, b] {
+            if self.degree(node) >= self.k {
+                self.significant.insert(node.clone());
+            }
+        }
+    }
+
+    fn interferes(&self, a: &Node, b: &Node) -> bool {
+        match (a, b) {
+            (Node::Register(a), Node::Register(b)) => a != b,
+            _ => self.neighbors[a].contains(b),
+        }
+    }
+
+    fn degree(&self, node: &Node) -> usize {
+        self.neighbors[node].len()
+    }
+
+    // SS: folds remove into keep, which inherits its edges and its uses
+    fn merge(&mut self, remove: &Node, keep: &Node) {
+        self.significant.remove(remove);
+        for neighbor in self.neighbors.remove(remove).unwrap() {
+            self.neighbors.get_mut(&neighbor).unwrap().remove(remove);
+            if matches!(neighbor, Node::Pseudo(_)) && self.degree(&neighbor) < self.k {
+                self.significant.remove(&neighbor);
+            }
+            self.add_edge(keep, &neighbor);
+        }
+        if let (Node::Pseudo(remove), Node::Pseudo(keep)) = (remove, keep) {
+            let cost = self.spill_costs.remove(remove).unwrap_or_default();
+            *self.spill_costs.entry(keep.clone()).or_default() += cost;
+        }
+    }
+}
+
+struct Allocator<'a> {
+    symbols: &'a SymbolTable,
+    class: RegisterClass,
+    aliased: &'a BTreeSet<String>,
+}
+
+impl Allocator<'_> {
+    fn allocate(
+        &self,
+        mut instructions: Vec<AssemblyInstructionAST>,
+    ) -> Vec<AssemblyInstructionAST> {
+        // SS: coalescing changes the live ranges, so rebuild the graph after every round
+        // until no more moves can go
+        loop {
+            let mut graph = self.build_graph(&instructions);
+            let coalesced = self.coalesce(&mut graph, &instructions);
+            if coalesced.is_empty() {
+                let colors = self.color(&graph);
+                return rewrite(instructions, &colors);
+            }
+            instructions = rewrite(instructions, &coalesced);
+        }
+    }
+
+    fn is_candidate(&self, node: &Node) -> bool {
+        match node {
+            Node::Register(register) => self.class.registers().contains(register),
+            Node::Pseudo(name) => {
+                let symbol = &self.symbols[name];
+                let class = match symbol.symbol_type {
+                    Type::Double => RegisterClass::Sse,
+                    _ => RegisterClass::General,
+                };
+                class == self.class && !symbol.is_static() && !self.aliased.contains(name)
+            }
+        }
+    }
+
+    fn build_graph(&self, instructions: &[AssemblyInstructionAST]) -> InterferenceGraph {
+        let mut graph = InterferenceGraph {
+            k: self.class.registers().len(),
+            ..Default::default()
+        };
+        for register in self.class.registers() {
+            graph
+                .neighbors
+                .insert(Node::Register(*register), BTreeSet::new());
+            graph.significant.insert(Node::Register(*register));
+        }
+        for instruction in instructions {
+            for operand in operands(instruction) {
+                if let AssemblyOperandAST::Pseudo(name) = operand
+                    && self.is_candidate(&Node::Pseudo(name.clone()))
+                {
+                    graph
+                        .neighbors
+                        .entry(Node::Pseudo(name.clone()))
+                        .or_default();
+                    *graph.spill_costs.entry(name.clone()).or_default() += 1;
+                }
+            }
+        }
+
+        let cfg = ControlFlowGraph::new(instructions.to_vec());
+        let live_out = self.find_live_nodes(&cfg);
+        for (id, block) in &cfg.blocks {
+            let mut live = live_out[id].clone();
+            for index in (0..block.instructions.len()).rev() {
+                let (uses, defs) = self.uses_and_defs(&block.instructions, index);
+                // SS: the destination of a move may share a register with its source,
+                // everything else live across a definition interferes with it
+                let src = match &block.instructions[index] {
+                    AssemblyInstructionAST::Mov { src, .. } => Node::from_operand(src),
+                    _ => None,
+                };
+                for def in &defs {
+                    for node in &live {
+                        if Some(node) != src.as_ref() {
+                            graph.add_edge(def, node);
+                        }
+                    }
+                }
+                transfer(&mut live, uses, defs);
+            }
+        }
+        graph
+    }
+
+    // SS: a backward analysis, a node is live at the end of a block if it is live at the
+    // start of any of its successors. Nothing is live after a return, since the return
+    // registers are counted as used by the ret itself.
+    fn find_live_nodes(
+        &self,
+        cfg: &ControlFlowGraph<AssemblyInstructionAST>,
+    ) -> BTreeMap<usize, BTreeSet<Node>> {
+        let mut incoming = cfg
+            .blocks
+            .keys()
+            .map(|id| (*id, BTreeSet::new()))
+            .collect::<BTreeMap<_, _>>();
+        let mut outgoing = BTreeMap::new();
+
+        let mut worklist = cfg.blocks.keys().rev().copied().collect::<VecDeque<_>>();
+        let mut in_worklist = cfg
+            .blocks
+            .keys()
+            .map(|id| (*id, true))
+            .collect::<BTreeMap<_, _>>();
+        while let Some(id) = worklist.pop_front() {
+            in_worklist.insert(id, false);
+            let mut live = BTreeSet::new();
+            for successor in cfg.successors(NodeId::Block(id)) {
+                if let NodeId::Block(successor) = successor {
+                    live.extend(incoming[successor].iter().cloned());
+                }
+            }
+            outgoing.insert(id, live.clone());
+
+            let instructions = &cfg.blocks[&id].instructions;
+            for index in (0..instructions.len()).rev() {
+                let (uses, defs) = self.uses_and_defs(instructions, index);
+                transfer(&mut live, uses, defs);
+            }
+            if live != incoming[&id] {
+                incoming.insert(id, live);
+                for predecessor in cfg.predecessors(NodeId::Block(id)) {
+                    if let NodeId::Block(predecessor) = predecessor
+                        && !in_worklist[predecessor]
+                    {
+                        in_worklist.insert(*predecessor, true);
+                        worklist.push_back(*predecessor);
+                    }
+                }
+            }
+        }
+        outgoing
+    }
+
+    // SS: the nodes of this class the instruction at index reads and writes. The whole
+    // block is passed since calls and returns read the registers set up right before them.
+    fn uses_and_defs(
+        &self,
+        instructions: &[AssemblyInstructionAST],
+        index: usize,
+    ) -> (Vec<Node>, Vec<Node>) {
+        let (uses, defs) = uses_and_defs(instructions, index);
+        (
+            uses.into_iter().filter(|n| self.is_candidate(n)).collect(),
+            defs.into_iter().filter(|n| self.is_candidate(n)).collect(),
+        )
+    }
+
+    // SS: merges the source and destination of moves that do not interfere. Briggs' test
+    // keeps two pseudos colourable, George's test one pseudo and a hard register. Returns
+    // what each merged pseudo became.
+    fn coalesce(
+        &self,
+        graph: &mut InterferenceGraph,
+        instructions: &[AssemblyInstructionAST],
+    ) -> BTreeMap<String, Node> {
+        let k = self.class.registers().len();
+        let mut merged = BTreeMap::new();
+        for instruction in instructions {
+            let AssemblyInstructionAST::Mov { src, dst, .. } = instruction else {
+                continue;
+            };
+            let (Some(src), Some(dst)) = (Node::from_operand(src), Node::from_operand(dst)) else {
+                continue;
+            };
+            let src = find(&mut merged, src);
+            let dst = find(&mut merged, dst);
+            if src == dst
+                || !graph.contains(&src)
+                || !graph.contains(&dst)
+                || graph.interferes(&src, &dst)
+            {
+                continue;
+            }
+
+            // SS: moving the edges of the pseudo that goes costs one step each, so the one
+            // with fewer goes, a pseudo live across the whole function keeps its own
+            let (keep, remove) = match (&src, &dst) {
+                (Node::Register(_), Node::Register(_)) => continue,
+                (Node::Register(_), _) => (src, dst),
+                (_, Node::Register(_)) => (dst, src),
+                _ if graph.degree(&src) > graph.degree(&dst) => (src, dst),
+                _ => (dst, src),
+            };
+            let conservative = match keep {
+                Node::Register(_) => george(graph, &keep, &remove, k),
+                Node::Pseudo(_) => briggs(graph, &keep, &remove, k),
+            };
+            if conservative {
+                graph.merge(&remove, &keep);
+                if let Node::Pseudo(name) = remove {
+                    merged.insert(name, keep);
+                }
+            }
+        }
+
+        let names = merged.keys().cloned().collect::<Vec<_>>();
+        for name in names {
+            find(&mut merged, Node::Pseudo(name));
+        }
+        merged
+    }
+
+    // SS: simplify pushes the pseudos on a stack, taking one with fewer neighbors than
+    // there are registers while there is one, otherwise the one cheapest to spill. Select
+    // then pops them and gives each the first register its neighbors do not have. One that
+    // finds none is spilled. The pseudos left are kept ordered both by degree and by spill
+    // metric, so neither choice means scanning them all.
+    fn color(&self, graph: &InterferenceGraph) -> BTreeMap<String, Node> {
+        let registers = self.class.registers();
+        let k = registers.len();
+        let mut degrees = graph
+            .neighbors
+            .iter()
+            .filter_map(|(node, neighbors)| match node {
+                Node::Pseudo(name) => Some((name.clone(), neighbors.len())),
+                Node::Register(_) => None,
+            })
+            .collect::<BTreeMap<_, _>>();
+        // SS: the metric is never negative, so its bits order the same way its value does
+        let spill_metric = |name: &String, degree: usize| {
+            (graph.spill_costs[name] as f64 / degree as f64).to_bits()
+        };
+        let mut by_degree = degrees
+            .iter()
+            .map(|(name, degree)| (*degree, name.clone()))
+            .collect::<BTreeSet<_>>();
+        let mut by_spill_metric = degrees
+            .iter()
+            .map(|(name, degree)| (spill_metric(name, *degree), name.clone()))
+            .collect::<BTreeSet<_>>();
+
+        let mut stack = vec![];
+        while let Some((degree, name)) = by_degree.first() {
+            let next = if *degree < k {
+                name.clone()
+            } else {
+                by_spill_metric.first().unwrap().1.clone()
+            };
+            let degree = degrees.remove(&next).unwrap();
+            by_degree.remove(&(degree, next.clone()));
+            by_spill_metric.remove(&(spill_metric(&next, degree), next.clone()));
+            for neighbor in &graph.neighbors[&Node::Pseudo(next.clone())] {
+                if let Node::Pseudo(name) = neighbor
+                    && let Some(degree) = degrees.get_mut(name)
+                {
+                    by_degree.remove(&(*degree, name.clone()));
+                    by_spill_metric.remove(&(spill_metric(name, *degree), name.clone()));
+                    *degree -= 1;
+                    by_degree.insert((*degree, name.clone()));
+                    by_spill_metric.insert((spill_metric(name, *degree), name.clone()));
+                }
+            }
+            stack.push(next);
+        }
+
+        let mut colors = BTreeMap::new();
+        while let Some(name) = stack.pop() {
+            let taken = graph.neighbors[&Node::Pseudo(name.clone())]
+                .iter()
+                .filter_map(|neighbor| match neighbor {
+                    Node::Register(register) => Some(Node::Register(*register)),
+                    Node::Pseudo(name) => colors.get(name).cloned(),
+                })
+                .collect::<Vec<_>>();
+            if let Some(register) = registers
+                .iter()
+                .find(|register| !taken.contains(&Node::Register(**register)))
+            {
+                colors.insert(name, Node::Register(*register));
+            }
+        }
+        colors
+    }
+}
+
+// SS: George's test, merging a pseudo into a hard register is safe if each neighbor of the
+// pseudo already interferes with the register or has few enough neighbors to get one anyway
+fn george(graph: &InterferenceGraph, register: &Node, pseudo: &Node, k: usize) -> bool {
+    graph.neighbors[pseudo]
+        .iter()
+        .all(|neighbor| graph.interferes(neighbor, register) || graph.degree(neighbor) < k)
+}
+
+// SS: Briggs' test, merging two pseudos is safe if the result has fewer than k neighbors
+// with k or more neighbors of their own. A pseudo live across a long function can have
+// thousands of neighbors, most of them with only a few, so the smaller of the neighbors and
+// the significant nodes is searched, and counting stops at k.
+fn briggs(graph: &InterferenceGraph, a: &Node, b: &Node, k: usize) -> bool {
+    let (a, b) = (&graph.neighbors[a], &graph.neighbors[b]);
+    let is_significant = |neighbor: &&Node| {
+        let mut degree = graph.degree(neighbor);
+        if a.contains(neighbor) && b.contains(neighbor) {
+            degree -= 1;
+        }
+        matches!(neighbor, Node::Register(_)) || degree >= k
+    };
+    let significant = if graph.significant.len() < a.len() + b.len() {
+        graph
+            .significant
+            .iter()
+            .filter(|node| a.contains(node) || b.contains(node))
+            .filter(is_significant)
+            .take(k)
+            .count()
+    } else {
+        a.union(b).filter(is_significant).take(k).count()
+    };
+    significant < k
+}
+
+// SS: what node was merged into, following the chain of merges. Every pseudo on the way is
+// then pointed straight at the end of the chain, so walking it again takes a single step.
+fn find(merged: &mut BTreeMap<String, Node>, mut node: Node) -> Node {
+    let mut path = vec![];
+    while let Node::Pseudo(name) = &node
+        && let Some(next) = merged.get(name)
+    {
+        path.push(name.clone());
+        node = next.clone();
+    }
+    for name in path {
+        merged.insert(name, node.clone());
+    }
+    node
+}
+
+fn transfer(live: &mut BTreeSet<Node>, uses: Vec<Node>, defs: Vec<Node>) {
+    for def in defs {
+        live.remove(&def);
+    }
+    live.extend(uses);
+}
+
+// SS: replaces the pseudos that got a register. Moves that became no-ops are kept, they are
+// only dropped once every register class is allocated.
+fn rewrite(
+    instructions: Vec<AssemblyInstructionAST>,
+    replacements: &BTreeMap<String, Node>,
+) -> Vec<AssemblyInstructionAST> {
+    instructions
+        .into_iter()
+        .map(|mut instruction| {
+            for operand in operands_mut(&mut instruction) {
+                if let AssemblyOperandAST::Pseudo(name) = operand
+                    && let Some(node) = replacements.get(name)
+                {
+                    *operand = node.clone().into_operand();
+                }
+            }
+            instruction
+        })
+        .collect()
+}
+
+// SS: the registers and pseudos an instruction reads and writes, over all classes. A
+// register used as the address of a memory operand is read.
+fn uses_and_defs(instructions: &[AssemblyInstructionAST], index: usize) -> (Vec<Node>, Vec<Node>) {
+    let mut uses = vec![];
+    let mut defs = vec![];
+    let read = |operand: &AssemblyOperandAST, uses: &mut Vec<Node>| match operand {
+        AssemblyOperandAST::Register(_) | AssemblyOperandAST::Pseudo(_) => {
+            uses.extend(Node::from_operand(operand))
+        }
+        AssemblyOperandAST::Memory(base, _) => uses.push(Node::Register(*base)),
+        AssemblyOperandAST::Indexed { base, index, .. } => {
+            uses.extend([Node::Register(*base), Node::Register(*index)])
+        }
+        _ => {}
+    };
+    let write =
+        |operand: &AssemblyOperandAST, uses: &mut Vec<Node>, defs: &mut Vec<Node>| match operand {
+            AssemblyOperandAST::Register(_) | AssemblyOperandAST::Pseudo(_) => {
+                defs.extend(Node::from_operand(operand))
+            }
+            _ => read(operand, uses),
+        };
+
+    match &instructions[index] {
+        // SS: writing the low byte of a register keeps the rest of it
+        AssemblyInstructionAST::Mov {
+            asm_type: AssemblyType::Byte,
+            src,
+            dst: dst @ AssemblyOperandAST::Register(_),
+        } => {
+            read(src, &mut uses);
+            read(dst, &mut uses);
+            write(dst, &mut uses, &mut defs);
+        }
+        AssemblyInstructionAST::Mov { src, dst, .. }
+        | AssemblyInstructionAST::Movsx { src, dst, .. }
+        | AssemblyInstructionAST::MovZeroExtend { src, dst, .. }
+        | AssemblyInstructionAST::Lea { src, dst }
+        | AssemblyInstructionAST::Cvttsd2si { src, dst, .. }
+        | AssemblyInstructionAST::Cvtsi2sd { src, dst, .. } => {
+            read(src, &mut uses);
+            write(dst, &mut uses, &mut defs);
+        }
+        AssemblyInstructionAST::Unary { operand, .. }
+        | AssemblyInstructionAST::SetCC { operand, .. } => {
+            read(operand, &mut uses);
+            write(operand, &mut uses, &mut defs);
+        }
+        AssemblyInstructionAST::Binary { src, dst, .. } => {
+            read(src, &mut uses);
+            read(dst, &mut uses);
+            write(dst, &mut uses, &mut defs);
+        }
+        AssemblyInstructionAST::Cmp { src, dst, .. } => {
+            read(src, &mut uses);
+            read(dst, &mut uses);
+        }
+        AssemblyInstructionAST::Idiv { operand, .. }
+        | AssemblyInstructionAST::Div { operand, .. } => {
+            read(operand, &mut uses);
+            uses.extend([Node::Register(Register::EAX), Node::Register(Register::EDX)]);
+            defs.extend([Node::Register(Register::EAX), Node::Register(Register::EDX)]);
+        }
+        AssemblyInstructionAST::Cdq(_) => {
+            uses.push(Node::Register(Register::EAX));
+            defs.push(Node::Register(Register::EDX));
+        }
+        AssemblyInstructionAST::Push(operand)
+        | AssemblyInstructionAST::JmpTable { index: operand, .. } => read(operand, &mut uses),
+        AssemblyInstructionAST::Call(_) => {
+            let arg_registers = ARG_REGISTERS
+                .iter()
+                .chain(DOUBLE_ARG_REGISTERS.iter())
+                .copied()
+                .collect::<Vec<_>>();
+            uses = registers_set_before(instructions, index, &arg_registers);
+            defs = GENERAL_REGISTERS
+                .iter()
+                .chain(SSE_REGISTERS.iter())
+                .filter(|register| !CALLEE_SAVED_REGISTERS.contains(register))
+                .map(|register| Node::Register(*register))
+                .collect();
+        }
+        AssemblyInstructionAST::Ret => {
+            let return_registers = INT_RETURN_REGISTERS
+                .iter()
+                .chain(DOUBLE_RETURN_REGISTERS.iter())
+                .copied()
+                .collect::<Vec<_>>();
+            uses = registers_set_before(instructions, index, &return_registers);
+        }
+        AssemblyInstructionAST::Jmp(_)
+        | AssemblyInstructionAST::JmpCC { .. }
+        | AssemblyInstructionAST::Label(_)
+        | AssemblyInstructionAST::AllocateStack(_)
+        | AssemblyInstructionAST::DeallocateStack(_) => {}
+    }
+    (uses, defs)
+}
+
+// SS: the registers among candidates written since the previous call in the block. The
+// arguments of a call and the value a function returns are always moved into their
+// registers right before the call or ret, so these are the ones it reads.
+fn registers_set_before(
+    instructions: &[AssemblyInstructionAST],
+    index: usize,
+    candidates: &[Register],
+) -> Vec<Node> {
+    let mut registers = vec![];
+    for previous in (0..index).rev() {
+        if matches!(instructions[previous], AssemblyInstructionAST::Call(_)) {
+            break;
+        }
+        for def in uses_and_defs(instructions, previous).1 {
+            if let Node::Register(register) = def
+                && candidates.contains(&register)
+                && !registers.contains(&def)
+            {
+                registers.push(def);
+            }
+        }
+    }
+    registers
+}
+
+fn operands(instruction: &AssemblyInstructionAST) -> Vec<&AssemblyOperandAST> {
+    match instruction {
+        AssemblyInstructionAST::Mov { src, dst, .. }
+        | AssemblyInstructionAST::Movsx { src, dst, .. }
+        | AssemblyInstructionAST::MovZeroExtend { src, dst, .. }
+        | AssemblyInstructionAST::Lea { src, dst }
+        | AssemblyInstructionAST::Cvttsd2si { src, dst, .. }
+        | AssemblyInstructionAST::Cvtsi2sd { src, dst, .. }
+        | AssemblyInstructionAST::Binary { src, dst, .. }
+        | AssemblyInstructionAST::Cmp { src, dst, .. } => vec![src, dst],
+        AssemblyInstructionAST::Unary { operand, .. }
+        | AssemblyInstructionAST::Idiv { operand, .. }
+        | AssemblyInstructionAST::Div { operand, .. }
+        | AssemblyInstructionAST::SetCC { operand, .. }
+        | AssemblyInstructionAST::Push(operand)
+        | AssemblyInstructionAST::JmpTable { index: operand, .. } => vec![operand],
+        AssemblyInstructionAST::Cdq(_)
+        | AssemblyInstructionAST::Jmp(_)
+        | AssemblyInstructionAST::JmpCC { .. }
+        | AssemblyInstructionAST::Label(_)
+        | AssemblyInstructionAST::AllocateStack(_)
+        | AssemblyInstructionAST::DeallocateStack(_)
+        | AssemblyInstructionAST::Call(_)
+        | AssemblyInstructionAST::Ret => vec![],
+    }
+}
+
+fn operands_mut(instruction: &mut AssemblyInstructionAST) -> Vec<&mut AssemblyOperandAST> {
+    match instruction {
+        AssemblyInstructionAST::Mov { src, dst, .. }
+        | AssemblyInstructionAST::Movsx { src, dst, .. }
+        | AssemblyInstructionAST::MovZeroExtend { src, dst, .. }
+        | AssemblyInstructionAST::Lea { src, dst }
+        | AssemblyInstructionAST::Cvttsd2si { src, dst, .. }
+        | AssemblyInstructionAST::Cvtsi2sd { src, dst, .. }
+        | AssemblyInstructionAST::Binary { src, dst, .. }
+        | AssemblyInstructionAST::Cmp { src, dst, .. } => vec![src, dst],
+        AssemblyInstructionAST::Unary { operand, .. }
+        | AssemblyInstructionAST::Idiv { operand, .. }
+        | AssemblyInstructionAST::Div { operand, .. }
+        | AssemblyInstructionAST::SetCC { operand, .. }
+        | AssemblyInstructionAST::Push(operand)
+        | AssemblyInstructionAST::JmpTable { index: operand, .. } => vec![operand],
+        AssemblyInstructionAST::Cdq(_)
+        | AssemblyInstructionAST::Jmp(_)
+        | AssemblyInstructionAST::JmpCC { .. }
+        | AssemblyInstructionAST::Label(_)
+        | AssemblyInstructionAST::AllocateStack(_)
+        | AssemblyInstructionAST::DeallocateStack(_)
+        | AssemblyInstructionAST::Call(_)
+        | AssemblyInstructionAST::Ret => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assembly_ast::{
+        AssemblyBinaryOperatorAST, AssemblyFunctionAST, AssemblyInstructionAST, AssemblyOperandAST,
+        AssemblyProgramAST, AssemblyTopLevelAST, AssemblyType, ConditionCode,
+    };
+    use crate::reg::Register;
+    use crate::register_allocation::allocate_registers;
+    use crate::symbol_table::{IdentifierAttrs, Symbol, SymbolTable};
+    use crate::types::Type;
+
+    fn pseudo(name: &str) -> AssemblyOperandAST {
+        AssemblyOperandAST::Pseudo(name.to_string())
+    }
+
+    fn locals(names: &[&str], symbol_type: Type) -> SymbolTable {
+        names
+            .iter()
+            .map(|name| {
+                (
+                    name.to_string(),
+                    Symbol {
+                        symbol_type: symbol_type.clone(),
+                        attrs: IdentifierAttrs::Local,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    fn program(instructions: Vec<AssemblyInstructionAST>) -> AssemblyProgramAST {
+        AssemblyProgramAST {
+            items: vec![AssemblyTopLevelAST::Function(AssemblyFunctionAST {
+                name: "main".to_string(),
+                global: true,
+                instructions,
+                callee_saved_registers: vec![],
+            })],
+        }
+    }
+
+    fn function(ast: &AssemblyProgramAST) -> AssemblyFunctionAST {
+        match &ast.items[0] {
+            AssemblyTopLevelAST::Function(function) => function.clone(),
+            item => panic!("expected a function, found {:?}", item),
+        }
+    }
+
+    #[test]
+    fn test_allocate_registers_coalesces_moves() {
+        // SS: arrange
+        let assembly_ast = program(vec![
+            AssemblyInstructionAST::Mov {
+                asm_type: AssemblyType::Longword,
+                src: AssemblyOperandAST::Immediate(1),
+                dst: pseudo("a"),
+            },
+            AssemblyInstructionAST::Mov {
+                asm_type: AssemblyType::Longword,
+                src: pseudo("a"),
+                dst: pseudo("b"),
+            },
+            AssemblyInstructionAST::Binary {
+                op: AssemblyBinaryOperatorAST::Add,
+                asm_type: AssemblyType::Longword,
+                src: AssemblyOperandAST::Immediate(2),
+                dst: pseudo("b"),
+            },
+            AssemblyInstructionAST::Mov {
+                asm_type: AssemblyType::Longword,
+                src: pseudo("b"),
+                dst: AssemblyOperandAST::Register(Register::EAX),
+            },
+            AssemblyInstructionAST::Ret,
+        ]);
+        let symbols = locals(&["a", "b"], Type::Int);
+
+        // SS: act
+        let assembly_ast = allocate_registers(assembly_ast, &symbols);
+
+        // SS: assert
+        let function = function(&assembly_ast);
+        assert_eq!(
+            function.instructions,
+            vec![
+                AssemblyInstructionAST::Mov {
+                    asm_type: AssemblyType::Longword,
+                    src: AssemblyOperandAST::Immediate(1),
+                    dst: AssemblyOperandAST::Register(Register::EAX),
+                },
+                AssemblyInstructionAST::Binary {
+                    op: AssemblyBinaryOperatorAST::Add,
+                    asm_type: AssemblyType::Longword,
+                    src: AssemblyOperandAST::Immediate(2),
+                    dst: AssemblyOperandAST::Register(Register::EAX),
+                },
+                AssemblyInstructionAST::Ret,
+            ]
+        );
+        assert!(function.callee_saved_registers.is_empty());
+    }
+
+    #[test]
+    fn test_allocate_registers_coalesces_chain_of_moves() {
+        // SS: arrange
+        let names = (0..1000).map(|i| format!("p.{}", i)).collect::<Vec<_>>();
+        let long = |src, dst| AssemblyInstructionAST::Mov {
+            asm_type: AssemblyType::Longword,
+            src,
+            dst,
+        };
+        let mut instructions = vec![long(AssemblyOperandAST::Immediate(1), pseudo(&names[0]))];
+        instructions.extend(
+            names
+                .windows(2)
+                .map(|pair| long(pseudo(&pair[0]), pseudo(&pair[1]))),
+        );
+        instructions.extend([
+            long(
+                pseudo(&names[999]),
+                AssemblyOperandAST::Register(Register::EAX),
+            ),
+            AssemblyInstructionAST::Ret,
+        ]);
+        let symbols = locals(
+            &names.iter().map(String::as_str).collect::<Vec<_>>(),
+            Type::Int,
+        );
+
+        // SS: act
+        let assembly_ast = allocate_registers(program(instructions), &symbols);
+
+        // SS: assert
+        assert_eq!(
+            function(&assembly_ast).instructions,
+            vec![
+                long(
+                    AssemblyOperandAST::Immediate(1),
+                    AssemblyOperandAST::Register(Register::EAX)
+                ),
+                AssemblyInstructionAST::Ret,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_allocate_registers_return_value_live_across_loop() {
+        // SS: arrange
+        let long = |src, dst| AssemblyInstructionAST::Mov {
+            asm_type: AssemblyType::Longword,
+            src,
+            dst,
+        };
+        let assembly_ast = program(vec![
+            long(AssemblyOperandAST::Immediate(5), pseudo("result")),
+            long(AssemblyOperandAST::Immediate(1), pseudo("tmp")),
+            AssemblyInstructionAST::Label("loop".to_string()),
+            AssemblyInstructionAST::Cmp {
+                asm_type: AssemblyType::Longword,
+                src: AssemblyOperandAST::Immediate(0),
+                dst: pseudo("tmp"),
+            },
+            AssemblyInstructionAST::JmpCC {
+                condition: ConditionCode::E,
+                target: "end".to_string(),
+            },
+            long(AssemblyOperandAST::Immediate(0), pseudo("tmp")),
+            AssemblyInstructionAST::Jmp("loop".to_string()),
+            AssemblyInstructionAST::Label("end".to_string()),
+            long(
+                pseudo("result"),
+                AssemblyOperandAST::Register(Register::EAX),
+            ),
+            AssemblyInstructionAST::Ret,
+        ]);
+        let symbols = locals(&["result", "tmp"], Type::Int);
+
+        // SS: act
+        let assembly_ast = allocate_registers(assembly_ast, &symbols);
+
+        // SS: assert
+        // SS: the result is coalesced into eax, which the ret still reads after the loop,
+        // so the loop counter has to go somewhere else
+        let eax = AssemblyOperandAST::Register(Register::EAX);
+        let ecx = AssemblyOperandAST::Register(Register::ECX);
+        let function = function(&assembly_ast);
+        assert_eq!(
+            function.instructions,
+            vec![
+                long(AssemblyOperandAST::Immediate(5), eax),
+                long(AssemblyOperandAST::Immediate(1), ecx.clone()),
+                AssemblyInstructionAST::Label("loop".to_string()),
+                AssemblyInstructionAST::Cmp {
+                    asm_type: AssemblyType::Longword,
+                    src: AssemblyOperandAST::Immediate(0),
+                    dst: ecx.clone(),
+                },
+                AssemblyInstructionAST::JmpCC {
+                    condition: ConditionCode::E,
+                    target: "end".to_string(),
+                },
+                long(AssemblyOperandAST::Immediate(0), ecx),
+                AssemblyInstructionAST::Jmp("loop".to_string()),
+                AssemblyInstructionAST::Label("end".to_string()),
+                AssemblyInstructionAST::Ret,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_allocate_registers_callee_saved_across_call() {
+        // SS: arrange
+        let assembly_ast = program(vec![
+            AssemblyInstructionAST::Mov {
+                asm_type: AssemblyType::Double,
+                src: AssemblyOperandAST::Register(Register::XMM0),
+                dst: pseudo("d"),
+            },
+            AssemblyInstructionAST::Mov {
+                asm_type: AssemblyType::Longword,
+                src: AssemblyOperandAST::Immediate(7),
+                dst: pseudo("x"),
+            },
+            AssemblyInstructionAST::Call("f".to_string()),
+            AssemblyInstructionAST::Cvttsd2si {
+                asm_type: AssemblyType::Longword,
+                src: pseudo("d"),
+                dst: AssemblyOperandAST::Register(Register::EAX),
+            },
+            AssemblyInstructionAST::Binary {
+                op: AssemblyBinaryOperatorAST::Add,
+                asm_type: AssemblyType::Longword,
+                src: pseudo("x"),
+                dst: AssemblyOperandAST::Register(Register::EAX),
+            },
+            AssemblyInstructionAST::Ret,
+        ]);
+        let mut symbols = locals(&["x"], Type::Int);
+        symbols.extend(locals(&["d"], Type::Double));
+
+        // SS: act
+        let assembly_ast = allocate_registers(assembly_ast, &symbols);
+
+        // SS: assert
+        let function = function(&assembly_ast);
+        assert_eq!(function.callee_saved_registers, vec![Register::EBX]);
+        assert_eq!(
+            function.instructions[1],
+            AssemblyInstructionAST::Mov {
+                asm_type: AssemblyType::Longword,
+                src: AssemblyOperandAST::Immediate(7),
+                dst: AssemblyOperandAST::Register(Register::EBX),
+            }
+        );
+        // SS: every SSE register is caller-saved, so the double has to be spilled
+        assert_eq!(
+            function.instructions[0],
+            AssemblyInstructionAST::Mov {
+                asm_type: AssemblyType::Double,
+                src: AssemblyOperandAST::Register(Register::XMM0),
+                dst: pseudo("d"),
+            }
+        );
+    }
+
+    #[test]
+    fn test_allocate_registers_spills_cheapest() {
+        // SS: arrange
+        let names = (0..13).map(|i| format!("p.{:02}", i)).collect::<Vec<_>>();
+        let mut instructions = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| AssemblyInstructionAST::Mov {
+                asm_type: AssemblyType::Longword,
+                src: AssemblyOperandAST::Immediate(i as i64),
+                dst: pseudo(name),
+            })
+            .collect::<Vec<_>>();
+        instructions.extend(
+            names[1..]
+                .iter()
+                .map(|name| AssemblyInstructionAST::Binary {
+                    op: AssemblyBinaryOperatorAST::Add,
+                    asm_type: AssemblyType::Longword,
+                    src: pseudo(name),
+                    dst: pseudo(&names[0]),
+                }),
+        );
+        instructions.extend([
+            AssemblyInstructionAST::Mov {
+                asm_type: AssemblyType::Longword,
+                src: pseudo(&names[0]),
+                dst: AssemblyOperandAST::Register(Register::EAX),
+            },
+            AssemblyInstructionAST::Ret,
+        ]);
+        let symbols = locals(
+            &names.iter().map(String::as_str).collect::<Vec<_>>(),
+            Type::Int,
+        );
+
+        // SS: act
+        let assembly_ast = allocate_registers(program(instructions), &symbols);
+
+        // SS: assert
+        let function = function(&assembly_ast);
+        let spilled = function
+            .instructions
+            .iter()
+            .filter_map(|instruction| match instruction {
+                AssemblyInstructionAST::Mov {
+                    dst: AssemblyOperandAST::Pseudo(name),
+                    ..
+                } => Some(name.clone()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(spilled, vec!["p.01".to_string()]);
+        assert_eq!(
+            function.callee_saved_registers,
+            vec![
+                Register::EBX,
+                Register::R12D,
+                Register::R13D,
+                Register::R14D,
+                Register::R15D
+            ]
+        );
+    }
+}
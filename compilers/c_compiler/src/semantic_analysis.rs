@@ -0,0 +1,1153 @@
+use crate::name_generator::NameGenerator;
+use crate::parse_ast::{
+    BlockAST, BlockItemAST, DeclarationAST, ExprAST, ExternalDeclarationAST, ForInitAST,
+    FunctionAST, InitializerAST, MemberDeclarationAST, ProgramAST, StmtAST, StorageClassAST,
+    StructDeclarationAST,
+};
+use crate::types::Type;
+use std::collections::HashMap;
+
+// SS: identifier resolution, renames every local variable to a unique name so that later
+// passes do not need to know about C's scoping rules. Functions, file scope variables and
+// extern locals have linkage and are left untouched. Structure and union tags live in a
+// namespace of their own and every tag declaration gets a unique name, including those at
+// file scope.
+pub fn resolve_program(
+    ast: ProgramAST,
+    name_generator: &mut NameGenerator,
+) -> Result<ProgramAST, String> {
+    let mut resolver = IdentifierResolver::new(name_generator);
+
+    // SS: the file scope
+    resolver.push_scope();
+    let declarations = ast
+        .declarations
+        .into_iter()
+        .map(|declaration| match declaration {
+            ExternalDeclarationAST::Function(function) => resolver
+                .resolve_function_declaration(function)
+                .map(ExternalDeclarationAST::Function),
+            ExternalDeclarationAST::Variable(declaration) => resolver
+                .resolve_file_scope_declaration(declaration)
+                .map(ExternalDeclarationAST::Variable),
+            ExternalDeclarationAST::Struct(declaration) => resolver
+                .resolve_struct_declaration(declaration)
+                .map(ExternalDeclarationAST::Struct),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(ProgramAST { declarations })
+}
+
+struct ScopeEntry {
+    unique_name: String,
+    has_linkage: bool,
+}
+
+struct TagEntry {
+    unique_tag: String,
+    is_union: bool,
+}
+
+struct IdentifierResolver<'a> {
+    name_generator: &'a mut NameGenerator,
+
+    // SS: one map per enclosing block, innermost last
+    scopes: Vec<HashMap<String, ScopeEntry>>,
+
+    // SS: the structure and union tags declared in each enclosing block
+    tag_scopes: Vec<HashMap<String, TagEntry>>,
+}
+
+impl<'a> IdentifierResolver<'a> {
+    fn new(name_generator: &'a mut NameGenerator) -> Self {
+        IdentifierResolver {
+            name_generator,
+            scopes: vec![],
+            tag_scopes: vec![],
+        }
+    }
+
+    fn push_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+        self.tag_scopes.push(HashMap::new());
+    }
+
+    fn pop_scope(&mut self) {
+        self.scopes.pop();
+        self.tag_scopes.pop();
+    }
+
+    fn resolve_function_declaration(
+        &mut self,
+        function: FunctionAST,
+    ) -> Result<FunctionAST, String> {
+        let FunctionAST {
+            name,
+            params,
+            body,
+            function_type,
+            storage_class,
+            line,
+        } = function;
+
+        // SS: a function may be redeclared in the same scope, but not as a variable
+        let current_scope = self.scopes.last_mut().unwrap();
+        if let Some(entry) = current_scope.get(&name)
+            && !entry.has_linkage
+        {
+            return Err(format!(
+                "Line {}: Semantic error: Duplicate declaration of {:?}",
+                line, name
+            ));
+        }
+        current_scope.insert(
+            name.clone(),
+            ScopeEntry {
+                unique_name: name.clone(),
+                has_linkage: true,
+            },
+        );
+
+        let function_type = self.resolve_type(function_type, line)?;
+
+        // SS: the parameters and the outermost block of the body share one scope
+        self.push_scope();
+        let resolved = self.resolve_params_and_body(params, body, line);
+        self.pop_scope();
+        let (params, body) = resolved?;
+
+        Ok(FunctionAST {
+            name,
+            params,
+            body,
+            function_type,
+            storage_class,
+            line,
+        })
+    }
+
+    // SS: file scope variables keep their names, redeclarations are sorted out during type
+    // checking
+    fn resolve_file_scope_declaration(
+        &mut self,
+        declaration: DeclarationAST,
+    ) -> Result<DeclarationAST, String> {
+        self.scopes.last_mut().unwrap().insert(
+            declaration.name.clone(),
+            ScopeEntry {
+                unique_name: declaration.name.clone(),
+                has_linkage: true,
+            },
+        );
+        let var_type = self.resolve_type(declaration.var_type, declaration.line)?;
+        let init = declaration
+            .init
+            .map(|init| self.resolve_initializer(init))
+            .transpose()?;
+        Ok(DeclarationAST {
+            init,
+            var_type,
+            ..declaration
+        })
+    }
+
+    // SS: a declaration of a tag that is already declared in the same scope refers to the
+    // same type, otherwise it declares a new type that hides any outer one. Whether a type
+    // is defined more than once is checked during type checking.
+    fn resolve_struct_declaration(
+        &mut self,
+        declaration: StructDeclarationAST,
+    ) -> Result<StructDeclarationAST, String> {
+        let StructDeclarationAST {
+            tag,
+            is_union,
+            members,
+            line,
+        } = declaration;
+
+        let unique_tag = match self.tag_scopes.last().unwrap().get(&tag) {
+            Some(entry) if entry.is_union != is_union => {
+                return Err(format!(
+                    "Line {}: Semantic error: Conflicting declarations of tag {:?}",
+                    line, tag
+                ));
+            }
+            Some(entry) => entry.unique_tag.clone(),
+            None => self.declare_tag(&tag, is_union),
+        };
+
+        let members = members
+            .map(|members| {
+                members
+                    .into_iter()
+                    .map(|member| {
+                        Ok(MemberDeclarationAST {
+                            member_type: self.resolve_type(member.member_type, member.line)?,
+                            ..member
+                        })
+                    })
+                    .collect::<Result<Vec<_>, String>>()
+            })
+            .transpose()?;
+
+        Ok(StructDeclarationAST {
+            tag: unique_tag,
+            is_union,
+            members,
+            line,
+        })
+    }
+
+    fn declare_tag(&mut self, tag: &str, is_union: bool) -> String {
+        let unique_tag = self.name_generator.make_unique(tag);
+        self.tag_scopes.last_mut().unwrap().insert(
+            tag.to_string(),
+            TagEntry {
+                unique_tag: unique_tag.clone(),
+                is_union,
+            },
+        );
+        unique_tag
+    }
+
+    // SS: replaces the tags in a type by their unique names. Referring to a tag that is not
+    // visible declares it in the current scope, as an incomplete type.
+    fn resolve_type(&mut self, resolved_type: Type, line: usize) -> Result<Type, String> {
+        let is_union = matches!(resolved_type, Type::Union(_));
+        match resolved_type {
+            Type::Structure(tag) | Type::Union(tag) => {
+                let entry = self
+                    .tag_scopes
+                    .iter()
+                    .rev()
+                    .find_map(|scope| scope.get(&tag));
+                let unique_tag = match entry {
+                    Some(entry) if entry.is_union != is_union => {
+                        return Err(format!(
+                            "Line {}: Semantic error: Conflicting declarations of tag {:?}",
+                            line, tag
+                        ));
+                    }
+                    Some(entry) => entry.unique_tag.clone(),
+                    None => self.declare_tag(&tag, is_union),
+                };
+                Ok(if is_union {
+                    Type::Union(unique_tag)
+                } else {
+                    Type::Structure(unique_tag)
+                })
+            }
+            Type::Pointer(referenced) => Ok(Type::Pointer(Box::new(
+                self.resolve_type(*referenced, line)?,
+            ))),
+            Type::Array { element, size } => Ok(Type::Array {
+                element: Box::new(self.resolve_type(*element, line)?),
+                size,
+            }),
+            Type::Function { params, ret } => Ok(Type::Function {
+                params: params
+                    .into_iter()
+                    .map(|param| self.resolve_type(param, line))
+                    .collect::<Result<Vec<_>, _>>()?,
+                ret: Box::new(self.resolve_type(*ret, line)?),
+            }),
+            scalar => Ok(scalar),
+        }
+    }
+
+    fn resolve_params_and_body(
+        &mut self,
+        params: Vec<String>,
+        body: Option<BlockAST>,
+        line: usize,
+    ) -> Result<(Vec<String>, Option<BlockAST>), String> {
+        let params = params
+            .into_iter()
+            .map(|param| self.declare_variable(param, line))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let body = body
+            .map(|body| {
+                self.resolve_block_items(body.items)
+                    .map(|items| BlockAST { items })
+            })
+            .transpose()?;
+
+        Ok((params, body))
+    }
+
+    fn resolve_block(&mut self, block: BlockAST) -> Result<BlockAST, String> {
+        self.push_scope();
+        let items = self.resolve_block_items(block.items);
+        self.pop_scope();
+        Ok(BlockAST { items: items? })
+    }
+
+    fn resolve_block_items(
+        &mut self,
+        items: Vec<BlockItemAST>,
+    ) -> Result<Vec<BlockItemAST>, String> {
+        items
+            .into_iter()
+            .map(|block_item| self.resolve_block_item(block_item))
+            .collect()
+    }
+
+    fn lookup(&self, name: &str) -> Option<&ScopeEntry> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    fn resolve_block_item(&mut self, block_item: BlockItemAST) -> Result<BlockItemAST, String> {
+        match block_item {
+            BlockItemAST::Statement(stmt) => Ok(BlockItemAST::Statement(self.resolve_stmt(stmt)?)),
+            BlockItemAST::Declaration(declaration) => Ok(BlockItemAST::Declaration(
+                self.resolve_declaration(declaration)?,
+            )),
+            BlockItemAST::FunctionDeclaration(function) => {
+                if function.body.is_some() {
+                    return Err(format!(
+                        "Line {}: Semantic error: Nested definition of function {:?}",
+                        function.line, function.name
+                    ));
+                }
+                if function.storage_class == Some(StorageClassAST::Static) {
+                    return Err(format!(
+                        "Line {}: Semantic error: Static declaration of function {:?} in block scope",
+                        function.line, function.name
+                    ));
+                }
+                Ok(BlockItemAST::FunctionDeclaration(
+                    self.resolve_function_declaration(function)?,
+                ))
+            }
+            BlockItemAST::StructDeclaration(declaration) => Ok(BlockItemAST::StructDeclaration(
+                self.resolve_struct_declaration(declaration)?,
+            )),
+        }
+    }
+
+    fn resolve_declaration(
+        &mut self,
+        declaration: DeclarationAST,
+    ) -> Result<DeclarationAST, String> {
+        let DeclarationAST {
+            name,
+            init,
+            var_type,
+            storage_class,
+            line,
+        } = declaration;
+
+        // SS: the variable is in scope in its own initializer, i.e. int a = a; is legal C
+        let unique_name = if storage_class == Some(StorageClassAST::Extern) {
+            self.declare_extern_variable(name, line)?
+        } else {
+            self.declare_variable(name, line)?
+        };
+        let var_type = self.resolve_type(var_type, line)?;
+        let init = init
+            .map(|init| self.resolve_initializer(init))
+            .transpose()?;
+
+        Ok(DeclarationAST {
+            name: unique_name,
+            init,
+            var_type,
+            storage_class,
+            line,
+        })
+    }
+
+    // SS: an extern local refers to the variable with linkage of the same name, so it keeps
+    // its name. It may repeat an earlier extern declaration in the same block, but must not
+    // clash with a local variable.
+    fn declare_extern_variable(&mut self, name: String, line: usize) -> Result<String, String> {
+        let current_scope = self.scopes.last_mut().unwrap();
+        if let Some(entry) = current_scope.get(&name)
+            && !entry.has_linkage
+        {
+            return Err(format!(
+                "Line {}: Semantic error: Duplicate declaration of variable {:?}",
+                line, name
+            ));
+        }
+
+        current_scope.insert(
+            name.clone(),
+            ScopeEntry {
+                unique_name: name.clone(),
+                has_linkage: true,
+            },
+        );
+        Ok(name)
+    }
+
+    // SS: adds a variable to the current scope and returns its unique name
+    fn declare_variable(&mut self, name: String, line: usize) -> Result<String, String> {
+        // SS: shadowing a variable from an enclosing block is fine, redeclaring one in the same
+        // block is not
+        let current_scope = self.scopes.last_mut().unwrap();
+        if current_scope.contains_key(&name) {
+            return Err(format!(
+                "Line {}: Semantic error: Duplicate declaration of variable {:?}",
+                line, name
+            ));
+        }
+
+        let unique_name = self.name_generator.make_unique(&name);
+        current_scope.insert(
+            name,
+            ScopeEntry {
+                unique_name: unique_name.clone(),
+                has_linkage: false,
+            },
+        );
+        Ok(unique_name)
+    }
+
+    fn resolve_stmt(&mut self, stmt: StmtAST) -> Result<StmtAST, String> {
+        match stmt {
+            StmtAST::Return(expr) => Ok(StmtAST::Return(self.resolve_expr(expr)?)),
+            StmtAST::Expression(expr) => Ok(StmtAST::Expression(self.resolve_expr(expr)?)),
+            StmtAST::If {
+                condition,
+                then_stmt,
+                else_stmt,
+            } => Ok(StmtAST::If {
+                condition: self.resolve_expr(condition)?,
+                then_stmt: Box::new(self.resolve_stmt(*then_stmt)?),
+                else_stmt: else_stmt
+                    .map(|stmt| self.resolve_stmt(*stmt).map(Box::new))
+                    .transpose()?,
+            }),
+            StmtAST::Compound(block) => Ok(StmtAST::Compound(self.resolve_block(block)?)),
+            StmtAST::Break { .. } | StmtAST::Continue { .. } | StmtAST::Goto { .. } => Ok(stmt),
+            StmtAST::Labeled { label, body, line } => Ok(StmtAST::Labeled {
+                label,
+                body: Box::new(self.resolve_stmt(*body)?),
+                line,
+            }),
+            StmtAST::While {
+                condition,
+                body,
+                label,
+            } => Ok(StmtAST::While {
+                condition: self.resolve_expr(condition)?,
+                body: Box::new(self.resolve_stmt(*body)?),
+                label,
+            }),
+            StmtAST::DoWhile {
+                body,
+                condition,
+                label,
+            } => Ok(StmtAST::DoWhile {
+                body: Box::new(self.resolve_stmt(*body)?),
+                condition: self.resolve_expr(condition)?,
+                label,
+            }),
+            StmtAST::For {
+                init,
+                condition,
+                post,
+                body,
+                label,
+            } => {
+                // SS: a declaration in the for header is scoped to the loop
+                self.push_scope();
+                let stmt = self.resolve_for(init, condition, post, *body, label);
+                self.pop_scope();
+                stmt
+            }
+            StmtAST::Switch {
+                condition,
+                body,
+                label,
+                cases,
+            } => Ok(StmtAST::Switch {
+                condition: self.resolve_expr(condition)?,
+                body: Box::new(self.resolve_stmt(*body)?),
+                label,
+                cases,
+            }),
+            StmtAST::Case {
+                value,
+                body,
+                label,
+                line,
+            } => Ok(StmtAST::Case {
+                value: self.resolve_expr(value)?,
+                body: Box::new(self.resolve_stmt(*body)?),
+                label,
+                line,
+            }),
+            StmtAST::Default { body, label, line } => Ok(StmtAST::Default {
+                body: Box::new(self.resolve_stmt(*body)?),
+                label,
+                line,
+            }),
+            StmtAST::Null => Ok(StmtAST::Null),
+        }
+    }
+
+    fn resolve_for(
+        &mut self,
+        init: ForInitAST,
+        condition: Option<ExprAST>,
+        post: Option<ExprAST>,
+        body: StmtAST,
+        label: Option<String>,
+    ) -> Result<StmtAST, String> {
+        let init = match init {
+            ForInitAST::InitDecl(declaration) => {
+                if declaration.storage_class.is_some() {
+                    return Err(format!(
+                        "Line {}: Semantic error: Storage class specifier in for loop header",
+                        declaration.line
+                    ));
+                }
+                ForInitAST::InitDecl(self.resolve_declaration(declaration)?)
+            }
+            ForInitAST::InitExpr(expr) => {
+                ForInitAST::InitExpr(expr.map(|expr| self.resolve_expr(expr)).transpose()?)
+            }
+        };
+        let condition = condition.map(|expr| self.resolve_expr(expr)).transpose()?;
+        let post = post.map(|expr| self.resolve_expr(expr)).transpose()?;
+        let body = self.resolve_stmt(body)?;
+
+        Ok(StmtAST::For {
+            init,
+            condition,
+            post,
+            body: Box::new(body),
+            label,
+        })
+    }
+
+    fn resolve_initializer(&mut self, init: InitializerAST) -> Result<InitializerAST, String> {
+        match init {
+            InitializerAST::Single(expr) => Ok(InitializerAST::Single(self.resolve_expr(expr)?)),
+            InitializerAST::Compound(initializers) => Ok(InitializerAST::Compound(
+                initializers
+                    .into_iter()
+                    .map(|init| self.resolve_initializer(init))
+                    .collect::<Result<Vec<_>, _>>()?,
+            )),
+        }
+    }
+
+    fn resolve_expr(&mut self, expr: ExprAST) -> Result<ExprAST, String> {
+        match expr {
+            ExprAST::Constant(_) | ExprAST::String(_) => Ok(expr),
+            ExprAST::Var { name, line } => match self.lookup(&name) {
+                Some(entry) => Ok(ExprAST::Var {
+                    name: entry.unique_name.clone(),
+                    line,
+                }),
+                None => Err(format!(
+                    "Line {}: Semantic error: Use of undeclared variable {:?}",
+                    line, name
+                )),
+            },
+            ExprAST::Unary(op, inner) => {
+                Ok(ExprAST::Unary(op, Box::new(self.resolve_expr(*inner)?)))
+            }
+            ExprAST::Binary(op, left, right) => Ok(ExprAST::Binary(
+                op,
+                Box::new(self.resolve_expr(*left)?),
+                Box::new(self.resolve_expr(*right)?),
+            )),
+            ExprAST::Assignment { lhs, rhs, line } => {
+                check_lvalue(&lhs, line)?;
+                Ok(ExprAST::Assignment {
+                    lhs: Box::new(self.resolve_expr(*lhs)?),
+                    rhs: Box::new(self.resolve_expr(*rhs)?),
+                    line,
+                })
+            }
+            ExprAST::CompoundAssignment { op, lhs, rhs, line } => {
+                check_lvalue(&lhs, line)?;
+                Ok(ExprAST::CompoundAssignment {
+                    op,
+                    lhs: Box::new(self.resolve_expr(*lhs)?),
+                    rhs: Box::new(self.resolve_expr(*rhs)?),
+                    line,
+                })
+            }
+            ExprAST::Increment { op, operand, line } => {
+                check_lvalue(&operand, line)?;
+                Ok(ExprAST::Increment {
+                    op,
+                    operand: Box::new(self.resolve_expr(*operand)?),
+                    line,
+                })
+            }
+            ExprAST::Conditional {
+                condition,
+                then_expr,
+                else_expr,
+            } => Ok(ExprAST::Conditional {
+                condition: Box::new(self.resolve_expr(*condition)?),
+                then_expr: Box::new(self.resolve_expr(*then_expr)?),
+                else_expr: Box::new(self.resolve_expr(*else_expr)?),
+            }),
+            ExprAST::FunctionCall { name, args, line } => {
+                let Some(entry) = self.lookup(&name) else {
+                    return Err(format!(
+                        "Line {}: Semantic error: Use of undeclared function {:?}",
+                        line, name
+                    ));
+                };
+                let name = entry.unique_name.clone();
+                let args = args
+                    .into_iter()
+                    .map(|arg| self.resolve_expr(arg))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(ExprAST::FunctionCall { name, args, line })
+            }
+            ExprAST::Cast {
+                target_type,
+                expr,
+                line,
+            } => Ok(ExprAST::Cast {
+                target_type: self.resolve_type(target_type, line)?,
+                expr: Box::new(self.resolve_expr(*expr)?),
+                line,
+            }),
+            ExprAST::Dereference { expr, line } => Ok(ExprAST::Dereference {
+                expr: Box::new(self.resolve_expr(*expr)?),
+                line,
+            }),
+            ExprAST::AddressOf { expr, line } => {
+                check_lvalue(&expr, line)?;
+                Ok(ExprAST::AddressOf {
+                    expr: Box::new(self.resolve_expr(*expr)?),
+                    line,
+                })
+            }
+            ExprAST::Subscript { array, index, line } => Ok(ExprAST::Subscript {
+                array: Box::new(self.resolve_expr(*array)?),
+                index: Box::new(self.resolve_expr(*index)?),
+                line,
+            }),
+            ExprAST::SizeOf { expr, line } => Ok(ExprAST::SizeOf {
+                expr: Box::new(self.resolve_expr(*expr)?),
+                line,
+            }),
+            ExprAST::SizeOfType { target_type, line } => Ok(ExprAST::SizeOfType {
+                target_type: self.resolve_type(target_type, line)?,
+                line,
+            }),
+            // SS: member names are looked up in the structure type during type checking
+            ExprAST::Dot {
+                structure,
+                member,
+                line,
+            } => Ok(ExprAST::Dot {
+                structure: Box::new(self.resolve_expr(*structure)?),
+                member,
+                line,
+            }),
+            ExprAST::Arrow {
+                pointer,
+                member,
+                line,
+            } => Ok(ExprAST::Arrow {
+                pointer: Box::new(self.resolve_expr(*pointer)?),
+                member,
+                line,
+            }),
+        }
+    }
+}
+
+// SS: whether an array is assignable depends on its type, which is checked later
+fn check_lvalue(expr: &ExprAST, line: usize) -> Result<(), String> {
+    match expr {
+        ExprAST::Var { .. }
+        | ExprAST::Dereference { .. }
+        | ExprAST::Subscript { .. }
+        | ExprAST::Dot { .. }
+        | ExprAST::Arrow { .. } => Ok(()),
+        _ => Err(format!("Line {}: Semantic error: Invalid lvalue", line)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lexer::Lexer;
+    use crate::name_generator::NameGenerator;
+    use crate::parse_ast::{
+        BlockAST, BlockItemAST, DeclarationAST, ExprAST, ExternalDeclarationAST, ForInitAST,
+        InitializerAST, ProgramAST, StmtAST, StorageClassAST,
+    };
+    use crate::parser::Parser;
+    use crate::semantic_analysis::resolve_program;
+    use crate::types::{Constant, Type};
+
+    fn resolve(input: &str) -> Result<ProgramAST, String> {
+        let lexer = Lexer::new(input.to_string());
+        let mut parser = Parser::new(lexer);
+        let ast = parser.parse().unwrap();
+        resolve_program(ast, &mut NameGenerator::new())
+    }
+
+    fn body_items(ast: &ProgramAST) -> Vec<BlockItemAST> {
+        match &ast.declarations[0] {
+            ExternalDeclarationAST::Function(function) => {
+                function.body.as_ref().unwrap().items.clone()
+            }
+            declaration => panic!("expected a function, found {:?}", declaration),
+        }
+    }
+
+    #[test]
+    fn test_resolve_variables() {
+        // SS: arrange
+        let input = r"int main(void) {
+                            int a = 1;
+                            int b = a;
+                            return b;
+                    }";
+
+        // SS: act
+        let ast = resolve(input).unwrap();
+
+        // SS: assert
+        assert_eq!(
+            body_items(&ast),
+            vec![
+                BlockItemAST::Declaration(DeclarationAST {
+                    name: "a.0".to_string(),
+                    init: Some(InitializerAST::Single(ExprAST::Constant(Constant::Int(1)))),
+                    var_type: Type::Int,
+                    storage_class: None,
+                    line: 2,
+                }),
+                BlockItemAST::Declaration(DeclarationAST {
+                    name: "b.1".to_string(),
+                    init: Some(InitializerAST::Single(ExprAST::Var {
+                        name: "a.0".to_string(),
+                        line: 3
+                    })),
+                    var_type: Type::Int,
+                    storage_class: None,
+                    line: 3,
+                }),
+                BlockItemAST::Statement(StmtAST::Return(ExprAST::Var {
+                    name: "b.1".to_string(),
+                    line: 4
+                })),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resolve_undeclared_variable() {
+        // SS: arrange
+        let input = r"int main(void) {
+                            int a = 1;
+                            return a + b;
+                    }";
+
+        // SS: act
+        let ast = resolve(input);
+
+        // SS: assert
+        assert_eq!(
+            ast,
+            Err(r#"Line 3: Semantic error: Use of undeclared variable "b""#.to_string())
+        );
+    }
+
+    #[test]
+    fn test_resolve_duplicate_declaration() {
+        // SS: arrange
+        let input = r"int main(void) {
+                            int a = 1;
+                            int a;
+                            return a;
+                    }";
+
+        // SS: act
+        let ast = resolve(input);
+
+        // SS: assert
+        assert_eq!(
+            ast,
+            Err(r#"Line 3: Semantic error: Duplicate declaration of variable "a""#.to_string())
+        );
+    }
+
+    #[test]
+    fn test_resolve_invalid_lvalue() {
+        // SS: arrange
+        let input = r"int main(void) {
+                            int a = 1;
+                            a + 1 = 2;
+                            return --2;
+                    }";
+
+        // SS: act
+        let ast = resolve(input);
+
+        // SS: assert
+        assert_eq!(
+            ast,
+            Err(r#"Line 3: Semantic error: Invalid lvalue"#.to_string())
+        );
+    }
+
+    #[test]
+    fn test_resolve_invalid_lvalue_decrement() {
+        // SS: arrange
+        let input = r"int main(void) {
+                            return --2;
+                    }";
+
+        // SS: act
+        let ast = resolve(input);
+
+        // SS: assert
+        assert_eq!(
+            ast,
+            Err(r#"Line 2: Semantic error: Invalid lvalue"#.to_string())
+        );
+    }
+
+    #[test]
+    fn test_resolve_shadowing() {
+        // SS: arrange
+        let input = r"int main(void) {
+                            int a = 1;
+                            {
+                                int a = a + 1;
+                                a;
+                            }
+                            return a;
+                    }";
+
+        // SS: act
+        let ast = resolve(input).unwrap();
+
+        // SS: assert
+        // the inner a is a new variable, initialized from itself since its scope starts
+        // right after the declarator
+        assert_eq!(
+            body_items(&ast),
+            vec![
+                BlockItemAST::Declaration(DeclarationAST {
+                    name: "a.0".to_string(),
+                    init: Some(InitializerAST::Single(ExprAST::Constant(Constant::Int(1)))),
+                    var_type: Type::Int,
+                    storage_class: None,
+                    line: 2,
+                }),
+                BlockItemAST::Statement(StmtAST::Compound(BlockAST {
+                    items: vec![
+                        BlockItemAST::Declaration(DeclarationAST {
+                            name: "a.1".to_string(),
+                            init: Some(InitializerAST::Single(ExprAST::Binary(
+                                crate::parse_ast::BinaryOperatorAST::Add,
+                                Box::new(ExprAST::Var {
+                                    name: "a.1".to_string(),
+                                    line: 4
+                                }),
+                                Box::new(ExprAST::Constant(Constant::Int(1)))
+                            ))),
+                            var_type: Type::Int,
+                            storage_class: None,
+                            line: 4,
+                        }),
+                        BlockItemAST::Statement(StmtAST::Expression(ExprAST::Var {
+                            name: "a.1".to_string(),
+                            line: 5
+                        })),
+                    ],
+                })),
+                BlockItemAST::Statement(StmtAST::Return(ExprAST::Var {
+                    name: "a.0".to_string(),
+                    line: 7
+                })),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resolve_variable_out_of_scope() {
+        // SS: arrange
+        let input = r"int main(void) {
+                            if (1) {
+                                int b = 2;
+                            }
+                            return b;
+                    }";
+
+        // SS: act
+        let ast = resolve(input);
+
+        // SS: assert
+        assert_eq!(
+            ast,
+            Err(r#"Line 5: Semantic error: Use of undeclared variable "b""#.to_string())
+        );
+    }
+
+    #[test]
+    fn test_resolve_for_loop_scope() {
+        // SS: arrange
+        let input = r"int main(void) {
+                            int i = 10;
+                            for (int i = 0; i < 3; i++) {
+                                int i = 5;
+                            }
+                            return i;
+                    }";
+
+        // SS: act
+        let ast = resolve(input).unwrap();
+
+        // SS: assert
+        // the header variable gets its own scope, the body block yet another one
+        let items = body_items(&ast);
+        let BlockItemAST::Statement(StmtAST::For { init, body, .. }) = &items[1] else {
+            panic!("expected a for loop");
+        };
+        assert_eq!(
+            init,
+            &ForInitAST::InitDecl(DeclarationAST {
+                name: "i.1".to_string(),
+                init: Some(InitializerAST::Single(ExprAST::Constant(Constant::Int(0)))),
+                var_type: Type::Int,
+                storage_class: None,
+                line: 3,
+            })
+        );
+        assert_eq!(
+            **body,
+            StmtAST::Compound(BlockAST {
+                items: vec![BlockItemAST::Declaration(DeclarationAST {
+                    name: "i.2".to_string(),
+                    init: Some(InitializerAST::Single(ExprAST::Constant(Constant::Int(5)))),
+                    var_type: Type::Int,
+                    storage_class: None,
+                    line: 4,
+                })]
+            })
+        );
+        assert_eq!(
+            items[2],
+            BlockItemAST::Statement(StmtAST::Return(ExprAST::Var {
+                name: "i.0".to_string(),
+                line: 6
+            }))
+        );
+    }
+
+    #[test]
+    fn test_resolve_undeclared_function() {
+        // SS: arrange
+        let input = r"int main(void) {
+                            return foo(1);
+                    }";
+
+        // SS: act
+        let ast = resolve(input);
+
+        // SS: assert
+        assert_eq!(
+            ast,
+            Err(r#"Line 2: Semantic error: Use of undeclared function "foo""#.to_string())
+        );
+    }
+
+    #[test]
+    fn test_resolve_nested_function_definition() {
+        // SS: arrange
+        let input = r"int main(void) {
+                            int foo(void) {
+                                return 1;
+                            }
+                            return foo();
+                    }";
+
+        // SS: act
+        let ast = resolve(input);
+
+        // SS: assert
+        assert_eq!(
+            ast,
+            Err(r#"Line 2: Semantic error: Nested definition of function "foo""#.to_string())
+        );
+    }
+
+    #[test]
+    fn test_resolve_parameter_redeclared_in_body() {
+        // SS: arrange
+        let input = r"int foo(int a) {
+                            int a = 2;
+                            return a;
+                    }";
+
+        // SS: act
+        let ast = resolve(input);
+
+        // SS: assert
+        assert_eq!(
+            ast,
+            Err(r#"Line 2: Semantic error: Duplicate declaration of variable "a""#.to_string())
+        );
+    }
+
+    #[test]
+    fn test_resolve_extern_keeps_name() {
+        // SS: arrange
+        let input = r"int a = 3;
+                    int main(void) {
+                            int a = 1;
+                            {
+                                extern int a;
+                                return a;
+                            }
+                    }";
+
+        // SS: act
+        let ast = resolve(input).unwrap();
+
+        // SS: assert
+        let ExternalDeclarationAST::Function(function) = &ast.declarations[1] else {
+            panic!("expected a function");
+        };
+        assert_eq!(
+            function.body.as_ref().unwrap().items[1],
+            BlockItemAST::Statement(StmtAST::Compound(BlockAST {
+                items: vec![
+                    BlockItemAST::Declaration(DeclarationAST {
+                        name: "a".to_string(),
+                        init: None,
+                        var_type: Type::Int,
+                        storage_class: Some(StorageClassAST::Extern),
+                        line: 5,
+                    }),
+                    BlockItemAST::Statement(StmtAST::Return(ExprAST::Var {
+                        name: "a".to_string(),
+                        line: 6,
+                    })),
+                ]
+            }))
+        );
+    }
+
+    #[test]
+    fn test_resolve_extern_conflicts_with_local() {
+        // SS: arrange
+        let input = r"int main(void) {
+                            int a = 1;
+                            extern int a;
+                            return a;
+                    }";
+
+        // SS: act
+        let ast = resolve(input);
+
+        // SS: assert
+        assert_eq!(
+            ast,
+            Err(r#"Line 3: Semantic error: Duplicate declaration of variable "a""#.to_string())
+        );
+    }
+
+    #[test]
+    fn test_resolve_static_function_in_block() {
+        // SS: arrange
+        let input = r"int main(void) {
+                            static int foo(void);
+                            return 0;
+                    }";
+
+        // SS: act
+        let ast = resolve(input);
+
+        // SS: assert
+        assert_eq!(
+            ast,
+            Err(
+                r#"Line 2: Semantic error: Static declaration of function "foo" in block scope"#
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_resolve_storage_class_in_for_loop_header() {
+        // SS: arrange
+        let input = r"int main(void) {
+                            for (static int i = 0; i < 3; i++);
+                            return 0;
+                    }";
+
+        // SS: act
+        let ast = resolve(input);
+
+        // SS: assert
+        assert_eq!(
+            ast,
+            Err("Line 2: Semantic error: Storage class specifier in for loop header".to_string())
+        );
+    }
+
+    #[test]
+    fn test_resolve_structure_tags() {
+        // SS: arrange
+        let input = r"struct s { int a; };
+                    int main(void) {
+                            struct s *outer;
+                            {
+                                struct s { long b; };
+                                struct s inner;
+                            }
+                            return 0;
+                    }";
+
+        // SS: act
+        let ast = resolve(input).unwrap();
+
+        // SS: assert
+        // the inner declaration hides the outer tag, each gets a unique name
+        let ExternalDeclarationAST::Function(function) = &ast.declarations[1] else {
+            panic!("expected a function");
+        };
+        let items = &function.body.as_ref().unwrap().items;
+        let BlockItemAST::Declaration(outer) = &items[0] else {
+            panic!("expected a declaration");
+        };
+        assert_eq!(
+            outer.var_type,
+            Type::Pointer(Box::new(Type::Structure("s.0".to_string())))
+        );
+        let BlockItemAST::Statement(StmtAST::Compound(block)) = &items[1] else {
+            panic!("expected a block");
+        };
+        let BlockItemAST::Declaration(inner) = &block.items[1] else {
+            panic!("expected a declaration");
+        };
+        assert_eq!(inner.var_type, Type::Structure("s.2".to_string()));
+    }
+
+    #[test]
+    fn test_resolve_conflicting_tag_kinds() {
+        // SS: arrange
+        let input = r"struct s { int a; };
+                    union s *p;";
+
+        // SS: act
+        let ast = resolve(input);
+
+        // SS: assert
+        assert_eq!(
+            ast,
+            Err(r#"Line 2: Semantic error: Conflicting declarations of tag "s""#.to_string())
+        );
+    }
+}
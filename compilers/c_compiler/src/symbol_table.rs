@@ -0,0 +1,44 @@
+use crate::types::{StaticInit, Type};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InitialValue {
+    // SS: a file scope declaration without an initializer or storage class, it becomes a
+    // zero-initialized definition unless the variable is initialized elsewhere
+    Tentative,
+    // SS: already converted to the type of the variable, one entry per scalar with runs
+    // of zeros merged
+    Initial(Vec<StaticInit>),
+    NoInitializer,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum IdentifierAttrs {
+    // SS: defined is true once a body has been seen, global if it has external linkage
+    Function { defined: bool, global: bool },
+    Static { init: InitialValue, global: bool },
+    // SS: a read-only object the compiler introduces, i.e. a string literal
+    Constant(StaticInit),
+    Local,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    pub symbol_type: Type,
+    pub attrs: IdentifierAttrs,
+}
+
+impl Symbol {
+    // SS: true for objects with static storage duration, which live in a data section
+    // rather than on the stack
+    pub fn is_static(&self) -> bool {
+        matches!(
+            self.attrs,
+            IdentifierAttrs::Static { .. } | IdentifierAttrs::Constant(_)
+        )
+    }
+}
+
+// SS: keyed by the unique names assigned during identifier resolution, TACKY generation
+// adds its temporaries so that every value in the program has a type
+pub type SymbolTable = HashMap<String, Symbol>;
@@ -0,0 +1,366 @@
+use crate::types::{Constant, StaticInit};
+use std::fmt::Display;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TackyProgramAST {
+    pub items: Vec<TackyTopLevelAST>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TackyTopLevelAST {
+    Function(TackyFunctionAST),
+    StaticVariable {
+        name: String,
+        global: bool,
+        // SS: already converted to the type of the variable, one entry per scalar
+        init: Vec<StaticInit>,
+    },
+    // SS: a read-only object, i.e. a string literal
+    StaticConstant {
+        name: String,
+        init: StaticInit,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TackyFunctionAST {
+    pub name: String,
+    pub global: bool,
+    pub params: Vec<String>,
+    pub instructions: Vec<TackyInstructionAST>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TackyInstructionAST {
+    Return(TackyValueAST),
+    Unary {
+        op: TackyUnaryOperatorAST,
+        src: TackyValueAST,
+        dst: TackyValueAST,
+    },
+    Binary {
+        op: TackyBinaryOperatorAST,
+        src1: TackyValueAST,
+        src2: TackyValueAST,
+        dst: TackyValueAST,
+    },
+    Copy {
+        src: TackyValueAST,
+        dst: TackyValueAST,
+    },
+    // SS: conversions between types, the type of src and dst follows from the constant or
+    // the symbol table entry of the variable
+    SignExtend {
+        src: TackyValueAST,
+        dst: TackyValueAST,
+    },
+    Truncate {
+        src: TackyValueAST,
+        dst: TackyValueAST,
+    },
+    ZeroExtend {
+        src: TackyValueAST,
+        dst: TackyValueAST,
+    },
+    DoubleToInt {
+        src: TackyValueAST,
+        dst: TackyValueAST,
+    },
+    DoubleToUInt {
+        src: TackyValueAST,
+        dst: TackyValueAST,
+    },
+    IntToDouble {
+        src: TackyValueAST,
+        dst: TackyValueAST,
+    },
+    UIntToDouble {
+        src: TackyValueAST,
+        dst: TackyValueAST,
+    },
+    // SS: dst is set to the address of the object src
+    GetAddress {
+        src: TackyValueAST,
+        dst: TackyValueAST,
+    },
+    // SS: dst is set to the value src_ptr points to
+    Load {
+        src_ptr: TackyValueAST,
+        dst: TackyValueAST,
+    },
+    // SS: the value src is written to where dst_ptr points
+    Store {
+        src: TackyValueAST,
+        dst_ptr: TackyValueAST,
+    },
+    // SS: dst = ptr + index * scale, where scale is the size of the pointed-to type
+    AddPtr {
+        ptr: TackyValueAST,
+        index: TackyValueAST,
+        scale: i64,
+        dst: TackyValueAST,
+    },
+    // SS: writes src offset bytes into the array or structure dst, used to initialize
+    // local arrays and to assign to members
+    CopyToOffset {
+        src: TackyValueAST,
+        dst: String,
+        offset: i32,
+    },
+    // SS: reads dst from offset bytes into the structure src
+    CopyFromOffset {
+        src: String,
+        offset: i32,
+        dst: TackyValueAST,
+    },
+    Jump(String),
+    JumpIfZero(TackyValueAST, String),
+    JumpIfNotZero(TackyValueAST, String),
+    // SS: jumps to the target at position index, an unsigned long that is within bounds
+    JumpTable {
+        index: TackyValueAST,
+        targets: Vec<String>,
+    },
+    Label(String),
+    FunctionCall {
+        name: String,
+        args: Vec<TackyValueAST>,
+        dst: TackyValueAST,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TackyValueAST {
+    Constant(Constant),
+    Var(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TackyUnaryOperatorAST {
+    Negate,
+    Complement,
+    Not,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TackyBinaryOperatorAST {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+    Equal,
+    NotEqual,
+    LessThan,
+    LessOrEqual,
+    GreaterThan,
+    GreaterOrEqual,
+}
+
+// SS: the IR prints as C-like text, one instruction per line with labels pulled out to the
+// left so that the jumps are easy to follow
+impl Display for TackyProgramAST {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let items = self
+            .items
+            .iter()
+            .map(TackyTopLevelAST::to_string)
+            .collect::<Vec<_>>();
+        write!(f, "{}", items.join("\n\n"))
+    }
+}
+
+impl Display for TackyTopLevelAST {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TackyTopLevelAST::Function(function) => {
+                let linkage = if function.global { "global" } else { "static" };
+                write!(
+                    f,
+                    "{} function {}({}) {{",
+                    linkage,
+                    function.name,
+                    function.params.join(", ")
+                )?;
+                for instruction in &function.instructions {
+                    match instruction {
+                        TackyInstructionAST::Label(_) => write!(f, "\n{}", instruction)?,
+                        _ => write!(f, "\n    {}", instruction)?,
+                    }
+                }
+                write!(f, "\n}}")
+            }
+            TackyTopLevelAST::StaticVariable { name, global, init } => {
+                let linkage = if *global { "global" } else { "static" };
+                let init = init.iter().map(StaticInit::to_string).collect::<Vec<_>>();
+                write!(f, "{} variable {} = [{}]", linkage, name, init.join(", "))
+            }
+            TackyTopLevelAST::StaticConstant { name, init } => {
+                write!(f, "constant {} = {}", name, init)
+            }
+        }
+    }
+}
+
+impl Display for TackyInstructionAST {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let conversion = match self {
+            TackyInstructionAST::SignExtend { .. } => "sign_extend",
+            TackyInstructionAST::Truncate { .. } => "truncate",
+            TackyInstructionAST::ZeroExtend { .. } => "zero_extend",
+            TackyInstructionAST::DoubleToInt { .. } => "double_to_int",
+            TackyInstructionAST::DoubleToUInt { .. } => "double_to_uint",
+            TackyInstructionAST::IntToDouble { .. } => "int_to_double",
+            TackyInstructionAST::UIntToDouble { .. } => "uint_to_double",
+            _ => "",
+        };
+        match self {
+            TackyInstructionAST::Return(value) => write!(f, "return {}", value),
+            TackyInstructionAST::Unary { op, src, dst } => write!(f, "{} = {}{}", dst, op, src),
+            TackyInstructionAST::Binary {
+                op,
+                src1,
+                src2,
+                dst,
+            } => write!(f, "{} = {} {} {}", dst, src1, op, src2),
+            TackyInstructionAST::Copy { src, dst } => write!(f, "{} = {}", dst, src),
+            TackyInstructionAST::SignExtend { src, dst }
+            | TackyInstructionAST::Truncate { src, dst }
+            | TackyInstructionAST::ZeroExtend { src, dst }
+            | TackyInstructionAST::DoubleToInt { src, dst }
+            | TackyInstructionAST::DoubleToUInt { src, dst }
+            | TackyInstructionAST::IntToDouble { src, dst }
+            | TackyInstructionAST::UIntToDouble { src, dst } => {
+                write!(f, "{} = {} {}", dst, conversion, src)
+            }
+            TackyInstructionAST::GetAddress { src, dst } => write!(f, "{} = &{}", dst, src),
+            TackyInstructionAST::Load { src_ptr, dst } => write!(f, "{} = *{}", dst, src_ptr),
+            TackyInstructionAST::Store { src, dst_ptr } => write!(f, "*{} = {}", dst_ptr, src),
+            TackyInstructionAST::AddPtr {
+                ptr,
+                index,
+                scale,
+                dst,
+            } => write!(f, "{} = {} + {} * {}", dst, ptr, index, scale),
+            TackyInstructionAST::CopyToOffset { src, dst, offset } => {
+                write!(f, "{}[byte {}] = {}", dst, offset, src)
+            }
+            TackyInstructionAST::CopyFromOffset { src, offset, dst } => {
+                write!(f, "{} = {}[byte {}]", dst, src, offset)
+            }
+            TackyInstructionAST::Jump(target) => write!(f, "goto {}", target),
+            TackyInstructionAST::JumpIfZero(value, target) => {
+                write!(f, "if !{} goto {}", value, target)
+            }
+            TackyInstructionAST::JumpIfNotZero(value, target) => {
+                write!(f, "if {} goto {}", value, target)
+            }
+            TackyInstructionAST::JumpTable { index, targets } => {
+                write!(f, "goto [{}][{}]", targets.join(", "), index)
+            }
+            TackyInstructionAST::Label(label) => write!(f, "{}:", label),
+            TackyInstructionAST::FunctionCall { name, args, dst } => {
+                let args = args
+                    .iter()
+                    .map(TackyValueAST::to_string)
+                    .collect::<Vec<_>>();
+                write!(f, "{} = {}({})", dst, name, args.join(", "))
+            }
+        }
+    }
+}
+
+impl Display for TackyValueAST {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TackyValueAST::Constant(c) => write!(f, "{}", c),
+            TackyValueAST::Var(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+impl Display for TackyUnaryOperatorAST {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TackyUnaryOperatorAST::Negate => write!(f, "-"),
+            TackyUnaryOperatorAST::Complement => write!(f, "~"),
+            TackyUnaryOperatorAST::Not => write!(f, "!"),
+        }
+    }
+}
+
+impl Display for TackyBinaryOperatorAST {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TackyBinaryOperatorAST::Add => write!(f, "+"),
+            TackyBinaryOperatorAST::Subtract => write!(f, "-"),
+            TackyBinaryOperatorAST::Multiply => write!(f, "*"),
+            TackyBinaryOperatorAST::Divide => write!(f, "/"),
+            TackyBinaryOperatorAST::Remainder => write!(f, "%"),
+            TackyBinaryOperatorAST::Equal => write!(f, "=="),
+            TackyBinaryOperatorAST::NotEqual => write!(f, "!="),
+            TackyBinaryOperatorAST::LessThan => write!(f, "<"),
+            TackyBinaryOperatorAST::LessOrEqual => write!(f, "<="),
+            TackyBinaryOperatorAST::GreaterThan => write!(f, ">"),
+            TackyBinaryOperatorAST::GreaterOrEqual => write!(f, ">="),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tacky_ast::{
+        TackyBinaryOperatorAST, TackyFunctionAST, TackyInstructionAST, TackyProgramAST,
+        TackyTopLevelAST, TackyValueAST,
+    };
+    use crate::types::{Constant, StaticInit};
+
+    #[test]
+    fn test_display_program() {
+        // SS: arrange
+        let var = |name: &str| TackyValueAST::Var(name.to_string());
+        let tacky_ast = TackyProgramAST {
+            items: vec![
+                TackyTopLevelAST::Function(TackyFunctionAST {
+                    name: "f".to_string(),
+                    global: true,
+                    params: vec!["a".to_string(), "p".to_string()],
+                    instructions: vec![
+                        TackyInstructionAST::Binary {
+                            op: TackyBinaryOperatorAST::LessThan,
+                            src1: var("a"),
+                            src2: TackyValueAST::Constant(Constant::Long(10)),
+                            dst: var("tmp.0"),
+                        },
+                        TackyInstructionAST::JumpIfZero(var("tmp.0"), "end".to_string()),
+                        TackyInstructionAST::Store {
+                            src: var("a"),
+                            dst_ptr: var("p"),
+                        },
+                        TackyInstructionAST::Label("end".to_string()),
+                        TackyInstructionAST::FunctionCall {
+                            name: "g".to_string(),
+                            args: vec![var("a"), TackyValueAST::Constant(Constant::Double(0.5))],
+                            dst: var("tmp.1"),
+                        },
+                        TackyInstructionAST::Return(var("tmp.1")),
+                    ],
+                }),
+                TackyTopLevelAST::StaticVariable {
+                    name: "x".to_string(),
+                    global: false,
+                    init: vec![StaticInit::Value(Constant::UInt(1)), StaticInit::Zero(4)],
+                },
+            ],
+        };
+
+        // SS: act
+        let text = tacky_ast.to_string();
+
+        // SS: assert
+        assert_eq!(
+            text,
+            "global function f(a, p) {\n    tmp.0 = a < 10L\n    if !tmp.0 goto end\n    *p = a\nend:\n    tmp.1 = g(a, 0.5)\n    return tmp.1\n}\n\nstatic variable x = [1U, zero[4]]"
+        );
+    }
+}
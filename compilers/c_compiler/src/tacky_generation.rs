@@ -0,0 +1,2338 @@
+use crate::name_generator::NameGenerator;
+use crate::parse_ast::{
+    BinaryOperatorAST, BlockAST, BlockItemAST, DeclarationAST, ExprAST, ExternalDeclarationAST,
+    ForInitAST, FunctionAST, IncrementOperatorAST, InitializerAST, ProgramAST, StmtAST,
+    SwitchCaseAST, UnaryOperatorAST,
+};
+use crate::symbol_table::{IdentifierAttrs, InitialValue, Symbol, SymbolTable};
+use crate::tacky_ast::{
+    TackyBinaryOperatorAST, TackyFunctionAST, TackyInstructionAST, TackyProgramAST,
+    TackyTopLevelAST, TackyUnaryOperatorAST, TackyValueAST,
+};
+use crate::type_table::{MemberEntry, TypeTable};
+use crate::types::{Constant, StaticInit, Type};
+
+const MIN_JUMP_TABLE_CASES: usize = 4;
+
+pub fn generate_tacky_program_ast(
+    parse_ast: ProgramAST,
+    symbols: &mut SymbolTable,
+    types: &TypeTable,
+    name_generator: &mut NameGenerator,
+) -> TackyProgramAST {
+    let mut generator = TackyGenerator::new(symbols, types, name_generator);
+
+    // SS: declarations without a body produce no code, variables with static storage
+    // duration are emitted from the symbol table since their initial value depends on every
+    // declaration of them
+    let mut items = parse_ast
+        .declarations
+        .into_iter()
+        .filter_map(|declaration| match declaration {
+            ExternalDeclarationAST::Function(function) => generator.generate_function(function),
+            ExternalDeclarationAST::Variable(_) | ExternalDeclarationAST::Struct(_) => None,
+        })
+        .map(TackyTopLevelAST::Function)
+        .collect::<Vec<_>>();
+    items.extend(generate_static_variables(symbols, types));
+    items.extend(generate_static_constants(symbols));
+
+    TackyProgramAST { items }
+}
+
+// SS: sorted by name so that the output does not depend on the order of the hash map
+fn generate_static_variables(symbols: &SymbolTable, types: &TypeTable) -> Vec<TackyTopLevelAST> {
+    let mut variables = symbols
+        .iter()
+        .filter_map(|(name, symbol)| match &symbol.attrs {
+            IdentifierAttrs::Static { init, global } => match init {
+                InitialValue::Initial(init) => Some((name.clone(), *global, init.clone())),
+                InitialValue::Tentative => Some((
+                    name.clone(),
+                    *global,
+                    vec![StaticInit::Zero(symbol.symbol_type.size(types))],
+                )),
+                // SS: defined in another translation unit
+                InitialValue::NoInitializer => None,
+            },
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    variables.sort_by(|(a, ..), (b, ..)| a.cmp(b));
+    variables
+        .into_iter()
+        .map(|(name, global, init)| TackyTopLevelAST::StaticVariable { name, global, init })
+        .collect()
+}
+
+// SS: the string literals, both those found by the type checker and those added while
+// generating TACKY
+fn generate_static_constants(symbols: &SymbolTable) -> Vec<TackyTopLevelAST> {
+    let mut constants = symbols
+        .iter()
+        .filter_map(|(name, symbol)| match &symbol.attrs {
+            IdentifierAttrs::Constant(init) => Some((name.clone(), init.clone())),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    constants.sort_by(|(a, _), (b, _)| a.cmp(b));
+    constants
+        .into_iter()
+        .map(|(name, init)| TackyTopLevelAST::StaticConstant { name, init })
+        .collect()
+}
+
+// SS: the object an lvalue expression designates, either a variable, the memory a pointer
+// points to, or a member at the given offset within a structure variable
+enum Lvalue {
+    Plain(TackyValueAST),
+    Dereferenced(TackyValueAST),
+    SubObject {
+        base: String,
+        offset: i32,
+        member_type: Type,
+    },
+}
+
+struct TackyGenerator<'a> {
+    symbols: &'a mut SymbolTable,
+    types: &'a TypeTable,
+    name_generator: &'a mut NameGenerator,
+}
+
+impl<'a> TackyGenerator<'a> {
+    fn new(
+        symbols: &'a mut SymbolTable,
+        types: &'a TypeTable,
+        name_generator: &'a mut NameGenerator,
+    ) -> Self {
+        TackyGenerator {
+            symbols,
+            types,
+            name_generator,
+        }
+    }
+
+    fn generate_function(&mut self, function: FunctionAST) -> Option<TackyFunctionAST> {
+        let FunctionAST {
+            name, params, body, ..
+        } = function;
+        let body = body?;
+        let IdentifierAttrs::Function { global, .. } = self.symbols[&name].attrs else {
+            unreachable!("{} is not a function", name);
+        };
+        let Type::Function { ret, .. } = self.symbols[&name].symbol_type.clone() else {
+            unreachable!("{} does not have function type", name);
+        };
+
+        let mut instructions = vec![];
+        self.generate_block(body, &mut instructions);
+
+        // SS: falling off the end of a function returns 0, which is what C requires for main
+        // and harmless otherwise. A function returning a structure returns whatever is in an
+        // uninitialized one.
+        let fall_through_value = if ret.is_structure() {
+            self.make_temporary(&ret)
+        } else {
+            TackyValueAST::Constant(Constant::Int(0).convert_to(&ret))
+        };
+        instructions.push(TackyInstructionAST::Return(fall_through_value));
+
+        Some(TackyFunctionAST {
+            name,
+            global,
+            params,
+            instructions,
+        })
+    }
+
+    fn generate_block(&mut self, block: BlockAST, instructions: &mut Vec<TackyInstructionAST>) {
+        for block_item in block.items {
+            self.generate_block_item(block_item, instructions);
+        }
+    }
+
+    fn generate_block_item(
+        &mut self,
+        block_item: BlockItemAST,
+        instructions: &mut Vec<TackyInstructionAST>,
+    ) {
+        match block_item {
+            BlockItemAST::Statement(stmt) => self.generate_stmt(stmt, instructions),
+            BlockItemAST::Declaration(declaration) => {
+                self.generate_declaration(declaration, instructions)
+            }
+            BlockItemAST::FunctionDeclaration(_) | BlockItemAST::StructDeclaration(_) => {}
+        }
+    }
+
+    fn generate_declaration(
+        &mut self,
+        declaration: DeclarationAST,
+        instructions: &mut Vec<TackyInstructionAST>,
+    ) {
+        // SS: a declaration without an initializer produces no code, neither does one with a
+        // storage class since static locals are initialized before the program starts
+        if declaration.storage_class.is_some() {
+            return;
+        }
+        match declaration.init {
+            Some(InitializerAST::Single(expr))
+                if !matches!(declaration.var_type, Type::Array { .. }) =>
+            {
+                let value = self.generate_expr(expr, instructions);
+                instructions.push(TackyInstructionAST::Copy {
+                    src: value,
+                    dst: TackyValueAST::Var(declaration.name),
+                });
+            }
+            Some(init) => {
+                let var_type = declaration.var_type.clone();
+                self.generate_initializer(&declaration.name, 0, init, &var_type, instructions)
+            }
+            None => {}
+        }
+    }
+
+    // SS: initializes the part of the array or structure variable name at the given
+    // offset. The type checker padded every initializer list to the length of its array
+    // or the number of members of its structure, so every element is written.
+    fn generate_initializer(
+        &mut self,
+        name: &str,
+        offset: i32,
+        init: InitializerAST,
+        init_type: &Type,
+        instructions: &mut Vec<TackyInstructionAST>,
+    ) {
+        match (init, init_type) {
+            (InitializerAST::Single(ExprAST::String(bytes)), Type::Array { size, .. }) => {
+                self.generate_string_initializer(name, offset, bytes, *size, instructions)
+            }
+            (InitializerAST::Single(expr), _) => {
+                let value = self.generate_expr(expr, instructions);
+                instructions.push(TackyInstructionAST::CopyToOffset {
+                    src: value,
+                    dst: name.to_string(),
+                    offset,
+                });
+            }
+            (InitializerAST::Compound(initializers), Type::Structure(tag) | Type::Union(tag)) => {
+                let members = &self.types[tag].members;
+                for (init, member) in initializers.into_iter().zip(members) {
+                    self.generate_initializer(
+                        name,
+                        offset + member.offset,
+                        init,
+                        &member.member_type,
+                        instructions,
+                    );
+                }
+            }
+            (InitializerAST::Compound(initializers), _) => {
+                let element_type = init_type.referenced_type();
+                for (i, init) in initializers.into_iter().enumerate() {
+                    self.generate_initializer(
+                        name,
+                        offset + i as i32 * element_type.size(self.types),
+                        init,
+                        element_type,
+                        instructions,
+                    );
+                }
+            }
+        }
+    }
+
+    // SS: copies the characters to the array and fills the rest of it with zeros, eight
+    // or four bytes at a time where possible
+    fn generate_string_initializer(
+        &mut self,
+        name: &str,
+        offset: i32,
+        mut bytes: Vec<u8>,
+        size: usize,
+        instructions: &mut Vec<TackyInstructionAST>,
+    ) {
+        bytes.resize(size, 0);
+        let mut start = 0;
+        while start < size {
+            let (val, length) = if size - start >= 8 {
+                let chunk = bytes[start..start + 8].try_into().unwrap();
+                (Constant::Long(i64::from_le_bytes(chunk)), 8)
+            } else if size - start >= 4 {
+                let chunk = bytes[start..start + 4].try_into().unwrap();
+                (Constant::Int(i32::from_le_bytes(chunk)), 4)
+            } else {
+                (Constant::Char(bytes[start] as i8), 1)
+            };
+            instructions.push(TackyInstructionAST::CopyToOffset {
+                src: TackyValueAST::Constant(val),
+                dst: name.to_string(),
+                offset: offset + start as i32,
+            });
+            start += length;
+        }
+    }
+
+    fn generate_stmt(&mut self, stmt: StmtAST, instructions: &mut Vec<TackyInstructionAST>) {
+        match stmt {
+            StmtAST::Return(expr) => {
+                let value = self.generate_expr(expr, instructions);
+                instructions.push(TackyInstructionAST::Return(value));
+            }
+            StmtAST::Expression(expr) => {
+                self.generate_expr(expr, instructions);
+            }
+            StmtAST::If {
+                condition,
+                then_stmt,
+                else_stmt: None,
+            } => {
+                let end_label = self.make_label("if_end");
+                let condition = self.generate_expr(condition, instructions);
+                instructions.push(TackyInstructionAST::JumpIfZero(
+                    condition,
+                    end_label.clone(),
+                ));
+                self.generate_stmt(*then_stmt, instructions);
+                instructions.push(TackyInstructionAST::Label(end_label));
+            }
+            StmtAST::If {
+                condition,
+                then_stmt,
+                else_stmt: Some(else_stmt),
+            } => {
+                let else_label = self.make_label("if_else");
+                let end_label = self.make_label("if_end");
+                let condition = self.generate_expr(condition, instructions);
+                instructions.push(TackyInstructionAST::JumpIfZero(
+                    condition,
+                    else_label.clone(),
+                ));
+                self.generate_stmt(*then_stmt, instructions);
+                instructions.push(TackyInstructionAST::Jump(end_label.clone()));
+                instructions.push(TackyInstructionAST::Label(else_label));
+                self.generate_stmt(*else_stmt, instructions);
+                instructions.push(TackyInstructionAST::Label(end_label));
+            }
+            StmtAST::Compound(block) => self.generate_block(block, instructions),
+            StmtAST::Break { label, .. } => {
+                instructions.push(TackyInstructionAST::Jump(break_label(&loop_label(label))));
+            }
+            StmtAST::Continue { label, .. } => {
+                instructions.push(TackyInstructionAST::Jump(continue_label(&loop_label(
+                    label,
+                ))));
+            }
+            StmtAST::While {
+                condition,
+                body,
+                label,
+            } => {
+                let label = loop_label(label);
+                instructions.push(TackyInstructionAST::Label(continue_label(&label)));
+                let condition = self.generate_expr(condition, instructions);
+                instructions.push(TackyInstructionAST::JumpIfZero(
+                    condition,
+                    break_label(&label),
+                ));
+                self.generate_stmt(*body, instructions);
+                instructions.push(TackyInstructionAST::Jump(continue_label(&label)));
+                instructions.push(TackyInstructionAST::Label(break_label(&label)));
+            }
+            StmtAST::DoWhile {
+                body,
+                condition,
+                label,
+            } => {
+                let label = loop_label(label);
+                instructions.push(TackyInstructionAST::Label(start_label(&label)));
+                self.generate_stmt(*body, instructions);
+                instructions.push(TackyInstructionAST::Label(continue_label(&label)));
+                let condition = self.generate_expr(condition, instructions);
+                instructions.push(TackyInstructionAST::JumpIfNotZero(
+                    condition,
+                    start_label(&label),
+                ));
+                instructions.push(TackyInstructionAST::Label(break_label(&label)));
+            }
+            StmtAST::For {
+                init,
+                condition,
+                post,
+                body,
+                label,
+            } => {
+                let label = loop_label(label);
+                match init {
+                    ForInitAST::InitDecl(declaration) => {
+                        self.generate_declaration(declaration, instructions)
+                    }
+                    ForInitAST::InitExpr(Some(expr)) => {
+                        self.generate_expr(expr, instructions);
+                    }
+                    ForInitAST::InitExpr(None) => {}
+                }
+                instructions.push(TackyInstructionAST::Label(start_label(&label)));
+                // SS: a missing condition is treated as always true
+                if let Some(condition) = condition {
+                    let condition = self.generate_expr(condition, instructions);
+                    instructions.push(TackyInstructionAST::JumpIfZero(
+                        condition,
+                        break_label(&label),
+                    ));
+                }
+                self.generate_stmt(*body, instructions);
+                instructions.push(TackyInstructionAST::Label(continue_label(&label)));
+                if let Some(post) = post {
+                    self.generate_expr(post, instructions);
+                }
+                instructions.push(TackyInstructionAST::Jump(start_label(&label)));
+                instructions.push(TackyInstructionAST::Label(break_label(&label)));
+            }
+            StmtAST::Switch {
+                condition,
+                body,
+                label,
+                cases,
+            } => {
+                let label = loop_label(label);
+                let condition = self.generate_expr(condition, instructions);
+                self.generate_dispatch(condition, &cases, &break_label(&label), instructions);
+                self.generate_stmt(*body, instructions);
+                instructions.push(TackyInstructionAST::Label(break_label(&label)));
+            }
+            StmtAST::Case { body, label, .. } | StmtAST::Default { body, label, .. } => {
+                instructions.push(TackyInstructionAST::Label(loop_label(label)));
+                self.generate_stmt(*body, instructions);
+            }
+            StmtAST::Goto { label, .. } => instructions.push(TackyInstructionAST::Jump(label)),
+            StmtAST::Labeled { label, body, .. } => {
+                instructions.push(TackyInstructionAST::Label(label));
+                self.generate_stmt(*body, instructions);
+            }
+            StmtAST::Null => {}
+        }
+    }
+
+    // SS: jumps to the case matching the condition, or to the default or the end of the
+    // switch when there is none. The values of the cases are distinct and have the type of
+    // the condition. Dense cases index a jump table, sparse ones are compared one by one.
+    fn generate_dispatch(
+        &mut self,
+        condition: TackyValueAST,
+        cases: &[SwitchCaseAST],
+        end_label: &str,
+        instructions: &mut Vec<TackyInstructionAST>,
+    ) {
+        let default_label = cases
+            .iter()
+            .find(|case| case.value.is_none())
+            .map_or(end_label, |case| &case.label)
+            .to_string();
+        let values = cases
+            .iter()
+            .filter_map(|case| case.value.map(|value| (value, &case.label)))
+            .collect::<Vec<_>>();
+        if values.is_empty() {
+            instructions.push(TackyInstructionAST::Jump(default_label));
+            return;
+        }
+
+        let switch_type = values[0].0.constant_type();
+        let key = |value: &Constant| {
+            if switch_type.is_signed() {
+                value.bits() as i128
+            } else {
+                value.bits() as u64 as i128
+            }
+        };
+        let min = values.iter().map(|(value, _)| key(value)).min().unwrap();
+        let max = values.iter().map(|(value, _)| key(value)).max().unwrap();
+        let range = (max - min + 1) as u128;
+        if !use_jump_table(values.len(), range) {
+            for (value, label) in values {
+                let equal = self.make_temporary(&Type::Int);
+                instructions.push(TackyInstructionAST::Binary {
+                    op: TackyBinaryOperatorAST::Equal,
+                    src1: condition.clone(),
+                    src2: TackyValueAST::Constant(value),
+                    dst: equal.clone(),
+                });
+                instructions.push(TackyInstructionAST::JumpIfNotZero(equal, label.clone()));
+            }
+            instructions.push(TackyInstructionAST::Jump(default_label));
+            return;
+        }
+
+        // SS: the index is the distance from the smallest value, computed unsigned so that a
+        // condition below the smallest value wraps around and fails the bounds check as well
+        let index_type = if switch_type.scalar_size() == 4 {
+            Type::UInt
+        } else {
+            Type::ULong
+        };
+        let unsigned_condition = self.make_temporary(&index_type);
+        instructions.push(TackyInstructionAST::Copy {
+            src: condition,
+            dst: unsigned_condition.clone(),
+        });
+        let index = self.make_temporary(&index_type);
+        instructions.push(TackyInstructionAST::Binary {
+            op: TackyBinaryOperatorAST::Subtract,
+            src1: unsigned_condition,
+            src2: TackyValueAST::Constant(Constant::Long(min as i64).convert_to(&index_type)),
+            dst: index.clone(),
+        });
+        let out_of_range = self.make_temporary(&Type::Int);
+        instructions.push(TackyInstructionAST::Binary {
+            op: TackyBinaryOperatorAST::GreaterThan,
+            src1: index.clone(),
+            src2: TackyValueAST::Constant(Constant::Long(range as i64 - 1).convert_to(&index_type)),
+            dst: out_of_range.clone(),
+        });
+        instructions.push(TackyInstructionAST::JumpIfNotZero(
+            out_of_range,
+            default_label.clone(),
+        ));
+        let index = if index_type == Type::UInt {
+            let extended = self.make_temporary(&Type::ULong);
+            instructions.push(TackyInstructionAST::ZeroExtend {
+                src: index,
+                dst: extended.clone(),
+            });
+            extended
+        } else {
+            index
+        };
+
+        let mut targets = vec![default_label; range as usize];
+        for (value, label) in values {
+            targets[(key(&value) - min) as usize] = label.clone();
+        }
+        instructions.push(TackyInstructionAST::JumpTable { index, targets });
+    }
+
+    fn generate_expr(
+        &mut self,
+        expr: ExprAST,
+        instructions: &mut Vec<TackyInstructionAST>,
+    ) -> TackyValueAST {
+        match expr {
+            ExprAST::Constant(val) => TackyValueAST::Constant(val),
+            ExprAST::Var { name, .. } => TackyValueAST::Var(name),
+            ExprAST::Assignment { lhs, rhs, .. } => {
+                let lvalue = self.generate_lvalue(*lhs, instructions);
+                let value = self.generate_expr(*rhs, instructions);
+                match lvalue {
+                    Lvalue::Plain(dst) => {
+                        instructions.push(TackyInstructionAST::Copy {
+                            src: value,
+                            dst: dst.clone(),
+                        });
+                        dst
+                    }
+                    Lvalue::Dereferenced(ptr) => {
+                        instructions.push(TackyInstructionAST::Store {
+                            src: value.clone(),
+                            dst_ptr: ptr,
+                        });
+                        value
+                    }
+                    Lvalue::SubObject { base, offset, .. } => {
+                        instructions.push(TackyInstructionAST::CopyToOffset {
+                            src: value.clone(),
+                            dst: base,
+                            offset,
+                        });
+                        value
+                    }
+                }
+            }
+            ExprAST::CompoundAssignment { op, lhs, rhs, .. } => {
+                let lvalue = self.generate_lvalue(*lhs, instructions);
+                let value = self.generate_expr(*rhs, instructions);
+                let current = self.generate_lvalue_value(&lvalue, instructions);
+
+                // SS: the type checker converted the rhs to the type the operation is carried
+                // out in, the lhs is converted to it and the result back to the type of the
+                // lhs. Pointer arithmetic is carried out in the type of the pointer.
+                let lhs_type = self.value_type(&current);
+                let common_type = self.value_type(&value);
+                let dst = self.lvalue_destination(&lvalue, &lhs_type);
+                if lhs_type == common_type || lhs_type.is_pointer() {
+                    self.generate_arithmetic(op, current, value, dst.clone(), instructions);
+                } else {
+                    let src1 = self.generate_cast(current, &common_type, instructions);
+                    let result = self.make_temporary(&common_type);
+                    self.generate_arithmetic(op, src1, value, result.clone(), instructions);
+                    let result = self.generate_cast(result, &lhs_type, instructions);
+                    instructions.push(TackyInstructionAST::Copy {
+                        src: result,
+                        dst: dst.clone(),
+                    });
+                }
+                self.generate_store(&lvalue, &dst, instructions);
+                dst
+            }
+            ExprAST::Cast {
+                target_type, expr, ..
+            } => {
+                let value = self.generate_expr(*expr, instructions);
+                self.generate_cast(value, &target_type, instructions)
+            }
+            ExprAST::Increment { op, operand, .. } => {
+                let lvalue = self.generate_lvalue(*operand, instructions);
+                let current = self.generate_lvalue_value(&lvalue, instructions);
+                let operand_type = self.value_type(&current);
+                let binary_op =
+                    match op {
+                        IncrementOperatorAST::PreIncrement
+                        | IncrementOperatorAST::PostIncrement => BinaryOperatorAST::Add,
+                        IncrementOperatorAST::PreDecrement
+                        | IncrementOperatorAST::PostDecrement => BinaryOperatorAST::Subtract,
+                    };
+
+                // SS: the postfix forms evaluate to the value before the update, a value loaded
+                // through a pointer is already a copy of it
+                let is_postfix = matches!(
+                    op,
+                    IncrementOperatorAST::PostIncrement | IncrementOperatorAST::PostDecrement
+                );
+                let old_value = match &lvalue {
+                    Lvalue::Plain(_) if is_postfix => {
+                        let old_value = self.make_temporary(&operand_type);
+                        instructions.push(TackyInstructionAST::Copy {
+                            src: current.clone(),
+                            dst: old_value.clone(),
+                        });
+                        Some(old_value)
+                    }
+                    Lvalue::Plain(_) => None,
+                    Lvalue::Dereferenced(_) | Lvalue::SubObject { .. } => Some(current.clone()),
+                };
+
+                // SS: a pointer moves by one element, a character is promoted to int and the
+                // result converted back
+                let dst = self.lvalue_destination(&lvalue, &operand_type);
+                if operand_type.is_character() {
+                    let src1 = self.generate_cast(current, &Type::Int, instructions);
+                    let result = self.make_temporary(&Type::Int);
+                    self.generate_arithmetic(
+                        binary_op,
+                        src1,
+                        TackyValueAST::Constant(Constant::Int(1)),
+                        result.clone(),
+                        instructions,
+                    );
+                    let result = self.generate_cast(result, &operand_type, instructions);
+                    instructions.push(TackyInstructionAST::Copy {
+                        src: result,
+                        dst: dst.clone(),
+                    });
+                } else {
+                    let one = if operand_type.is_pointer() {
+                        Constant::Long(1)
+                    } else {
+                        Constant::Int(1).convert_to(&operand_type)
+                    };
+                    self.generate_arithmetic(
+                        binary_op,
+                        current,
+                        TackyValueAST::Constant(one),
+                        dst.clone(),
+                        instructions,
+                    );
+                }
+                self.generate_store(&lvalue, &dst, instructions);
+                match old_value {
+                    Some(old_value) if is_postfix => old_value,
+                    _ => dst,
+                }
+            }
+            ExprAST::Unary(op, inner) => {
+                let src = self.generate_expr(*inner, instructions);
+                let dst_type = match op {
+                    UnaryOperatorAST::Not => Type::Int,
+                    UnaryOperatorAST::Negate | UnaryOperatorAST::Complement => {
+                        self.value_type(&src)
+                    }
+                };
+                let dst = self.make_temporary(&dst_type);
+                instructions.push(TackyInstructionAST::Unary {
+                    op: convert_unary_operator(op),
+                    src,
+                    dst: dst.clone(),
+                });
+                dst
+            }
+            ExprAST::Conditional {
+                condition,
+                then_expr,
+                else_expr,
+            } => {
+                let else_label = self.make_label("cond_else");
+                let end_label = self.make_label("cond_end");
+
+                let condition = self.generate_expr(*condition, instructions);
+                instructions.push(TackyInstructionAST::JumpIfZero(
+                    condition,
+                    else_label.clone(),
+                ));
+                let v1 = self.generate_expr(*then_expr, instructions);
+
+                // SS: the type checker converted both branches to their common type
+                let dst_type = self.value_type(&v1);
+                let dst = self.make_temporary(&dst_type);
+                instructions.push(TackyInstructionAST::Copy {
+                    src: v1,
+                    dst: dst.clone(),
+                });
+                instructions.push(TackyInstructionAST::Jump(end_label.clone()));
+                instructions.push(TackyInstructionAST::Label(else_label));
+                let v2 = self.generate_expr(*else_expr, instructions);
+                instructions.push(TackyInstructionAST::Copy {
+                    src: v2,
+                    dst: dst.clone(),
+                });
+                instructions.push(TackyInstructionAST::Label(end_label));
+                dst
+            }
+            ExprAST::FunctionCall { name, args, .. } => {
+                let args = args
+                    .into_iter()
+                    .map(|arg| self.generate_expr(arg, instructions))
+                    .collect();
+                let Type::Function { ret, .. } = self.symbols[&name].symbol_type.clone() else {
+                    unreachable!("{} is not a function", name);
+                };
+                let dst = self.make_temporary(&ret);
+                instructions.push(TackyInstructionAST::FunctionCall {
+                    name,
+                    args,
+                    dst: dst.clone(),
+                });
+                dst
+            }
+            ExprAST::Binary(BinaryOperatorAST::And, left, right) => {
+                // SS: short-circuit, the right operand is only evaluated if the left one is true
+                let false_label = self.make_label("and_false");
+                let end_label = self.make_label("and_end");
+                let dst = self.make_temporary(&Type::Int);
+
+                let v1 = self.generate_expr(*left, instructions);
+                instructions.push(TackyInstructionAST::JumpIfZero(v1, false_label.clone()));
+                let v2 = self.generate_expr(*right, instructions);
+                instructions.push(TackyInstructionAST::JumpIfZero(v2, false_label.clone()));
+                instructions.push(TackyInstructionAST::Copy {
+                    src: TackyValueAST::Constant(Constant::Int(1)),
+                    dst: dst.clone(),
+                });
+                instructions.push(TackyInstructionAST::Jump(end_label.clone()));
+                instructions.push(TackyInstructionAST::Label(false_label));
+                instructions.push(TackyInstructionAST::Copy {
+                    src: TackyValueAST::Constant(Constant::Int(0)),
+                    dst: dst.clone(),
+                });
+                instructions.push(TackyInstructionAST::Label(end_label));
+                dst
+            }
+            ExprAST::Binary(BinaryOperatorAST::Or, left, right) => {
+                // SS: short-circuit, the right operand is only evaluated if the left one is false
+                let true_label = self.make_label("or_true");
+                let end_label = self.make_label("or_end");
+                let dst = self.make_temporary(&Type::Int);
+
+                let v1 = self.generate_expr(*left, instructions);
+                instructions.push(TackyInstructionAST::JumpIfNotZero(v1, true_label.clone()));
+                let v2 = self.generate_expr(*right, instructions);
+                instructions.push(TackyInstructionAST::JumpIfNotZero(v2, true_label.clone()));
+                instructions.push(TackyInstructionAST::Copy {
+                    src: TackyValueAST::Constant(Constant::Int(0)),
+                    dst: dst.clone(),
+                });
+                instructions.push(TackyInstructionAST::Jump(end_label.clone()));
+                instructions.push(TackyInstructionAST::Label(true_label));
+                instructions.push(TackyInstructionAST::Copy {
+                    src: TackyValueAST::Constant(Constant::Int(1)),
+                    dst: dst.clone(),
+                });
+                instructions.push(TackyInstructionAST::Label(end_label));
+                dst
+            }
+            ExprAST::Binary(op, left, right) => {
+                let src1 = self.generate_expr(*left, instructions);
+                let src2 = self.generate_expr(*right, instructions);
+                let src1_type = self.value_type(&src1);
+                let dst_type = if is_relational(op) {
+                    Type::Int
+                } else if src1_type.is_pointer() && self.value_type(&src2).is_pointer() {
+                    Type::Long
+                } else {
+                    src1_type
+                };
+                let dst = self.make_temporary(&dst_type);
+                self.generate_arithmetic(op, src1, src2, dst.clone(), instructions);
+                dst
+            }
+            ExprAST::Dereference { expr, .. } => {
+                let ptr = self.generate_expr(*expr, instructions);
+                let dst_type = self.value_type(&ptr).referenced_type().clone();
+                let dst = self.make_temporary(&dst_type);
+                instructions.push(TackyInstructionAST::Load {
+                    src_ptr: ptr,
+                    dst: dst.clone(),
+                });
+                dst
+            }
+            // SS: the address of a dereferenced pointer is the pointer itself
+            ExprAST::AddressOf { expr, .. } => match self.generate_lvalue(*expr, instructions) {
+                Lvalue::Plain(src) => {
+                    let dst_type = Type::Pointer(Box::new(self.value_type(&src)));
+                    let dst = self.make_temporary(&dst_type);
+                    instructions.push(TackyInstructionAST::GetAddress {
+                        src,
+                        dst: dst.clone(),
+                    });
+                    dst
+                }
+                Lvalue::Dereferenced(ptr) => ptr,
+                Lvalue::SubObject {
+                    base,
+                    offset,
+                    member_type,
+                } => {
+                    let base = TackyValueAST::Var(base);
+                    let base_type = Type::Pointer(Box::new(self.value_type(&base)));
+                    let address = self.make_temporary(&base_type);
+                    instructions.push(TackyInstructionAST::GetAddress {
+                        src: base,
+                        dst: address.clone(),
+                    });
+                    self.generate_member_pointer(address, offset, &member_type, instructions)
+                }
+            },
+            ExprAST::String(bytes) => TackyValueAST::Var(self.make_string_constant(bytes)),
+            expr @ (ExprAST::Dot { .. } | ExprAST::Arrow { .. }) => {
+                let lvalue = self.generate_lvalue(expr, instructions);
+                self.generate_lvalue_value(&lvalue, instructions)
+            }
+            ExprAST::Subscript { .. } => {
+                unreachable!("type checking rewrites subscripts as pointer arithmetic")
+            }
+            ExprAST::SizeOf { .. } | ExprAST::SizeOfType { .. } => {
+                unreachable!("type checking replaces sizeof with a constant")
+            }
+        }
+    }
+
+    // SS: a pointer to the member at the given offset within the structure ptr points to
+    fn generate_member_pointer(
+        &mut self,
+        ptr: TackyValueAST,
+        offset: i32,
+        member_type: &Type,
+        instructions: &mut Vec<TackyInstructionAST>,
+    ) -> TackyValueAST {
+        let dst = self.make_temporary(&Type::Pointer(Box::new(member_type.clone())));
+        instructions.push(TackyInstructionAST::AddPtr {
+            ptr,
+            index: TackyValueAST::Constant(Constant::Long(offset as i64)),
+            scale: 1,
+            dst: dst.clone(),
+        });
+        dst
+    }
+
+    fn member(&self, structure_type: &Type, member: &str) -> MemberEntry {
+        let (Type::Structure(tag) | Type::Union(tag)) = structure_type else {
+            unreachable!("{:?} is not a structure type", structure_type);
+        };
+        self.types[tag]
+            .member(member)
+            .expect("type checking only admits existing members")
+            .clone()
+    }
+
+    // SS: string literals are read-only objects of their own
+    fn make_string_constant(&mut self, bytes: Vec<u8>) -> String {
+        let name = self.name_generator.make_unique("string");
+        self.symbols.insert(
+            name.clone(),
+            Symbol {
+                symbol_type: Type::Array {
+                    element: Box::new(Type::Char),
+                    size: bytes.len() + 1,
+                },
+                attrs: IdentifierAttrs::Constant(StaticInit::String {
+                    bytes,
+                    null_terminated: true,
+                }),
+            },
+        );
+        name
+    }
+
+    // SS: emits a binary operation whose operands have already been evaluated. Pointer
+    // arithmetic scales the integer operand by the size of the pointed-to type, the type
+    // checker has put the pointer on the left.
+    fn generate_arithmetic(
+        &mut self,
+        op: BinaryOperatorAST,
+        src1: TackyValueAST,
+        src2: TackyValueAST,
+        dst: TackyValueAST,
+        instructions: &mut Vec<TackyInstructionAST>,
+    ) {
+        let src1_type = self.value_type(&src1);
+        let src2_type = self.value_type(&src2);
+        match op {
+            BinaryOperatorAST::Add | BinaryOperatorAST::Subtract
+                if src1_type.is_pointer() && !src2_type.is_pointer() =>
+            {
+                let index = if op == BinaryOperatorAST::Add {
+                    src2
+                } else if let TackyValueAST::Constant(val) = src2 {
+                    TackyValueAST::Constant(Constant::Long(val.bits().wrapping_neg()))
+                } else {
+                    let negated = self.make_temporary(&Type::Long);
+                    instructions.push(TackyInstructionAST::Unary {
+                        op: TackyUnaryOperatorAST::Negate,
+                        src: src2,
+                        dst: negated.clone(),
+                    });
+                    negated
+                };
+                instructions.push(TackyInstructionAST::AddPtr {
+                    ptr: src1,
+                    index,
+                    scale: src1_type.referenced_type().size(self.types) as i64,
+                    dst,
+                });
+            }
+            // SS: the difference of two pointers counts elements, not bytes
+            BinaryOperatorAST::Subtract if src1_type.is_pointer() => {
+                let difference = self.make_temporary(&Type::Long);
+                instructions.push(TackyInstructionAST::Binary {
+                    op: TackyBinaryOperatorAST::Subtract,
+                    src1,
+                    src2,
+                    dst: difference.clone(),
+                });
+                instructions.push(TackyInstructionAST::Binary {
+                    op: TackyBinaryOperatorAST::Divide,
+                    src1: difference,
+                    src2: TackyValueAST::Constant(Constant::Long(
+                        src1_type.referenced_type().size(self.types) as i64,
+                    )),
+                    dst,
+                });
+            }
+            _ => instructions.push(TackyInstructionAST::Binary {
+                op: convert_binary_operator(op),
+                src1,
+                src2,
+                dst,
+            }),
+        }
+    }
+
+    fn generate_lvalue(
+        &mut self,
+        expr: ExprAST,
+        instructions: &mut Vec<TackyInstructionAST>,
+    ) -> Lvalue {
+        match expr {
+            ExprAST::Var { name, .. } => Lvalue::Plain(TackyValueAST::Var(name)),
+            ExprAST::Dereference { expr, .. } => {
+                Lvalue::Dereferenced(self.generate_expr(*expr, instructions))
+            }
+            ExprAST::String(bytes) => {
+                Lvalue::Plain(TackyValueAST::Var(self.make_string_constant(bytes)))
+            }
+            ExprAST::Dot {
+                structure, member, ..
+            } => {
+                // SS: a structure returned by a function or produced by another operator is
+                // not an lvalue, its members are read from the temporary holding it
+                let structure = match *structure {
+                    structure @ (ExprAST::Var { .. }
+                    | ExprAST::Dereference { .. }
+                    | ExprAST::Dot { .. }
+                    | ExprAST::Arrow { .. }) => self.generate_lvalue(structure, instructions),
+                    structure => Lvalue::Plain(self.generate_expr(structure, instructions)),
+                };
+                let member = self.member(&self.lvalue_type(&structure), &member);
+                match structure {
+                    Lvalue::Plain(TackyValueAST::Var(base)) => Lvalue::SubObject {
+                        base,
+                        offset: member.offset,
+                        member_type: member.member_type,
+                    },
+                    Lvalue::SubObject { base, offset, .. } => Lvalue::SubObject {
+                        base,
+                        offset: offset + member.offset,
+                        member_type: member.member_type,
+                    },
+                    Lvalue::Dereferenced(ptr) => {
+                        Lvalue::Dereferenced(self.generate_member_pointer(
+                            ptr,
+                            member.offset,
+                            &member.member_type,
+                            instructions,
+                        ))
+                    }
+                    Lvalue::Plain(TackyValueAST::Constant(_)) => {
+                        unreachable!("a constant is not a structure")
+                    }
+                }
+            }
+            ExprAST::Arrow {
+                pointer, member, ..
+            } => {
+                let ptr = self.generate_expr(*pointer, instructions);
+                let member = self.member(self.value_type(&ptr).referenced_type(), &member);
+                Lvalue::Dereferenced(self.generate_member_pointer(
+                    ptr,
+                    member.offset,
+                    &member.member_type,
+                    instructions,
+                ))
+            }
+            _ => {
+                unreachable!(
+                    "semantic analysis only admits variables, dereferences and members as lvalues"
+                )
+            }
+        }
+    }
+
+    fn lvalue_type(&self, lvalue: &Lvalue) -> Type {
+        match lvalue {
+            Lvalue::Plain(value) => self.value_type(value),
+            Lvalue::Dereferenced(ptr) => self.value_type(ptr).referenced_type().clone(),
+            Lvalue::SubObject { member_type, .. } => member_type.clone(),
+        }
+    }
+
+    // SS: the current value of the object an lvalue designates
+    fn generate_lvalue_value(
+        &mut self,
+        lvalue: &Lvalue,
+        instructions: &mut Vec<TackyInstructionAST>,
+    ) -> TackyValueAST {
+        match lvalue {
+            Lvalue::Plain(value) => value.clone(),
+            Lvalue::Dereferenced(ptr) => {
+                let dst_type = self.value_type(ptr).referenced_type().clone();
+                let dst = self.make_temporary(&dst_type);
+                instructions.push(TackyInstructionAST::Load {
+                    src_ptr: ptr.clone(),
+                    dst: dst.clone(),
+                });
+                dst
+            }
+            Lvalue::SubObject {
+                base,
+                offset,
+                member_type,
+            } => {
+                let dst = self.make_temporary(member_type);
+                instructions.push(TackyInstructionAST::CopyFromOffset {
+                    src: base.clone(),
+                    offset: *offset,
+                    dst: dst.clone(),
+                });
+                dst
+            }
+        }
+    }
+
+    // SS: where to compute the new value of an lvalue that is updated in place, a variable
+    // is updated directly while memory behind a pointer or a member is written by
+    // generate_store
+    fn lvalue_destination(&mut self, lvalue: &Lvalue, lvalue_type: &Type) -> TackyValueAST {
+        match lvalue {
+            Lvalue::Plain(value) => value.clone(),
+            Lvalue::Dereferenced(_) | Lvalue::SubObject { .. } => self.make_temporary(lvalue_type),
+        }
+    }
+
+    fn generate_store(
+        &mut self,
+        lvalue: &Lvalue,
+        value: &TackyValueAST,
+        instructions: &mut Vec<TackyInstructionAST>,
+    ) {
+        match lvalue {
+            Lvalue::Plain(_) => {}
+            Lvalue::Dereferenced(ptr) => instructions.push(TackyInstructionAST::Store {
+                src: value.clone(),
+                dst_ptr: ptr.clone(),
+            }),
+            Lvalue::SubObject { base, offset, .. } => {
+                instructions.push(TackyInstructionAST::CopyToOffset {
+                    src: value.clone(),
+                    dst: base.clone(),
+                    offset: *offset,
+                })
+            }
+        }
+    }
+
+    // SS: converts the value to the target type, picking the conversion from the source
+    // and target types
+    fn generate_cast(
+        &mut self,
+        value: TackyValueAST,
+        target_type: &Type,
+        instructions: &mut Vec<TackyInstructionAST>,
+    ) -> TackyValueAST {
+        let source_type = self.value_type(&value);
+        if source_type == *target_type {
+            return value;
+        }
+        let dst = self.make_temporary(target_type);
+        let src = value;
+        let instruction = if *target_type == Type::Double {
+            if source_type.is_signed() {
+                TackyInstructionAST::IntToDouble {
+                    src,
+                    dst: dst.clone(),
+                }
+            } else {
+                TackyInstructionAST::UIntToDouble {
+                    src,
+                    dst: dst.clone(),
+                }
+            }
+        } else if source_type == Type::Double {
+            if target_type.is_signed() {
+                TackyInstructionAST::DoubleToInt {
+                    src,
+                    dst: dst.clone(),
+                }
+            } else {
+                TackyInstructionAST::DoubleToUInt {
+                    src,
+                    dst: dst.clone(),
+                }
+            }
+        } else if source_type.scalar_size() == target_type.scalar_size() {
+            // SS: only the interpretation of the bits changes
+            TackyInstructionAST::Copy {
+                src,
+                dst: dst.clone(),
+            }
+        } else if target_type.scalar_size() < source_type.scalar_size() {
+            TackyInstructionAST::Truncate {
+                src,
+                dst: dst.clone(),
+            }
+        } else if source_type.is_signed() {
+            TackyInstructionAST::SignExtend {
+                src,
+                dst: dst.clone(),
+            }
+        } else {
+            TackyInstructionAST::ZeroExtend {
+                src,
+                dst: dst.clone(),
+            }
+        };
+        instructions.push(instruction);
+        dst
+    }
+
+    fn value_type(&self, value: &TackyValueAST) -> Type {
+        match value {
+            TackyValueAST::Constant(val) => val.constant_type(),
+            TackyValueAST::Var(name) => self.symbols[name].symbol_type.clone(),
+        }
+    }
+
+    // SS: temporaries are added to the symbol table so later stages know their type
+    fn make_temporary(&mut self, temporary_type: &Type) -> TackyValueAST {
+        let name = self.name_generator.make_unique("tmp");
+        self.symbols.insert(
+            name.clone(),
+            Symbol {
+                symbol_type: temporary_type.clone(),
+                attrs: IdentifierAttrs::Local,
+            },
+        );
+        TackyValueAST::Var(name)
+    }
+
+    fn make_label(&mut self, prefix: &str) -> String {
+        self.name_generator.make_unique(prefix)
+    }
+}
+
+// SS: loop labels are attached by the loop labeling pass, which always runs before tacky generation
+fn loop_label(label: Option<String>) -> String {
+    label.expect("loop has not been labeled")
+}
+
+// SS: a jump table pays off when there are enough cases to make a compare chain slow and
+// they fill at least a third of the range between the smallest and the largest value, so
+// the table stays small
+fn use_jump_table(case_count: usize, range: u128) -> bool {
+    case_count >= MIN_JUMP_TABLE_CASES && range <= (case_count * 3) as u128
+}
+
+fn start_label(label: &str) -> String {
+    format!("start_{}", label)
+}
+
+fn continue_label(label: &str) -> String {
+    format!("continue_{}", label)
+}
+
+fn break_label(label: &str) -> String {
+    format!("break_{}", label)
+}
+
+fn is_relational(op: BinaryOperatorAST) -> bool {
+    matches!(
+        op,
+        BinaryOperatorAST::Equal
+            | BinaryOperatorAST::NotEqual
+            | BinaryOperatorAST::LessThan
+            | BinaryOperatorAST::LessOrEqual
+            | BinaryOperatorAST::GreaterThan
+            | BinaryOperatorAST::GreaterOrEqual
+    )
+}
+
+pub fn convert_unary_operator(op: UnaryOperatorAST) -> TackyUnaryOperatorAST {
+    match op {
+        UnaryOperatorAST::Negate => TackyUnaryOperatorAST::Negate,
+        UnaryOperatorAST::Complement => TackyUnaryOperatorAST::Complement,
+        UnaryOperatorAST::Not => TackyUnaryOperatorAST::Not,
+    }
+}
+
+pub fn convert_binary_operator(op: BinaryOperatorAST) -> TackyBinaryOperatorAST {
+    match op {
+        BinaryOperatorAST::Add => TackyBinaryOperatorAST::Add,
+        BinaryOperatorAST::Subtract => TackyBinaryOperatorAST::Subtract,
+        BinaryOperatorAST::Multiply => TackyBinaryOperatorAST::Multiply,
+        BinaryOperatorAST::Divide => TackyBinaryOperatorAST::Divide,
+        BinaryOperatorAST::Remainder => TackyBinaryOperatorAST::Remainder,
+        BinaryOperatorAST::Equal => TackyBinaryOperatorAST::Equal,
+        BinaryOperatorAST::NotEqual => TackyBinaryOperatorAST::NotEqual,
+        BinaryOperatorAST::LessThan => TackyBinaryOperatorAST::LessThan,
+        BinaryOperatorAST::LessOrEqual => TackyBinaryOperatorAST::LessOrEqual,
+        BinaryOperatorAST::GreaterThan => TackyBinaryOperatorAST::GreaterThan,
+        BinaryOperatorAST::GreaterOrEqual => TackyBinaryOperatorAST::GreaterOrEqual,
+        BinaryOperatorAST::And | BinaryOperatorAST::Or => {
+            unreachable!("logical operators are lowered to conditional jumps")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::name_generator::NameGenerator;
+    use crate::parse_ast::{
+        BinaryOperatorAST, BlockAST, BlockItemAST, DeclarationAST, ExprAST, ExternalDeclarationAST,
+        ForInitAST, FunctionAST, IncrementOperatorAST, InitializerAST, ProgramAST, StmtAST,
+        StorageClassAST, SwitchCaseAST, UnaryOperatorAST,
+    };
+    use crate::symbol_table::{IdentifierAttrs, InitialValue, Symbol, SymbolTable};
+    use crate::tacky_ast::{
+        TackyBinaryOperatorAST, TackyFunctionAST, TackyInstructionAST, TackyProgramAST,
+        TackyTopLevelAST, TackyUnaryOperatorAST, TackyValueAST,
+    };
+    use crate::tacky_generation::generate_tacky_program_ast;
+    use crate::type_table::TypeTable;
+    use crate::types::{Constant, StaticInit, Type};
+
+    // SS: the symbol table type checking would build for the functions and local variables
+    // of the program
+    fn function_symbols(ast: &ProgramAST) -> SymbolTable {
+        let mut symbols = SymbolTable::new();
+        for declaration in &ast.declarations {
+            if let ExternalDeclarationAST::Function(function) = declaration {
+                symbols.insert(
+                    function.name.clone(),
+                    Symbol {
+                        symbol_type: function.function_type.clone(),
+                        attrs: IdentifierAttrs::Function {
+                            defined: function.body.is_some(),
+                            global: true,
+                        },
+                    },
+                );
+                let Type::Function { params, .. } = &function.function_type else {
+                    panic!("{} does not have function type", function.name);
+                };
+                for (param, param_type) in function.params.iter().zip(params) {
+                    symbols.insert(param.clone(), local(param_type.clone()));
+                }
+                for item in function.body.iter().flat_map(|body| &body.items) {
+                    add_block_item_symbols(item, &mut symbols);
+                }
+            }
+        }
+        symbols
+    }
+
+    fn local(symbol_type: Type) -> Symbol {
+        Symbol {
+            symbol_type,
+            attrs: IdentifierAttrs::Local,
+        }
+    }
+
+    fn add_block_item_symbols(item: &BlockItemAST, symbols: &mut SymbolTable) {
+        match item {
+            BlockItemAST::Declaration(declaration) => add_declaration_symbol(declaration, symbols),
+            BlockItemAST::Statement(stmt) => add_stmt_symbols(stmt, symbols),
+            BlockItemAST::FunctionDeclaration(_) | BlockItemAST::StructDeclaration(_) => {}
+        }
+    }
+
+    fn add_declaration_symbol(declaration: &DeclarationAST, symbols: &mut SymbolTable) {
+        if declaration.storage_class.is_none() {
+            symbols.insert(
+                declaration.name.clone(),
+                local(declaration.var_type.clone()),
+            );
+        }
+    }
+
+    fn add_stmt_symbols(stmt: &StmtAST, symbols: &mut SymbolTable) {
+        match stmt {
+            StmtAST::Compound(block) => {
+                for item in &block.items {
+                    add_block_item_symbols(item, symbols);
+                }
+            }
+            StmtAST::For { init, body, .. } => {
+                if let ForInitAST::InitDecl(declaration) = init {
+                    add_declaration_symbol(declaration, symbols);
+                }
+                add_stmt_symbols(body, symbols);
+            }
+            StmtAST::If {
+                then_stmt,
+                else_stmt,
+                ..
+            } => {
+                add_stmt_symbols(then_stmt, symbols);
+                if let Some(else_stmt) = else_stmt {
+                    add_stmt_symbols(else_stmt, symbols);
+                }
+            }
+            StmtAST::While { body, .. }
+            | StmtAST::DoWhile { body, .. }
+            | StmtAST::Switch { body, .. }
+            | StmtAST::Case { body, .. }
+            | StmtAST::Default { body, .. }
+            | StmtAST::Labeled { body, .. } => add_stmt_symbols(body, symbols),
+            _ => {}
+        }
+    }
+
+    fn instructions(ast: &TackyProgramAST) -> Vec<TackyInstructionAST> {
+        match &ast.items[0] {
+            TackyTopLevelAST::Function(function) => function.instructions.clone(),
+            item => panic!("expected a function, found {:?}", item),
+        }
+    }
+
+    fn program(body: Vec<BlockItemAST>) -> ProgramAST {
+        ProgramAST {
+            declarations: vec![ExternalDeclarationAST::Function(FunctionAST {
+                name: "main".to_string(),
+                params: vec![],
+                body: Some(BlockAST { items: body }),
+                function_type: Type::Function {
+                    params: vec![],
+                    ret: Box::new(Type::Int),
+                },
+                storage_class: None,
+                line: 1,
+            })],
+        }
+    }
+
+    #[test]
+    fn test_generate_tacky_program_ast() {
+        // SS: arrange
+        let parse_ast = program(vec![BlockItemAST::Statement(StmtAST::Return(
+            ExprAST::Unary(
+                UnaryOperatorAST::Negate,
+                Box::new(ExprAST::Unary(
+                    UnaryOperatorAST::Complement,
+                    Box::new(ExprAST::Constant(Constant::Int(2))),
+                )),
+            ),
+        ))]);
+
+        // SS: act
+        let mut symbols = function_symbols(&parse_ast);
+        let tacky_ast = generate_tacky_program_ast(
+            parse_ast,
+            &mut symbols,
+            &TypeTable::new(),
+            &mut NameGenerator::new(),
+        );
+
+        // SS: assert
+        assert_eq!(
+            tacky_ast,
+            TackyProgramAST {
+                items: vec![TackyTopLevelAST::Function(TackyFunctionAST {
+                    name: "main".to_string(),
+                    global: true,
+                    params: vec![],
+                    instructions: vec![
+                        TackyInstructionAST::Unary {
+                            op: TackyUnaryOperatorAST::Complement,
+                            src: TackyValueAST::Constant(Constant::Int(2)),
+                            dst: TackyValueAST::Var("tmp.0".to_string()),
+                        },
+                        TackyInstructionAST::Unary {
+                            op: TackyUnaryOperatorAST::Negate,
+                            src: TackyValueAST::Var("tmp.0".to_string()),
+                            dst: TackyValueAST::Var("tmp.1".to_string()),
+                        },
+                        TackyInstructionAST::Return(TackyValueAST::Var("tmp.1".to_string())),
+                        TackyInstructionAST::Return(TackyValueAST::Constant(Constant::Int(0))),
+                    ],
+                })]
+            }
+        );
+    }
+
+    #[test]
+    fn test_generate_tacky_program_ast_binary() {
+        // SS: arrange
+        let parse_ast = program(vec![BlockItemAST::Statement(StmtAST::Return(
+            ExprAST::Binary(
+                BinaryOperatorAST::Add,
+                Box::new(ExprAST::Constant(Constant::Int(1))),
+                Box::new(ExprAST::Binary(
+                    BinaryOperatorAST::Multiply,
+                    Box::new(ExprAST::Constant(Constant::Int(2))),
+                    Box::new(ExprAST::Constant(Constant::Int(3))),
+                )),
+            ),
+        ))]);
+
+        // SS: act
+        let mut symbols = function_symbols(&parse_ast);
+        let tacky_ast = generate_tacky_program_ast(
+            parse_ast,
+            &mut symbols,
+            &TypeTable::new(),
+            &mut NameGenerator::new(),
+        );
+
+        // SS: assert
+        assert_eq!(
+            instructions(&tacky_ast),
+            vec![
+                TackyInstructionAST::Binary {
+                    op: TackyBinaryOperatorAST::Multiply,
+                    src1: TackyValueAST::Constant(Constant::Int(2)),
+                    src2: TackyValueAST::Constant(Constant::Int(3)),
+                    dst: TackyValueAST::Var("tmp.0".to_string()),
+                },
+                TackyInstructionAST::Binary {
+                    op: TackyBinaryOperatorAST::Add,
+                    src1: TackyValueAST::Constant(Constant::Int(1)),
+                    src2: TackyValueAST::Var("tmp.0".to_string()),
+                    dst: TackyValueAST::Var("tmp.1".to_string()),
+                },
+                TackyInstructionAST::Return(TackyValueAST::Var("tmp.1".to_string())),
+                TackyInstructionAST::Return(TackyValueAST::Constant(Constant::Int(0))),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_generate_tacky_program_ast_short_circuit() {
+        // SS: arrange
+        let parse_ast = program(vec![BlockItemAST::Statement(StmtAST::Return(
+            ExprAST::Binary(
+                BinaryOperatorAST::And,
+                Box::new(ExprAST::Constant(Constant::Int(1))),
+                Box::new(ExprAST::Unary(
+                    UnaryOperatorAST::Negate,
+                    Box::new(ExprAST::Constant(Constant::Int(2))),
+                )),
+            ),
+        ))]);
+
+        // SS: act
+        let mut symbols = function_symbols(&parse_ast);
+        let tacky_ast = generate_tacky_program_ast(
+            parse_ast,
+            &mut symbols,
+            &TypeTable::new(),
+            &mut NameGenerator::new(),
+        );
+
+        // SS: assert
+        assert_eq!(
+            instructions(&tacky_ast),
+            vec![
+                TackyInstructionAST::JumpIfZero(
+                    TackyValueAST::Constant(Constant::Int(1)),
+                    "and_false.0".to_string()
+                ),
+                TackyInstructionAST::Unary {
+                    op: TackyUnaryOperatorAST::Negate,
+                    src: TackyValueAST::Constant(Constant::Int(2)),
+                    dst: TackyValueAST::Var("tmp.3".to_string()),
+                },
+                TackyInstructionAST::JumpIfZero(
+                    TackyValueAST::Var("tmp.3".to_string()),
+                    "and_false.0".to_string()
+                ),
+                TackyInstructionAST::Copy {
+                    src: TackyValueAST::Constant(Constant::Int(1)),
+                    dst: TackyValueAST::Var("tmp.2".to_string()),
+                },
+                TackyInstructionAST::Jump("and_end.1".to_string()),
+                TackyInstructionAST::Label("and_false.0".to_string()),
+                TackyInstructionAST::Copy {
+                    src: TackyValueAST::Constant(Constant::Int(0)),
+                    dst: TackyValueAST::Var("tmp.2".to_string()),
+                },
+                TackyInstructionAST::Label("and_end.1".to_string()),
+                TackyInstructionAST::Return(TackyValueAST::Var("tmp.2".to_string())),
+                TackyInstructionAST::Return(TackyValueAST::Constant(Constant::Int(0))),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_generate_tacky_program_ast_assignment() {
+        // SS: arrange
+        let var = |name: &str| ExprAST::Var {
+            name: name.to_string(),
+            line: 1,
+        };
+        let parse_ast = program(vec![
+            BlockItemAST::Declaration(DeclarationAST {
+                name: "a.0".to_string(),
+                init: Some(InitializerAST::Single(ExprAST::Constant(Constant::Int(1)))),
+                var_type: Type::Int,
+                storage_class: None,
+                line: 1,
+            }),
+            BlockItemAST::Statement(StmtAST::Expression(ExprAST::CompoundAssignment {
+                op: BinaryOperatorAST::Multiply,
+                lhs: Box::new(var("a.0")),
+                rhs: Box::new(ExprAST::Constant(Constant::Int(3))),
+                line: 1,
+            })),
+            BlockItemAST::Statement(StmtAST::Return(ExprAST::Increment {
+                op: IncrementOperatorAST::PostDecrement,
+                operand: Box::new(var("a.0")),
+                line: 1,
+            })),
+        ]);
+
+        // SS: act
+        let mut symbols = function_symbols(&parse_ast);
+        let tacky_ast = generate_tacky_program_ast(
+            parse_ast,
+            &mut symbols,
+            &TypeTable::new(),
+            &mut NameGenerator::new(),
+        );
+
+        // SS: assert
+        assert_eq!(
+            instructions(&tacky_ast),
+            vec![
+                TackyInstructionAST::Copy {
+                    src: TackyValueAST::Constant(Constant::Int(1)),
+                    dst: TackyValueAST::Var("a.0".to_string()),
+                },
+                TackyInstructionAST::Binary {
+                    op: TackyBinaryOperatorAST::Multiply,
+                    src1: TackyValueAST::Var("a.0".to_string()),
+                    src2: TackyValueAST::Constant(Constant::Int(3)),
+                    dst: TackyValueAST::Var("a.0".to_string()),
+                },
+                TackyInstructionAST::Copy {
+                    src: TackyValueAST::Var("a.0".to_string()),
+                    dst: TackyValueAST::Var("tmp.0".to_string()),
+                },
+                TackyInstructionAST::Binary {
+                    op: TackyBinaryOperatorAST::Subtract,
+                    src1: TackyValueAST::Var("a.0".to_string()),
+                    src2: TackyValueAST::Constant(Constant::Int(1)),
+                    dst: TackyValueAST::Var("a.0".to_string()),
+                },
+                TackyInstructionAST::Return(TackyValueAST::Var("tmp.0".to_string())),
+                TackyInstructionAST::Return(TackyValueAST::Constant(Constant::Int(0))),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_generate_tacky_program_ast_if_else() {
+        // SS: arrange
+        let parse_ast = program(vec![BlockItemAST::Statement(StmtAST::If {
+            condition: ExprAST::Constant(Constant::Int(1)),
+            then_stmt: Box::new(StmtAST::Return(ExprAST::Constant(Constant::Int(2)))),
+            else_stmt: Some(Box::new(StmtAST::Return(ExprAST::Constant(Constant::Int(
+                3,
+            ))))),
+        })]);
+
+        // SS: act
+        let mut symbols = function_symbols(&parse_ast);
+        let tacky_ast = generate_tacky_program_ast(
+            parse_ast,
+            &mut symbols,
+            &TypeTable::new(),
+            &mut NameGenerator::new(),
+        );
+
+        // SS: assert
+        assert_eq!(
+            instructions(&tacky_ast),
+            vec![
+                TackyInstructionAST::JumpIfZero(
+                    TackyValueAST::Constant(Constant::Int(1)),
+                    "if_else.0".to_string()
+                ),
+                TackyInstructionAST::Return(TackyValueAST::Constant(Constant::Int(2))),
+                TackyInstructionAST::Jump("if_end.1".to_string()),
+                TackyInstructionAST::Label("if_else.0".to_string()),
+                TackyInstructionAST::Return(TackyValueAST::Constant(Constant::Int(3))),
+                TackyInstructionAST::Label("if_end.1".to_string()),
+                TackyInstructionAST::Return(TackyValueAST::Constant(Constant::Int(0))),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_generate_tacky_program_ast_for_loop() {
+        // SS: arrange
+        let parse_ast = program(vec![BlockItemAST::Statement(StmtAST::For {
+            init: ForInitAST::InitExpr(None),
+            condition: Some(ExprAST::Var {
+                name: "a.0".to_string(),
+                line: 1,
+            }),
+            post: None,
+            body: Box::new(StmtAST::If {
+                condition: ExprAST::Constant(Constant::Int(1)),
+                then_stmt: Box::new(StmtAST::Break {
+                    label: Some("loop.0".to_string()),
+                    line: 1,
+                }),
+                else_stmt: Some(Box::new(StmtAST::Continue {
+                    label: Some("loop.0".to_string()),
+                    line: 1,
+                })),
+            }),
+            label: Some("loop.0".to_string()),
+        })]);
+
+        // SS: act
+        let mut symbols = function_symbols(&parse_ast);
+        let tacky_ast = generate_tacky_program_ast(
+            parse_ast,
+            &mut symbols,
+            &TypeTable::new(),
+            &mut NameGenerator::new(),
+        );
+
+        // SS: assert
+        assert_eq!(
+            instructions(&tacky_ast),
+            vec![
+                TackyInstructionAST::Label("start_loop.0".to_string()),
+                TackyInstructionAST::JumpIfZero(
+                    TackyValueAST::Var("a.0".to_string()),
+                    "break_loop.0".to_string()
+                ),
+                TackyInstructionAST::JumpIfZero(
+                    TackyValueAST::Constant(Constant::Int(1)),
+                    "if_else.0".to_string()
+                ),
+                TackyInstructionAST::Jump("break_loop.0".to_string()),
+                TackyInstructionAST::Jump("if_end.1".to_string()),
+                TackyInstructionAST::Label("if_else.0".to_string()),
+                TackyInstructionAST::Jump("continue_loop.0".to_string()),
+                TackyInstructionAST::Label("if_end.1".to_string()),
+                TackyInstructionAST::Label("continue_loop.0".to_string()),
+                TackyInstructionAST::Jump("start_loop.0".to_string()),
+                TackyInstructionAST::Label("break_loop.0".to_string()),
+                TackyInstructionAST::Return(TackyValueAST::Constant(Constant::Int(0))),
+            ]
+        );
+    }
+
+    fn switch(values: &[Option<i32>]) -> ProgramAST {
+        let cases = values
+            .iter()
+            .enumerate()
+            .map(|(index, value)| SwitchCaseAST {
+                value: value.map(Constant::Int),
+                label: format!("case.{}", index),
+            })
+            .collect::<Vec<_>>();
+        let items = cases
+            .iter()
+            .map(|case| {
+                BlockItemAST::Statement(match case.value {
+                    Some(value) => StmtAST::Case {
+                        value: ExprAST::Constant(value),
+                        body: Box::new(StmtAST::Null),
+                        label: Some(case.label.clone()),
+                        line: 1,
+                    },
+                    None => StmtAST::Default {
+                        body: Box::new(StmtAST::Null),
+                        label: Some(case.label.clone()),
+                        line: 1,
+                    },
+                })
+            })
+            .collect();
+        program(vec![BlockItemAST::Statement(StmtAST::Switch {
+            condition: ExprAST::Var {
+                name: "a.0".to_string(),
+                line: 1,
+            },
+            body: Box::new(StmtAST::Compound(BlockAST { items })),
+            label: Some("switch.0".to_string()),
+            cases,
+        })])
+    }
+
+    #[test]
+    fn test_generate_tacky_program_ast_switch_jump_table() {
+        // SS: arrange
+        let parse_ast = switch(&[Some(3), Some(1), Some(2), None, Some(5)]);
+
+        // SS: act
+        let mut symbols = function_symbols(&parse_ast);
+        let tacky_ast = generate_tacky_program_ast(
+            parse_ast,
+            &mut symbols,
+            &TypeTable::new(),
+            &mut NameGenerator::new(),
+        );
+
+        // SS: assert
+        let var = |name: &str| TackyValueAST::Var(name.to_string());
+        let labels = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+        assert_eq!(
+            instructions(&tacky_ast),
+            vec![
+                TackyInstructionAST::Copy {
+                    src: var("a.0"),
+                    dst: var("tmp.0"),
+                },
+                TackyInstructionAST::Binary {
+                    op: TackyBinaryOperatorAST::Subtract,
+                    src1: var("tmp.0"),
+                    src2: TackyValueAST::Constant(Constant::UInt(1)),
+                    dst: var("tmp.1"),
+                },
+                TackyInstructionAST::Binary {
+                    op: TackyBinaryOperatorAST::GreaterThan,
+                    src1: var("tmp.1"),
+                    src2: TackyValueAST::Constant(Constant::UInt(4)),
+                    dst: var("tmp.2"),
+                },
+                TackyInstructionAST::JumpIfNotZero(var("tmp.2"), "case.3".to_string()),
+                TackyInstructionAST::ZeroExtend {
+                    src: var("tmp.1"),
+                    dst: var("tmp.3"),
+                },
+                TackyInstructionAST::JumpTable {
+                    index: var("tmp.3"),
+                    targets: labels(&["case.1", "case.2", "case.0", "case.3", "case.4"]),
+                },
+                TackyInstructionAST::Label("case.0".to_string()),
+                TackyInstructionAST::Label("case.1".to_string()),
+                TackyInstructionAST::Label("case.2".to_string()),
+                TackyInstructionAST::Label("case.3".to_string()),
+                TackyInstructionAST::Label("case.4".to_string()),
+                TackyInstructionAST::Label("break_switch.0".to_string()),
+                TackyInstructionAST::Return(TackyValueAST::Constant(Constant::Int(0))),
+            ]
+        );
+        assert_eq!(symbols["tmp.0"].symbol_type, Type::UInt);
+        assert_eq!(symbols["tmp.3"].symbol_type, Type::ULong);
+    }
+
+    #[test]
+    fn test_generate_tacky_program_ast_switch_compare_chain() {
+        // SS: arrange
+        let parse_ast = switch(&[Some(1), Some(1000), Some(-7), Some(20)]);
+
+        // SS: act
+        let mut symbols = function_symbols(&parse_ast);
+        let tacky_ast = generate_tacky_program_ast(
+            parse_ast,
+            &mut symbols,
+            &TypeTable::new(),
+            &mut NameGenerator::new(),
+        );
+
+        // SS: assert
+        let compare = |value: i32, tmp: &str, label: &str| {
+            [
+                TackyInstructionAST::Binary {
+                    op: TackyBinaryOperatorAST::Equal,
+                    src1: TackyValueAST::Var("a.0".to_string()),
+                    src2: TackyValueAST::Constant(Constant::Int(value)),
+                    dst: TackyValueAST::Var(tmp.to_string()),
+                },
+                TackyInstructionAST::JumpIfNotZero(
+                    TackyValueAST::Var(tmp.to_string()),
+                    label.to_string(),
+                ),
+            ]
+        };
+        let mut expected = vec![];
+        expected.extend(compare(1, "tmp.0", "case.0"));
+        expected.extend(compare(1000, "tmp.1", "case.1"));
+        expected.extend(compare(-7, "tmp.2", "case.2"));
+        expected.extend(compare(20, "tmp.3", "case.3"));
+        expected.extend([
+            TackyInstructionAST::Jump("break_switch.0".to_string()),
+            TackyInstructionAST::Label("case.0".to_string()),
+            TackyInstructionAST::Label("case.1".to_string()),
+            TackyInstructionAST::Label("case.2".to_string()),
+            TackyInstructionAST::Label("case.3".to_string()),
+            TackyInstructionAST::Label("break_switch.0".to_string()),
+            TackyInstructionAST::Return(TackyValueAST::Constant(Constant::Int(0))),
+        ]);
+        assert_eq!(instructions(&tacky_ast), expected);
+    }
+
+    #[test]
+    fn test_generate_tacky_program_ast_function_call() {
+        // SS: arrange
+        let parse_ast = ProgramAST {
+            declarations: vec![
+                ExternalDeclarationAST::Function(FunctionAST {
+                    name: "add".to_string(),
+                    params: vec!["a".to_string(), "b".to_string()],
+                    body: None,
+                    function_type: Type::Function {
+                        params: vec![Type::Int, Type::Int],
+                        ret: Box::new(Type::Int),
+                    },
+                    storage_class: None,
+                    line: 1,
+                }),
+                ExternalDeclarationAST::Function(FunctionAST {
+                    name: "main".to_string(),
+                    params: vec![],
+                    body: Some(BlockAST {
+                        items: vec![BlockItemAST::Statement(StmtAST::Return(
+                            ExprAST::FunctionCall {
+                                name: "add".to_string(),
+                                args: vec![
+                                    ExprAST::Constant(Constant::Int(1)),
+                                    ExprAST::Unary(
+                                        UnaryOperatorAST::Negate,
+                                        Box::new(ExprAST::Constant(Constant::Int(2))),
+                                    ),
+                                ],
+                                line: 2,
+                            },
+                        ))],
+                    }),
+                    function_type: Type::Function {
+                        params: vec![],
+                        ret: Box::new(Type::Int),
+                    },
+                    storage_class: None,
+                    line: 2,
+                }),
+            ],
+        };
+
+        // SS: act
+        let mut symbols = function_symbols(&parse_ast);
+        let tacky_ast = generate_tacky_program_ast(
+            parse_ast,
+            &mut symbols,
+            &TypeTable::new(),
+            &mut NameGenerator::new(),
+        );
+
+        // SS: assert
+        assert_eq!(
+            tacky_ast,
+            TackyProgramAST {
+                items: vec![TackyTopLevelAST::Function(TackyFunctionAST {
+                    name: "main".to_string(),
+                    global: true,
+                    params: vec![],
+                    instructions: vec![
+                        TackyInstructionAST::Unary {
+                            op: TackyUnaryOperatorAST::Negate,
+                            src: TackyValueAST::Constant(Constant::Int(2)),
+                            dst: TackyValueAST::Var("tmp.0".to_string()),
+                        },
+                        TackyInstructionAST::FunctionCall {
+                            name: "add".to_string(),
+                            args: vec![
+                                TackyValueAST::Constant(Constant::Int(1)),
+                                TackyValueAST::Var("tmp.0".to_string()),
+                            ],
+                            dst: TackyValueAST::Var("tmp.1".to_string()),
+                        },
+                        TackyInstructionAST::Return(TackyValueAST::Var("tmp.1".to_string())),
+                        TackyInstructionAST::Return(TackyValueAST::Constant(Constant::Int(0))),
+                    ],
+                })]
+            }
+        );
+    }
+
+    #[test]
+    fn test_generate_tacky_program_ast_static_variables() {
+        // SS: arrange
+        let parse_ast = program(vec![BlockItemAST::Declaration(DeclarationAST {
+            name: "b.0".to_string(),
+            init: Some(InitializerAST::Single(ExprAST::Constant(Constant::Int(3)))),
+            var_type: Type::Int,
+            storage_class: Some(StorageClassAST::Static),
+            line: 1,
+        })]);
+        let mut symbols = function_symbols(&parse_ast);
+        let variables = [
+            ("a", InitialValue::Tentative, true),
+            (
+                "b.0",
+                InitialValue::Initial(vec![StaticInit::Value(Constant::Int(3))]),
+                false,
+            ),
+            ("c", InitialValue::NoInitializer, true),
+        ];
+        for (name, init, global) in variables {
+            symbols.insert(
+                name.to_string(),
+                Symbol {
+                    symbol_type: Type::Int,
+                    attrs: IdentifierAttrs::Static { init, global },
+                },
+            );
+        }
+
+        // SS: act
+        let tacky_ast = generate_tacky_program_ast(
+            parse_ast,
+            &mut symbols,
+            &TypeTable::new(),
+            &mut NameGenerator::new(),
+        );
+
+        // SS: assert
+        assert_eq!(
+            tacky_ast.items,
+            vec![
+                TackyTopLevelAST::Function(TackyFunctionAST {
+                    name: "main".to_string(),
+                    global: true,
+                    params: vec![],
+                    instructions: vec![TackyInstructionAST::Return(TackyValueAST::Constant(
+                        Constant::Int(0)
+                    ))],
+                }),
+                TackyTopLevelAST::StaticVariable {
+                    name: "a".to_string(),
+                    global: true,
+                    init: vec![StaticInit::Zero(4)],
+                },
+                TackyTopLevelAST::StaticVariable {
+                    name: "b.0".to_string(),
+                    global: false,
+                    init: vec![StaticInit::Value(Constant::Int(3))],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_generate_tacky_program_ast_casts() {
+        // SS: arrange
+        let var = |name: &str| ExprAST::Var {
+            name: name.to_string(),
+            line: 1,
+        };
+        let cast = |target_type: Type, expr: ExprAST| ExprAST::Cast {
+            target_type,
+            expr: Box::new(expr),
+            line: 1,
+        };
+        let parse_ast = program(vec![
+            BlockItemAST::Declaration(DeclarationAST {
+                name: "i.0".to_string(),
+                init: Some(InitializerAST::Single(ExprAST::Constant(Constant::Int(-1)))),
+                var_type: Type::Int,
+                storage_class: None,
+                line: 1,
+            }),
+            BlockItemAST::Declaration(DeclarationAST {
+                name: "l.1".to_string(),
+                init: Some(InitializerAST::Single(cast(Type::Long, var("i.0")))),
+                var_type: Type::Long,
+                storage_class: None,
+                line: 1,
+            }),
+            BlockItemAST::Statement(StmtAST::Expression(cast(
+                Type::ULong,
+                cast(Type::Double, cast(Type::UInt, var("l.1"))),
+            ))),
+            BlockItemAST::Statement(StmtAST::Return(cast(
+                Type::Int,
+                cast(Type::Long, cast(Type::UInt, var("i.0"))),
+            ))),
+        ]);
+
+        // SS: act
+        let mut symbols = function_symbols(&parse_ast);
+        let tacky_ast = generate_tacky_program_ast(
+            parse_ast,
+            &mut symbols,
+            &TypeTable::new(),
+            &mut NameGenerator::new(),
+        );
+
+        // SS: assert
+        let tmp = |n: usize| TackyValueAST::Var(format!("tmp.{}", n));
+        assert_eq!(
+            instructions(&tacky_ast),
+            vec![
+                TackyInstructionAST::Copy {
+                    src: TackyValueAST::Constant(Constant::Int(-1)),
+                    dst: TackyValueAST::Var("i.0".to_string()),
+                },
+                TackyInstructionAST::SignExtend {
+                    src: TackyValueAST::Var("i.0".to_string()),
+                    dst: tmp(0),
+                },
+                TackyInstructionAST::Copy {
+                    src: tmp(0),
+                    dst: TackyValueAST::Var("l.1".to_string()),
+                },
+                TackyInstructionAST::Truncate {
+                    src: TackyValueAST::Var("l.1".to_string()),
+                    dst: tmp(1),
+                },
+                TackyInstructionAST::UIntToDouble {
+                    src: tmp(1),
+                    dst: tmp(2),
+                },
+                TackyInstructionAST::DoubleToUInt {
+                    src: tmp(2),
+                    dst: tmp(3),
+                },
+                TackyInstructionAST::Copy {
+                    src: TackyValueAST::Var("i.0".to_string()),
+                    dst: tmp(4),
+                },
+                TackyInstructionAST::ZeroExtend {
+                    src: tmp(4),
+                    dst: tmp(5),
+                },
+                TackyInstructionAST::Truncate {
+                    src: tmp(5),
+                    dst: tmp(6),
+                },
+                TackyInstructionAST::Return(tmp(6)),
+                TackyInstructionAST::Return(TackyValueAST::Constant(Constant::Int(0))),
+            ]
+        );
+        let types = (0..=6)
+            .map(|n| symbols[&format!("tmp.{}", n)].symbol_type.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            types,
+            vec![
+                Type::Long,
+                Type::UInt,
+                Type::Double,
+                Type::ULong,
+                Type::UInt,
+                Type::Long,
+                Type::Int
+            ]
+        );
+    }
+
+    #[test]
+    fn test_generate_tacky_program_ast_compound_assignment_conversion() {
+        // SS: arrange
+        let parse_ast = program(vec![
+            BlockItemAST::Declaration(DeclarationAST {
+                name: "i.0".to_string(),
+                init: Some(InitializerAST::Single(ExprAST::Constant(Constant::Int(3)))),
+                var_type: Type::Int,
+                storage_class: None,
+                line: 1,
+            }),
+            BlockItemAST::Statement(StmtAST::Expression(ExprAST::CompoundAssignment {
+                op: BinaryOperatorAST::Multiply,
+                lhs: Box::new(ExprAST::Var {
+                    name: "i.0".to_string(),
+                    line: 1,
+                }),
+                rhs: Box::new(ExprAST::Constant(Constant::Double(2.5))),
+                line: 1,
+            })),
+        ]);
+
+        // SS: act
+        let mut symbols = function_symbols(&parse_ast);
+        let tacky_ast = generate_tacky_program_ast(
+            parse_ast,
+            &mut symbols,
+            &TypeTable::new(),
+            &mut NameGenerator::new(),
+        );
+
+        // SS: assert
+        let i = TackyValueAST::Var("i.0".to_string());
+        let tmp = |n: usize| TackyValueAST::Var(format!("tmp.{}", n));
+        assert_eq!(
+            instructions(&tacky_ast),
+            vec![
+                TackyInstructionAST::Copy {
+                    src: TackyValueAST::Constant(Constant::Int(3)),
+                    dst: i.clone(),
+                },
+                TackyInstructionAST::IntToDouble {
+                    src: i.clone(),
+                    dst: tmp(0),
+                },
+                TackyInstructionAST::Binary {
+                    op: TackyBinaryOperatorAST::Multiply,
+                    src1: tmp(0),
+                    src2: TackyValueAST::Constant(Constant::Double(2.5)),
+                    dst: tmp(1),
+                },
+                TackyInstructionAST::DoubleToInt {
+                    src: tmp(1),
+                    dst: tmp(2),
+                },
+                TackyInstructionAST::Copy {
+                    src: tmp(2),
+                    dst: i,
+                },
+                TackyInstructionAST::Return(TackyValueAST::Constant(Constant::Int(0))),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_generate_tacky_program_ast_arrays_and_pointers() {
+        // SS: arrange
+        let int_ptr = Type::Pointer(Box::new(Type::Int));
+        let array_type = Type::Array {
+            element: Box::new(Type::Int),
+            size: 2,
+        };
+        let var = |name: &str| ExprAST::Var {
+            name: name.to_string(),
+            line: 1,
+        };
+        let int = |val| ExprAST::Constant(Constant::Int(val));
+        let parse_ast = program(vec![
+            BlockItemAST::Declaration(DeclarationAST {
+                name: "a.0".to_string(),
+                init: Some(InitializerAST::Compound(vec![
+                    InitializerAST::Single(int(1)),
+                    InitializerAST::Single(int(2)),
+                ])),
+                var_type: array_type.clone(),
+                storage_class: None,
+                line: 1,
+            }),
+            // SS: int *p = a;
+            BlockItemAST::Declaration(DeclarationAST {
+                name: "p.1".to_string(),
+                init: Some(InitializerAST::Single(ExprAST::Cast {
+                    target_type: int_ptr.clone(),
+                    expr: Box::new(ExprAST::AddressOf {
+                        expr: Box::new(var("a.0")),
+                        line: 1,
+                    }),
+                    line: 1,
+                })),
+                var_type: int_ptr.clone(),
+                storage_class: None,
+                line: 1,
+            }),
+            // SS: *(p + 1L) = 5;
+            BlockItemAST::Statement(StmtAST::Expression(ExprAST::Assignment {
+                lhs: Box::new(ExprAST::Dereference {
+                    expr: Box::new(ExprAST::Binary(
+                        BinaryOperatorAST::Add,
+                        Box::new(var("p.1")),
+                        Box::new(ExprAST::Constant(Constant::Long(1))),
+                    )),
+                    line: 1,
+                }),
+                rhs: Box::new(int(5)),
+                line: 1,
+            })),
+            // SS: return *p;
+            BlockItemAST::Statement(StmtAST::Return(ExprAST::Dereference {
+                expr: Box::new(var("p.1")),
+                line: 1,
+            })),
+        ]);
+
+        // SS: act
+        let mut symbols = function_symbols(&parse_ast);
+        let tacky_ast = generate_tacky_program_ast(
+            parse_ast,
+            &mut symbols,
+            &TypeTable::new(),
+            &mut NameGenerator::new(),
+        );
+
+        // SS: assert
+        let p = TackyValueAST::Var("p.1".to_string());
+        let tmp = |n: usize| TackyValueAST::Var(format!("tmp.{}", n));
+        let constant = |val| TackyValueAST::Constant(Constant::Int(val));
+        assert_eq!(
+            instructions(&tacky_ast),
+            vec![
+                TackyInstructionAST::CopyToOffset {
+                    src: constant(1),
+                    dst: "a.0".to_string(),
+                    offset: 0,
+                },
+                TackyInstructionAST::CopyToOffset {
+                    src: constant(2),
+                    dst: "a.0".to_string(),
+                    offset: 4,
+                },
+                TackyInstructionAST::GetAddress {
+                    src: TackyValueAST::Var("a.0".to_string()),
+                    dst: tmp(0),
+                },
+                TackyInstructionAST::Copy {
+                    src: tmp(0),
+                    dst: tmp(1),
+                },
+                TackyInstructionAST::Copy {
+                    src: tmp(1),
+                    dst: p.clone(),
+                },
+                TackyInstructionAST::AddPtr {
+                    ptr: p.clone(),
+                    index: TackyValueAST::Constant(Constant::Long(1)),
+                    scale: 4,
+                    dst: tmp(2),
+                },
+                TackyInstructionAST::Store {
+                    src: constant(5),
+                    dst_ptr: tmp(2),
+                },
+                TackyInstructionAST::Load {
+                    src_ptr: p,
+                    dst: tmp(3),
+                },
+                TackyInstructionAST::Return(tmp(3)),
+                TackyInstructionAST::Return(constant(0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_generate_tacky_program_ast_structure_members() {
+        // SS: arrange
+        let structure = Type::Structure("s.0".to_string());
+        let var = |name: &str| ExprAST::Var {
+            name: name.to_string(),
+            line: 1,
+        };
+        let member = |structure: ExprAST, member: &str| ExprAST::Dot {
+            structure: Box::new(structure),
+            member: member.to_string(),
+            line: 1,
+        };
+        let parse_ast = program(vec![
+            BlockItemAST::Declaration(DeclarationAST {
+                name: "x.1".to_string(),
+                init: None,
+                var_type: structure.clone(),
+                storage_class: None,
+                line: 1,
+            }),
+            // SS: x.i = 3;
+            BlockItemAST::Statement(StmtAST::Expression(ExprAST::Assignment {
+                lhs: Box::new(member(var("x.1"), "i")),
+                rhs: Box::new(ExprAST::Constant(Constant::Int(3))),
+                line: 1,
+            })),
+            // SS: return (&x)->i;
+            BlockItemAST::Statement(StmtAST::Return(ExprAST::Arrow {
+                pointer: Box::new(ExprAST::AddressOf {
+                    expr: Box::new(var("x.1")),
+                    line: 1,
+                }),
+                member: "i".to_string(),
+                line: 1,
+            })),
+        ]);
+        let mut types = TypeTable::new();
+        types.insert(
+            "s.0".to_string(),
+            crate::type_table::StructEntry::new(
+                vec![("c".to_string(), Type::Char), ("i".to_string(), Type::Int)],
+                false,
+                &TypeTable::new(),
+            ),
+        );
+
+        // SS: act
+        let mut symbols = function_symbols(&parse_ast);
+        let tacky_ast =
+            generate_tacky_program_ast(parse_ast, &mut symbols, &types, &mut NameGenerator::new());
+
+        // SS: assert
+        let tmp = |n: usize| TackyValueAST::Var(format!("tmp.{}", n));
+        assert_eq!(
+            instructions(&tacky_ast),
+            vec![
+                TackyInstructionAST::CopyToOffset {
+                    src: TackyValueAST::Constant(Constant::Int(3)),
+                    dst: "x.1".to_string(),
+                    offset: 4,
+                },
+                TackyInstructionAST::GetAddress {
+                    src: TackyValueAST::Var("x.1".to_string()),
+                    dst: tmp(0),
+                },
+                TackyInstructionAST::AddPtr {
+                    ptr: tmp(0),
+                    index: TackyValueAST::Constant(Constant::Long(4)),
+                    scale: 1,
+                    dst: tmp(1),
+                },
+                TackyInstructionAST::Load {
+                    src_ptr: tmp(1),
+                    dst: tmp(2),
+                },
+                TackyInstructionAST::Return(tmp(2)),
+                TackyInstructionAST::Return(TackyValueAST::Constant(Constant::Int(0))),
+            ]
+        );
+    }
+}
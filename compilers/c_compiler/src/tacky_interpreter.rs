@@ -0,0 +1,560 @@
+use crate::constant_folding::{fold_binary, fold_unary, is_zero};
+use crate::optimization::value_type;
+use crate::symbol_table::SymbolTable;
+use crate::tacky_ast::{
+    TackyBinaryOperatorAST, TackyFunctionAST, TackyInstructionAST, TackyProgramAST,
+    TackyTopLevelAST, TackyValueAST,
+};
+use crate::type_table::TypeTable;
+use crate::types::{Constant, StaticInit, Type};
+use std::collections::HashMap;
+use std::io::Write;
+
+// SS: addresses below this are never handed out, so that a null pointer dereference fails
+// instead of reading the first object
+const NULL_GUARD: usize = 4096;
+
+// SS: limits on the simulated stack, a program going beyond them has run away
+const MAX_MEMORY: usize = 64 * 1024 * 1024;
+const MAX_CALL_DEPTH: usize = 10_000;
+
+// SS: runs the program by executing its TACKY directly, starting from main. Every object,
+// temporaries included, lives in a single simulated memory, statics at the bottom and the
+// stack frames of the calls above them. The only library functions are putchar, which
+// writes to output, and exit. Returns the exit code of the program, or a description of
+// what went wrong at run time.
+pub fn interpret_program(
+    program: &TackyProgramAST,
+    symbols: &SymbolTable,
+    types: &TypeTable,
+    output: &mut dyn Write,
+) -> Result<i32, String> {
+    let functions = program
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            TackyTopLevelAST::Function(function) => {
+                Some((function.name.as_str(), Function::new(function)))
+            }
+            _ => None,
+        })
+        .collect();
+    let mut interpreter = Interpreter {
+        symbols,
+        types,
+        output,
+        functions: &functions,
+        statics: HashMap::new(),
+        frames: vec![],
+        memory: vec![0; NULL_GUARD],
+    };
+    interpreter.load_statics(program)?;
+
+    let result = match interpreter.call("main", vec![]) {
+        Ok(bytes) => Ok(decode(&bytes, &Type::Int).bits() as i32),
+        Err(Stop::Exit(code)) => Ok(code),
+        Err(Stop::Error(message)) => Err(message),
+    };
+    interpreter
+        .output
+        .flush()
+        .map_err(|err| format!("Could not write output: {}", err))?;
+    result
+}
+
+// SS: why execution stopped before main returned
+enum Stop {
+    Exit(i32),
+    Error(String),
+}
+
+impl From<String> for Stop {
+    fn from(message: String) -> Self {
+        Stop::Error(message)
+    }
+}
+
+struct Function<'a> {
+    definition: &'a TackyFunctionAST,
+    // SS: the index of the instruction following each label
+    labels: HashMap<&'a str, usize>,
+}
+
+impl<'a> Function<'a> {
+    fn new(definition: &'a TackyFunctionAST) -> Self {
+        let labels = definition
+            .instructions
+            .iter()
+            .enumerate()
+            .filter_map(|(index, instruction)| match instruction {
+                TackyInstructionAST::Label(label) => Some((label.as_str(), index + 1)),
+                _ => None,
+            })
+            .collect();
+        Function { definition, labels }
+    }
+}
+
+// SS: the local variables of a call, allocated on first use. Everything above base is
+// released when the call returns.
+struct Frame {
+    base: usize,
+    variables: HashMap<String, usize>,
+}
+
+struct Interpreter<'a> {
+    symbols: &'a SymbolTable,
+    types: &'a TypeTable,
+    output: &'a mut dyn Write,
+    functions: &'a HashMap<&'a str, Function<'a>>,
+    statics: HashMap<&'a str, usize>,
+    frames: Vec<Frame>,
+    memory: Vec<u8>,
+}
+
+impl<'a> Interpreter<'a> {
+    // SS: lays out every static object first, so that an initializer can take the address
+    // of one defined after it, then writes the initial values
+    fn load_statics(&mut self, program: &'a TackyProgramAST) -> Result<(), String> {
+        for item in &program.items {
+            match item {
+                TackyTopLevelAST::Function(_) => {}
+                TackyTopLevelAST::StaticVariable { name, .. } => {
+                    let address = self.allocate(&self.symbols[name].symbol_type.clone())?;
+                    self.statics.insert(name, address);
+                }
+                TackyTopLevelAST::StaticConstant { name, init } => {
+                    let address = self.allocate_bytes(init.size() as usize, 1)?;
+                    self.statics.insert(name, address);
+                }
+            }
+        }
+
+        for item in &program.items {
+            let (name, init) = match item {
+                TackyTopLevelAST::StaticVariable { name, init, .. } => (name, init.as_slice()),
+                TackyTopLevelAST::StaticConstant { name, init } => {
+                    (name, std::slice::from_ref(init))
+                }
+                TackyTopLevelAST::Function(_) => continue,
+            };
+            let mut address = self.statics[name.as_str()];
+            for init in init {
+                let bytes = match init {
+                    StaticInit::Value(c) => encode(*c),
+                    StaticInit::Zero(size) => vec![0; *size as usize],
+                    StaticInit::String {
+                        bytes,
+                        null_terminated,
+                    } => {
+                        let mut bytes = bytes.clone();
+                        if *null_terminated {
+                            bytes.push(0);
+                        }
+                        bytes
+                    }
+                    StaticInit::Pointer(target) => {
+                        let target = self.static_address(target)?;
+                        encode(Constant::ULong(target as u64))
+                    }
+                };
+                self.write(address, &bytes)?;
+                address += bytes.len();
+            }
+        }
+        Ok(())
+    }
+
+    fn allocate(&mut self, object_type: &Type) -> Result<usize, String> {
+        let size = object_type.size(self.types) as usize;
+        let alignment = object_type.variable_alignment(self.types) as usize;
+        self.allocate_bytes(size, alignment)
+    }
+
+    fn allocate_bytes(&mut self, size: usize, alignment: usize) -> Result<usize, String> {
+        let address = self.memory.len().next_multiple_of(alignment.max(1));
+        if address + size > MAX_MEMORY {
+            return Err("Stack overflow".to_string());
+        }
+        self.memory.resize(address + size, 0);
+        Ok(address)
+    }
+
+    fn static_address(&self, name: &str) -> Result<usize, String> {
+        self.statics
+            .get(name)
+            .copied()
+            .ok_or_else(|| format!("Undefined reference to \"{}\"", name))
+    }
+
+    // SS: the address of a variable, a local one is allocated in the current frame the first
+    // time it is used
+    fn address(&mut self, name: &str) -> Result<usize, String> {
+        if self.symbols[name].is_static() {
+            return self.static_address(name);
+        }
+        if let Some(address) = self.frames.last().unwrap().variables.get(name) {
+            return Ok(*address);
+        }
+        let address = self.allocate(&self.symbols[name].symbol_type.clone())?;
+        self.frames
+            .last_mut()
+            .unwrap()
+            .variables
+            .insert(name.to_string(), address);
+        Ok(address)
+    }
+
+    fn read(&self, address: usize, size: usize) -> Result<&[u8], String> {
+        address
+            .checked_add(size)
+            .and_then(|end| self.memory.get(address..end))
+            .filter(|_| address >= NULL_GUARD)
+            .ok_or_else(|| format!("Invalid read of {} bytes at address {:#x}", size, address))
+    }
+
+    fn write(&mut self, address: usize, bytes: &[u8]) -> Result<(), String> {
+        address
+            .checked_add(bytes.len())
+            .and_then(|end| self.memory.get_mut(address..end))
+            .filter(|_| address >= NULL_GUARD)
+            .ok_or_else(|| {
+                format!(
+                    "Invalid write of {} bytes at address {:#x}",
+                    bytes.len(),
+                    address
+                )
+            })?
+            .copy_from_slice(bytes);
+        Ok(())
+    }
+
+    // SS: the bytes of a value, an aggregate variable is copied as a whole
+    fn bytes(&mut self, value: &TackyValueAST) -> Result<Vec<u8>, String> {
+        match value {
+            TackyValueAST::Constant(c) => Ok(encode(*c)),
+            TackyValueAST::Var(name) => {
+                let address = self.address(name)?;
+                let size = self.symbols[name].symbol_type.size(self.types) as usize;
+                Ok(self.read(address, size)?.to_vec())
+            }
+        }
+    }
+
+    fn scalar(&mut self, value: &TackyValueAST) -> Result<Constant, String> {
+        match value {
+            TackyValueAST::Constant(c) => Ok(*c),
+            TackyValueAST::Var(name) => {
+                let bytes = self.bytes(value)?;
+                Ok(decode(&bytes, &self.symbols[name].symbol_type))
+            }
+        }
+    }
+
+    fn pointer(&mut self, value: &TackyValueAST) -> Result<usize, String> {
+        Ok(self.scalar(value)?.bits() as usize)
+    }
+
+    // SS: a scalar is converted to the type of the destination, the result of a comparison
+    // is an int even where the destination is not
+    fn assign(&mut self, dst: &TackyValueAST, c: Constant) -> Result<(), String> {
+        let dst_type = value_type(dst, self.symbols);
+        self.assign_bytes(dst, &encode(c.convert_to(&dst_type)))
+    }
+
+    fn assign_bytes(&mut self, dst: &TackyValueAST, bytes: &[u8]) -> Result<(), String> {
+        let TackyValueAST::Var(name) = dst else {
+            unreachable!("cannot assign to the constant {}", dst);
+        };
+        let address = self.address(name)?;
+        self.write(address, bytes)
+    }
+
+    // SS: calls a function with the bytes of its arguments, returning the bytes of its
+    // result
+    fn call(&mut self, name: &str, args: Vec<Vec<u8>>) -> Result<Vec<u8>, Stop> {
+        let Some(function) = self.functions.get(name) else {
+            return self.call_builtin(name, &args);
+        };
+        let definition = function.definition;
+        if self.frames.len() >= MAX_CALL_DEPTH {
+            return Err(Stop::Error("Stack overflow".to_string()));
+        }
+
+        self.frames.push(Frame {
+            base: self.memory.len(),
+            variables: HashMap::new(),
+        });
+        for (param, arg) in definition.params.iter().zip(&args) {
+            let address = self.address(param)?;
+            self.write(address, arg)?;
+        }
+        let result = self.execute(name);
+        let frame = self.frames.pop().unwrap();
+        self.memory.truncate(frame.base);
+        result
+    }
+
+    fn call_builtin(&mut self, name: &str, args: &[Vec<u8>]) -> Result<Vec<u8>, Stop> {
+        let int_arg = || decode(args.first().map_or(&[][..], Vec::as_slice), &Type::Int).bits();
+        match name {
+            "putchar" => {
+                let c = int_arg() as u8;
+                self.output
+                    .write_all(&[c])
+                    .map_err(|err| format!("Could not write output: {}", err))?;
+                Ok(encode(Constant::Int(i32::from(c))))
+            }
+            "exit" => Err(Stop::Exit(int_arg() as i32)),
+            _ => Err(Stop::Error(format!(
+                "Call to \"{}\", which is neither defined nor built in",
+                name
+            ))),
+        }
+    }
+
+    fn execute(&mut self, name: &str) -> Result<Vec<u8>, Stop> {
+        let functions = self.functions;
+        let function = &functions[name];
+        let (instructions, labels) = (&function.definition.instructions, &function.labels);
+        let mut index = 0;
+        while let Some(instruction) = instructions.get(index) {
+            index += 1;
+            match instruction {
+                TackyInstructionAST::Return(value) => return Ok(self.bytes(value)?),
+                TackyInstructionAST::Unary { op, src, dst } => {
+                    let c = self.scalar(src)?;
+                    let result = fold_unary(*op, c)
+                        .ok_or_else(|| format!("Cannot apply {:?} to {}", op, c))?;
+                    self.assign(dst, result)?;
+                }
+                TackyInstructionAST::Binary {
+                    op,
+                    src1,
+                    src2,
+                    dst,
+                } => {
+                    let c1 = self.scalar(src1)?;
+                    let c2 = self.scalar(src2)?.convert_to(&c1.constant_type());
+                    let result = fold_binary(*op, c1, c2).ok_or_else(|| match op {
+                        TackyBinaryOperatorAST::Divide | TackyBinaryOperatorAST::Remainder => {
+                            "Division by zero".to_string()
+                        }
+                        _ => format!("Cannot apply {:?} to {} and {}", op, c1, c2),
+                    })?;
+                    self.assign(dst, result)?;
+                }
+                TackyInstructionAST::Copy { src, dst } => {
+                    if value_type(dst, self.symbols).is_scalar() {
+                        let c = self.scalar(src)?;
+                        self.assign(dst, c)?;
+                    } else {
+                        let bytes = self.bytes(src)?;
+                        self.assign_bytes(dst, &bytes)?;
+                    }
+                }
+                TackyInstructionAST::SignExtend { src, dst }
+                | TackyInstructionAST::Truncate { src, dst }
+                | TackyInstructionAST::ZeroExtend { src, dst }
+                | TackyInstructionAST::DoubleToInt { src, dst }
+                | TackyInstructionAST::DoubleToUInt { src, dst }
+                | TackyInstructionAST::IntToDouble { src, dst }
+                | TackyInstructionAST::UIntToDouble { src, dst } => {
+                    // SS: the type of src decides whether it is sign or zero extended
+                    let c = self.scalar(src)?;
+                    self.assign(dst, c)?;
+                }
+                TackyInstructionAST::GetAddress { src, dst } => {
+                    let TackyValueAST::Var(name) = src else {
+                        unreachable!("cannot take the address of the constant {}", src);
+                    };
+                    let address = self.address(name)?;
+                    self.assign(dst, Constant::ULong(address as u64))?;
+                }
+                TackyInstructionAST::Load { src_ptr, dst } => {
+                    let address = self.pointer(src_ptr)?;
+                    let size = value_type(dst, self.symbols).size(self.types) as usize;
+                    let bytes = self.read(address, size)?.to_vec();
+                    self.assign_bytes(dst, &bytes)?;
+                }
+                TackyInstructionAST::Store { src, dst_ptr } => {
+                    let address = self.pointer(dst_ptr)?;
+                    let bytes = self.bytes(src)?;
+                    self.write(address, &bytes)?;
+                }
+                TackyInstructionAST::AddPtr {
+                    ptr,
+                    index,
+                    scale,
+                    dst,
+                } => {
+                    let address = self.scalar(ptr)?.bits();
+                    let index = self.scalar(index)?.bits();
+                    let address = address.wrapping_add(index.wrapping_mul(*scale));
+                    self.assign(dst, Constant::ULong(address as u64))?;
+                }
+                TackyInstructionAST::CopyToOffset { src, dst, offset } => {
+                    let address = self.address(dst)? + *offset as usize;
+                    let bytes = self.bytes(src)?;
+                    self.write(address, &bytes)?;
+                }
+                TackyInstructionAST::CopyFromOffset { src, offset, dst } => {
+                    let address = self.address(src)? + *offset as usize;
+                    let size = value_type(dst, self.symbols).size(self.types) as usize;
+                    let bytes = self.read(address, size)?.to_vec();
+                    self.assign_bytes(dst, &bytes)?;
+                }
+                TackyInstructionAST::Jump(target) => index = labels[target.as_str()],
+                TackyInstructionAST::JumpIfZero(condition, target) => {
+                    if is_zero(&self.scalar(condition)?) {
+                        index = labels[target.as_str()];
+                    }
+                }
+                TackyInstructionAST::JumpIfNotZero(condition, target) => {
+                    if !is_zero(&self.scalar(condition)?) {
+                        index = labels[target.as_str()];
+                    }
+                }
+                TackyInstructionAST::JumpTable {
+                    index: position,
+                    targets,
+                } => {
+                    let position = self.scalar(position)?.bits() as u64;
+                    let target = usize::try_from(position)
+                        .ok()
+                        .and_then(|position| targets.get(position))
+                        .ok_or_else(|| format!("Jump table index {} out of bounds", position))?;
+                    index = labels[target.as_str()];
+                }
+                TackyInstructionAST::Label(_) => {}
+                TackyInstructionAST::FunctionCall {
+                    name: callee,
+                    args,
+                    dst,
+                } => {
+                    let args = args
+                        .iter()
+                        .map(|arg| self.bytes(arg))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    let result = self.call(callee, args)?;
+                    let size = value_type(dst, self.symbols).size(self.types) as usize;
+                    self.assign_bytes(dst, &result[..size.min(result.len())])?;
+                }
+            }
+        }
+        Err(Stop::Error(format!(
+            "Function \"{}\" ran past its end",
+            name
+        )))
+    }
+}
+
+// SS: constants are stored in little-endian order, in as many bytes as their type takes
+fn encode(c: Constant) -> Vec<u8> {
+    c.bits().to_le_bytes()[..c.size() as usize].to_vec()
+}
+
+fn decode(bytes: &[u8], value_type: &Type) -> Constant {
+    let mut buffer = [0; 8];
+    let len = bytes.len().min(8);
+    buffer[..len].copy_from_slice(&bytes[..len]);
+    let bits = u64::from_le_bytes(buffer);
+    match value_type {
+        Type::Char | Type::SChar => Constant::Char(bits as i8),
+        Type::UChar => Constant::UChar(bits as u8),
+        Type::Int => Constant::Int(bits as i32),
+        Type::UInt => Constant::UInt(bits as u32),
+        Type::Long => Constant::Long(bits as i64),
+        Type::ULong | Type::Pointer(_) => Constant::ULong(bits),
+        Type::Double => Constant::Double(f64::from_bits(bits)),
+        _ => unreachable!("{:?} is not a scalar type", value_type),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::label_validation::validate_labels;
+    use crate::lexer::Lexer;
+    use crate::loop_labeling::label_loops;
+    use crate::name_generator::NameGenerator;
+    use crate::parser::Parser;
+    use crate::semantic_analysis::resolve_program;
+    use crate::tacky_generation::generate_tacky_program_ast;
+    use crate::tacky_interpreter::interpret_program;
+    use crate::type_checking::typecheck_program;
+
+    // SS: compiles the source to TACKY and runs it, returning the result and the output
+    fn interpret(source: &str) -> (Result<i32, String>, String) {
+        let ast = Parser::new(Lexer::new(source.to_string())).parse().unwrap();
+        let mut name_generator = NameGenerator::new();
+        let (ast, mut symbols, types) = resolve_program(ast, &mut name_generator)
+            .and_then(|ast| label_loops(ast, &mut name_generator))
+            .and_then(|ast| validate_labels(ast, &mut name_generator))
+            .and_then(|ast| typecheck_program(ast, &mut name_generator))
+            .unwrap();
+        let tacky_ast = generate_tacky_program_ast(ast, &mut symbols, &types, &mut name_generator);
+        let mut output = vec![];
+        let result = interpret_program(&tacky_ast, &symbols, &types, &mut output);
+        (result, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn test_interpret_program() {
+        // SS: arrange
+        let source = r#"
+int putchar(int c);
+struct pair { char tag; double value; };
+static char *greeting = "hi";
+long counter;
+
+int fib(int n) { return n < 2 ? n : fib(n - 1) + fib(n - 2); }
+struct pair make(char tag, double value) { struct pair p = {tag, value}; return p; }
+
+int main(void) {
+    int squares[4];
+    for (int i = 0; i < 4; i = i + 1) squares[i] = i * i;
+    int *p = squares + 3;
+    for (char *s = greeting; *s; s = s + 1) putchar(*s);
+    putchar('\n');
+    struct pair pair = make('x', 2.5);
+    unsigned int wrapped = 0u - 1u;
+    counter = counter + (wrapped > 0) + (long)(pair.value * 2);
+    return fib(10) + *p + pair.tag - 'x' + counter;
+}
+"#;
+
+        // SS: act
+        let (result, output) = interpret(source);
+
+        // SS: assert
+        assert_eq!(result, Ok(55 + 9 + 6));
+        assert_eq!(output, "hi\n");
+    }
+
+    #[test]
+    fn test_interpret_exit_and_runtime_errors() {
+        // SS: arrange
+        let exits = "int putchar(int c);
+int exit(int status);
+int stop(int code) { putchar('a'); exit(code); putchar('b'); return 0; }
+int main(void) { return stop(7) + 1; }";
+        let divides_by_zero = "int main(void) { int zero = 0; return 1 / zero; }";
+        let dereferences_null = "int main(void) { int *p = 0; return *p; }";
+        let calls_library = "int puts(char *s);\nint main(void) { return puts(\"x\"); }";
+
+        // SS: act
+        let results = [exits, divides_by_zero, dereferences_null, calls_library].map(interpret);
+
+        // SS: assert
+        assert_eq!(results[0], (Ok(7), "a".to_string()));
+        assert_eq!(results[1].0, Err("Division by zero".to_string()));
+        assert_eq!(
+            results[2].0,
+            Err("Invalid read of 4 bytes at address 0x0".to_string())
+        );
+        assert_eq!(
+            results[3].0,
+            Err("Call to \"puts\", which is neither defined nor built in".to_string())
+        );
+    }
+}
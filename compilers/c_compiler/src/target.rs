@@ -0,0 +1,57 @@
+use clap::ValueEnum;
+
+// SS: the platforms we can emit assembly for, they differ only in assembler conventions
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Target {
+    #[value(name = "x86_64-linux")]
+    Linux,
+    #[value(name = "x86_64-macos")]
+    MacOs,
+}
+
+impl Target {
+    pub fn host() -> Self {
+        if cfg!(target_os = "macos") {
+            Target::MacOs
+        } else {
+            Target::Linux
+        }
+    }
+
+    // SS: Mach-O prefixes C symbols with an underscore, ELF does not
+    pub fn symbol_name(&self, name: &str) -> String {
+        match self {
+            Target::Linux => name.to_string(),
+            Target::MacOs => format!("_{}", name),
+        }
+    }
+
+    // SS: assembler-local labels do not end up in the object's symbol table
+    pub fn local_label(&self, name: &str) -> String {
+        match self {
+            Target::Linux => format!(".L{}", name),
+            Target::MacOs => format!("L{}", name),
+        }
+    }
+
+    // SS: where #include looks after the directories given with -I. Multiarch Linux keeps
+    // the headers that differ between architectures in a directory of their own.
+    pub fn system_include_dirs(&self) -> &'static [&'static str] {
+        match self {
+            Target::Linux => &[
+                "/usr/local/include",
+                "/usr/include/x86_64-linux-gnu",
+                "/usr/include",
+            ],
+            Target::MacOs => &["/usr/local/include", "/usr/include"],
+        }
+    }
+
+    // SS: the macros system headers test to pick the definitions for the platform
+    pub fn predefined_macros(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Target::Linux => &[("__x86_64__", "1"), ("__LP64__", "1"), ("__linux__", "1")],
+            Target::MacOs => &[("__x86_64__", "1"), ("__LP64__", "1"), ("__APPLE__", "1")],
+        }
+    }
+}
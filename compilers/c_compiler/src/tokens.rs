@@ -1,32 +1,327 @@
-#[derive(Debug, Clone, PartialEq, Eq)]
+use std::fmt::Display;
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Tokens {
     Identifier(String),
     Constant(i64),
+    LongConstant(i64),
+    UnsignedConstant(u64),
+    UnsignedLongConstant(u64),
+    DoubleConstant(f64),
+    // SS: the value of a character constant is that of the char converted to int
+    CharConstant(i64),
+    // SS: the bytes of a string literal after escape sequences are replaced, without a
+    // terminating null
+    StringLiteral(Vec<u8>),
+    Auto,
+    Break,
+    Case,
+    Char,
+    Const,
+    Continue,
+    Default,
+    Do,
+    Double,
+    Else,
+    Enum,
+    Extern,
+    Float,
+    For,
+    Goto,
+    If,
+    Inline,
     Int,
-    Void,
+    Long,
+    Register,
+    Restrict,
     Return,
+    Short,
+    Signed,
+    Sizeof,
+    Static,
+    Struct,
+    Switch,
+    Typedef,
+    Union,
+    Unsigned,
+    Void,
+    Volatile,
+    While,
+    Alignas,
+    Alignof,
+    Atomic,
+    Bool,
+    Complex,
+    Generic,
+    Imaginary,
+    Noreturn,
+    StaticAssert,
+    ThreadLocal,
     OpenParen,
     CloseParen,
     OpenBrace,
     CloseBrace,
+    OpenBracket,
+    CloseBracket,
     Semicolon,
+    Comma,
+    Dot,
+    Arrow,
+    Ellipsis,
+    Tilde,
+    Minus,
+    Decrement,
+    Bang,
+    Plus,
+    Star,
+    Slash,
+    Percent,
+    Ampersand,
+    Pipe,
+    Caret,
+    LessLess,
+    GreaterGreater,
+    AmpersandAmpersand,
+    PipePipe,
+    EqualEqual,
+    BangEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Equal,
+    PlusEqual,
+    MinusEqual,
+    StarEqual,
+    SlashEqual,
+    PercentEqual,
+    AmpersandEqual,
+    PipeEqual,
+    CaretEqual,
+    LessLessEqual,
+    GreaterGreaterEqual,
+    Increment,
+    Question,
+    Colon,
+    Hash,
+    HashHash,
     EOF,
 }
 
+// SS: every C17 keyword with its spelling
+pub const KEYWORDS: &[(&str, Tokens)] = &[
+    ("auto", Tokens::Auto),
+    ("break", Tokens::Break),
+    ("case", Tokens::Case),
+    ("char", Tokens::Char),
+    ("const", Tokens::Const),
+    ("continue", Tokens::Continue),
+    ("default", Tokens::Default),
+    ("do", Tokens::Do),
+    ("double", Tokens::Double),
+    ("else", Tokens::Else),
+    ("enum", Tokens::Enum),
+    ("extern", Tokens::Extern),
+    ("float", Tokens::Float),
+    ("for", Tokens::For),
+    ("goto", Tokens::Goto),
+    ("if", Tokens::If),
+    ("inline", Tokens::Inline),
+    ("int", Tokens::Int),
+    ("long", Tokens::Long),
+    ("register", Tokens::Register),
+    ("restrict", Tokens::Restrict),
+    ("return", Tokens::Return),
+    ("short", Tokens::Short),
+    ("signed", Tokens::Signed),
+    ("sizeof", Tokens::Sizeof),
+    ("static", Tokens::Static),
+    ("struct", Tokens::Struct),
+    ("switch", Tokens::Switch),
+    ("typedef", Tokens::Typedef),
+    ("union", Tokens::Union),
+    ("unsigned", Tokens::Unsigned),
+    ("void", Tokens::Void),
+    ("volatile", Tokens::Volatile),
+    ("while", Tokens::While),
+    ("_Alignas", Tokens::Alignas),
+    ("_Alignof", Tokens::Alignof),
+    ("_Atomic", Tokens::Atomic),
+    ("_Bool", Tokens::Bool),
+    ("_Complex", Tokens::Complex),
+    ("_Generic", Tokens::Generic),
+    ("_Imaginary", Tokens::Imaginary),
+    ("_Noreturn", Tokens::Noreturn),
+    ("_Static_assert", Tokens::StaticAssert),
+    ("_Thread_local", Tokens::ThreadLocal),
+];
+
+// SS: every C17 punctuator with its spelling, the digraphs come last so that looking up
+// the spelling of a token finds the ordinary form
+pub const PUNCTUATORS: &[(&str, Tokens)] = &[
+    ("(", Tokens::OpenParen),
+    (")", Tokens::CloseParen),
+    ("{", Tokens::OpenBrace),
+    ("}", Tokens::CloseBrace),
+    ("[", Tokens::OpenBracket),
+    ("]", Tokens::CloseBracket),
+    (";", Tokens::Semicolon),
+    (",", Tokens::Comma),
+    (".", Tokens::Dot),
+    ("->", Tokens::Arrow),
+    ("...", Tokens::Ellipsis),
+    ("~", Tokens::Tilde),
+    ("-", Tokens::Minus),
+    ("--", Tokens::Decrement),
+    ("!", Tokens::Bang),
+    ("+", Tokens::Plus),
+    ("*", Tokens::Star),
+    ("/", Tokens::Slash),
+    ("%", Tokens::Percent),
+    ("&", Tokens::Ampersand),
+    ("|", Tokens::Pipe),
+    ("^", Tokens::Caret),
+    ("<<", Tokens::LessLess),
+    (">>", Tokens::GreaterGreater),
+    ("&&", Tokens::AmpersandAmpersand),
+    ("||", Tokens::PipePipe),
+    ("==", Tokens::EqualEqual),
+    ("!=", Tokens::BangEqual),
+    ("<", Tokens::Less),
+    ("<=", Tokens::LessEqual),
+    (">", Tokens::Greater),
+    (">=", Tokens::GreaterEqual),
+    ("=", Tokens::Equal),
+    ("+=", Tokens::PlusEqual),
+    ("-=", Tokens::MinusEqual),
+    ("*=", Tokens::StarEqual),
+    ("/=", Tokens::SlashEqual),
+    ("%=", Tokens::PercentEqual),
+    ("&=", Tokens::AmpersandEqual),
+    ("|=", Tokens::PipeEqual),
+    ("^=", Tokens::CaretEqual),
+    ("<<=", Tokens::LessLessEqual),
+    (">>=", Tokens::GreaterGreaterEqual),
+    ("++", Tokens::Increment),
+    ("?", Tokens::Question),
+    (":", Tokens::Colon),
+    ("#", Tokens::Hash),
+    ("##", Tokens::HashHash),
+    ("<:", Tokens::OpenBracket),
+    (":>", Tokens::CloseBracket),
+    ("<%", Tokens::OpenBrace),
+    ("%>", Tokens::CloseBrace),
+    ("%:", Tokens::Hash),
+    ("%:%:", Tokens::HashHash),
+];
+
 impl Tokens {
-    pub fn to_string(&self) -> String {
+    // SS: the C source text of the token, lexing it gives back the same token
+    pub fn spelling(&self) -> String {
+        match self {
+            Tokens::Identifier(name) => name.clone(),
+            Tokens::Constant(val) => val.to_string(),
+            Tokens::LongConstant(val) => format!("{}l", val),
+            Tokens::UnsignedConstant(val) => format!("{}u", val),
+            Tokens::UnsignedLongConstant(val) => format!("{}ul", val),
+            // SS: the exponent form always lexes as a floating constant and prints the
+            // shortest digits that read back as the same value. A constant too large for a
+            // double reads as infinity.
+            Tokens::DoubleConstant(val) if val.is_infinite() => "1e999".to_string(),
+            Tokens::DoubleConstant(val) => format!("{:e}", val),
+            Tokens::CharConstant(val) => format!("'{}'", escape(&[*val as u8], b'\'')),
+            Tokens::StringLiteral(bytes) => format!("\"{}\"", escape(bytes, b'"')),
+            Tokens::EOF => String::new(),
+            token => KEYWORDS
+                .iter()
+                .chain(PUNCTUATORS)
+                .find(|(_, candidate)| candidate == token)
+                .map(|(spelling, _)| spelling.to_string())
+                .unwrap(),
+        }
+    }
+}
+
+// SS: escapes the bytes of a character constant or string literal, anything that is not
+// printable ASCII becomes a three digit octal escape so that a following digit cannot
+// extend it
+fn escape(bytes: &[u8], quote: u8) -> String {
+    let mut result = String::new();
+    for &byte in bytes {
+        match byte {
+            b'\\' => result.push_str(r"\\"),
+            b'\n' => result.push_str(r"\n"),
+            b'\t' => result.push_str(r"\t"),
+            _ if byte == quote => {
+                result.push('\\');
+                result.push(byte as char);
+            }
+            b' '..=b'~' => result.push(byte as char),
+            _ => result.push_str(&format!("\\{:03o}", byte)),
+        }
+    }
+    result
+}
+
+impl Display for Tokens {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Tokens::Identifier(name) => format!("Identifier({})", name),
-            Tokens::Constant(val) => format!("Constant({})", val),
-            Tokens::Int => "Int".to_string(),
-            Tokens::Void => "Void".to_string(),
-            Tokens::Return => "Return".to_string(),
-            Tokens::OpenParen => "(".to_string(),
-            Tokens::CloseParen => ")".to_string(),
-            Tokens::OpenBrace => "{".to_string(),
-            Tokens::CloseBrace => "}".to_string(),
-            Tokens::Semicolon => ";".to_string(),
-            Tokens::EOF => "EOF".to_string(),
+            Tokens::Identifier(name) => write!(f, "Identifier({})", name),
+            Tokens::Constant(val) => write!(f, "Constant({})", val),
+            Tokens::LongConstant(val) => write!(f, "LongConstant({})", val),
+            Tokens::UnsignedConstant(val) => write!(f, "UnsignedConstant({})", val),
+            Tokens::UnsignedLongConstant(val) => write!(f, "UnsignedLongConstant({})", val),
+            Tokens::DoubleConstant(val) => write!(f, "DoubleConstant({})", val),
+            Tokens::CharConstant(_) => write!(f, "CharConstant({})", self.spelling()),
+            Tokens::StringLiteral(_) => write!(f, "StringLiteral({})", self.spelling()),
+            Tokens::Auto => write!(f, "Auto"),
+            Tokens::Break => write!(f, "Break"),
+            Tokens::Case => write!(f, "Case"),
+            Tokens::Char => write!(f, "Char"),
+            Tokens::Const => write!(f, "Const"),
+            Tokens::Continue => write!(f, "Continue"),
+            Tokens::Default => write!(f, "Default"),
+            Tokens::Do => write!(f, "Do"),
+            Tokens::Double => write!(f, "Double"),
+            Tokens::Else => write!(f, "Else"),
+            Tokens::Enum => write!(f, "Enum"),
+            Tokens::Extern => write!(f, "Extern"),
+            Tokens::Float => write!(f, "Float"),
+            Tokens::For => write!(f, "For"),
+            Tokens::Goto => write!(f, "Goto"),
+            Tokens::If => write!(f, "If"),
+            Tokens::Inline => write!(f, "Inline"),
+            Tokens::Int => write!(f, "Int"),
+            Tokens::Long => write!(f, "Long"),
+            Tokens::Register => write!(f, "Register"),
+            Tokens::Restrict => write!(f, "Restrict"),
+            Tokens::Return => write!(f, "Return"),
+            Tokens::Short => write!(f, "Short"),
+            Tokens::Signed => write!(f, "Signed"),
+            Tokens::Sizeof => write!(f, "Sizeof"),
+            Tokens::Static => write!(f, "Static"),
+            Tokens::Struct => write!(f, "Struct"),
+            Tokens::Switch => write!(f, "Switch"),
+            Tokens::Typedef => write!(f, "Typedef"),
+            Tokens::Union => write!(f, "Union"),
+            Tokens::Unsigned => write!(f, "Unsigned"),
+            Tokens::Void => write!(f, "Void"),
+            Tokens::Volatile => write!(f, "Volatile"),
+            Tokens::While => write!(f, "While"),
+            Tokens::Alignas => write!(f, "_Alignas"),
+            Tokens::Alignof => write!(f, "_Alignof"),
+            Tokens::Atomic => write!(f, "_Atomic"),
+            Tokens::Bool => write!(f, "_Bool"),
+            Tokens::Complex => write!(f, "_Complex"),
+            Tokens::Generic => write!(f, "_Generic"),
+            Tokens::Imaginary => write!(f, "_Imaginary"),
+            Tokens::Noreturn => write!(f, "_Noreturn"),
+            Tokens::StaticAssert => write!(f, "_Static_assert"),
+            Tokens::ThreadLocal => write!(f, "_Thread_local"),
+            Tokens::EOF => write!(f, "EOF"),
+            // SS: punctuators display as they are spelled
+            token => write!(f, "{}", token.spelling()),
         }
     }
 }
@@ -0,0 +1,2509 @@
+use crate::constant_folding::{fold_binary, fold_unary, is_zero};
+use crate::name_generator::NameGenerator;
+use crate::parse_ast::{
+    BinaryOperatorAST, BlockAST, BlockItemAST, DeclarationAST, ExprAST, ExternalDeclarationAST,
+    ForInitAST, FunctionAST, IncrementOperatorAST, InitializerAST, ProgramAST, StmtAST,
+    StorageClassAST, StructDeclarationAST, SwitchCaseAST, UnaryOperatorAST,
+};
+use crate::symbol_table::{IdentifierAttrs, InitialValue, Symbol, SymbolTable};
+use crate::tacky_generation::{convert_binary_operator, convert_unary_operator};
+use crate::type_table::{MemberEntry, StructEntry, TypeTable};
+use crate::types::{Constant, MAX_OBJECT_SIZE, StaticInit, Type};
+use std::collections::HashSet;
+
+// SS: type checking, runs after identifier resolution so every name is unique and the
+// symbol table can be flat. Rejects calls with the wrong number of arguments, using a
+// function as a variable and vice versa, conflicting declarations, initializers of static
+// variables that are not constant and operators applied to operands of the wrong type.
+// Also works out the linkage and initial value of every variable with static storage
+// duration. Every implicit conversion is made explicit as a cast, including arrays decaying
+// to pointers, and subscripts are rewritten as pointer arithmetic. Afterwards the operands
+// of every arithmetic operator have the same type, except that pointer arithmetic adds a
+// long to a pointer, and the type of any expression follows from its operands. Character
+// operands are promoted to int. The layout of every structure and union type goes into
+// the type table, and a string literal that initializes a static pointer becomes a
+// constant of its own in the symbol table. The value of every case label is evaluated and
+// collected into its switch, which rejects duplicates.
+pub fn typecheck_program(
+    ast: ProgramAST,
+    name_generator: &mut NameGenerator,
+) -> Result<(ProgramAST, SymbolTable, TypeTable), String> {
+    let mut checker = TypeChecker {
+        symbols: SymbolTable::new(),
+        types: TypeTable::new(),
+        name_generator,
+        return_type: Type::Int,
+        line: 1,
+        switches: vec![],
+    };
+    let declarations = ast
+        .declarations
+        .into_iter()
+        .map(|declaration| match declaration {
+            ExternalDeclarationAST::Function(function) => checker
+                .check_function_declaration(function)
+                .map(ExternalDeclarationAST::Function),
+            ExternalDeclarationAST::Variable(declaration) => checker
+                .check_file_scope_declaration(declaration)
+                .map(ExternalDeclarationAST::Variable),
+            ExternalDeclarationAST::Struct(declaration) => checker
+                .check_struct_declaration(declaration)
+                .map(ExternalDeclarationAST::Struct),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((ProgramAST { declarations }, checker.symbols, checker.types))
+}
+
+struct TypeChecker<'a> {
+    symbols: SymbolTable,
+    types: TypeTable,
+    name_generator: &'a mut NameGenerator,
+
+    // SS: the return type of the function whose body is being checked
+    return_type: Type,
+
+    // SS: the line of the most recently checked node that records one, operators do not
+    // record their own line so errors about their operands are reported here
+    line: usize,
+
+    // SS: the promoted type of the controlling expression and the cases found so far of
+    // every switch enclosing the current statement, innermost last
+    switches: Vec<(Type, Vec<SwitchCaseAST>)>,
+}
+
+impl TypeChecker<'_> {
+    // SS: a declaration without members only names the tag, which semantic analysis has
+    // already taken care of
+    fn check_struct_declaration(
+        &mut self,
+        declaration: StructDeclarationAST,
+    ) -> Result<StructDeclarationAST, String> {
+        self.line = declaration.line;
+        let Some(members) = &declaration.members else {
+            return Ok(declaration);
+        };
+        let struct_type = if declaration.is_union {
+            Type::Union(declaration.tag.clone())
+        } else {
+            Type::Structure(declaration.tag.clone())
+        };
+        if self.types.contains_key(&declaration.tag) {
+            return Err(format!(
+                "Line {}: Semantic error: {} is defined more than once",
+                declaration.line,
+                type_name(&struct_type)
+            ));
+        }
+
+        let mut names = HashSet::new();
+        for member in members {
+            self.line = member.line;
+            if !names.insert(&member.name) {
+                return Err(format!(
+                    "Line {}: Semantic error: Duplicate member {:?} in {}",
+                    member.line,
+                    member.name,
+                    type_name(&struct_type)
+                ));
+            }
+            self.validate_type(&member.member_type)?;
+            if !member.member_type.is_complete(&self.types) {
+                return Err(format!(
+                    "Line {}: Semantic error: Member {:?} has incomplete type {}",
+                    member.line,
+                    member.name,
+                    type_name(&member.member_type)
+                ));
+            }
+        }
+
+        // SS: every member fits on its own, together with the padding before each one they
+        // still have to
+        let size_bound = members
+            .iter()
+            .map(|member| {
+                i64::from(member.member_type.size(&self.types))
+                    + i64::from(member.member_type.alignment(&self.types))
+            })
+            .sum::<i64>();
+        if size_bound > MAX_OBJECT_SIZE {
+            return Err(format!(
+                "Line {}: Semantic error: {} is too large",
+                declaration.line,
+                type_name(&struct_type)
+            ));
+        }
+
+        let entry = StructEntry::new(
+            members
+                .iter()
+                .map(|member| (member.name.clone(), member.member_type.clone()))
+                .collect(),
+            declaration.is_union,
+            &self.types,
+        );
+        self.types.insert(declaration.tag.clone(), entry);
+        Ok(declaration)
+    }
+
+    // SS: an array of an incomplete type cannot be declared, even behind a pointer
+    fn validate_type(&self, validated_type: &Type) -> Result<(), String> {
+        match validated_type {
+            Type::Array { element, .. } => {
+                if !element.is_complete(&self.types) {
+                    return Err(format!(
+                        "Line {}: Semantic error: Array of incomplete type {}",
+                        self.line,
+                        type_name(element)
+                    ));
+                }
+                self.validate_type(element)?;
+                if validated_type.checked_size(&self.types).is_none() {
+                    return Err(format!(
+                        "Line {}: Semantic error: Array type {} is too large",
+                        self.line,
+                        type_name(validated_type)
+                    ));
+                }
+                Ok(())
+            }
+            Type::Pointer(referenced) => self.validate_type(referenced),
+            Type::Function { params, ret } => {
+                for param in params {
+                    self.validate_type(param)?;
+                }
+                self.validate_type(ret)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn check_complete(&self, checked_type: &Type) -> Result<(), String> {
+        if !checked_type.is_complete(&self.types) {
+            return Err(format!(
+                "Line {}: Semantic error: Incomplete type {} used as a value",
+                self.line,
+                type_name(checked_type)
+            ));
+        }
+        Ok(())
+    }
+
+    // SS: pointer arithmetic scales by the size of the referenced type, so it has to be
+    // known
+    fn check_pointer_arithmetic(&self, pointer_type: &Type) -> Result<(), String> {
+        let referenced_type = pointer_type.referenced_type();
+        if !referenced_type.is_complete(&self.types) {
+            return Err(format!(
+                "Line {}: Semantic error: Arithmetic on pointer to incomplete type {}",
+                self.line,
+                type_name(referenced_type)
+            ));
+        }
+        Ok(())
+    }
+
+    fn check_variable_type(&self, name: &str, var_type: &Type) -> Result<(), String> {
+        self.validate_type(var_type)?;
+        if !var_type.is_complete(&self.types) {
+            return Err(format!(
+                "Line {}: Semantic error: Variable {:?} has incomplete type {}",
+                self.line,
+                source_name(name),
+                type_name(var_type)
+            ));
+        }
+        Ok(())
+    }
+
+    fn check_function_declaration(&mut self, function: FunctionAST) -> Result<FunctionAST, String> {
+        self.line = function.line;
+        self.validate_type(&function.function_type)?;
+        let has_body = function.body.is_some();
+        let is_static = function.storage_class == Some(StorageClassAST::Static);
+
+        let mut already_defined = false;
+        let mut global = !is_static;
+        if let Some(symbol) = self.symbols.get(&function.name) {
+            if symbol.symbol_type != function.function_type {
+                return Err(format!(
+                    "Line {}: Semantic error: Incompatible declarations of function {:?}",
+                    function.line, function.name
+                ));
+            }
+            let IdentifierAttrs::Function {
+                defined,
+                global: previously_global,
+            } = symbol.attrs
+            else {
+                unreachable!("only functions have function type");
+            };
+            already_defined = defined;
+            if already_defined && has_body {
+                return Err(format!(
+                    "Line {}: Semantic error: Function {:?} is defined more than once",
+                    function.line, function.name
+                ));
+            }
+
+            // SS: a later declaration inherits the linkage of the first one, but cannot make
+            // an external function internal
+            if previously_global && is_static {
+                return Err(format!(
+                    "Line {}: Semantic error: Static declaration of function {:?} follows non-static declaration",
+                    function.line, function.name
+                ));
+            }
+            global = previously_global;
+        }
+
+        self.symbols.insert(
+            function.name.clone(),
+            Symbol {
+                symbol_type: function.function_type.clone(),
+                attrs: IdentifierAttrs::Function {
+                    defined: already_defined || has_body,
+                    global,
+                },
+            },
+        );
+
+        let Type::Function { params, ret } = &function.function_type else {
+            unreachable!("the parser gives every function a function type");
+        };
+        let body = match function.body {
+            Some(body) => {
+                if !ret.is_complete(&self.types) {
+                    return Err(format!(
+                        "Line {}: Semantic error: Function {:?} returns incomplete type {}",
+                        function.line,
+                        function.name,
+                        type_name(ret)
+                    ));
+                }
+                for (param, param_type) in function.params.iter().zip(params) {
+                    self.check_variable_type(param, param_type)?;
+                    self.declare_local_variable(param, param_type.clone());
+                }
+                self.return_type = (**ret).clone();
+                Some(self.check_block(body)?)
+            }
+            None => None,
+        };
+        Ok(FunctionAST { body, ..function })
+    }
+
+    fn check_file_scope_declaration(
+        &mut self,
+        declaration: DeclarationAST,
+    ) -> Result<DeclarationAST, String> {
+        self.line = declaration.line;
+        if declaration.storage_class == Some(StorageClassAST::Extern) && declaration.init.is_none()
+        {
+            self.validate_type(&declaration.var_type)?;
+        } else {
+            self.check_variable_type(&declaration.name, &declaration.var_type)?;
+        }
+        let mut init = match &declaration.init {
+            Some(init) => InitialValue::Initial(self.check_static_initializer(
+                init,
+                &declaration.var_type,
+                &format!("{:?}", declaration.name),
+            )?),
+            None if declaration.storage_class == Some(StorageClassAST::Extern) => {
+                InitialValue::NoInitializer
+            }
+            None => InitialValue::Tentative,
+        };
+        let mut global = declaration.storage_class != Some(StorageClassAST::Static);
+
+        if let Some(symbol) = self.symbols.get(&declaration.name) {
+            let IdentifierAttrs::Static {
+                init: previous_init,
+                global: previously_global,
+            } = &symbol.attrs
+            else {
+                return Err(format!(
+                    "Line {}: Semantic error: Function {:?} redeclared as variable",
+                    declaration.line, declaration.name
+                ));
+            };
+            if symbol.symbol_type != declaration.var_type {
+                return Err(format!(
+                    "Line {}: Semantic error: Conflicting types for variable {:?}",
+                    declaration.line, declaration.name
+                ));
+            }
+
+            // SS: extern takes on the linkage of an earlier declaration, otherwise both have
+            // to agree
+            if declaration.storage_class == Some(StorageClassAST::Extern) {
+                global = *previously_global;
+            } else if *previously_global != global {
+                return Err(format!(
+                    "Line {}: Semantic error: Conflicting linkage for variable {:?}",
+                    declaration.line, declaration.name
+                ));
+            }
+
+            init = match (previous_init, init) {
+                (InitialValue::Initial(_), InitialValue::Initial(_)) => {
+                    return Err(format!(
+                        "Line {}: Semantic error: Variable {:?} is defined more than once",
+                        declaration.line, declaration.name
+                    ));
+                }
+                (InitialValue::Initial(_), _) => previous_init.clone(),
+                (InitialValue::Tentative, InitialValue::NoInitializer) => previous_init.clone(),
+                (_, init) => init,
+            };
+        }
+
+        self.symbols.insert(
+            declaration.name.clone(),
+            Symbol {
+                symbol_type: declaration.var_type.clone(),
+                attrs: IdentifierAttrs::Static { init, global },
+            },
+        );
+        Ok(declaration)
+    }
+
+    // SS: works out the initial value of a variable with static storage duration, which has
+    // to be known at compile time. Elements of an array and members of a structure without
+    // an initializer are zero, and so is the padding between members.
+    fn check_static_initializer(
+        &mut self,
+        init: &InitializerAST,
+        target_type: &Type,
+        variable: &str,
+    ) -> Result<Vec<StaticInit>, String> {
+        match (init, target_type) {
+            (InitializerAST::Single(ExprAST::String(bytes)), Type::Array { element, size })
+                if element.is_character() =>
+            {
+                self.check_string_length(bytes.len(), *size, variable)?;
+                let string = StaticInit::String {
+                    bytes: bytes.clone(),
+                    null_terminated: bytes.len() < *size,
+                };
+                let padding = *size as i32 - string.size();
+                let mut static_inits = vec![string];
+                if padding > 0 {
+                    static_inits.push(StaticInit::Zero(padding));
+                }
+                Ok(static_inits)
+            }
+            // SS: the pointer is initialized with the address of a constant holding the string
+            (InitializerAST::Single(ExprAST::String(bytes)), Type::Pointer(referenced))
+                if **referenced == Type::Char =>
+            {
+                let name = self.name_generator.make_unique("string");
+                self.symbols.insert(
+                    name.clone(),
+                    Symbol {
+                        symbol_type: Type::Array {
+                            element: Box::new(Type::Char),
+                            size: bytes.len() + 1,
+                        },
+                        attrs: IdentifierAttrs::Constant(StaticInit::String {
+                            bytes: bytes.clone(),
+                            null_terminated: true,
+                        }),
+                    },
+                );
+                Ok(vec![StaticInit::Pointer(name)])
+            }
+            (InitializerAST::Single(ExprAST::String(_)), _) => Err(format!(
+                "Line {}: Semantic error: Cannot initialize {} with a string literal",
+                self.line,
+                type_name(target_type)
+            )),
+            (InitializerAST::Single(_), Type::Array { .. }) => Err(format!(
+                "Line {}: Semantic error: Array {} initialized with a scalar",
+                self.line, variable
+            )),
+            (InitializerAST::Single(ExprAST::Constant(val)), _) => {
+                let invalid = target_type.is_structure()
+                    || (target_type.is_pointer()
+                        && !is_null_pointer_constant(&ExprAST::Constant(*val)));
+                if invalid {
+                    return Err(format!(
+                        "Line {}: Semantic error: Cannot convert {} to {}",
+                        self.line,
+                        type_name(&val.constant_type()),
+                        type_name(target_type)
+                    ));
+                }
+                Ok(vec![StaticInit::Value(val.convert_to(target_type))])
+            }
+            (InitializerAST::Single(_), _) => Err(format!(
+                "Line {}: Semantic error: Non-constant initializer for {}",
+                self.line, variable
+            )),
+            (InitializerAST::Compound(initializers), Type::Array { element, size }) => {
+                self.check_initializer_count(initializers.len(), *size, variable)?;
+                let mut static_inits = vec![];
+                for init in initializers {
+                    static_inits.extend(self.check_static_initializer(init, element, variable)?);
+                }
+                if initializers.len() < *size {
+                    static_inits.push(StaticInit::Zero(
+                        element.size(&self.types) * (*size - initializers.len()) as i32,
+                    ));
+                }
+                Ok(static_inits)
+            }
+            (InitializerAST::Compound(initializers), Type::Structure(tag) | Type::Union(tag)) => {
+                let entry = self.types[tag].clone();
+                let members = initialized_members(&entry, target_type);
+                self.check_initializer_count(initializers.len(), members.len(), variable)?;
+                let mut static_inits = vec![];
+                let mut offset = 0;
+                for (init, member) in initializers.iter().zip(members) {
+                    if member.offset > offset {
+                        static_inits.push(StaticInit::Zero(member.offset - offset));
+                    }
+                    static_inits.extend(self.check_static_initializer(
+                        init,
+                        &member.member_type,
+                        variable,
+                    )?);
+                    offset = member.offset + member.member_type.size(&self.types);
+                }
+                if entry.size > offset {
+                    static_inits.push(StaticInit::Zero(entry.size - offset));
+                }
+                Ok(static_inits)
+            }
+            (InitializerAST::Compound(_), _) => Err(format!(
+                "Line {}: Semantic error: Initializer list for scalar {}",
+                self.line, variable
+            )),
+        }
+    }
+
+    fn check_initializer_count(
+        &self,
+        count: usize,
+        size: usize,
+        variable: &str,
+    ) -> Result<(), String> {
+        if count > size {
+            return Err(format!(
+                "Line {}: Semantic error: Too many elements in initializer for {}",
+                self.line, variable
+            ));
+        }
+        Ok(())
+    }
+
+    // SS: the terminating null is dropped if the array has no room for it
+    fn check_string_length(
+        &self,
+        length: usize,
+        size: usize,
+        variable: &str,
+    ) -> Result<(), String> {
+        if length > size {
+            return Err(format!(
+                "Line {}: Semantic error: String literal is too long for {}",
+                self.line, variable
+            ));
+        }
+        Ok(())
+    }
+
+    fn declare_local_variable(&mut self, name: &str, var_type: Type) {
+        self.symbols.insert(
+            name.to_string(),
+            Symbol {
+                symbol_type: var_type,
+                attrs: IdentifierAttrs::Local,
+            },
+        );
+    }
+
+    fn check_block(&mut self, block: BlockAST) -> Result<BlockAST, String> {
+        let items = block
+            .items
+            .into_iter()
+            .map(|block_item| match block_item {
+                BlockItemAST::Statement(stmt) => self.check_stmt(stmt).map(BlockItemAST::Statement),
+                BlockItemAST::Declaration(declaration) => self
+                    .check_declaration(declaration)
+                    .map(BlockItemAST::Declaration),
+                BlockItemAST::FunctionDeclaration(function) => self
+                    .check_function_declaration(function)
+                    .map(BlockItemAST::FunctionDeclaration),
+                BlockItemAST::StructDeclaration(declaration) => self
+                    .check_struct_declaration(declaration)
+                    .map(BlockItemAST::StructDeclaration),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(BlockAST { items })
+    }
+
+    fn check_declaration(&mut self, declaration: DeclarationAST) -> Result<DeclarationAST, String> {
+        self.line = declaration.line;
+        match declaration.storage_class {
+            Some(StorageClassAST::Extern) => {
+                if declaration.init.is_some() {
+                    return Err(format!(
+                        "Line {}: Semantic error: Initializer on local extern declaration of {:?}",
+                        declaration.line, declaration.name
+                    ));
+                }
+                self.validate_type(&declaration.var_type)?;
+                match self.symbols.get(&declaration.name) {
+                    Some(Symbol {
+                        symbol_type: Type::Function { .. },
+                        ..
+                    }) => {
+                        return Err(format!(
+                            "Line {}: Semantic error: Function {:?} redeclared as variable",
+                            declaration.line, declaration.name
+                        ));
+                    }
+                    Some(symbol) if symbol.symbol_type != declaration.var_type => {
+                        return Err(format!(
+                            "Line {}: Semantic error: Conflicting types for variable {:?}",
+                            declaration.line, declaration.name
+                        ));
+                    }
+                    Some(_) => {}
+                    None => {
+                        self.symbols.insert(
+                            declaration.name.clone(),
+                            Symbol {
+                                symbol_type: declaration.var_type.clone(),
+                                attrs: IdentifierAttrs::Static {
+                                    init: InitialValue::NoInitializer,
+                                    global: true,
+                                },
+                            },
+                        );
+                    }
+                }
+                Ok(declaration)
+            }
+            Some(StorageClassAST::Static) => {
+                self.check_variable_type(&declaration.name, &declaration.var_type)?;
+                let init = match &declaration.init {
+                    Some(init) => self.check_static_initializer(
+                        init,
+                        &declaration.var_type,
+                        &format!("static variable {:?}", source_name(&declaration.name)),
+                    )?,
+                    None => vec![StaticInit::Zero(declaration.var_type.size(&self.types))],
+                };
+                self.symbols.insert(
+                    declaration.name.clone(),
+                    Symbol {
+                        symbol_type: declaration.var_type.clone(),
+                        attrs: IdentifierAttrs::Static {
+                            init: InitialValue::Initial(init),
+                            global: false,
+                        },
+                    },
+                );
+                Ok(declaration)
+            }
+            None => {
+                self.check_variable_type(&declaration.name, &declaration.var_type)?;
+                self.declare_local_variable(&declaration.name, declaration.var_type.clone());
+                let init = declaration
+                    .init
+                    .map(|init| {
+                        self.check_initializer(
+                            init,
+                            &declaration.var_type,
+                            source_name(&declaration.name),
+                        )
+                    })
+                    .transpose()?;
+                Ok(DeclarationAST {
+                    init,
+                    ..declaration
+                })
+            }
+        }
+    }
+
+    // SS: converts every initializer to the type of the object it initializes and pads an
+    // initializer list with zeros, so that there is one initializer per element or member.
+    // A string literal that initializes a character array is left as it is, TACKY
+    // generation copies its characters and pads the rest of the array.
+    fn check_initializer(
+        &mut self,
+        init: InitializerAST,
+        target_type: &Type,
+        variable: &str,
+    ) -> Result<InitializerAST, String> {
+        match (init, target_type) {
+            (InitializerAST::Single(ExprAST::String(bytes)), Type::Array { element, size })
+                if element.is_character() =>
+            {
+                self.check_string_length(bytes.len(), *size, &format!("{:?}", variable))?;
+                Ok(InitializerAST::Single(ExprAST::String(bytes)))
+            }
+            (InitializerAST::Single(ExprAST::String(_)), Type::Array { .. }) => Err(format!(
+                "Line {}: Semantic error: Cannot initialize {} with a string literal",
+                self.line,
+                type_name(target_type)
+            )),
+            (InitializerAST::Single(_), Type::Array { .. }) => Err(format!(
+                "Line {}: Semantic error: Array {:?} initialized with a scalar",
+                self.line, variable
+            )),
+            (InitializerAST::Single(expr), _) => Ok(InitializerAST::Single(
+                self.check_expr_as(expr, target_type)?,
+            )),
+            (InitializerAST::Compound(initializers), Type::Array { element, size }) => {
+                self.check_initializer_count(
+                    initializers.len(),
+                    *size,
+                    &format!("{:?}", variable),
+                )?;
+                let mut initializers = initializers
+                    .into_iter()
+                    .map(|init| self.check_initializer(init, element, variable))
+                    .collect::<Result<Vec<_>, _>>()?;
+                while initializers.len() < *size {
+                    initializers.push(zero_initializer(element, &self.types));
+                }
+                Ok(InitializerAST::Compound(initializers))
+            }
+            (InitializerAST::Compound(initializers), Type::Structure(tag) | Type::Union(tag)) => {
+                let entry = self.types[tag].clone();
+                let members = initialized_members(&entry, target_type);
+                self.check_initializer_count(
+                    initializers.len(),
+                    members.len(),
+                    &format!("{:?}", variable),
+                )?;
+                let mut initializers = initializers
+                    .into_iter()
+                    .zip(members)
+                    .map(|(init, member)| {
+                        self.check_initializer(init, &member.member_type, variable)
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                for member in &members[initializers.len()..] {
+                    initializers.push(zero_initializer(&member.member_type, &self.types));
+                }
+                Ok(InitializerAST::Compound(initializers))
+            }
+            (InitializerAST::Compound(_), _) => Err(format!(
+                "Line {}: Semantic error: Initializer list for scalar {:?}",
+                self.line, variable
+            )),
+        }
+    }
+
+    fn check_stmt(&mut self, stmt: StmtAST) -> Result<StmtAST, String> {
+        match stmt {
+            StmtAST::Return(expr) => {
+                let return_type = self.return_type.clone();
+                Ok(StmtAST::Return(self.check_expr_as(expr, &return_type)?))
+            }
+            StmtAST::Expression(expr) => Ok(StmtAST::Expression(self.check_expr(expr)?.0)),
+            StmtAST::If {
+                condition,
+                then_stmt,
+                else_stmt,
+            } => Ok(StmtAST::If {
+                condition: self.check_scalar(condition)?,
+                then_stmt: Box::new(self.check_stmt(*then_stmt)?),
+                else_stmt: else_stmt
+                    .map(|stmt| self.check_stmt(*stmt).map(Box::new))
+                    .transpose()?,
+            }),
+            StmtAST::Compound(block) => Ok(StmtAST::Compound(self.check_block(block)?)),
+            StmtAST::While {
+                condition,
+                body,
+                label,
+            } => Ok(StmtAST::While {
+                condition: self.check_scalar(condition)?,
+                body: Box::new(self.check_stmt(*body)?),
+                label,
+            }),
+            StmtAST::DoWhile {
+                body,
+                condition,
+                label,
+            } => Ok(StmtAST::DoWhile {
+                body: Box::new(self.check_stmt(*body)?),
+                condition: self.check_scalar(condition)?,
+                label,
+            }),
+            StmtAST::For {
+                init,
+                condition,
+                post,
+                body,
+                label,
+            } => {
+                let init = match init {
+                    ForInitAST::InitDecl(declaration) => {
+                        ForInitAST::InitDecl(self.check_declaration(declaration)?)
+                    }
+                    ForInitAST::InitExpr(expr) => ForInitAST::InitExpr(
+                        expr.map(|expr| self.check_expr(expr).map(|(expr, _)| expr))
+                            .transpose()?,
+                    ),
+                };
+                let condition = condition.map(|expr| self.check_scalar(expr)).transpose()?;
+                let post = post
+                    .map(|expr| self.check_expr(expr).map(|(expr, _)| expr))
+                    .transpose()?;
+                Ok(StmtAST::For {
+                    init,
+                    condition,
+                    post,
+                    body: Box::new(self.check_stmt(*body)?),
+                    label,
+                })
+            }
+            StmtAST::Break { line, .. }
+            | StmtAST::Continue { line, .. }
+            | StmtAST::Goto { line, .. } => {
+                self.line = line;
+                Ok(stmt)
+            }
+            StmtAST::Labeled { label, body, line } => {
+                self.line = line;
+                Ok(StmtAST::Labeled {
+                    label,
+                    body: Box::new(self.check_stmt(*body)?),
+                    line,
+                })
+            }
+            StmtAST::Switch {
+                condition,
+                body,
+                label,
+                ..
+            } => {
+                let (condition, condition_type) = self.check_expr(condition)?;
+                if !condition_type.is_integer() {
+                    return Err(format!(
+                        "Line {}: Semantic error: Switch on {}, which is not an integer",
+                        self.line,
+                        type_name(&condition_type)
+                    ));
+                }
+                let switch_type = promote(&condition_type);
+                let condition = self.convert_to(condition, &condition_type, &switch_type);
+                self.switches.push((switch_type, vec![]));
+                let body = self.check_stmt(*body);
+                let (_, cases) = self.switches.pop().unwrap();
+                Ok(StmtAST::Switch {
+                    condition,
+                    body: Box::new(body?),
+                    label,
+                    cases,
+                })
+            }
+            StmtAST::Case {
+                value,
+                body,
+                label,
+                line,
+            } => {
+                self.line = line;
+                let (value, value_type) = self.check_expr(value)?;
+                let constant = evaluate_constant(&value)
+                    .filter(|_| value_type.is_integer())
+                    .ok_or_else(|| {
+                        format!(
+                            "Line {}: Semantic error: Case label does not reduce to an integer constant",
+                            line
+                        )
+                    })?;
+                let (switch_type, cases) = self
+                    .switches
+                    .last_mut()
+                    .expect("loop labeling rejects a case outside a switch");
+                let constant = constant.convert_to(switch_type);
+                if cases.iter().any(|case| case.value == Some(constant)) {
+                    let value = if switch_type.is_signed() {
+                        constant.bits().to_string()
+                    } else {
+                        (constant.bits() as u64).to_string()
+                    };
+                    return Err(format!(
+                        "Line {}: Semantic error: Duplicate case value {}",
+                        line, value
+                    ));
+                }
+                cases.push(SwitchCaseAST {
+                    value: Some(constant),
+                    label: label.clone().unwrap(),
+                });
+                Ok(StmtAST::Case {
+                    value: ExprAST::Constant(constant),
+                    body: Box::new(self.check_stmt(*body)?),
+                    label,
+                    line,
+                })
+            }
+            StmtAST::Default { body, label, line } => {
+                self.line = line;
+                let (_, cases) = self
+                    .switches
+                    .last_mut()
+                    .expect("loop labeling rejects a default outside a switch");
+                cases.push(SwitchCaseAST {
+                    value: None,
+                    label: label.clone().unwrap(),
+                });
+                Ok(StmtAST::Default {
+                    body: Box::new(self.check_stmt(*body)?),
+                    label,
+                    line,
+                })
+            }
+            StmtAST::Null => Ok(stmt),
+        }
+    }
+
+    // SS: a condition, or an operand of !, && or ||, is compared against zero
+    fn check_scalar(&mut self, expr: ExprAST) -> Result<ExprAST, String> {
+        let (expr, expr_type) = self.check_expr(expr)?;
+        if !expr_type.is_scalar() {
+            return Err(format!(
+                "Line {}: Semantic error: {} used where a scalar is required",
+                self.line,
+                type_name(&expr_type)
+            ));
+        }
+        Ok(expr)
+    }
+
+    // SS: checks the expression and converts it to the given type as if by assignment. An
+    // arithmetic value converts to any arithmetic type, a pointer or structure only to its
+    // own type, and the null pointer constant to any pointer type.
+    fn check_expr_as(&mut self, expr: ExprAST, target_type: &Type) -> Result<ExprAST, String> {
+        let (expr, expr_type) = self.check_expr(expr)?;
+        let convertible = expr_type == *target_type
+            || (expr_type.is_arithmetic() && target_type.is_arithmetic())
+            || (target_type.is_pointer() && is_null_pointer_constant(&expr));
+        if !convertible {
+            return Err(format!(
+                "Line {}: Semantic error: Cannot convert {} to {}",
+                self.line,
+                type_name(&expr_type),
+                type_name(target_type)
+            ));
+        }
+        Ok(self.convert_to(expr, &expr_type, target_type))
+    }
+
+    fn convert_to(&self, expr: ExprAST, expr_type: &Type, target_type: &Type) -> ExprAST {
+        if expr_type == target_type {
+            return expr;
+        }
+        ExprAST::Cast {
+            target_type: target_type.clone(),
+            expr: Box::new(expr),
+            line: self.line,
+        }
+    }
+
+    // SS: the type both operands of a comparison or a conditional are converted to, where at
+    // least one of them is a pointer
+    fn common_pointer_type(
+        &self,
+        left: &ExprAST,
+        left_type: &Type,
+        right: &ExprAST,
+        right_type: &Type,
+    ) -> Result<Type, String> {
+        if left_type == right_type || is_null_pointer_constant(right) {
+            Ok(left_type.clone())
+        } else if is_null_pointer_constant(left) {
+            Ok(right_type.clone())
+        } else {
+            Err(format!(
+                "Line {}: Semantic error: Incompatible types {} and {}",
+                self.line,
+                type_name(left_type),
+                type_name(right_type)
+            ))
+        }
+    }
+
+    // SS: returns the expression with its implicit conversions made explicit, along with
+    // its type. An array used as a value decays to a pointer to its first element, which is
+    // made explicit by taking its address and casting that to the element pointer type.
+    fn check_expr(&mut self, expr: ExprAST) -> Result<(ExprAST, Type), String> {
+        let (expr, expr_type) = self.check_expr_without_decay(expr)?;
+        self.check_complete(&expr_type)?;
+        match expr_type {
+            Type::Array { element, .. } => {
+                let pointer_type = Type::Pointer(element);
+                let address = ExprAST::AddressOf {
+                    expr: Box::new(expr),
+                    line: self.line,
+                };
+                Ok((
+                    ExprAST::Cast {
+                        target_type: pointer_type.clone(),
+                        expr: Box::new(address),
+                        line: self.line,
+                    },
+                    pointer_type,
+                ))
+            }
+            expr_type => Ok((expr, expr_type)),
+        }
+    }
+
+    // SS: the operand of an assignment, increment or & is an object rather than a value, so
+    // it does not decay. An array is not assignable.
+    fn check_assignable(&mut self, expr: ExprAST) -> Result<(ExprAST, Type), String> {
+        let (expr, expr_type) = self.check_expr_without_decay(expr)?;
+        if let Type::Array { .. } = expr_type {
+            return Err(format!(
+                "Line {}: Semantic error: Cannot assign to an array",
+                self.line
+            ));
+        }
+        self.check_complete(&expr_type)?;
+        Ok((expr, expr_type))
+    }
+
+    fn check_expr_without_decay(&mut self, expr: ExprAST) -> Result<(ExprAST, Type), String> {
+        match expr {
+            ExprAST::Constant(val) => Ok((expr, val.constant_type())),
+            // SS: the terminating null is part of the array
+            ExprAST::String(ref bytes) => {
+                let string_type = Type::Array {
+                    element: Box::new(Type::Char),
+                    size: bytes.len() + 1,
+                };
+                Ok((expr, string_type))
+            }
+            ExprAST::Var { ref name, line } => {
+                self.line = line;
+                match &self.symbols[name].symbol_type {
+                    Type::Function { .. } => Err(format!(
+                        "Line {}: Semantic error: Function {:?} used as a variable",
+                        line, name
+                    )),
+                    var_type => {
+                        let var_type = var_type.clone();
+                        Ok((expr, var_type))
+                    }
+                }
+            }
+            ExprAST::Cast {
+                target_type,
+                expr,
+                line,
+            } => {
+                self.line = line;
+                self.validate_type(&target_type)?;
+                let (expr, expr_type) = self.check_expr(*expr)?;
+                let invalid = !target_type.is_scalar()
+                    || !expr_type.is_scalar()
+                    || (target_type.is_pointer() && expr_type == Type::Double)
+                    || (target_type == Type::Double && expr_type.is_pointer());
+                if invalid {
+                    return Err(format!(
+                        "Line {}: Semantic error: Cannot cast {} to {}",
+                        line,
+                        type_name(&expr_type),
+                        type_name(&target_type)
+                    ));
+                }
+                Ok((
+                    ExprAST::Cast {
+                        target_type: target_type.clone(),
+                        expr: Box::new(expr),
+                        line,
+                    },
+                    target_type,
+                ))
+            }
+            ExprAST::Unary(UnaryOperatorAST::Not, operand) => {
+                let operand = self.check_scalar(*operand)?;
+                Ok((
+                    ExprAST::Unary(UnaryOperatorAST::Not, Box::new(operand)),
+                    Type::Int,
+                ))
+            }
+            ExprAST::Unary(op, operand) => {
+                let (operand, operand_type) = self.check_expr(*operand)?;
+                match op {
+                    UnaryOperatorAST::Complement if operand_type == Type::Double => {
+                        return Err(format!(
+                            "Line {}: Semantic error: Operand of ~ has type double",
+                            self.line
+                        ));
+                    }
+                    _ if !operand_type.is_arithmetic() => {
+                        return Err(format!(
+                            "Line {}: Semantic error: Invalid operand {} for {}",
+                            self.line,
+                            type_name(&operand_type),
+                            if op == UnaryOperatorAST::Negate {
+                                "-"
+                            } else {
+                                "~"
+                            }
+                        ));
+                    }
+                    _ => {}
+                }
+                let result_type = promote(&operand_type);
+                let operand = self.convert_to(operand, &operand_type, &result_type);
+                Ok((ExprAST::Unary(op, Box::new(operand)), result_type))
+            }
+            // SS: the operands of && and || are only compared against zero, so they keep
+            // their own types
+            ExprAST::Binary(op @ (BinaryOperatorAST::And | BinaryOperatorAST::Or), left, right) => {
+                let left = self.check_scalar(*left)?;
+                let right = self.check_scalar(*right)?;
+                Ok((
+                    ExprAST::Binary(op, Box::new(left), Box::new(right)),
+                    Type::Int,
+                ))
+            }
+            ExprAST::Binary(op, left, right) => {
+                let (left, left_type) = self.check_expr(*left)?;
+                let (right, right_type) = self.check_expr(*right)?;
+                if left_type.is_pointer() || right_type.is_pointer() {
+                    return self.check_pointer_operator(op, left, left_type, right, right_type);
+                }
+                if !left_type.is_arithmetic() || !right_type.is_arithmetic() {
+                    return Err(self.invalid_operands(op, &left_type, &right_type));
+                }
+                let common_type = common_type(&left_type, &right_type);
+                self.check_arithmetic_operator(op, &common_type)?;
+                let left = self.convert_to(left, &left_type, &common_type);
+                let right = self.convert_to(right, &right_type, &common_type);
+                let result_type = if is_relational(op) {
+                    Type::Int
+                } else {
+                    common_type
+                };
+                Ok((
+                    ExprAST::Binary(op, Box::new(left), Box::new(right)),
+                    result_type,
+                ))
+            }
+            ExprAST::Assignment { lhs, rhs, line } => {
+                self.line = line;
+                let (lhs, lhs_type) = self.check_assignable(*lhs)?;
+                let rhs = self.check_expr_as(*rhs, &lhs_type)?;
+                Ok((
+                    ExprAST::Assignment {
+                        lhs: Box::new(lhs),
+                        rhs: Box::new(rhs),
+                        line,
+                    },
+                    lhs_type,
+                ))
+            }
+            // SS: the operation is carried out in the common type of both sides, the rhs is
+            // converted to it here and TACKY generation converts the lhs there and back. A
+            // pointer can only be moved by an integer, which is converted to long.
+            ExprAST::CompoundAssignment { op, lhs, rhs, line } => {
+                self.line = line;
+                let (lhs, lhs_type) = self.check_assignable(*lhs)?;
+                let (rhs, rhs_type) = self.check_expr(*rhs)?;
+                let rhs = if lhs_type.is_pointer() {
+                    let is_valid =
+                        matches!(op, BinaryOperatorAST::Add | BinaryOperatorAST::Subtract)
+                            && rhs_type.is_integer();
+                    if !is_valid {
+                        return Err(self.invalid_operands(op, &lhs_type, &rhs_type));
+                    }
+                    self.check_pointer_arithmetic(&lhs_type)?;
+                    self.convert_to(rhs, &rhs_type, &Type::Long)
+                } else if !lhs_type.is_arithmetic() || !rhs_type.is_arithmetic() {
+                    return Err(self.invalid_operands(op, &lhs_type, &rhs_type));
+                } else {
+                    let common_type = common_type(&lhs_type, &rhs_type);
+                    self.check_arithmetic_operator(op, &common_type)?;
+                    self.convert_to(rhs, &rhs_type, &common_type)
+                };
+                Ok((
+                    ExprAST::CompoundAssignment {
+                        op,
+                        lhs: Box::new(lhs),
+                        rhs: Box::new(rhs),
+                        line,
+                    },
+                    lhs_type,
+                ))
+            }
+            ExprAST::Increment { op, operand, line } => {
+                self.line = line;
+                let (operand, operand_type) = self.check_assignable(*operand)?;
+                if operand_type.is_pointer() {
+                    self.check_pointer_arithmetic(&operand_type)?;
+                } else if !operand_type.is_arithmetic() {
+                    return Err(format!(
+                        "Line {}: Semantic error: Invalid operand {} for {}",
+                        line,
+                        type_name(&operand_type),
+                        match op {
+                            IncrementOperatorAST::PreIncrement
+                            | IncrementOperatorAST::PostIncrement => "++",
+                            IncrementOperatorAST::PreDecrement
+                            | IncrementOperatorAST::PostDecrement => "--",
+                        }
+                    ));
+                }
+                Ok((
+                    ExprAST::Increment {
+                        op,
+                        operand: Box::new(operand),
+                        line,
+                    },
+                    operand_type,
+                ))
+            }
+            ExprAST::Conditional {
+                condition,
+                then_expr,
+                else_expr,
+            } => {
+                let condition = self.check_scalar(*condition)?;
+                let (then_expr, then_type) = self.check_expr(*then_expr)?;
+                let (else_expr, else_type) = self.check_expr(*else_expr)?;
+                let common_type = if then_type.is_pointer() || else_type.is_pointer() {
+                    self.common_pointer_type(&then_expr, &then_type, &else_expr, &else_type)?
+                } else if then_type.is_arithmetic() && else_type.is_arithmetic() {
+                    common_type(&then_type, &else_type)
+                } else if then_type == else_type {
+                    then_type.clone()
+                } else {
+                    return Err(format!(
+                        "Line {}: Semantic error: Incompatible types {} and {}",
+                        self.line,
+                        type_name(&then_type),
+                        type_name(&else_type)
+                    ));
+                };
+                Ok((
+                    ExprAST::Conditional {
+                        condition: Box::new(condition),
+                        then_expr: Box::new(self.convert_to(then_expr, &then_type, &common_type)),
+                        else_expr: Box::new(self.convert_to(else_expr, &else_type, &common_type)),
+                    },
+                    common_type,
+                ))
+            }
+            ExprAST::FunctionCall { name, args, line } => {
+                self.line = line;
+                let (params, ret) = match &self.symbols[&name].symbol_type {
+                    Type::Function { params, .. } if params.len() != args.len() => {
+                        return Err(format!(
+                            "Line {}: Semantic error: Function {:?} expects {} arguments, but was called with {}",
+                            line,
+                            name,
+                            params.len(),
+                            args.len()
+                        ));
+                    }
+                    Type::Function { params, ret } => (params.clone(), (**ret).clone()),
+                    _ => {
+                        return Err(format!(
+                            "Line {}: Semantic error: Variable {:?} used as a function",
+                            line,
+                            source_name(&name)
+                        ));
+                    }
+                };
+
+                // SS: arguments are converted to the parameter types as if by assignment
+                let args = args
+                    .into_iter()
+                    .zip(&params)
+                    .map(|(arg, param_type)| self.check_expr_as(arg, param_type))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok((ExprAST::FunctionCall { name, args, line }, ret))
+            }
+            ExprAST::Dereference { expr, line } => {
+                self.line = line;
+                let (expr, expr_type) = self.check_expr(*expr)?;
+                let Type::Pointer(referenced_type) = expr_type else {
+                    return Err(format!(
+                        "Line {}: Semantic error: Cannot dereference {}",
+                        line,
+                        type_name(&expr_type)
+                    ));
+                };
+                Ok((
+                    ExprAST::Dereference {
+                        expr: Box::new(expr),
+                        line,
+                    },
+                    *referenced_type,
+                ))
+            }
+            ExprAST::AddressOf { expr, line } => {
+                self.line = line;
+                let (expr, expr_type) = self.check_expr_without_decay(*expr)?;
+                Ok((
+                    ExprAST::AddressOf {
+                        expr: Box::new(expr),
+                        line,
+                    },
+                    Type::Pointer(Box::new(expr_type)),
+                ))
+            }
+            // SS: either operand may be the pointer, i.e. 1[a] is the same as a[1]
+            ExprAST::Subscript { array, index, line } => {
+                self.line = line;
+                let (array, array_type) = self.check_expr(*array)?;
+                let (index, index_type) = self.check_expr(*index)?;
+                if !(array_type.is_pointer() && index_type.is_integer()
+                    || array_type.is_integer() && index_type.is_pointer())
+                {
+                    return Err(format!(
+                        "Line {}: Semantic error: Cannot subscript {} with {}",
+                        line,
+                        type_name(&array_type),
+                        type_name(&index_type)
+                    ));
+                }
+                let (sum, sum_type) = self.check_pointer_operator(
+                    BinaryOperatorAST::Add,
+                    array,
+                    array_type,
+                    index,
+                    index_type,
+                )?;
+                Ok((
+                    ExprAST::Dereference {
+                        expr: Box::new(sum),
+                        line,
+                    },
+                    sum_type.referenced_type().clone(),
+                ))
+            }
+            // SS: the operand is not evaluated, only its type matters
+            ExprAST::SizeOf { expr, line } => {
+                self.line = line;
+                let (_, expr_type) = self.check_expr_without_decay(*expr)?;
+                self.check_size_of(&expr_type)
+            }
+            ExprAST::SizeOfType { target_type, line } => {
+                self.line = line;
+                self.validate_type(&target_type)?;
+                self.check_size_of(&target_type)
+            }
+            ExprAST::Dot {
+                structure,
+                member,
+                line,
+            } => {
+                self.line = line;
+                let (structure, structure_type) = self.check_expr(*structure)?;
+                let member_type = self.member_type(&structure_type, &member)?;
+                Ok((
+                    ExprAST::Dot {
+                        structure: Box::new(structure),
+                        member,
+                        line,
+                    },
+                    member_type,
+                ))
+            }
+            ExprAST::Arrow {
+                pointer,
+                member,
+                line,
+            } => {
+                self.line = line;
+                let (pointer, pointer_type) = self.check_expr(*pointer)?;
+                let Type::Pointer(structure_type) = &pointer_type else {
+                    return Err(format!(
+                        "Line {}: Semantic error: Cannot apply -> to {}",
+                        line,
+                        type_name(&pointer_type)
+                    ));
+                };
+                self.check_complete(structure_type)?;
+                let member_type = self.member_type(structure_type, &member)?;
+                Ok((
+                    ExprAST::Arrow {
+                        pointer: Box::new(pointer),
+                        member,
+                        line,
+                    },
+                    member_type,
+                ))
+            }
+        }
+    }
+
+    // SS: sizeof is a constant of type size_t, which is unsigned long on x64
+    fn check_size_of(&self, operand_type: &Type) -> Result<(ExprAST, Type), String> {
+        if matches!(operand_type, Type::Function { .. }) || !operand_type.is_complete(&self.types) {
+            return Err(format!(
+                "Line {}: Semantic error: Cannot take the size of {}",
+                self.line,
+                type_name(operand_type)
+            ));
+        }
+        Ok((
+            ExprAST::Constant(Constant::ULong(operand_type.size(&self.types) as u64)),
+            Type::ULong,
+        ))
+    }
+
+    fn member_type(&self, structure_type: &Type, member: &str) -> Result<Type, String> {
+        let (Type::Structure(tag) | Type::Union(tag)) = structure_type else {
+            return Err(format!(
+                "Line {}: Semantic error: Member {:?} requested in non-structure type {}",
+                self.line,
+                member,
+                type_name(structure_type)
+            ));
+        };
+        match self.types[tag].member(member) {
+            Some(entry) => Ok(entry.member_type.clone()),
+            None => Err(format!(
+                "Line {}: Semantic error: {} has no member {:?}",
+                self.line,
+                type_name(structure_type),
+                member
+            )),
+        }
+    }
+
+    // SS: a binary operator where at least one operand is a pointer. Pointer arithmetic is
+    // normalized so the pointer is always the left operand and the integer is a long, and
+    // the difference of two pointers is a long.
+    fn check_pointer_operator(
+        &self,
+        op: BinaryOperatorAST,
+        left: ExprAST,
+        left_type: Type,
+        right: ExprAST,
+        right_type: Type,
+    ) -> Result<(ExprAST, Type), String> {
+        let binary = |op, left, right| ExprAST::Binary(op, Box::new(left), Box::new(right));
+        if matches!(op, BinaryOperatorAST::Add | BinaryOperatorAST::Subtract) {
+            for operand_type in [&left_type, &right_type] {
+                if operand_type.is_pointer() {
+                    self.check_pointer_arithmetic(operand_type)?;
+                }
+            }
+        }
+        match op {
+            BinaryOperatorAST::Add if left_type.is_pointer() && right_type.is_integer() => {
+                let right = self.convert_to(right, &right_type, &Type::Long);
+                Ok((binary(op, left, right), left_type))
+            }
+            BinaryOperatorAST::Add if left_type.is_integer() && right_type.is_pointer() => {
+                let left = self.convert_to(left, &left_type, &Type::Long);
+                Ok((binary(op, right, left), right_type))
+            }
+            BinaryOperatorAST::Subtract if left_type.is_pointer() && right_type.is_integer() => {
+                let right = self.convert_to(right, &right_type, &Type::Long);
+                Ok((binary(op, left, right), left_type))
+            }
+            BinaryOperatorAST::Subtract if left_type == right_type => {
+                Ok((binary(op, left, right), Type::Long))
+            }
+            BinaryOperatorAST::Equal | BinaryOperatorAST::NotEqual => {
+                let common_type =
+                    self.common_pointer_type(&left, &left_type, &right, &right_type)?;
+                let left = self.convert_to(left, &left_type, &common_type);
+                let right = self.convert_to(right, &right_type, &common_type);
+                Ok((binary(op, left, right), Type::Int))
+            }
+            BinaryOperatorAST::LessThan
+            | BinaryOperatorAST::LessOrEqual
+            | BinaryOperatorAST::GreaterThan
+            | BinaryOperatorAST::GreaterOrEqual
+                if left_type == right_type =>
+            {
+                Ok((binary(op, left, right), Type::Int))
+            }
+            _ => Err(self.invalid_operands(op, &left_type, &right_type)),
+        }
+    }
+
+    fn invalid_operands(&self, op: BinaryOperatorAST, left: &Type, right: &Type) -> String {
+        format!(
+            "Line {}: Semantic error: Invalid operands {} and {} for {}",
+            self.line,
+            type_name(left),
+            type_name(right),
+            operator_symbol(op)
+        )
+    }
+
+    // SS: % is only defined on integers
+    fn check_arithmetic_operator(
+        &self,
+        op: BinaryOperatorAST,
+        operand_type: &Type,
+    ) -> Result<(), String> {
+        if op == BinaryOperatorAST::Remainder && *operand_type == Type::Double {
+            return Err(format!(
+                "Line {}: Semantic error: Operand of % has type double",
+                self.line
+            ));
+        }
+        Ok(())
+    }
+}
+
+// SS: the usual arithmetic conversions. Character types are promoted to int first, then
+// both operands are converted to double if either is one, otherwise to the larger type,
+// and to the unsigned type if both have the same size.
+// SS: the value of a type checked constant expression, None when it is not one. Every
+// implicit conversion is an explicit cast by now, so the operators see operands of the
+// same type just like constant folding does on TACKY.
+fn evaluate_constant(expr: &ExprAST) -> Option<Constant> {
+    match expr {
+        ExprAST::Constant(c) => Some(*c),
+        ExprAST::Cast {
+            target_type, expr, ..
+        } if target_type.is_arithmetic() => {
+            evaluate_constant(expr).map(|c| c.convert_to(target_type))
+        }
+        ExprAST::Unary(op, operand) => {
+            fold_unary(convert_unary_operator(*op), evaluate_constant(operand)?)
+        }
+        ExprAST::Binary(BinaryOperatorAST::And, lhs, rhs) => {
+            let value = !is_zero(&evaluate_constant(lhs)?) && !is_zero(&evaluate_constant(rhs)?);
+            Some(Constant::Int(value as i32))
+        }
+        ExprAST::Binary(BinaryOperatorAST::Or, lhs, rhs) => {
+            let value = !is_zero(&evaluate_constant(lhs)?) || !is_zero(&evaluate_constant(rhs)?);
+            Some(Constant::Int(value as i32))
+        }
+        ExprAST::Binary(op, lhs, rhs) => fold_binary(
+            convert_binary_operator(*op),
+            evaluate_constant(lhs)?,
+            evaluate_constant(rhs)?,
+        ),
+        ExprAST::Conditional {
+            condition,
+            then_expr,
+            else_expr,
+        } => {
+            if is_zero(&evaluate_constant(condition)?) {
+                evaluate_constant(else_expr)
+            } else {
+                evaluate_constant(then_expr)
+            }
+        }
+        _ => None,
+    }
+}
+
+fn common_type(left: &Type, right: &Type) -> Type {
+    let left = promote(left);
+    let right = promote(right);
+    if left == right {
+        left
+    } else if left == Type::Double || right == Type::Double {
+        Type::Double
+    } else if left.scalar_size() == right.scalar_size() {
+        if left.is_signed() { right } else { left }
+    } else if left.scalar_size() > right.scalar_size() {
+        left
+    } else {
+        right
+    }
+}
+
+// SS: the integer promotions, every type narrower than int fits into an int
+fn promote(operand_type: &Type) -> Type {
+    if operand_type.is_character() {
+        Type::Int
+    } else {
+        operand_type.clone()
+    }
+}
+
+// SS: the members an initializer list sets, only the first one for a union
+fn initialized_members<'a>(entry: &'a StructEntry, target_type: &Type) -> &'a [MemberEntry] {
+    match target_type {
+        Type::Union(_) => &entry.members[..1],
+        _ => &entry.members,
+    }
+}
+
+// SS: an integer constant of value zero converts to any pointer type
+fn is_null_pointer_constant(expr: &ExprAST) -> bool {
+    match expr {
+        ExprAST::Constant(val) => val.constant_type().is_integer() && val.bits() == 0,
+        _ => false,
+    }
+}
+
+// SS: an initializer that sets every scalar in an object of the given type to zero
+fn zero_initializer(target_type: &Type, types: &TypeTable) -> InitializerAST {
+    match target_type {
+        Type::Array { element, size } => {
+            InitializerAST::Compound(vec![zero_initializer(element, types); *size])
+        }
+        Type::Structure(tag) | Type::Union(tag) => InitializerAST::Compound(
+            initialized_members(&types[tag], target_type)
+                .iter()
+                .map(|member| zero_initializer(&member.member_type, types))
+                .collect(),
+        ),
+        _ => InitializerAST::Single(ExprAST::Constant(Constant::Int(0).convert_to(target_type))),
+    }
+}
+
+fn is_relational(op: BinaryOperatorAST) -> bool {
+    matches!(
+        op,
+        BinaryOperatorAST::Equal
+            | BinaryOperatorAST::NotEqual
+            | BinaryOperatorAST::LessThan
+            | BinaryOperatorAST::LessOrEqual
+            | BinaryOperatorAST::GreaterThan
+            | BinaryOperatorAST::GreaterOrEqual
+    )
+}
+
+fn operator_symbol(op: BinaryOperatorAST) -> &'static str {
+    match op {
+        BinaryOperatorAST::Add => "+",
+        BinaryOperatorAST::Subtract => "-",
+        BinaryOperatorAST::Multiply => "*",
+        BinaryOperatorAST::Divide => "/",
+        BinaryOperatorAST::Remainder => "%",
+        BinaryOperatorAST::And => "&&",
+        BinaryOperatorAST::Or => "||",
+        BinaryOperatorAST::Equal => "==",
+        BinaryOperatorAST::NotEqual => "!=",
+        BinaryOperatorAST::LessThan => "<",
+        BinaryOperatorAST::LessOrEqual => "<=",
+        BinaryOperatorAST::GreaterThan => ">",
+        BinaryOperatorAST::GreaterOrEqual => ">=",
+    }
+}
+
+// SS: the type as it would be written in C, for diagnostics
+fn type_name(name_type: &Type) -> String {
+    match name_type {
+        Type::Char => "char".to_string(),
+        Type::SChar => "signed char".to_string(),
+        Type::UChar => "unsigned char".to_string(),
+        Type::Int => "int".to_string(),
+        Type::Long => "long".to_string(),
+        Type::UInt => "unsigned int".to_string(),
+        Type::ULong => "unsigned long".to_string(),
+        Type::Double => "double".to_string(),
+        Type::Pointer(referenced) => format!("{} *", type_name(referenced)),
+        Type::Array { element, size } => format!("{}[{}]", type_name(element), size),
+        Type::Function { .. } => "function".to_string(),
+        Type::Structure(tag) => format!("struct {}", source_name(tag)),
+        Type::Union(tag) => format!("union {}", source_name(tag)),
+    }
+}
+
+// SS: strips the suffix added during identifier resolution so diagnostics show the name
+// as written in the source
+fn source_name(unique_name: &str) -> &str {
+    unique_name
+        .rsplit_once('.')
+        .map_or(unique_name, |(name, _)| name)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lexer::Lexer;
+    use crate::loop_labeling::label_loops;
+    use crate::name_generator::NameGenerator;
+    use crate::parse_ast::{
+        BinaryOperatorAST, BlockItemAST, DeclarationAST, ExprAST, ExternalDeclarationAST,
+        FunctionAST, InitializerAST, ProgramAST, StmtAST, SwitchCaseAST,
+    };
+    use crate::parser::Parser;
+    use crate::semantic_analysis::resolve_program;
+    use crate::symbol_table::{IdentifierAttrs, InitialValue, Symbol, SymbolTable};
+    use crate::type_checking::common_type;
+    use crate::type_checking::typecheck_program;
+    use crate::type_table::TypeTable;
+    use crate::types::{Constant, StaticInit, Type};
+
+    fn check(input: &str) -> Result<(ProgramAST, SymbolTable, TypeTable), String> {
+        let lexer = Lexer::new(input.to_string());
+        let mut parser = Parser::new(lexer);
+        let ast = parser.parse().unwrap();
+        let mut name_generator = NameGenerator::new();
+        let ast = resolve_program(ast, &mut name_generator).unwrap();
+        let ast = label_loops(ast, &mut name_generator).unwrap();
+        typecheck_program(ast, &mut name_generator)
+    }
+
+    fn typecheck(input: &str) -> Result<SymbolTable, String> {
+        check(input).map(|(_, symbols, _)| symbols)
+    }
+
+    // SS: the block items of the last function in the program
+    fn body_items(ast: &ProgramAST) -> Vec<BlockItemAST> {
+        match ast.declarations.last() {
+            Some(ExternalDeclarationAST::Function(FunctionAST {
+                body: Some(body), ..
+            })) => body.items.clone(),
+            declaration => panic!("expected a function definition, found {:?}", declaration),
+        }
+    }
+
+    #[test]
+    fn test_typecheck_functions() {
+        // SS: arrange
+        let input = r"int add(int a, int b);
+                    int main(void) {
+                            int putchar(int c);
+                            putchar(65);
+                            return add(1, 2);
+                    }
+                    int add(int a, int b) {
+                            return a + b;
+                    }";
+
+        // SS: act
+        let symbols = typecheck(input).unwrap();
+
+        // SS: assert
+        assert_eq!(
+            symbols["add"],
+            Symbol {
+                symbol_type: Type::Function {
+                    params: vec![Type::Int, Type::Int],
+                    ret: Box::new(Type::Int),
+                },
+                attrs: IdentifierAttrs::Function {
+                    defined: true,
+                    global: true,
+                },
+            }
+        );
+        assert_eq!(
+            symbols["putchar"],
+            Symbol {
+                symbol_type: Type::Function {
+                    params: vec![Type::Int],
+                    ret: Box::new(Type::Int),
+                },
+                attrs: IdentifierAttrs::Function {
+                    defined: false,
+                    global: true,
+                },
+            }
+        );
+        assert_eq!(symbols["a.3"].symbol_type, Type::Int);
+    }
+
+    #[test]
+    fn test_typecheck_wrong_argument_count() {
+        // SS: arrange
+        let input = r"int add(int a, int b);
+                    int main(void) {
+                            return add(1);
+                    }";
+
+        // SS: act
+        let symbols = typecheck(input);
+
+        // SS: assert
+        assert_eq!(
+            symbols,
+            Err(
+                r#"Line 3: Semantic error: Function "add" expects 2 arguments, but was called with 1"#
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_typecheck_conflicting_declarations() {
+        // SS: arrange
+        let input = r"int foo(int a);
+                    int foo(int a, int b);
+                    int main(void) {
+                            return 0;
+                    }";
+
+        // SS: act
+        let symbols = typecheck(input);
+
+        // SS: assert
+        assert_eq!(
+            symbols,
+            Err(
+                r#"Line 2: Semantic error: Incompatible declarations of function "foo""#
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_typecheck_duplicate_definition() {
+        // SS: arrange
+        let input = r"int foo(void) { return 1; }
+                    int foo(void) { return 2; }";
+
+        // SS: act
+        let symbols = typecheck(input);
+
+        // SS: assert
+        assert_eq!(
+            symbols,
+            Err(r#"Line 2: Semantic error: Function "foo" is defined more than once"#.to_string())
+        );
+    }
+
+    #[test]
+    fn test_typecheck_variable_used_as_function() {
+        // SS: arrange
+        let input = r"int main(void) {
+                            int a = 1;
+                            return a();
+                    }";
+
+        // SS: act
+        let symbols = typecheck(input);
+
+        // SS: assert
+        assert_eq!(
+            symbols,
+            Err(r#"Line 3: Semantic error: Variable "a" used as a function"#.to_string())
+        );
+    }
+
+    #[test]
+    fn test_typecheck_static_variables() {
+        // SS: arrange
+        let input = r"int a;
+                    static int b = 2;
+                    extern int c;
+                    static int f(void);
+                    int main(void) {
+                            static int d;
+                            extern int e;
+                            return a + b + c + d + e;
+                    }
+                    int a = 5;";
+
+        // SS: act
+        let symbols = typecheck(input).unwrap();
+
+        // SS: assert
+        let attrs = |name: &str| symbols[name].attrs.clone();
+        assert_eq!(
+            attrs("a"),
+            IdentifierAttrs::Static {
+                init: InitialValue::Initial(vec![StaticInit::Value(Constant::Int(5))]),
+                global: true
+            }
+        );
+        assert_eq!(
+            attrs("b"),
+            IdentifierAttrs::Static {
+                init: InitialValue::Initial(vec![StaticInit::Value(Constant::Int(2))]),
+                global: false
+            }
+        );
+        assert_eq!(
+            attrs("c"),
+            IdentifierAttrs::Static {
+                init: InitialValue::NoInitializer,
+                global: true
+            }
+        );
+        assert_eq!(
+            attrs("f"),
+            IdentifierAttrs::Function {
+                defined: false,
+                global: false
+            }
+        );
+        assert_eq!(
+            attrs("d.0"),
+            IdentifierAttrs::Static {
+                init: InitialValue::Initial(vec![StaticInit::Zero(4)]),
+                global: false
+            }
+        );
+        assert_eq!(
+            attrs("e"),
+            IdentifierAttrs::Static {
+                init: InitialValue::NoInitializer,
+                global: true
+            }
+        );
+    }
+
+    #[test]
+    fn test_typecheck_switch_cases() {
+        // SS: arrange
+        let input = r"int main(void) {
+                        long x = 5;
+                        switch (x) {
+                            case 1: ;
+                            default: ;
+                            case 'a' + (1 ? 2 : x): ;
+                        }
+                    }";
+
+        // SS: act
+        let (ast, _, _) = check(input).unwrap();
+
+        // SS: assert
+        let BlockItemAST::Statement(StmtAST::Switch { cases, .. }) = &body_items(&ast)[1] else {
+            panic!("expected a switch");
+        };
+        assert_eq!(
+            cases,
+            &vec![
+                SwitchCaseAST {
+                    value: Some(Constant::Long(1)),
+                    label: "case.2".to_string(),
+                },
+                SwitchCaseAST {
+                    value: None,
+                    label: "default.3".to_string(),
+                },
+                SwitchCaseAST {
+                    value: Some(Constant::Long(99)),
+                    label: "case.4".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_typecheck_duplicate_case() {
+        // SS: arrange
+        let input = r"int main(void) {
+                        unsigned char c = 3;
+                        switch (c) {
+                            case 3: ;
+                            case 259 - 256: ;
+                        }
+                    }";
+
+        // SS: act
+        let symbols = typecheck(input);
+
+        // SS: assert
+        assert_eq!(
+            symbols,
+            Err(r#"Line 5: Semantic error: Duplicate case value 3"#.to_string())
+        );
+    }
+
+    #[test]
+    fn test_typecheck_non_constant_case() {
+        // SS: arrange
+        let input = r"int main(void) {
+                        int x = 3;
+                        switch (x) {
+                            case x: ;
+                        }
+                    }";
+
+        // SS: act
+        let symbols = typecheck(input);
+
+        // SS: assert
+        assert_eq!(
+            symbols,
+            Err(
+                r#"Line 4: Semantic error: Case label does not reduce to an integer constant"#
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_typecheck_switch_on_double() {
+        // SS: arrange
+        let input = r"int main(void) {
+                        double d = 3.0;
+                        switch (d) { }
+                    }";
+
+        // SS: act
+        let symbols = typecheck(input);
+
+        // SS: assert
+        assert_eq!(
+            symbols,
+            Err(r#"Line 3: Semantic error: Switch on double, which is not an integer"#.to_string())
+        );
+    }
+
+    #[test]
+    fn test_typecheck_tentative_definition() {
+        // SS: arrange
+        let input = r"int a;
+                    int a;";
+
+        // SS: act
+        let symbols = typecheck(input).unwrap();
+
+        // SS: assert
+        assert_eq!(
+            symbols["a"].attrs,
+            IdentifierAttrs::Static {
+                init: InitialValue::Tentative,
+                global: true
+            }
+        );
+    }
+
+    #[test]
+    fn test_typecheck_non_constant_initializer() {
+        // SS: arrange
+        let input = r"int a = 1;
+                    int b = a;";
+
+        // SS: act
+        let symbols = typecheck(input);
+
+        // SS: assert
+        assert_eq!(
+            symbols,
+            Err(r#"Line 2: Semantic error: Non-constant initializer for "b""#.to_string())
+        );
+    }
+
+    #[test]
+    fn test_typecheck_non_constant_static_local_initializer() {
+        // SS: arrange
+        let input = r"int main(void) {
+                            int a = 1;
+                            static int b = a;
+                            return b;
+                    }";
+
+        // SS: act
+        let symbols = typecheck(input);
+
+        // SS: assert
+        assert_eq!(
+            symbols,
+            Err(
+                r#"Line 3: Semantic error: Non-constant initializer for static variable "b""#
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_typecheck_conflicting_linkage() {
+        // SS: arrange
+        let input = r"static int a;
+                    int a;";
+
+        // SS: act
+        let symbols = typecheck(input);
+
+        // SS: assert
+        assert_eq!(
+            symbols,
+            Err(r#"Line 2: Semantic error: Conflicting linkage for variable "a""#.to_string())
+        );
+    }
+
+    #[test]
+    fn test_typecheck_variable_defined_twice() {
+        // SS: arrange
+        let input = r"int a = 1;
+                    int a = 2;";
+
+        // SS: act
+        let symbols = typecheck(input);
+
+        // SS: assert
+        assert_eq!(
+            symbols,
+            Err(r#"Line 2: Semantic error: Variable "a" is defined more than once"#.to_string())
+        );
+    }
+
+    #[test]
+    fn test_typecheck_static_function_follows_non_static() {
+        // SS: arrange
+        let input = r"int f(void);
+                    static int f(void) { return 1; }";
+
+        // SS: act
+        let symbols = typecheck(input);
+
+        // SS: assert
+        assert_eq!(
+            symbols,
+            Err(
+                r#"Line 2: Semantic error: Static declaration of function "f" follows non-static declaration"#
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_typecheck_local_extern_with_initializer() {
+        // SS: arrange
+        let input = r"int main(void) {
+                            extern int a = 1;
+                            return a;
+                    }";
+
+        // SS: act
+        let symbols = typecheck(input);
+
+        // SS: assert
+        assert_eq!(
+            symbols,
+            Err(
+                r#"Line 2: Semantic error: Initializer on local extern declaration of "a""#
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_typecheck_converts_static_initializers() {
+        // SS: arrange
+        let input = r"long a = 5;
+                    unsigned int b = 4294967295;
+                    double c = 3;
+                    int d = 2.9;
+                    unsigned long e;";
+
+        // SS: act
+        let symbols = typecheck(input).unwrap();
+
+        // SS: assert
+        let init = |name: &str| match &symbols[name].attrs {
+            IdentifierAttrs::Static { init, .. } => init.clone(),
+            ref attrs => panic!("{} is not static: {:?}", name, attrs),
+        };
+        assert_eq!(
+            init("a"),
+            InitialValue::Initial(vec![StaticInit::Value(Constant::Long(5))])
+        );
+        assert_eq!(
+            init("b"),
+            InitialValue::Initial(vec![StaticInit::Value(Constant::UInt(u32::MAX))])
+        );
+        assert_eq!(
+            init("c"),
+            InitialValue::Initial(vec![StaticInit::Value(Constant::Double(3.0))])
+        );
+        assert_eq!(
+            init("d"),
+            InitialValue::Initial(vec![StaticInit::Value(Constant::Int(2))])
+        );
+        assert_eq!(init("e"), InitialValue::Tentative);
+        assert_eq!(symbols["e"].symbol_type, Type::ULong);
+    }
+
+    #[test]
+    fn test_typecheck_inserts_implicit_conversions() {
+        // SS: arrange
+        let input = r"int main(void) {
+                            int i = 1;
+                            long l = i + 2L;
+                            return l;
+                    }";
+
+        // SS: act
+        let (ast, _, _) = check(input).unwrap();
+
+        // SS: assert
+        let items = body_items(&ast);
+        assert_eq!(
+            items[1],
+            BlockItemAST::Declaration(DeclarationAST {
+                name: "l.1".to_string(),
+                init: Some(InitializerAST::Single(ExprAST::Binary(
+                    BinaryOperatorAST::Add,
+                    Box::new(ExprAST::Cast {
+                        target_type: Type::Long,
+                        expr: Box::new(ExprAST::Var {
+                            name: "i.0".to_string(),
+                            line: 3
+                        }),
+                        line: 3,
+                    }),
+                    Box::new(ExprAST::Constant(Constant::Long(2))),
+                ))),
+                var_type: Type::Long,
+                storage_class: None,
+                line: 3,
+            })
+        );
+        assert_eq!(
+            items[2],
+            BlockItemAST::Statement(StmtAST::Return(ExprAST::Cast {
+                target_type: Type::Int,
+                expr: Box::new(ExprAST::Var {
+                    name: "l.1".to_string(),
+                    line: 4
+                }),
+                line: 4,
+            }))
+        );
+    }
+
+    #[test]
+    fn test_typecheck_converts_arguments_to_parameter_types() {
+        // SS: arrange
+        let input = r"double half(double x);
+                    int main(void) {
+                            return half(3);
+                    }";
+
+        // SS: act
+        let (ast, _, _) = check(input).unwrap();
+
+        // SS: assert
+        assert_eq!(
+            body_items(&ast)[0],
+            BlockItemAST::Statement(StmtAST::Return(ExprAST::Cast {
+                target_type: Type::Int,
+                expr: Box::new(ExprAST::FunctionCall {
+                    name: "half".to_string(),
+                    args: vec![ExprAST::Cast {
+                        target_type: Type::Double,
+                        expr: Box::new(ExprAST::Constant(Constant::Int(3))),
+                        line: 3,
+                    }],
+                    line: 3,
+                }),
+                line: 3,
+            }))
+        );
+    }
+
+    #[test]
+    fn test_common_type() {
+        // SS: arrange
+        let cases = [
+            (Type::Int, Type::Int, Type::Int),
+            (Type::Int, Type::UInt, Type::UInt),
+            (Type::UInt, Type::Long, Type::Long),
+            (Type::ULong, Type::Long, Type::ULong),
+            (Type::Int, Type::ULong, Type::ULong),
+            (Type::ULong, Type::Double, Type::Double),
+            (Type::Char, Type::UChar, Type::Int),
+            (Type::UChar, Type::UInt, Type::UInt),
+        ];
+
+        for (left, right, expected) in cases {
+            // SS: act
+            let common = common_type(&left, &right);
+
+            // SS: assert
+            assert_eq!(common, expected, "{:?} and {:?}", left, right);
+        }
+    }
+
+    #[test]
+    fn test_typecheck_conflicting_variable_types() {
+        // SS: arrange
+        let input = r"int a;
+                    long a;";
+
+        // SS: act
+        let symbols = typecheck(input);
+
+        // SS: assert
+        assert_eq!(
+            symbols,
+            Err(r#"Line 2: Semantic error: Conflicting types for variable "a""#.to_string())
+        );
+    }
+
+    #[test]
+    fn test_typecheck_integer_operators_on_double() {
+        // SS: arrange
+        let complement = r"int main(void) {
+                            double d = 1.0;
+                            return ~d;
+                    }";
+        let remainder = r"int main(void) {
+                            double d = 1.0;
+                            return 3 % d;
+                    }";
+
+        // SS: act
+        let complement = typecheck(complement);
+        let remainder = typecheck(remainder);
+
+        // SS: assert
+        assert_eq!(
+            complement,
+            Err("Line 3: Semantic error: Operand of ~ has type double".to_string())
+        );
+        assert_eq!(
+            remainder,
+            Err("Line 3: Semantic error: Operand of % has type double".to_string())
+        );
+    }
+
+    #[test]
+    fn test_typecheck_rewrites_subscripts() {
+        // SS: arrange
+        let input = r"int main(void) {
+                            int a[3] = {1, 2};
+                            return a[2];
+                    }";
+
+        // SS: act
+        let (ast, _, _) = check(input).unwrap();
+
+        // SS: assert
+        let int = |val| InitializerAST::Single(ExprAST::Constant(Constant::Int(val)));
+        let items = body_items(&ast);
+        assert_eq!(
+            items[0],
+            BlockItemAST::Declaration(DeclarationAST {
+                name: "a.0".to_string(),
+                init: Some(InitializerAST::Compound(vec![int(1), int(2), int(0)])),
+                var_type: Type::Array {
+                    element: Box::new(Type::Int),
+                    size: 3,
+                },
+                storage_class: None,
+                line: 2,
+            })
+        );
+        assert_eq!(
+            items[1],
+            BlockItemAST::Statement(StmtAST::Return(ExprAST::Dereference {
+                expr: Box::new(ExprAST::Binary(
+                    BinaryOperatorAST::Add,
+                    Box::new(ExprAST::Cast {
+                        target_type: Type::Pointer(Box::new(Type::Int)),
+                        expr: Box::new(ExprAST::AddressOf {
+                            expr: Box::new(ExprAST::Var {
+                                name: "a.0".to_string(),
+                                line: 3,
+                            }),
+                            line: 3,
+                        }),
+                        line: 3,
+                    }),
+                    Box::new(ExprAST::Cast {
+                        target_type: Type::Long,
+                        expr: Box::new(ExprAST::Constant(Constant::Int(2))),
+                        line: 3,
+                    }),
+                )),
+                line: 3,
+            }))
+        );
+    }
+
+    #[test]
+    fn test_typecheck_static_array_initializers() {
+        // SS: arrange
+        let input = r"long a[4] = {1, 2};
+                    double b[2][2] = {{1}, {2, 3}};
+                    int c[3];";
+
+        // SS: act
+        let symbols = typecheck(input).unwrap();
+
+        // SS: assert
+        let init = |name: &str| match &symbols[name].attrs {
+            IdentifierAttrs::Static { init, .. } => init.clone(),
+            attrs => panic!("expected a static variable, found {:?}", attrs),
+        };
+        assert_eq!(
+            init("a"),
+            InitialValue::Initial(vec![
+                StaticInit::Value(Constant::Long(1)),
+                StaticInit::Value(Constant::Long(2)),
+                StaticInit::Zero(16),
+            ])
+        );
+        assert_eq!(
+            init("b"),
+            InitialValue::Initial(vec![
+                StaticInit::Value(Constant::Double(1.0)),
+                StaticInit::Zero(8),
+                StaticInit::Value(Constant::Double(2.0)),
+                StaticInit::Value(Constant::Double(3.0)),
+            ])
+        );
+        assert_eq!(init("c"), InitialValue::Tentative);
+    }
+
+    #[test]
+    fn test_typecheck_invalid_pointer_operations() {
+        // SS: arrange
+        let cases = [
+            (
+                "int *p = 0; double *q = p;",
+                "Line 1: Semantic error: Cannot convert int * to double *",
+            ),
+            (
+                "int i = 0; *i;",
+                "Line 1: Semantic error: Cannot dereference int",
+            ),
+            (
+                "int *p = 0; p * 2;",
+                "Line 1: Semantic error: Invalid operands int * and int for *",
+            ),
+            (
+                "int *p = 0; long *q = 0; p == q;",
+                "Line 1: Semantic error: Incompatible types int * and long *",
+            ),
+            (
+                "int a[2]; int b[2]; a = b;",
+                "Line 1: Semantic error: Cannot assign to an array",
+            ),
+            (
+                "int a[2] = {1, 2, 3};",
+                r#"Line 1: Semantic error: Too many elements in initializer for "a""#,
+            ),
+            (
+                "int a[2] = 1;",
+                r#"Line 1: Semantic error: Array "a" initialized with a scalar"#,
+            ),
+        ];
+
+        for (body, expected) in cases {
+            // SS: act
+            let result = typecheck(&format!("int main(void) {{ {} return 0; }}", body));
+
+            // SS: assert
+            assert_eq!(result, Err(expected.to_string()), "{}", body);
+        }
+    }
+
+    #[test]
+    fn test_typecheck_static_string_and_structure_initializers() {
+        // SS: arrange
+        let input = r#"struct s { char c; long l; };
+                    char a[4] = "hi";
+                    char *p = "hi";
+                    struct s x = {'a', 2};"#;
+
+        // SS: act
+        let symbols = typecheck(input).unwrap();
+
+        // SS: assert
+        let init = |name: &str| match &symbols[name].attrs {
+            IdentifierAttrs::Static { init, .. } => init.clone(),
+            attrs => panic!("expected a static variable, found {:?}", attrs),
+        };
+        assert_eq!(
+            init("a"),
+            InitialValue::Initial(vec![
+                StaticInit::String {
+                    bytes: b"hi".to_vec(),
+                    null_terminated: true,
+                },
+                StaticInit::Zero(1),
+            ])
+        );
+        assert_eq!(
+            init("p"),
+            InitialValue::Initial(vec![StaticInit::Pointer("string.1".to_string())])
+        );
+        assert_eq!(
+            symbols["string.1"],
+            Symbol {
+                symbol_type: Type::Array {
+                    element: Box::new(Type::Char),
+                    size: 3,
+                },
+                attrs: IdentifierAttrs::Constant(StaticInit::String {
+                    bytes: b"hi".to_vec(),
+                    null_terminated: true,
+                }),
+            }
+        );
+        assert_eq!(
+            init("x"),
+            InitialValue::Initial(vec![
+                StaticInit::Value(Constant::Char(97)),
+                StaticInit::Zero(7),
+                StaticInit::Value(Constant::Long(2)),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_typecheck_invalid_structure_operations() {
+        // SS: arrange
+        let cases = [
+            (
+                "struct s { int a; int a; };",
+                r#"Line 1: Semantic error: Duplicate member "a" in struct s"#,
+            ),
+            (
+                "struct s { int a; }; struct s { int b; };",
+                "Line 1: Semantic error: struct s is defined more than once",
+            ),
+            (
+                "struct s; struct s x;",
+                r#"Line 1: Semantic error: Variable "x" has incomplete type struct s"#,
+            ),
+            (
+                "struct s; sizeof(struct s);",
+                "Line 1: Semantic error: Cannot take the size of struct s",
+            ),
+            (
+                "struct s { int a; }; struct s x; x.b;",
+                r#"Line 1: Semantic error: struct s has no member "b""#,
+            ),
+            (
+                "int i = 0; i.a;",
+                r#"Line 1: Semantic error: Member "a" requested in non-structure type int"#,
+            ),
+            (
+                "union u { int a; }; union u x; x->a;",
+                "Line 1: Semantic error: Cannot apply -> to union u",
+            ),
+            (
+                "struct s { int a; }; struct s x; if (x) {}",
+                "Line 1: Semantic error: struct s used where a scalar is required",
+            ),
+            (
+                r#"char c[2] = "abc";"#,
+                r#"Line 1: Semantic error: String literal is too long for "c""#,
+            ),
+            (
+                r#"int a[3] = "ab";"#,
+                "Line 1: Semantic error: Cannot initialize int[3] with a string literal",
+            ),
+            (
+                "long a[1000000000];",
+                "Line 1: Semantic error: Array type long[1000000000] is too large",
+            ),
+            (
+                "sizeof(long (*)[4000000000000000000]);",
+                "Line 1: Semantic error: Array type long[4000000000000000000] is too large",
+            ),
+            (
+                "struct s { char a[2000000000]; char b[2000000000]; };",
+                "Line 1: Semantic error: struct s is too large",
+            ),
+        ];
+
+        for (body, expected) in cases {
+            // SS: act
+            let result = typecheck(&format!("int main(void) {{ {} return 0; }}", body));
+
+            // SS: assert
+            assert_eq!(result, Err(expected.to_string()), "{}", body);
+        }
+    }
+}
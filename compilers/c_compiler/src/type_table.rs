@@ -0,0 +1,145 @@
+use crate::types::Type;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemberEntry {
+    pub name: String,
+    pub member_type: Type,
+    // SS: in bytes from the start of the structure, always zero in a union
+    pub offset: i32,
+}
+
+// SS: the layout of a complete structure or union type
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructEntry {
+    pub alignment: i32,
+    pub size: i32,
+    pub members: Vec<MemberEntry>,
+}
+
+impl StructEntry {
+    // SS: lays out the members following the System V ABI. Each member of a structure goes
+    // at the next offset aligned for its type, the members of a union all start at zero.
+    // The whole is aligned like its most strictly aligned member and padded to a multiple
+    // of that alignment, so that every element of an array of it is aligned as well.
+    pub fn new(members: Vec<(String, Type)>, is_union: bool, types: &TypeTable) -> Self {
+        let mut alignment = 1;
+        let mut size = 0;
+        let mut entries = vec![];
+        for (name, member_type) in members {
+            let member_alignment = member_type.alignment(types);
+            let member_size = member_type.size(types);
+            let offset = if is_union {
+                size = size.max(member_size);
+                0
+            } else {
+                let offset = round_up(size, member_alignment);
+                size = offset + member_size;
+                offset
+            };
+            alignment = alignment.max(member_alignment);
+            entries.push(MemberEntry {
+                name,
+                member_type,
+                offset,
+            });
+        }
+
+        StructEntry {
+            alignment,
+            size: round_up(size, alignment),
+            members: entries,
+        }
+    }
+
+    pub fn member(&self, name: &str) -> Option<&MemberEntry> {
+        self.members.iter().find(|member| member.name == name)
+    }
+}
+
+fn round_up(val: i32, alignment: i32) -> i32 {
+    (val + alignment - 1) / alignment * alignment
+}
+
+// SS: keyed by the unique tags assigned during identifier resolution, a tag that has been
+// declared but whose members have not has no entry
+pub type TypeTable = HashMap<String, StructEntry>;
+
+#[cfg(test)]
+mod tests {
+    use crate::type_table::{MemberEntry, StructEntry, TypeTable};
+    use crate::types::Type;
+
+    #[test]
+    fn test_struct_layout() {
+        // SS: arrange
+        let mut types = TypeTable::new();
+        types.insert(
+            "inner.0".to_string(),
+            StructEntry::new(
+                vec![
+                    ("c".to_string(), Type::Char),
+                    ("d".to_string(), Type::Double),
+                ],
+                false,
+                &types,
+            ),
+        );
+        let members = vec![
+            ("a".to_string(), Type::Char),
+            ("b".to_string(), Type::Int),
+            (
+                "s".to_string(),
+                Type::Array {
+                    element: Box::new(Type::Char),
+                    size: 3,
+                },
+            ),
+            ("i".to_string(), Type::Structure("inner.0".to_string())),
+            ("e".to_string(), Type::Char),
+        ];
+
+        // SS: act
+        let entry = StructEntry::new(members, false, &types);
+
+        // SS: assert
+        let offsets = entry
+            .members
+            .iter()
+            .map(|member| member.offset)
+            .collect::<Vec<_>>();
+        assert_eq!(offsets, vec![0, 4, 8, 16, 32]);
+        assert_eq!(entry.alignment, 8);
+        assert_eq!(entry.size, 40);
+    }
+
+    #[test]
+    fn test_union_layout() {
+        // SS: arrange
+        let members = vec![
+            (
+                "bytes".to_string(),
+                Type::Array {
+                    element: Box::new(Type::UChar),
+                    size: 5,
+                },
+            ),
+            ("i".to_string(), Type::Int),
+        ];
+
+        // SS: act
+        let entry = StructEntry::new(members, true, &TypeTable::new());
+
+        // SS: assert
+        assert_eq!(
+            entry.member("i"),
+            Some(&MemberEntry {
+                name: "i".to_string(),
+                member_type: Type::Int,
+                offset: 0,
+            })
+        );
+        assert_eq!(entry.alignment, 4);
+        assert_eq!(entry.size, 8);
+    }
+}
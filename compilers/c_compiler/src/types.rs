@@ -0,0 +1,474 @@
+use crate::tokens::Tokens;
+use crate::type_table::TypeTable;
+use std::fmt::Display;
+
+// SS: the largest object the compiler can lay out, stack offsets and sizes are 32 bits wide
+pub const MAX_OBJECT_SIZE: i64 = i32::MAX as i64;
+
+// SS: the C types the compiler knows about
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    // SS: plain char is signed on x64 but a distinct type from signed char
+    Char,
+    SChar,
+    UChar,
+    Int,
+    Long,
+    UInt,
+    ULong,
+    Double,
+    Pointer(Box<Type>),
+    Array { element: Box<Type>, size: usize },
+    Function { params: Vec<Type>, ret: Box<Type> },
+    // SS: identified by the unique tag assigned during identifier resolution, the members
+    // and layout are in the type table
+    Structure(String),
+    Union(String),
+}
+
+impl Type {
+    // SS: size in bytes of an object of this type. Type checking rejects the types whose size
+    // is larger than MAX_OBJECT_SIZE, so every type that gets this far fits.
+    pub fn size(&self, types: &TypeTable) -> i32 {
+        self.checked_size(types)
+            .unwrap_or_else(|| panic!("size of {:?} was not checked", self)) as i32
+    }
+
+    // SS: None when the size is larger than MAX_OBJECT_SIZE
+    pub fn checked_size(&self, types: &TypeTable) -> Option<i64> {
+        let size = match self {
+            Type::Array { element, size } => element
+                .checked_size(types)?
+                .checked_mul(i64::try_from(*size).ok()?)?,
+            Type::Structure(tag) | Type::Union(tag) => i64::from(types[tag].size),
+            _ => i64::from(self.scalar_size()),
+        };
+        (size <= MAX_OBJECT_SIZE).then_some(size)
+    }
+
+    // SS: the size of a scalar type, which does not depend on any structure layout
+    pub fn scalar_size(&self) -> i32 {
+        match self {
+            Type::Char | Type::SChar | Type::UChar => 1,
+            Type::Int | Type::UInt => 4,
+            Type::Long | Type::ULong | Type::Double | Type::Pointer(_) => 8,
+            _ => unreachable!("{:?} is not a scalar type", self),
+        }
+    }
+
+    // SS: every scalar type is aligned to its size on x64, an array to its element type and
+    // a structure to its most strictly aligned member
+    pub fn alignment(&self, types: &TypeTable) -> i32 {
+        match self {
+            Type::Array { element, .. } => element.alignment(types),
+            Type::Structure(tag) | Type::Union(tag) => types[tag].alignment,
+            _ => self.size(types),
+        }
+    }
+
+    // SS: the System V ABI aligns array variables of 16 bytes or more to 16 bytes, whatever
+    // their element type
+    pub fn variable_alignment(&self, types: &TypeTable) -> i32 {
+        match self {
+            Type::Array { .. } if self.size(types) >= 16 => 16,
+            _ => self.alignment(types),
+        }
+    }
+
+    // SS: pointers compare as unsigned integers
+    pub fn is_signed(&self) -> bool {
+        matches!(self, Type::Char | Type::SChar | Type::Int | Type::Long)
+    }
+
+    pub fn is_arithmetic(&self) -> bool {
+        self.is_integer() || *self == Type::Double
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.is_character() || matches!(self, Type::Int | Type::Long | Type::UInt | Type::ULong)
+    }
+
+    pub fn is_character(&self) -> bool {
+        matches!(self, Type::Char | Type::SChar | Type::UChar)
+    }
+
+    pub fn is_pointer(&self) -> bool {
+        matches!(self, Type::Pointer(_))
+    }
+
+    // SS: the types that can be compared against zero, i.e. used as a condition
+    pub fn is_scalar(&self) -> bool {
+        self.is_arithmetic() || self.is_pointer()
+    }
+
+    pub fn is_structure(&self) -> bool {
+        matches!(self, Type::Structure(_) | Type::Union(_))
+    }
+
+    // SS: a structure is incomplete until its members have been declared, and so is an
+    // array of them
+    pub fn is_complete(&self, types: &TypeTable) -> bool {
+        match self {
+            Type::Structure(tag) | Type::Union(tag) => types.contains_key(tag),
+            Type::Array { element, .. } => element.is_complete(types),
+            _ => true,
+        }
+    }
+
+    // SS: the type a pointer points to or the element type of an array
+    pub fn referenced_type(&self) -> &Type {
+        match self {
+            Type::Pointer(referenced) => referenced,
+            Type::Array { element, .. } => element,
+            _ => unreachable!("{:?} is neither a pointer nor an array", self),
+        }
+    }
+}
+
+// SS: a constant together with its type, as written in the source or after conversion
+#[derive(Debug, Clone, Copy)]
+pub enum Constant {
+    Char(i8),
+    UChar(u8),
+    Int(i32),
+    Long(i64),
+    UInt(u32),
+    ULong(u64),
+    Double(f64),
+}
+
+impl Constant {
+    // SS: a signed char constant has type char, there is no way to write either in C
+    pub fn constant_type(&self) -> Type {
+        match self {
+            Constant::Char(_) => Type::Char,
+            Constant::UChar(_) => Type::UChar,
+            Constant::Int(_) => Type::Int,
+            Constant::Long(_) => Type::Long,
+            Constant::UInt(_) => Type::UInt,
+            Constant::ULong(_) => Type::ULong,
+            Constant::Double(_) => Type::Double,
+        }
+    }
+
+    pub fn size(&self) -> i32 {
+        match self {
+            Constant::Char(_) | Constant::UChar(_) => 1,
+            Constant::Int(_) | Constant::UInt(_) => 4,
+            Constant::Long(_) | Constant::ULong(_) | Constant::Double(_) => 8,
+        }
+    }
+
+    // SS: converts the constant the way a cast would at run time. Integers wrap around,
+    // doubles are truncated toward zero. Out-of-range double to integer conversions are
+    // undefined in C, Rust saturates them.
+    pub fn convert_to(&self, target: &Type) -> Constant {
+        if let Constant::Double(val) = *self {
+            return match target {
+                Type::Char | Type::SChar => Constant::Char(val as i8),
+                Type::UChar => Constant::UChar(val as u8),
+                Type::Int => Constant::Int(val as i32),
+                Type::Long => Constant::Long(val as i64),
+                Type::UInt => Constant::UInt(val as u32),
+                Type::ULong => Constant::ULong(val as u64),
+                Type::Double => *self,
+                _ => unreachable!("cannot convert a double constant to {:?}", target),
+            };
+        }
+
+        // SS: the two's complement bits of the integer, extended to 64 bits according to
+        // the signedness of its type
+        let bits = self.bits();
+        match target {
+            Type::Char | Type::SChar => Constant::Char(bits as i8),
+            Type::UChar => Constant::UChar(bits as u8),
+            Type::Int => Constant::Int(bits as i32),
+            Type::Long => Constant::Long(bits),
+            Type::UInt => Constant::UInt(bits as u32),
+            // SS: the only pointer constant is the null pointer, which is stored like a ulong
+            Type::ULong | Type::Pointer(_) => Constant::ULong(bits as u64),
+            Type::Double if self.constant_type().is_signed() => Constant::Double(bits as f64),
+            Type::Double => Constant::Double(bits as u64 as f64),
+            _ => unreachable!("cannot convert a constant to {:?}", target),
+        }
+    }
+
+    // SS: the bit pattern of the constant as it is stored in memory, sign or zero extended
+    // to 64 bits
+    pub fn bits(&self) -> i64 {
+        match *self {
+            Constant::Char(val) => val as i64,
+            Constant::UChar(val) => val as i64,
+            Constant::Int(val) => val as i64,
+            Constant::Long(val) => val,
+            Constant::UInt(val) => val as i64,
+            Constant::ULong(val) => val as i64,
+            Constant::Double(val) => val.to_bits() as i64,
+        }
+    }
+}
+
+// SS: constants are equal if they have the same type and bits, so that 0.0 and -0.0 are
+// different constants and a NaN is equal to itself
+impl PartialEq for Constant {
+    fn eq(&self, other: &Self) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other) && self.bits() == other.bits()
+    }
+}
+
+impl Eq for Constant {}
+
+impl std::hash::Hash for Constant {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        self.bits().hash(state);
+    }
+}
+
+// SS: the initial value of an object with static storage duration, an array or structure
+// is initialized by a list of them
+#[derive(Debug, Clone, PartialEq)]
+pub enum StaticInit {
+    Value(Constant),
+    // SS: the given number of zero bytes
+    Zero(i32),
+    // SS: the characters of a string literal, with or without the terminating null
+    String {
+        bytes: Vec<u8>,
+        null_terminated: bool,
+    },
+    // SS: the address of the static object with the given name
+    Pointer(String),
+}
+
+impl StaticInit {
+    pub fn size(&self) -> i32 {
+        match self {
+            StaticInit::Value(val) => val.size(),
+            StaticInit::Zero(size) => *size,
+            StaticInit::String {
+                bytes,
+                null_terminated,
+            } => bytes.len() as i32 + i32::from(*null_terminated),
+            StaticInit::Pointer(_) => 8,
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        match self {
+            StaticInit::Value(val) => val.bits() == 0,
+            StaticInit::Zero(_) => true,
+            StaticInit::String { .. } | StaticInit::Pointer(_) => false,
+        }
+    }
+}
+
+// SS: types print the way C spells them in a cast, i.e. int (*)[3] for a pointer to an
+// array. The declarator is built inside out, starting from the outermost derived type.
+impl Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fn declarator(t: &Type, inner: String) -> String {
+            let base = match t {
+                Type::Char => "char".to_string(),
+                Type::SChar => "signed char".to_string(),
+                Type::UChar => "unsigned char".to_string(),
+                Type::Int => "int".to_string(),
+                Type::Long => "long".to_string(),
+                Type::UInt => "unsigned int".to_string(),
+                Type::ULong => "unsigned long".to_string(),
+                Type::Double => "double".to_string(),
+                Type::Structure(tag) => format!("struct {}", tag),
+                Type::Union(tag) => format!("union {}", tag),
+                Type::Pointer(referenced) => {
+                    return match **referenced {
+                        Type::Array { .. } | Type::Function { .. } => {
+                            declarator(referenced, format!("(*{})", inner))
+                        }
+                        _ => declarator(referenced, format!("*{}", inner)),
+                    };
+                }
+                Type::Array { element, size } => {
+                    return declarator(element, format!("{}[{}]", inner, size));
+                }
+                Type::Function { params, ret } => {
+                    let params = if params.is_empty() {
+                        "void".to_string()
+                    } else {
+                        params
+                            .iter()
+                            .map(Type::to_string)
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    };
+                    return declarator(ret, format!("{}({})", inner, params));
+                }
+            };
+            if inner.is_empty() || inner.starts_with('[') {
+                base + &inner
+            } else {
+                format!("{} {}", base, inner)
+            }
+        }
+        write!(f, "{}", declarator(self, String::new()))
+    }
+}
+
+// SS: constants print with the suffix that gives them their type, there is none for the
+// character types so those print as a cast
+impl Display for Constant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Constant::Char(val) => write!(f, "(char){}", val),
+            Constant::UChar(val) => write!(f, "(unsigned char){}", val),
+            Constant::Int(val) => write!(f, "{}", val),
+            Constant::Long(val) => write!(f, "{}L", val),
+            Constant::UInt(val) => write!(f, "{}U", val),
+            Constant::ULong(val) => write!(f, "{}UL", val),
+            Constant::Double(val) => write!(f, "{:?}", val),
+        }
+    }
+}
+
+impl Display for StaticInit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StaticInit::Value(val) => write!(f, "{}", val),
+            StaticInit::Zero(size) => write!(f, "zero[{}]", size),
+            StaticInit::String {
+                bytes,
+                null_terminated,
+            } => {
+                let spelling = Tokens::StringLiteral(bytes.clone()).spelling();
+                if *null_terminated {
+                    write!(f, "{}", spelling)
+                } else {
+                    write!(f, "{} without null", spelling)
+                }
+            }
+            StaticInit::Pointer(name) => write!(f, "&{}", name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::type_table::TypeTable;
+    use crate::types::{Constant, Type};
+
+    #[test]
+    fn test_convert_integer_constants() {
+        // SS: arrange
+        let cases = [
+            (Constant::Long(4294967298), Type::Int, Constant::Int(2)),
+            (Constant::Int(-1), Type::ULong, Constant::ULong(u64::MAX)),
+            (
+                Constant::UInt(u32::MAX),
+                Type::Long,
+                Constant::Long(4294967295),
+            ),
+            (Constant::ULong(u64::MAX), Type::Int, Constant::Int(-1)),
+            (Constant::Int(-3), Type::UInt, Constant::UInt(4294967293)),
+            (Constant::Int(300), Type::Char, Constant::Char(44)),
+            (Constant::Int(-1), Type::UChar, Constant::UChar(255)),
+            (Constant::Char(-1), Type::UInt, Constant::UInt(u32::MAX)),
+        ];
+
+        for (constant, target, expected) in cases {
+            // SS: act
+            let converted = constant.convert_to(&target);
+
+            // SS: assert
+            assert_eq!(converted, expected, "{:?} to {:?}", constant, target);
+        }
+    }
+
+    #[test]
+    fn test_convert_double_constants() {
+        // SS: arrange
+        let cases = [
+            (Constant::Double(-2.9), Type::Int, Constant::Int(-2)),
+            (
+                Constant::Double(1e19),
+                Type::ULong,
+                Constant::ULong(10000000000000000000),
+            ),
+            (
+                Constant::ULong(u64::MAX),
+                Type::Double,
+                Constant::Double(18446744073709551616.0),
+            ),
+            (Constant::Int(-7), Type::Double, Constant::Double(-7.0)),
+        ];
+
+        for (constant, target, expected) in cases {
+            // SS: act
+            let converted = constant.convert_to(&target);
+
+            // SS: assert
+            assert_eq!(converted, expected, "{:?} to {:?}", constant, target);
+        }
+    }
+
+    #[test]
+    fn test_array_size_and_alignment() {
+        // SS: arrange
+        let array = |element: Type, size: usize| Type::Array {
+            element: Box::new(element),
+            size,
+        };
+        let cases = [
+            (array(Type::Int, 3), 12, 4, 4),
+            (array(array(Type::Long, 2), 3), 48, 8, 16),
+            (Type::Pointer(Box::new(array(Type::Int, 10))), 8, 8, 8),
+        ];
+
+        let types = TypeTable::new();
+
+        for (array_type, size, alignment, variable_alignment) in cases {
+            // SS: act / assert
+            assert_eq!(array_type.size(&types), size, "{:?}", array_type);
+            assert_eq!(array_type.alignment(&types), alignment, "{:?}", array_type);
+            assert_eq!(
+                array_type.variable_alignment(&types),
+                variable_alignment,
+                "{:?}",
+                array_type
+            );
+        }
+    }
+
+    #[test]
+    fn test_display_types() {
+        // SS: arrange
+        let pointer = |t: Type| Type::Pointer(Box::new(t));
+        let array = |element: Type, size: usize| Type::Array {
+            element: Box::new(element),
+            size,
+        };
+        let cases = [
+            (Type::UInt, "unsigned int"),
+            (pointer(pointer(Type::Char)), "char **"),
+            (array(array(Type::Long, 3), 2), "long[2][3]"),
+            (pointer(array(Type::Int, 3)), "int (*)[3]"),
+            (
+                Type::Function {
+                    params: vec![Type::Double, pointer(Type::Structure("s.0".to_string()))],
+                    ret: Box::new(pointer(Type::Int)),
+                },
+                "int *(double, struct s.0 *)",
+            ),
+            (
+                pointer(Type::Function {
+                    params: vec![],
+                    ret: Box::new(Type::Int),
+                }),
+                "int (*)(void)",
+            ),
+        ];
+
+        for (t, expected) in cases {
+            // SS: act / assert
+            assert_eq!(t.to_string(), expected, "{:?}", t);
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use crate::control_flow_graph::{ControlFlow, ControlFlowGraph, Instruction, NodeId};
+use std::collections::HashSet;
+
+// SS: removes blocks that can never execute, jumps to the block that follows anyway and
+// labels that are only reached by falling through to them. Returns whether anything was
+// removed.
+pub fn eliminate_unreachable_code<I: Instruction>(cfg: &mut ControlFlowGraph<I>) -> bool {
+    let mut changed = remove_unreachable_blocks(cfg);
+    changed |= remove_redundant_jumps(cfg);
+    changed |= remove_redundant_labels(cfg);
+    changed |= remove_empty_blocks(cfg);
+    changed
+}
+
+fn remove_unreachable_blocks<I>(cfg: &mut ControlFlowGraph<I>) -> bool {
+    let mut reachable = HashSet::new();
+    let mut stack = vec![NodeId::Entry];
+    while let Some(node) = stack.pop() {
+        if reachable.insert(node) {
+            stack.extend_from_slice(cfg.successors(node));
+        }
+    }
+
+    let unreachable = cfg
+        .blocks
+        .keys()
+        .filter(|id| !reachable.contains(&NodeId::Block(**id)))
+        .copied()
+        .collect::<Vec<_>>();
+    let changed = !unreachable.is_empty();
+    for id in unreachable {
+        cfg.remove_block(id);
+    }
+    changed
+}
+
+// SS: a jump is redundant if every path out of it leads to the next block, which is where
+// execution would fall through to without it
+fn remove_redundant_jumps<I: Instruction>(cfg: &mut ControlFlowGraph<I>) -> bool {
+    let mut changed = false;
+    let ids = cfg.blocks.keys().copied().collect::<Vec<_>>();
+    for id in ids {
+        let next = cfg.next_block(id);
+        let block = cfg.blocks.get_mut(&id).unwrap();
+        let is_jump = matches!(
+            block.instructions.last().map(|i| i.control_flow()),
+            Some(
+                ControlFlow::Jump(_) | ControlFlow::ConditionalJump(_) | ControlFlow::JumpTable(_)
+            )
+        );
+        if is_jump && block.successors.iter().all(|successor| *successor == next) {
+            block.instructions.pop();
+            changed = true;
+        }
+    }
+    changed
+}
+
+// SS: a label is redundant if the only way to reach it is falling through from the
+// previous block. A jump table that remains still names all of its targets, even when they
+// are all the next block.
+fn remove_redundant_labels<I: Instruction>(cfg: &mut ControlFlowGraph<I>) -> bool {
+    let mut changed = false;
+    let ids = cfg.blocks.keys().copied().collect::<Vec<_>>();
+    for id in ids {
+        let previous = cfg.previous_block(id);
+        if let NodeId::Block(previous) = previous
+            && matches!(
+                cfg.blocks[&previous]
+                    .instructions
+                    .last()
+                    .map(|i| i.control_flow()),
+                Some(ControlFlow::JumpTable(_))
+            )
+        {
+            continue;
+        }
+        let block = cfg.blocks.get_mut(&id).unwrap();
+        let is_label = matches!(
+            block.instructions.first().map(|i| i.control_flow()),
+            Some(ControlFlow::Label(_))
+        );
+        if is_label && block.predecessors == [previous] {
+            block.instructions.remove(0);
+            changed = true;
+        }
+    }
+    changed
+}
+
+// SS: a block left without instructions falls through to its only successor, so its
+// predecessors can go there directly
+fn remove_empty_blocks<I>(cfg: &mut ControlFlowGraph<I>) -> bool {
+    let empty = cfg
+        .blocks
+        .iter()
+        .filter(|(_, block)| block.instructions.is_empty())
+        .map(|(id, _)| *id)
+        .collect::<Vec<_>>();
+    let changed = !empty.is_empty();
+    for id in empty {
+        let block = &cfg.blocks[&id];
+        let predecessors = block.predecessors.clone();
+        let successors = block.successors.clone();
+        cfg.remove_block(id);
+        for predecessor in predecessors {
+            for successor in &successors {
+                cfg.add_edge(predecessor, *successor);
+            }
+        }
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::control_flow_graph::ControlFlowGraph;
+    use crate::tacky_ast::{TackyInstructionAST, TackyValueAST};
+    use crate::types::Constant;
+    use crate::unreachable_code_elimination::eliminate_unreachable_code;
+
+    #[test]
+    fn test_eliminate_unreachable_code() {
+        // SS: arrange
+        let x = TackyValueAST::Var("x".to_string());
+        let mut cfg = ControlFlowGraph::new(vec![
+            TackyInstructionAST::Jump("end".to_string()),
+            TackyInstructionAST::Label("dead".to_string()),
+            TackyInstructionAST::Return(TackyValueAST::Constant(Constant::Int(1))),
+            TackyInstructionAST::Label("end".to_string()),
+            TackyInstructionAST::JumpIfZero(x.clone(), "next".to_string()),
+            TackyInstructionAST::Label("next".to_string()),
+            TackyInstructionAST::Return(x.clone()),
+        ]);
+
+        // SS: act
+        eliminate_unreachable_code(&mut cfg);
+
+        // SS: assert
+        assert_eq!(
+            cfg.into_instructions(),
+            vec![TackyInstructionAST::Return(x)]
+        );
+    }
+
+    #[test]
+    fn test_eliminate_unreachable_code_keeps_loops() {
+        // SS: arrange
+        let x = TackyValueAST::Var("x".to_string());
+        let instructions = vec![
+            TackyInstructionAST::Label("loop".to_string()),
+            TackyInstructionAST::JumpIfZero(x.clone(), "end".to_string()),
+            TackyInstructionAST::Jump("loop".to_string()),
+            TackyInstructionAST::Label("end".to_string()),
+            TackyInstructionAST::Return(x),
+        ];
+        let mut cfg = ControlFlowGraph::new(instructions.clone());
+
+        // SS: act
+        eliminate_unreachable_code(&mut cfg);
+
+        // SS: assert
+        assert_eq!(cfg.into_instructions(), instructions);
+    }
+
+    #[test]
+    fn test_eliminate_unreachable_code_keeps_jump_table_targets() {
+        // SS: arrange
+        let x = TackyValueAST::Var("x".to_string());
+        let instructions = vec![
+            TackyInstructionAST::JumpTable {
+                index: x.clone(),
+                targets: vec!["a".to_string(), "b".to_string()],
+            },
+            TackyInstructionAST::Label("a".to_string()),
+            TackyInstructionAST::Return(TackyValueAST::Constant(Constant::Int(1))),
+            TackyInstructionAST::Label("b".to_string()),
+            TackyInstructionAST::Return(x),
+        ];
+        let mut cfg = ControlFlowGraph::new(instructions.clone());
+
+        // SS: act
+        eliminate_unreachable_code(&mut cfg);
+
+        // SS: assert
+        assert_eq!(cfg.into_instructions(), instructions);
+    }
+}
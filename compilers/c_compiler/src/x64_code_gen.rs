@@ -1,76 +1,542 @@
 use crate::assembly_ast::{
-    AssemblyFunctionAST, AssemblyInstructionAST, AssemblyOperandAST, AssemblyProgramAST,
+    AssemblyBinaryOperatorAST, AssemblyFunctionAST, AssemblyInstructionAST, AssemblyOperandAST,
+    AssemblyProgramAST, AssemblyTopLevelAST, AssemblyType, AssemblyUnaryOperatorAST, ConditionCode,
 };
 use crate::emitter::Emitter;
+use crate::reg::Register;
+use crate::target::Target;
+use crate::types::StaticInit;
+use std::collections::HashSet;
 
 pub(crate) struct X64CodeGen<'a, E: Emitter> {
     emitter: &'a mut E,
+    target: Target,
+
+    // SS: functions defined in this translation unit, calls to anything else go through the
+    // PLT on Linux
+    defined_functions: HashSet<String>,
+
+    // SS: constants introduced by the compiler, they are addressed through local labels
+    static_constants: HashSet<String>,
+
+    // SS: the callee-saved registers of the function being emitted, restored on return
+    callee_saved_registers: Vec<Register>,
 }
 
 impl<'a, E: Emitter> X64CodeGen<'a, E> {
-    pub fn new(emitter: &'a mut E) -> Self {
-        X64CodeGen { emitter }
+    pub fn new(emitter: &'a mut E, target: Target) -> Self {
+        X64CodeGen {
+            emitter,
+            target,
+            defined_functions: HashSet::new(),
+            static_constants: HashSet::new(),
+            callee_saved_registers: vec![],
+        }
     }
 
     pub fn emit(&mut self, ast: &AssemblyProgramAST) {
-        self.emitter.emit(".globl _main");
-        self.emit_function(&ast.function_definition);
+        self.defined_functions = ast
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                AssemblyTopLevelAST::Function(function) => Some(function.name.clone()),
+                _ => None,
+            })
+            .collect();
+        self.static_constants = ast
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                AssemblyTopLevelAST::StaticConstant { name, .. } => Some(name.clone()),
+                _ => None,
+            })
+            .collect();
+
+        for item in &ast.items {
+            match item {
+                AssemblyTopLevelAST::Function(function) => self.emit_function(function),
+                AssemblyTopLevelAST::StaticVariable {
+                    name,
+                    global,
+                    alignment,
+                    init,
+                } => self.emit_static_variable(name, *global, *alignment, init),
+                AssemblyTopLevelAST::StaticConstant {
+                    name,
+                    alignment,
+                    init,
+                } => self.emit_static_constant(name, *alignment, init),
+            }
+        }
+
+        // SS: without this note the GNU linker assumes the object needs an executable stack
+        if self.target == Target::Linux {
+            self.emitter
+                .emit("    .section .note.GNU-stack,\"\",@progbits");
+        }
     }
 
     fn emit_function(&mut self, function_ast: &AssemblyFunctionAST) {
-        self.emitter.emit("_main:");
+        let symbol = self.target.symbol_name(&function_ast.name);
+        if function_ast.global {
+            self.emitter.emit(&format!(".globl {}", symbol));
+        }
+        self.emitter.emit("    .text");
+        self.emitter.emit(&format!("{}:", symbol));
+        self.emitter.emit("    pushq %rbp");
+        self.emitter.emit("    movq %rsp, %rbp");
+        for register in &function_ast.callee_saved_registers {
+            self.emitter
+                .emit(&format!("    pushq %{}", register.quadword_name()));
+        }
+        self.callee_saved_registers = function_ast.callee_saved_registers.clone();
         for instruction in &function_ast.instructions {
-            self.emit_instruction(&instruction);
+            self.emit_instruction(instruction);
+        }
+    }
+
+    // SS: zero-initialized variables go into .bss, which takes no space in the object file
+    fn emit_static_variable(
+        &mut self,
+        name: &str,
+        global: bool,
+        alignment: i32,
+        init: &[StaticInit],
+    ) {
+        let symbol = self.target.symbol_name(name);
+        if global {
+            self.emitter.emit(&format!(".globl {}", symbol));
+        }
+        let is_zero = init.iter().all(StaticInit::is_zero);
+        if is_zero {
+            self.emitter.emit("    .bss");
+        } else {
+            self.emitter.emit("    .data");
+        }
+        self.emitter.emit(&format!("    .balign {}", alignment));
+        self.emitter.emit(&format!("{}:", symbol));
+        if is_zero {
+            let size = init.iter().map(StaticInit::size).sum::<i32>();
+            self.emitter.emit(&format!("    .zero {}", size));
+            return;
+        }
+        for init in init {
+            self.emit_static_init(init);
+        }
+    }
+
+    fn emit_static_init(&mut self, init: &StaticInit) {
+        match init {
+            StaticInit::Value(val) => {
+                let directive = match init.size() {
+                    1 => ".byte",
+                    4 => ".long",
+                    _ => ".quad",
+                };
+                self.emitter
+                    .emit(&format!("    {} {}", directive, val.bits()))
+            }
+            StaticInit::Zero(size) => self.emitter.emit(&format!("    .zero {}", size)),
+            StaticInit::String {
+                bytes,
+                null_terminated,
+            } => {
+                let directive = if *null_terminated { ".asciz" } else { ".ascii" };
+                self.emitter
+                    .emit(&format!("    {} \"{}\"", directive, escape(bytes)))
+            }
+            StaticInit::Pointer(name) => {
+                let label = if self.static_constants.contains(name) {
+                    self.target.local_label(name)
+                } else {
+                    self.target.symbol_name(name)
+                };
+                self.emitter.emit(&format!("    .quad {}", label))
+            }
+        }
+    }
+
+    // SS: Mach-O keeps literals in sections by size, a 16-byte aligned constant is padded
+    // to 16 bytes so it can be an operand of a packed instruction. String literals go in
+    // their own section there.
+    fn emit_static_constant(&mut self, name: &str, alignment: i32, init: &StaticInit) {
+        let is_string = matches!(init, StaticInit::String { .. });
+        match self.target {
+            Target::Linux => self.emitter.emit("    .section .rodata"),
+            Target::MacOs if is_string => self.emitter.emit("    .cstring"),
+            Target::MacOs if alignment == 16 => self.emitter.emit("    .literal16"),
+            Target::MacOs => self.emitter.emit("    .literal8"),
+        }
+        self.emitter.emit(&format!("    .balign {}", alignment));
+        self.emitter
+            .emit(&format!("{}:", self.target.local_label(name)));
+        self.emit_static_init(init);
+        if alignment == 16 {
+            self.emitter.emit("    .quad 0");
         }
     }
 
     fn emit_instruction(&mut self, instruction: &AssemblyInstructionAST) {
         match instruction {
-            AssemblyInstructionAST::Mov { src, dst } => {
-                let src_str = self.emit_operand(src);
-                let dst_str = self.emit_operand(dst);
+            AssemblyInstructionAST::Mov { asm_type, src, dst } => {
+                let src_str = self.emit_operand(src, *asm_type);
+                let dst_str = self.emit_operand(dst, *asm_type);
+                let mnemonic = match asm_type {
+                    AssemblyType::Double => "movsd".to_string(),
+                    asm_type => format!("mov{}", suffix(*asm_type)),
+                };
+                self.emitter
+                    .emit(&format!("    {} {}, {}", mnemonic, src_str, dst_str));
+            }
+            AssemblyInstructionAST::Movsx {
+                src_type,
+                dst_type,
+                src,
+                dst,
+            } => {
+                let src_str = self.emit_operand(src, *src_type);
+                let dst_str = self.emit_operand(dst, *dst_type);
+                self.emitter.emit(&format!(
+                    "    movs{}{} {}, {}",
+                    suffix(*src_type),
+                    suffix(*dst_type),
+                    src_str,
+                    dst_str
+                ));
+            }
+            // SS: a 32-bit mov clears the upper half of its destination register
+            AssemblyInstructionAST::MovZeroExtend {
+                src_type: AssemblyType::Longword,
+                src,
+                dst,
+                ..
+            } => {
+                let src_str = self.emit_operand(src, AssemblyType::Longword);
+                let dst_str = self.emit_operand(dst, AssemblyType::Longword);
                 self.emitter
                     .emit(&format!("    movl {}, {}", src_str, dst_str));
             }
+            AssemblyInstructionAST::MovZeroExtend {
+                src_type,
+                dst_type,
+                src,
+                dst,
+            } => {
+                let src_str = self.emit_operand(src, *src_type);
+                let dst_str = self.emit_operand(dst, *dst_type);
+                self.emitter.emit(&format!(
+                    "    movz{}{} {}, {}",
+                    suffix(*src_type),
+                    suffix(*dst_type),
+                    src_str,
+                    dst_str
+                ));
+            }
+            AssemblyInstructionAST::Lea { src, dst } => {
+                let src_str = self.emit_operand(src, AssemblyType::Quadword);
+                let dst_str = self.emit_operand(dst, AssemblyType::Quadword);
+                self.emitter
+                    .emit(&format!("    leaq {}, {}", src_str, dst_str));
+            }
+            AssemblyInstructionAST::Cvttsd2si { asm_type, src, dst } => {
+                let src_str = self.emit_operand(src, AssemblyType::Double);
+                let dst_str = self.emit_operand(dst, *asm_type);
+                self.emitter.emit(&format!(
+                    "    cvttsd2si{} {}, {}",
+                    suffix(*asm_type),
+                    src_str,
+                    dst_str
+                ));
+            }
+            AssemblyInstructionAST::Cvtsi2sd { asm_type, src, dst } => {
+                let src_str = self.emit_operand(src, *asm_type);
+                let dst_str = self.emit_operand(dst, AssemblyType::Double);
+                self.emitter.emit(&format!(
+                    "    cvtsi2sd{} {}, {}",
+                    suffix(*asm_type),
+                    src_str,
+                    dst_str
+                ));
+            }
+            AssemblyInstructionAST::Unary {
+                op,
+                asm_type,
+                operand,
+            } => {
+                let op_str = match op {
+                    AssemblyUnaryOperatorAST::Neg => "neg",
+                    AssemblyUnaryOperatorAST::Not => "not",
+                };
+                let operand_str = self.emit_operand(operand, *asm_type);
+                self.emitter.emit(&format!(
+                    "    {}{} {}",
+                    op_str,
+                    suffix(*asm_type),
+                    operand_str
+                ));
+            }
+            AssemblyInstructionAST::Binary {
+                op,
+                asm_type,
+                src,
+                dst,
+            } => {
+                let mnemonic = match (op, asm_type) {
+                    (AssemblyBinaryOperatorAST::Add, AssemblyType::Double) => "addsd".to_string(),
+                    (AssemblyBinaryOperatorAST::Sub, AssemblyType::Double) => "subsd".to_string(),
+                    (AssemblyBinaryOperatorAST::Mult, AssemblyType::Double) => "mulsd".to_string(),
+                    (AssemblyBinaryOperatorAST::DivDouble, _) => "divsd".to_string(),
+                    (AssemblyBinaryOperatorAST::Xor, AssemblyType::Double) => "xorpd".to_string(),
+                    (op, asm_type) => {
+                        let op_str = match op {
+                            AssemblyBinaryOperatorAST::Add => "add",
+                            AssemblyBinaryOperatorAST::Sub => "sub",
+                            AssemblyBinaryOperatorAST::Mult => "imul",
+                            AssemblyBinaryOperatorAST::And => "and",
+                            AssemblyBinaryOperatorAST::Or => "or",
+                            AssemblyBinaryOperatorAST::Xor => "xor",
+                            AssemblyBinaryOperatorAST::Shl => "shl",
+                            AssemblyBinaryOperatorAST::Shr => "shr",
+                            AssemblyBinaryOperatorAST::DivDouble => unreachable!("handled above"),
+                        };
+                        format!("{}{}", op_str, suffix(*asm_type))
+                    }
+                };
+                let src_str = self.emit_operand(src, *asm_type);
+                let dst_str = self.emit_operand(dst, *asm_type);
+                self.emitter
+                    .emit(&format!("    {} {}, {}", mnemonic, src_str, dst_str));
+            }
+            AssemblyInstructionAST::Idiv { asm_type, operand } => {
+                let operand_str = self.emit_operand(operand, *asm_type);
+                self.emitter
+                    .emit(&format!("    idiv{} {}", suffix(*asm_type), operand_str));
+            }
+            AssemblyInstructionAST::Div { asm_type, operand } => {
+                let operand_str = self.emit_operand(operand, *asm_type);
+                self.emitter
+                    .emit(&format!("    div{} {}", suffix(*asm_type), operand_str));
+            }
+            AssemblyInstructionAST::Cdq(AssemblyType::Quadword) => {
+                self.emitter.emit("    cqo");
+            }
+            AssemblyInstructionAST::Cdq(_) => {
+                self.emitter.emit("    cdq");
+            }
+            AssemblyInstructionAST::Cmp { asm_type, src, dst } => {
+                let src_str = self.emit_operand(src, *asm_type);
+                let dst_str = self.emit_operand(dst, *asm_type);
+                let mnemonic = match asm_type {
+                    AssemblyType::Double => "comisd".to_string(),
+                    asm_type => format!("cmp{}", suffix(*asm_type)),
+                };
+                self.emitter
+                    .emit(&format!("    {} {}, {}", mnemonic, src_str, dst_str));
+            }
+            AssemblyInstructionAST::Jmp(target) => {
+                let label = self.target.local_label(target);
+                self.emitter.emit(&format!("    jmp {}", label));
+            }
+            AssemblyInstructionAST::JmpCC { condition, target } => {
+                self.emitter.emit(&format!(
+                    "    j{} {}",
+                    condition_suffix(*condition),
+                    self.target.local_label(target)
+                ));
+            }
+            AssemblyInstructionAST::JmpTable {
+                index,
+                table,
+                targets,
+            } => self.emit_jump_table(index, table, targets),
+            AssemblyInstructionAST::SetCC { condition, operand } => {
+                let operand_str = self.emit_operand(operand, AssemblyType::Byte);
+                self.emitter.emit(&format!(
+                    "    set{} {}",
+                    condition_suffix(*condition),
+                    operand_str
+                ));
+            }
+            AssemblyInstructionAST::Label(name) => {
+                self.emitter
+                    .emit(&format!("{}:", self.target.local_label(name)));
+            }
+            AssemblyInstructionAST::AllocateStack(size) => {
+                self.emitter.emit(&format!("    subq ${}, %rsp", size));
+            }
+            AssemblyInstructionAST::DeallocateStack(size) => {
+                self.emitter.emit(&format!("    addq ${}, %rsp", size));
+            }
+            AssemblyInstructionAST::Push(operand) => {
+                // SS: push always operates on 8 bytes
+                let operand_str = self.emit_operand(operand, AssemblyType::Quadword);
+                self.emitter.emit(&format!("    pushq {}", operand_str));
+            }
+            AssemblyInstructionAST::Call(name) => {
+                let symbol = self.target.symbol_name(name);
+                if self.target == Target::Linux && !self.defined_functions.contains(name) {
+                    self.emitter.emit(&format!("    call {}@PLT", symbol));
+                } else {
+                    self.emitter.emit(&format!("    call {}", symbol));
+                }
+            }
             AssemblyInstructionAST::Ret => {
+                // SS: the callee-saved registers were pushed right below the saved rbp
+                if !self.callee_saved_registers.is_empty() {
+                    self.emitter.emit(&format!(
+                        "    leaq -{}(%rbp), %rsp",
+                        8 * self.callee_saved_registers.len()
+                    ));
+                    for register in self.callee_saved_registers.iter().rev() {
+                        self.emitter
+                            .emit(&format!("    popq %{}", register.quadword_name()));
+                    }
+                }
+                self.emitter.emit("    movq %rbp, %rsp");
+                self.emitter.emit("    popq %rbp");
                 self.emitter.emit("    ret");
             }
         }
     }
 
-    fn emit_operand(&self, operand: &AssemblyOperandAST) -> String {
+    // SS: an absolute address of the table would need a relocation the linker cannot apply
+    // in a position-independent executable, so each entry is the offset of its target from
+    // the start of the table instead. The offsets are known when assembling and the table
+    // stays read-only. Mach-O keeps it in the code right after the jump, ELF in .rodata.
+    fn emit_jump_table(&mut self, index: &AssemblyOperandAST, table: &str, targets: &[String]) {
+        let table = self.target.local_label(table);
+        let index = self.emit_operand(index, AssemblyType::Quadword);
+        self.emitter
+            .emit(&format!("    leaq {}(%rip), %r11", table));
+        self.emitter
+            .emit(&format!("    movslq (%r11,{},4), %r10", index));
+        self.emitter.emit("    addq %r11, %r10");
+        self.emitter.emit("    jmp *%r10");
+        match self.target {
+            Target::Linux => self.emitter.emit("    .section .rodata"),
+            Target::MacOs => self.emitter.emit("    .data_region jt32"),
+        }
+        self.emitter.emit("    .balign 4");
+        self.emitter.emit(&format!("{}:", table));
+        for target in targets {
+            self.emitter.emit(&format!(
+                "    .long {} - {}",
+                self.target.local_label(target),
+                table
+            ));
+        }
+        match self.target {
+            Target::Linux => self.emitter.emit("    .text"),
+            Target::MacOs => self.emitter.emit("    .end_data_region"),
+        }
+    }
+
+    // SS: registers are named after the size of the operand
+    fn emit_operand(&self, operand: &AssemblyOperandAST, asm_type: AssemblyType) -> String {
         match operand {
             AssemblyOperandAST::Immediate(val) => format!("${}", val),
-            AssemblyOperandAST::Register(reg) => format!("%{}", reg),
+            AssemblyOperandAST::Register(reg) => format!("%{}", reg.name(asm_type)),
+            AssemblyOperandAST::Stack(offset) => format!("{}(%rbp)", offset),
+            // SS: addresses are always 64 bits wide, whatever the size of the operand
+            AssemblyOperandAST::Memory(reg, offset) => {
+                format!("{}(%{})", offset, reg.quadword_name())
+            }
+            AssemblyOperandAST::Indexed { base, index, scale } => format!(
+                "(%{},%{},{})",
+                base.quadword_name(),
+                index.quadword_name(),
+                scale
+            ),
+            AssemblyOperandAST::Data(name, offset) => {
+                let label = if self.static_constants.contains(name) {
+                    self.target.local_label(name)
+                } else {
+                    self.target.symbol_name(name)
+                };
+                if *offset == 0 {
+                    format!("{}(%rip)", label)
+                } else {
+                    format!("{}+{}(%rip)", label, offset)
+                }
+            }
+            AssemblyOperandAST::Pseudo(name) | AssemblyOperandAST::PseudoMem(name, _) => {
+                panic!("pseudo-register {} has not been assigned a location", name)
+            }
         }
     }
 }
 
+// SS: printable characters other than quotes and backslashes are kept, everything else
+// becomes an octal escape
+fn escape(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&byte| match byte {
+            b'"' | b'\\' => format!("\\{}", byte as char),
+            b' '..=b'~' => (byte as char).to_string(),
+            _ => format!("\\{:03o}", byte),
+        })
+        .collect()
+}
+
+fn suffix(asm_type: AssemblyType) -> &'static str {
+    match asm_type {
+        AssemblyType::Byte => "b",
+        AssemblyType::Longword => "l",
+        AssemblyType::Quadword => "q",
+        AssemblyType::Double => "sd",
+    }
+}
+
+fn condition_suffix(condition: ConditionCode) -> &'static str {
+    match condition {
+        ConditionCode::E => "e",
+        ConditionCode::NE => "ne",
+        ConditionCode::G => "g",
+        ConditionCode::GE => "ge",
+        ConditionCode::L => "l",
+        ConditionCode::LE => "le",
+        ConditionCode::A => "a",
+        ConditionCode::AE => "ae",
+        ConditionCode::B => "b",
+        ConditionCode::BE => "be",
+        ConditionCode::P => "p",
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::assembly_ast;
     use crate::string_emitter::StringEmitter;
+    use crate::target::Target;
     use crate::x64_code_gen::X64CodeGen;
 
     #[test]
     fn test_x64_code_gen() {
         // SS: arrange
         let assembly_ast = assembly_ast::AssemblyProgramAST {
-            function_definition: assembly_ast::AssemblyFunctionAST {
-                name: "main".to_string(),
-                instructions: vec![
-                    assembly_ast::AssemblyInstructionAST::Mov {
-                        src: assembly_ast::AssemblyOperandAST::Immediate(2),
-                        dst: assembly_ast::AssemblyOperandAST::Register(crate::reg::Register::EAX),
-                    },
-                    assembly_ast::AssemblyInstructionAST::Ret,
-                ],
-            },
+            items: vec![assembly_ast::AssemblyTopLevelAST::Function(
+                assembly_ast::AssemblyFunctionAST {
+                    name: "main".to_string(),
+                    global: true,
+                    instructions: vec![
+                        assembly_ast::AssemblyInstructionAST::Mov {
+                            asm_type: assembly_ast::AssemblyType::Longword,
+                            src: assembly_ast::AssemblyOperandAST::Immediate(2),
+                            dst: assembly_ast::AssemblyOperandAST::Register(
+                                crate::reg::Register::EAX,
+                            ),
+                        },
+                        assembly_ast::AssemblyInstructionAST::Ret,
+                    ],
+                    callee_saved_registers: vec![],
+                },
+            )],
         };
 
         // SS: act
         let mut emitter = StringEmitter::new();
-        let mut code_gen = X64CodeGen::new(&mut emitter);
+        let mut code_gen = X64CodeGen::new(&mut emitter, Target::MacOs);
         code_gen.emit(&assembly_ast);
 
         // SS: assert
@@ -78,4 +544,794 @@ mod tests {
         assert!(emitted_code.contains(&"    movl $2, %eax".to_string()));
         assert!(emitted_code.contains(&"    ret".to_string()));
     }
+
+    #[test]
+    fn test_x64_code_gen_division() {
+        // SS: arrange
+        let assembly_ast = assembly_ast::AssemblyProgramAST {
+            items: vec![assembly_ast::AssemblyTopLevelAST::Function(
+                assembly_ast::AssemblyFunctionAST {
+                    name: "main".to_string(),
+                    global: true,
+                    instructions: vec![
+                        assembly_ast::AssemblyInstructionAST::Mov {
+                            asm_type: assembly_ast::AssemblyType::Longword,
+                            src: assembly_ast::AssemblyOperandAST::Immediate(7),
+                            dst: assembly_ast::AssemblyOperandAST::Register(
+                                crate::reg::Register::EAX,
+                            ),
+                        },
+                        assembly_ast::AssemblyInstructionAST::Cdq(
+                            assembly_ast::AssemblyType::Longword,
+                        ),
+                        assembly_ast::AssemblyInstructionAST::Mov {
+                            asm_type: assembly_ast::AssemblyType::Longword,
+                            src: assembly_ast::AssemblyOperandAST::Immediate(2),
+                            dst: assembly_ast::AssemblyOperandAST::Register(
+                                crate::reg::Register::R10D,
+                            ),
+                        },
+                        assembly_ast::AssemblyInstructionAST::Idiv {
+                            asm_type: assembly_ast::AssemblyType::Longword,
+                            operand: assembly_ast::AssemblyOperandAST::Register(
+                                crate::reg::Register::R10D,
+                            ),
+                        },
+                        assembly_ast::AssemblyInstructionAST::Binary {
+                            asm_type: assembly_ast::AssemblyType::Longword,
+                            op: assembly_ast::AssemblyBinaryOperatorAST::Add,
+                            src: assembly_ast::AssemblyOperandAST::Register(
+                                crate::reg::Register::EDX,
+                            ),
+                            dst: assembly_ast::AssemblyOperandAST::Register(
+                                crate::reg::Register::EAX,
+                            ),
+                        },
+                        assembly_ast::AssemblyInstructionAST::Ret,
+                    ],
+                    callee_saved_registers: vec![],
+                },
+            )],
+        };
+
+        // SS: act
+        let mut emitter = StringEmitter::new();
+        let mut code_gen = X64CodeGen::new(&mut emitter, Target::MacOs);
+        code_gen.emit(&assembly_ast);
+
+        // SS: assert
+        let emitted_code = emitter.buffer;
+        assert!(emitted_code.contains(&"    cdq".to_string()));
+        assert!(emitted_code.contains(&"    movl $2, %r10d".to_string()));
+        assert!(emitted_code.contains(&"    idivl %r10d".to_string()));
+        assert!(emitted_code.contains(&"    addl %edx, %eax".to_string()));
+    }
+
+    #[test]
+    fn test_x64_code_gen_stack_slots() {
+        // SS: arrange
+        let input = r"int main(void) {
+                            return 1 + 2 * -3;
+                    }"
+        .to_string();
+        let lexer = crate::lexer::Lexer::new(input);
+        let ast = crate::parser::Parser::new(lexer).parse().unwrap();
+        let mut name_generator = crate::name_generator::NameGenerator::new();
+        let ast = crate::semantic_analysis::resolve_program(ast, &mut name_generator).unwrap();
+        let (ast, mut symbols, types) =
+            crate::type_checking::typecheck_program(ast, &mut name_generator).unwrap();
+        let tacky_ast = crate::tacky_generation::generate_tacky_program_ast(
+            ast,
+            &mut symbols,
+            &types,
+            &mut name_generator,
+        );
+        let assembly_ast = crate::ir_generation::generate_assembly_program_ast(
+            tacky_ast,
+            &mut symbols,
+            &types,
+            &mut name_generator,
+        );
+        let assembly_ast =
+            crate::pseudo_replacement::replace_pseudo_registers(assembly_ast, &symbols, &types);
+        let assembly_ast = crate::instruction_fixup::fixup_instructions(assembly_ast);
+
+        // SS: act
+        let mut emitter = StringEmitter::new();
+        let mut code_gen = X64CodeGen::new(&mut emitter, Target::MacOs);
+        code_gen.emit(&assembly_ast);
+
+        // SS: assert
+        assert_eq!(
+            emitter.buffer,
+            vec![
+                ".globl _main",
+                "    .text",
+                "_main:",
+                "    pushq %rbp",
+                "    movq %rsp, %rbp",
+                "    subq $16, %rsp",
+                "    movl $3, -4(%rbp)",
+                "    negl -4(%rbp)",
+                "    movl $2, -8(%rbp)",
+                "    movl -8(%rbp), %r11d",
+                "    imull -4(%rbp), %r11d",
+                "    movl %r11d, -8(%rbp)",
+                "    movl $1, -12(%rbp)",
+                "    movl -8(%rbp), %r10d",
+                "    addl %r10d, -12(%rbp)",
+                "    movl -12(%rbp), %eax",
+                "    movq %rbp, %rsp",
+                "    popq %rbp",
+                "    ret",
+                "    movl $0, %eax",
+                "    movq %rbp, %rsp",
+                "    popq %rbp",
+                "    ret",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_x64_code_gen_jumps() {
+        // SS: arrange
+        let assembly_ast = assembly_ast::AssemblyProgramAST {
+            items: vec![assembly_ast::AssemblyTopLevelAST::Function(
+                assembly_ast::AssemblyFunctionAST {
+                    name: "main".to_string(),
+                    global: true,
+                    instructions: vec![
+                        assembly_ast::AssemblyInstructionAST::Cmp {
+                            asm_type: assembly_ast::AssemblyType::Longword,
+                            src: assembly_ast::AssemblyOperandAST::Immediate(0),
+                            dst: assembly_ast::AssemblyOperandAST::Stack(-4),
+                        },
+                        assembly_ast::AssemblyInstructionAST::JmpCC {
+                            condition: assembly_ast::ConditionCode::E,
+                            target: "and_false.0".to_string(),
+                        },
+                        assembly_ast::AssemblyInstructionAST::SetCC {
+                            condition: assembly_ast::ConditionCode::GE,
+                            operand: assembly_ast::AssemblyOperandAST::Stack(-8),
+                        },
+                        assembly_ast::AssemblyInstructionAST::Jmp("and_end.1".to_string()),
+                        assembly_ast::AssemblyInstructionAST::Label("and_false.0".to_string()),
+                    ],
+                    callee_saved_registers: vec![],
+                },
+            )],
+        };
+
+        // SS: act
+        let mut emitter = StringEmitter::new();
+        let mut code_gen = X64CodeGen::new(&mut emitter, Target::MacOs);
+        code_gen.emit(&assembly_ast);
+
+        // SS: assert
+        let emitted_code = emitter.buffer;
+        assert!(emitted_code.contains(&"    cmpl $0, -4(%rbp)".to_string()));
+        assert!(emitted_code.contains(&"    je Land_false.0".to_string()));
+        assert!(emitted_code.contains(&"    setge -8(%rbp)".to_string()));
+        assert!(emitted_code.contains(&"    jmp Land_end.1".to_string()));
+        assert!(emitted_code.contains(&"Land_false.0:".to_string()));
+    }
+
+    #[test]
+    fn test_x64_code_gen_function_call() {
+        // SS: arrange
+        let assembly_ast = assembly_ast::AssemblyProgramAST {
+            items: vec![assembly_ast::AssemblyTopLevelAST::Function(
+                assembly_ast::AssemblyFunctionAST {
+                    name: "foo".to_string(),
+                    global: true,
+                    instructions: vec![
+                        assembly_ast::AssemblyInstructionAST::AllocateStack(8),
+                        assembly_ast::AssemblyInstructionAST::Push(
+                            assembly_ast::AssemblyOperandAST::Register(crate::reg::Register::EAX),
+                        ),
+                        assembly_ast::AssemblyInstructionAST::Push(
+                            assembly_ast::AssemblyOperandAST::Immediate(7),
+                        ),
+                        assembly_ast::AssemblyInstructionAST::Call("bar".to_string()),
+                        assembly_ast::AssemblyInstructionAST::DeallocateStack(24),
+                    ],
+                    callee_saved_registers: vec![],
+                },
+            )],
+        };
+
+        // SS: act
+        let mut emitter = StringEmitter::new();
+        let mut code_gen = X64CodeGen::new(&mut emitter, Target::MacOs);
+        code_gen.emit(&assembly_ast);
+
+        // SS: assert
+        assert_eq!(
+            emitter.buffer,
+            vec![
+                ".globl _foo",
+                "    .text",
+                "_foo:",
+                "    pushq %rbp",
+                "    movq %rsp, %rbp",
+                "    subq $8, %rsp",
+                "    pushq %rax",
+                "    pushq $7",
+                "    call _bar",
+                "    addq $24, %rsp",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_x64_code_gen_linux() {
+        // SS: arrange
+        let assembly_ast = assembly_ast::AssemblyProgramAST {
+            items: vec![
+                assembly_ast::AssemblyTopLevelAST::Function(assembly_ast::AssemblyFunctionAST {
+                    name: "foo".to_string(),
+                    global: true,
+                    instructions: vec![assembly_ast::AssemblyInstructionAST::Ret],
+                    callee_saved_registers: vec![],
+                }),
+                assembly_ast::AssemblyTopLevelAST::Function(assembly_ast::AssemblyFunctionAST {
+                    name: "main".to_string(),
+                    global: true,
+                    instructions: vec![
+                        assembly_ast::AssemblyInstructionAST::Call("foo".to_string()),
+                        assembly_ast::AssemblyInstructionAST::Call("putchar".to_string()),
+                        assembly_ast::AssemblyInstructionAST::Jmp("loop.0".to_string()),
+                        assembly_ast::AssemblyInstructionAST::Label("loop.0".to_string()),
+                    ],
+                    callee_saved_registers: vec![],
+                }),
+            ],
+        };
+
+        // SS: act
+        let mut emitter = StringEmitter::new();
+        let mut code_gen = X64CodeGen::new(&mut emitter, Target::Linux);
+        code_gen.emit(&assembly_ast);
+
+        // SS: assert
+        assert_eq!(
+            emitter.buffer,
+            vec![
+                ".globl foo",
+                "    .text",
+                "foo:",
+                "    pushq %rbp",
+                "    movq %rsp, %rbp",
+                "    movq %rbp, %rsp",
+                "    popq %rbp",
+                "    ret",
+                ".globl main",
+                "    .text",
+                "main:",
+                "    pushq %rbp",
+                "    movq %rsp, %rbp",
+                "    call foo",
+                "    call putchar@PLT",
+                "    jmp .Lloop.0",
+                ".Lloop.0:",
+                "    .section .note.GNU-stack,\"\",@progbits",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_x64_code_gen_jump_table() {
+        // SS: arrange
+        let assembly_ast = assembly_ast::AssemblyProgramAST {
+            items: vec![assembly_ast::AssemblyTopLevelAST::Function(
+                assembly_ast::AssemblyFunctionAST {
+                    name: "main".to_string(),
+                    global: true,
+                    instructions: vec![
+                        assembly_ast::AssemblyInstructionAST::JmpTable {
+                            index: assembly_ast::AssemblyOperandAST::Register(
+                                crate::reg::Register::EAX,
+                            ),
+                            table: "jump_table.2".to_string(),
+                            targets: vec!["case.0".to_string(), "case.1".to_string()],
+                        },
+                        assembly_ast::AssemblyInstructionAST::Label("case.0".to_string()),
+                        assembly_ast::AssemblyInstructionAST::Label("case.1".to_string()),
+                    ],
+                    callee_saved_registers: vec![],
+                },
+            )],
+        };
+
+        // SS: act
+        let mut linux_emitter = StringEmitter::new();
+        X64CodeGen::new(&mut linux_emitter, Target::Linux).emit(&assembly_ast);
+        let mut macos_emitter = StringEmitter::new();
+        X64CodeGen::new(&mut macos_emitter, Target::MacOs).emit(&assembly_ast);
+
+        // SS: assert
+        assert_eq!(
+            linux_emitter.buffer[5..],
+            vec![
+                "    leaq .Ljump_table.2(%rip), %r11",
+                "    movslq (%r11,%rax,4), %r10",
+                "    addq %r11, %r10",
+                "    jmp *%r10",
+                "    .section .rodata",
+                "    .balign 4",
+                ".Ljump_table.2:",
+                "    .long .Lcase.0 - .Ljump_table.2",
+                "    .long .Lcase.1 - .Ljump_table.2",
+                "    .text",
+                ".Lcase.0:",
+                ".Lcase.1:",
+                "    .section .note.GNU-stack,\"\",@progbits",
+            ]
+        );
+        assert_eq!(
+            macos_emitter.buffer[9..14],
+            vec![
+                "    .data_region jt32",
+                "    .balign 4",
+                "Ljump_table.2:",
+                "    .long Lcase.0 - Ljump_table.2",
+                "    .long Lcase.1 - Ljump_table.2",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_x64_code_gen_static_variables() {
+        // SS: arrange
+        let assembly_ast = assembly_ast::AssemblyProgramAST {
+            items: vec![
+                assembly_ast::AssemblyTopLevelAST::Function(assembly_ast::AssemblyFunctionAST {
+                    name: "f".to_string(),
+                    global: false,
+                    instructions: vec![assembly_ast::AssemblyInstructionAST::Mov {
+                        asm_type: assembly_ast::AssemblyType::Longword,
+                        src: assembly_ast::AssemblyOperandAST::Data("a".to_string(), 0),
+                        dst: assembly_ast::AssemblyOperandAST::Register(crate::reg::Register::EAX),
+                    }],
+                    callee_saved_registers: vec![],
+                }),
+                assembly_ast::AssemblyTopLevelAST::StaticVariable {
+                    name: "a".to_string(),
+                    global: true,
+                    alignment: 4,
+                    init: vec![crate::types::StaticInit::Value(
+                        crate::types::Constant::Int(0),
+                    )],
+                },
+                assembly_ast::AssemblyTopLevelAST::StaticVariable {
+                    name: "b.0".to_string(),
+                    global: false,
+                    alignment: 4,
+                    init: vec![crate::types::StaticInit::Value(
+                        crate::types::Constant::Int(7),
+                    )],
+                },
+            ],
+        };
+
+        // SS: act
+        let mut emitter = StringEmitter::new();
+        let mut code_gen = X64CodeGen::new(&mut emitter, Target::Linux);
+        code_gen.emit(&assembly_ast);
+
+        // SS: assert
+        assert_eq!(
+            emitter.buffer,
+            vec![
+                "    .text",
+                "f:",
+                "    pushq %rbp",
+                "    movq %rsp, %rbp",
+                "    movl a(%rip), %eax",
+                ".globl a",
+                "    .bss",
+                "    .balign 4",
+                "a:",
+                "    .zero 4",
+                "    .data",
+                "    .balign 4",
+                "b.0:",
+                "    .long 7",
+                "    .section .note.GNU-stack,\"\",@progbits",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_x64_code_gen_typed_instructions() {
+        // SS: arrange
+        let register = assembly_ast::AssemblyOperandAST::Register;
+        let assembly_ast = assembly_ast::AssemblyProgramAST {
+            items: vec![assembly_ast::AssemblyTopLevelAST::Function(
+                assembly_ast::AssemblyFunctionAST {
+                    name: "main".to_string(),
+                    global: true,
+                    instructions: vec![
+                        assembly_ast::AssemblyInstructionAST::Movsx {
+                            src_type: assembly_ast::AssemblyType::Longword,
+                            dst_type: assembly_ast::AssemblyType::Quadword,
+                            src: register(crate::reg::Register::EAX),
+                            dst: register(crate::reg::Register::EDX),
+                        },
+                        assembly_ast::AssemblyInstructionAST::Cdq(
+                            assembly_ast::AssemblyType::Quadword,
+                        ),
+                        assembly_ast::AssemblyInstructionAST::Div {
+                            asm_type: assembly_ast::AssemblyType::Quadword,
+                            operand: register(crate::reg::Register::R10D),
+                        },
+                        assembly_ast::AssemblyInstructionAST::Cvtsi2sd {
+                            asm_type: assembly_ast::AssemblyType::Quadword,
+                            src: register(crate::reg::Register::EAX),
+                            dst: register(crate::reg::Register::XMM0),
+                        },
+                        assembly_ast::AssemblyInstructionAST::Binary {
+                            op: assembly_ast::AssemblyBinaryOperatorAST::DivDouble,
+                            asm_type: assembly_ast::AssemblyType::Double,
+                            src: assembly_ast::AssemblyOperandAST::Data(
+                                "const_double.0".to_string(),
+                                0,
+                            ),
+                            dst: register(crate::reg::Register::XMM0),
+                        },
+                        assembly_ast::AssemblyInstructionAST::Cmp {
+                            asm_type: assembly_ast::AssemblyType::Double,
+                            src: register(crate::reg::Register::XMM14),
+                            dst: register(crate::reg::Register::XMM0),
+                        },
+                        assembly_ast::AssemblyInstructionAST::Cvttsd2si {
+                            asm_type: assembly_ast::AssemblyType::Longword,
+                            src: register(crate::reg::Register::XMM0),
+                            dst: register(crate::reg::Register::EAX),
+                        },
+                        assembly_ast::AssemblyInstructionAST::Binary {
+                            op: assembly_ast::AssemblyBinaryOperatorAST::Shr,
+                            asm_type: assembly_ast::AssemblyType::Quadword,
+                            src: assembly_ast::AssemblyOperandAST::Immediate(1),
+                            dst: register(crate::reg::Register::EDX),
+                        },
+                    ],
+                    callee_saved_registers: vec![],
+                },
+            )],
+        };
+
+        // SS: act
+        let mut emitter = StringEmitter::new();
+        let mut code_gen = X64CodeGen::new(&mut emitter, Target::Linux);
+        code_gen.emit(&assembly_ast);
+
+        // SS: assert
+        assert_eq!(
+            emitter.buffer[5..13],
+            [
+                "    movslq %eax, %rdx",
+                "    cqo",
+                "    divq %r10",
+                "    cvtsi2sdq %rax, %xmm0",
+                "    divsd const_double.0(%rip), %xmm0",
+                "    comisd %xmm14, %xmm0",
+                "    cvttsd2sil %xmm0, %eax",
+                "    shrq $1, %rdx",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_x64_code_gen_static_constants() {
+        // SS: arrange
+        let assembly_ast = assembly_ast::AssemblyProgramAST {
+            items: vec![
+                assembly_ast::AssemblyTopLevelAST::StaticVariable {
+                    name: "l".to_string(),
+                    global: true,
+                    alignment: 8,
+                    init: vec![crate::types::StaticInit::Value(
+                        crate::types::Constant::Long(4294967296),
+                    )],
+                },
+                assembly_ast::AssemblyTopLevelAST::StaticVariable {
+                    name: "d".to_string(),
+                    global: false,
+                    alignment: 8,
+                    init: vec![crate::types::StaticInit::Value(
+                        crate::types::Constant::Double(0.0),
+                    )],
+                },
+                assembly_ast::AssemblyTopLevelAST::StaticConstant {
+                    name: "const_double.0".to_string(),
+                    alignment: 16,
+                    init: crate::types::StaticInit::Value(crate::types::Constant::Double(-0.0)),
+                },
+            ],
+        };
+
+        // SS: act
+        let mut linux_emitter = StringEmitter::new();
+        X64CodeGen::new(&mut linux_emitter, Target::Linux).emit(&assembly_ast);
+        let mut macos_emitter = StringEmitter::new();
+        X64CodeGen::new(&mut macos_emitter, Target::MacOs).emit(&assembly_ast);
+
+        // SS: assert
+        assert_eq!(
+            linux_emitter.buffer,
+            vec![
+                ".globl l",
+                "    .data",
+                "    .balign 8",
+                "l:",
+                "    .quad 4294967296",
+                "    .bss",
+                "    .balign 8",
+                "d:",
+                "    .zero 8",
+                "    .section .rodata",
+                "    .balign 16",
+                ".Lconst_double.0:",
+                "    .quad -9223372036854775808",
+                "    .quad 0",
+                "    .section .note.GNU-stack,\"\",@progbits",
+            ]
+        );
+        assert_eq!(
+            macos_emitter.buffer[9..12],
+            ["    .literal16", "    .balign 16", "Lconst_double.0:"]
+        );
+    }
+
+    #[test]
+    fn test_x64_code_gen_memory_operands_and_arrays() {
+        // SS: arrange
+        let assembly_ast = assembly_ast::AssemblyProgramAST {
+            items: vec![
+                assembly_ast::AssemblyTopLevelAST::Function(assembly_ast::AssemblyFunctionAST {
+                    name: "main".to_string(),
+                    global: true,
+                    instructions: vec![
+                        assembly_ast::AssemblyInstructionAST::Lea {
+                            src: assembly_ast::AssemblyOperandAST::Data("a".to_string(), 0),
+                            dst: assembly_ast::AssemblyOperandAST::Register(
+                                crate::reg::Register::EAX,
+                            ),
+                        },
+                        assembly_ast::AssemblyInstructionAST::Lea {
+                            src: assembly_ast::AssemblyOperandAST::Indexed {
+                                base: crate::reg::Register::EAX,
+                                index: crate::reg::Register::EDX,
+                                scale: 4,
+                            },
+                            dst: assembly_ast::AssemblyOperandAST::Register(
+                                crate::reg::Register::R11D,
+                            ),
+                        },
+                        assembly_ast::AssemblyInstructionAST::Mov {
+                            asm_type: assembly_ast::AssemblyType::Longword,
+                            src: assembly_ast::AssemblyOperandAST::Memory(
+                                crate::reg::Register::EAX,
+                                -8,
+                            ),
+                            dst: assembly_ast::AssemblyOperandAST::Register(
+                                crate::reg::Register::EAX,
+                            ),
+                        },
+                    ],
+                    callee_saved_registers: vec![],
+                }),
+                assembly_ast::AssemblyTopLevelAST::StaticVariable {
+                    name: "a".to_string(),
+                    global: true,
+                    alignment: 16,
+                    init: vec![
+                        crate::types::StaticInit::Value(crate::types::Constant::Int(1)),
+                        crate::types::StaticInit::Zero(8),
+                        crate::types::StaticInit::Value(crate::types::Constant::Long(2)),
+                    ],
+                },
+                assembly_ast::AssemblyTopLevelAST::StaticVariable {
+                    name: "b".to_string(),
+                    global: false,
+                    alignment: 16,
+                    init: vec![crate::types::StaticInit::Zero(40)],
+                },
+            ],
+        };
+
+        // SS: act
+        let mut emitter = StringEmitter::new();
+        X64CodeGen::new(&mut emitter, Target::Linux).emit(&assembly_ast);
+
+        // SS: assert
+        let emitted_code = emitter.buffer;
+        for line in [
+            "    leaq a(%rip), %rax",
+            "    leaq (%rax,%rdx,4), %r11",
+            "    movl -8(%rax), %eax",
+        ] {
+            assert!(emitted_code.contains(&line.to_string()), "{}", line);
+        }
+        let data = emitted_code
+            .iter()
+            .skip_while(|line| *line != ".globl a")
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(
+            data,
+            vec![
+                ".globl a",
+                "    .data",
+                "    .balign 16",
+                "a:",
+                "    .long 1",
+                "    .zero 8",
+                "    .quad 2",
+                "    .bss",
+                "    .balign 16",
+                "b:",
+                "    .zero 40",
+                "    .section .note.GNU-stack,\"\",@progbits",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_x64_code_gen_strings_and_bytes() {
+        // SS: arrange
+        let assembly_ast = assembly_ast::AssemblyProgramAST {
+            items: vec![
+                assembly_ast::AssemblyTopLevelAST::Function(assembly_ast::AssemblyFunctionAST {
+                    name: "main".to_string(),
+                    global: true,
+                    instructions: vec![
+                        assembly_ast::AssemblyInstructionAST::Movsx {
+                            src_type: assembly_ast::AssemblyType::Byte,
+                            dst_type: assembly_ast::AssemblyType::Quadword,
+                            src: assembly_ast::AssemblyOperandAST::Data("c".to_string(), 1),
+                            dst: assembly_ast::AssemblyOperandAST::Register(
+                                crate::reg::Register::EAX,
+                            ),
+                        },
+                        assembly_ast::AssemblyInstructionAST::MovZeroExtend {
+                            src_type: assembly_ast::AssemblyType::Byte,
+                            dst_type: assembly_ast::AssemblyType::Longword,
+                            src: assembly_ast::AssemblyOperandAST::Stack(-1),
+                            dst: assembly_ast::AssemblyOperandAST::Register(
+                                crate::reg::Register::R11D,
+                            ),
+                        },
+                        assembly_ast::AssemblyInstructionAST::Lea {
+                            src: assembly_ast::AssemblyOperandAST::Data("string.0".to_string(), 0),
+                            dst: assembly_ast::AssemblyOperandAST::Register(
+                                crate::reg::Register::EDI,
+                            ),
+                        },
+                    ],
+                    callee_saved_registers: vec![],
+                }),
+                assembly_ast::AssemblyTopLevelAST::StaticVariable {
+                    name: "c".to_string(),
+                    global: false,
+                    alignment: 1,
+                    init: vec![
+                        crate::types::StaticInit::Value(crate::types::Constant::Char(-1)),
+                        crate::types::StaticInit::String {
+                            bytes: b"a\"\n".to_vec(),
+                            null_terminated: false,
+                        },
+                    ],
+                },
+                assembly_ast::AssemblyTopLevelAST::StaticVariable {
+                    name: "p".to_string(),
+                    global: true,
+                    alignment: 8,
+                    init: vec![crate::types::StaticInit::Pointer("string.0".to_string())],
+                },
+                assembly_ast::AssemblyTopLevelAST::StaticConstant {
+                    name: "string.0".to_string(),
+                    alignment: 1,
+                    init: crate::types::StaticInit::String {
+                        bytes: b"hi".to_vec(),
+                        null_terminated: true,
+                    },
+                },
+            ],
+        };
+
+        // SS: act
+        let mut emitter = StringEmitter::new();
+        let mut code_gen = X64CodeGen::new(&mut emitter, Target::Linux);
+        code_gen.emit(&assembly_ast);
+
+        // SS: assert
+        assert_eq!(
+            emitter.buffer,
+            vec![
+                ".globl main",
+                "    .text",
+                "main:",
+                "    pushq %rbp",
+                "    movq %rsp, %rbp",
+                "    movsbq c+1(%rip), %rax",
+                "    movzbl -1(%rbp), %r11d",
+                "    leaq .Lstring.0(%rip), %rdi",
+                "    .data",
+                "    .balign 1",
+                "c:",
+                "    .byte -1",
+                "    .ascii \"a\\\"\\012\"",
+                ".globl p",
+                "    .data",
+                "    .balign 8",
+                "p:",
+                "    .quad .Lstring.0",
+                "    .section .rodata",
+                "    .balign 1",
+                ".Lstring.0:",
+                "    .asciz \"hi\"",
+                "    .section .note.GNU-stack,\"\",@progbits",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_x64_code_gen_callee_saved_registers() {
+        // SS: arrange
+        let assembly_ast = assembly_ast::AssemblyProgramAST {
+            items: vec![assembly_ast::AssemblyTopLevelAST::Function(
+                assembly_ast::AssemblyFunctionAST {
+                    name: "main".to_string(),
+                    global: true,
+                    instructions: vec![
+                        assembly_ast::AssemblyInstructionAST::AllocateStack(16),
+                        assembly_ast::AssemblyInstructionAST::Mov {
+                            asm_type: assembly_ast::AssemblyType::Longword,
+                            src: assembly_ast::AssemblyOperandAST::Register(
+                                crate::reg::Register::R12D,
+                            ),
+                            dst: assembly_ast::AssemblyOperandAST::Register(
+                                crate::reg::Register::EAX,
+                            ),
+                        },
+                        assembly_ast::AssemblyInstructionAST::Ret,
+                    ],
+                    callee_saved_registers: vec![
+                        crate::reg::Register::EBX,
+                        crate::reg::Register::R12D,
+                    ],
+                },
+            )],
+        };
+
+        // SS: act
+        let mut emitter = StringEmitter::new();
+        let mut code_gen = X64CodeGen::new(&mut emitter, Target::Linux);
+        code_gen.emit(&assembly_ast);
+
+        // SS: assert
+        assert_eq!(
+            emitter.buffer,
+            vec![
+                ".globl main",
+                "    .text",
+                "main:",
+                "    pushq %rbp",
+                "    movq %rsp, %rbp",
+                "    pushq %rbx",
+                "    pushq %r12",
+                "    subq $16, %rsp",
+                "    movl %r12d, %eax",
+                "    leaq -16(%rbp), %rsp",
+                "    popq %r12",
+                "    popq %rbx",
+                "    movq %rbp, %rsp",
+                "    popq %rbp",
+                "    ret",
+                "    .section .note.GNU-stack,\"\",@progbits",
+            ]
+        );
+    }
 }
@@ -0,0 +1,236 @@
+// SS: end-to-end tests. Every C file in tests/programs is compiled, linked and run, its exit
+// code and output have to match the .exit and .stdout files next to it, both when compiled
+// and when run by the interpreter. A missing .stdout file means the program prints nothing.
+// Every C file in tests/must_fail has to be rejected, reporting each line of the .errors file
+// next to it in that order. build.rs generates the test functions, one per file.
+#![cfg(target_os = "linux")]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+include!(concat!(env!("OUT_DIR"), "/e2e_tests.rs"));
+
+fn source_path(dir: &str, name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join(dir)
+        .join(format!("{}.c", name))
+}
+
+// SS: the compiler writes its output next to the input, so every test compiles a copy of
+// its source in a directory of its own, along with the header of the same name if there is
+// one. Other headers are found in the original directory. The intermediate files go to the
+// tmp directory inside it.
+fn compile(source: &Path, test_name: &str, flags: &[&str]) -> (PathBuf, Output) {
+    let work_dir = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join("e2e")
+        .join(test_name);
+    let _ = fs::remove_dir_all(&work_dir);
+    fs::create_dir_all(work_dir.join("tmp")).unwrap();
+    let file_name = source.file_name().unwrap();
+    fs::copy(source, work_dir.join(file_name)).unwrap();
+    let header = source.with_extension("h");
+    if header.exists() {
+        fs::copy(&header, work_dir.join(header.file_name().unwrap())).unwrap();
+    }
+
+    let output = Command::new(env!("CARGO_BIN_EXE_c_compiler"))
+        .current_dir(&work_dir)
+        .env("TMPDIR", work_dir.join("tmp"))
+        .arg(file_name)
+        .arg("-I")
+        .arg(source.parent().unwrap())
+        .args(flags)
+        .output()
+        .unwrap();
+    (work_dir, output)
+}
+
+// SS: the exit code and output a program is expected to have
+fn expected_results(source: &Path) -> (i32, String) {
+    let exit_code = fs::read_to_string(source.with_extension("exit"))
+        .unwrap()
+        .trim()
+        .parse::<i32>()
+        .unwrap();
+    let stdout = fs::read_to_string(source.with_extension("stdout")).unwrap_or_default();
+    (exit_code, stdout)
+}
+
+fn run_program(name: &str, flags: &[&str]) {
+    // SS: arrange
+    let source = source_path("programs", name);
+    let (expected_exit_code, expected_stdout) = expected_results(&source);
+
+    // SS: act
+    let test_name = format!("{}{}", name, flags.concat());
+    let (work_dir, output) = compile(&source, &test_name, flags);
+    assert!(
+        output.status.success(),
+        "{} failed to compile:\n{}",
+        name,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let output = Command::new(work_dir.join(name)).output().unwrap();
+
+    // SS: assert
+    assert_eq!(
+        output.status.code(),
+        Some(expected_exit_code),
+        "exit code of {}",
+        name
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        expected_stdout,
+        "output of {}",
+        name
+    );
+}
+
+fn interpret_program(name: &str) {
+    // SS: arrange
+    let source = source_path("programs", name);
+    let (expected_exit_code, expected_stdout) = expected_results(&source);
+
+    // SS: act
+    let (_, output) = compile(&source, &format!("{}_interpreted", name), &["--interpret"]);
+    // SS: the compiler reports its progress on stdout as well, the program output follows
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (_, program_stdout) = stdout
+        .split_once("Running interpreter...\n")
+        .unwrap_or_else(|| panic!("{} was not interpreted:\n{}", name, stdout));
+
+    // SS: assert
+    assert_eq!(
+        output.status.code(),
+        Some(expected_exit_code),
+        "exit code of {}, errors:\n{}",
+        name,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(program_stdout, expected_stdout, "output of {}", name);
+}
+
+fn fail_to_compile(name: &str) {
+    // SS: arrange
+    let source = source_path("must_fail", name);
+    let expected_errors = fs::read_to_string(source.with_extension("errors")).unwrap();
+
+    // SS: act
+    let (_, output) = compile(&source, &format!("must_fail_{}", name), &[]);
+
+    // SS: assert
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "{} compiled", name);
+    let mut lines = stderr.lines();
+    for expected in expected_errors.lines().filter(|line| !line.is_empty()) {
+        assert!(
+            lines.any(|line| line == expected),
+            "{} did not report {:?} in order, it reported:\n{}",
+            name,
+            expected,
+            stderr
+        );
+    }
+}
+
+// SS: the names of the files the compiler left in the work directory and its tmp directory
+fn files_left(work_dir: &Path) -> Vec<String> {
+    let mut names = vec![];
+    for dir in [work_dir.to_path_buf(), work_dir.join("tmp")] {
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_file() {
+                names.push(path.file_name().unwrap().to_string_lossy().into_owned());
+            }
+        }
+    }
+    names.sort();
+    names
+}
+
+#[test]
+fn driver_failing_link() {
+    // SS: arrange
+    let source = source_path("programs", "return_2");
+
+    // SS: act
+    let (work_dir, output) = compile(
+        &source,
+        "driver_failing_link",
+        &["-l", "library_that_does_not_exist"],
+    );
+
+    // SS: assert
+    assert!(!output.status.success(), "linking succeeded");
+    assert_eq!(files_left(&work_dir), vec!["return_2.c"]);
+}
+
+#[test]
+fn driver_object_file() {
+    // SS: arrange
+    let source = source_path("programs", "return_2");
+
+    // SS: act
+    let (work_dir, output) = compile(&source, "driver_object_file", &["-c"]);
+
+    // SS: assert
+    assert!(
+        output.status.success(),
+        "return_2 failed to assemble:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(files_left(&work_dir), vec!["return_2.c", "return_2.o"]);
+}
+
+// SS: an input named like the intermediate assembly file must not be overwritten by it
+#[test]
+fn driver_assembly_named_input() {
+    // SS: arrange
+    let source = source_path("programs", "return_2");
+    let work_dir = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join("e2e")
+        .join("driver_assembly_named_input");
+    let _ = fs::remove_dir_all(&work_dir);
+    fs::create_dir_all(work_dir.join("tmp")).unwrap();
+    fs::copy(&source, work_dir.join("return_2.s")).unwrap();
+
+    // SS: act
+    let output = Command::new(env!("CARGO_BIN_EXE_c_compiler"))
+        .current_dir(&work_dir)
+        .env("TMPDIR", work_dir.join("tmp"))
+        .arg("return_2.s")
+        .output()
+        .unwrap();
+
+    // SS: assert
+    assert!(
+        output.status.success(),
+        "return_2.s failed to compile:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        fs::read_to_string(work_dir.join("return_2.s")).unwrap(),
+        fs::read_to_string(&source).unwrap()
+    );
+    assert_eq!(files_left(&work_dir), vec!["return_2", "return_2.s"]);
+}
+
+// SS: the token dump locates each token in the file it came from, headers included
+#[test]
+fn lex_dump_locations() {
+    // SS: arrange
+    let source = source_path("programs", "preprocessor");
+
+    // SS: act
+    let (_, output) = compile(&source, "lex_dump_locations", &["--lex"]);
+
+    // SS: assert
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "lexing failed:\n{}", stdout);
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert!(lines.contains(&"preprocessor.h:4:5 Identifier(putchar)"));
+    assert!(lines.contains(&"preprocessor.c:22:5 Identifier(print)"));
+}
@@ -0,0 +1,36 @@
+// SS: runs the differential fuzzer on a handful of fixed seeds, so a change that miscompiles
+// one of them fails the build. Longer runs with other seeds are done by hand.
+#![cfg(target_os = "linux")]
+
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+fn fuzz_fixed_seeds() {
+    // SS: arrange
+    let output_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("fuzz");
+    let _ = std::fs::remove_dir_all(&output_dir);
+
+    // SS: act
+    let output = Command::new(env!("CARGO_BIN_EXE_fuzz"))
+        .arg("--seed")
+        .arg("1")
+        .arg("--count")
+        .arg("10")
+        .arg("--no-shrink")
+        .arg("--compiler")
+        .arg(env!("CARGO_BIN_EXE_c_compiler"))
+        .arg("--output-dir")
+        .arg(&output_dir)
+        .output()
+        .unwrap();
+
+    // SS: assert
+    assert!(
+        output.status.success(),
+        "fuzzing failed, the programs are in {:?}:\n{}{}",
+        output_dir,
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
@@ -0,0 +1,33 @@
+// Bit twiddling with every arithmetic, bitwise and assignment operator.
+#define MASK(n) ((1u << (n)) - 1u)
+#define CONCAT(a, b) a##b
+#define STRINGIFY(x) #x
+
+enum flags { FLAG_A = 0x1, FLAG_B = 0X2, FLAG_C = 04, FLAG_D = 010 };
+
+unsigned popcount(unsigned x) {
+    unsigned count = 0;
+    while (x) {
+        x &= x - 1;
+        count++;
+    }
+    return count;
+}
+
+long mix(long a, long b) {
+    a ^= b << 3;
+    b |= a >> 2;
+    a <<= 1;
+    b >>= 1;
+    a += b; a -= 7L; a *= 3; a /= 2; a %= 1000003;
+    return (a & ~b) | (a ^ b) ? a : -b;
+}
+
+int compare(int a, int b) {
+    return a < b ? -1 : a > b ? 1 : a <= b && a >= b || !(a != b) ? 0 : a == b;
+}
+
+unsigned long long big = 18446744073709551615ULL;
+long long also_big = 9223372036854775807ll;
+unsigned long hex_max = 0xffffffffffffffff;
+unsigned int small = 42U + 7uL + 8Lu + 0;
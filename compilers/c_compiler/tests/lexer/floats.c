@@ -0,0 +1,13 @@
+/* Floating constants in all the forms the lexer accepts. */
+double scale(double x) {
+    double factors[] = {1.0, 2., .5, 1e3, 2.5E-3, 1.e+2, 6.02214076e23};
+    double sum = 0.0;
+    for (int i = 0; i < 7; i++) {
+        sum += factors[i] * x;
+    }
+    return sum / 7.;
+}
+
+unsigned int mask(unsigned int bits) {
+    return bits & 0xffffffff;
+}
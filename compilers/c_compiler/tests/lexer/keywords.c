@@ -0,0 +1,48 @@
+/* Every C17 keyword, plus digraphs and the variadic ellipsis. */
+%:include <stdarg.h>
+%:define JOIN(a, b) a %:%: b
+
+_Static_assert(sizeof(int) == 4, "int is 32 bits");
+
+typedef union {
+    signed char small;
+    short medium;
+    long large;
+    float single;
+    double precise;
+    _Bool flag;
+} value_t;
+
+_Thread_local static volatile int counter;
+extern _Atomic int shared;
+_Alignas(16) static char buffer<:64:>;
+
+inline static int sum(int count, ...) <%
+    va_list args;
+    register int total = 0;
+    auto int i;
+    va_start(args, count);
+    for (i = 0; i < count; i++) total += va_arg(args, int);
+    va_end(args);
+    return total;
+%>
+
+_Noreturn void fail(void);
+
+int dispatch(int *restrict out, const int *restrict in) {
+    int n = (int)_Alignof(double);
+    double _Complex z;
+    (void)z;
+    if (!in) goto done;
+    do {
+        switch (*in) {
+        case 0: continue;
+        default: break;
+        }
+        *out++ = *in;
+    } while (*++in);
+done:
+    return _Generic(n, int: 1, default: 0) + n;
+}
+
+float _Imaginary _Imaginary_unit;
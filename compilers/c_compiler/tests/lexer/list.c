@@ -0,0 +1,44 @@
+#include <stdio.h>
+#include <stdlib.h>
+
+/* A singly linked list of integers, exercising the structure and pointer
+ * punctuators. */
+struct node {
+    int value;
+    struct node *next;
+};
+
+typedef struct node node_t;
+
+static node_t *push(node_t *head, int value) {
+    node_t *node = malloc(sizeof *node);
+    if (!node) {
+        return head;
+    }
+    node->value = value;
+    node->next = head;
+    return node;
+}
+
+static unsigned long length(const node_t *head) {
+    unsigned long count = 0ul;
+    for (; head != NULL; head = head->next) {
+        ++count;
+    }
+    return count;
+}
+
+int main(void) {
+    node_t *list = NULL;
+    int values[] = {3, 1, 4, 1, 5, 9, 2, 6};
+    for (int i = 0; i < (int)(sizeof values / sizeof values[0]); i++) {
+        list = push(list, values[i]);
+    }
+    printf("length: %lu\n", length(list));
+    while (list) {
+        node_t *next = list->next;
+        free(list);
+        list = next;
+    }
+    return 0;
+}
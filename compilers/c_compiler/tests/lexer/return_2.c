@@ -0,0 +1,4 @@
+// gcc -S -O -fno-asynchronous-unwind-tables -fcf-protection=none return_2.c
+int main(void) {
+	return 2;
+}
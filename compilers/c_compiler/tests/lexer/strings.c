@@ -0,0 +1,34 @@
+/* Character constants and string literals with every kind of escape. */
+extern int putchar(int c);
+extern int puts(const char *s);
+
+static const char *messages[] = {
+    "hello, world\n",
+    "tab\there, quote \" and backslash \\",
+    "alert\a backspace\b formfeed\f return\r vtab\v",
+    "octal \101\102\103 and \0 embedded",
+    "hex \x41\x42 and question mark \? done",
+    "", "// not a comment", "/* nor this */",
+};
+
+int classify(char c) {
+    switch (c) {
+    case 'a': case 'e': case 'i': case 'o': case 'u':
+        return 1;
+    case '\n': case '\t': case ' ':
+        return 2;
+    case '\'': case '"': case '\\':
+        return 3;
+    case '\0': case '\177': case '\xff':
+        return 4;
+    default:
+        return 0;
+    }
+}
+
+int main(void) {
+    for (unsigned i = 0; i < sizeof messages / sizeof *messages; i++) {
+        puts(messages[i]);
+    }
+    return classify('?') + classify('\x41');
+}
@@ -0,0 +1,5 @@
+long big[1000000000];
+
+int main(void) {
+    return 0;
+}
@@ -0,0 +1 @@
+int main(void){int x = 1; switch (x) { case 1: case 2 - 1: return 0; } return 1;}
@@ -0,0 +1,7 @@
+int main(void) {
+    int x = 0;
+again:
+    x = x + 1;
+again:
+    return x;
+}
@@ -0,0 +1 @@
+int main(void){int *p; return (double) p;}
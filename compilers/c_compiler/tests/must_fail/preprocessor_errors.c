@@ -0,0 +1,6 @@
+#include "missing.h"
+#define ADD(a, b) ((a) + (b))
+int x = ADD(1);
+#if 1 +
+#endif
+#error unsupported platform
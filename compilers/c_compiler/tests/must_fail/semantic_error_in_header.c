@@ -0,0 +1,5 @@
+#include "semantic_error_in_header.h"
+
+int main(void) {
+    return twice(1);
+}
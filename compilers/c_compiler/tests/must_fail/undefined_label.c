@@ -0,0 +1,6 @@
+int main(void) {
+    int x = 0;
+    if (x)
+        goto done;
+    return x;
+}
@@ -0,0 +1,7 @@
+int main(void) {
+    return 0;
+}
+
+  /* this comment
+   never ends
+int f(void) { return 1; }
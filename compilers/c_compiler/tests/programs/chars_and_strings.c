@@ -0,0 +1,42 @@
+int putchar(int c);
+int puts(char *s);
+unsigned long strlen(char *s);
+char g = 'x';
+static char msg[10] = "hi\tthere\n";
+char *gp = "global ptr";
+signed char sc = 251;
+unsigned char uc = 250;
+char arr[3] = "ab";
+int f(char c, unsigned char u, signed char s) { return c + u + s; }
+char ret_char(int i) { return i; }
+int main(void) {
+    char local[6] = "local";
+    char *p = "literal";
+    puts(p);
+    puts(local);
+    puts(msg);
+    puts(gp);
+    puts(arr);
+    putchar(g); putchar('\n');
+    unsigned char x = 255; x++;
+    char y = 200;
+    double d = uc;
+    double d2 = sc;
+    char from_d = (char) 65.7;
+    unsigned char ud = (unsigned char) 250.9;
+    if (sizeof(char) != 1 || sizeof local != 6 || sizeof(int[3]) != 12 || sizeof "abc" != 4) return 1;
+    if (x != 0) return 2;
+    if (y != -56) return 3;
+    if (d != 250.0 || d2 != -5.0) return 4;
+    if (from_d != 'A' || ud != 250) return 5;
+    if (f(-1, 255, -1) != 253) return 6;
+    if (ret_char(321) != 65) return 7;
+    if (strlen("hello") != 5) return 8;
+    char buf[4];
+    buf[0] = 'o'; buf[1] = 'k'; buf[2] = '\0';
+    puts(buf);
+    char *q = buf;
+    while (*q) putchar(*q++);
+    putchar('\n');
+    return sc + uc;
+}
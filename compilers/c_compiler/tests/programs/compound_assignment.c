@@ -0,0 +1,14 @@
+int main(void) {
+    int a = 5;
+    int tmp = a * 2;
+    int b;
+    b = tmp += 3;
+    a++;
+    --b;
+    int c = a++ + ++a;
+    c %= 4;
+    c -= -b;
+    c /= 2;
+    ;
+    return c + a * 3 - tmp;
+}
@@ -0,0 +1,34 @@
+int putchar(int c);
+int print_num(long v) {
+    if (v < 0) { putchar('-'); v = -v; }
+    if (v >= 10) print_num(v / 10);
+    putchar('0' + v % 10);
+    return 0;
+}
+double half(double v);
+double half(double v) { return v / 2; }
+unsigned long count(void) { static unsigned long n = 10; static double f = 3; f = f * 2; return n++ + (unsigned long)f; }
+long add_many(long a, long b, long c, long d, long e, long f, long g, int h) { return a + b + c + d + e + f + g + h; }
+int main(void) {
+    int i = 7;
+    i += 5000000000L;
+    print_num(i); putchar(10);
+    unsigned u = 10;
+    u -= 20;
+    print_num(u); putchar(10);
+    u /= 3;
+    print_num(u); putchar(10);
+    long l = 3;
+    print_num(half(l)); putchar(10);
+    double c = i > 0 ? 1 : 2.5;
+    print_num((long)(c * 10)); putchar(10);
+    print_num(count()); print_num(count()); putchar(10);
+    print_num(add_many(1, 2, 3, 4, 5, 6, 7000000000, 8)); putchar(10);
+    unsigned long ul = 0;
+    for (unsigned k = 5; k < 10; k++) ul += k;
+    print_num(ul); putchar(10);
+    print_num(~0u); putchar(10);
+    print_num(~0L); putchar(10);
+    print_num(-u); putchar(10);
+    return 0;
+}
@@ -0,0 +1,55 @@
+int putchar(int c);
+double fmix(int a, double b, long c, double d, unsigned e, double f, int g, double h, double i, double j, double k, double l, int m, int n, int o, long p) {
+    return a + b + c + d + e + f + g + h + i + j + k + l + m + n + o + p;
+}
+static double g = 2.5;
+long gl = 5000000000;
+unsigned long big = 18446744073709551615UL;
+double zero;
+unsigned int ui = 4294967295u;
+int print_num(long v) {
+    if (v < 0) { putchar('-'); v = -v; }
+    if (v >= 10) print_num(v / 10);
+    putchar('0' + v % 10);
+    return 0;
+}
+int main(void) {
+    double r = fmix(1, 2.0, 3, 4.0, 5, 6.0, 7, 8.0, 9.0, 10.0, 11.0, 12.0, 13, 14, 15, 16);
+    print_num((long)r); putchar(10);
+    print_num((long)(g * 4)); putchar(10);
+    print_num(gl / 3); putchar(10);
+    print_num((long)(big / 3 % 1000)); putchar(10);
+    double d = (double)big;
+    print_num((long)(d / 1e10)); putchar(10);
+    unsigned long u = (unsigned long)1e19;
+    print_num((long)(u % 100000)); putchar(10);
+    print_num((long)(u / 1000000000)); putchar(10);
+    print_num(ui + 1); putchar(10);
+    print_num((long)ui + 1); putchar(10);
+    print_num(ui > 0); putchar(10);
+    print_num(-1 < 0u); putchar(10);
+    print_num(-1L < 0u); putchar(10);
+    print_num(zero == 0.0); putchar(10);
+    double nan = zero / zero;
+    print_num(nan == nan); print_num(nan != nan); print_num(nan < 1.0); print_num(nan >= 1.0); print_num(!nan); putchar(10);
+    if (nan) putchar('y'); else putchar('n');
+    while (!nan) { putchar('x'); break; }
+    putchar(10);
+    double x = 3.75;
+    x += 1;
+    x *= 2;
+    int i = 10;
+    i *= 2.5;
+    print_num(i); putchar(10);
+    print_num((long)(x * 100)); putchar(10);
+    print_num((long)-x); putchar(10);
+    unsigned int uu = 3000000000u;
+    print_num((long)(double)uu); putchar(10);
+    print_num((unsigned int)(double)uu == uu); putchar(10);
+    long neg = -17;
+    print_num(neg / 5); print_num(neg % 5); putchar(10);
+    print_num(1.5 > 1 ? 7 : 8); putchar(10);
+    print_num(x++ > 9.4); print_num((long)x); putchar(10);
+
+    return (int)(r) % 256;
+}
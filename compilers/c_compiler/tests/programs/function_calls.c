@@ -0,0 +1,13 @@
+int putchar(int c);
+int sum8(int a, int b, int c, int d, int e, int f, int g, int h) {
+    return a + 2 * b + 3 * c + 4 * d + 5 * e + 6 * f + 7 * g - h;
+}
+int sum7(int a, int b, int c, int d, int e, int f, int g) {
+    return a - b + c - d + e - f + g * 10;
+}
+int fib(int n) { if (n < 2) return n; return fib(n - 1) + fib(n - 2); }
+int main(void) {
+    int x = 3;
+    putchar(72); putchar(105); putchar(10);
+    return (sum8(1, 2, 3, 4, 5, 6, x, 8) + sum7(1, 2, 3, 4, 5, 6, x) + fib(10)) % 256;
+}
@@ -0,0 +1,75 @@
+int putchar(int c);
+
+/* Labels share names with variables, with labels in other functions and with the
+   compiler's own loop and branch labels. */
+int loop(int n) {
+    int total = 0;
+    int i = 0;
+loop:
+    if (i >= n)
+        goto break_loop;
+    total += i;
+    i++;
+    goto loop;
+break_loop:
+    return total;
+}
+
+int search(int target) {
+    int found = -1;
+    for (int i = 0; i < 10; i++) {
+        for (int j = 0; j < 10; j++) {
+            if (i * j == target) {
+                found = i * 10 + j;
+                goto done;
+            }
+        }
+    }
+done:
+    return found;
+}
+
+int jump_into_loop(void) {
+    int count = 0;
+    goto inside;
+    while (count < 5) {
+        count += 10;
+    inside:
+        count++;
+    }
+    return count;
+}
+
+int switch_and_goto(int x) {
+    int result = 0;
+    switch (x) {
+    case 1:
+    retry:
+        result += 1;
+        if (result < 3)
+            goto retry;
+        break;
+    case 2:
+        goto end;
+    default:
+        result = 100;
+    }
+end:
+    return result;
+}
+
+int main(void) {
+    int end = 0;
+    goto print;
+skipped:
+    return 99;
+print:
+    putchar('g');
+    putchar('o');
+    putchar('\n');
+    end = loop(10) + search(42) + jump_into_loop() + switch_and_goto(1) + switch_and_goto(2) +
+          switch_and_goto(3);
+    if (end < 0)
+        goto skipped;
+    return end % 256;
+}
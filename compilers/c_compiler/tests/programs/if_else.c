@@ -0,0 +1,16 @@
+int main(void) {
+    int a = 3;
+    int b = 0;
+    if (a > 2)
+        if (a > 5) b = 1; else b = 2;
+    else b = 3;
+    {
+        int a = 10;
+        b += a;
+        { int b = 100; a = b; }
+        b = a > 50 ? b + a : b - 1;
+    }
+    int c = b ? a ? 4 : 5 : 6;
+    if (!c) return 99;
+    return b + a + c;
+}
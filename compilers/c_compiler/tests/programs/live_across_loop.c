@@ -0,0 +1,31 @@
+double scale(int n) {
+    double result = 2.5;
+    double step = 1.0;
+    while (n > 0) {
+        step = step * 0.5;
+        n = n - 1;
+    }
+    return result;
+}
+long count(long limit) {
+    long result = limit * 3;
+    long i = 0;
+    do {
+        i = i + 1;
+    } while (i < limit);
+    return result;
+}
+int first(void) {
+    int result = 5;
+    int tmp = 1;
+    while (tmp) {
+        tmp = 0;
+    }
+    return result;
+}
+int main(void) {
+    int total = first();
+    total = total + (int)scale(4) * 10;
+    total = total + count(7);
+    return total;
+}
@@ -0,0 +1,13 @@
+int main(void) {
+    int sum = 0;
+    for (int i = 0; i < 10; i++) {
+        if (i % 2) continue;
+        sum += i;
+    }
+    int j = 0;
+    while (1) { j++; if (j > 5) break; }
+    int k = 0;
+    do { k += 3; } while (k < 20);
+    for (;;) { break; }
+    return sum + j + k;
+}
@@ -0,0 +1,41 @@
+int putchar(int c);
+long g[3][5];
+int fill(long (*m)[5], int rows) {
+    for (int i = 0; i < rows; i++)
+        for (int j = 0; j < 5; j++)
+            m[i][j] = i * 10 + j;
+    return 0;
+}
+unsigned int *pick(unsigned int *a, long i) { return &a[i]; }
+int main(void) {
+    fill(g, 3);
+    int r = 2;
+    if (g[r][3] != 23) return 1;
+    long (*row)[5] = g;
+    row += r;
+    if ((*row)[4] != 24) return 2;
+    unsigned int u[4] = {4000000000u, 1, 2, 3};
+    unsigned int *up = pick(u, 0);
+    if (*up != 4000000000u) return 3;
+    up = pick(u, 3);
+    *up -= 1;
+    if (u[3] != 2) return 4;
+    int i = 0;
+    int v[3] = {10, 20, 30};
+    int *vp = v;
+    int w = *vp++ + *vp++;
+    if (vp - v != 2) return 5;
+    int *end = v + 3;
+    int count = 0;
+    for (int *it = v; it != end; ++it) count += *it;
+    if (count != 60) return 6;
+    if (end <= v || v > end || !(v < end) || !(end >= v)) return 7;
+    double d[2][2] = {{1.0, 2.0}, {3.0}};
+    double *dp = &d[1][0];
+    dp[1] = dp[0] * 2;
+    if (d[1][1] != 6.0) return 8;
+    int *np = 0;
+    np = np ? np : v;
+    if (*np != 10) return 9;
+    return (int) g[2][4] + i + w - 30 - 24;
+}
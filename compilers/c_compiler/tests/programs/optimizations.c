@@ -0,0 +1,28 @@
+int putchar(int c);
+static int counter = 0;
+int bump(void) { counter = counter + 1; return counter; }
+int g;
+int sum(int *p, int n) { int s = 0; for (int i = 0; i < n; i = i + 1) s = s + p[i]; return s; }
+int main(void) {
+    int x = 2 * 3 + 4;
+    int y = x;
+    int z = y + 1;
+    if (0) { putchar(65); }
+    if (1 < 2) putchar(66);
+    unsigned u = -1;
+    long l = u;
+    double d = 1.0 / 4;
+    int a[3] = {1, 2, 3};
+    int *p = &x;
+    *p = 20;
+    int w = x;
+    g = 5;
+    bump();
+    int k = counter;
+    int dead = 7;
+    dead = 8;
+    while (k < 10) k = k + 3;
+    char c = 300;
+    putchar(10);
+    return (w + z + l % 100 + (int)(d * 8) + sum(a, 3) + k + c + g) % 256;
+}
@@ -0,0 +1,67 @@
+int putchar(int c);
+int sum(int *a, int n) {
+    int total = 0;
+    for (int i = 0; i < n; i++)
+        total += a[i];
+    return total;
+}
+int sum2(int a[], int n) {
+    int *end = a + n;
+    int total = 0;
+    while (a < end) total = total + *a++;
+    return total;
+}
+static long table[4] = {1, 2, 3};
+double ds[3] = {1.5, 2.5};
+int grid[2][3] = {{1, 2, 3}, {4, 5, 6}};
+int *null_ptr = 0;
+int swap(int *x, int *y) { int t = *x; *x = *y; *y = t; return 0; }
+int main(void) {
+    int arr[5] = {1, 2, 3, 4, 5};
+    int x = 3;
+    int y = 4;
+    int *p = &x;
+    *p = 10;
+    if (x != 10) return 1;
+    if (sum(arr, 5) != 15) return 2;
+    if (sum2(arr, 5) != 15) return 3;
+    int *q = arr + 4;
+    if (q - arr != 4) return 4;
+    if (*(q - 1) != 4) return 5;
+    arr[2] += 10;
+    if (arr[2] != 13) return 6;
+    --q;
+    q[-1]++;
+    if (arr[2] != 14) return 7;
+    if (table[2] != 3 || table[3] != 0) return 8;
+    if (ds[1] != 2.5 || ds[2] != 0.0) return 9;
+    if (grid[1][2] != 6) return 10;
+    int (*row)[3] = grid + 1;
+    if ((*row)[0] != 4) return 11;
+    int local[2][2] = {{7}, {8, 9}};
+    if (local[0][1] != 0 || local[1][1] != 9) return 12;
+    if (null_ptr) return 13;
+    long idx = 1;
+    if (2[arr] != 14 || arr[idx] != 2) return 14;
+    double darr[3];
+    double *dp = darr;
+    for (int i = 0; i < 3; i = i + 1) dp[i] = i * 0.5;
+    if (darr[2] != 1.0) return 15;
+    *dp += 3;
+    if (darr[0] != 3.0) return 16;
+    int *pp[2] = {&x, &y};
+    *pp[1] = 42;
+    if (y != 42) return 17;
+    int **ppp = pp;
+    if (**ppp != 10) return 18;
+    unsigned long addr = (unsigned long) p;
+    if ((int *) addr != p) return 19;
+    if (&arr[3] - &arr[1] != 2) return 20;
+    if (!(p == &x) || p == 0) return 21;
+    static int sarr[3];
+    sarr[1] = 5;
+    int *sp = sarr;
+    if (sp[1] != 5) return 22;
+    putchar('o'); putchar('k'); putchar(10);
+    return 0;
+}
@@ -0,0 +1,48 @@
+#include "preprocessor.h"
+#include <preprocessor.h>
+
+#define PRINT(...) print(__VA_ARGS__)
+#define CALL(f, ...) f(__VA_ARGS__)
+#define LONG_MACRO(a, b) \
+    ((a) + \
+     (b))
+
+#if VERSION >= 3 && defined(SQUARE) && !defined NOT_DEFINED
+static int version = VERSION;
+#elif 1 / 0
+#error not reached
+#else
+static int version = 0;
+#endif
+
+#ifdef NOT_DEFINED
+#error not reached either
+#endif
+
+int print(char *s) {
+    int count = 0;
+    while (*s) {
+        putchar(*s++);
+        count++;
+    }
+    putchar('\n');
+    return count;
+}
+
+int sum(int a, int b, int c) { return a + b + c; }
+
+int main(void) {
+    int CAT(my, var) = SQUARE(1 + 2);
+    int limit = 4;
+#define limit limit + 1
+    PRINT(XSTR(VERSION) STR( "quoted\n"  'x' ));
+    PRINT(__FILE__);
+    if (__LINE__ != 40) return 1;
+    if (CALL(sum, 1, MAX(2, 3), 4) != 8) return 2;
+    if (LONG_MACRO(myvar,
+                   version) != 12) return 3;
+    if (MAX(limit, myvar - 5) != 5) return 4;
+#undef SQUARE
+#define SQUARE 5
+    return SQUARE + myvar;
+}
@@ -0,0 +1,13 @@
+#ifndef PREPROCESSOR_H
+#define PREPROCESSOR_H
+
+int putchar(int c);
+
+#define SQUARE(x) ((x) * (x))
+#define MAX(a, b) ((a) > (b) ? (a) : (b))
+#define CAT(a, b) a##b
+#define STR(x) #x
+#define XSTR(x) STR(x)
+#define VERSION 3
+
+#endif
@@ -0,0 +1,57 @@
+int putchar(int c);
+struct pair { long a; double b; };
+struct big { long a; long b; long c; };
+double dsum(double a, double b, double c, double d, double e, double f, double g, double h, double i, double j) {
+    return a + b * 2 + c * 3 + d * 4 + e * 5 + f * 6 + g * 7 + h * 8 + i * 9 + j * 10;
+}
+long lsum(long a, long b, long c, long d, long e, long f, long g, long h) {
+    return a - b + c * d - e / f + g % h;
+}
+struct pair mk(long a, double b) { struct pair p; p.a = a; p.b = b; return p; }
+struct big mkbig(long a) { struct big b; b.a = a; b.b = a * 2; b.c = a * 3; return b; }
+int fib(int n) { return n < 2 ? n : fib(n - 1) + fib(n - 2); }
+unsigned long ud(unsigned long a, unsigned long b) { return a / b + a % b; }
+int print(long x) { if (x < 0) { putchar('-'); x = -x; } if (x >= 10) print(x / 10); putchar('0' + x % 10); return 0; }
+int main(void) {
+    int a = 1;
+    int b = 2;
+    int c = 3;
+    int d = 4;
+    int e = 5;
+    int f = 6;
+    int g = 7;
+    int h = 8;
+    int i = 9;
+    int j = 10;
+    int k = 11;
+    int l = 12;
+    int m = 13;
+    int n = 14;
+    double x = 1.5;
+    double y = 2.5;
+    double z = 3.5;
+    double w = 4.5;
+    for (int t = 0; t < 10; t = t + 1) {
+        a = a + b; b = b + c; c = c + d; d = d + e; e = e + f; f = f + g; g = g + h;
+        h = h + i; i = i + j; j = j + k; k = k + l; l = l + m; m = m + n; n = n + a;
+        x = x * 1.1 + y; y = y - z / 3; z = z + w; w = w * 0.9;
+    }
+    print(a + b + c + d + e + f + g + h + i + j + k + l + m + n); putchar(10);
+    print((long)dsum(x, y, z, w, x, y, z, w, x, y)); putchar(10);
+    print(lsum(a, b, c, d, e, f, g, h) + fib(15)); putchar(10);
+    struct pair p = mk(a, x);
+    struct big q = mkbig(b);
+    print(p.a + (long)p.b + q.a + q.b + q.c); putchar(10);
+    print(ud(1000000007ul, 97ul)); putchar(10);
+    char s[4] = "abc";
+    char cc = s[1];
+    print(cc + a / (b % 7 + 1)); putchar(10);
+    double arr[3] = {x, y, z};
+    double *pd = &arr[1];
+    print((long)(*pd * 100)); putchar(10);
+    int addr = 5; int *pa = &addr; *pa = *pa + n;
+    print(addr); putchar(10);
+    unsigned u = 4000000000u; double du = u; unsigned long ul = (unsigned long)(du * 3);
+    print(ul % 1000); putchar(10);
+    return (a + b + c + (x > 2.0)) % 256;
+}
@@ -0,0 +1,28 @@
+int counter;
+int limit = 10;
+static int hidden = 3;
+extern int limit;
+int counter;
+
+static int bump(void) {
+    static int calls = 0;
+    calls = calls + 1;
+    counter = counter + hidden;
+    return calls;
+}
+
+int read_limit(void) {
+    extern int limit;
+    return limit;
+}
+
+int main(void) {
+    int total = 0;
+    for (int i = 0; i < read_limit(); i = i + 1)
+        total = total + bump();
+    {
+        extern int counter;
+        total = total + counter;
+    }
+    return total + hidden;
+}
@@ -0,0 +1,65 @@
+int puts(char *s);
+struct point { int x; int y; };
+struct mixed { char c; double d; long l; };
+struct small { char a; char b; char c; };
+struct dbl { double a; double b; };
+struct big { long a; long b; long c; };
+struct id { int i; double d; };
+union u { int i; char c[5]; double d; };
+struct nested { struct point p; char name[6]; struct nested *next; };
+
+struct point make_point(int x, int y) { struct point p; p.x = x; p.y = y; return p; }
+struct small make_small(void) { struct small s = {1, 2, 3}; return s; }
+struct dbl make_dbl(double a) { struct dbl r = {a, a * 2}; return r; }
+struct big make_big(long v) { struct big b = {v, v + 1, v + 2}; return b; }
+struct id make_id(void) { struct id r = {7, 2.5}; return r; }
+long sum_big(struct big b) { return b.a + b.b + b.c; }
+int sum_small(struct small s) { return s.a + s.b + s.c; }
+double sum_dbl(struct dbl d) { return d.a + d.b; }
+int many(struct point a, struct point b, struct point c, struct point d, struct small e, struct id f, struct point g) {
+    return a.x + b.y + c.x + d.y + e.c + f.i + g.x + g.y;
+}
+static struct point gpt = {3, 4};
+struct nested gn = {{1, 2}, "node", 0};
+union u gu = {65};
+
+int main(void) {
+    struct point p = make_point(10, 20);
+    if (p.x != 10 || p.y != 20) return 1;
+    struct small s = make_small();
+    if (s.a + s.b + s.c != 6 || sum_small(s) != 6) return 2;
+    struct dbl d = make_dbl(1.5);
+    if (d.b != 3.0 || sum_dbl(d) != 4.5) return 3;
+    struct big b = make_big(100);
+    if (b.c != 102 || sum_big(b) != 303) return 4;
+    struct id i = make_id();
+    if (i.i != 7 || i.d != 2.5) return 5;
+    if (many(p, p, p, p, s, i, gpt) != 10 + 20 + 10 + 20 + 3 + 7 + 3 + 4) return 6;
+    struct point *pp = &p;
+    pp->x = 5;
+    (*pp).y = 6;
+    if (p.x + p.y != 11) return 7;
+    if (sizeof(struct mixed) != 24 || sizeof(union u) != 8 || sizeof(struct nested) != 24) return 8;
+    struct nested n = gn;
+    n.next = &gn;
+    puts(n.next->name);
+    if (n.next->p.y != 2) return 9;
+    if (gu.c[0] != 'A') return 10;
+    union u lu;
+    lu.d = 0.0;
+    lu.i = 0x41424344;
+    if (lu.c[0] != 'D') return 11;
+    struct point arr[3] = {{1, 2}, {3, 4}, {5, 6}};
+    int total = 0;
+    for (int k = 0; k < 3; k++) total += arr[k].x * arr[k].y;
+    if (total != 2 + 12 + 30) return 12;
+    struct point *q = arr + 1;
+    if (q->y != 4) return 13;
+    struct mixed m = {'z', 1.25, 9};
+    struct mixed m2;
+    m2 = m;
+    if (m2.c != 'z' || m2.d != 1.25 || m2.l != 9) return 14;
+    int *px = &m2.l != 0 ? &p.y : 0;
+    if (*px != 6) return 15;
+    return (1 ? p : gpt).x;
+}
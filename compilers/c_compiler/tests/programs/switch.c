@@ -0,0 +1,136 @@
+int putchar(int c);
+
+/* A state machine that counts the signed numbers in a string, the states are dense enough
+   for a jump table. */
+int count_numbers(char *s) {
+    int state = 0;
+    int count = 0;
+    for (; *s; s++) {
+        char c = *s;
+        int digit = c >= '0' && c <= '9';
+        switch (state) {
+        case 0:
+            if (c == '-' || c == '+') {
+                state = 1;
+                break;
+            }
+            /* fall through */
+        case 1:
+            if (digit) {
+                state = 2;
+                count++;
+            } else {
+                state = 0;
+            }
+            break;
+        case 2:
+            if (!digit)
+                state = c == ' ' ? 0 : 3;
+            break;
+        case 3:
+            if (c == ' ')
+                state = 0;
+            continue;
+        default:
+            return -1;
+        }
+        putchar('0' + state);
+    }
+    putchar('\n');
+    return count;
+}
+
+/* Cases far apart become a chain of comparisons. */
+long sparse(long x) {
+    switch (x) {
+    case -1000000000000L:
+        return 1;
+    case 7:
+        return 2;
+    default:
+        return 5;
+    case 100000:
+        return 3;
+    case 'a' * 2:
+        return 4;
+    }
+}
+
+unsigned dense_unsigned(unsigned x) {
+    unsigned result = 0;
+    switch (x - 4000000000u) {
+    case 0u: result += 1;
+    case 1u: result += 10;
+    case 2u: result += 100;
+    case 3u: result += 1000;
+        break;
+    case 4u: result = 7;
+    }
+    return result;
+}
+
+int negative(int x) {
+    switch (x) {
+    case -3: return 30;
+    case -2: return 20;
+    case -1: return 10;
+    case 0: return 0;
+    case 1: return -10;
+    }
+    return 99;
+}
+
+int nested(int n) {
+    int total = 0;
+    for (int i = 0; i < n; i++) {
+        switch (i % 4) {
+        case 0:
+            switch (i) {
+            case 4:
+                continue;
+            case 8:
+                break;
+            default:
+                total += 100;
+            }
+            total += 1;
+            break;
+        case 1: {
+            int j = 0;
+            while (1) {
+                j++;
+                if (j > 3)
+                    break;
+            }
+            total += j;
+            break;
+        }
+        case 3:
+            if (i > 10)
+                return total;
+        }
+    }
+    return total;
+}
+
+int main(void) {
+    int numbers = count_numbers("12 -3 x4 +56 7a 8");
+    int sum = 0;
+    for (int i = -5; i < 6; i++)
+        sum += negative(i);
+    switch (sum) {
+    }
+    switch (sum)
+        default:
+            sum++;
+    char c = 'b';
+    switch (c) {
+    case 'a': putchar('A'); break;
+    case 'b': putchar('B');
+    case 'c': putchar('C'); break;
+    }
+    putchar('\n');
+    return numbers + sum + sparse(7) + sparse(100000) * 10 + sparse(-1000000000000L) * 100 +
+           sparse(194) + sparse(6) + dense_unsigned(4000000000u) + dense_unsigned(4000000002u) +
+           dense_unsigned(4000000004u) + dense_unsigned(3) + nested(20);
+}
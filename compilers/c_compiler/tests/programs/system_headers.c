@@ -0,0 +1,14 @@
+#include <limits.h>
+
+#if !defined __x86_64__ || !defined __LP64__ || __STDC_VERSION__ < 201710L
+#error not a 64-bit C17 target
+#endif
+
+int main(void) {
+    if (CHAR_BIT != 8) return 1;
+    if (INT_MAX != 2147483647 || INT_MIN != -INT_MAX - 1) return 2;
+    if (UINT_MAX != 4294967295u) return 3;
+    if (LONG_MAX != 9223372036854775807L) return 4;
+    if (SCHAR_MIN != -128 || UCHAR_MAX != 255) return 5;
+    return 0;
+}